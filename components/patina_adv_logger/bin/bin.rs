@@ -8,6 +8,7 @@
 //!
 
 use clap::Parser;
+use patina_adv_logger::logger::Phase;
 use std::{
     fs::File,
     io::{self, Read},
@@ -27,6 +28,9 @@ struct Args {
     /// Flag to include the header in the output.
     #[arg(long, default_value_t = false)]
     header: bool,
+    /// Optional boot phase, such as DXE or RUNTIME, to only output the entries of.
+    #[arg(short, long)]
+    phase: Option<Phase>,
 }
 
 fn main() -> io::Result<()> {
@@ -45,6 +49,7 @@ fn main() -> io::Result<()> {
     })?;
 
     parser.configure_print_entry_metadata(args.entry_metadata);
+    parser.configure_phase_filter(args.phase);
     // Write to standard if no output file is specified.
     match args.output_path {
         Some(path) => {
@@ -11,16 +11,19 @@
 //!
 use alloc::boxed::Box;
 use core::{ffi::c_void, ptr};
+#[cfg(feature = "validate_page_attributes")]
+use patina::component::service::{Service, memory::MemoryManager};
 use patina::pi::hob::{Hob, PhaseHandoffInformationTable};
 use patina::{
     boot_services::{BootServices, StandardBootServices},
     component::IntoComponent,
     error::{EfiError, Result},
+    runtime_services::StandardRuntimeServices,
     serial::SerialIO,
 };
 use r_efi::efi;
 
-use crate::{logger::AdvancedLogger, memory_log, protocol::AdvancedLoggerProtocol};
+use crate::{log_file::LogFile, logger::AdvancedLogger, memory_log, protocol::AdvancedLoggerProtocol};
 
 /// C struct for the internal Advanced Logger protocol for the component.
 #[repr(C)]
@@ -42,6 +45,7 @@ where
     S: SerialIO + Send + 'static,
 {
     adv_logger: &'static AdvancedLogger<'static, S>,
+    log_file: Option<&'static str>,
 }
 
 impl<S> AdvancedLoggerComponent<S>
@@ -50,7 +54,15 @@ where
 {
     /// Creates a new AdvancedLoggerComponent.
     pub const fn new(adv_logger: &'static AdvancedLogger<S>) -> Self {
-        Self { adv_logger }
+        Self { adv_logger, log_file: None }
+    }
+
+    /// Writes the memory log to the file at `path` on the first Simple File System when ReadyToBoot is signaled,
+    /// replacing any existing file. This allows the log to be retrieved from the EFI System Partition on platforms
+    /// without a serial port. If no file system is available, a warning is logged and the file is not written.
+    pub const fn with_log_file(mut self, path: &'static str) -> Self {
+        self.log_file = Some(path);
+        self
     }
 
     /// Initialize the advanced logger.
@@ -81,8 +93,10 @@ where
                 // immediately following the HOB header.
                 unsafe {
                     let address: *const efi::PhysicalAddress = ptr::from_ref(data) as *const efi::PhysicalAddress;
-                    let log_info_addr = (*address) as efi::PhysicalAddress;
+                    let log_info_addr = address.read_unaligned();
                     self.adv_logger.set_log_info_address(log_info_addr);
+                    // The HOB is updated if the memory log is relocated, so later readers find the new buffer.
+                    self.adv_logger.set_log_info_hob(address as *mut efi::PhysicalAddress);
                 };
                 return Ok(());
             }
@@ -91,6 +105,24 @@ where
         Err(EfiError::NotFound)
     }
 
+    /// Relocates the advanced logger memory log.
+    ///
+    /// Moves the memory log from the buffer found by [`Self::init_advanced_logger`] to the buffer at `new_base` of
+    /// `new_size` bytes, such as a reserved allocation made once memory services are available, so the log survives
+    /// into the OS. The entries written so far are copied, and subsequent entries are written to the new buffer. Once
+    /// the component has run, the memory log can instead be relocated through
+    /// [`AdvancedLogger::relocate_memory_log`], which updates the installed protocol. See it for the errors returned.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure the new buffer is valid for `new_size` bytes, does not overlap the current memory log,
+    /// and is never freed.
+    ///
+    pub unsafe fn relocate_buffer(&self, new_base: efi::PhysicalAddress, new_size: u32) -> Result<()> {
+        // SAFETY: The caller must provide a valid buffer.
+        unsafe { self.adv_logger.relocate_memory_log(new_base, new_size) }
+    }
+
     /// EFI API to write to the advanced logger through the advanced logger protocol.
     extern "efiapi" fn adv_log_write(
         this: *const AdvancedLoggerProtocol,
@@ -112,9 +144,50 @@ where
 
     /// Entry point to the AdvancedLoggerComponent.
     ///
-    /// Installs the Advanced Logger Protocol for use by non-local components.
+    /// Applies the log level override variable, if any, and registers the log file, if configured, then installs the
+    /// Advanced Logger Protocol for use by non-local components. The override is applied here rather than in
+    /// [`Self::init_advanced_logger`], which runs before the variable services are available.
+    ///
+    #[cfg(not(feature = "validate_page_attributes"))]
+    fn entry_point(self, bs: StandardBootServices, rs: StandardRuntimeServices) -> Result<()> {
+        self.adv_logger.apply_level_override(&rs);
+        self.register_log_file(&bs)?;
+        self.install_protocol(bs)
+    }
+
+    /// Entry point to the AdvancedLoggerComponent.
+    ///
+    /// Validates the memory log is entirely writable, applies the log level override variable, if any, and registers
+    /// the log file, if configured, then installs the Advanced Logger Protocol for use by non-local components. A
+    /// memory log that is not entirely writable is reported and no longer written to, rather than faulting partway
+    /// through a log write. The protocol is still installed if the memory log fails validation.
     ///
-    fn entry_point(self, bs: StandardBootServices) -> Result<()> {
+    #[cfg(feature = "validate_page_attributes")]
+    fn entry_point(
+        self,
+        bs: StandardBootServices,
+        rs: StandardRuntimeServices,
+        memory_manager: Service<dyn MemoryManager>,
+    ) -> Result<()> {
+        self.adv_logger.apply_level_override(&rs);
+        if self.adv_logger.get_log_address().is_some()
+            && let Err(err) = self.adv_logger.validate_page_attributes(*memory_manager)
+        {
+            log::error!("Advanced logger buffer validation failed, installing the protocol anyway. Error = {err:?}");
+        }
+        self.register_log_file(&bs)?;
+        self.install_protocol(bs)
+    }
+
+    fn register_log_file(&self, bs: &StandardBootServices) -> Result<()> {
+        if let Some(path) = self.log_file {
+            let log_file = Box::leak(Box::new(LogFile::new(self.adv_logger, path, bs.clone())));
+            log_file.create_event()?;
+        }
+        Ok(())
+    }
+
+    fn install_protocol(self, bs: StandardBootServices) -> Result<()> {
         let Some(address) = self.adv_logger.get_log_address() else {
             log::error!("Advanced logger not initialized before component entry point!");
             return Err(EfiError::NotStarted);
@@ -126,12 +199,15 @@ where
         };
 
         let protocol = Box::leak(Box::new(protocol));
+        let protocol_log_info = ptr::addr_of_mut!(protocol.protocol.log_info);
         match bs.install_protocol_interface(None, &mut protocol.protocol) {
             Err(status) => {
                 log::error!("Failed to install Advanced Logger protocol! Status = {status:#x?}");
                 Err(EfiError::ProtocolError)
             }
             Ok(_) => {
+                // SAFETY: The protocol is leaked, so is never freed.
+                unsafe { self.adv_logger.set_protocol_log_info(protocol_log_info) };
                 log::info!("Advanced Logger protocol installed.");
                 Ok(())
             }
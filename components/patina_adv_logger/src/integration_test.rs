@@ -84,3 +84,138 @@ fn adv_logger_test(bs: StandardBootServices) -> patina::test::Result {
 
     Ok(())
 }
+
+/// End to end tests of the logger writing the memory log and the parser reading it back.
+#[cfg(all(test, feature = "std"))]
+#[coverage(off)]
+mod tests {
+    use std::{boxed::Box, string::String, sync::Mutex, vec::Vec};
+
+    use log::{Level, LevelFilter, Log};
+    use patina::{log::Format, serial::SerialIO};
+
+    use crate::{
+        logger::{AdvancedLogger, Phase, TimeSource},
+        memory_log::{self, AdvancedLog},
+        parser::{Parser, StreamingParser},
+    };
+
+    /// Records the bytes written to the hardware port.
+    #[derive(Default)]
+    struct RecordingSerial {
+        written: Mutex<Vec<u8>>,
+    }
+
+    impl SerialIO for &RecordingSerial {
+        fn init(&self) {}
+
+        fn write(&self, buffer: &[u8]) {
+            self.written.lock().unwrap().extend_from_slice(buffer);
+        }
+
+        fn read(&self) -> u8 {
+            0
+        }
+
+        fn try_read(&self) -> Option<u8> {
+            None
+        }
+    }
+
+    /// Reports one second at a 1 MHz frequency.
+    struct FixedTimeSource;
+
+    impl TimeSource for FixedTimeSource {
+        fn timestamp(&self) -> u64 {
+            1_000_000
+        }
+
+        fn frequency(&self) -> u64 {
+            1_000_000
+        }
+    }
+
+    const TARGET_FILTERS: &[(&str, LevelFilter)] = &[("noisy", LevelFilter::Warn), ("chatty", LevelFilter::Debug)];
+
+    fn log(logger: &impl Log, level: Level, target: &str, message: &str) {
+        logger.log(&log::Record::builder().level(level).target(target).args(format_args!("{message}")).build());
+    }
+
+    #[test]
+    fn logged_records_should_be_parsed_back_from_the_memory_log() {
+        let buffer = Box::leak(Box::new([0_u64; 0x400]));
+        let address = buffer.as_mut_ptr() as r_efi::efi::PhysicalAddress;
+        let all_levels = memory_log::DEBUG_LEVEL_ERROR
+            | memory_log::DEBUG_LEVEL_WARNING
+            | memory_log::DEBUG_LEVEL_INFO
+            | memory_log::DEBUG_LEVEL_VERBOSE;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe {
+            AdvancedLog::initialize_memory_log_with_hw_print_level(address, size_of_val(buffer) as u32, all_levels)
+        }
+        .unwrap();
+
+        let serial = RecordingSerial::default();
+        let logger = AdvancedLogger::new(Format::Standard, TARGET_FILTERS, LevelFilter::Info, &serial)
+            .with_time_source(&FixedTimeSource);
+        logger.set_log_info_address(address);
+
+        log(&logger, Level::Error, "app", "disk failed");
+        log(&logger, Level::Warn, "app", "low memory");
+        log(&logger, Level::Info, "app", "booting");
+        log(&logger, Level::Debug, "app", "filtered by the maximum level");
+        log(&logger, Level::Info, "noisy::sub", "filtered by the target filter");
+        log(&logger, Level::Error, "noisy", "noisy failure");
+        log(&logger, Level::Debug, "chatty", "chatty detail");
+        logger.set_phase(Phase::Runtime);
+        log(&logger, Level::Info, "app", "at runtime");
+
+        // SAFETY: The buffer is leaked and no longer written to.
+        let data = unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, size_of_val(buffer)) };
+
+        let expected = [
+            (memory_log::DEBUG_LEVEL_ERROR, Phase::Dxe, "ERROR - disk failed\n"),
+            (memory_log::DEBUG_LEVEL_WARNING, Phase::Dxe, "WARN - low memory\n"),
+            (memory_log::DEBUG_LEVEL_INFO, Phase::Dxe, "INFO - booting\n"),
+            (memory_log::DEBUG_LEVEL_ERROR, Phase::Dxe, "ERROR - noisy failure\n"),
+            (memory_log::DEBUG_LEVEL_VERBOSE, Phase::Dxe, "DEBUG - chatty detail\n"),
+            (memory_log::DEBUG_LEVEL_INFO, Phase::Runtime, "INFO - at runtime\n"),
+        ];
+
+        // The streaming parser reads a log as it is written, so is only fed the bytes written so far.
+        let end = AdvancedLog::open_log(data).unwrap().iter().last().unwrap().get_message().as_ptr_range().end;
+        let mut parser = StreamingParser::new();
+        parser.feed(&data[..end as usize - data.as_ptr() as usize]);
+        let entries: Vec<_> = parser.drain().collect();
+        assert_eq!(parser.error(), None);
+        assert_eq!(parser.frequency(), Some(1_000_000));
+        assert_eq!(entries.len(), expected.len());
+        for (entry, (level, phase, message)) in entries.iter().zip(expected) {
+            assert_eq!(entry.level, level, "{message}");
+            assert_eq!(entry.boot_phase(), Some(phase), "{message}");
+            assert_eq!(entry.timestamp, 1_000_000, "{message}");
+            assert_eq!(String::from_utf8_lossy(&entry.message), message);
+        }
+
+        let write = |parser: &Parser| {
+            let mut output = Vec::new();
+            parser.write_log(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let mut parser = Parser::open(data).unwrap();
+        let log = write(&parser);
+        assert!(!log.contains("filtered"));
+        assert_eq!(log.lines().next(), Some("ERR  |DXE     |00:00:01.000| ERROR - disk failed"));
+        assert_eq!(log.lines().nth(4), Some("VERB |DXE     |00:00:01.000| DEBUG - chatty detail"));
+        assert_eq!(log.lines().count(), expected.len());
+
+        parser.configure_print_entry_metadata(false);
+        let messages: String = expected.iter().map(|(_, _, message)| *message).collect();
+        assert_eq!(write(&parser), messages);
+        parser.configure_phase_filter(Some(Phase::Runtime));
+        assert_eq!(write(&parser), "INFO - at runtime\n");
+
+        // Every level is printed to the hardware port, so it received the same messages as the memory log.
+        assert_eq!(String::from_utf8(serial.written.lock().unwrap().clone()).unwrap(), messages);
+    }
+}
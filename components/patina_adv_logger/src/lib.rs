@@ -45,6 +45,14 @@
 //! For the protocol to be created for use of by external components, the platform
 //! should invoke patina_dxe_core.start with the advanced logger component.
 //!
+//! To use the advanced logger alongside other loggers, such as an in-memory
+//! capture for tests, set a [`MultiLogger`](multi_logger::MultiLogger) holding
+//! each of them as the global logger instead.
+//!
+//! Platforms that publish a Serial I/O protocol may also register the
+//! [`AdvancedLoggerSerialIoComponent`](serial_io::AdvancedLoggerSerialIoComponent)
+//! to mirror the hardware port output to that protocol.
+//!
 //! ## License
 //!
 //! Copyright (c) Microsoft Corporation.
@@ -58,10 +66,13 @@ extern crate alloc;
 
 pub mod component;
 pub mod logger;
+pub mod multi_logger;
 pub mod protocol;
+pub mod serial_io;
 
 #[cfg(feature = "std")]
 pub mod parser;
 
 mod integration_test;
+mod log_file;
 mod memory_log;
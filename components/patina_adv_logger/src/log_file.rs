@@ -0,0 +1,340 @@
+//! Advanced Logger File Support
+//!
+//! This module writes the advanced logger memory log to a file at ReadyToBoot, for
+//! platforms where the memory log cannot otherwise be retrieved, such as hardware
+//! without a serial port.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use alloc::vec::Vec;
+use core::{ffi::c_void, iter, ptr};
+use patina::{
+    boot_once::BootOnce,
+    boot_services::{BootServices, event::EventType, tpl::Tpl},
+    error::{EfiError, Result},
+    serial::SerialIO,
+};
+use r_efi::efi::{
+    self,
+    protocols::{file, simple_file_system},
+};
+
+use crate::logger::AdvancedLogger;
+
+/// Writes the memory log to a file on the first Simple File System when ReadyToBoot is signaled.
+pub(crate) struct LogFile<S, B>
+where
+    S: SerialIO + Send + 'static,
+    B: BootServices + 'static,
+{
+    adv_logger: &'static AdvancedLogger<'static, S>,
+    path: &'static str,
+    boot_services: B,
+    written: BootOnce,
+}
+
+impl<S, B> LogFile<S, B>
+where
+    S: SerialIO + Send + 'static,
+    B: BootServices + 'static,
+{
+    /// Creates a new LogFile writing the memory log of `adv_logger` to `path`.
+    pub(crate) const fn new(
+        adv_logger: &'static AdvancedLogger<'static, S>,
+        path: &'static str,
+        boot_services: B,
+    ) -> Self {
+        Self { adv_logger, path, boot_services, written: BootOnce::new() }
+    }
+
+    /// Creates the ReadyToBoot event that writes the log file.
+    pub(crate) fn create_event(&'static self) -> Result<efi::Event> {
+        self.boot_services
+            .create_event_ex(
+                EventType::NOTIFY_SIGNAL,
+                Tpl::CALLBACK,
+                Some(Self::ready_to_boot_notify),
+                self,
+                &efi::EVENT_GROUP_READY_TO_BOOT,
+            )
+            .map_err(|status| {
+                log::error!("Failed to create advanced logger file ready to boot event! Status = {status:#x?}");
+                EfiError::from(status)
+            })
+    }
+
+    /// Writes the log file the first time ReadyToBoot is signaled.
+    extern "efiapi" fn ready_to_boot_notify(_event: efi::Event, log_file: &'static Self) {
+        log_file.written.run(|| match log_file.write() {
+            Ok(()) => log::info!("Advanced logger memory log written to {}.", log_file.path),
+            Err(err) => log::warn!("Failed to write advanced logger memory log to {}. Error = {err:?}", log_file.path),
+        });
+    }
+
+    /// Writes the messages of the memory log to the file, replacing any existing file.
+    fn write(&self) -> Result<()> {
+        let memory_log = self.adv_logger.get_memory_log().ok_or(EfiError::NotStarted)?;
+
+        // Copy the messages before writing, as the file system may itself add to the memory log.
+        let mut contents = Vec::new();
+        for entry in memory_log.iter() {
+            contents.extend_from_slice(entry.get_message());
+        }
+
+        // SAFETY: The protocol is only used within this function.
+        let sfs = unsafe { self.boot_services.locate_protocol::<simple_file_system::Protocol>(None) }?;
+        let mut root_ptr = ptr::null_mut();
+        EfiError::status_to_result((sfs.open_volume)(sfs, ptr::addr_of_mut!(root_ptr)))?;
+        // SAFETY: The volume was successfully opened and is valid until closed.
+        let root = unsafe { root_ptr.as_mut() }.ok_or(EfiError::NotFound)?;
+
+        let path: Vec<u16> = self
+            .path
+            .encode_utf16()
+            .map(|c| if c == u16::from(b'/') { u16::from(b'\\') } else { c })
+            .chain(iter::once(0))
+            .collect();
+        let result = Self::open(root, &path).and_then(|file| {
+            // Delete closes the file, so any existing contents are not left beyond the end of the new ones.
+            let _ = (file.delete)(file);
+            let file = Self::open(root, &path)?;
+            let mut size = contents.len();
+            let result = EfiError::status_to_result((file.write)(
+                file,
+                ptr::addr_of_mut!(size),
+                contents.as_mut_ptr() as *mut c_void,
+            ));
+            let _ = (file.close)(file);
+            result
+        });
+        let _ = (root.close)(root);
+        result
+    }
+
+    /// Opens or creates the file at the NUL terminated `path` relative to `root`.
+    fn open(root: &mut file::Protocol, path: &[u16]) -> Result<&'static mut file::Protocol> {
+        let mut file_ptr = ptr::null_mut();
+        EfiError::status_to_result((root.open)(
+            root,
+            ptr::addr_of_mut!(file_ptr),
+            path.as_ptr() as *mut u16,
+            file::MODE_CREATE | file::MODE_READ | file::MODE_WRITE,
+            0,
+        ))?;
+        // SAFETY: The file was successfully opened and is valid until closed.
+        unsafe { file_ptr.as_mut() }.ok_or(EfiError::NotFound)
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    extern crate std;
+
+    use std::{boxed::Box, string::String, sync::Mutex, vec};
+
+    use patina::{boot_services::MockBootServices, serial::uart::UartNull};
+
+    use super::*;
+    use crate::memory_log::AdvancedLog;
+
+    static FILE_SYSTEM: Mutex<FileSystem> = Mutex::new(FileSystem { opened: Vec::new(), contents: Vec::new() });
+
+    struct FileSystem {
+        opened: Vec<String>,
+        contents: Vec<u8>,
+    }
+
+    extern "efiapi" fn mock_open_volume(
+        _this: *mut simple_file_system::Protocol,
+        root: *mut *mut file::Protocol,
+    ) -> efi::Status {
+        // SAFETY: The caller provides a valid pointer.
+        unsafe { *root = mock_file() };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_open(
+        _this: *mut file::Protocol,
+        new_handle: *mut *mut file::Protocol,
+        file_name: *mut efi::Char16,
+        _open_mode: u64,
+        _attributes: u64,
+    ) -> efi::Status {
+        // SAFETY: The caller provides a NUL terminated file name.
+        let name =
+            unsafe { std::slice::from_raw_parts(file_name, (0..).take_while(|&i| *file_name.add(i) != 0).count()) };
+        FILE_SYSTEM.lock().unwrap().opened.push(String::from_utf16(name).unwrap());
+        // SAFETY: The caller provides a valid pointer.
+        unsafe { *new_handle = mock_file() };
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_close(_this: *mut file::Protocol) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_delete(_this: *mut file::Protocol) -> efi::Status {
+        FILE_SYSTEM.lock().unwrap().contents.clear();
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_write(_this: *mut file::Protocol, size: *mut usize, buffer: *mut c_void) -> efi::Status {
+        // SAFETY: The caller provides a valid buffer of the given size.
+        let data = unsafe { std::slice::from_raw_parts(buffer as *const u8, *size) };
+        FILE_SYSTEM.lock().unwrap().contents.extend_from_slice(data);
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_read(_this: *mut file::Protocol, _size: *mut usize, _buffer: *mut c_void) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_get_position(_this: *mut file::Protocol, _position: *mut u64) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_set_position(_this: *mut file::Protocol, _position: u64) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_get_info(
+        _this: *mut file::Protocol,
+        _information_type: *mut efi::Guid,
+        _buffer_size: *mut usize,
+        _buffer: *mut c_void,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_set_info(
+        _this: *mut file::Protocol,
+        _information_type: *mut efi::Guid,
+        _buffer_size: usize,
+        _buffer: *mut c_void,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_flush(_this: *mut file::Protocol) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_open_ex(
+        _this: *mut file::Protocol,
+        _new_handle: *mut *mut file::Protocol,
+        _file_name: *mut efi::Char16,
+        _open_mode: u64,
+        _attributes: u64,
+        _token: *mut file::IoToken,
+    ) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    extern "efiapi" fn mock_io_ex(_this: *mut file::Protocol, _token: *mut file::IoToken) -> efi::Status {
+        efi::Status::UNSUPPORTED
+    }
+
+    fn mock_file() -> *mut file::Protocol {
+        Box::leak(Box::new(file::Protocol {
+            revision: file::REVISION,
+            open: mock_open,
+            close: mock_close,
+            delete: mock_delete,
+            read: mock_read,
+            write: mock_write,
+            get_position: mock_get_position,
+            set_position: mock_set_position,
+            get_info: mock_get_info,
+            set_info: mock_set_info,
+            flush: mock_flush,
+            open_ex: mock_open_ex,
+            read_ex: mock_io_ex,
+            write_ex: mock_io_ex,
+            flush_ex: mock_io_ex,
+        }))
+    }
+
+    fn mock_file_system() -> &'static mut simple_file_system::Protocol {
+        Box::leak(Box::new(simple_file_system::Protocol {
+            revision: simple_file_system::REVISION,
+            open_volume: mock_open_volume,
+        }))
+    }
+
+    fn logger_with_memory_log() -> &'static AdvancedLogger<'static, UartNull> {
+        const LOG_LEN: usize = 0x1000;
+        let logger = Box::leak(Box::new(AdvancedLogger::new(
+            patina::log::Format::Standard,
+            &[],
+            log::LevelFilter::Trace,
+            UartNull {},
+        )));
+        let address = Box::leak(vec![0_u64; LOG_LEN / 8].into_boxed_slice()).as_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated with the given length.
+        unsafe { AdvancedLog::initialize_memory_log(address, LOG_LEN as u32) };
+        logger.set_log_info_address(address);
+        logger
+    }
+
+    #[test]
+    fn log_file_should_be_written_once_at_ready_to_boot() {
+        let logger = logger_with_memory_log();
+        let initial_contents: Vec<u8> =
+            logger.get_memory_log().unwrap().iter().flat_map(|entry| entry.get_message().to_vec()).collect();
+        logger.log_write(0, b"First line\n");
+        logger.log_write(0, b"Second line\n");
+
+        let protocol = mock_file_system() as *mut simple_file_system::Protocol;
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_locate_protocol::<simple_file_system::Protocol>()
+            .once()
+            // SAFETY: The protocol was leaked and is valid for the test.
+            .returning_st(move |_| Ok(unsafe { &mut *protocol }));
+        boot_services
+            .expect_create_event_ex::<&'static LogFile<UartNull, MockBootServices>>()
+            .once()
+            .withf(|event_type, tpl, notify_function, _, event_group| {
+                *event_type == EventType::NOTIFY_SIGNAL
+                    && *tpl == Tpl::CALLBACK
+                    && notify_function.is_some()
+                    && *event_group == efi::EVENT_GROUP_READY_TO_BOOT
+            })
+            .returning(|_, _, _, _, _| Ok(1_usize as efi::Event));
+
+        let log_file: &'static LogFile<_, _> =
+            Box::leak(Box::new(LogFile::new(logger, "/logs/uefi.log", boot_services)));
+        assert_eq!(log_file.create_event(), Ok(1_usize as efi::Event));
+
+        LogFile::ready_to_boot_notify(1_usize as efi::Event, log_file);
+        {
+            let file_system = FILE_SYSTEM.lock().unwrap();
+            assert_eq!(file_system.opened, vec!["\\logs\\uefi.log", "\\logs\\uefi.log"]);
+            assert_eq!(file_system.contents, [initial_contents.as_slice(), b"First line\nSecond line\n"].concat());
+        }
+
+        // The file is not written again, so the protocol is not located again.
+        LogFile::ready_to_boot_notify(1_usize as efi::Event, log_file);
+        assert_eq!(FILE_SYSTEM.lock().unwrap().opened.len(), 2);
+    }
+
+    #[test]
+    fn log_file_should_not_be_written_without_file_system() {
+        let logger = logger_with_memory_log();
+
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_locate_protocol::<simple_file_system::Protocol>()
+            .once()
+            .returning(|_| Err(efi::Status::NOT_FOUND));
+
+        let log_file: &'static LogFile<_, _> = Box::leak(Box::new(LogFile::new(logger, "uefi.log", boot_services)));
+        LogFile::ready_to_boot_notify(1_usize as efi::Event, log_file);
+        LogFile::ready_to_boot_notify(1_usize as efi::Event, log_file);
+    }
+}
@@ -10,10 +10,22 @@
 //! SPDX-License-Identifier: Apache-2.0
 //!
 use crate::memory_log::{self, AdvancedLog, LogEntry};
-use core::marker::Send;
-use log::Level;
+use alloc::vec::Vec;
+use core::{
+    marker::Send,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU16, AtomicU32, AtomicUsize, Ordering},
+};
+use log::{Level, LevelFilter};
 use mu_rust_helpers::perf_timer::{Arch, ArchFunctionality};
-use patina::{log::Format, serial::SerialIO};
+use patina::{
+    base::{UEFI_PAGE_SIZE, align_down, align_up},
+    component::service::memory::{AccessType, MemoryError, MemoryManager},
+    error::EfiError,
+    log::Format,
+    runtime_services::RuntimeServices,
+    serial::SerialIO,
+};
 use r_efi::efi;
 use spin::Once;
 
@@ -21,6 +33,140 @@ use spin::Once;
 #[used]
 static mut DBG_ADV_LOG_BUFFER: u64 = 0;
 
+/// An additional destination for entries written to the hardware port, such as the Serial I/O protocol.
+pub(crate) trait LogSink: Sync {
+    /// Writes an entry, returning whether it was written.
+    fn write(&self, data: &[u8]) -> bool;
+}
+
+/// A source of timestamps for entries written to the memory log.
+pub trait TimeSource: Sync {
+    /// Returns the current timestamp, in ticks.
+    fn timestamp(&self) -> u64;
+
+    /// Returns the number of ticks per second, or zero if unknown.
+    fn frequency(&self) -> u64;
+}
+
+/// A [`TimeSource`] backed by the architecture performance timer.
+pub struct PerfTimerTimeSource;
+
+impl TimeSource for PerfTimerTimeSource {
+    fn timestamp(&self) -> u64 {
+        Arch::cpu_count()
+    }
+
+    fn frequency(&self) -> u64 {
+        Arch::perf_frequency()
+    }
+}
+
+/// The boot phase that produced a memory log entry, with the values of the Advanced Logger memory log format.
+///
+/// The format has no phase for BDS, which runs within the DXE phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Phase {
+    /// The phase was not specified.
+    Unspecified = 0,
+    /// The Security phase.
+    Sec = 1,
+    /// The Pre-EFI Initialization phase.
+    Pei = 2,
+    /// The Pre-EFI Initialization phase, in 64-bit mode.
+    Pei64 = 3,
+    /// The Driver Execution Environment phase.
+    Dxe = 4,
+    /// After ExitBootServices.
+    Runtime = 5,
+    /// The Management Mode core.
+    MmCore = 6,
+    /// A Management Mode driver.
+    Mm = 7,
+    /// The System Management Mode core.
+    SmmCore = 8,
+    /// A System Management Mode driver.
+    Smm = 9,
+    /// Trusted Firmware-A.
+    Tfa = 10,
+    /// A controller firmware.
+    Cnt = 11,
+}
+
+impl Phase {
+    const ALL: [Phase; 12] = [
+        Self::Unspecified,
+        Self::Sec,
+        Self::Pei,
+        Self::Pei64,
+        Self::Dxe,
+        Self::Runtime,
+        Self::MmCore,
+        Self::Mm,
+        Self::SmmCore,
+        Self::Smm,
+        Self::Tfa,
+        Self::Cnt,
+    ];
+
+    /// Returns the phase with the value recorded in a memory log entry, if it is a known phase.
+    pub fn from_raw(value: u16) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Returns the short name of the phase used by log parsers.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Unspecified => "UNSPEC",
+            Self::Sec => "SEC",
+            Self::Pei => "PEI",
+            Self::Pei64 => "PEI64",
+            Self::Dxe => "DXE",
+            Self::Runtime => "RUNTIME",
+            Self::MmCore => "MM_CORE",
+            Self::Mm => "MM",
+            Self::SmmCore => "SMM_CORE",
+            Self::Smm => "SMM",
+            Self::Tfa => "TFA",
+            Self::Cnt => "CNT",
+        }
+    }
+}
+
+/// What the memory log does with an entry that does not fit in its remaining space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// The entry is discarded; later entries are still written if they fit.
+    #[default]
+    DropNewest,
+    /// The memory log wraps as a circular buffer, overwriting its earliest entries with the entry.
+    DropOldest,
+    /// The entry and every later entry are discarded, so the log ends at the first entry that did not fit.
+    HaltLogging,
+}
+
+/// What the logger does when a write to the hardware port fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardwarePortErrorPolicy {
+    /// The entry is not written to the hardware port; later entries are still written.
+    #[default]
+    Drop,
+    /// The write is retried up to the given number of times before the entry is dropped.
+    Retry(u32),
+    /// The hardware port is disabled after the given number of consecutive failed writes, and a message saying so is
+    /// written to the memory log.
+    DisableAfter(u32),
+}
+
+impl core::str::FromStr for Phase {
+    type Err = &'static str;
+
+    /// Parses the short name of a phase, ignoring case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|phase| phase.name().eq_ignore_ascii_case(name)).ok_or("Unknown boot phase.")
+    }
+}
+
 /// The logger for memory/hardware port logging.
 pub struct AdvancedLogger<'a, S>
 where
@@ -28,9 +174,27 @@ where
 {
     hardware_port: S,
     target_filters: &'a [(&'a str, log::LevelFilter)],
-    max_level: log::LevelFilter,
+    max_level: AtomicUsize,
+    widest_level: AtomicUsize,
+    level_variable: Option<(efi::Guid, &'a [u16])>,
     format: Format,
+    entry_alignment: u32,
+    overflow_policy: OverflowPolicy,
+    hardware_port_error_policy: HardwarePortErrorPolicy,
+    hardware_port_failures: AtomicU32,
+    hardware_port_disabled: AtomicBool,
     memory_log: Once<AdvancedLog<'static>>,
+    relocated_memory_log: Once<AdvancedLog<'static>>,
+    memory_log_writable: AtomicBool,
+    memory_log_writers: AtomicU32,
+    memory_log_relocating: AtomicBool,
+    relocation_discarded_size: AtomicU32,
+    log_info_hob: AtomicPtr<efi::PhysicalAddress>,
+    protocol_log_info: AtomicPtr<efi::PhysicalAddress>,
+    serial_io: Once<&'static dyn LogSink>,
+    serial_io_replaces_hardware_port: AtomicBool,
+    time_source: &'a dyn TimeSource,
+    phase: AtomicU16,
 }
 
 impl<'a, S> AdvancedLogger<'a, S>
@@ -52,40 +216,240 @@ where
         max_level: log::LevelFilter,
         hardware_port: S,
     ) -> Self {
-        Self { hardware_port, target_filters, max_level, format, memory_log: Once::new() }
+        Self {
+            hardware_port,
+            target_filters,
+            max_level: AtomicUsize::new(max_level as usize),
+            widest_level: AtomicUsize::new(widest_level(max_level, target_filters) as usize),
+            level_variable: None,
+            format,
+            entry_alignment: memory_log::MIN_ENTRY_ALIGNMENT,
+            overflow_policy: OverflowPolicy::DropNewest,
+            hardware_port_error_policy: HardwarePortErrorPolicy::Drop,
+            hardware_port_failures: AtomicU32::new(0),
+            hardware_port_disabled: AtomicBool::new(false),
+            memory_log: Once::new(),
+            relocated_memory_log: Once::new(),
+            memory_log_writable: AtomicBool::new(true),
+            memory_log_writers: AtomicU32::new(0),
+            memory_log_relocating: AtomicBool::new(false),
+            relocation_discarded_size: AtomicU32::new(0),
+            log_info_hob: AtomicPtr::new(ptr::null_mut()),
+            protocol_log_info: AtomicPtr::new(ptr::null_mut()),
+            serial_io: Once::new(),
+            serial_io_replaces_hardware_port: AtomicBool::new(false),
+            time_source: &PerfTimerTimeSource,
+            phase: AtomicU16::new(Phase::Dxe as u16),
+        }
+    }
+
+    /// Sets the source of timestamps for entries written to the memory log.
+    ///
+    /// The frequency of the source is recorded in the memory log if it was not already set. The default is
+    /// [`PerfTimerTimeSource`].
+    pub const fn with_time_source(mut self, time_source: &'a dyn TimeSource) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Sets a UEFI variable that overrides the maximum log level at runtime.
+    ///
+    /// The variable is read by [`AdvancedLogger::apply_level_override`]. Its payload is either a single byte holding
+    /// a [`LevelFilter`] value (0 for `Off` through 5 for `Trace`), or a level name such as `info`, optionally NUL
+    /// terminated. The override only replaces the maximum level passed to [`AdvancedLogger::new`]; target filters
+    /// still apply to their targets. `name` must be NUL terminated.
+    pub const fn with_variable_override(mut self, namespace: efi::Guid, name: &'a [u16]) -> Self {
+        self.level_variable = Some((namespace, name));
+        self
+    }
+
+    /// Sets the alignment of the start of each entry written to the memory log.
+    ///
+    /// The alignment must be a power of two, and applies once the memory log is set. The default
+    /// is [`memory_log::MIN_ENTRY_ALIGNMENT`].
+    pub const fn with_entry_alignment(mut self, alignment: u32) -> Self {
+        self.entry_alignment = alignment;
+        self
+    }
+
+    /// Sets what the memory log does with an entry that does not fit in its remaining space.
+    ///
+    /// The policy applies once the memory log is set. The default is [`OverflowPolicy::DropNewest`].
+    pub const fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Sets what the logger does when a write to the hardware port fails.
+    ///
+    /// The default is [`HardwarePortErrorPolicy::Drop`].
+    pub const fn with_hardware_port_error_policy(mut self, policy: HardwarePortErrorPolicy) -> Self {
+        self.hardware_port_error_policy = policy;
+        self
+    }
+
+    /// Returns whether a record at `level` could be logged by any target filter.
+    ///
+    /// This is a single integer comparison intended for hot call sites to check before building a record. A `true`
+    /// result still requires [`log::Log::enabled`] to apply the target filters.
+    #[inline(always)]
+    pub fn level_enabled(&self, level: Level) -> bool {
+        level as usize <= self.widest_level.load(Ordering::Relaxed)
+    }
+
+    /// Returns the maximum level of records whose target matches no target filter.
+    pub fn max_level(&self) -> LevelFilter {
+        level_filter_from_usize(self.max_level.load(Ordering::Relaxed))
+    }
+
+    /// Applies the log level override variable set by [`AdvancedLogger::with_variable_override`], if any.
+    ///
+    /// The maximum level passed to [`AdvancedLogger::new`] is kept if the variable is absent or its payload is not a
+    /// log level. Returns the resulting maximum level.
+    ///
+    /// If the override makes the logger more verbose than the global [`log::max_level`], the global maximum is raised
+    /// so the newly enabled records reach the logger. A more restrictive override leaves the global maximum as is.
+    pub fn apply_level_override(&self, runtime_services: &impl RuntimeServices) -> LevelFilter {
+        let Some((namespace, name)) = self.level_variable else {
+            return self.max_level();
+        };
+
+        match runtime_services.get_variable::<Vec<u8>>(name, &namespace, None) {
+            Ok((payload, _)) => match parse_level_filter(&payload) {
+                Some(level) => {
+                    self.set_max_level(level);
+                    let widest_level = level_filter_from_usize(self.widest_level.load(Ordering::Relaxed));
+                    if widest_level > log::max_level() {
+                        log::set_max_level(widest_level);
+                    }
+                    log::info!("Advanced logger level overridden to {level}.");
+                }
+                None => log::warn!("Ignoring malformed advanced logger level override: {payload:x?}"),
+            },
+            Err(efi::Status::NOT_FOUND) => {}
+            Err(status) => log::warn!("Could not read advanced logger level override: {status:#x?}"),
+        }
+        self.max_level()
+    }
+
+    fn set_max_level(&self, max_level: LevelFilter) {
+        self.max_level.store(max_level as usize, Ordering::Relaxed);
+        self.widest_level.store(widest_level(max_level, self.target_filters) as usize, Ordering::Relaxed);
+    }
+
+    /// Sets the boot phase recorded in subsequent memory log entries.
+    ///
+    /// The default is [`Phase::Dxe`].
+    pub fn set_phase(&self, phase: Phase) {
+        self.phase.store(phase as u16, Ordering::Relaxed);
+    }
+
+    /// Returns the boot phase recorded in memory log entries.
+    pub fn phase(&self) -> Phase {
+        Phase::from_raw(self.phase.load(Ordering::Relaxed)).unwrap_or(Phase::Unspecified)
     }
 
     /// Writes a log entry to the hardware port and memory log if available.
     pub(crate) fn log_write(&self, error_level: u32, data: &[u8]) {
-        let mut hw_write = true;
-        if let Some(memory_log) = self.memory_log.get() {
-            hw_write = memory_log.hardware_write_enabled(error_level);
-            let timestamp = Arch::cpu_count();
-            let _ = memory_log.add_log_entry(LogEntry {
-                phase: memory_log::ADVANCED_LOGGER_PHASE_DXE,
-                level: error_level,
-                timestamp,
-                data,
-            });
-        }
+        let hw_write = self.memory_log_write(error_level, data);
 
         if hw_write {
-            self.hardware_port.write(data);
+            // An entry the Serial I/O sink does not write is still written to the hardware port, even if the sink
+            // replaces it.
+            let serial_io_written = self.serial_io.get().is_some_and(|sink| sink.write(data));
+            if !serial_io_written || !self.serial_io_replaces_hardware_port.load(Ordering::Relaxed) {
+                self.hardware_port_write(data);
+            }
+        }
+    }
+
+    /// Writes a log entry to the memory log if available, returning whether it should also be written to the ports.
+    ///
+    /// Writers are counted so the memory log is not relocated while it is written. An entry written while the memory
+    /// log is being relocated is only written to the ports, and is counted as discarded by the memory log.
+    fn memory_log_write(&self, error_level: u32, data: &[u8]) -> bool {
+        self.memory_log_writers.fetch_add(1, Ordering::SeqCst);
+        let relocating = self.memory_log_relocating.load(Ordering::SeqCst);
+
+        let hw_write = match self.current_memory_log().filter(|_| self.memory_log_writable.load(Ordering::Relaxed)) {
+            Some(memory_log) => {
+                if relocating {
+                    self.relocation_discarded_size.fetch_add(memory_log::entry_size(data.len()), Ordering::Relaxed);
+                } else {
+                    let timestamp = self.time_source.timestamp();
+                    let _ = memory_log.add_log_entry(LogEntry {
+                        phase: self.phase.load(Ordering::Relaxed),
+                        level: error_level,
+                        timestamp,
+                        data,
+                    });
+                }
+                memory_log.hardware_write_enabled(error_level)
+            }
+            None => true,
+        };
+
+        self.memory_log_writers.fetch_sub(1, Ordering::SeqCst);
+        hw_write
+    }
+
+    /// Writes a log entry to the hardware port, handling a failed write with the hardware port error policy.
+    fn hardware_port_write(&self, data: &[u8]) {
+        if self.hardware_port_disabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let attempts = match self.hardware_port_error_policy {
+            HardwarePortErrorPolicy::Retry(retries) => retries.saturating_add(1),
+            HardwarePortErrorPolicy::Drop | HardwarePortErrorPolicy::DisableAfter(_) => 1,
+        };
+        if (0..attempts).any(|_| self.hardware_port.try_write(data).is_ok()) {
+            self.hardware_port_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+
+        let HardwarePortErrorPolicy::DisableAfter(limit) = self.hardware_port_error_policy else {
+            return;
+        };
+        let failures = self.hardware_port_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= limit && !self.hardware_port_disabled.swap(true, Ordering::Relaxed) {
+            self.memory_log_write(
+                log_level_to_debug_level(Level::Warn),
+                b"Advanced logger disabled the hardware port after repeated write failures.\n",
+            );
+        }
+    }
+
+    /// Sets the Serial I/O protocol sink that entries written to the hardware port are also written to.
+    ///
+    /// If `replace_hardware_port` is set, entries the sink writes are not written to the hardware port. Returns false
+    /// if a sink was already set.
+    pub(crate) fn set_serial_io_sink(&self, sink: &'static dyn LogSink, replace_hardware_port: bool) -> bool {
+        if self.serial_io.is_completed() {
+            return false;
         }
+
+        self.serial_io_replaces_hardware_port.store(replace_hardware_port, Ordering::Relaxed);
+        self.serial_io.call_once(|| sink);
+        true
     }
 
     /// Sets the address of the advanced logger memory log.
     pub(crate) fn set_log_info_address(&self, address: efi::PhysicalAddress) {
         assert!(!self.memory_log.is_completed());
         // SAFETY: The caller must ensure the address is valid for an AdvancedLog.
-        if let Some(log) = unsafe { AdvancedLog::adopt_memory_log(address) } {
+        if let Some(mut log) = unsafe { AdvancedLog::adopt_memory_log(address) } {
+            if log.set_entry_alignment(self.entry_alignment).is_err() {
+                log::error!("Invalid advanced logger entry alignment {}, using default.", self.entry_alignment);
+            }
+            log.set_overflow_policy(self.overflow_policy);
+
             let memory_log = self.memory_log.call_once(|| log);
             log::info!("Advanced logger buffer initialized. Address = {:#x}", memory_log.get_address());
 
             // The frequency may not be initialized, if not do so now.
             if memory_log.get_frequency() == 0 {
-                let frequency = Arch::perf_frequency();
-                memory_log.set_frequency(frequency);
+                memory_log.set_frequency(self.time_source.frequency());
             }
 
             // SAFETY: This is only set for discoverability while debugging.
@@ -97,8 +461,137 @@ where
         }
     }
 
+    /// Records where the address of the memory log is found in the HOB list, so that it is updated if the memory log
+    /// is relocated.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure the address is that of the memory log address in the advanced logger HOB, and that the
+    /// HOB list remains valid and writable while the logger is used.
+    pub(crate) unsafe fn set_log_info_hob(&self, hob_log_info: *mut efi::PhysicalAddress) {
+        self.log_info_hob.store(hob_log_info, Ordering::Release);
+    }
+
+    /// Records the memory log address of the installed Advanced Logger protocol, so that it is updated if the memory
+    /// log is relocated.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure the protocol is never freed.
+    pub(crate) unsafe fn set_protocol_log_info(&self, protocol_log_info: *mut efi::PhysicalAddress) {
+        self.protocol_log_info.store(protocol_log_info, Ordering::Release);
+    }
+
+    /// Moves the memory log to a new buffer at `address` of `length` bytes, copying the entries written so far.
+    ///
+    /// Subsequent entries are written to the new buffer, and the memory log address in the advanced logger HOB and
+    /// the installed Advanced Logger protocol is updated to it. The old buffer is closed, so that C producers which
+    /// located it earlier stop writing to it. Entries written while the memory log is copied are counted as
+    /// discarded.
+    ///
+    /// This may be called before or after the Advanced Logger protocol is installed. The memory log can be relocated
+    /// once. Returns `NotStarted` if the memory log is not initialized, `AlreadyStarted` if it was already relocated,
+    /// `BufferTooSmall` if the new buffer cannot hold the entries written so far, and `NotReady` without relocating if
+    /// an entry is being written, such as by a logging call that was interrupted to relocate the log, in which case the
+    /// relocation can be retried.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure the buffer is valid for `length` bytes, does not overlap the memory log, and remains
+    /// valid as long as the logger is used.
+    pub unsafe fn relocate_memory_log(&self, address: efi::PhysicalAddress, length: u32) -> Result<(), EfiError> {
+        let Some(memory_log) = self.memory_log.get() else {
+            return Err(EfiError::NotStarted);
+        };
+        if self.relocated_memory_log.is_completed() || self.memory_log_relocating.swap(true, Ordering::SeqCst) {
+            return Err(EfiError::AlreadyStarted);
+        }
+
+        let result = if self.memory_log_writers.load(Ordering::SeqCst) != 0 {
+            Err(EfiError::NotReady)
+        } else {
+            // SAFETY: The caller ensures the buffer is valid, and no entry is written while relocating.
+            unsafe { memory_log.relocate(address, length) }.map(|log| {
+                self.relocated_memory_log.call_once(|| log);
+            })
+        };
+        self.memory_log_relocating.store(false, Ordering::SeqCst);
+        if let Some(memory_log) = self.current_memory_log() {
+            memory_log.add_discarded_size(self.relocation_discarded_size.swap(0, Ordering::Relaxed));
+        }
+        result?;
+
+        let hob_log_info = self.log_info_hob.load(Ordering::Acquire);
+        if !hob_log_info.is_null() {
+            // SAFETY: The HOB list was ensured to be valid and writable when the HOB was recorded.
+            unsafe { hob_log_info.write_unaligned(address) };
+        }
+        let protocol_log_info = self.protocol_log_info.load(Ordering::Acquire);
+        if !protocol_log_info.is_null() {
+            // SAFETY: The protocol was ensured to never be freed when it was recorded.
+            unsafe { protocol_log_info.write_unaligned(address) };
+        }
+
+        log::info!("Advanced logger buffer relocated. Address = {address:#x}");
+        // SAFETY: This is only set for discoverability while debugging.
+        unsafe {
+            DBG_ADV_LOG_BUFFER = address;
+        }
+        Ok(())
+    }
+
+    /// Returns the memory log entries are written to, which is the relocated memory log if it has been relocated.
+    fn current_memory_log(&self) -> Option<&AdvancedLog<'static>> {
+        self.relocated_memory_log.get().or_else(|| self.memory_log.get())
+    }
+
+    /// Verifies the whole memory log is mapped writable, so that a write cannot fault partway through the log.
+    ///
+    /// If any page of the memory log is not writable, writing to the memory log is stopped and an error is logged
+    /// with the first page that is not writable.
+    #[cfg_attr(not(feature = "validate_page_attributes"), allow(dead_code))]
+    pub(crate) fn validate_page_attributes(&self, memory_manager: &dyn MemoryManager) -> Result<(), EfiError> {
+        let Some(memory_log) = self.current_memory_log() else {
+            return Err(EfiError::NotStarted);
+        };
+
+        let address = memory_log.get_address() as usize;
+        let start = align_down(address, UEFI_PAGE_SIZE).map_err(|_| EfiError::InvalidParameter)?;
+        let end = align_up(address + memory_log.get_size() as usize, UEFI_PAGE_SIZE)
+            .map_err(|_| EfiError::InvalidParameter)?;
+        let page_count = (end - start) / UEFI_PAGE_SIZE;
+
+        let writable = |access| matches!(access, AccessType::ReadWrite | AccessType::ReadWriteExecute);
+        let error = match memory_manager.get_page_attributes(start, page_count) {
+            Ok((access, _)) if writable(access) => return Ok(()),
+            Ok((access, _)) => (start, Ok(access)),
+            // Find the first page that is not writable.
+            Err(MemoryError::InconsistentRangeAttributes) => (0..page_count)
+                .map(|page| start + page * UEFI_PAGE_SIZE)
+                .map(|page| (page, memory_manager.get_page_attributes(page, 1).map(|(access, _)| access)))
+                .find(|(_, attributes)| !attributes.as_ref().is_ok_and(|access| writable(*access)))
+                .unwrap_or((start, Err(MemoryError::InconsistentRangeAttributes))),
+            Err(err) => (start, Err(err)),
+        };
+
+        // Stop writing to the memory log before reporting, as the report itself would be written to it.
+        self.memory_log_writable.store(false, Ordering::Relaxed);
+        log::error!(
+            "Advanced logger buffer {address:#x}-{:#x} is not entirely writable, page {:#x} attributes are {:?}. \
+             Memory logging is disabled.",
+            address + memory_log.get_size() as usize,
+            error.0,
+            error.1
+        );
+        Err(EfiError::AccessDenied)
+    }
+
     pub(crate) fn get_log_address(&self) -> Option<efi::PhysicalAddress> {
-        self.memory_log.get().map(|log| log.get_address())
+        self.current_memory_log().map(|log| log.get_address())
+    }
+
+    pub(crate) fn get_memory_log(&self) -> Option<&AdvancedLog<'static>> {
+        self.current_memory_log()
     }
 }
 
@@ -106,21 +599,31 @@ impl<S> log::Log for AdvancedLogger<'_, S>
 where
     S: SerialIO + Send,
 {
+    #[inline]
     fn enabled(&self, metadata: &log::Metadata) -> bool {
+        if !self.level_enabled(metadata.level()) {
+            return false;
+        }
+
         metadata.level().to_level_filter()
-            <= *self
+            <= self
                 .target_filters
                 .iter()
                 .find(|(name, _)| metadata.target().starts_with(name))
-                .map(|(_, level)| level)
-                .unwrap_or(&self.max_level)
+                .map(|(_, level)| *level)
+                .unwrap_or_else(|| self.max_level())
     }
 
     fn log(&self, record: &log::Record) {
+        // The record's arguments are only formatted by `Format::write`, so this check must come first.
         if self.enabled(record.metadata()) {
             let level = log_level_to_debug_level(record.metadata().level());
             let mut writer = BufferedWriter::new(level, self);
-            self.format.write(&mut writer, record);
+            let timestamp = match self.format {
+                Format::Standard => None,
+                Format::Json | Format::VerboseJson => Some(self.time_source.timestamp()),
+            };
+            self.format.write_with_timestamp(&mut writer, record, timestamp);
             writer.flush();
         }
     }
@@ -141,6 +644,42 @@ const fn log_level_to_debug_level(level: Level) -> u32 {
     }
 }
 
+/// Returns the most verbose level any filter allows, used to reject records before searching the target filters.
+const fn widest_level(max_level: LevelFilter, target_filters: &[(&str, LevelFilter)]) -> LevelFilter {
+    let mut widest_level = max_level;
+    let mut i = 0;
+    while i < target_filters.len() {
+        if target_filters[i].1 as usize > widest_level as usize {
+            widest_level = target_filters[i].1;
+        }
+        i += 1;
+    }
+    widest_level
+}
+
+/// Converts a value stored by `LevelFilter as usize` back to the level filter.
+const fn level_filter_from_usize(value: usize) -> LevelFilter {
+    match value {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Parses the payload of the log level override variable.
+fn parse_level_filter(payload: &[u8]) -> Option<LevelFilter> {
+    match payload {
+        [value] if (*value as usize) <= LevelFilter::max() as usize => Some(level_filter_from_usize(*value as usize)),
+        _ => {
+            let name = core::str::from_utf8(payload).ok()?.trim_end_matches('\0');
+            name.parse().ok()
+        }
+    }
+}
+
 /// Size of the buffer for the buffered writer.
 const WRITER_BUFFER_SIZE: usize = 128;
 
@@ -201,3 +740,499 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use core::{
+        fmt,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use alloc::boxed::Box;
+    use log::{LevelFilter, Log};
+    use patina::{
+        component::service::memory::{CachingType, MockMemoryManager},
+        runtime_services::MockRuntimeServices,
+        serial::uart::UartNull,
+    };
+
+    use super::*;
+
+    /// Counts how many times it has been formatted.
+    struct FormatCounter<'a>(&'a AtomicUsize);
+
+    impl fmt::Display for FormatCounter<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            f.write_str("counted")
+        }
+    }
+
+    fn log_counted(logger: &impl Log, level: Level, target: &str, counter: &AtomicUsize) {
+        let counted = FormatCounter(counter);
+        logger.log(&log::Record::builder().level(level).target(target).args(format_args!("{counted}")).build());
+    }
+
+    #[test]
+    fn level_enabled_should_use_the_widest_filter() {
+        let logger = AdvancedLogger::new(
+            Format::Standard,
+            &[("noisy", LevelFilter::Trace), ("quiet", LevelFilter::Off)],
+            LevelFilter::Warn,
+            UartNull {},
+        );
+        assert!(logger.level_enabled(Level::Trace));
+
+        let logger =
+            AdvancedLogger::new(Format::Standard, &[("quiet", LevelFilter::Error)], LevelFilter::Info, UartNull {});
+        assert!(logger.level_enabled(Level::Info));
+        assert!(!logger.level_enabled(Level::Debug));
+    }
+
+    const LEVEL_VARIABLE_GUID: efi::Guid =
+        efi::Guid::from_fields(0x6f2b8a52, 0x3f1c, 0x4d0e, 0x9a, 0x61, &[0x2c, 0x7e, 0x43, 0x15, 0xb8, 0xd9]);
+    const LEVEL_VARIABLE_NAME: &[u16] = &[b'L' as u16, b'v' as u16, 0];
+
+    fn apply_level_override(
+        logger: &AdvancedLogger<'_, UartNull>,
+        variable: Result<Vec<u8>, efi::Status>,
+    ) -> LevelFilter {
+        let mut runtime_services = MockRuntimeServices::new();
+        runtime_services.expect_get_variable::<Vec<u8>>().once().returning(move |name, namespace, _| {
+            assert_eq!(name, LEVEL_VARIABLE_NAME);
+            assert_eq!(*namespace, LEVEL_VARIABLE_GUID);
+            variable.clone().map(|payload| (payload, 0))
+        });
+        logger.apply_level_override(&runtime_services)
+    }
+
+    fn level_override_logger() -> AdvancedLogger<'static, UartNull> {
+        AdvancedLogger::new(Format::Standard, &[("quiet", LevelFilter::Error)], LevelFilter::Info, UartNull {})
+            .with_variable_override(LEVEL_VARIABLE_GUID, LEVEL_VARIABLE_NAME)
+    }
+
+    /// Restores the global [`log::max_level`] when dropped, for tests that change it.
+    struct MaxLevelGuard(LevelFilter);
+
+    impl MaxLevelGuard {
+        fn new() -> Self {
+            Self(log::max_level())
+        }
+    }
+
+    impl Drop for MaxLevelGuard {
+        fn drop(&mut self) {
+            log::set_max_level(self.0);
+        }
+    }
+
+    fn metadata(level: Level, target: &str) -> log::Metadata<'_> {
+        log::Metadata::builder().level(level).target(target).build()
+    }
+
+    #[test]
+    fn absent_level_override_should_keep_the_max_level() {
+        let logger = level_override_logger();
+        assert_eq!(apply_level_override(&logger, Err(efi::Status::NOT_FOUND)), LevelFilter::Info);
+        assert!(logger.enabled(&metadata(Level::Info, "any")));
+        assert!(!logger.enabled(&metadata(Level::Debug, "any")));
+
+        // Without a variable, runtime services are not used.
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Warn, UartNull {});
+        assert_eq!(logger.apply_level_override(&MockRuntimeServices::new()), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn level_override_should_replace_the_max_level() {
+        let _max_level = MaxLevelGuard::new();
+        let logger = level_override_logger();
+        assert_eq!(apply_level_override(&logger, Ok(alloc::vec![LevelFilter::Trace as u8])), LevelFilter::Trace);
+        assert!(logger.level_enabled(Level::Trace));
+        // The global maximum is raised so trace records reach the logger.
+        assert_eq!(log::max_level(), LevelFilter::Trace);
+        assert!(logger.enabled(&metadata(Level::Trace, "any")));
+        // Target filters still apply.
+        assert!(!logger.enabled(&metadata(Level::Warn, "quiet")));
+
+        assert_eq!(apply_level_override(&logger, Ok(b"warn\0".to_vec())), LevelFilter::Warn);
+        assert!(!logger.level_enabled(Level::Info));
+        assert!(logger.enabled(&metadata(Level::Warn, "any")));
+
+        assert_eq!(apply_level_override(&logger, Ok(b"OFF".to_vec())), LevelFilter::Off);
+        assert!(!logger.enabled(&metadata(Level::Error, "any")));
+        assert!(logger.enabled(&metadata(Level::Error, "quiet")));
+    }
+
+    #[test]
+    fn malformed_level_override_should_keep_the_max_level() {
+        let logger = level_override_logger();
+        for payload in [alloc::vec![], alloc::vec![6], alloc::vec![0xff, 0xfe], b"loud".to_vec()] {
+            assert_eq!(apply_level_override(&logger, Ok(payload)), LevelFilter::Info);
+        }
+        assert!(logger.enabled(&metadata(Level::Info, "any")));
+        assert!(!logger.level_enabled(Level::Debug));
+    }
+
+    #[test]
+    fn filtered_records_should_not_be_formatted() {
+        let counter = AtomicUsize::new(0);
+        let logger =
+            AdvancedLogger::new(Format::Standard, &[("noisy", LevelFilter::Debug)], LevelFilter::Warn, UartNull {});
+
+        // Rejected by the fast path.
+        log_counted(&logger, Level::Trace, "noisy", &counter);
+        // Rejected by the target filters.
+        log_counted(&logger, Level::Info, "other", &counter);
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+        log_counted(&logger, Level::Debug, "noisy", &counter);
+        log_counted(&logger, Level::Warn, "other", &counter);
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+    }
+
+    fn logger_with_memory_log() -> AdvancedLogger<'static, UartNull> {
+        let buffer = Box::leak(Box::new([0_u64; 0x2000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, UartNull {});
+        logger.set_log_info_address(address);
+        logger
+    }
+
+    /// Returns scripted timestamps in order, repeating the last one once exhausted.
+    struct ScriptedTimeSource {
+        timestamps: &'static [u64],
+        frequency: u64,
+        next: AtomicUsize,
+    }
+
+    impl TimeSource for ScriptedTimeSource {
+        fn timestamp(&self) -> u64 {
+            let index = self.next.fetch_add(1, Ordering::Relaxed).min(self.timestamps.len() - 1);
+            self.timestamps[index]
+        }
+
+        fn frequency(&self) -> u64 {
+            self.frequency
+        }
+    }
+
+    #[test]
+    fn memory_log_entries_should_use_the_time_source() {
+        static TIME_SOURCE: ScriptedTimeSource =
+            ScriptedTimeSource { timestamps: &[1_000, 2_500], frequency: 1_000_000, next: AtomicUsize::new(0) };
+
+        let buffer = Box::leak(Box::new([0_u64; 0x2000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+
+        let logger =
+            AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, UartNull {}).with_time_source(&TIME_SOURCE);
+        logger.set_log_info_address(address);
+        logger.log_write(0, b"first");
+        logger.log_write(0, b"second");
+
+        let memory_log = logger.memory_log.get().unwrap();
+        assert_eq!(memory_log.get_frequency(), 1_000_000);
+        let timestamps: alloc::vec::Vec<_> = memory_log.iter().map(|entry| entry.timestamp).collect();
+        assert_eq!(timestamps, [1_000, 2_500]);
+    }
+
+    #[test]
+    fn json_records_should_be_chunked_into_the_memory_log() {
+        static TIME_SOURCE: ScriptedTimeSource =
+            ScriptedTimeSource { timestamps: &[77], frequency: 1_000_000, next: AtomicUsize::new(0) };
+
+        let buffer = Box::leak(Box::new([0_u64; 0x2000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+
+        let logger =
+            AdvancedLogger::new(Format::Json, &[], LevelFilter::Trace, UartNull {}).with_time_source(&TIME_SOURCE);
+        logger.set_log_info_address(address);
+
+        // Longer than the writer buffer, so it is written in several entries.
+        let message = "\"quoted\"\n".repeat(WRITER_BUFFER_SIZE / 4);
+        logger.log(&log::Record::builder().level(Level::Info).args(format_args!("{message}")).build());
+
+        let memory_log = logger.memory_log.get().unwrap();
+        let entries: Vec<_> = memory_log.iter().map(|entry| entry.data.to_vec()).collect();
+        assert!(entries.len() > 1);
+        let expected = alloc::format!(
+            "{{\"timestamp\": 77, \"level\": \"INFO\", \"message\": \"{}\"}}\n",
+            "\\\"quoted\\\"\\n".repeat(WRITER_BUFFER_SIZE / 4)
+        );
+        assert_eq!(entries.concat(), expected.as_bytes());
+    }
+
+    #[test]
+    fn memory_log_entries_should_record_the_current_phase() {
+        let logger = logger_with_memory_log();
+        assert_eq!(logger.phase(), Phase::Dxe);
+        logger.log_write(0, b"before");
+        logger.set_phase(Phase::Runtime);
+        assert_eq!(logger.phase(), Phase::Runtime);
+        logger.log_write(0, b"after");
+
+        let phases: Vec<_> = logger.memory_log.get().unwrap().iter().map(|entry| entry.phase).collect();
+        assert_eq!(phases[phases.len() - 2..], [Phase::Dxe as u16, Phase::Runtime as u16]);
+    }
+
+    #[test]
+    fn phase_should_round_trip_through_its_value_and_name() {
+        for value in 0..=11 {
+            let phase = Phase::from_raw(value).unwrap();
+            assert_eq!(phase as u16, value);
+            assert_eq!(phase.name().to_ascii_lowercase().parse(), Ok(phase));
+        }
+        assert_eq!(Phase::from_raw(12), None);
+        assert_eq!("BDS".parse::<Phase>(), Err("Unknown boot phase."));
+    }
+
+    /// Fails a number of writes, then accepts the rest.
+    struct FailingPort {
+        failures: AtomicU32,
+        attempts: AtomicU32,
+    }
+
+    impl FailingPort {
+        fn leak(failures: u32) -> &'static Self {
+            Box::leak(Box::new(Self { failures: AtomicU32::new(failures), attempts: AtomicU32::new(0) }))
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.swap(0, Ordering::Relaxed)
+        }
+    }
+
+    impl SerialIO for &FailingPort {
+        fn init(&self) {}
+
+        fn write(&self, buffer: &[u8]) {
+            let _ = self.try_write(buffer);
+        }
+
+        fn try_write(&self, _buffer: &[u8]) -> patina::error::Result<()> {
+            self.attempts.fetch_add(1, Ordering::Relaxed);
+            match self.failures.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |failures| failures.checked_sub(1)) {
+                Ok(_) => Err(EfiError::DeviceError),
+                Err(_) => Ok(()),
+            }
+        }
+
+        fn read(&self) -> u8 {
+            0
+        }
+
+        fn try_read(&self) -> Option<u8> {
+            None
+        }
+    }
+
+    fn failing_port_logger(
+        port: &'static FailingPort,
+        policy: HardwarePortErrorPolicy,
+    ) -> AdvancedLogger<'static, &'static FailingPort> {
+        let buffer = Box::leak(Box::new([0_u64; 0x2000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe { AdvancedLog::initialize_memory_log_with_hw_print_level(address, size_of_val(buffer) as u32, ERROR) }
+            .unwrap();
+
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, port)
+            .with_hardware_port_error_policy(policy);
+        logger.set_log_info_address(address);
+        logger
+    }
+
+    const ERROR: u32 = log_level_to_debug_level(Level::Error);
+
+    fn memory_log_messages<S: SerialIO + Send>(logger: &AdvancedLogger<'static, S>) -> Vec<Vec<u8>> {
+        logger.get_memory_log().unwrap().iter().map(|entry| entry.data.to_vec()).collect()
+    }
+
+    #[test]
+    fn failed_hardware_port_writes_should_be_dropped() {
+        let port = FailingPort::leak(u32::MAX);
+        let logger = failing_port_logger(port, HardwarePortErrorPolicy::Drop);
+
+        for _ in 0..3 {
+            logger.log_write(ERROR, b"entry");
+            assert_eq!(port.attempts(), 1);
+        }
+        assert_eq!(memory_log_messages(&logger), [b"entry"; 3]);
+    }
+
+    #[test]
+    fn failed_hardware_port_writes_should_be_retried() {
+        let port = FailingPort::leak(u32::MAX);
+        let logger = failing_port_logger(port, HardwarePortErrorPolicy::Retry(2));
+        logger.log_write(ERROR, b"entry");
+        assert_eq!(port.attempts(), 3);
+
+        let port = FailingPort::leak(1);
+        let logger = failing_port_logger(port, HardwarePortErrorPolicy::Retry(2));
+        logger.log_write(ERROR, b"entry");
+        assert_eq!(port.attempts(), 2);
+        logger.log_write(ERROR, b"entry");
+        assert_eq!(port.attempts(), 1);
+    }
+
+    #[test]
+    fn hardware_port_should_be_disabled_after_repeated_failures() {
+        const DISABLED: &[u8] = b"Advanced logger disabled the hardware port after repeated write failures.\n";
+
+        // A successful write resets the count of consecutive failures.
+        let port = FailingPort::leak(1);
+        let logger = failing_port_logger(port, HardwarePortErrorPolicy::DisableAfter(2));
+        for _ in 0..3 {
+            logger.log_write(ERROR, b"entry");
+        }
+        assert_eq!(port.attempts(), 3);
+        assert!(!memory_log_messages(&logger).iter().any(|message| message == DISABLED));
+
+        let port = FailingPort::leak(u32::MAX);
+        let logger = failing_port_logger(port, HardwarePortErrorPolicy::DisableAfter(2));
+        for _ in 0..4 {
+            logger.log_write(ERROR, b"entry");
+        }
+        assert_eq!(port.attempts(), 2);
+        let messages = memory_log_messages(&logger);
+        assert_eq!(messages.iter().filter(|message| *message == DISABLED).count(), 1);
+        assert_eq!(messages.iter().filter(|message| *message == b"entry").count(), 4);
+    }
+
+    fn memory_log_entries(logger: &AdvancedLogger<'static, UartNull>) -> usize {
+        logger.memory_log.get().unwrap().iter().count()
+    }
+
+    #[test]
+    fn relocated_memory_log_should_keep_entries_and_receive_new_ones() {
+        let logger = logger_with_memory_log();
+        let old_address = logger.get_log_address().unwrap();
+        logger.log_write(0, b"first");
+        logger.log_write(0, b"second");
+
+        // The memory log address is also held by the HOB and the installed protocol.
+        let hob_log_info = Box::leak(Box::new(old_address));
+        let protocol_log_info = Box::leak(Box::new(old_address));
+        // SAFETY: Both addresses are leaked, so are valid for the life of the test.
+        unsafe {
+            logger.set_log_info_hob(hob_log_info);
+            logger.set_protocol_log_info(protocol_log_info);
+        }
+
+        let buffer = Box::leak(Box::new([0_u64; 0x4000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        assert_eq!(unsafe { logger.relocate_memory_log(address, size_of_val(buffer) as u32) }, Ok(()));
+        assert_eq!(logger.get_log_address(), Some(address));
+        assert_eq!(logger.get_memory_log().unwrap().get_size(), size_of_val(buffer) as u32);
+        assert_eq!((*hob_log_info, *protocol_log_info), (address, address));
+
+        logger.log_write(0, b"third");
+        assert_eq!(memory_log_messages(&logger), [b"first".to_vec(), b"second".to_vec(), b"third".to_vec()]);
+        let old_log = logger.memory_log.get().unwrap();
+        assert_eq!(old_log.get_address(), old_address);
+        assert_eq!(old_log.iter().count(), 2);
+
+        // The old buffer is closed to writers still holding its address.
+        let entry = LogEntry { phase: 0, level: 0, timestamp: 0, data: b"late" };
+        assert_eq!(old_log.add_log_entry(entry), Err(EfiError::OutOfResources));
+
+        // SAFETY: The buffer is valid, the logger refuses a second relocation.
+        assert_eq!(
+            unsafe { logger.relocate_memory_log(address, size_of_val(buffer) as u32) },
+            Err(EfiError::AlreadyStarted)
+        );
+    }
+
+    #[test]
+    fn relocate_memory_log_should_refuse_a_small_buffer_or_a_log_being_written() {
+        let logger = logger_with_memory_log();
+        logger.log_write(0, b"entry");
+        let buffer = Box::leak(Box::new([0_u64; 0x4000]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        assert_eq!(unsafe { logger.relocate_memory_log(address, 64) }, Err(EfiError::BufferTooSmall));
+
+        // An interrupted writer holds the memory log.
+        logger.memory_log_writers.fetch_add(1, Ordering::SeqCst);
+        // SAFETY: As above.
+        assert_eq!(unsafe { logger.relocate_memory_log(address, size_of_val(buffer) as u32) }, Err(EfiError::NotReady));
+        logger.memory_log_writers.fetch_sub(1, Ordering::SeqCst);
+
+        // An entry written while the memory log is copied is counted as discarded.
+        logger.memory_log_relocating.store(true, Ordering::SeqCst);
+        logger.log_write(0, b"dropped");
+        logger.memory_log_relocating.store(false, Ordering::SeqCst);
+        assert_eq!(logger.get_memory_log().unwrap().discarded_size(), 0);
+
+        // SAFETY: As above.
+        assert_eq!(unsafe { logger.relocate_memory_log(address, size_of_val(buffer) as u32) }, Ok(()));
+        assert_eq!(memory_log_messages(&logger), [b"entry".to_vec()]);
+        assert_eq!(logger.get_memory_log().unwrap().discarded_size(), memory_log::entry_size(b"dropped".len()));
+
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, UartNull {});
+        // SAFETY: As above.
+        assert_eq!(unsafe { logger.relocate_memory_log(address, 64) }, Err(EfiError::NotStarted));
+    }
+
+    #[test]
+    fn validate_page_attributes_should_accept_a_writable_log() {
+        let logger = logger_with_memory_log();
+
+        let mut memory_manager = MockMemoryManager::new();
+        memory_manager
+            .expect_get_page_attributes()
+            .times(1)
+            .returning(|_, _| Ok((AccessType::ReadWrite, CachingType::WriteBack)));
+
+        assert!(logger.validate_page_attributes(&memory_manager).is_ok());
+
+        let entries = memory_log_entries(&logger);
+        logger.log_write(0, b"still logged");
+        assert_eq!(memory_log_entries(&logger), entries + 1);
+    }
+
+    #[test]
+    fn validate_page_attributes_should_disable_a_log_with_mismatched_pages() {
+        let logger = logger_with_memory_log();
+        let start = align_down(logger.get_log_address().unwrap() as usize, UEFI_PAGE_SIZE).unwrap();
+        let read_only_page = start + 2 * UEFI_PAGE_SIZE;
+
+        let mut memory_manager = MockMemoryManager::new();
+        memory_manager
+            .expect_get_page_attributes()
+            .withf(|_, page_count| *page_count > 1)
+            .times(1)
+            .returning(|_, _| Err(MemoryError::InconsistentRangeAttributes));
+        memory_manager.expect_get_page_attributes().withf(|_, page_count| *page_count == 1).times(3).returning(
+            move |address, _| {
+                if address == read_only_page {
+                    Ok((AccessType::ReadOnly, CachingType::WriteBack))
+                } else {
+                    Ok((AccessType::ReadWrite, CachingType::WriteBack))
+                }
+            },
+        );
+
+        assert_eq!(logger.validate_page_attributes(&memory_manager), Err(EfiError::AccessDenied));
+
+        let entries = memory_log_entries(&logger);
+        logger.log_write(0, b"not logged");
+        assert_eq!(memory_log_entries(&logger), entries);
+    }
+
+    #[test]
+    fn validate_page_attributes_should_require_a_memory_log() {
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, UartNull {});
+        assert_eq!(logger.validate_page_attributes(&MockMemoryManager::new()), Err(EfiError::NotStarted));
+    }
+}
@@ -11,18 +11,21 @@
 //!
 use core::{
     cell::UnsafeCell,
-    mem::size_of,
+    mem::{self, size_of},
+    ops::Range,
     ptr, slice,
-    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
 };
 use patina::{
-    base::align_up,
+    base::{UEFI_PAGE_SIZE, align_up},
     error::{EfiError, Result},
 };
 use r_efi::efi;
 use zerocopy::{FromBytes, IntoBytes};
 use zerocopy_derive::*;
 
+use crate::logger::OverflowPolicy;
+
 // { 0x4d60cfb5, 0xf481, 0x4a98, {0x9c, 0x81, 0xbf, 0xf8, 0x64, 0x60, 0xc4, 0x3e }}
 pub const ADV_LOGGER_HOB_GUID: efi::Guid =
     efi::Guid::from_fields(0x4d60cfb5, 0xf481, 0x4a98, 0x9c, 0x81, &[0xbf, 0xf8, 0x64, 0x60, 0xc4, 0x3e]);
@@ -33,8 +36,11 @@ pub const DEBUG_LEVEL_WARNING: u32 = 0x00000002;
 pub const DEBUG_LEVEL_INFO: u32 = 0x00000040;
 pub const DEBUG_LEVEL_VERBOSE: u32 = 0x00400000;
 
-// Phase definitions.
-pub const ADVANCED_LOGGER_PHASE_DXE: u16 = 4;
+/// The alignment of log entries required by the memory log format.
+pub const MIN_ENTRY_ALIGNMENT: u32 = 8;
+
+/// All debug level bits defined by the EDK II DebugLib, DEBUG_INIT through DEBUG_MANAGEABILITY and DEBUG_ERROR.
+const DEBUG_LEVEL_KNOWN_MASK: u32 = 0x80FB55FF;
 
 /// A struct for carrying log entry both as input and output to this module.
 /// This struct contains the key information for the log entry, but excludes the
@@ -62,6 +68,19 @@ pub struct AdvancedLog<'a> {
     pub(crate) header: &'a AdvLoggerInfo,
     /// The data portion of the memory log.
     data: LogData<'a>,
+    /// The alignment of the start of each entry written, relative to the header.
+    entry_alignment: u32,
+    /// What is done with an entry that does not fit in the remaining space.
+    overflow_policy: OverflowPolicy,
+    /// Set once an entry is discarded under [`OverflowPolicy::HaltLogging`].
+    halted: AtomicBool,
+    /// Held while an entry is written under [`OverflowPolicy::DropOldest`].
+    wrap_lock: AtomicBool,
+    /// The offset of the earliest entry written before the log last wrapped.
+    oldest_offset: AtomicU32,
+    /// The offset of the end of the entries written before the log last wrapped, or 0
+    /// if it has not.
+    wrap_end_offset: AtomicU32,
 }
 
 // SAFETY: The only interior mutability is the UnsafeCell for the data region of
@@ -86,20 +105,20 @@ impl AdvancedLog<'static> {
         //         that the memory is readable. Check just the header to establish
         //         that the log is valid.
         if unsafe {
-            (*log_info).signature != AdvLoggerInfo::SIGNATURE
-                || (*log_info).version != AdvLoggerInfo::VERSION
-                || (*log_info).log_buffer_offset < size_of::<AdvLoggerInfo>() as u32
+            (*log_info).signature() != AdvLoggerInfo::SIGNATURE
+                || (*log_info).version() != AdvLoggerInfo::VERSION
+                || (*log_info).log_buffer_offset() < size_of::<AdvLoggerInfo>() as u32
         } {
             None
         } else {
             // SAFETY: The log_info is valid, convert the data for future safety.
             unsafe {
                 let header = log_info.as_ref()?;
-                let data_size = header.log_buffer_size;
-                let data_start = (address + header.log_buffer_offset as u64) as *mut u8;
+                let data_size = header.log_buffer_size();
+                let data_start = (address + header.log_buffer_offset() as u64) as *mut u8;
                 let data = slice::from_raw_parts_mut(data_start, data_size as usize);
 
-                Some(Self { header, data: LogData::ReadWrite(UnsafeCell::from_mut(data)) })
+                Some(Self::new(header, LogData::ReadWrite(UnsafeCell::from_mut(data))))
             }
         }
     }
@@ -114,6 +133,21 @@ impl AdvancedLog<'static> {
     /// The caller is responsible for ensuring that the provided address is appropriately
     /// allocated and accessible.
     pub unsafe fn initialize_memory_log(address: efi::PhysicalAddress, length: u32) -> Option<Self> {
+        // SAFETY: The caller upholds the requirements of this function.
+        unsafe { Self::initialize_memory_log_with_hw_print_level(address, length, 0) }
+    }
+
+    /// Initializes a new Advanced Log buffer as [`Self::initialize_memory_log`] does, with entries of the levels in
+    /// `hw_print_level` also written to the hardware port.
+    ///
+    /// ### Safety
+    ///
+    /// As for [`Self::initialize_memory_log`].
+    pub(crate) unsafe fn initialize_memory_log_with_hw_print_level(
+        address: efi::PhysicalAddress,
+        length: u32,
+        hw_print_level: u32,
+    ) -> Option<Self> {
         if length < size_of::<AdvLoggerInfo>() as u32
             || !address.is_multiple_of(core::mem::align_of::<AdvLoggerInfo>() as u64)
         {
@@ -126,13 +160,69 @@ impl AdvancedLog<'static> {
         } else {
             // SAFETY: The caller should ensure that the address is valid and
             //         that the memory is writable.
-            unsafe { ptr::write(header, AdvLoggerInfo::new(length, false, 0, 0, efi::Time::default(), 0)) };
+            unsafe {
+                ptr::write(header, AdvLoggerInfo::new(length, false, 0, 0, efi::Time::default(), hw_print_level))
+            };
 
             // SAFETY: The header is now initialized, so we can safely create the
             //         AdvancedLog instance.
             unsafe { Self::adopt_memory_log(address) }
         }
     }
+
+    /// Copies the memory log to a new buffer at the provided address with the
+    /// specified length, returning the log in the new buffer.
+    ///
+    /// The header and the entries written so far are copied, and the header is
+    /// updated for the new length. The entry alignment and overflow policy of the
+    /// log are kept.
+    ///
+    /// This log is closed before it is copied by moving its current offset to the end
+    /// of its buffer, so that writers still holding its address, such as C producers
+    /// that located it earlier, find it full and count their entries as discarded
+    /// rather than writing them where they are no longer read.
+    ///
+    /// ### Safety
+    ///
+    /// The caller is responsible for ensuring that the provided address is appropriately
+    /// allocated, accessible and does not overlap this log. No entry may be written to
+    /// this log through this instance while it is copied.
+    pub(crate) unsafe fn relocate(&self, address: efi::PhysicalAddress, length: u32) -> Result<Self> {
+        if !address.is_multiple_of(core::mem::align_of::<AdvLoggerInfo>() as u64) {
+            return Err(EfiError::InvalidParameter);
+        }
+
+        let wrap_end = self.wrap_end_offset.load(Ordering::Relaxed);
+        if length < self.header.log_current_offset().max(wrap_end) {
+            return Err(EfiError::BufferTooSmall);
+        }
+
+        let current =
+            u32::from_le(self.header.log_current_offset.swap(self.header.full_size().to_le(), Ordering::Relaxed));
+        let used = current.max(wrap_end);
+        if length < used {
+            // An entry was written since the size was checked, reopen the log.
+            self.header.log_current_offset.store(current.to_le(), Ordering::Relaxed);
+            return Err(EfiError::BufferTooSmall);
+        }
+
+        let header = address as *mut AdvLoggerInfo;
+        // SAFETY: The caller ensures the new buffer is valid for `length` bytes. This log is valid up to the current
+        //         offset and the wrap end offset, and is closed so nothing else writes to it.
+        unsafe {
+            ptr::copy_nonoverlapping(self.get_address() as *const u8, header as *mut u8, used as usize);
+            ptr::write_bytes((header as *mut u8).add(used as usize), 0, (length - used) as usize);
+            (*header).log_buffer_size.store((length - self.header.log_buffer_offset()).to_le(), Ordering::Relaxed);
+            (*header).log_current_offset.store(current.to_le(), Ordering::Relaxed);
+        }
+
+        // SAFETY: The header was copied from this log, so describes a valid log in the new buffer.
+        let mut log = unsafe { Self::adopt_memory_log(address) }.ok_or(EfiError::InvalidParameter)?;
+        log.entry_alignment = self.entry_alignment;
+        log.overflow_policy = self.overflow_policy;
+        log.halted.store(self.halted.load(Ordering::Relaxed), Ordering::Relaxed);
+        Ok(log)
+    }
 }
 
 impl<'a> AdvancedLog<'a> {
@@ -150,32 +240,136 @@ impl<'a> AdvancedLog<'a> {
             unsafe { log_bytes.as_ptr().cast::<AdvLoggerInfo>().as_ref() }.ok_or(EfiError::InvalidParameter)?;
 
         // Check that this is a valid log header.
-        if header.signature != AdvLoggerInfo::SIGNATURE {
+        if header.signature() != AdvLoggerInfo::SIGNATURE {
             return Err(EfiError::InvalidParameter);
         }
 
         // Currently only supports version 5.
-        if header.version != AdvLoggerInfo::VERSION {
+        if header.version() != AdvLoggerInfo::VERSION {
             return Err(EfiError::Unsupported);
         }
 
         // Check that the various pointers are consistent.
-        let log_current = header.log_current_offset.load(Ordering::Relaxed);
-        if log_current < header.log_buffer_offset
+        let log_current = header.log_current_offset();
+        if log_current < header.log_buffer_offset()
             || log_current > header.full_size()
-            || header.log_buffer_offset < size_of::<AdvLoggerInfo>() as u32
+            || header.log_buffer_offset() < size_of::<AdvLoggerInfo>() as u32
         {
             return Err(EfiError::InvalidParameter);
         }
 
-        // Only require that the valid portion of the log buffer be present.
+        // Only require that the valid portion of the log buffer be present. The entries
+        // written before a wrapped log last wrapped are read only if present.
         if log_current > log_bytes.len() as u32 {
             return Err(EfiError::BufferTooSmall);
         }
 
-        let (_, data_slice) = log_bytes.split_at(header.log_buffer_offset as usize);
+        let (_, data_slice) = log_bytes.split_at(header.log_buffer_offset() as usize);
+
+        Ok(Self::new(header, LogData::ReadOnly(data_slice)))
+    }
+
+    fn new(header: &'a AdvLoggerInfo, data: LogData<'a>) -> Self {
+        let log = Self {
+            header,
+            data,
+            entry_alignment: MIN_ENTRY_ALIGNMENT,
+            overflow_policy: OverflowPolicy::DropNewest,
+            halted: AtomicBool::new(false),
+            wrap_lock: AtomicBool::new(false),
+            oldest_offset: AtomicU32::new(header.log_buffer_offset()),
+            wrap_end_offset: AtomicU32::new(0),
+        };
+
+        if let Some((oldest, wrap_end)) = log.find_wrapped_entries() {
+            log.oldest_offset.store(oldest, Ordering::Relaxed);
+            log.wrap_end_offset.store(wrap_end, Ordering::Relaxed);
+        }
+        log
+    }
+
+    /// Finds the entries written before the log last wrapped, returning the offsets of
+    /// the oldest of them and of their end.
+    ///
+    /// The header only describes the entries written since the log wrapped, so that C
+    /// readers are unaware of wrapping. The space after the current offset is kept zeroed
+    /// apart from the earlier entries, which are the valid entries following the zeroed
+    /// gap.
+    fn find_wrapped_entries(&self) -> Option<(u32, u32)> {
+        let data_end = self.header.log_buffer_offset() + self.data.get().len() as u32;
+        let oldest = self.first_entry_offset(self.header.log_current_offset(), data_end);
+        let mut wrap_end = oldest;
+        while let Some(len) = self.entry_len(wrap_end, data_end) {
+            wrap_end += len;
+        }
+        (wrap_end > oldest).then_some((oldest, wrap_end))
+    }
+
+    /// Sets the alignment of the start of each entry subsequently written to the
+    /// log, relative to the start of the log header.
+    ///
+    /// The alignment must be a power of two no greater than a UEFI page. Alignments
+    /// less than [`MIN_ENTRY_ALIGNMENT`] have no effect. Padding is placed between an
+    /// entry's header and its message, and is recorded in the header's message offset
+    /// so readers skip it.
+    ///
+    /// If nothing has been written to the log yet, the start of the log buffer in the
+    /// header is moved up to the alignment, so the first entry is aligned as well.
+    pub fn set_entry_alignment(&mut self, alignment: u32) -> Result<()> {
+        if !alignment.is_power_of_two() || alignment as usize > UEFI_PAGE_SIZE {
+            return Err(EfiError::InvalidParameter);
+        }
+
+        self.entry_alignment = alignment.max(MIN_ENTRY_ALIGNMENT);
+        self.align_empty_log_buffer();
+        Ok(())
+    }
+
+    /// Moves the start of the log buffer up to the entry alignment if the log is empty.
+    fn align_empty_log_buffer(&mut self) {
+        let buffer_offset = self.header.log_buffer_offset();
+        let Ok(aligned_offset) = align_up(buffer_offset, self.entry_alignment) else {
+            return;
+        };
+        let padding = aligned_offset - buffer_offset;
+        if padding == 0
+            || padding >= self.header.log_buffer_size()
+            || self.wrap_end_offset.load(Ordering::Relaxed) != 0
+            || self.data.get_mut().is_err()
+        {
+            return;
+        }
+
+        // Claim the padding as the current offset first, so the log is left as is if
+        // another writer, such as a C producer, has written to it.
+        if self
+            .header
+            .log_current_offset
+            .compare_exchange(buffer_offset.to_le(), aligned_offset.to_le(), Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        // The size is reduced before the offset is moved, so the end of the log never
+        // appears beyond the buffer.
+        self.header.log_buffer_size.store((self.header.log_buffer_size() - padding).to_le(), Ordering::Relaxed);
+        self.header.log_buffer_offset.store(aligned_offset.to_le(), Ordering::Relaxed);
+        if let LogData::ReadWrite(cell) = self.data {
+            let data: *mut [u8] = cell.get();
+            // SAFETY: The data is valid for its length, and the padding is less than it. The
+            //         padding is no longer part of the log buffer, so is not accessed again.
+            self.data = LogData::ReadWrite(UnsafeCell::from_mut(unsafe {
+                slice::from_raw_parts_mut((data as *mut u8).add(padding as usize), data.len() - padding as usize)
+            }));
+        }
+        self.oldest_offset.store(aligned_offset, Ordering::Relaxed);
+    }
 
-        Ok(Self { header, data: LogData::ReadOnly(data_slice) })
+    /// Sets what is done with an entry subsequently written that does not fit in
+    /// the remaining space of the log. See [`OverflowPolicy`].
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
     }
 
     pub fn add_log_entry(&self, log_entry: LogEntry) -> Result<()> {
@@ -186,21 +380,32 @@ impl<'a> AdvancedLog<'a> {
         // 2. Write the log entry to the allocated space in the log buffer.
         //
 
-        // Get the total size of the long entry with the header, including the
-        // alignment padding for 8 byte alignment.
-        let data_offset = size_of::<AdvLoggerMessageEntry>() as u16;
-        let unaligned_size = data_offset as u32 + log_entry.data.len() as u32;
-        let message_size = align_up(unaligned_size, 8).unwrap() as u32;
+        // Get the size of the log entry with the header, including the alignment
+        // padding for 8 byte alignment.
+        let entry_size = entry_size(log_entry.data.len());
+
+        // The entry is grown so the following entry starts at the entry alignment.
+        // Entries always start 8 byte aligned, so the padding is a multiple of 8.
+        let message_size = |offset: u32| align_up(offset + entry_size, self.entry_alignment).unwrap() as u32 - offset;
+
+        if self.overflow_policy == OverflowPolicy::DropOldest {
+            return self.add_wrapping_log_entry(log_entry, entry_size, message_size);
+        }
+
+        if self.halted.load(Ordering::Relaxed) {
+            self.header.discarded_size.fetch_add(entry_size, Ordering::Relaxed);
+            return Err(EfiError::OutOfResources);
+        }
 
         // try to swap in the updated value. if this grows beyond the buffer, fall out.
         // Using relaxed here as we only want the atomic swap and are not concerned
         // with ordering. The loop should still use the atomic swap and update each
         // iteration.
         let mut current_offset = self.header.log_current_offset.load(Ordering::Relaxed);
-        while current_offset + message_size <= self.header.full_size() {
+        while current_offset + message_size(current_offset) <= self.header.full_size() {
             match self.header.log_current_offset.compare_exchange(
                 current_offset,
-                current_offset + message_size,
+                current_offset + message_size(current_offset),
                 Ordering::Relaxed,
                 Ordering::Relaxed,
             ) {
@@ -210,14 +415,154 @@ impl<'a> AdvancedLog<'a> {
         }
 
         // check if we fell out of bounds.
+        let message_size = message_size(current_offset);
         if current_offset + message_size > self.header.full_size() {
+            if self.overflow_policy == OverflowPolicy::HaltLogging {
+                self.halted.store(true, Ordering::Relaxed);
+            }
             // Add the discarded value. No ordering needed as this is a single
             // operation.
             self.header.discarded_size.fetch_add(message_size, Ordering::Relaxed);
             return Err(EfiError::OutOfResources);
         }
 
-        let data_index = (current_offset - self.header.log_buffer_offset) as usize;
+        self.write_log_entry(&log_entry, current_offset, entry_size, message_size)
+    }
+
+    /// Adds a log entry under [`OverflowPolicy::DropOldest`], wrapping to the start
+    /// of the log buffer when the entry does not fit at the end.
+    ///
+    /// Once wrapped, the entries written before the wrap, from the oldest entry not yet
+    /// overwritten up to the wrap end offset in the header, are read before those from
+    /// the start of the buffer up to the current offset. The gap between the current
+    /// offset and the oldest entry is zeroed so readers can find the oldest entry.
+    fn add_wrapping_log_entry(
+        &self,
+        log_entry: LogEntry,
+        entry_size: u32,
+        message_size: impl Fn(u32) -> u32,
+    ) -> Result<()> {
+        // Entries are written one at a time so that the oldest entries are not read
+        // while being overwritten. An entry written from within another, such as from
+        // an interrupt, is discarded rather than waiting.
+        if self.wrap_lock.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            self.header.discarded_size.fetch_add(entry_size, Ordering::Relaxed);
+            return Err(EfiError::NotReady);
+        }
+
+        let result = self
+            .allocate_wrapping(&message_size)
+            .and_then(|offset| self.write_log_entry(&log_entry, offset, entry_size, message_size(offset)));
+        self.wrap_lock.store(false, Ordering::Release);
+        result
+    }
+
+    /// Allocates space for an entry of `message_size` at the current offset, wrapping
+    /// to the start of the buffer and overwriting the oldest entries as needed.
+    ///
+    /// The space is claimed with a compare exchange of the current offset, as other
+    /// writers to the buffer claim space, and is recomputed if another writer moved it.
+    fn allocate_wrapping(&self, message_size: &impl Fn(u32) -> u32) -> Result<u32> {
+        let header = self.header;
+        let buffer_start = header.log_buffer_offset();
+
+        loop {
+            let current_offset = header.log_current_offset();
+            let mut offset = current_offset;
+            let mut wrap_end = self.wrap_end_offset.load(Ordering::Relaxed);
+            let mut oldest = self.oldest_offset.load(Ordering::Relaxed);
+            let mut discarded = 0;
+
+            if offset + message_size(offset) > header.full_size() {
+                if buffer_start + message_size(buffer_start) > header.full_size() {
+                    header.discarded_size.fetch_add(message_size(offset), Ordering::Relaxed);
+                    return Err(EfiError::OutOfResources);
+                }
+
+                // Any entries remaining from before the previous wrap are older than all
+                // of those now in the buffer, so they are dropped.
+                if wrap_end > offset {
+                    discarded += wrap_end - oldest;
+                    self.zero(offset..wrap_end)?;
+                }
+                wrap_end = offset;
+                offset = buffer_start;
+                oldest = buffer_start;
+            }
+
+            let end = offset + message_size(offset);
+            if wrap_end > offset {
+                while oldest < end && oldest < wrap_end {
+                    match self.entry_len(oldest, wrap_end) {
+                        Some(len) => {
+                            discarded += len;
+                            oldest += len;
+                        }
+                        None => oldest = wrap_end,
+                    }
+                }
+
+                // What remains of the entries partly overwritten is zeroed, so readers can find
+                // the oldest entry.
+                if end < oldest {
+                    self.zero(end..oldest)?;
+                }
+                if oldest >= wrap_end {
+                    wrap_end = 0;
+                }
+            }
+
+            if header
+                .log_current_offset
+                .compare_exchange(current_offset.to_le(), end.to_le(), Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+            {
+                header.discarded_size.fetch_add(discarded, Ordering::Relaxed);
+                self.oldest_offset.store(oldest, Ordering::Relaxed);
+                self.wrap_end_offset.store(wrap_end, Ordering::Relaxed);
+                return Ok(offset);
+            }
+        }
+    }
+
+    /// Zeroes the log buffer in `range`, which holds only entries being dropped.
+    fn zero(&self, range: Range<u32>) -> Result<()> {
+        let buffer_start = self.header.log_buffer_offset();
+        // SAFETY: The range is within the log buffer, and no other entry is being written to it.
+        let bytes = unsafe {
+            let data: *mut [u8] = self.data.get_mut()?;
+            (&mut *data).get_mut((range.start - buffer_start) as usize..(range.end - buffer_start) as usize)
+        };
+        bytes.ok_or(EfiError::BufferTooSmall)?.fill(0);
+        Ok(())
+    }
+
+    /// Returns the aligned length of the entry at `offset`, in the entries ending at `end`.
+    fn entry_len(&self, offset: u32, end: u32) -> Option<u32> {
+        let buffer_start = self.header.log_buffer_offset();
+        let bytes = self.data.get().get((offset - buffer_start) as usize..(end - buffer_start) as usize)?;
+        read_entry(bytes).ok().map(|(_, len)| len as u32)
+    }
+
+    /// Returns the offset of the first entry at or after `offset`, skipping the zeroed
+    /// gap before the oldest entry of a wrapped log, or `end` if there is none.
+    fn first_entry_offset(&self, offset: u32, end: u32) -> u32 {
+        let buffer_start = self.header.log_buffer_offset();
+        let data = self.data.get();
+        (offset..end)
+            .step_by(MIN_ENTRY_ALIGNMENT as usize)
+            .find(|&offset| {
+                let start = (offset - buffer_start) as usize;
+                data.get(start..start + MIN_ENTRY_ALIGNMENT as usize).is_none_or(|word| word.iter().any(|&b| b != 0))
+            })
+            .unwrap_or(end)
+    }
+
+    /// Writes the entry into the space allocated for it at `offset`.
+    fn write_log_entry(&self, log_entry: &LogEntry, offset: u32, entry_size: u32, message_size: u32) -> Result<()> {
+        let padding = message_size - entry_size;
+
+        let data_index = (offset - self.header.log_buffer_offset()) as usize;
 
         // SAFETY: The space hase been allocated. It should now be safe to write
         // data so long as it sticks to the range of the allocated entry. Get only
@@ -228,12 +573,14 @@ impl<'a> AdvancedLog<'a> {
         };
 
         let (header_slice, entry_slice) = entry_slice.split_at_mut(size_of::<AdvLoggerMessageEntry>());
+        let (padding_slice, entry_slice) = entry_slice.split_at_mut(padding as usize);
         let (data_slice, remainder_slice) = entry_slice.split_at_mut(log_entry.data.len());
 
-        AdvLoggerMessageEntry::from_log_entry(&log_entry)
-            .write_to(header_slice)
-            .map_err(|_| EfiError::BufferTooSmall)?;
+        let mut entry_header = AdvLoggerMessageEntry::from_log_entry(log_entry);
+        entry_header.message_offset = (entry_header.message_offset() + padding as u16).to_le();
+        entry_header.write_to(header_slice).map_err(|_| EfiError::BufferTooSmall)?;
 
+        padding_slice.fill(0);
         log_entry.data.write_to(data_slice).map_err(|_| EfiError::BufferTooSmall)?;
         remainder_slice.fill(0);
 
@@ -249,7 +596,7 @@ impl<'a> AdvancedLog<'a> {
     }
 
     pub fn get_frequency(&self) -> u64 {
-        self.header.timer_frequency.load(Ordering::Relaxed)
+        self.header.timer_frequency()
     }
 
     pub fn set_frequency(&self, frequency: u64) {
@@ -261,15 +608,35 @@ impl<'a> AdvancedLog<'a> {
         self.header as *const AdvLoggerInfo as efi::PhysicalAddress
     }
 
+    /// Returns the size of the memory log, including the header.
+    pub fn get_size(&self) -> u32 {
+        self.header.full_size()
+    }
+
     // Allow unused as it is used in tests and intended for future general use.
     #[allow(dead_code)]
     pub fn discarded_size(&self) -> u32 {
         self.header.discarded_size.load(Ordering::Relaxed)
     }
+
+    /// Records `size` bytes of entries as discarded without being written to the log.
+    pub(crate) fn add_discarded_size(&self, size: u32) {
+        self.header.discarded_size.fetch_add(size, Ordering::Relaxed);
+    }
+}
+
+/// Returns the size of an entry with a message of `message_len` bytes, including the
+/// entry header and the padding for 8 byte alignment.
+pub(crate) fn entry_size(message_len: usize) -> u32 {
+    align_up(size_of::<AdvLoggerMessageEntry>() + message_len, MIN_ENTRY_ALIGNMENT as usize).unwrap() as u32
 }
 
 /// Implementation of the C struct ADVANCED_LOGGER_INFO for tracking in-memory
 /// logging structure for Advanced Logger.
+///
+/// The memory log is little-endian, the firmware's native byte order. Fields
+/// read while walking the log are decoded explicitly through the accessors below
+/// so that a log parsed on a big-endian host is not misread.
 #[derive(Debug)]
 #[repr(C)]
 pub(crate) struct AdvLoggerInfo {
@@ -280,7 +647,7 @@ pub(crate) struct AdvLoggerInfo {
     /// Reserved for future
     reserved1: [u16; 3],
     /// Offset from LoggerInfo to start of log, expected to be the size of this structure 8 byte aligned
-    log_buffer_offset: u32,
+    log_buffer_offset: AtomicU32,
     /// Reserved for future
     reserved2: u32,
     /// Offset from LoggerInfo to where to store next log entry.
@@ -288,7 +655,7 @@ pub(crate) struct AdvLoggerInfo {
     /// Number of bytes of messages missed
     discarded_size: AtomicU32,
     /// Size of allocated buffer
-    log_buffer_size: u32,
+    log_buffer_size: AtomicU32,
     /// Log in permanent RAM
     in_permanent_ram: bool,
     /// After ExitBootServices
@@ -330,11 +697,11 @@ impl AdvLoggerInfo {
             signature: Self::SIGNATURE,
             version: Self::VERSION,
             reserved1: [0, 0, 0],
-            log_buffer_offset: size_of::<AdvLoggerInfo>() as u32,
+            log_buffer_offset: AtomicU32::new(size_of::<AdvLoggerInfo>() as u32),
             reserved2: 0,
             log_current_offset: AtomicU32::new(size_of::<AdvLoggerInfo>() as u32),
             discarded_size: AtomicU32::new(0),
-            log_buffer_size: log_buffer_size - size_of::<AdvLoggerInfo>() as u32,
+            log_buffer_size: AtomicU32::new(log_buffer_size - size_of::<AdvLoggerInfo>() as u32),
             in_permanent_ram: true,
             at_runtime: false,
             gone_virtual: false,
@@ -351,7 +718,60 @@ impl AdvLoggerInfo {
     /// Returns the size of the log buffer, which is the size of the header plus
     /// the size of the data buffer.
     pub fn full_size(&self) -> u32 {
-        self.log_buffer_offset + self.log_buffer_size
+        self.log_buffer_offset() + self.log_buffer_size()
+    }
+
+    const fn signature(&self) -> u32 {
+        u32::from_le_bytes(self.signature.to_ne_bytes())
+    }
+
+    const fn version(&self) -> u16 {
+        u16::from_le_bytes(self.version.to_ne_bytes())
+    }
+
+    fn log_buffer_offset(&self) -> u32 {
+        u32::from_le_bytes(self.log_buffer_offset.load(Ordering::Relaxed).to_ne_bytes())
+    }
+
+    fn log_buffer_size(&self) -> u32 {
+        u32::from_le_bytes(self.log_buffer_size.load(Ordering::Relaxed).to_ne_bytes())
+    }
+
+    fn log_current_offset(&self) -> u32 {
+        u32::from_le_bytes(self.log_current_offset.load(Ordering::Relaxed).to_ne_bytes())
+    }
+
+    fn timer_frequency(&self) -> u64 {
+        u64::from_le_bytes(self.timer_frequency.load(Ordering::Relaxed).to_ne_bytes())
+    }
+
+    /// Reads the log buffer offset and timer frequency from a header at the start of
+    /// `bytes`, which need not be aligned, after checking its signature and version.
+    // Only used in the parser which is not always compiled.
+    #[allow(dead_code)]
+    pub(crate) fn read_stream_info(bytes: &[u8]) -> Result<(u32, u64)> {
+        fn read<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+            bytes[offset..offset + N].try_into().unwrap()
+        }
+
+        if bytes.len() < size_of::<Self>() {
+            return Err(EfiError::BufferTooSmall);
+        }
+
+        if u32::from_le_bytes(read(bytes, mem::offset_of!(Self, signature))) != Self::SIGNATURE {
+            return Err(EfiError::InvalidParameter);
+        }
+
+        if u16::from_le_bytes(read(bytes, mem::offset_of!(Self, version))) != Self::VERSION {
+            return Err(EfiError::Unsupported);
+        }
+
+        let log_buffer_offset = u32::from_le_bytes(read(bytes, mem::offset_of!(Self, log_buffer_offset)));
+        if log_buffer_offset < size_of::<Self>() as u32 {
+            return Err(EfiError::InvalidParameter);
+        }
+
+        Ok((log_buffer_offset, u64::from_le_bytes(read(bytes, mem::offset_of!(Self, timer_frequency)))))
     }
 }
 
@@ -385,6 +805,9 @@ impl<'a> LogData<'a> {
 
 /// Implementation of the C struct ADVANCED_LOGGER_MESSAGE_ENTRY_V2 for heading
 /// a memory log entry.
+///
+/// Multi-byte fields are stored little-endian and must be read through the
+/// accessors, which decode them regardless of the host byte order.
 #[repr(C)]
 #[repr(packed)]
 #[derive(Debug, IntoBytes, FromBytes, Immutable, KnownLayout)]
@@ -425,14 +848,14 @@ impl AdvLoggerMessageEntry {
     ///
     const fn new(boot_phase: u16, level: u32, timestamp: u64, message_length: u16) -> Self {
         Self {
-            signature: Self::SIGNATURE,
+            signature: Self::SIGNATURE.to_le(),
             major_version: Self::MAJOR_VERSION,
             minor_version: Self::MINOR_VERSION,
-            level,
-            timestamp,
-            boot_phase,
-            message_length,
-            message_offset: size_of::<Self>() as u16,
+            level: level.to_le(),
+            timestamp: timestamp.to_le(),
+            boot_phase: boot_phase.to_le(),
+            message_length: message_length.to_le(),
+            message_offset: (size_of::<Self>() as u16).to_le(),
         }
     }
 
@@ -441,9 +864,66 @@ impl AdvLoggerMessageEntry {
         Self::new(entry.phase, entry.level, entry.timestamp, entry.data.len() as u16)
     }
 
-    /// Returns the length of the entire log entry.
+    /// Reads an entry header from the start of `bytes` and validates it, where
+    /// `bytes` extends to the end of the valid log data.
+    fn parse(bytes: &[u8]) -> Result<&Self> {
+        let (header, _) = Self::ref_from_prefix(bytes).map_err(|_| EfiError::BufferTooSmall)?;
+        header.validate(bytes.len())?;
+        Ok(header)
+    }
+
+    /// Validates that the header is a supported version, that its message fits in
+    /// the `available` bytes following the start of the header, and that the level
+    /// only contains known debug level bits.
+    fn validate(&self, available: usize) -> Result<()> {
+        if self.signature() != Self::SIGNATURE
+            || self.major_version != Self::MAJOR_VERSION
+            || (self.message_offset() as usize) < size_of::<Self>()
+        {
+            return Err(EfiError::Unsupported);
+        }
+
+        if self.len() > available {
+            return Err(EfiError::BufferTooSmall);
+        }
+
+        if self.level() & !DEBUG_LEVEL_KNOWN_MASK != 0 {
+            return Err(EfiError::CompromisedData);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the length of the entire log entry, including any padding before the message.
     pub fn len(&self) -> usize {
-        size_of::<Self>() + self.message_length as usize
+        self.message_offset() as usize + self.message_length() as usize
+    }
+
+    const fn signature(&self) -> u32 {
+        u32::from_le_bytes(self.signature.to_ne_bytes())
+    }
+
+    /// Returns the error level of the entry.
+    pub const fn level(&self) -> u32 {
+        u32::from_le_bytes(self.level.to_ne_bytes())
+    }
+
+    /// Returns the timestamp of the entry.
+    pub const fn timestamp(&self) -> u64 {
+        u64::from_le_bytes(self.timestamp.to_ne_bytes())
+    }
+
+    /// Returns the boot phase that produced the entry.
+    pub const fn boot_phase(&self) -> u16 {
+        u16::from_le_bytes(self.boot_phase.to_ne_bytes())
+    }
+
+    const fn message_length(&self) -> u16 {
+        u16::from_le_bytes(self.message_length.to_ne_bytes())
+    }
+
+    const fn message_offset(&self) -> u16 {
+        u16::from_le_bytes(self.message_offset.to_ne_bytes())
     }
 
     /// Returns the aligned length of the entire log entry.
@@ -455,16 +935,28 @@ impl AdvLoggerMessageEntry {
 }
 
 /// Iterator for an advanced logger memory buffer log.
+///
+/// The entries of a wrapped log are returned oldest first, starting with those
+/// written before it wrapped.
 pub struct AdvLogIterator<'a> {
     log: &'a AdvancedLog<'a>,
     offset: usize,
+    /// The end of the entries written before the log wrapped, while they are read.
+    wrap_end: Option<usize>,
 }
 
 /// Iterator for an Advanced Logger memory buffer.
 impl<'a> AdvLogIterator<'a> {
     /// Creates a new log iterator from a given AdvLoggerInfo reference.
-    const fn new(log: &'a AdvancedLog) -> Self {
-        AdvLogIterator { log, offset: log.header.log_buffer_offset as usize }
+    fn new(log: &'a AdvancedLog) -> Self {
+        let current = log.header.log_current_offset();
+        let wrap_end = log.wrap_end_offset.load(Ordering::Relaxed);
+        if wrap_end > current {
+            let offset = log.oldest_offset.load(Ordering::Relaxed) as usize;
+            AdvLogIterator { log, offset, wrap_end: Some(wrap_end as usize) }
+        } else {
+            AdvLogIterator { log, offset: log.header.log_buffer_offset() as usize, wrap_end: None }
+        }
     }
 }
 
@@ -473,51 +965,61 @@ impl<'a> Iterator for AdvLogIterator<'a> {
 
     /// Provides the next advanced logger entry in the Advanced Logger memory buffer.
     fn next(&mut self) -> Option<Self::Item> {
-        if self.offset + size_of::<AdvLoggerMessageEntry>()
-            > self.log.header.log_current_offset.load(Ordering::Relaxed) as usize
-        {
-            None
-        } else {
-            // Get the data relative offset.
-            let mut data_index = self.offset - self.log.header.log_buffer_offset as usize;
-
-            // SAFETY: We have verified the buffer through the header, read that
-            //         to check the rest of the range.
-            let header_slice = unsafe {
-                let data: *const [u8] = self.log.data.get();
-                (&*data).get(data_index..data_index + size_of::<AdvLoggerMessageEntry>())?
-            };
-
-            let entry_header = AdvLoggerMessageEntry::ref_from_bytes(header_slice).ok()?;
-            data_index += size_of::<AdvLoggerMessageEntry>();
-
-            if self.offset + size_of::<AdvLoggerMessageEntry>() + entry_header.message_length as usize
-                > self.log.header.log_current_offset.load(Ordering::Relaxed) as usize
-            {
-                None
-            } else {
-                // SAFETY: We know that the buffer is valid through previous checks,
-                //         and this structure should be well formed from the header
-                //         information.
-                let entry_data = unsafe {
-                    let data: *const [u8] = self.log.data.get();
-                    (&*data).get(data_index..data_index + entry_header.message_length as usize)?
-                };
-
-                // Move the offset up by the aligned total size.
-                self.offset += entry_header.aligned_len();
-
-                Some(LogEntry {
-                    phase: entry_header.boot_phase,
-                    level: entry_header.level,
-                    timestamp: entry_header.timestamp,
-                    data: entry_data,
-                })
+        if let Some(wrap_end) = self.wrap_end {
+            let entry = self.log.data.get().get(
+                self.offset - self.log.header.log_buffer_offset() as usize
+                    ..wrap_end - self.log.header.log_buffer_offset() as usize,
+            );
+            if let Some((entry, aligned_len)) = entry.and_then(|bytes| read_entry(bytes).ok()) {
+                self.offset += aligned_len;
+                return Some(entry);
             }
+
+            // Continue with the entries written since the log wrapped.
+            self.wrap_end = None;
+            self.offset = self.log.header.log_buffer_offset() as usize;
         }
+
+        let log_current = self.log.header.log_current_offset() as usize;
+        let data_start = self.offset.checked_sub(self.log.header.log_buffer_offset() as usize)?;
+        let data_end = log_current.checked_sub(self.log.header.log_buffer_offset() as usize)?;
+
+        // SAFETY: We have verified the buffer through the header, the entry
+        //         header will verify the rest of the range.
+        let entry_slice = unsafe {
+            let data: *const [u8] = self.log.data.get();
+            (&*data).get(data_start..data_end)?
+        };
+
+        let (entry, aligned_len) = read_entry(entry_slice).ok()?;
+
+        // Move the offset up by the aligned total size.
+        self.offset += aligned_len;
+        Some(entry)
     }
 }
 
+/// Reads the entry at the start of `bytes`, where `bytes` extends to the end of the
+/// valid log data, returning it and its aligned length.
+///
+/// Returns `BufferTooSmall` if `bytes` ends before the entry does, which is not an
+/// error for a log that is still being read.
+pub(crate) fn read_entry(bytes: &[u8]) -> Result<(LogEntry<'_>, usize)> {
+    let entry_header = AdvLoggerMessageEntry::parse(bytes)?;
+    let entry_data =
+        bytes.get(entry_header.message_offset() as usize..entry_header.len()).ok_or(EfiError::BufferTooSmall)?;
+
+    Ok((
+        LogEntry {
+            phase: entry_header.boot_phase(),
+            level: entry_header.level(),
+            timestamp: entry_header.timestamp(),
+            data: entry_data,
+        },
+        entry_header.aligned_len(),
+    ))
+}
+
 #[cfg(test)]
 #[coverage(off)]
 mod tests {
@@ -526,6 +1028,7 @@ mod tests {
     use efi::PhysicalAddress;
 
     use super::*;
+    use crate::logger::Phase;
 
     #[test]
     fn create_fill_check_test() {
@@ -606,4 +1109,210 @@ mod tests {
 
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn message_entry_header_round_trip() {
+        let data = b"hello";
+        let entry = LogEntry { level: DEBUG_LEVEL_INFO, phase: Phase::Dxe as u16, timestamp: 1234, data };
+
+        let mut bytes = [0_u8; size_of::<AdvLoggerMessageEntry>() + 5];
+        AdvLoggerMessageEntry::from_log_entry(&entry).write_to_prefix(&mut bytes).unwrap();
+
+        let header = AdvLoggerMessageEntry::parse(&bytes).unwrap();
+        assert_eq!(header.level(), DEBUG_LEVEL_INFO);
+        assert_eq!(header.timestamp(), 1234);
+        assert_eq!(header.boot_phase(), Phase::Dxe as u16);
+        assert_eq!(header.len(), bytes.len());
+    }
+
+    #[test]
+    fn message_entry_header_validate_rejects_bad_headers() {
+        let data = [0_u8; 16];
+        let entry = LogEntry { level: DEBUG_LEVEL_ERROR, phase: 0, timestamp: 0, data: &data };
+        let size = size_of::<AdvLoggerMessageEntry>() + data.len();
+
+        // The message extends beyond the available log data.
+        assert_eq!(AdvLoggerMessageEntry::from_log_entry(&entry).validate(size - 1), Err(EfiError::BufferTooSmall));
+
+        let mut header = AdvLoggerMessageEntry::from_log_entry(&entry);
+        header.signature = 0x534D4C41_u32.to_le(); // ALMS, the version 1 signature.
+        assert_eq!(header.validate(size), Err(EfiError::Unsupported));
+
+        let mut header = AdvLoggerMessageEntry::from_log_entry(&entry);
+        header.message_offset = 4_u16.to_le();
+        assert_eq!(header.validate(size), Err(EfiError::Unsupported));
+
+        let mut header = AdvLoggerMessageEntry::from_log_entry(&entry);
+        header.level = 0x0000_0200_u32.to_le();
+        assert_eq!(header.validate(size), Err(EfiError::CompromisedData));
+
+        // Too short to hold a header.
+        assert!(AdvLoggerMessageEntry::parse(&[0_u8; 4]).is_err());
+    }
+
+    #[test]
+    fn aligned_entries_test() {
+        const ALIGNMENT: u32 = 64;
+        let mut buff_box = Box::new([0_u64; 0x400]);
+        let buffer = buff_box.as_mut();
+        let address = buffer as *mut u64 as PhysicalAddress;
+        let len = size_of_val(buffer) as u32;
+
+        // SAFETY: We just allocated this memory so it's valid.
+        let mut log = unsafe { AdvancedLog::initialize_memory_log(address, len) }.unwrap();
+        assert_eq!(log.set_entry_alignment(24), Err(EfiError::InvalidParameter));
+        assert_eq!(log.set_entry_alignment(0x2000), Err(EfiError::InvalidParameter));
+        log.set_entry_alignment(ALIGNMENT).unwrap();
+
+        let messages: [&[u8]; 4] = [b"a", b"0123456789abcdef0123456789abcdef0123456789", b"", b"last"];
+        let mut entry_starts = std::vec::Vec::new();
+        for message in messages {
+            entry_starts.push(log.header.log_current_offset.load(Ordering::Relaxed));
+            log.add_log_entry(LogEntry { level: DEBUG_LEVEL_INFO, phase: 0, timestamp: 0, data: message }).unwrap();
+        }
+
+        // The log was empty, so its buffer was moved up for the first entry to start
+        // aligned. Every entry after it pads itself so the following entry is aligned.
+        assert_ne!(size_of::<AdvLoggerInfo>() as u32 % ALIGNMENT, 0);
+        for start in &entry_starts {
+            assert_eq!(start % ALIGNMENT, 0);
+        }
+        assert_eq!(log.header.log_current_offset.load(Ordering::Relaxed) % ALIGNMENT, 0);
+
+        // The log can be read back, skipping the padding.
+        let log_bytes = unsafe { slice::from_raw_parts(address as *const u8, len as usize) };
+        let read_log = AdvancedLog::open_log(log_bytes).unwrap();
+        let read: std::vec::Vec<_> = read_log.iter().map(|entry| entry.get_message()).collect();
+        assert_eq!(read, messages);
+    }
+
+    #[test]
+    fn open_log_reads_little_endian_fields() {
+        let message = b"LE\n";
+        let header_size = size_of::<AdvLoggerInfo>();
+        let entry_size = size_of::<AdvLoggerMessageEntry>();
+        let log_current = header_size + align_up(entry_size + message.len(), 8).unwrap();
+
+        // Build the buffer byte by byte in little-endian order, independent of the host byte order.
+        let mut storage = [0_u64; 0x40];
+        let bytes = storage.as_mut_bytes();
+        let mut put = |offset: usize, field: &[u8]| bytes[offset..offset + field.len()].copy_from_slice(field);
+        put(core::mem::offset_of!(AdvLoggerInfo, signature), &AdvLoggerInfo::SIGNATURE.to_le_bytes());
+        put(core::mem::offset_of!(AdvLoggerInfo, version), &AdvLoggerInfo::VERSION.to_le_bytes());
+        put(core::mem::offset_of!(AdvLoggerInfo, log_buffer_offset), &(header_size as u32).to_le_bytes());
+        put(core::mem::offset_of!(AdvLoggerInfo, log_current_offset), &(log_current as u32).to_le_bytes());
+        put(core::mem::offset_of!(AdvLoggerInfo, log_buffer_size), &(0x200 - header_size as u32).to_le_bytes());
+        put(core::mem::offset_of!(AdvLoggerInfo, timer_frequency), &0x0102_0304_0506_0708_u64.to_le_bytes());
+
+        let entry = header_size;
+        put(entry, &AdvLoggerMessageEntry::SIGNATURE.to_le_bytes());
+        put(entry + 4, &[AdvLoggerMessageEntry::MAJOR_VERSION, AdvLoggerMessageEntry::MINOR_VERSION]);
+        put(entry + 6, &DEBUG_LEVEL_WARNING.to_le_bytes());
+        put(entry + 10, &0x1122_3344_5566_7788_u64.to_le_bytes());
+        put(entry + 18, &(Phase::Dxe as u16).to_le_bytes());
+        put(entry + 20, &(message.len() as u16).to_le_bytes());
+        put(entry + 22, &(entry_size as u16).to_le_bytes());
+        put(entry + entry_size, message);
+
+        let log = AdvancedLog::open_log(&bytes[..0x200]).unwrap();
+        assert_eq!(log.get_frequency(), 0x0102_0304_0506_0708);
+        assert_eq!(log.get_size(), 0x200);
+
+        let mut iter = log.iter();
+        let log_entry = iter.next().unwrap();
+        assert_eq!(log_entry.level, DEBUG_LEVEL_WARNING);
+        assert_eq!(log_entry.timestamp, 0x1122_3344_5566_7788);
+        assert_eq!(log_entry.phase, Phase::Dxe as u16);
+        assert_eq!(log_entry.get_message(), message);
+        assert!(iter.next().is_none());
+    }
+
+    /// Writes `count` numbered entries to a small log with `policy`, with message
+    /// lengths cycling through `lengths`, returning the numbers of the surviving
+    /// entries in the order read and the log.
+    fn fill_log(policy: OverflowPolicy, count: u32, lengths: &[usize]) -> (std::vec::Vec<u32>, AdvancedLog<'static>) {
+        const LOG_LEN: usize = size_of::<AdvLoggerInfo>() + 0x100;
+        let buffer = Box::leak(Box::new([0_u64; LOG_LEN / 8]));
+        let address = buffer.as_mut_ptr() as PhysicalAddress;
+
+        // SAFETY: The buffer was just allocated with the given length.
+        let mut log = unsafe { AdvancedLog::initialize_memory_log(address, LOG_LEN as u32) }.unwrap();
+        log.set_overflow_policy(policy);
+
+        for number in 0..count {
+            let mut data = std::vec![0xA5_u8; lengths[number as usize % lengths.len()]];
+            data[..4].copy_from_slice(&number.to_le_bytes());
+            let _ = log.add_log_entry(LogEntry { level: DEBUG_LEVEL_INFO, phase: 0, timestamp: 0, data: &data });
+        }
+
+        let numbers = |log: &AdvancedLog| {
+            log.iter().map(|entry| u32::from_le_bytes(entry.get_message()[..4].try_into().unwrap())).collect()
+        };
+        let surviving: std::vec::Vec<u32> = numbers(&log);
+
+        // A reader of the raw log recovers the same entries.
+        // SAFETY: The buffer is valid for its length.
+        let bytes = unsafe { slice::from_raw_parts(address as *const u8, LOG_LEN) };
+        assert_eq!(numbers(&AdvancedLog::open_log(bytes).unwrap()), surviving);
+        (surviving, log)
+    }
+
+    #[test]
+    fn drop_newest_overflow_policy_keeps_the_earliest_entries() {
+        // Each entry takes 32 bytes, so 8 fit.
+        let (surviving, log) = fill_log(OverflowPolicy::DropNewest, 20, &[4]);
+        assert_eq!(surviving, (0..8).collect::<std::vec::Vec<_>>());
+        assert_eq!(log.discarded_size(), 12 * 32);
+
+        // A smaller entry than the one discarded is still written if it fits.
+        let (surviving, _) = fill_log(OverflowPolicy::DropNewest, 7, &[4, 4, 4, 4, 4, 100, 4]);
+        assert_eq!(surviving, [0, 1, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn halt_logging_overflow_policy_stops_at_the_first_discarded_entry() {
+        let (surviving, log) = fill_log(OverflowPolicy::HaltLogging, 20, &[4]);
+        assert_eq!(surviving, (0..8).collect::<std::vec::Vec<_>>());
+        assert_eq!(log.discarded_size(), 12 * 32);
+
+        let (surviving, _) = fill_log(OverflowPolicy::HaltLogging, 7, &[4, 4, 4, 4, 4, 100, 4]);
+        assert_eq!(surviving, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drop_oldest_overflow_policy_keeps_the_latest_entries() {
+        let (surviving, log) = fill_log(OverflowPolicy::DropOldest, 20, &[4]);
+        assert_eq!(surviving, (12..20).collect::<std::vec::Vec<_>>());
+        assert_eq!(log.discarded_size(), 12 * 32);
+
+        // Entries of differing sizes overwrite parts of older entries, which are dropped whole.
+        for count in 1..60 {
+            let (surviving, _) = fill_log(OverflowPolicy::DropOldest, count, &[4, 20, 60, 12, 36]);
+            assert_eq!(surviving.last(), Some(&(count - 1)), "count {count}");
+            let first = surviving[0];
+            assert_eq!(surviving, (first..count).collect::<std::vec::Vec<_>>(), "count {count}");
+            // The gap before the oldest entry and the end of the buffer left at the wrap
+            // are each smaller than the largest entry, 88 bytes.
+            let lengths = [4_usize, 20, 60, 12, 36];
+            let used: usize = surviving.iter().map(|&n| align_up(24 + lengths[n as usize % 5], 8).unwrap()).sum();
+            assert!(used > 0x100 - 2 * 88 || first == 0, "count {count} uses {used} bytes");
+        }
+
+        // An entry too large for the whole log is discarded without dropping others.
+        let (surviving, _) = fill_log(OverflowPolicy::DropOldest, 3, &[4, 4, 0x200]);
+        assert_eq!(surviving, [0, 1]);
+    }
+
+    #[test]
+    fn wrapped_log_is_recovered_without_header_fields() {
+        let (surviving, log) = fill_log(OverflowPolicy::DropOldest, 23, &[4, 20, 60, 12, 36]);
+        assert_ne!(surviving[0], 0);
+        assert_eq!(log.header.reserved2, 0);
+
+        // SAFETY: The buffer of the log is still valid.
+        let adopted = unsafe { AdvancedLog::adopt_memory_log(log.get_address()) }.unwrap();
+        let adopted_numbers: std::vec::Vec<u32> =
+            adopted.iter().map(|entry| u32::from_le_bytes(entry.get_message()[..4].try_into().unwrap())).collect();
+        assert_eq!(adopted_numbers, surviving);
+    }
 }
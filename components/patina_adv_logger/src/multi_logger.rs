@@ -0,0 +1,142 @@
+//! Multiple Logger Support
+//!
+//! This module provides a struct that implements log::Log by forwarding each
+//! record to several loggers, so that the advanced logger can be used alongside
+//! other loggers while the log crate only allows a single global logger.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+
+/// A logger that forwards each record to all of a fixed set of loggers.
+///
+/// The set of loggers is fixed when the logger is created, so no locking is required to log a record. Each logger
+/// applies its own filtering, and only receives the records it has enabled.
+///
+/// ## Example
+///
+/// ```
+/// use patina_adv_logger::{logger::AdvancedLogger, multi_logger::MultiLogger};
+///
+/// static ADV_LOGGER: AdvancedLogger<patina::serial::uart::UartNull> = AdvancedLogger::new(
+///      patina::log::Format::Standard,
+///      &[],
+///      log::LevelFilter::Info,
+///      patina::serial::uart::UartNull{},
+/// );
+///
+/// static LOGGER: MultiLogger = MultiLogger::new(&[&ADV_LOGGER]);
+///
+/// log::set_logger(&LOGGER).map(|()| log::set_max_level(log::LevelFilter::Trace)).unwrap();
+/// ```
+pub struct MultiLogger<'a> {
+    loggers: &'a [&'a dyn log::Log],
+}
+
+impl<'a> MultiLogger<'a> {
+    /// Creates a new MultiLogger.
+    ///
+    /// ## Arguments
+    ///
+    /// * `loggers` - The loggers to forward each record to, in the order they receive it.
+    ///
+    pub const fn new(loggers: &'a [&'a dyn log::Log]) -> Self {
+        Self { loggers }
+    }
+}
+
+impl log::Log for MultiLogger<'_> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.loggers.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &log::Record) {
+        for logger in self.loggers.iter().filter(|logger| logger.enabled(record.metadata())) {
+            logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.loggers.iter().for_each(|logger| logger.flush());
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use log::{Level, LevelFilter, Log};
+
+    use super::*;
+
+    /// Counts the records and flushes it receives at or below its level.
+    struct CountingLogger {
+        level: LevelFilter,
+        records: AtomicUsize,
+        flushes: AtomicUsize,
+    }
+
+    impl CountingLogger {
+        const fn new(level: LevelFilter) -> Self {
+            Self { level, records: AtomicUsize::new(0), flushes: AtomicUsize::new(0) }
+        }
+    }
+
+    impl Log for CountingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= self.level
+        }
+
+        fn log(&self, record: &log::Record) {
+            assert!(self.enabled(record.metadata()));
+            self.records.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn flush(&self) {
+            self.flushes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn log_at(logger: &impl Log, level: Level) {
+        logger.log(&log::Record::builder().level(level).target("test").args(format_args!("record")).build());
+    }
+
+    #[test]
+    fn multi_logger_should_forward_records_to_all_loggers() {
+        let first = CountingLogger::new(LevelFilter::Trace);
+        let second = CountingLogger::new(LevelFilter::Trace);
+        let loggers: [&dyn Log; 2] = [&first, &second];
+        let logger = MultiLogger::new(&loggers);
+
+        log_at(&logger, Level::Info);
+        logger.flush();
+
+        assert_eq!(first.records.load(Ordering::Relaxed), 1);
+        assert_eq!(second.records.load(Ordering::Relaxed), 1);
+        assert_eq!(first.flushes.load(Ordering::Relaxed), 1);
+        assert_eq!(second.flushes.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn multi_logger_should_respect_each_logger_level() {
+        let verbose = CountingLogger::new(LevelFilter::Trace);
+        let quiet = CountingLogger::new(LevelFilter::Warn);
+        let loggers: [&dyn Log; 2] = [&verbose, &quiet];
+        let logger = MultiLogger::new(&loggers);
+
+        log_at(&logger, Level::Error);
+        log_at(&logger, Level::Debug);
+
+        assert_eq!(verbose.records.load(Ordering::Relaxed), 2);
+        assert_eq!(quiet.records.load(Ordering::Relaxed), 1);
+
+        let metadata = |level| log::Metadata::builder().level(level).target("test").build();
+        assert!(logger.enabled(&metadata(Level::Trace)));
+        assert!(!MultiLogger::new(&[&quiet]).enabled(&metadata(Level::Info)));
+        assert!(!MultiLogger::new(&[]).enabled(&metadata(Level::Error)));
+    }
+}
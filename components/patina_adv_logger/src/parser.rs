@@ -7,8 +7,11 @@
 //! SPDX-License-Identifier: Apache-2.0
 //!
 
-use crate::memory_log::{AdvancedLog, LogEntry};
-use alloc::format;
+use crate::{
+    logger::Phase,
+    memory_log::{self, AdvLoggerInfo, AdvancedLog, LogEntry},
+};
+use alloc::{format, vec::Vec};
 use core::str;
 use patina::error::EfiError;
 
@@ -16,6 +19,7 @@ use patina::error::EfiError;
 pub struct Parser<'a> {
     log: AdvancedLog<'a>,
     entry_meta: bool,
+    phase_filter: Option<Phase>,
 }
 
 impl<'a> Parser<'a> {
@@ -29,7 +33,7 @@ impl<'a> Parser<'a> {
             _ => "Failed to open log data.",
         })?;
 
-        Ok(Parser { log, entry_meta: true })
+        Ok(Parser { log, entry_meta: true, phase_filter: None })
     }
 
     /// Sets whether to print entry metadata (level, phase, timestamp) in the log output.
@@ -37,6 +41,11 @@ impl<'a> Parser<'a> {
         self.entry_meta = with_meta;
     }
 
+    /// Sets the boot phase whose entries are written to the log output, or `None` to write every entry.
+    pub const fn configure_phase_filter(&mut self, phase: Option<Phase>) {
+        self.phase_filter = phase;
+    }
+
     /// Writes the log header information to the provided output stream.
     pub fn write_header<W: std::io::Write>(&self, out: &mut W) -> Result<(), &'static str> {
         let header = &format!("{:#x?}\n", self.log.header);
@@ -49,7 +58,8 @@ impl<'a> Parser<'a> {
         let frequency = self.log.get_frequency();
 
         let mut carry_entry: Option<LogEntry> = None;
-        for entry in self.log.iter() {
+        let entries = self.log.iter().filter(|entry| self.phase_filter.is_none_or(|phase| entry.phase == phase as u16));
+        for entry in entries {
             if let Some(carry) = carry_entry {
                 // If the carry entry is not the same boot phase, drop it. This
                 // means messages from different environments are interleaved.
@@ -78,6 +88,135 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// A log entry read by a [`StreamingParser`], owning its message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedEntry {
+    /// The boot phase that produced the entry.
+    pub phase: u16,
+    /// The debug level of the entry.
+    pub level: u32,
+    /// The timestamp of the entry, in timer ticks.
+    pub timestamp: u64,
+    /// The message of the entry.
+    pub message: Vec<u8>,
+}
+
+impl StreamedEntry {
+    /// Returns the boot phase that produced the entry, if it is a known phase.
+    pub fn boot_phase(&self) -> Option<Phase> {
+        Phase::from_raw(self.phase)
+    }
+}
+
+/// Incremental parser for an Advanced Logger buffer received in chunks, such as
+/// when tailing a live log over a debug transport.
+///
+/// The chunks are the bytes of the buffer in order, starting with the log header.
+/// Bytes are kept only until the entry they belong to is complete, so an entry
+/// spanning several chunks is read once it is entirely fed. The entries are read
+/// in buffer order, so a log that has wrapped under
+/// [`OverflowPolicy::DropOldest`](crate::logger::OverflowPolicy::DropOldest) is
+/// better read whole with [`Parser`].
+#[derive(Default)]
+pub struct StreamingParser {
+    /// Bytes fed but not yet consumed.
+    pending: Vec<u8>,
+    /// Offset in `pending` of the first byte not yet consumed.
+    consumed: usize,
+    /// Bytes still to be skipped before the next entry, such as the rest of the
+    /// header or the alignment padding after the last entry.
+    skip: usize,
+    /// The timer frequency, once the header is read.
+    frequency: Option<u64>,
+    error: Option<&'static str>,
+}
+
+impl StreamingParser {
+    /// Creates a parser expecting the log header as the first bytes fed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next bytes of the buffer.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.drain(..self.consumed);
+        self.consumed = 0;
+
+        let skipped = self.skip.min(bytes.len());
+        self.skip -= skipped;
+        self.pending.extend_from_slice(&bytes[skipped..]);
+    }
+
+    /// Returns the entries completed by the bytes fed so far, each only once.
+    pub fn drain(&mut self) -> impl Iterator<Item = StreamedEntry> + '_ {
+        core::iter::from_fn(|| self.next_entry())
+    }
+
+    /// Returns the timer frequency of the log, once its header is read.
+    pub fn frequency(&self) -> Option<u64> {
+        self.frequency
+    }
+
+    /// Returns why parsing stopped, if the bytes fed are not a valid log.
+    pub fn error(&self) -> Option<&'static str> {
+        self.error
+    }
+
+    fn next_entry(&mut self) -> Option<StreamedEntry> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        if self.frequency.is_none() && !self.read_header() {
+            return None;
+        }
+
+        let bytes = &self.pending[self.consumed..];
+        match memory_log::read_entry(bytes) {
+            Ok((entry, aligned_len)) => {
+                let entry = StreamedEntry {
+                    phase: entry.phase,
+                    level: entry.level,
+                    timestamp: entry.timestamp,
+                    message: entry.get_message().to_vec(),
+                };
+                // The padding after the entry may not have been fed yet.
+                let available = aligned_len.min(bytes.len());
+                self.consumed += available;
+                self.skip = aligned_len - available;
+                Some(entry)
+            }
+            Err(EfiError::BufferTooSmall) => None,
+            Err(_) => {
+                self.error = Some("Invalid log entry.");
+                None
+            }
+        }
+    }
+
+    /// Reads the log header once it is fed, returning whether it was read.
+    fn read_header(&mut self) -> bool {
+        match AdvLoggerInfo::read_stream_info(&self.pending) {
+            Ok((log_buffer_offset, frequency)) => {
+                let available = (log_buffer_offset as usize).min(self.pending.len());
+                self.consumed = available;
+                self.skip = log_buffer_offset as usize - available;
+                self.frequency = Some(frequency);
+                true
+            }
+            Err(EfiError::BufferTooSmall) => false,
+            Err(EfiError::Unsupported) => {
+                self.error = Some("Log data format not supported.");
+                false
+            }
+            Err(_) => {
+                self.error = Some("Invalid log data provided.");
+                false
+            }
+        }
+    }
+}
+
 fn get_time_str(timestamp: u64, frequency: u64) -> String {
     // If there is no frequency, return the raw timestamp.
     if frequency == 0 {
@@ -98,21 +237,7 @@ fn get_time_str(timestamp: u64, frequency: u64) -> String {
 }
 
 fn phase_name(phase: u16) -> &'static str {
-    match phase {
-        0 => "UNSPEC",
-        1 => "SEC",
-        2 => "PEI",
-        3 => "PEI64",
-        4 => "DXE",
-        5 => "RUNTIME",
-        6 => "MM_CORE",
-        7 => "MM",
-        8 => "SMM_CORE",
-        9 => "SMM",
-        10 => "TFA",
-        11 => "CNT",
-        _ => "UNKNOWN",
-    }
+    Phase::from_raw(phase).map_or("UNKNOWN", Phase::name)
 }
 
 fn level_name(level: u32) -> &'static str {
@@ -128,3 +253,139 @@ fn level_name(level: u32) -> &'static str {
         "UNKN"
     }
 }
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use crate::logger::{AdvancedLogger, TimeSource};
+    use log::LevelFilter;
+    use patina::{log::Format, serial::uart::UartNull};
+    use r_efi::efi;
+
+    /// Reports 1 hour, 2 minutes, 3.456 seconds at a 1 MHz frequency.
+    struct FixedTimeSource;
+
+    impl TimeSource for FixedTimeSource {
+        fn timestamp(&self) -> u64 {
+            3_723_456_000
+        }
+
+        fn frequency(&self) -> u64 {
+            1_000_000
+        }
+    }
+
+    fn entry(entry: LogEntry) -> StreamedEntry {
+        StreamedEntry {
+            phase: entry.phase,
+            level: entry.level,
+            timestamp: entry.timestamp,
+            message: entry.get_message().to_vec(),
+        }
+    }
+
+    /// Returns the valid portion of a memory log holding entries of varying lengths.
+    fn filled_log() -> &'static [u8] {
+        let buffer = Box::leak(Box::new([0_u64; 0x200]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        let log = unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+        log.set_frequency(1_000_000);
+        for (i, message) in [&b"a"[..], b"split across chunks\n", b"", b"0123456789abcdef"].into_iter().enumerate() {
+            let entry = LogEntry { phase: 4, level: memory_log::DEBUG_LEVEL_INFO, timestamp: i as u64, data: message };
+            log.add_log_entry(entry).unwrap();
+        }
+
+        // SAFETY: The buffer is leaked and no longer written to.
+        let data = unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, size_of_val(buffer)) };
+        let log = AdvancedLog::open_log(data).unwrap();
+        let end = log.iter().last().unwrap().get_message().as_ptr_range().end;
+        &data[..end as usize - data.as_ptr() as usize]
+    }
+
+    #[test]
+    fn streaming_parser_should_match_the_batch_parser_one_byte_at_a_time() {
+        let data = filled_log();
+        let expected: Vec<_> = AdvancedLog::open_log(data).unwrap().iter().map(entry).collect();
+        assert_eq!(expected.len(), 4);
+
+        let mut parser = StreamingParser::new();
+        let mut streamed = Vec::new();
+        for byte in data {
+            parser.feed(core::slice::from_ref(byte));
+            streamed.extend(parser.drain());
+        }
+        assert_eq!(streamed, expected);
+        assert_eq!(parser.frequency(), Some(1_000_000));
+        assert_eq!(parser.error(), None);
+
+        // Feeding everything at once gives the same entries.
+        let mut parser = StreamingParser::new();
+        parser.feed(data);
+        assert_eq!(parser.drain().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn streaming_parser_should_stop_at_invalid_data() {
+        let mut parser = StreamingParser::new();
+        parser.feed(&[0xff; 0x100]);
+        assert_eq!(parser.drain().count(), 0);
+        assert_eq!(parser.error(), Some("Invalid log data provided."));
+
+        let data = filled_log();
+        let mut parser = StreamingParser::new();
+        let mut corrupt = data.to_vec();
+        let header_len = size_of::<AdvLoggerInfo>();
+        corrupt[header_len] ^= 0xff;
+        parser.feed(&corrupt);
+        assert_eq!(parser.drain().count(), 0);
+        assert_eq!(parser.error(), Some("Invalid log entry."));
+    }
+
+    #[test]
+    fn write_log_should_only_write_entries_of_the_filtered_phase() {
+        let buffer = Box::leak(Box::new([0_u64; 0x200]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        let log = unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+        for (phase, message) in [(Phase::Dxe, &b"before\n"[..]), (Phase::Runtime, b"after\n")] {
+            let entry =
+                LogEntry { phase: phase as u16, level: memory_log::DEBUG_LEVEL_INFO, timestamp: 0, data: message };
+            log.add_log_entry(entry).unwrap();
+        }
+
+        // SAFETY: The buffer is leaked and no longer written to.
+        let data = unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, size_of_val(buffer)) };
+        let mut parser = Parser::open(data).unwrap();
+        parser.configure_print_entry_metadata(false);
+        let write = |parser: &Parser| {
+            let mut output = Vec::new();
+            parser.write_log(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(write(&parser), "before\nafter\n");
+
+        parser.configure_phase_filter(Some(Phase::Runtime));
+        assert_eq!(write(&parser), "after\n");
+    }
+
+    #[test]
+    fn write_log_should_render_the_time_source_timestamp() {
+        let buffer = Box::leak(Box::new([0_u64; 0x200]));
+        let address = buffer.as_mut_ptr() as efi::PhysicalAddress;
+        // SAFETY: The buffer was just allocated and is leaked, so it is valid for the life of the test.
+        unsafe { AdvancedLog::initialize_memory_log(address, size_of_val(buffer) as u32) }.unwrap();
+
+        let logger = AdvancedLogger::new(Format::Standard, &[], LevelFilter::Trace, UartNull {})
+            .with_time_source(&FixedTimeSource);
+        logger.set_log_info_address(address);
+        logger.log_write(crate::memory_log::DEBUG_LEVEL_INFO, b"timestamped\n");
+
+        // SAFETY: The buffer is leaked and no longer written to.
+        let data = unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, size_of_val(buffer)) };
+        let mut output = Vec::new();
+        Parser::open(data).unwrap().write_log(&mut output).unwrap();
+        assert_eq!(str::from_utf8(&output).unwrap(), "INFO |DXE     |01:02:03.456| timestamped\n");
+    }
+}
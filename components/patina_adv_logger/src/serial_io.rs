@@ -0,0 +1,351 @@
+//! UEFI Serial I/O Protocol Support
+//!
+//! This module provides a component to bridge the advanced logger output to the
+//! UEFI Serial I/O protocol, so logs appear on the firmware's standard console.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use alloc::boxed::Box;
+use core::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+use patina::{
+    boot_services::{BootServices, StandardBootServices, event::EventType, tpl::Tpl},
+    component::IntoComponent,
+    error::{EfiError, Result},
+    serial::SerialIO,
+    uefi_protocol::serial_io,
+};
+use r_efi::efi;
+
+use crate::logger::{AdvancedLogger, LogSink};
+
+/// The component that will bridge the advanced logger to the Serial I/O protocol.
+///
+/// By default, entries written to the hardware port are also written to the Serial I/O
+/// protocol. The protocol is located again each time a Serial I/O protocol is installed,
+/// so it is bridged once installed if it is not present when the component is dispatched.
+/// The bridge is removed at ExitBootServices.
+#[derive(IntoComponent)]
+pub struct AdvancedLoggerSerialIoComponent<S>
+where
+    S: SerialIO + Send + 'static,
+{
+    adv_logger: &'static AdvancedLogger<'static, S>,
+    replace_hardware_port: bool,
+}
+
+impl<S> AdvancedLoggerSerialIoComponent<S>
+where
+    S: SerialIO + Send + 'static,
+{
+    /// Creates a new AdvancedLoggerSerialIoComponent.
+    pub const fn new(adv_logger: &'static AdvancedLogger<S>) -> Self {
+        Self { adv_logger, replace_hardware_port: false }
+    }
+
+    /// Writes to the Serial I/O protocol instead of the logger's hardware port, rather than
+    /// in addition to it. This is suggested when both are backed by the same device.
+    ///
+    /// Entries that cannot be written to the Serial I/O protocol, such as those logged above
+    /// TPL_CALLBACK, are still written to the hardware port.
+    pub const fn with_hardware_port_replaced(mut self) -> Self {
+        self.replace_hardware_port = true;
+        self
+    }
+
+    /// Entry point to the AdvancedLoggerSerialIoComponent.
+    ///
+    /// Registers a Serial I/O protocol sink for the advanced logger, and locates the protocol
+    /// whenever one is installed.
+    ///
+    fn entry_point(self, bs: StandardBootServices) -> Result<()> {
+        self.bridge_serial_io(bs)
+    }
+
+    fn bridge_serial_io<B: BootServices + Sync + 'static>(self, bs: B) -> Result<()> {
+        let sink: &'static SerialIoSink<B> = Box::leak(Box::new(SerialIoSink::new(bs)));
+        if !self.adv_logger.set_serial_io_sink(sink, self.replace_hardware_port) {
+            log::warn!("Advanced logger is already bridged to a Serial I/O protocol.");
+            return Ok(());
+        }
+
+        let event = sink
+            .boot_services
+            .create_event(EventType::NOTIFY_SIGNAL, Tpl::CALLBACK, Some(SerialIoSink::serial_io_installed), sink)
+            .map_err(|status| {
+                log::error!("Failed to create Serial I/O protocol notify event! Status = {status:#x?}");
+                EfiError::from(status)
+            })?;
+
+        sink.boot_services.register_protocol_notify(&serial_io::PROTOCOL_GUID, event).map_err(|status| {
+            log::error!("Failed to register for Serial I/O protocol installation! Status = {status:#x?}");
+            EfiError::from(status)
+        })?;
+
+        sink.boot_services
+            .create_event(
+                EventType::SIGNAL_EXIT_BOOT_SERVICES,
+                Tpl::NOTIFY,
+                Some(SerialIoSink::exit_boot_services_notify),
+                sink,
+            )
+            .map_err(|status| {
+                log::error!("Failed to create Serial I/O bridge exit boot services event! Status = {status:#x?}");
+                EfiError::from(status)
+            })?;
+
+        if sink.locate() {
+            log::info!("Advanced logger bridged to Serial I/O protocol.");
+        } else {
+            log::info!("Serial I/O protocol not found, advanced logger will be bridged once it is installed.");
+        }
+        Ok(())
+    }
+}
+
+/// The Serial I/O protocol the advanced logger writes to, located again each time a Serial I/O protocol is installed.
+struct SerialIoSink<B: BootServices> {
+    boot_services: B,
+    protocol: AtomicPtr<serial_io::Protocol>,
+    busy: AtomicBool,
+}
+
+impl<B: BootServices + Sync + 'static> SerialIoSink<B> {
+    fn new(boot_services: B) -> Self {
+        Self { boot_services, protocol: AtomicPtr::new(ptr::null_mut()), busy: AtomicBool::new(false) }
+    }
+
+    /// Locates the Serial I/O protocol, returning whether it was found.
+    fn locate(&self) -> bool {
+        // SAFETY: The protocol is only accessed through the sink, which does not create other references.
+        match unsafe { self.boot_services.locate_protocol::<serial_io::Protocol>(None) } {
+            Ok(protocol) => {
+                self.protocol.store(protocol, Ordering::Release);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    extern "efiapi" fn serial_io_installed(_event: efi::Event, sink: &'static Self) {
+        sink.locate();
+    }
+
+    /// Removes the Serial I/O protocol, as it is no longer usable after ExitBootServices.
+    extern "efiapi" fn exit_boot_services_notify(_event: efi::Event, sink: &'static Self) {
+        sink.protocol.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl<B: BootServices + Sync> LogSink for SerialIoSink<B> {
+    fn write(&self, data: &[u8]) -> bool {
+        let protocol = self.protocol.load(Ordering::Acquire);
+        if protocol.is_null() {
+            return false;
+        }
+
+        // Serial I/O may only be called at or below TPL_CALLBACK, so entries logged at a higher TPL, such as from an
+        // event notify function or an interrupt handler, are not written to it.
+        let tpl = self.boot_services.raise_tpl(Tpl(efi::TPL_HIGH_LEVEL));
+        self.boot_services.restore_tpl(tpl);
+        if tpl > Tpl::CALLBACK {
+            return false;
+        }
+
+        // The Serial I/O implementation may itself log, drop those messages rather than recursing.
+        if self.busy.swap(true, Ordering::Acquire) {
+            return false;
+        }
+
+        let mut size = data.len();
+        // SAFETY: The protocol was located and is cleared at ExitBootServices, before it is no longer valid.
+        let status = unsafe { ((*protocol).write)(protocol, &mut size, data.as_ptr() as *mut c_void) };
+        self.busy.store(false, Ordering::Release);
+        !status.is_error()
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    extern crate std;
+
+    use core::{
+        cell::{Cell, RefCell},
+        ffi::c_void,
+        ptr,
+    };
+    use std::{boxed::Box, vec::Vec};
+
+    use patina::{boot_services::MockBootServices, serial::uart::UartNull};
+
+    use super::*;
+
+    std::thread_local! {
+        static WRITTEN: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+        static CURRENT_TPL: Cell<Tpl> = const { Cell::new(Tpl::APPLICATION) };
+    }
+
+    extern "efiapi" fn mock_reset(_this: *mut serial_io::Protocol) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_set_attributes(
+        _this: *mut serial_io::Protocol,
+        _baud_rate: u64,
+        _receive_fifo_depth: u32,
+        _timeout: u32,
+        _parity: u32,
+        _data_bits: u8,
+        _stop_bits: u32,
+    ) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_set_control(_this: *mut serial_io::Protocol, _control: u32) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_get_control(_this: *mut serial_io::Protocol, _control: *mut u32) -> efi::Status {
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_write(
+        _this: *mut serial_io::Protocol,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> efi::Status {
+        // SAFETY: The logger provides a valid buffer of the given size.
+        let data = unsafe { core::slice::from_raw_parts(buffer as *const u8, *buffer_size) };
+        WRITTEN.with_borrow_mut(|written| written.extend_from_slice(data));
+        efi::Status::SUCCESS
+    }
+
+    extern "efiapi" fn mock_read(
+        _this: *mut serial_io::Protocol,
+        buffer_size: *mut usize,
+        _buffer: *mut c_void,
+    ) -> efi::Status {
+        // SAFETY: The caller provides a valid size pointer.
+        unsafe { *buffer_size = 0 };
+        efi::Status::TIMEOUT
+    }
+
+    fn mock_serial_io() -> &'static mut serial_io::Protocol {
+        Box::leak(Box::new(serial_io::Protocol {
+            revision: serial_io::REVISION,
+            reset: mock_reset,
+            set_attributes: mock_set_attributes,
+            set_control: mock_set_control,
+            get_control: mock_get_control,
+            write: mock_write,
+            read: mock_read,
+            mode: ptr::null_mut(),
+            device_type_guid: ptr::null(),
+        }))
+    }
+
+    fn written() -> Vec<u8> {
+        WRITTEN.with_borrow(|written| written.clone())
+    }
+
+    /// Returns boot services that report the TPL in `CURRENT_TPL` as the current TPL.
+    fn mock_boot_services() -> MockBootServices {
+        let mut boot_services = MockBootServices::new();
+        boot_services.expect_raise_tpl().returning(|_| CURRENT_TPL.get());
+        boot_services.expect_restore_tpl().returning(|_| ());
+        boot_services
+    }
+
+    #[test]
+    fn serial_io_bridge_should_write_to_located_protocol() {
+        static LOGGER: AdvancedLogger<UartNull> =
+            AdvancedLogger::new(patina::log::Format::Standard, &[], log::LevelFilter::Trace, UartNull {});
+
+        let protocol = mock_serial_io() as *mut serial_io::Protocol;
+        let mut boot_services = mock_boot_services();
+        boot_services
+            .expect_locate_protocol::<serial_io::Protocol>()
+            .once()
+            // SAFETY: The protocol was leaked and is valid for the test.
+            .returning_st(move |_| Ok(unsafe { &mut *protocol }));
+        boot_services
+            .expect_create_event::<&'static SerialIoSink<MockBootServices>>()
+            .once()
+            .withf(|event_type, tpl, _, _| *event_type == EventType::NOTIFY_SIGNAL && *tpl == Tpl::CALLBACK)
+            .returning(|_, _, _, _| Ok(1_usize as efi::Event));
+        boot_services
+            .expect_register_protocol_notify()
+            .once()
+            .withf(|guid, event| *guid == serial_io::PROTOCOL_GUID && *event == 1_usize as efi::Event)
+            .returning(|_, _| Ok(ptr::NonNull::dangling()));
+        boot_services
+            .expect_create_event::<&'static SerialIoSink<MockBootServices>>()
+            .once()
+            .withf(|event_type, tpl, _, _| *event_type == EventType::SIGNAL_EXIT_BOOT_SERVICES && *tpl == Tpl::NOTIFY)
+            .returning(|_, _, _, _| Ok(2_usize as efi::Event));
+
+        let component = AdvancedLoggerSerialIoComponent::new(&LOGGER);
+        assert_eq!(component.bridge_serial_io(boot_services), Ok(()));
+
+        LOGGER.log_write(0, b"Hello Serial I/O");
+        assert_eq!(written(), b"Hello Serial I/O");
+
+        // A second bridge is not registered.
+        let component = AdvancedLoggerSerialIoComponent::new(&LOGGER);
+        assert_eq!(component.bridge_serial_io(MockBootServices::new()), Ok(()));
+    }
+
+    #[test]
+    fn serial_io_sink_should_write_at_or_below_tpl_callback() {
+        let protocol = mock_serial_io() as *mut serial_io::Protocol;
+        let mut boot_services = mock_boot_services();
+        boot_services
+            .expect_locate_protocol::<serial_io::Protocol>()
+            .once()
+            // SAFETY: The protocol was leaked and is valid for the test.
+            .returning_st(move |_| Ok(unsafe { &mut *protocol }));
+        let sink: &'static SerialIoSink<MockBootServices> = Box::leak(Box::new(SerialIoSink::new(boot_services)));
+        assert!(sink.locate());
+
+        CURRENT_TPL.set(Tpl::CALLBACK);
+        assert!(sink.write(b"Callback."));
+        CURRENT_TPL.set(Tpl::NOTIFY);
+        assert!(!sink.write(b"Notify."));
+        CURRENT_TPL.set(Tpl::APPLICATION);
+        assert!(sink.write(b"Application."));
+        assert_eq!(written(), b"Callback.Application.");
+
+        // Writing should stop at exit boot services.
+        SerialIoSink::exit_boot_services_notify(2_usize as efi::Event, sink);
+        assert!(!sink.write(b"After EBS"));
+        assert_eq!(written(), b"Callback.Application.");
+    }
+
+    #[test]
+    fn serial_io_sink_should_write_once_the_protocol_is_installed() {
+        let protocol = mock_serial_io() as *mut serial_io::Protocol;
+        let mut boot_services = mock_boot_services();
+        boot_services.expect_locate_protocol::<serial_io::Protocol>().once().returning(|_| Err(efi::Status::NOT_FOUND));
+        boot_services
+            .expect_locate_protocol::<serial_io::Protocol>()
+            .once()
+            // SAFETY: The protocol was leaked and is valid for the test.
+            .returning_st(move |_| Ok(unsafe { &mut *protocol }));
+        let sink: &'static SerialIoSink<MockBootServices> = Box::leak(Box::new(SerialIoSink::new(boot_services)));
+
+        assert!(!sink.locate());
+        assert!(!sink.write(b"Before install."));
+
+        SerialIoSink::serial_io_installed(1_usize as efi::Event, sink);
+        assert!(sink.write(b"After install."));
+        assert_eq!(written(), b"After install.");
+    }
+}
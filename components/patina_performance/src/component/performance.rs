@@ -19,11 +19,16 @@ use patina::{
     boot_services::{BootServices, StandardBootServices, event::EventType, tpl::Tpl},
     component::{IntoComponent, hob::Hob, params::Config},
     error::EfiError,
-    guids::{EVENT_GROUP_END_OF_DXE, PERFORMANCE_PROTOCOL},
+    guids::EVENT_GROUP_END_OF_DXE,
     performance::{
         _smm::MmCommRegion,
-        globals::{get_static_state, set_load_image_count, set_perf_measurement_mask, set_static_state},
-        measurement::{PerformanceProperty, create_performance_measurement, event_callback},
+        globals::{
+            get_perf_frequency, get_static_state, set_load_image_count, set_perf_frequency, set_perf_measurement_mask,
+            set_static_state,
+        },
+        measurement::{
+            PERFORMANCE_PROPERTY_TABLE, PerformanceProperty, create_performance_measurement, event_callback,
+        },
         record::hob::{HobPerformanceData, HobPerformanceDataExtractor},
         table::FirmwareBasicBootPerfTable,
     },
@@ -98,6 +103,9 @@ impl Performance {
         P: HobPerformanceDataExtractor,
         F: FirmwareBasicBootPerfTable,
     {
+        // Measurements are recorded without timestamps if the timer frequency is unusable.
+        set_perf_frequency(Arch::perf_frequency());
+
         // Register EndOfDxe event to allocate the boot performance table and report the table address through status code.
         boot_services.as_ref().create_event_ex(
             EventType::NOTIFY_SIGNAL,
@@ -152,17 +160,14 @@ impl Performance {
         }
 
         // Install configuration table for performance property.
-        // SAFETY: `install_configuration_table` requires that the data match the GUID; PERFORMANCE_PROTOCOL matches `PerformanceProperty`.
-        unsafe {
-            boot_services.as_ref().install_configuration_table(
-                &PERFORMANCE_PROTOCOL,
-                Box::new(PerformanceProperty::new(
-                    Arch::perf_frequency(),
-                    Arch::cpu_count_start(),
-                    Arch::cpu_count_end(),
-                )),
-            )?
-        };
+        boot_services.as_ref().install_configuration_table_entry(
+            &PERFORMANCE_PROPERTY_TABLE,
+            Box::new(PerformanceProperty::new(
+                get_perf_frequency().unwrap_or(0),
+                Arch::cpu_count_start(),
+                Arch::cpu_count_end(),
+            )),
+        )?;
 
         Ok(())
     }
@@ -179,6 +184,7 @@ mod tests {
 
     use patina::{
         boot_services::{MockBootServices, c_ptr::CPtr},
+        guids::PERFORMANCE_PROTOCOL,
         runtime_services::MockRuntimeServices,
         uefi_protocol::{ProtocolInterface, performance_measurement::EDKII_PERFORMANCE_MEASUREMENT_PROTOCOL_GUID},
     };
@@ -257,10 +263,10 @@ mod tests {
 
         // Test that the address of the fbpt is installed to the configuration table.
         boot_services
-            .expect_install_configuration_table::<Box<PerformanceProperty>>()
+            .expect_install_configuration_table_entry::<PerformanceProperty>()
             .once()
-            .withf(|guid, _data| {
-                assert_eq!(&PERFORMANCE_PROTOCOL, guid);
+            .withf(|entry, _data| {
+                assert_eq!(&PERFORMANCE_PROTOCOL, entry.guid());
                 true
             })
             .return_const(Ok(()));
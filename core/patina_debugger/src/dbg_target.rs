@@ -21,6 +21,7 @@ use gdbstub::target::{
         breakpoints::{self, BreakpointsOps},
     },
 };
+use patina::serial::SerialIO;
 use spin::Mutex;
 
 use crate::{
@@ -47,12 +48,18 @@ pub struct PatinaTarget {
     disable_checks: bool,
     /// Tracks external system state.
     system_state: &'static Mutex<SystemState>,
+    /// The debugger transport, polled for interrupts while monitor commands run.
+    transport: &'static dyn SerialIO,
 }
 
 impl PatinaTarget {
     /// Create a new Patina target.
-    pub fn new(exception_info: ExceptionInfo, system_state: &'static Mutex<SystemState>) -> Self {
-        PatinaTarget { exception_info, resume: false, reboot: false, disable_checks: false, system_state }
+    pub fn new(
+        exception_info: ExceptionInfo,
+        system_state: &'static Mutex<SystemState>,
+        transport: &'static dyn SerialIO,
+    ) -> Self {
+        PatinaTarget { exception_info, resume: false, reboot: false, disable_checks: false, system_state, transport }
     }
 
     /// Checks if the target has been resumed.
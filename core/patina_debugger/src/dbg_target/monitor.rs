@@ -12,8 +12,9 @@
 
 use core::{fmt::Write, str::SplitWhitespace};
 use gdbstub::target::ext::{self, monitor_cmd::ConsoleOutput};
+use patina::serial::SerialIO;
 
-use crate::{arch::DebuggerArch, arch::SystemArch};
+use crate::{MonitorWriter, arch::DebuggerArch, arch::SystemArch};
 
 use super::PatinaTarget;
 
@@ -41,7 +42,7 @@ impl ext::monitor_cmd::MonitorCmd for PatinaTarget {
 
         // Wrap the output in a buffer to reduce the number of packets sent. Without
         // this formated string may send a packet for each character.
-        let mut buf = MonitorBuffer::<'_, 128>::new(out);
+        let mut buf = MonitorBuffer::<_, 128>::new(TransportOutput { console: out, transport: self.transport });
 
         // Check for an offset modifier, and configure the monitor buffer accordingly.
         let cmd = match tokens.next() {
@@ -59,9 +60,7 @@ impl ext::monitor_cmd::MonitorCmd for PatinaTarget {
                 let _ = buf.write_str(MONITOR_HELP);
                 let _ = buf.write_str("External commands:\n");
                 if let Some(state) = self.system_state.try_lock() {
-                    for cmd in state.monitor_commands.iter() {
-                        let _ = writeln!(buf, "    {} - {}", cmd.command, cmd.description);
-                    }
+                    state.write_monitor_command_help(&mut buf);
                 };
             }
             Some("mod") => {
@@ -163,19 +162,61 @@ impl PatinaTarget {
     }
 }
 
-/// A wrapper that batches writes. This is to reduce the number of packets
-/// for a monitor transaction.
-struct MonitorBuffer<'a, const N: usize> {
+/// The GDB interrupt character, sent by the client on Ctrl-C.
+const INTERRUPT: u8 = 0x03;
+
+/// The destination a [MonitorBuffer] flushes its data to.
+trait MonitorOutput {
+    /// Writes raw bytes to the output.
+    fn write_raw(&mut self, data: &[u8]);
+
+    /// Checks if an interrupt has been received since the last check.
+    fn interrupted(&mut self) -> bool {
+        false
+    }
+}
+
+/// Console output of a monitor command, with the transport it is sent on.
+struct TransportOutput<'a> {
+    console: ConsoleOutput<'a>,
+    transport: &'static dyn SerialIO,
+}
+
+impl MonitorOutput for TransportOutput<'_> {
+    fn write_raw(&mut self, data: &[u8]) {
+        self.console.write_raw(data);
+    }
+
+    fn interrupted(&mut self) -> bool {
+        interrupt_received(self.transport)
+    }
+}
+
+/// Drains the pending bytes of the transport, returning whether an interrupt was among them. The client sends
+/// nothing else while waiting for a monitor command to complete, so the other bytes can be discarded.
+fn interrupt_received(transport: &dyn SerialIO) -> bool {
+    let mut interrupted = false;
+    while let Some(byte) = transport.try_read() {
+        interrupted |= byte == INTERRUPT;
+    }
+    interrupted
+}
+
+/// A wrapper that batches writes into a fixed stack buffer. This is to reduce the number
+/// of packets for a monitor transaction. Output larger than the buffer is flushed in
+/// buffer sized chunks, so no allocations are needed regardless of the output length.
+struct MonitorBuffer<O: MonitorOutput, const N: usize> {
     buffer: [u8; N],
     pos: usize,
     start_offset: usize,
-    out: ConsoleOutput<'a>,
+    cancelled: bool,
+    out: O,
 }
 
-impl<'a, const N: usize> MonitorBuffer<'a, N> {
+impl<O: MonitorOutput, const N: usize> MonitorBuffer<O, N> {
     /// Creates a new BufferedWriter with the specified log level and writer.
-    const fn new(out: ConsoleOutput<'a>) -> Self {
-        MonitorBuffer { buffer: [0; N], pos: 0, start_offset: 0, out }
+    const fn new(out: O) -> Self {
+        MonitorBuffer { buffer: [0; N], pos: 0, start_offset: 0, cancelled: false, out }
     }
 
     /// Sets the start offset for the buffer.
@@ -193,44 +234,170 @@ impl<'a, const N: usize> MonitorBuffer<'a, N> {
     }
 }
 
-impl<const N: usize> Write for MonitorBuffer<'_, N> {
+impl<O: MonitorOutput, const N: usize> Write for MonitorBuffer<O, N> {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         let mut data = s.as_bytes();
-        let mut len = data.len();
 
         // Ignore the start offset number of characters.
         if self.start_offset > 0 {
-            if self.start_offset >= len {
-                self.start_offset -= len;
+            if self.start_offset >= data.len() {
+                self.start_offset -= data.len();
                 return Ok(());
             } else {
                 // Adjust the data to skip the start offset.
                 data = &data[self.start_offset..];
-                len = data.len();
                 self.start_offset = 0; // Reset start offset after using it.
             }
         }
 
-        // buffer the message if it will fit.
-        if len < N {
-            // If it will not fit with the current data, flush the current data.
-            if len > N - self.pos {
+        // Fill the buffer, flushing each time it is full.
+        while !data.is_empty() {
+            let len = data.len().min(N - self.pos);
+            self.buffer[self.pos..self.pos + len].copy_from_slice(&data[..len]);
+            self.pos += len;
+            data = &data[len..];
+            if self.pos == N {
                 self.flush();
             }
-            self.buffer[self.pos..self.pos + len].copy_from_slice(data);
-            self.pos += len;
-        } else {
-            // this message is too big to buffer, flush then write the message.
-            self.flush();
-            self.out.write_raw(data);
         }
 
         Ok(())
     }
 }
 
-impl<const N: usize> Drop for MonitorBuffer<'_, N> {
+impl<O: MonitorOutput, const N: usize> MonitorWriter for MonitorBuffer<O, N> {
+    fn is_cancelled(&mut self) -> bool {
+        if !self.cancelled {
+            self.cancelled = self.out.interrupted();
+        }
+        self.cancelled
+    }
+}
+
+impl<O: MonitorOutput, const N: usize> Drop for MonitorBuffer<O, N> {
     fn drop(&mut self) {
         self.flush();
     }
 }
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use crate::{MonitorCommandFn, system::SystemState};
+
+    /// Records each chunk flushed by a [MonitorBuffer].
+    impl MonitorOutput for &mut Vec<Vec<u8>> {
+        fn write_raw(&mut self, data: &[u8]) {
+            self.push(data.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_monitor_command_output_is_flushed_in_chunks() {
+        let mut system_state = SystemState::new();
+        let callback: MonitorCommandFn = |args, out| {
+            let count: usize = args.next().unwrap().parse().unwrap();
+            for line in 0..count {
+                let _ = writeln!(out, "{line:08x}: {:#018x}", line * 8);
+            }
+        };
+        system_state.add_monitor_command("dump", "Dumps lines", callback);
+
+        let mut chunks = Vec::new();
+        {
+            let mut buf = MonitorBuffer::<_, 64>::new(&mut chunks);
+            assert!(system_state.handle_monitor_command("dump", &mut "10".split_whitespace(), &mut buf));
+        }
+
+        // Each line is 29 bytes, so 290 bytes are flushed as 4 full buffers and the remainder.
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), [64, 64, 64, 64, 34]);
+        let output = String::from_utf8(chunks.concat()).unwrap();
+        assert_eq!(output.lines().count(), 10);
+        assert_eq!(output.lines().last(), Some("00000009: 0x0000000000000048"));
+    }
+
+    /// Records each flushed chunk, reporting an interrupt once `interrupt_after` chunks are flushed.
+    struct InterruptedOutput {
+        chunks: Vec<Vec<u8>>,
+        interrupt_after: usize,
+    }
+
+    impl MonitorOutput for &mut InterruptedOutput {
+        fn write_raw(&mut self, data: &[u8]) {
+            self.chunks.push(data.to_vec());
+        }
+
+        fn interrupted(&mut self) -> bool {
+            self.chunks.len() >= self.interrupt_after
+        }
+    }
+
+    /// A transport with pending bytes sent by the client.
+    struct PendingSerial(spin::Mutex<Vec<u8>>);
+
+    impl SerialIO for PendingSerial {
+        fn init(&self) {}
+
+        fn write(&self, _buffer: &[u8]) {}
+
+        fn read(&self) -> u8 {
+            unimplemented!()
+        }
+
+        fn try_read(&self) -> Option<u8> {
+            self.0.lock().pop()
+        }
+    }
+
+    #[test]
+    fn test_monitor_command_stops_when_cancelled() {
+        let mut system_state = SystemState::new();
+        let callback: MonitorCommandFn = |_, out| {
+            for line in 0..1000 {
+                if out.is_cancelled() {
+                    let _ = writeln!(out, "Cancelled at line {line}.");
+                    return;
+                }
+                let _ = writeln!(out, "{line:08x}: {:#018x}", line * 8);
+            }
+        };
+        system_state.add_monitor_command("dump", "Dumps lines", callback);
+
+        let mut output = InterruptedOutput { chunks: Vec::new(), interrupt_after: 2 };
+        {
+            let mut buf = MonitorBuffer::<_, 64>::new(&mut output);
+            assert!(system_state.handle_monitor_command("dump", &mut "".split_whitespace(), &mut buf));
+            assert!(buf.is_cancelled());
+        }
+
+        // Each line is 29 bytes, so the second buffer is flushed while writing the fifth line.
+        let output = String::from_utf8(output.chunks.concat()).unwrap();
+        assert_eq!(output.lines().count(), 6);
+        assert_eq!(output.lines().last(), Some("Cancelled at line 5."));
+    }
+
+    #[test]
+    fn test_interrupt_received_drains_the_transport() {
+        let transport = PendingSerial(spin::Mutex::new(vec![b'+', INTERRUPT, b'+']));
+        assert!(interrupt_received(&transport));
+        assert!(transport.0.lock().is_empty());
+
+        transport.0.lock().push(b'+');
+        assert!(!interrupt_received(&transport));
+        assert!(!interrupt_received(&transport));
+    }
+
+    #[test]
+    fn test_monitor_buffer_skips_start_offset_across_writes() {
+        let mut chunks = Vec::new();
+        {
+            let mut buf = MonitorBuffer::<_, 4>::new(&mut chunks);
+            buf.set_start_offset(3);
+            let _ = buf.write_str("ab");
+            let _ = buf.write_str("cdefghij");
+        }
+
+        assert_eq!(chunks, [b"defg".to_vec(), b"hij".to_vec()]);
+    }
+}
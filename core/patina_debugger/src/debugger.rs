@@ -12,12 +12,12 @@
 //!
 
 #[cfg(feature = "alloc")]
-use alloc::boxed::Box;
+use alloc::{boxed::Box, string::String};
 use gdbstub::{
     conn::ConnectionExt,
     stub::{GdbStubBuilder, SingleThreadStopReason, state_machine::GdbStubStateMachine},
 };
-use patina::serial::SerialIO;
+use patina::{error::EfiError, serial::SerialIO};
 use patina_internal_cpu::interrupts::{ExceptionType, HandlerType, InterruptHandler, InterruptManager};
 use spin::Mutex;
 
@@ -26,12 +26,17 @@ use crate::{
     arch::{DebuggerArch, SystemArch},
     dbg_target::PatinaTarget,
     system::SystemState,
-    transport::{LoggingSuspender, SerialConnection},
+    transport::{LoggingSuspender, SerialConnection, probe_baud_rate},
 };
 
-/// Length of the static buffer used for GDB communication.
+/// Length of the static buffer used for GDB communication, and the default length of the allocated buffer.
 const GDB_BUFF_LEN: usize = 0x2000;
 
+/// The smallest buffer that holds a `g` response of the architecture's full register state, hex encoded and framed
+/// as `$<registers>#<checksum>`.
+#[cfg(feature = "alloc")]
+const MIN_GDB_BUFF_LEN: usize = 2 * size_of::<<SystemArch as gdbstub::arch::Arch>::Registers>() + 4;
+
 #[cfg(not(feature = "alloc"))]
 static GDB_BUFFER: [u8; GDB_BUFF_LEN] = [0; GDB_BUFF_LEN];
 
@@ -60,6 +65,11 @@ where
     log_policy: DebuggerLoggingPolicy,
     /// Whether initializing the transport should be skipped.
     no_transport_init: bool,
+    /// Candidate baud rates to probe for a debugger connection during initialization.
+    baud_probe: Option<&'static [u32]>,
+    /// Length of the buffer GDB packets are assembled in.
+    #[cfg(feature = "alloc")]
+    packet_buffer_size: usize,
     /// Internal mutable debugger config.
     config: spin::RwLock<DebuggerConfig>,
     /// Internal mutable debugger state.
@@ -89,7 +99,7 @@ where
     T: SerialIO,
 {
     gdb: Option<GdbStubStateMachine<'a, PatinaTarget, SerialConnection<'a, T>>>,
-    gdb_buffer: Option<&'a [u8; GDB_BUFF_LEN]>,
+    gdb_buffer: Option<&'a [u8]>,
 }
 
 impl<T: SerialIO> PatinaDebugger<T> {
@@ -102,6 +112,9 @@ impl<T: SerialIO> PatinaDebugger<T> {
             transport,
             log_policy: DebuggerLoggingPolicy::SuspendLogging,
             no_transport_init: false,
+            baud_probe: None,
+            #[cfg(feature = "alloc")]
+            packet_buffer_size: GDB_BUFF_LEN,
             exception_types: SystemArch::DEFAULT_EXCEPTION_TYPES,
             config: spin::RwLock::new(DebuggerConfig { enabled: false, initial_break: true, initial_break_timeout: 0 }),
             internal: Mutex::new(DebuggerInternal { gdb_buffer: None, gdb: None }),
@@ -139,6 +152,37 @@ impl<T: SerialIO> PatinaDebugger<T> {
         self
     }
 
+    /// Probes the provided baud rates during initialization, settling on the first one
+    /// on which a GDB handshake (`+` or `$`) is received from the debugger client within
+    /// a second. If no candidate responds, the transport is left at the first candidate.
+    ///
+    /// This requires the transport to support runtime baud rate changes through
+    /// [`SerialIO::set_baud_rate`]. If the transport does not support this, the probe
+    /// is skipped and the transport is left in its initialized state.
+    pub const fn with_baud_probe(mut self, baud_rates: &'static [u32]) -> Self {
+        self.baud_probe = Some(baud_rates);
+        self
+    }
+
+    /// Sets the length of the buffer GDB packets are assembled in, which is advertised to the client as the maximum
+    /// packet size (`PacketSize` in the `qSupported` response). Defaults to `0x2000` bytes.
+    ///
+    /// A larger buffer lets the client read or write more memory per `m`/`M` packet, reducing round trips for bulk
+    /// transfers, at the cost of the buffer being allocated for the lifetime of the system. The buffer must be large
+    /// enough to hold the architecture's full register state in a `g` response, and this panics if it is not.
+    #[cfg(feature = "alloc")]
+    pub const fn with_packet_buffer_size(mut self, size: usize) -> Self {
+        assert!(size >= MIN_GDB_BUFF_LEN, "The debugger packet buffer is too small to hold a GDB register packet.");
+        self.packet_buffer_size = size;
+        self
+    }
+
+    /// Allocates the buffer GDB packets are assembled in, sized per [`Self::with_packet_buffer_size`].
+    #[cfg(feature = "alloc")]
+    fn allocate_packet_buffer(&self) -> &'static [u8] {
+        Box::leak(alloc::vec![0u8; self.packet_buffer_size].into_boxed_slice())
+    }
+
     /// Customizes the exception types for which the debugger will be invoked.
     pub const fn with_exception_types(mut self, exception_types: &'static [usize]) -> Self {
         self.exception_types = exception_types;
@@ -157,6 +201,26 @@ impl<T: SerialIO> PatinaDebugger<T> {
         config.enabled = enabled;
     }
 
+    /// Runs a registered monitor command and returns its output, without a connected debugger.
+    ///
+    /// `args` holds the whitespace separated arguments to the command, as they would follow it in a `monitor`
+    /// request. This allows monitor commands to be exercised by automated tests. Only commands added with
+    /// [`add_monitor_command`](crate::add_monitor_command) can be run, as the built-in commands require the debugger
+    /// to be broken in.
+    ///
+    /// Returns `NotFound` if no command named `name` is registered, or `NotReady` if the monitor commands are in use
+    /// by the debugger.
+    #[cfg(feature = "alloc")]
+    pub fn run_command(&self, name: &str, args: &str) -> Result<String, EfiError> {
+        let state = self.system_state.try_lock().ok_or(EfiError::NotReady)?;
+        let mut output = String::new();
+        if state.handle_monitor_command(name, &mut args.split_whitespace(), &mut output) {
+            Ok(output)
+        } else {
+            Err(EfiError::NotFound)
+        }
+    }
+
     /// Enters the debugger from an exception.
     fn enter_debugger(&'static self, exception_info: ExceptionInfo) -> Result<ExceptionInfo, DebugError> {
         let mut debug = match self.internal.try_lock() {
@@ -164,7 +228,7 @@ impl<T: SerialIO> PatinaDebugger<T> {
             None => return Err(DebugError::Reentry),
         };
 
-        let mut target = PatinaTarget::new(exception_info, &self.system_state);
+        let mut target = PatinaTarget::new(exception_info, &self.system_state, &self.transport);
 
         // Either take the existing state machine, or start one if this is the first break.
         let mut gdb = match debug.gdb {
@@ -274,6 +338,23 @@ impl<T: SerialIO> Debugger for PatinaDebugger<T> {
             self.transport.init();
         }
 
+        // Find the baud rate the debugger client is using, if requested.
+        if let Some(baud_rates) = self.baud_probe {
+            match probe_baud_rate(&self.transport, baud_rates) {
+                Ok(Some(baud_rate)) => log::info!("Debugger: Detected client at {baud_rate} baud."),
+                Ok(None) => {
+                    log::warn!("Debugger: No client detected during baud probe.");
+                    if let Some(&baud_rate) = baud_rates.first() {
+                        let _ = self.transport.set_baud_rate(baud_rate);
+                    }
+                }
+                Err(EfiError::Unsupported) => {
+                    log::error!("Debugger: Baud probe skipped, transport does not support runtime baud rate changes.")
+                }
+                Err(err) => log::error!("Debugger: Baud probe stopped, failed to set a candidate baud rate. {err:?}"),
+            }
+        }
+
         // Initialize any architecture specifics.
         SystemArch::initialize();
 
@@ -283,11 +364,11 @@ impl<T: SerialIO> Debugger for PatinaDebugger<T> {
             cfg_if::cfg_if! {
                 if #[cfg(feature = "alloc")] {
                     if internal.gdb_buffer.is_none() {
-                        internal.gdb_buffer = Some(Box::leak(Box::new([0u8; GDB_BUFF_LEN])));
+                        internal.gdb_buffer = Some(self.allocate_packet_buffer());
                     }
                 }
                 else {
-                    internal.gdb_buffer = unsafe { Some(&*(GDB_BUFFER.as_ptr() as *mut [u8; GDB_BUFF_LEN])) };
+                    internal.gdb_buffer = unsafe { Some(&*(GDB_BUFFER.as_ptr() as *mut [u8; GDB_BUFF_LEN]) as &[u8]) };
                 }
             }
         }
@@ -426,3 +507,130 @@ fn debugger_crash(error: DebugError, exception_type: ExceptionType) -> ! {
     #[allow(clippy::empty_loop)]
     loop {}
 }
+
+#[cfg(all(test, feature = "alloc"))]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use crate::{ExceptionType, transport::ReplaySerial};
+    use std::{boxed::Box, format, string::String};
+
+    /// Frames a GDB remote protocol packet body with its checksum.
+    fn packet(body: &str) -> String {
+        let checksum = body.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        format!("${body}#{checksum:02x}")
+    }
+
+    fn leaked_packet(prefix: &str, body: &str) -> &'static [u8] {
+        Box::leak(format!("{prefix}{}", packet(body)).into_boxed_str()).as_bytes()
+    }
+
+    fn replay_debugger(script: &'static [&'static [u8]]) -> &'static PatinaDebugger<ReplaySerial> {
+        let debugger = Box::leak(Box::new(PatinaDebugger::new(ReplaySerial::new(script))));
+        debugger.internal.lock().gdb_buffer = Some(Box::leak(Box::new([0u8; GDB_BUFF_LEN])));
+        debugger
+    }
+
+    fn breakpoint_exception() -> ExceptionInfo {
+        ExceptionInfo {
+            exception_type: ExceptionType::Breakpoint,
+            instruction_pointer: 0,
+            // SAFETY: The architecture context is plain register state, for which all zeroes is valid.
+            context: unsafe { core::mem::zeroed() },
+        }
+    }
+
+    /// Expands the run-length encoding of a GDB remote protocol packet body.
+    fn run_length_decode(body: &str) -> String {
+        let mut decoded = String::new();
+        let mut bytes = body.bytes();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'*' => {
+                    let repeat = bytes.next().expect("missing run length") - 29;
+                    let last = decoded.chars().last().expect("run length without a character");
+                    decoded.extend(core::iter::repeat_n(last, repeat as usize));
+                }
+                b => decoded.push(b as char),
+            }
+        }
+        decoded
+    }
+
+    #[test]
+    fn test_replay_supported_registers_continue() {
+        let script = Box::leak(Box::new([
+            leaked_packet("", "qSupported:multiprocess+;swbreak+;hwbreak+"),
+            leaked_packet("+", "g"),
+            leaked_packet("+", "c"),
+        ]));
+        let debugger = replay_debugger(script);
+
+        assert!(debugger.enter_debugger(breakpoint_exception()).is_ok());
+        assert!(debugger.transport.is_exhausted());
+
+        let output = String::from_utf8(debugger.transport.output()).unwrap();
+        // The Windbg workarounds disable no-ack mode.
+        let no_ack = if cfg!(feature = "windbg_workarounds") { "" } else { "QStartNoAckMode+;" };
+        let supported = packet(&format!(
+            "PacketSize=2000;vContSupported+;multiprocess+;{no_ack}fork-events+;vfork-events+;vforkdone-events+;\
+             swbreak+;hwbreak+;qXfer:features:read+",
+        ));
+        let output = output.strip_prefix("$T05thread:01;#07").expect("missing initial stop packet");
+        let output = output.strip_prefix(&format!("+{supported}")).expect("unexpected qSupported response");
+
+        // The register dump of a zeroed context is all zeroes, sized by the architecture.
+        let (registers, rest) =
+            output.strip_prefix("+$").and_then(|rest| rest.split_once('#')).expect("missing g response");
+        let decoded = run_length_decode(registers);
+        assert!(!decoded.is_empty() && decoded.bytes().all(|b| b == b'0'));
+        let (checksum, rest) = rest.split_at(2);
+        assert_eq!(format!("${registers}#{checksum}"), packet(registers));
+
+        // The continue packet is acknowledged, with no response until the next stop.
+        assert_eq!(rest, "+");
+    }
+
+    #[test]
+    fn test_replay_advertises_configured_packet_size() {
+        let script = Box::leak(Box::new([
+            leaked_packet("", "qSupported:multiprocess+;swbreak+;hwbreak+"),
+            leaked_packet("+", "c"),
+        ]));
+        let debugger =
+            Box::leak(Box::new(PatinaDebugger::new(ReplaySerial::new(script)).with_packet_buffer_size(0x8000)));
+        let buffer = debugger.allocate_packet_buffer();
+        assert_eq!(buffer.len(), 0x8000);
+        debugger.internal.lock().gdb_buffer = Some(buffer);
+
+        assert!(debugger.enter_debugger(breakpoint_exception()).is_ok());
+        assert!(debugger.transport.is_exhausted());
+
+        let output = String::from_utf8(debugger.transport.output()).unwrap();
+        let output = output.strip_prefix("$T05thread:01;#07+$").expect("missing qSupported response");
+        assert!(output.starts_with("PacketSize=8000;"), "unexpected qSupported response: {output}");
+    }
+
+    #[test]
+    #[should_panic = "The debugger packet buffer is too small to hold a GDB register packet."]
+    fn test_packet_buffer_size_rejects_a_buffer_too_small_for_the_registers() {
+        let _ = PatinaDebugger::new(ReplaySerial::new(&[])).with_packet_buffer_size(MIN_GDB_BUFF_LEN - 1);
+    }
+
+    #[test]
+    fn test_run_command_captures_monitor_command_output() {
+        let debugger = Box::leak(Box::new(PatinaDebugger::new(ReplaySerial::new(&[])).with_force_enable(true)));
+        debugger.add_monitor_command("echo", "Echoes its arguments", |args, out| {
+            for arg in args {
+                let _ = writeln!(out, "{arg}");
+            }
+        });
+
+        assert_eq!(debugger.run_command("echo", "first  second"), Ok(String::from("first\nsecond\n")));
+        assert_eq!(debugger.run_command("echo", ""), Ok(String::new()));
+        assert_eq!(debugger.run_command("missing", ""), Err(EfiError::NotFound));
+
+        let _state = debugger.system_state.lock();
+        assert_eq!(debugger.run_command("echo", "locked"), Err(EfiError::NotReady));
+    }
+}
@@ -127,8 +127,22 @@ static DEBUGGER: spin::Once<&dyn Debugger> = spin::Once::new();
 ///
 /// the second argument is a writer that should be used to write the output of the
 /// command. This can be done by directly invoking the [core::fmt::Write] trait methods
-/// or using the `write!` macro.
-pub type MonitorCommandFn = fn(&mut core::str::SplitWhitespace<'_>, &mut dyn core::fmt::Write);
+/// or using the `write!` macro. The writer is backed by a fixed stack buffer that is
+/// sent to the debugger in chunks as it fills, so output of any length can be written
+/// without allocations. Long running commands should check [MonitorWriter::is_cancelled]
+/// periodically, and return early once the debugger has asked them to stop.
+pub type MonitorCommandFn = fn(&mut core::str::SplitWhitespace<'_>, &mut dyn MonitorWriter);
+
+/// Writer for the output of a monitor command.
+pub trait MonitorWriter: core::fmt::Write {
+    /// Checks if the command has been asked to stop, such as by a Ctrl-C from the
+    /// debugger. Once cancelled, the writer stays cancelled for the rest of the command.
+    fn is_cancelled(&mut self) -> bool {
+        false
+    }
+}
+
+impl MonitorWriter for alloc::string::String {}
 
 /// Trait for debugger interaction. This is required to allow for a global to the
 /// platform specific debugger implementation. For safety, these routines should
@@ -230,9 +244,10 @@ pub fn enabled() -> bool {
     }
 }
 
-/// Adds a monitor command to the debugger. This may be called before initialization,
-/// but should not be called before memory allocations are available. See [MonitorCommandFn]
-/// for more details on the callback function expectations.
+/// Adds a monitor command to the debugger. This may be called before initialization. With
+/// the `alloc` feature, this should not be called before memory allocations are available.
+/// Without it, up to 16 commands are stored in a static table. See [MonitorCommandFn] for
+/// more details on the callback function expectations.
 ///
 /// ## Example
 ///
@@ -10,19 +10,46 @@
 
 use alloc::{string::String, vec::Vec};
 
-use crate::MonitorCommandFn;
+use crate::{MonitorCommandFn, MonitorWriter};
+
+/// The number of external monitor commands that can be added without the `alloc` feature.
+#[cfg(not(feature = "alloc"))]
+pub(crate) const MAX_MONITOR_COMMANDS: usize = 16;
 
 pub(crate) struct SystemState {
     /// Tracks modules state.
     pub modules: Modules,
     /// Tracks external monitor commands.
+    #[cfg(feature = "alloc")]
     pub monitor_commands: Vec<MonitorCallback>,
+    /// Tracks external monitor commands in a fixed table, as allocations may not be available.
+    #[cfg(not(feature = "alloc"))]
+    pub monitor_commands: [Option<MonitorCallback>; MAX_MONITOR_COMMANDS],
 }
 
 impl SystemState {
     /// Create a new system state.
     pub const fn new() -> Self {
-        SystemState { modules: Modules::new(), monitor_commands: Vec::new() }
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "alloc")] {
+                SystemState { modules: Modules::new(), monitor_commands: Vec::new() }
+            }
+            else {
+                SystemState { modules: Modules::new(), monitor_commands: [const { None }; MAX_MONITOR_COMMANDS] }
+            }
+        }
+    }
+
+    /// Iterates over the registered external monitor commands.
+    fn monitor_commands(&self) -> impl Iterator<Item = &MonitorCallback> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "alloc")] {
+                self.monitor_commands.iter()
+            }
+            else {
+                self.monitor_commands.iter().flatten()
+            }
+        }
     }
 
     pub fn add_monitor_command(
@@ -31,16 +58,28 @@ impl SystemState {
         description: &'static str,
         callback: MonitorCommandFn,
     ) {
-        let _monitor = MonitorCallback { command, description, callback };
+        let monitor = MonitorCallback { command, description, callback };
         cfg_if::cfg_if! {
             if #[cfg(feature = "alloc")] {
-                self.monitor_commands.push(_monitor);
-                log::info!("Added debugger monitor command: {command}");
+                self.monitor_commands.push(monitor);
             }
             else {
-                log::warn!("Monitor commands are only supported with the 'alloc' feature enabled. Will not add command: {command}");
+                let Some(slot) = self.monitor_commands.iter_mut().find(|slot| slot.is_none()) else {
+                    log::warn!("Debugger monitor command table is full. Will not add command: {command}");
+                    return;
+                };
+                *slot = Some(monitor);
             }
         }
+        log::info!("Added debugger monitor command: {command}");
+    }
+
+    /// Writes the name and description of each registered monitor command to `out`,
+    /// one per line.
+    pub fn write_monitor_command_help(&self, out: &mut dyn core::fmt::Write) {
+        for monitor_cmd in self.monitor_commands() {
+            let _ = writeln!(out, "    {} - {}", monitor_cmd.command, monitor_cmd.description);
+        }
     }
 
     /// Add a monitor command to the system state. Returns `true` if the command
@@ -49,9 +88,9 @@ impl SystemState {
         &self,
         command: &str,
         args: &mut core::str::SplitWhitespace<'_>,
-        out: &mut dyn core::fmt::Write,
+        out: &mut dyn MonitorWriter,
     ) -> bool {
-        for monitor_cmd in &self.monitor_commands {
+        for monitor_cmd in self.monitor_commands() {
             if monitor_cmd.command == command {
                 (monitor_cmd.callback)(args, out);
                 return true;
@@ -203,4 +242,16 @@ mod tests {
 
         assert!(!system_state.handle_monitor_command("invalid", args, &mut out));
     }
+
+    #[test]
+    fn test_monitor_command_help_lists_commands() {
+        let mut system_state = SystemState::new();
+        let callback: MonitorCommandFn = |_, _| {};
+        system_state.add_monitor_command("first", "The first command", callback);
+        system_state.add_monitor_command("second", "The second command", callback);
+
+        let mut out = String::new();
+        system_state.write_monitor_command_help(&mut out);
+        assert_eq!(out, "    first - The first command\n    second - The second command\n");
+    }
 }
@@ -12,6 +12,7 @@
 
 use core::result::Result;
 use gdbstub::conn::{Connection, ConnectionExt};
+use mu_rust_helpers::perf_timer::{Arch, ArchFunctionality};
 use patina::serial::SerialIO;
 
 /// Serial Connection for use with GdbStub
@@ -76,6 +77,48 @@ impl<T: SerialIO> ConnectionExt for SerialConnection<'_, T> {
     }
 }
 
+/// How long the transport is polled for a GDB handshake at each probed baud rate, in microseconds.
+const BAUD_PROBE_TIMEOUT_US: u64 = 1_000_000;
+
+/// Probes the candidate baud rates for one on which a GDB handshake is received.
+///
+/// For each candidate, the transport is switched to the baud rate and polled for
+/// a `+` (acknowledgement) or `$` (packet start) byte until the probe timeout
+/// elapses, as measured by the performance timer. Any other bytes are discarded,
+/// as they are likely the result of a mismatched baud rate. The handshake byte is
+/// consumed, which the client will recover from by retransmitting the packet.
+///
+/// Returns the detected baud rate, or `None` if no candidate responded. Returns an
+/// error if the transport does not support runtime baud rate changes.
+pub(crate) fn probe_baud_rate<T: SerialIO>(transport: &T, baud_rates: &[u32]) -> patina::error::Result<Option<u32>> {
+    probe_baud_rate_with_timeout(transport, baud_rates, BAUD_PROBE_TIMEOUT_US, perf_timer_us)
+}
+
+fn probe_baud_rate_with_timeout<T: SerialIO>(
+    transport: &T,
+    baud_rates: &[u32],
+    timeout_us: u64,
+    now_us: fn() -> u64,
+) -> patina::error::Result<Option<u32>> {
+    for &baud_rate in baud_rates {
+        transport.set_baud_rate(baud_rate)?;
+        let start = now_us();
+        while now_us().saturating_sub(start) < timeout_us {
+            if let Some(b'+' | b'$') = transport.try_read() {
+                return Ok(Some(baud_rate));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the current value of the performance timer, in microseconds.
+fn perf_timer_us() -> u64 {
+    let frequency = (Arch::perf_frequency() as u128).max(1);
+    (Arch::cpu_count() as u128 * 1_000_000 / frequency) as u64
+}
+
 /// Structure for suspending logging within a given scope.
 pub struct LoggingSuspender {
     level: log::LevelFilter,
@@ -96,3 +139,172 @@ impl Drop for LoggingSuspender {
         log::set_max_level(self.level);
     }
 }
+
+/// A scripted serial transport for exercising the debugger protocol in tests.
+///
+/// The host side of the conversation is provided as a sequence of byte chunks. A
+/// chunk only becomes readable once the stub has written something since the previous
+/// chunk was released, mirroring a host that waits for a response before sending its
+/// next packet. Everything the stub writes is recorded and can be inspected with
+/// [`ReplaySerial::output`].
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) struct ReplaySerial {
+    state: spin::Mutex<ReplayState>,
+}
+
+#[cfg(all(test, feature = "alloc"))]
+struct ReplayState {
+    /// Host byte chunks in the order they are sent.
+    script: &'static [&'static [u8]],
+    /// Index of the next chunk to release.
+    next_chunk: usize,
+    /// Unread bytes of the most recently released chunk.
+    pending: &'static [u8],
+    /// Whether the stub has written since the last chunk was released.
+    responded: bool,
+    /// Bytes written by the stub.
+    output: std::vec::Vec<u8>,
+}
+
+#[cfg(all(test, feature = "alloc"))]
+impl ReplaySerial {
+    /// Creates a transport that replays the given host chunks.
+    pub(crate) fn new(script: &'static [&'static [u8]]) -> Self {
+        Self {
+            state: spin::Mutex::new(ReplayState {
+                script,
+                next_chunk: 0,
+                pending: &[],
+                responded: false,
+                output: std::vec::Vec::new(),
+            }),
+        }
+    }
+
+    /// Returns everything the stub has written so far.
+    pub(crate) fn output(&self) -> std::vec::Vec<u8> {
+        self.state.lock().output.clone()
+    }
+
+    /// Returns true if every scripted chunk has been fully consumed.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        let state = self.state.lock();
+        state.next_chunk == state.script.len() && state.pending.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+impl SerialIO for ReplaySerial {
+    fn init(&self) {}
+
+    fn write(&self, buffer: &[u8]) {
+        let mut state = self.state.lock();
+        state.output.extend_from_slice(buffer);
+        state.responded = true;
+    }
+
+    fn read(&self) -> u8 {
+        match self.try_read() {
+            Some(byte) => byte,
+            None => {
+                let state = self.state.lock();
+                panic!(
+                    "Replay script stalled after chunk {} of {}. Stub output: {:?}",
+                    state.next_chunk,
+                    state.script.len(),
+                    std::string::String::from_utf8_lossy(&state.output)
+                );
+            }
+        }
+    }
+
+    fn try_read(&self) -> Option<u8> {
+        let mut state = self.state.lock();
+        if state.pending.is_empty() {
+            if !state.responded || state.next_chunk == state.script.len() {
+                return None;
+            }
+            state.pending = state.script[state.next_chunk];
+            state.next_chunk += 1;
+            state.responded = false;
+        }
+
+        let (&byte, rest) = state.pending.split_first()?;
+        state.pending = rest;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use patina::error::EfiError;
+
+    /// A serial transport that only produces data when set to the client's baud rate.
+    struct ProbeSerial {
+        client_baud_rate: u32,
+        supports_baud_change: bool,
+        current_baud_rate: spin::Mutex<u32>,
+    }
+
+    impl ProbeSerial {
+        fn new(client_baud_rate: u32, supports_baud_change: bool) -> Self {
+            Self { client_baud_rate, supports_baud_change, current_baud_rate: spin::Mutex::new(0) }
+        }
+    }
+
+    impl SerialIO for ProbeSerial {
+        fn init(&self) {}
+
+        fn write(&self, _buffer: &[u8]) {}
+
+        fn read(&self) -> u8 {
+            unimplemented!()
+        }
+
+        fn try_read(&self) -> Option<u8> {
+            // Produce noise at mismatched baud rates and a handshake when matched.
+            if *self.current_baud_rate.lock() == self.client_baud_rate { Some(b'$') } else { Some(0xFE) }
+        }
+
+        fn set_baud_rate(&self, baud_rate: u32) -> patina::error::Result<()> {
+            if !self.supports_baud_change {
+                return Err(EfiError::Unsupported);
+            }
+            *self.current_baud_rate.lock() = baud_rate;
+            Ok(())
+        }
+    }
+
+    std::thread_local! {
+        static TEST_CLOCK_US: core::cell::Cell<u64> = const { core::cell::Cell::new(0) };
+    }
+
+    /// A clock that advances by a microsecond each time it is read.
+    fn test_clock_us() -> u64 {
+        TEST_CLOCK_US.with(|clock| clock.replace(clock.get() + 1))
+    }
+
+    #[test]
+    fn test_baud_probe_detects_client() {
+        let serial = ProbeSerial::new(57600, true);
+        assert_eq!(probe_baud_rate_with_timeout(&serial, &[115200, 57600, 9600], 16, test_clock_us), Ok(Some(57600)));
+        assert_eq!(*serial.current_baud_rate.lock(), 57600);
+    }
+
+    #[test]
+    fn test_baud_probe_no_client() {
+        let serial = ProbeSerial::new(38400, true);
+        let start = TEST_CLOCK_US.get();
+        assert_eq!(probe_baud_rate_with_timeout(&serial, &[115200, 57600], 16, test_clock_us), Ok(None));
+        // Each candidate is polled until its timeout elapses.
+        assert!(TEST_CLOCK_US.get() - start >= 2 * 16);
+    }
+
+    #[test]
+    fn test_baud_probe_unsupported_transport() {
+        let serial = ProbeSerial::new(115200, false);
+        assert_eq!(probe_baud_rate_with_timeout(&serial, &[115200], 16, test_clock_us), Err(EfiError::Unsupported));
+    }
+}
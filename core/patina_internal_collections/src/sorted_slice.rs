@@ -87,6 +87,54 @@ where
         Ok(idx)
     }
 
+    /// Merges the provided sorted elements into the sorted slice in a single pass.
+    ///
+    /// Unlike [`Self::add_contiguous_slice`], the elements may be interleaved with the existing elements. Each existing
+    /// element is moved at most once, merging from the back of the slice.
+    pub fn merge_sorted(&mut self, elements: &[T]) -> Result<(), Error> {
+        if elements.is_empty() {
+            return Ok(());
+        }
+
+        if self.len() + elements.len() > self.capacity() {
+            return Err(Error::OutOfSpace);
+        }
+
+        if !elements.is_sorted_by_key(|e| e.key()) {
+            return Err(Error::NotSorted);
+        }
+
+        // Check for duplicates before moving anything so the slice is left unchanged on error.
+        let mut e = elements.windows(2);
+        while let Some([a, b]) = e.next() {
+            if a.key() == b.key() {
+                return Err(Error::AlreadyExists);
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < self.len() && j < elements.len() {
+            match self.slice[i].key().cmp(elements[j].key()) {
+                core::cmp::Ordering::Less => i += 1,
+                core::cmp::Ordering::Greater => j += 1,
+                core::cmp::Ordering::Equal => return Err(Error::AlreadyExists),
+            }
+        }
+
+        let (mut i, mut j) = (self.len(), elements.len());
+        while j > 0 {
+            if i > 0 && self.slice[i - 1].key() > elements[j - 1].key() {
+                self.slice[i + j - 1] = self.slice[i - 1];
+                i -= 1;
+            } else {
+                self.slice[i + j - 1] = elements[j - 1];
+                j -= 1;
+            }
+        }
+        self.item_count += elements.len();
+        Ok(())
+    }
+
     /// Removes the datum and returns it's previous index.
     pub fn remove(&mut self, element: T) -> Result<usize, Error> {
         let Ok(idx) = self.search(element) else {
@@ -192,7 +240,7 @@ mod tests {
     extern crate std;
     use super::*;
     extern crate alloc;
-    use alloc::vec::Vec;
+    use alloc::{vec, vec::Vec};
 
     #[test]
     fn test_init_state_of_new_sorted_slice() {
@@ -256,6 +304,44 @@ mod tests {
         assert_eq!(Err(Error::OutOfSpace), ss.add_contiguous_slice(&[11]));
     }
 
+    #[test]
+    fn test_merge_sorted_matches_repeated_add() {
+        let existing = [3, 10, 11, 20, 42];
+        let elements = [0, 1, 4, 12, 13, 21, 43, 50];
+
+        let mut merged_mem = [0; 16 * mem::size_of::<usize>()];
+        let mut merged = SortedSlice::<'_, usize>::new(&mut merged_mem);
+        merged.add_contiguous_slice(&existing).unwrap();
+        assert_eq!(Ok(()), merged.merge_sorted(&elements));
+
+        let mut added_mem = [0; 16 * mem::size_of::<usize>()];
+        let mut added = SortedSlice::<'_, usize>::new(&mut added_mem);
+        for e in existing.iter().chain(elements.iter()) {
+            added.add(*e).unwrap();
+        }
+
+        assert_eq!(added.iter().collect::<Vec<_>>(), merged.iter().collect::<Vec<_>>());
+        assert_eq!(Ok(()), merged.merge_sorted(&[]));
+        assert_eq!(13, merged.len());
+    }
+
+    #[test]
+    fn test_merge_sorted_errors_leave_slice_unchanged() {
+        let mut mem = [0; 6 * mem::size_of::<usize>()];
+        let mut ss = SortedSlice::<'_, usize>::new(&mut mem);
+        ss.add_contiguous_slice(&[2, 4, 6]).unwrap();
+
+        assert_eq!(Err(Error::OutOfSpace), ss.merge_sorted(&[1, 3, 5, 7]));
+        assert_eq!(Err(Error::NotSorted), ss.merge_sorted(&[3, 1]));
+        assert_eq!(Err(Error::AlreadyExists), ss.merge_sorted(&[1, 1]));
+        assert_eq!(Err(Error::AlreadyExists), ss.merge_sorted(&[1, 6]));
+        assert_eq!(vec![2, 4, 6], ss.iter().copied().collect::<Vec<_>>());
+
+        assert_eq!(Ok(()), ss.merge_sorted(&[1, 3, 5]));
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], ss.iter().copied().collect::<Vec<_>>());
+        assert_eq!(Err(Error::OutOfSpace), ss.merge_sorted(&[7]));
+    }
+
     #[test]
     fn test_remove_in_sorted_array() {
         let mut mem = [0; 10 * mem::size_of::<usize>()];
@@ -19,6 +19,9 @@ use patina::error::EfiError;
 use patina::pi::protocols::cpu_arch::EfiSystemContext;
 
 mod exception_handling;
+mod timer;
+
+pub use timer::{TimerInterrupt, TimerTickCallback, set_timer_notify};
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "uefi", target_arch = "x86_64"))] {
@@ -145,6 +148,28 @@ pub trait InterruptManager {
     fn unregister_exception_handler(&self, exception_type: ExceptionType) -> Result<(), EfiError> {
         exception_handling::unregister_exception_handler(exception_type)
     }
+
+    /// Registers the tick callback of a platform timer on the timer's interrupt vector.
+    fn register_timer_interrupt(&self, timer: &'static TimerInterrupt) -> Result<(), EfiError> {
+        self.register_exception_handler(timer.vector(), HandlerType::Handler(timer))?;
+        timer.set_registered(true);
+        Ok(())
+    }
+
+    /// Removes the tick callback of a platform timer from the timer's interrupt vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidParameter`](EfiError::InvalidParameter) if the timer is not registered, so that a handler
+    /// registered on the vector by someone else is left in place.
+    fn unregister_timer_interrupt(&self, timer: &TimerInterrupt) -> Result<(), EfiError> {
+        if !timer.is_registered() {
+            return Err(EfiError::InvalidParameter);
+        }
+        self.unregister_exception_handler(timer.vector())?;
+        timer.set_registered(false);
+        Ok(())
+    }
 }
 
 /// Type for storing the handler for a given exception.
@@ -0,0 +1,160 @@
+//! Registration of a platform timer's tick callback on the timer interrupt vector.
+//!
+//! A platform timer driver backing the Timer and Metronome Architectural Protocols describes its interrupt with a
+//! static [`TimerInterrupt`] and registers it with [`InterruptManager::register_timer_interrupt`], rather than
+//! registering an `extern "efiapi"` routine through the CPU Architectural Protocol. The time elapsed on each tick is
+//! reported to the timer notify function connected with [`set_timer_notify`], which the DXE Core connects when it
+//! installs the CPU Architectural Protocol, so the driver does not call the notify function passed to its Timer
+//! Architectural Protocol.
+//!
+//! ```ignore
+//! fn timer_tick(context: &mut ExceptionContext) -> u64 {
+//!     // Acknowledge the timer, then return the time elapsed since the previous tick.
+//! }
+//!
+//! static TIMER: TimerInterrupt = TimerInterrupt::new(TIMER_VECTOR, timer_tick);
+//!
+//! interrupt_manager.register_timer_interrupt(&TIMER)?;
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use patina::pi::protocols::timer::EfiTimerNotify;
+use spin::rwlock::RwLock;
+
+use super::{ExceptionContext, ExceptionType, InterruptHandler};
+
+/// Callback invoked from the timer interrupt on each tick of a platform timer.
+///
+/// The callback runs in interrupt context with the context of the interrupted code. It is expected to acknowledge the
+/// timer interrupt and return the time elapsed since the previous tick, in 100 ns units, which is reported to the
+/// timer notify function.
+pub type TimerTickCallback = fn(context: &mut ExceptionContext) -> u64;
+
+/// The timer notify function the elapsed time of each tick is reported to.
+static TIMER_NOTIFY: RwLock<Option<EfiTimerNotify>> = RwLock::new(None);
+
+/// Connects the timer notify function that the elapsed time of each tick of a [`TimerInterrupt`] is reported to, or
+/// disconnects it with `None`.
+pub fn set_timer_notify(notify: Option<EfiTimerNotify>) {
+    *TIMER_NOTIFY.write() = notify;
+}
+
+/// A platform timer's tick callback, and the interrupt vector the timer interrupt is delivered on.
+pub struct TimerInterrupt {
+    vector: ExceptionType,
+    tick: TimerTickCallback,
+    registered: AtomicBool,
+}
+
+impl TimerInterrupt {
+    /// Creates a timer interrupt invoking `tick` each time `vector` is taken.
+    pub const fn new(vector: ExceptionType, tick: TimerTickCallback) -> Self {
+        Self { vector, tick, registered: AtomicBool::new(false) }
+    }
+
+    /// Returns the interrupt vector of the timer.
+    pub const fn vector(&self) -> ExceptionType {
+        self.vector
+    }
+
+    /// Returns whether the timer is registered on its vector.
+    pub fn is_registered(&self) -> bool {
+        self.registered.load(Ordering::Acquire)
+    }
+
+    pub(super) fn set_registered(&self, registered: bool) {
+        self.registered.store(registered, Ordering::Release);
+    }
+}
+
+impl InterruptHandler for TimerInterrupt {
+    fn handle_interrupt(&'static self, _exception_type: ExceptionType, context: &mut ExceptionContext) {
+        let elapsed = (self.tick)(context);
+        if let Some(notify) = *TIMER_NOTIFY.read() {
+            notify(elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    use mockall::{mock, predicate::*};
+    use patina::error::EfiError;
+
+    use super::*;
+    use crate::interrupts::{HandlerType, InterruptManager, null::ExceptionContextNull};
+
+    const TIMER_VECTOR: ExceptionType = 5;
+    const TIMER_PERIOD: u64 = 100_000;
+
+    static TICKS: AtomicUsize = AtomicUsize::new(0);
+    static NOTIFIED_TIME: AtomicU64 = AtomicU64::new(0);
+
+    fn timer_tick(_context: &mut ExceptionContext) -> u64 {
+        TICKS.fetch_add(1, Ordering::SeqCst);
+        TIMER_PERIOD
+    }
+
+    extern "efiapi" fn timer_notify(time: u64) {
+        NOTIFIED_TIME.fetch_add(time, Ordering::SeqCst);
+    }
+
+    static TIMER: TimerInterrupt = TimerInterrupt::new(TIMER_VECTOR, timer_tick);
+
+    mock! {
+        InterruptManager {}
+        impl InterruptManager for InterruptManager {
+            fn register_exception_handler(
+                &self,
+                exception_type: ExceptionType,
+                handler: HandlerType,
+            ) -> Result<(), EfiError>;
+            fn unregister_exception_handler(&self, exception_type: ExceptionType) -> Result<(), EfiError>;
+        }
+    }
+
+    #[test]
+    fn test_timer_tick_is_registered_on_the_timer_vector() {
+        set_timer_notify(Some(timer_notify));
+
+        let mut interrupt_manager = MockInterruptManager::new();
+        interrupt_manager.expect_register_exception_handler().with(eq(TIMER_VECTOR), always()).once().returning(
+            |vector, handler| {
+                let HandlerType::Handler(handler) = handler else {
+                    panic!("The timer tick was not registered as an interrupt handler.");
+                };
+                handler.handle_interrupt(vector, &mut ExceptionContextNull {});
+                Ok(())
+            },
+        );
+        interrupt_manager.expect_unregister_exception_handler().with(eq(TIMER_VECTOR)).once().returning(|_| Ok(()));
+
+        assert_eq!(TIMER.vector(), TIMER_VECTOR);
+        interrupt_manager.register_timer_interrupt(&TIMER).unwrap();
+        assert!(TIMER.is_registered());
+        assert_eq!(TICKS.load(Ordering::SeqCst), 1);
+        assert_eq!(NOTIFIED_TIME.load(Ordering::SeqCst), TIMER_PERIOD);
+
+        // A second registration fails without taking the vector from the first.
+        interrupt_manager.expect_register_exception_handler().returning(|_, _| Err(EfiError::AlreadyStarted));
+        assert_eq!(interrupt_manager.register_timer_interrupt(&TIMER), Err(EfiError::AlreadyStarted));
+        assert!(TIMER.is_registered());
+
+        interrupt_manager.unregister_timer_interrupt(&TIMER).unwrap();
+        assert!(!TIMER.is_registered());
+
+        // The vector is no longer owned by the timer, so it is not unregistered again.
+        assert_eq!(interrupt_manager.unregister_timer_interrupt(&TIMER), Err(EfiError::InvalidParameter));
+    }
+}
@@ -21,3 +21,190 @@ cfg_if::cfg_if! {
         pub use null::create_cpu_null_paging as create_cpu_paging;
     }
 }
+
+use patina_paging::{MemoryAttributes, PageTable, PtError};
+use r_efi::efi;
+
+/// The granularity at which a range with mixed mappings is split.
+const PAGE_SIZE: u64 = 0x1000;
+
+/// Queries of the CPU paging implementations beyond those of [`PageTable`].
+///
+/// Implemented for every [`PageTable`], including the one returned by [`create_cpu_paging`].
+pub trait EfiCpuPaging: PageTable {
+    /// Checks whether a memory range is fully mapped with the `required` attributes and none of the `forbidden`
+    /// attributes.
+    ///
+    /// Unlike [`PageTable::query_memory_region`], the range may span multiple mappings with different attributes, in
+    /// which case each sub-range is checked independently. Returns `Ok(false)` if any part of the range is unmapped or
+    /// violates the attribute requirements.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `INVALID_PARAMETER` if the address or size is not page aligned or the size is zero, or the status
+    /// corresponding to any other paging error.
+    fn range_has_attributes(
+        &self,
+        address: u64,
+        size: u64,
+        required: u64,
+        forbidden: u64,
+    ) -> Result<bool, efi::Status> {
+        if size == 0
+            || !address.is_multiple_of(PAGE_SIZE)
+            || !size.is_multiple_of(PAGE_SIZE)
+            || address.checked_add(size).is_none()
+        {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+
+        let required = MemoryAttributes::from_bits_retain(required);
+        let forbidden = MemoryAttributes::from_bits_retain(forbidden);
+        sub_range_has_attributes(self, address, size, required, forbidden)
+    }
+}
+
+impl<P: PageTable + ?Sized> EfiCpuPaging for P {}
+
+fn sub_range_has_attributes<P: PageTable + ?Sized>(
+    page_table: &P,
+    address: u64,
+    size: u64,
+    required: MemoryAttributes,
+    forbidden: MemoryAttributes,
+) -> Result<bool, efi::Status> {
+    match page_table.query_memory_region(address, size) {
+        Ok(attributes) => Ok(attributes.contains(required) && !attributes.intersects(forbidden)),
+        Err(PtError::NoMapping | PtError::InconsistentMappingAcrossRange) => Ok(false),
+        // The range is mapped with differing attributes, so check each half independently.
+        Err(PtError::IncompatibleMemoryAttributes) if size > PAGE_SIZE => {
+            let lower_size = (size / PAGE_SIZE / 2) * PAGE_SIZE;
+            Ok(sub_range_has_attributes(page_table, address, lower_size, required, forbidden)?
+                && sub_range_has_attributes(page_table, address + lower_size, size - lower_size, required, forbidden)?)
+        }
+        Err(err) => Err(pterror_to_status(err)),
+    }
+}
+
+fn pterror_to_status(err: PtError) -> efi::Status {
+    match err {
+        PtError::OutOfResources | PtError::AllocationFailure => efi::Status::OUT_OF_RESOURCES,
+        PtError::NoMapping => efi::Status::NOT_FOUND,
+        PtError::UnsupportedPagingType => efi::Status::UNSUPPORTED,
+        PtError::InternalError => efi::Status::DEVICE_ERROR,
+        _ => efi::Status::INVALID_PARAMETER,
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use patina_paging::PtResult;
+
+    /// A page table backed by a list of mapped regions.
+    struct RegionPageTable {
+        regions: Vec<(u64, u64, MemoryAttributes)>,
+    }
+
+    impl RegionPageTable {
+        fn attributes_at(&self, address: u64) -> Option<MemoryAttributes> {
+            self.regions
+                .iter()
+                .find(|(base, size, _)| (*base..*base + *size).contains(&address))
+                .map(|(_, _, attributes)| *attributes)
+        }
+    }
+
+    impl PageTable for RegionPageTable {
+        fn map_memory_region(&mut self, _address: u64, _size: u64, _attributes: MemoryAttributes) -> PtResult<()> {
+            unimplemented!()
+        }
+
+        fn unmap_memory_region(&mut self, _address: u64, _size: u64) -> PtResult<()> {
+            unimplemented!()
+        }
+
+        fn install_page_table(&mut self) -> PtResult<()> {
+            unimplemented!()
+        }
+
+        fn query_memory_region(&self, address: u64, size: u64) -> PtResult<MemoryAttributes> {
+            let pages: Vec<_> =
+                (address..address + size).step_by(PAGE_SIZE as usize).map(|a| self.attributes_at(a)).collect();
+            match (pages.iter().all(Option::is_some), pages.iter().all(Option::is_none)) {
+                (false, true) => Err(PtError::NoMapping),
+                (false, false) => Err(PtError::InconsistentMappingAcrossRange),
+                _ if pages.windows(2).any(|w| w[0] != w[1]) => Err(PtError::IncompatibleMemoryAttributes),
+                _ => Ok(pages[0].unwrap()),
+            }
+        }
+
+        fn dump_page_tables(&self, _address: u64, _size: u64) -> PtResult<()> {
+            Ok(())
+        }
+    }
+
+    fn mixed_page_table() -> RegionPageTable {
+        RegionPageTable {
+            regions: vec![
+                (0x1000, 0x3000, MemoryAttributes::Writeback | MemoryAttributes::ExecuteProtect),
+                (0x4000, 0x1000, MemoryAttributes::Uncacheable | MemoryAttributes::ExecuteProtect),
+                (0x5000, 0x2000, MemoryAttributes::Writeback | MemoryAttributes::ReadOnly),
+                (0x8000, 0x1000, MemoryAttributes::Writeback),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_range_has_attributes_single_mapping() {
+        let page_table = mixed_page_table();
+        let xp = MemoryAttributes::ExecuteProtect.bits();
+        let ro = MemoryAttributes::ReadOnly.bits();
+
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x3000, xp, ro), Ok(true));
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x3000, ro, 0), Ok(false));
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x3000, 0, xp), Ok(false));
+    }
+
+    #[test]
+    fn test_range_has_attributes_across_mixed_mappings() {
+        let page_table = mixed_page_table();
+        let xp = MemoryAttributes::ExecuteProtect.bits();
+        let ro = MemoryAttributes::ReadOnly.bits();
+        let uc = MemoryAttributes::Uncacheable.bits();
+
+        // Different cache types, but all execute protected and writable.
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x4000, xp, ro), Ok(true));
+        // The uncached page in the middle violates the forbidden attribute.
+        assert_eq!(page_table.range_has_attributes(0x2000, 0x4000, 0, uc), Ok(false));
+        // The read-only pages at the end are missing the required attribute.
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x6000, xp, 0), Ok(false));
+        // No requirements only checks that the range is mapped.
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x6000, 0, 0), Ok(true));
+    }
+
+    #[test]
+    fn test_range_has_attributes_unmapped() {
+        let page_table = mixed_page_table();
+
+        // Partially mapped due to the hole at 0x7000.
+        assert_eq!(page_table.range_has_attributes(0x5000, 0x4000, 0, 0), Ok(false));
+        // Fully unmapped.
+        assert_eq!(page_table.range_has_attributes(0x10000, 0x2000, 0, 0), Ok(false));
+    }
+
+    #[test]
+    fn test_range_has_attributes_invalid_parameters() {
+        let page_table = mixed_page_table();
+
+        assert_eq!(page_table.range_has_attributes(0x1000, 0, 0, 0), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(page_table.range_has_attributes(0x1001, 0x1000, 0, 0), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(page_table.range_has_attributes(0x1000, 0x1001, 0, 0), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(
+            page_table.range_has_attributes(u64::MAX - 0xFFF, 0x2000, 0, 0),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+    }
+}
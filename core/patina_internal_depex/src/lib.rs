@@ -14,7 +14,11 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use core::mem;
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicU8, Ordering},
+};
+use patina::base::guid::from_uuid;
 use r_efi::efi;
 use uuid::Uuid;
 
@@ -53,9 +57,9 @@ pub enum Opcode {
     /// If present, this must be the first opcode in the expression.
     /// Used to schedule on request.
     Sor,
-    /// An unknown opcode. Indicates an unrecognized opcode
-    /// that should be treated as an error during evaluation.
-    Unknown,
+    /// An unknown opcode, with its value, or `None` if there is no opcode byte.
+    /// Indicates an unrecognized opcode that should be treated as an error during evaluation.
+    Unknown(Option<u8>),
     /// A known opcode with an unexpected payload length.
     Malformed {
         /// The unhandled opcode value.
@@ -65,11 +69,22 @@ pub enum Opcode {
     },
 }
 
-/// Converts a UUID to an EFI GUID.
-fn guid_from_uuid(uuid: &Uuid) -> Option<efi::Guid> {
-    let fields = uuid.as_fields();
-    let node = &fields.3[2..].try_into().ok()?;
-    Some(efi::Guid::from_fields(fields.0, fields.1, fields.2, fields.3[0], fields.3[1], node))
+/// Returns the result of an `End` opcode, which must leave exactly one value on the stack.
+fn end_result(stack: &[bool]) -> Result<bool, DepexError> {
+    match *stack {
+        [] => Err(DepexError::StackUnderflow),
+        [result] => Ok(result),
+        _ => Err(DepexError::StackImbalance { remaining: stack.len() }),
+    }
+}
+
+/// How [`Depex::eval_inner`] treats operators without enough operands and unknown opcodes.
+#[derive(Clone, Copy)]
+enum EvalMode {
+    /// A missing operand evaluates as `false`, and unknown opcodes are handled by the policy.
+    Lenient(UnknownOpcodePolicy),
+    /// A missing operand and an unknown opcode are both errors.
+    Strict,
 }
 
 /// Converts a byte slice to a GUID.
@@ -80,7 +95,10 @@ fn uuid_from_slice(slice: Option<&[u8]>) -> Option<Uuid> {
 impl<'a> From<&'a [u8]> for Opcode {
     /// Creates an Opcode from a byte slice.
     fn from(bytes: &'a [u8]) -> Self {
-        match bytes[0] {
+        let Some(&opcode) = bytes.first() else {
+            return Opcode::Unknown(None);
+        };
+        match opcode {
             0x00 => match uuid_from_slice(bytes.get(1..GUID_SIZE + 1)) {
                 Some(uuid) => Opcode::Before(uuid),
                 None => Opcode::Malformed { opcode: 0x00, len: bytes.len() - 1 },
@@ -100,7 +118,7 @@ impl<'a> From<&'a [u8]> for Opcode {
             0x07 => Opcode::False,
             0x08 => Opcode::End,
             0x09 => Opcode::Sor,
-            _ => Opcode::Unknown,
+            _ => Opcode::Unknown(Some(opcode)),
         }
     }
 }
@@ -123,16 +141,88 @@ pub enum AssociatedDependency {
     After(efi::Guid),
 }
 
+/// Errors describing why a DEPEX expression is structurally invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepexError {
+    /// The expression contains no opcodes.
+    Empty,
+    /// A `Before` or `After` opcode is present somewhere other than the start of the expression.
+    MisplacedBeforeAfter,
+    /// A `Before` or `After` opcode is followed by something other than a single `End` opcode.
+    InvalidAssociatedDependency,
+    /// A `Sor` opcode is present somewhere other than the start of the expression.
+    MisplacedSor,
+    /// The expression does not terminate with an `End` opcode.
+    MissingEnd,
+    /// An opcode follows the first `End` opcode of the expression.
+    TrailingOpcodes,
+    /// The expression contains an unrecognized opcode, with its value if there is one.
+    UnknownOpcode(Option<u8>),
+    /// The expression contains a known opcode with an unexpected payload length.
+    MalformedOpcode {
+        /// The malformed opcode value.
+        opcode: u8,
+        /// The length of the payload sent with the opcode.
+        len: usize,
+    },
+    /// An opcode has fewer operands on the stack than it requires.
+    StackUnderflow,
+    /// More than one value is left on the stack at the `End` opcode.
+    StackImbalance {
+        /// The number of values on the stack at the `End` opcode.
+        remaining: usize,
+    },
+}
+
+/// How [`Depex::eval`] treats an unknown or reserved opcode in the expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum UnknownOpcodePolicy {
+    /// The expression is invalid. Evaluation asserts in debug builds and evaluates to `false`.
+    #[default]
+    Error,
+    /// The expression is not satisfied. Evaluation logs a warning and evaluates to `false`.
+    NotDispatchable,
+    /// The opcode is ignored, and the rest of the expression is evaluated.
+    Skip,
+}
+
+/// The policy of expressions that have not set their own, as set by [`set_default_unknown_opcode_policy`].
+static DEFAULT_UNKNOWN_OPCODE_POLICY: AtomicU8 = AtomicU8::new(UnknownOpcodePolicy::Error as u8);
+
+/// Sets how expressions that have not set their own policy treat unknown opcodes.
+pub fn set_default_unknown_opcode_policy(policy: UnknownOpcodePolicy) {
+    DEFAULT_UNKNOWN_OPCODE_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns how expressions that have not set their own policy treat unknown opcodes.
+pub fn default_unknown_opcode_policy() -> UnknownOpcodePolicy {
+    match DEFAULT_UNKNOWN_OPCODE_POLICY.load(Ordering::Relaxed) {
+        policy if policy == UnknownOpcodePolicy::NotDispatchable as u8 => UnknownOpcodePolicy::NotDispatchable,
+        policy if policy == UnknownOpcodePolicy::Skip as u8 => UnknownOpcodePolicy::Skip,
+        _ => UnknownOpcodePolicy::Error,
+    }
+}
+
+/// Parses and evaluates a DEPEX expression from untrusted bytes.
+///
+/// This never panics, regardless of the input, making it suitable as a fuzzing entry point. See
+/// [`Depex::try_eval`] for the evaluation rules.
+pub fn parse_and_eval(bytes: &[u8], protocols: &[efi::Guid]) -> Result<bool, DepexError> {
+    Depex::from(bytes).try_eval(protocols)
+}
+
 #[derive(Debug)]
 /// A UEFI dependency expression (DEPEX)
 pub struct Depex {
     expression: Vec<Opcode>,
+    unknown_opcode_policy: Option<UnknownOpcodePolicy>,
 }
 
 impl From<&[u8]> for Depex {
     fn from(value: &[u8]) -> Self {
         let depex_parser = DepexParser::new(value);
-        Self { expression: depex_parser.into_iter().collect() }
+        Self { expression: depex_parser.into_iter().collect(), unknown_opcode_policy: None }
     }
 }
 
@@ -144,150 +234,281 @@ impl From<Vec<u8>> for Depex {
 
 impl From<&[Opcode]> for Depex {
     fn from(value: &[Opcode]) -> Self {
-        Self { expression: value.to_vec() }
+        Self { expression: value.to_vec(), unknown_opcode_policy: None }
     }
 }
 
 impl Depex {
+    /// Sets how [`eval`](Self::eval) treats unknown opcodes in this expression, overriding the
+    /// [default policy](default_unknown_opcode_policy).
+    ///
+    /// [`try_eval`](Self::try_eval) always reports an unknown opcode as an error.
+    pub fn set_unknown_opcode_policy(&mut self, policy: UnknownOpcodePolicy) {
+        self.unknown_opcode_policy = Some(policy);
+    }
+
+    /// Returns how [`eval`](Self::eval) treats unknown opcodes in this expression.
+    pub fn unknown_opcode_policy(&self) -> UnknownOpcodePolicy {
+        self.unknown_opcode_policy.unwrap_or_else(default_unknown_opcode_policy)
+    }
+
     /// Evaluates a DEPEX expression.
     pub fn eval(&mut self, protocols: &[efi::Guid]) -> bool {
-        let mut stack = Vec::with_capacity(DEPEX_STACK_SIZE_INCREMENT);
+        self.eval_with(|guid| protocols.contains(guid))
+    }
+
+    /// Evaluates a DEPEX expression, using `is_present` to determine whether a protocol has been installed.
+    ///
+    /// This allows the caller to back protocol lookups with any data structure (e.g. a hash set) rather than
+    /// materializing the full protocol list as a slice.
+    pub fn eval_with<F>(&mut self, is_present: F) -> bool
+    where
+        F: FnMut(&efi::Guid) -> bool,
+    {
         log::trace!("Depex:");
+        self.eval_lenient(is_present, &mut |opcode, stack| {
+            log::trace!("  {opcode:x?} => {:?}, stack ->{:?}", stack.last(), stack.iter().rev().collect::<Vec<_>>());
+        })
+    }
+
+    /// Evaluates a DEPEX expression, invoking `tracer` for each opcode evaluated.
+    ///
+    /// The tracer receives the opcode and the full stack after the opcode has been applied, with the top of the stack
+    /// as the last element. When `End` is traced, the stack still holds the final result.
+    pub fn eval_traced(&mut self, protocols: &[efi::Guid], tracer: &mut dyn FnMut(&Opcode, &[bool])) -> bool {
+        self.eval_lenient(|guid| protocols.contains(guid), tracer)
+    }
+
+    /// Evaluates a DEPEX expression, returning an error rather than asserting if the expression is invalid.
+    ///
+    /// The structure of the expression is validated with [`validate_structure`](Self::validate_structure) before
+    /// evaluation. An operator without enough operands on the stack is an error rather than evaluating them as
+    /// `false`, as is more than one value left on the stack at `End`. `Before`, `After`, and an unscheduled `Sor`
+    /// expression evaluate to `false`, as with [`eval`](Self::eval).
+    pub fn try_eval(&mut self, protocols: &[efi::Guid]) -> Result<bool, DepexError> {
+        self.validate_structure()?;
+        self.eval_inner(|guid| protocols.contains(guid), &mut |_, _| (), EvalMode::Strict)
+    }
+
+    /// Evaluates a DEPEX expression, returning the error [`eval`](Self::eval) would assert on rather than `false`.
+    ///
+    /// This is the same as [`try_eval`](Self::try_eval). An unknown opcode is always an error, whatever the
+    /// [`UnknownOpcodePolicy`].
+    pub fn eval_checked(&mut self, protocols: &[efi::Guid]) -> Result<bool, DepexError> {
+        self.try_eval(protocols)
+    }
+
+    /// Evaluates a DEPEX expression as [`eval`](Self::eval) does, asserting in debug builds if it is invalid.
+    ///
+    /// An empty expression, or one without a final value on the stack, evaluates to `false` without asserting. So does
+    /// one with opcodes after `End` or more than one value on the stack at `End`, which logs a warning.
+    fn eval_lenient<F>(&mut self, is_present: F, tracer: &mut dyn FnMut(&Opcode, &[bool])) -> bool
+    where
+        F: FnMut(&efi::Guid) -> bool,
+    {
+        let mode = EvalMode::Lenient(self.unknown_opcode_policy());
+        let error = match self.eval_inner(is_present, tracer, mode) {
+            Ok(result) => return result,
+            Err(error) => error,
+        };
+        match error {
+            DepexError::Empty | DepexError::MissingEnd | DepexError::StackUnderflow => (),
+            DepexError::MisplacedBeforeAfter => {
+                debug_assert!(false, "Invalid BEFORE or AFTER not at start of depex {:#x?}", self.expression);
+            }
+            DepexError::InvalidAssociatedDependency => {
+                debug_assert!(false, "Invalid BEFORE or AFTER with additional opcodes {:#x?}.", self.expression);
+            }
+            DepexError::MisplacedSor => debug_assert!(false, "Invalid SOR not at start of depex."),
+            DepexError::TrailingOpcodes => {
+                log::warn!("Invalid opcodes after END in depex {:#x?}, it is not dispatchable.", self.expression);
+            }
+            DepexError::StackImbalance { remaining } => {
+                log::warn!("Invalid END with {remaining} values on the stack, the depex is not dispatchable.");
+            }
+            DepexError::UnknownOpcode(_) => debug_assert!(false, "Exiting early due to an unknown opcode."),
+            DepexError::MalformedOpcode { opcode, len } => {
+                log::error!("Opcode [0x{opcode:x?}] expects a guid, only has a length of: {len}");
+                debug_assert!(
+                    false,
+                    "Exiting early because opcode [0x{opcode:x?}] expects a guid, only has a length of: {len}"
+                );
+            }
+        }
+        false
+    }
+
+    /// Evaluates a DEPEX expression, stopping at the first error.
+    ///
+    /// `tracer` is invoked for each opcode evaluated, after the opcode has been applied to the stack.
+    fn eval_inner<F>(
+        &mut self,
+        mut is_present: F,
+        tracer: &mut dyn FnMut(&Opcode, &[bool]),
+        mode: EvalMode,
+    ) -> Result<bool, DepexError>
+    where
+        F: FnMut(&efi::Guid) -> bool,
+    {
+        if self.expression.is_empty() {
+            return Err(DepexError::Empty);
+        }
+
+        let mut stack = Vec::with_capacity(DEPEX_STACK_SIZE_INCREMENT);
+        let pop = |stack: &mut Vec<bool>| match mode {
+            EvalMode::Lenient(_) => Ok(stack.pop().unwrap_or(false)),
+            EvalMode::Strict => stack.pop().ok_or(DepexError::StackUnderflow),
+        };
+        let last = self.expression.len() - 1;
+        let ends_after_first = self.expression[1..] == [Opcode::End];
         for (index, opcode) in self.expression.iter_mut().enumerate() {
             match opcode {
                 Opcode::Before(_) | Opcode::After(_) => {
-                    log::trace!("  {opcode:#x?}");
+                    tracer(opcode, &stack);
                     if index != 0 {
-                        debug_assert!(false, "Invalid BEFORE or AFTER not at start of depex {:#x?}", self.expression);
-                        return false;
-                    }
-
-                    if self.expression.len() > 2 {
-                        debug_assert!(
-                            false,
-                            "Invalid BEFORE or AFTER with additional opcodes {:#x?}.",
-                            self.expression
-                        );
-                        return false;
+                        return Err(DepexError::MisplacedBeforeAfter);
                     }
-
-                    if self.expression.len() == 2 && self.expression[1] != Opcode::End {
-                        debug_assert!(
-                            false,
-                            "Invalid BEFORE or AFTER with additional opcodes {:#x?}.",
-                            self.expression
-                        );
-                        return false;
-                    }
-                    return false;
+                    // An associated dependency is only satisfied by the dispatcher, never by evaluation.
+                    return if ends_after_first { Ok(false) } else { Err(DepexError::InvalidAssociatedDependency) };
                 }
                 Opcode::Sor => {
-                    log::trace!("  {opcode:#x?}");
-                    if index != 0 {
-                        debug_assert!(false, "Invalid SOR not at start of depex.");
-                        return false;
-                    }
-                    return false;
+                    tracer(opcode, &stack);
+                    return if index == 0 { Ok(false) } else { Err(DepexError::MisplacedSor) };
                 }
                 Opcode::Push(guid, present) => {
-                    if *present {
-                        stack.push(true)
-                    } else {
-                        if let Some(guid) = guid_from_uuid(guid)
-                            && protocols.contains(&guid)
-                        {
-                            *present = true;
-                            stack.push(true);
-                            continue;
-                        }
-                        stack.push(false);
+                    if !*present && is_present(&from_uuid(guid)) {
+                        *present = true;
                     }
-                    log::trace!(
-                        "  {opcode:x?} => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    stack.push(*present);
+                    tracer(opcode, &stack);
                 }
                 Opcode::And => {
-                    let operator1 = stack.pop().unwrap_or(false);
-                    let operator2 = stack.pop().unwrap_or(false);
+                    let (operator1, operator2) = (pop(&mut stack)?, pop(&mut stack)?);
                     stack.push(operator1 && operator2);
-                    log::trace!(
-                        "  {opcode:x?}({operator1:?},{operator2:?}) => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    tracer(opcode, &stack);
                 }
                 Opcode::Or => {
-                    let operator1 = stack.pop().unwrap_or(false);
-                    let operator2 = stack.pop().unwrap_or(false);
+                    let (operator1, operator2) = (pop(&mut stack)?, pop(&mut stack)?);
                     stack.push(operator1 || operator2);
-                    log::trace!(
-                        "  {opcode:x?}({operator1:?},{operator2:?}) => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    tracer(opcode, &stack);
                 }
                 Opcode::Not => {
-                    let operator = stack.pop().unwrap_or(false);
+                    let operator = pop(&mut stack)?;
                     stack.push(!operator);
-                    log::trace!(
-                        "  {opcode:x?}({operator:?}) => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    tracer(opcode, &stack);
                 }
                 Opcode::True => {
                     stack.push(true);
-                    log::trace!(
-                        "  {opcode:x?} => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    tracer(opcode, &stack);
                 }
                 Opcode::False => {
                     stack.push(false);
-                    log::trace!(
-                        "  {opcode:x?} => {:?}, stack ->{:?}",
-                        stack.last(),
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
+                    tracer(opcode, &stack);
                 }
                 Opcode::End => {
-                    let operator = stack.pop().unwrap_or(false);
-                    log::trace!(
-                        "  {opcode:x?} => final result: {:?}, final stack ->{:?}",
-                        operator,
-                        stack.iter().rev().collect::<Vec<_>>()
-                    );
-                    return operator;
+                    tracer(opcode, &stack);
+                    if index != last {
+                        return Err(DepexError::TrailingOpcodes);
+                    }
+                    return end_result(&stack);
                 }
-                Opcode::Unknown => {
-                    debug_assert!(false, "Exiting early due to an unknown opcode.");
-                    return false;
+                Opcode::Unknown(byte) => {
+                    let byte = *byte;
+                    tracer(opcode, &stack);
+                    match mode {
+                        EvalMode::Lenient(UnknownOpcodePolicy::NotDispatchable) => {
+                            log::warn!("Depex contains an unknown opcode, it is not dispatchable.");
+                            return Ok(false);
+                        }
+                        EvalMode::Lenient(UnknownOpcodePolicy::Skip) => {
+                            log::warn!("Skipping an unknown opcode in the depex.")
+                        }
+                        EvalMode::Lenient(UnknownOpcodePolicy::Error) | EvalMode::Strict => {
+                            return Err(DepexError::UnknownOpcode(byte));
+                        }
+                    }
                 }
+                Opcode::Malformed { opcode: byte, len } => {
+                    let error = DepexError::MalformedOpcode { opcode: *byte, len: *len };
+                    tracer(opcode, &stack);
+                    return Err(error);
+                }
+            }
+        }
+        Err(DepexError::MissingEnd)
+    }
+
+    /// Validates the structure of the DEPEX expression without evaluating it.
+    ///
+    /// The following rules from the PI specification are enforced:
+    /// - `Before` and `After` must be the first opcode and may only be followed by `End`.
+    /// - `Sor` must be the first opcode and may be followed by a normal expression.
+    /// - The expression must not contain unknown or malformed opcodes.
+    /// - The expression must terminate with `End`, and no opcodes may follow it.
+    pub fn validate_structure(&self) -> Result<(), DepexError> {
+        let Some(first) = self.expression.first() else {
+            return Err(DepexError::Empty);
+        };
+
+        for (index, opcode) in self.expression.iter().enumerate() {
+            match opcode {
+                Opcode::Before(_) | Opcode::After(_) if index != 0 => return Err(DepexError::MisplacedBeforeAfter),
+                Opcode::Sor if index != 0 => return Err(DepexError::MisplacedSor),
+                Opcode::Unknown(byte) => return Err(DepexError::UnknownOpcode(*byte)),
                 Opcode::Malformed { opcode, len } => {
-                    log::error!("Opcode [0x{opcode:x?}] expects a guid, only has a length of: {len}");
-                    debug_assert!(
-                        false,
-                        "Exiting early because opcode [0x{opcode:x?}] expects a guid, only has a length of: {len}"
-                    );
-                    return false;
+                    return Err(DepexError::MalformedOpcode { opcode: *opcode, len: *len });
                 }
+                _ => (),
             }
         }
-        false
+
+        if matches!(first, Opcode::Before(_) | Opcode::After(_)) && self.expression[1..] != [Opcode::End] {
+            return Err(DepexError::InvalidAssociatedDependency);
+        }
+
+        match self.expression.iter().position(|opcode| *opcode == Opcode::End) {
+            None => return Err(DepexError::MissingEnd),
+            Some(end) if end != self.expression.len() - 1 => return Err(DepexError::TrailingOpcodes),
+            Some(_) => (),
+        }
+
+        Ok(())
     }
 
     /// If the depex expression is an associated dependency, it returns the associated dependency.
     pub fn is_associated(&self) -> Option<AssociatedDependency> {
         match self.expression.first() {
-            Some(Opcode::Before(uid)) => Some(AssociatedDependency::Before(guid_from_uuid(uid)?)),
-            Some(Opcode::After(uid)) => Some(AssociatedDependency::After(guid_from_uuid(uid)?)),
+            Some(Opcode::Before(uid)) => Some(AssociatedDependency::Before(from_uuid(uid))),
+            Some(Opcode::After(uid)) => Some(AssociatedDependency::After(from_uuid(uid))),
             _ => None,
         }
     }
 
+    /// Returns every GUID the expression pushes, in the order they are first seen, without evaluating it.
+    ///
+    /// Each GUID is returned once, whether or not its protocol is installed. The GUID of a `Before` or `After`
+    /// opcode is not included, see [`is_associated`](Self::is_associated).
+    pub fn required_guids(&self) -> Vec<efi::Guid> {
+        let mut guids = Vec::new();
+        for opcode in &self.expression {
+            if let Opcode::Push(uuid, _) = opcode {
+                let guid = from_uuid(uuid);
+                if !guids.contains(&guid) {
+                    guids.push(guid);
+                }
+            }
+        }
+        guids
+    }
+
+    /// Returns whether the expression contains no opcodes, as parsed from a zero-length DEPEX section.
+    ///
+    /// An empty expression evaluates to `false`. Callers dispatching drivers should treat it as if the driver had no
+    /// DEPEX section at all.
+    pub fn is_empty(&self) -> bool {
+        self.expression.is_empty()
+    }
+
     /// indicates that this is a "schedule on request" depex.
     pub fn is_sor(&self) -> bool {
         self.expression.first() == Some(&Opcode::Sor)
@@ -299,6 +520,42 @@ impl Depex {
             self.expression.remove(0);
         }
     }
+
+    /// Returns a displayable disassembly of the DEPEX expression, with one opcode per line.
+    ///
+    /// GUIDs are formatted as upper case registry strings, and unknown or malformed opcodes are shown in place so
+    /// that an invalid expression can still be inspected.
+    pub fn disassemble(&self) -> Disassembly<'_> {
+        Disassembly(&self.expression)
+    }
+}
+
+/// A disassembled DEPEX expression, created by [`Depex::disassemble`].
+pub struct Disassembly<'a>(&'a [Opcode]);
+
+impl fmt::Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, opcode) in self.0.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+            match opcode {
+                Opcode::Before(uuid) => write!(f, "BEFORE {uuid:X}")?,
+                Opcode::After(uuid) => write!(f, "AFTER {uuid:X}")?,
+                Opcode::Push(uuid, _) => write!(f, "PUSH {uuid:X}")?,
+                Opcode::And => f.write_str("AND")?,
+                Opcode::Or => f.write_str("OR")?,
+                Opcode::Not => f.write_str("NOT")?,
+                Opcode::True => f.write_str("TRUE")?,
+                Opcode::False => f.write_str("FALSE")?,
+                Opcode::End => f.write_str("END")?,
+                Opcode::Sor => f.write_str("SOR")?,
+                Opcode::Unknown(_) => f.write_str("UNKNOWN")?,
+                Opcode::Malformed { opcode, len } => write!(f, "MALFORMED {opcode:#04x} (payload length {len})")?,
+            }
+        }
+        Ok(())
+    }
 }
 
 struct DepexParser {
@@ -334,11 +591,40 @@ mod tests {
     use alloc::vec;
     use core::str::FromStr;
     use r_efi::efi;
-    use std::println;
+    use std::{println, sync::Mutex};
     use uuid::Uuid;
 
     use super::*;
 
+    /// The DEPEX of the BdsDxe driver.
+    const BDS_DXE_DEPEX: [u8; 144] = [
+        0x02, 0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02,
+        0x4E, 0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x02, 0x74,
+        0x69, 0xD9, 0x0F, 0xAA, 0x23, 0xDC, 0x4C, 0xB9, 0xCB, 0x98, 0xD1, 0x77, 0x50, 0x32, 0x2A, 0x02, 0x72, 0xC1,
+        0x9F, 0xEF, 0xB2, 0xA1, 0x93, 0x46, 0xB3, 0x27, 0x6D, 0x32, 0xFC, 0x41, 0x60, 0x42, 0x02, 0xD7, 0x72, 0x7E,
+        0x58, 0x50, 0xCC, 0x79, 0x4F, 0x82, 0x09, 0xCA, 0x29, 0x1F, 0xC1, 0xA1, 0x0F, 0x02, 0x88, 0xAC, 0xCF, 0x27,
+        0xCC, 0x46, 0xD4, 0x11, 0x9A, 0x38, 0x00, 0x90, 0x27, 0x3F, 0xC1, 0x4D, 0x02, 0x18, 0xF8, 0x41, 0x64, 0x62,
+        0x63, 0x44, 0xEB, 0x57, 0x00, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84,
+        0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x08,
+    ];
+
+    /// The DEPEX of the TcgMor driver.
+    const TCG_MOR_DEPEX: [u8; 108] = [
+        0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84, 0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x02,
+        0x18, 0xF8, 0x41, 0x64, 0x62, 0x63, 0x44, 0xEB, 0x57, 0x0, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0x6D,
+        0x79, 0x41, 0xF5, 0x2E, 0xA6, 0x54, 0x49, 0xA7, 0x75, 0x95, 0x84, 0xF6, 0x1B, 0x9C, 0xDD, 0x02, 0x6C, 0x76,
+        0x7F, 0x60, 0x55, 0x74, 0xBE, 0x42, 0x93, 0x0B, 0xE4, 0xD7, 0x6D, 0xB2, 0x72, 0x0F, 0x04, 0x03, 0x03, 0x02,
+        0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02, 0x4E,
+        0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x03, 0x03, 0x08,
+    ];
+
+    /// Held by tests that evaluate an unknown opcode with the default policy, or that change it.
+    static DEFAULT_POLICY_LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock_default_policy() -> std::sync::MutexGuard<'static, ()> {
+        DEFAULT_POLICY_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     #[test]
     fn malformed_opcodes_should_generate_correct_malformed_opcode_enum_variant() {
         // Verify "Before" opcode with no GUID
@@ -363,6 +649,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn opcode_from_empty_slice_should_be_unknown() {
+        assert_eq!(Opcode::from([].as_slice()), Opcode::Unknown(None));
+    }
+
     #[test]
     fn true_should_eval_true() {
         let mut depex = Depex::from(vec![0x06, 0x08]);
@@ -399,9 +690,9 @@ mod tests {
 
         assert_eq!(
             depex.is_associated(),
-            Some(AssociatedDependency::Before(
-                guid_from_uuid(&Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap()).unwrap()
-            ))
+            Some(AssociatedDependency::Before(from_uuid(
+                &Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap()
+            )))
         );
     }
 
@@ -413,9 +704,9 @@ mod tests {
 
         assert_eq!(
             depex.is_associated(),
-            Some(AssociatedDependency::After(
-                guid_from_uuid(&Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap()).unwrap()
-            ))
+            Some(AssociatedDependency::After(from_uuid(
+                &Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap()
+            )))
         );
     }
 
@@ -451,6 +742,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "Exiting early due to an unknown opcode.")]
     fn replacetrue_should_eval_false() {
+        let _lock = lock_default_policy();
         let mut depex = Depex::from(vec![0xFF, 0x08]);
         assert!(!depex.eval(&[]));
     }
@@ -458,14 +750,61 @@ mod tests {
     #[test]
     #[should_panic(expected = "Exiting early due to an unknown opcode.")]
     fn unknown_opcode_should_return_false() {
+        let _lock = lock_default_policy();
         let mut depex = Depex::from(vec![0xE0, 0x08]);
         assert!(!depex.eval(&[]));
     }
 
+    /// TRUE, a reserved opcode, END.
+    const RESERVED_OPCODE_DEPEX: [u8; 3] = [0x06, 0x0A, 0x08];
+
     #[test]
-    fn not_true_should_eval_false() {
-        let mut depex = Depex::from(vec![0x07, 0x06, 0x08]);
+    #[should_panic(expected = "Exiting early due to an unknown opcode.")]
+    fn unknown_opcode_error_policy_should_assert() {
+        let mut depex = Depex::from(RESERVED_OPCODE_DEPEX.as_slice());
+        depex.set_unknown_opcode_policy(UnknownOpcodePolicy::Error);
+        depex.eval(&[]);
+    }
+
+    #[test]
+    fn unknown_opcode_not_dispatchable_policy_should_eval_false() {
+        let mut depex = Depex::from(RESERVED_OPCODE_DEPEX.as_slice());
+        depex.set_unknown_opcode_policy(UnknownOpcodePolicy::NotDispatchable);
+        assert!(!depex.eval(&[]));
+    }
+
+    #[test]
+    fn unknown_opcode_skip_policy_should_eval_the_rest_of_the_expression() {
+        let mut depex = Depex::from(RESERVED_OPCODE_DEPEX.as_slice());
+        depex.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
         assert!(depex.eval(&[]));
+
+        // Skipping does not make an invalid expression valid.
+        assert_eq!(depex.try_eval(&[]), Err(DepexError::UnknownOpcode(Some(0x0A))));
+    }
+
+    #[test]
+    fn unknown_opcode_default_policy_should_apply_unless_overridden() {
+        let _lock = lock_default_policy();
+        assert_eq!(default_unknown_opcode_policy(), UnknownOpcodePolicy::Error);
+
+        set_default_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+        let mut depex = Depex::from(RESERVED_OPCODE_DEPEX.as_slice());
+        assert_eq!(depex.unknown_opcode_policy(), UnknownOpcodePolicy::Skip);
+        assert!(depex.eval(&[]));
+
+        set_default_unknown_opcode_policy(UnknownOpcodePolicy::NotDispatchable);
+        assert!(!depex.eval(&[]));
+        depex.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+        assert!(depex.eval(&[]));
+
+        set_default_unknown_opcode_policy(UnknownOpcodePolicy::Error);
+    }
+
+    #[test]
+    fn not_true_should_eval_false() {
+        let mut depex = Depex::from(vec![0x06, 0x05, 0x08]);
+        assert!(!depex.eval(&[]));
     }
 
     #[test]
@@ -496,22 +835,21 @@ mod tests {
     ///   END
     fn all_protocols_installed_and_should_eval_true() {
         let efi_pcd_prot_uuid = Uuid::from_str("13a3f0f6-264a-3ef0-f2e0-dec512342f34").unwrap();
-        let efi_pcd_prot_guid: efi::Guid = guid_from_uuid(&efi_pcd_prot_uuid).unwrap();
+        let efi_pcd_prot_guid: efi::Guid = from_uuid(&efi_pcd_prot_uuid);
         let efi_device_path_utilities_prot_uuid = Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap();
-        let efi_device_path_utilities_prot_guid: efi::Guid =
-            guid_from_uuid(&efi_device_path_utilities_prot_uuid).unwrap();
+        let efi_device_path_utilities_prot_guid: efi::Guid = from_uuid(&efi_device_path_utilities_prot_uuid);
         let efi_hii_string_prot_uuid = Uuid::from_str("0fd96974-23aa-4cdc-b9cb-98d17750322a").unwrap();
-        let efi_hii_string_prot_guid: efi::Guid = guid_from_uuid(&efi_hii_string_prot_uuid).unwrap();
+        let efi_hii_string_prot_guid: efi::Guid = from_uuid(&efi_hii_string_prot_uuid);
         let efi_hii_db_prot_uuid = Uuid::from_str("ef9fc172-a1b2-4693-b327-6d32fc416042").unwrap();
-        let efi_hii_db_prot_guid: efi::Guid = guid_from_uuid(&efi_hii_db_prot_uuid).unwrap();
+        let efi_hii_db_prot_guid: efi::Guid = from_uuid(&efi_hii_db_prot_uuid);
         let efi_hii_config_routing_prot_uuid = Uuid::from_str("587e72d7-cc50-4f79-8209-ca291fc1a10f").unwrap();
-        let efi_hii_config_routing_prot_guid: efi::Guid = guid_from_uuid(&efi_hii_config_routing_prot_uuid).unwrap();
+        let efi_hii_config_routing_prot_guid: efi::Guid = from_uuid(&efi_hii_config_routing_prot_uuid);
         let efi_reset_arch_prot_uuid = Uuid::from_str("27cfac88-46cc-11d4-9a38-0090273fc14d").unwrap();
-        let efi_reset_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_reset_arch_prot_uuid).unwrap();
+        let efi_reset_arch_prot_guid: efi::Guid = from_uuid(&efi_reset_arch_prot_uuid);
         let efi_var_write_arch_prot_uuid = Uuid::from_str("6441f818-6362-eb44-5700-7dba31dd2453").unwrap();
-        let efi_var_write_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_write_arch_prot_uuid).unwrap();
+        let efi_var_write_arch_prot_guid: efi::Guid = from_uuid(&efi_var_write_arch_prot_uuid);
         let efi_var_arch_prot_uuid = Uuid::from_str("1e5668e2-8481-11d4-bcf1-0080c73c8881").unwrap();
-        let efi_var_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_arch_prot_uuid).unwrap();
+        let efi_var_arch_prot_guid: efi::Guid = from_uuid(&efi_var_arch_prot_uuid);
 
         let protocols = [
             efi_pcd_prot_guid,
@@ -526,16 +864,7 @@ mod tests {
 
         println!("Testing DEPEX for BdsDxe DXE driver...\n");
 
-        let expression: &[u8] = &[
-            0x02, 0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02,
-            0x4E, 0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x02, 0x74,
-            0x69, 0xD9, 0x0F, 0xAA, 0x23, 0xDC, 0x4C, 0xB9, 0xCB, 0x98, 0xD1, 0x77, 0x50, 0x32, 0x2A, 0x02, 0x72, 0xC1,
-            0x9F, 0xEF, 0xB2, 0xA1, 0x93, 0x46, 0xB3, 0x27, 0x6D, 0x32, 0xFC, 0x41, 0x60, 0x42, 0x02, 0xD7, 0x72, 0x7E,
-            0x58, 0x50, 0xCC, 0x79, 0x4F, 0x82, 0x09, 0xCA, 0x29, 0x1F, 0xC1, 0xA1, 0x0F, 0x02, 0x88, 0xAC, 0xCF, 0x27,
-            0xCC, 0x46, 0xD4, 0x11, 0x9A, 0x38, 0x00, 0x90, 0x27, 0x3F, 0xC1, 0x4D, 0x02, 0x18, 0xF8, 0x41, 0x64, 0x62,
-            0x63, 0x44, 0xEB, 0x57, 0x00, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84,
-            0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x08,
-        ];
+        let expression: &[u8] = &BDS_DXE_DEPEX;
         let mut depex = Depex::from(expression.to_vec());
 
         assert!(depex.eval(&protocols));
@@ -559,18 +888,17 @@ mod tests {
     ///   END
     fn all_protocols_installed_or_and_should_eval_true() {
         let efi_var_arch_prot_uuid = Uuid::from_str("1e5668e2-8481-11d4-bcf1-0080c73c8881").unwrap();
-        let efi_var_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_arch_prot_uuid).unwrap();
+        let efi_var_arch_prot_guid: efi::Guid = from_uuid(&efi_var_arch_prot_uuid);
         let efi_var_write_arch_prot_uuid = Uuid::from_str("6441f818-6362-eb44-5700-7dba31dd2453").unwrap();
-        let efi_var_write_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_write_arch_prot_uuid).unwrap();
+        let efi_var_write_arch_prot_guid: efi::Guid = from_uuid(&efi_var_write_arch_prot_uuid);
         let efi_tcg_prot_uuid = Uuid::from_str("f541796d-a62e-4954-a775-9584f61b9cdd").unwrap();
-        let efi_tcg_prot_guid: efi::Guid = guid_from_uuid(&efi_tcg_prot_uuid).unwrap();
+        let efi_tcg_prot_guid: efi::Guid = from_uuid(&efi_tcg_prot_uuid);
         let efi_tree_prot_uuid = Uuid::from_str("607f766c-7455-42be-930b-e4d76db2720f").unwrap();
-        let efi_tree_prot_guid: efi::Guid = guid_from_uuid(&efi_tree_prot_uuid).unwrap();
+        let efi_tree_prot_guid: efi::Guid = from_uuid(&efi_tree_prot_uuid);
         let efi_pcd_prot_uuid = Uuid::from_str("13a3f0f6-264a-3ef0-f2e0-dec512342f34").unwrap();
-        let efi_pcd_prot_guid: efi::Guid = guid_from_uuid(&efi_pcd_prot_uuid).unwrap();
+        let efi_pcd_prot_guid: efi::Guid = from_uuid(&efi_pcd_prot_uuid);
         let efi_device_path_utilities_prot_uuid = Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap();
-        let efi_device_path_utilities_prot_guid: efi::Guid =
-            guid_from_uuid(&efi_device_path_utilities_prot_uuid).unwrap();
+        let efi_device_path_utilities_prot_guid: efi::Guid = from_uuid(&efi_device_path_utilities_prot_uuid);
 
         let protocols = [
             efi_var_arch_prot_guid,
@@ -583,14 +911,7 @@ mod tests {
 
         println!("Testing DEPEX for TcgMor DXE driver...\n");
 
-        let expression: &[u8] = &[
-            0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84, 0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x02,
-            0x18, 0xF8, 0x41, 0x64, 0x62, 0x63, 0x44, 0xEB, 0x57, 0x0, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0x6D,
-            0x79, 0x41, 0xF5, 0x2E, 0xA6, 0x54, 0x49, 0xA7, 0x75, 0x95, 0x84, 0xF6, 0x1B, 0x9C, 0xDD, 0x02, 0x6C, 0x76,
-            0x7F, 0x60, 0x55, 0x74, 0xBE, 0x42, 0x93, 0x0B, 0xE4, 0xD7, 0x6D, 0xB2, 0x72, 0x0F, 0x04, 0x03, 0x03, 0x02,
-            0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02, 0x4E,
-            0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x03, 0x03, 0x08,
-        ];
+        let expression: &[u8] = &TCG_MOR_DEPEX;
         let mut depex = Depex::from(expression.to_vec());
 
         assert!(depex.eval(&protocols));
@@ -612,18 +933,17 @@ mod tests {
     ///   END
     fn opcode_list_to_depex_should_work() {
         let efi_var_arch_prot_uuid = Uuid::from_str("1e5668e2-8481-11d4-bcf1-0080c73c8881").unwrap();
-        let efi_var_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_arch_prot_uuid).unwrap();
+        let efi_var_arch_prot_guid: efi::Guid = from_uuid(&efi_var_arch_prot_uuid);
         let efi_var_write_arch_prot_uuid = Uuid::from_str("6441f818-6362-eb44-5700-7dba31dd2453").unwrap();
-        let efi_var_write_arch_prot_guid: efi::Guid = guid_from_uuid(&efi_var_write_arch_prot_uuid).unwrap();
+        let efi_var_write_arch_prot_guid: efi::Guid = from_uuid(&efi_var_write_arch_prot_uuid);
         let efi_tcg_prot_uuid = Uuid::from_str("f541796d-a62e-4954-a775-9584f61b9cdd").unwrap();
-        let efi_tcg_prot_guid: efi::Guid = guid_from_uuid(&efi_tcg_prot_uuid).unwrap();
+        let efi_tcg_prot_guid: efi::Guid = from_uuid(&efi_tcg_prot_uuid);
         let efi_tree_prot_uuid = Uuid::from_str("607f766c-7455-42be-930b-e4d76db2720f").unwrap();
-        let efi_tree_prot_guid: efi::Guid = guid_from_uuid(&efi_tree_prot_uuid).unwrap();
+        let efi_tree_prot_guid: efi::Guid = from_uuid(&efi_tree_prot_uuid);
         let efi_pcd_prot_uuid = Uuid::from_str("13a3f0f6-264a-3ef0-f2e0-dec512342f34").unwrap();
-        let efi_pcd_prot_guid: efi::Guid = guid_from_uuid(&efi_pcd_prot_uuid).unwrap();
+        let efi_pcd_prot_guid: efi::Guid = from_uuid(&efi_pcd_prot_uuid);
         let efi_device_path_utilities_prot_uuid = Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap();
-        let efi_device_path_utilities_prot_guid: efi::Guid =
-            guid_from_uuid(&efi_device_path_utilities_prot_uuid).unwrap();
+        let efi_device_path_utilities_prot_guid: efi::Guid = from_uuid(&efi_device_path_utilities_prot_uuid);
 
         let protocols = [
             efi_var_arch_prot_guid,
@@ -654,6 +974,65 @@ mod tests {
         assert!(depex.eval(&protocols));
     }
 
+    #[test]
+    fn required_guids_should_list_pushed_guids_in_first_seen_order() {
+        let guid = |uuid: &str| from_uuid(&Uuid::from_str(uuid).unwrap());
+        let pcd = guid("13a3f0f6-264a-3ef0-f2e0-dec512342f34");
+        let device_path_utilities = guid("0379be4e-d706-437d-b037-edb82fb772a4");
+        let variable_arch = guid("1e5668e2-8481-11d4-bcf1-0080c73c8881");
+        let variable_write_arch = guid("6441f818-6362-eb44-5700-7dba31dd2453");
+
+        assert_eq!(
+            Depex::from(BDS_DXE_DEPEX.as_slice()).required_guids(),
+            [
+                pcd,
+                device_path_utilities,
+                guid("0fd96974-23aa-4cdc-b9cb-98d17750322a"),
+                guid("ef9fc172-a1b2-4693-b327-6d32fc416042"),
+                guid("587e72d7-cc50-4f79-8209-ca291fc1a10f"),
+                guid("27cfac88-46cc-11d4-9a38-0090273fc14d"),
+                variable_write_arch,
+                variable_arch,
+            ]
+        );
+
+        // Requiring a GUID does not depend on whether its protocol is installed.
+        let mut depex = Depex::from(TCG_MOR_DEPEX.as_slice());
+        let expected = [
+            variable_arch,
+            variable_write_arch,
+            guid("f541796d-a62e-4954-a775-9584f61b9cdd"),
+            guid("607f766c-7455-42be-930b-e4d76db2720f"),
+            pcd,
+            device_path_utilities,
+        ];
+        assert_eq!(depex.required_guids(), expected);
+        assert!(!depex.eval(&[variable_arch]));
+        assert_eq!(depex.required_guids(), expected);
+    }
+
+    #[test]
+    fn required_guids_should_skip_associated_and_repeated_guids() {
+        let protocol = Uuid::from_str("26BACCB1-6F42-11D4-BCE7-0080C73C8881").unwrap();
+        let other = Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap();
+
+        assert!(Depex::from([Opcode::Before(protocol), Opcode::End].as_slice()).required_guids().is_empty());
+        assert!(Depex::from([Opcode::After(protocol), Opcode::End].as_slice()).required_guids().is_empty());
+
+        let depex = Depex::from(
+            [
+                Opcode::Push(protocol, false),
+                Opcode::Push(other, false),
+                Opcode::Push(protocol, true),
+                Opcode::And,
+                Opcode::And,
+                Opcode::End,
+            ]
+            .as_slice(),
+        );
+        assert_eq!(depex.required_guids(), [from_uuid(&protocol), from_uuid(&other)]);
+    }
+
     #[test]
     fn guid_to_uuid_conversion_should_produce_correct_bytes() {
         let device_path_protocol_guid_bytes: &[u8] =
@@ -662,8 +1041,8 @@ mod tests {
         let uuid = uuid_from_slice(Some(device_path_protocol_guid_bytes)).unwrap();
         assert_eq!(uuid, uuid::Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap());
 
-        let guid = guid_from_uuid(&uuid);
-        assert_eq!(guid.unwrap().as_bytes(), device_path_protocol_guid_bytes);
+        let guid = from_uuid(&uuid);
+        assert_eq!(guid.as_bytes(), device_path_protocol_guid_bytes);
     }
 
     #[test]
@@ -731,4 +1110,411 @@ mod tests {
         let mut depex = Depex::from(opcodes.as_slice());
         depex.eval(&[]);
     }
+
+    #[test]
+    fn validate_structure_should_accept_valid_expressions() {
+        let guid = Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap();
+        let valid: &[&[Opcode]] = &[
+            &[Opcode::True, Opcode::End],
+            &[Opcode::Push(guid, false), Opcode::Not, Opcode::End],
+            &[Opcode::Before(guid), Opcode::End],
+            &[Opcode::After(guid), Opcode::End],
+            &[Opcode::Sor, Opcode::End],
+            &[Opcode::Sor, Opcode::Push(guid, false), Opcode::True, Opcode::And, Opcode::End],
+        ];
+
+        for expression in valid {
+            assert_eq!(Depex::from(*expression).validate_structure(), Ok(()), "{expression:?}");
+        }
+    }
+
+    #[test]
+    fn validate_structure_should_reject_empty_expression() {
+        assert_eq!(Depex::from(vec![]).validate_structure(), Err(DepexError::Empty));
+    }
+
+    #[test]
+    fn validate_structure_should_reject_misplaced_before_after() {
+        let guid = Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap();
+        let invalid: &[&[Opcode]] = &[
+            &[Opcode::True, Opcode::Before(guid), Opcode::End],
+            &[Opcode::True, Opcode::After(guid), Opcode::End],
+            &[Opcode::Sor, Opcode::Before(guid), Opcode::End],
+            &[Opcode::Sor, Opcode::After(guid), Opcode::End],
+            &[Opcode::Before(guid), Opcode::After(guid), Opcode::End],
+        ];
+
+        for expression in invalid {
+            assert_eq!(
+                Depex::from(*expression).validate_structure(),
+                Err(DepexError::MisplacedBeforeAfter),
+                "{expression:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_structure_should_reject_before_after_not_followed_by_end() {
+        let guid = Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap();
+        let invalid: &[&[Opcode]] = &[
+            &[Opcode::Before(guid)],
+            &[Opcode::After(guid)],
+            &[Opcode::Before(guid), Opcode::And],
+            &[Opcode::After(guid), Opcode::True, Opcode::End],
+            &[Opcode::Before(guid), Opcode::End, Opcode::End],
+        ];
+
+        for expression in invalid {
+            assert_eq!(
+                Depex::from(*expression).validate_structure(),
+                Err(DepexError::InvalidAssociatedDependency),
+                "{expression:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_structure_should_reject_misplaced_sor() {
+        let guid = Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap();
+        let invalid: &[&[Opcode]] = &[
+            &[Opcode::True, Opcode::Sor, Opcode::End],
+            &[Opcode::Sor, Opcode::Sor, Opcode::End],
+            &[Opcode::Before(guid), Opcode::Sor, Opcode::End],
+        ];
+
+        for expression in invalid {
+            assert_eq!(Depex::from(*expression).validate_structure(), Err(DepexError::MisplacedSor), "{expression:?}");
+        }
+    }
+
+    #[test]
+    fn validate_structure_should_reject_missing_end() {
+        assert_eq!(Depex::from(vec![0x06]).validate_structure(), Err(DepexError::MissingEnd));
+        assert_eq!(Depex::from(vec![0x09, 0x06]).validate_structure(), Err(DepexError::MissingEnd));
+    }
+
+    #[test]
+    fn validate_structure_should_reject_unknown_and_malformed_opcodes() {
+        assert_eq!(Depex::from(vec![0xE0, 0x08]).validate_structure(), Err(DepexError::UnknownOpcode(Some(0xE0))));
+        assert_eq!(
+            Depex::from(vec![0x02, 0x01, 0x02, 0x03]).validate_structure(),
+            Err(DepexError::MalformedOpcode { opcode: 0x02, len: 3 })
+        );
+    }
+
+    #[test]
+    fn eval_checked_should_match_eval_for_valid_expressions() {
+        let efi_tcg_prot_guid = from_uuid(&Uuid::from_str("f541796d-a62e-4954-a775-9584f61b9cdd").unwrap());
+        let mut depex = Depex::from(TCG_MOR_DEPEX.as_slice());
+        assert_eq!(depex.eval_checked(&[]), Ok(false));
+        assert_eq!(depex.eval_checked(&[efi_tcg_prot_guid]), Ok(false));
+
+        assert_eq!(Depex::from(vec![0x06, 0x08]).eval_checked(&[]), Ok(true));
+        assert_eq!(Depex::from(vec![0x07, 0x05, 0x08]).eval_checked(&[]), Ok(true));
+
+        let mut depex = Depex::from(vec![0x09, 0x06, 0x08]);
+        assert_eq!(depex.eval_checked(&[]), Ok(false));
+        depex.schedule();
+        assert_eq!(depex.eval_checked(&[]), Ok(true));
+    }
+
+    #[test]
+    fn eval_checked_should_report_unknown_opcodes() {
+        assert_eq!(Depex::from(vec![0xFF, 0x08]).eval_checked(&[]), Err(DepexError::UnknownOpcode(Some(0xFF))));
+        assert_eq!(Depex::from(vec![0xE0, 0x08]).eval_checked(&[]), Err(DepexError::UnknownOpcode(Some(0xE0))));
+
+        let mut depex = Depex::from(RESERVED_OPCODE_DEPEX.as_slice());
+        depex.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+        assert_eq!(depex.eval_checked(&[]), Err(DepexError::UnknownOpcode(Some(0x0A))));
+    }
+
+    #[test]
+    fn eval_checked_should_report_malformed_opcodes() {
+        assert_eq!(
+            Depex::from(vec![0x00]).eval_checked(&[]),
+            Err(DepexError::MalformedOpcode { opcode: 0x00, len: 0 })
+        );
+        assert_eq!(
+            Depex::from(vec![0x06, 0x02, 0x01, 0x02, 0x03]).eval_checked(&[]),
+            Err(DepexError::MalformedOpcode { opcode: 0x02, len: 3 })
+        );
+    }
+
+    #[test]
+    fn eval_checked_should_report_misplaced_sor_before_and_after() {
+        let guid = Uuid::from_str("76b6bdfa-2acd-4462-9e3f-cb58c969d937").unwrap();
+        assert_eq!(Depex::from(vec![0x06, 0x09, 0x08]).eval_checked(&[]), Err(DepexError::MisplacedSor));
+
+        let misplaced: &[&[Opcode]] =
+            &[&[Opcode::True, Opcode::Before(guid), Opcode::End], &[Opcode::True, Opcode::After(guid), Opcode::End]];
+        for expression in misplaced {
+            assert_eq!(Depex::from(*expression).eval_checked(&[]), Err(DepexError::MisplacedBeforeAfter));
+        }
+
+        let invalid: &[&[Opcode]] = &[&[Opcode::Before(guid)], &[Opcode::After(guid), Opcode::And, Opcode::End]];
+        for expression in invalid {
+            assert_eq!(Depex::from(*expression).eval_checked(&[]), Err(DepexError::InvalidAssociatedDependency));
+        }
+    }
+
+    #[test]
+    fn eval_checked_should_report_stack_underflow_and_missing_end() {
+        assert_eq!(Depex::from(vec![]).eval_checked(&[]), Err(DepexError::Empty));
+        assert_eq!(Depex::from(vec![0x06, 0x03, 0x08]).eval_checked(&[]), Err(DepexError::StackUnderflow));
+        assert_eq!(Depex::from(vec![0x05, 0x08]).eval_checked(&[]), Err(DepexError::StackUnderflow));
+        assert_eq!(Depex::from(vec![0x08]).eval_checked(&[]), Err(DepexError::StackUnderflow));
+        assert_eq!(Depex::from(vec![0x06]).eval_checked(&[]), Err(DepexError::MissingEnd));
+    }
+
+    #[test]
+    fn eval_checked_should_match_try_eval_for_malformed_expressions() {
+        let malformed: &[&[u8]] = &[
+            &[],
+            &[0x00],
+            &[0xFF, 0x08],
+            &[0x06, 0x09, 0x08],
+            &[0x06, 0x03, 0x08],
+            &[0x06],
+            &[0x06, 0x06, 0x08],
+            &[0x06, 0x08, 0x06],
+            &[0x06, 0x08, 0xFF],
+            &[0x06, 0x02, 0x01, 0x02, 0x03],
+        ];
+        for expression in malformed {
+            let expected = Depex::from(*expression).try_eval(&[]);
+            assert!(expected.is_err(), "{expression:x?}");
+            assert_eq!(Depex::from(*expression).eval_checked(&[]), expected, "{expression:x?}");
+        }
+    }
+
+    #[test]
+    fn overfull_stack_at_end_should_be_rejected() {
+        let overfull: &[&[u8]] = &[&[0x06, 0x06, 0x08], &[0x07, 0x06, 0x06, 0x03, 0x08]];
+        for expression in overfull {
+            assert_eq!(Depex::from(*expression).try_eval(&[]), Err(DepexError::StackImbalance { remaining: 2 }));
+            assert_eq!(Depex::from(*expression).eval_checked(&[]), Err(DepexError::StackImbalance { remaining: 2 }));
+        }
+    }
+
+    #[test]
+    fn overfull_stack_at_end_should_eval_false() {
+        let mut depex = Depex::from(vec![0x06, 0x06, 0x08]);
+        assert!(!depex.eval(&[]));
+    }
+
+    #[test]
+    fn opcodes_after_end_should_be_rejected() {
+        let trailing: &[&[u8]] = &[&[0x06, 0x08, 0x06], &[0x06, 0x08, 0x08], &[0x07, 0x05, 0x08, 0x07]];
+        for expression in trailing {
+            let mut depex = Depex::from(*expression);
+            assert_eq!(depex.validate_structure(), Err(DepexError::TrailingOpcodes), "{expression:x?}");
+            assert_eq!(depex.try_eval(&[]), Err(DepexError::TrailingOpcodes), "{expression:x?}");
+            assert_eq!(depex.eval_checked(&[]), Err(DepexError::TrailingOpcodes), "{expression:x?}");
+        }
+    }
+
+    #[test]
+    fn opcodes_after_end_should_eval_false() {
+        let mut depex = Depex::from(vec![0x06, 0x08, 0x06]);
+        assert!(!depex.eval(&[]));
+    }
+
+    #[test]
+    fn eval_with_closure_should_match_slice_based_eval() {
+        let efi_var_arch_prot_guid = from_uuid(&Uuid::from_str("1e5668e2-8481-11d4-bcf1-0080c73c8881").unwrap());
+        let efi_var_write_arch_prot_guid = from_uuid(&Uuid::from_str("6441f818-6362-eb44-5700-7dba31dd2453").unwrap());
+        let efi_tcg_prot_guid = from_uuid(&Uuid::from_str("f541796d-a62e-4954-a775-9584f61b9cdd").unwrap());
+        let efi_pcd_prot_guid = from_uuid(&Uuid::from_str("13a3f0f6-264a-3ef0-f2e0-dec512342f34").unwrap());
+        let efi_device_path_utilities_prot_guid =
+            from_uuid(&Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap());
+
+        // TcgMor DXE driver expression from all_protocols_installed_or_and_should_eval_true.
+        let expression: &[u8] = &[
+            0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84, 0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x02,
+            0x18, 0xF8, 0x41, 0x64, 0x62, 0x63, 0x44, 0xEB, 0x57, 0x0, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0x6D,
+            0x79, 0x41, 0xF5, 0x2E, 0xA6, 0x54, 0x49, 0xA7, 0x75, 0x95, 0x84, 0xF6, 0x1B, 0x9C, 0xDD, 0x02, 0x6C, 0x76,
+            0x7F, 0x60, 0x55, 0x74, 0xBE, 0x42, 0x93, 0x0B, 0xE4, 0xD7, 0x6D, 0xB2, 0x72, 0x0F, 0x04, 0x03, 0x03, 0x02,
+            0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02, 0x4E,
+            0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x03, 0x03, 0x08,
+        ];
+
+        let protocol_sets: &[&[efi::Guid]] = &[
+            &[],
+            &[efi_var_arch_prot_guid, efi_var_write_arch_prot_guid, efi_tcg_prot_guid],
+            &[
+                efi_var_arch_prot_guid,
+                efi_var_write_arch_prot_guid,
+                efi_tcg_prot_guid,
+                efi_pcd_prot_guid,
+                efi_device_path_utilities_prot_guid,
+            ],
+        ];
+
+        for protocols in protocol_sets {
+            let protocol_set: std::collections::HashSet<efi::Guid> = protocols.iter().copied().collect();
+
+            let slice_result = Depex::from(expression).eval(protocols);
+            let closure_result = Depex::from(expression).eval_with(|guid| protocol_set.contains(guid));
+            assert_eq!(slice_result, closure_result);
+        }
+
+        let all_protocols = protocol_sets.last().unwrap();
+        let protocol_set: std::collections::HashSet<efi::Guid> = all_protocols.iter().copied().collect();
+        assert!(Depex::from(expression).eval_with(|guid| protocol_set.contains(guid)));
+    }
+
+    #[test]
+    fn eval_traced_should_capture_each_opcode_and_stack() {
+        let protocols = [
+            from_uuid(&Uuid::from_str("1e5668e2-8481-11d4-bcf1-0080c73c8881").unwrap()),
+            from_uuid(&Uuid::from_str("6441f818-6362-eb44-5700-7dba31dd2453").unwrap()),
+            from_uuid(&Uuid::from_str("f541796d-a62e-4954-a775-9584f61b9cdd").unwrap()),
+            from_uuid(&Uuid::from_str("13a3f0f6-264a-3ef0-f2e0-dec512342f34").unwrap()),
+            from_uuid(&Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap()),
+        ];
+
+        // TcgMor DXE driver expression from all_protocols_installed_or_and_should_eval_true, with EfiTrEEProtocolGuid
+        // not installed.
+        let expression: &[u8] = &[
+            0x02, 0xE2, 0x68, 0x56, 0x1E, 0x81, 0x84, 0xD4, 0x11, 0xBC, 0xF1, 0x00, 0x80, 0xC7, 0x3C, 0x88, 0x81, 0x02,
+            0x18, 0xF8, 0x41, 0x64, 0x62, 0x63, 0x44, 0xEB, 0x57, 0x0, 0x7D, 0xBA, 0x31, 0xDD, 0x24, 0x53, 0x02, 0x6D,
+            0x79, 0x41, 0xF5, 0x2E, 0xA6, 0x54, 0x49, 0xA7, 0x75, 0x95, 0x84, 0xF6, 0x1B, 0x9C, 0xDD, 0x02, 0x6C, 0x76,
+            0x7F, 0x60, 0x55, 0x74, 0xBE, 0x42, 0x93, 0x0B, 0xE4, 0xD7, 0x6D, 0xB2, 0x72, 0x0F, 0x04, 0x03, 0x03, 0x02,
+            0xF6, 0xF0, 0xA3, 0x13, 0x4A, 0x26, 0xF0, 0x3E, 0xF2, 0xE0, 0xDE, 0xC5, 0x12, 0x34, 0x2F, 0x34, 0x02, 0x4E,
+            0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4, 0x03, 0x03, 0x08,
+        ];
+
+        let mut trace = Vec::new();
+        let result = Depex::from(expression).eval_traced(&protocols, &mut |opcode, stack| {
+            let name = match opcode {
+                Opcode::Push(..) => "PUSH",
+                Opcode::And => "AND",
+                Opcode::Or => "OR",
+                Opcode::End => "END",
+                _ => "OTHER",
+            };
+            trace.push((name, stack.to_vec()));
+        });
+
+        assert!(result);
+        assert_eq!(
+            trace,
+            [
+                ("PUSH", vec![true]),
+                ("PUSH", vec![true, true]),
+                ("PUSH", vec![true, true, true]),
+                ("PUSH", vec![true, true, true, false]),
+                ("OR", vec![true, true, true]),
+                ("AND", vec![true, true]),
+                ("AND", vec![true]),
+                ("PUSH", vec![true, true]),
+                ("PUSH", vec![true, true, true]),
+                ("AND", vec![true, true]),
+                ("AND", vec![true]),
+                ("END", vec![true]),
+            ]
+        );
+    }
+
+    #[test]
+    fn try_eval_should_evaluate_valid_expressions() {
+        let protocol = Uuid::from_str("0379be4e-d706-437d-b037-edb82fb772a4").unwrap();
+        let protocols = [from_uuid(&protocol)];
+
+        let mut push = vec![0x02];
+        push.extend_from_slice(&protocol.to_bytes_le());
+        let expression = [push.as_slice(), &[0x06, 0x03, 0x05, 0x07, 0x04, 0x08]].concat();
+
+        // NOT (protocol AND TRUE) OR FALSE
+        assert_eq!(parse_and_eval(&expression, &protocols), Ok(false));
+        assert_eq!(parse_and_eval(&expression, &[]), Ok(true));
+        assert_eq!(parse_and_eval(&[0x09, 0x06, 0x08], &[]), Ok(false));
+        assert_eq!(parse_and_eval(&[push.as_slice(), &[0x08]].concat(), &[]), Ok(false));
+    }
+
+    #[test]
+    fn try_eval_should_reject_stack_underflow() {
+        for expression in
+            [&[0x08][..], &[0x03, 0x08], &[0x06, 0x04, 0x08], &[0x05, 0x08], &[0x06, 0x05, 0x05, 0x03, 0x08]]
+        {
+            assert_eq!(parse_and_eval(expression, &[]), Err(DepexError::StackUnderflow), "{expression:x?}");
+        }
+    }
+
+    #[test]
+    fn parse_and_eval_should_reject_adversarial_inputs() {
+        let device_path_protocol_guid: &[u8] =
+            &[0x4E, 0xBE, 0x79, 0x03, 0x06, 0xD7, 0x7D, 0x43, 0xB0, 0x37, 0xED, 0xB8, 0x2F, 0xB7, 0x72, 0xA4];
+
+        // Truncated GUIDs for each opcode carrying one.
+        for opcode in [0x00, 0x01, 0x02] {
+            for len in 0..GUID_SIZE {
+                let expression = [&[opcode][..], &device_path_protocol_guid[..len]].concat();
+                assert_eq!(
+                    parse_and_eval(&expression, &[]),
+                    Err(DepexError::MalformedOpcode { opcode, len }),
+                    "{expression:x?}"
+                );
+            }
+        }
+
+        // A giant expression that never terminates.
+        let giant = vec![0x06; 0x10_0000];
+        assert_eq!(parse_and_eval(&giant, &[]), Err(DepexError::MissingEnd));
+
+        // A giant expression of operators without operands.
+        let giant = [vec![0x03; 0x10_0000], vec![0x08]].concat();
+        assert_eq!(parse_and_eval(&giant, &[]), Err(DepexError::StackUnderflow));
+
+        // Every opcode byte, in order, in reverse, and with an END appended.
+        let noise: Vec<u8> = (0..=u8::MAX).collect();
+        assert!(parse_and_eval(&noise, &[]).is_err());
+        assert!(parse_and_eval(&noise.iter().rev().copied().collect::<Vec<_>>(), &[]).is_err());
+        assert!(parse_and_eval(&[noise.as_slice(), &[0x08]].concat(), &[]).is_err());
+        assert_eq!(parse_and_eval(&[], &[]), Err(DepexError::Empty));
+    }
+
+    #[test]
+    fn parse_and_eval_should_not_panic_on_random_inputs() {
+        // A simple linear congruential generator, so the inputs are reproducible.
+        let mut state: u32 = 0x1234_5678;
+        let mut next = || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        };
+
+        for _ in 0..0x1000 {
+            let len = next() as usize % 64;
+            // Bias towards the valid opcode range, so the evaluator is exercised as well as the parser.
+            let expression: Vec<u8> = (0..len).map(|_| next() % 0x0C).collect();
+            let _ = parse_and_eval(&expression, &[]);
+        }
+    }
+
+    #[test]
+    fn disassemble_should_list_one_opcode_per_line() {
+        let protocol = Uuid::from_str("26BACCB1-6F42-11D4-BCE7-0080C73C8881").unwrap();
+        let depex = Depex::from(
+            [Opcode::Sor, Opcode::Push(protocol, false), Opcode::Not, Opcode::True, Opcode::Or, Opcode::End].as_slice(),
+        );
+        assert_eq!(
+            std::format!("{}", depex.disassemble()),
+            "SOR\nPUSH 26BACCB1-6F42-11D4-BCE7-0080C73C8881\nNOT\nTRUE\nOR\nEND"
+        );
+
+        let depex = Depex::from(vec![0x02]);
+        assert_eq!(std::format!("{}", depex.disassemble()), "MALFORMED 0x02 (payload length 0)");
+        let depex = Depex::from(vec![0x0F, 0x08]);
+        assert_eq!(std::format!("{}", depex.disassemble()), "UNKNOWN\nEND");
+    }
+
+    #[test]
+    fn empty_depex_should_be_empty_and_eval_false() {
+        let mut depex = Depex::from(Vec::new());
+        assert!(depex.is_empty());
+        assert!(!depex.eval(&[]));
+        assert_eq!(depex.try_eval(&[]), Err(DepexError::Empty));
+
+        assert!(!Depex::from(vec![0x08]).is_empty());
+    }
 }
@@ -94,6 +94,118 @@ pub fn device_path_node_count(
     Ok((node_count, dev_path_size))
 }
 
+/// Reasons a device path fails [`validate_device_path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevicePathError {
+    /// The device path pointer is null.
+    NullPointer,
+    /// A non-end node at the given byte offset has a length of zero.
+    ZeroLengthNode {
+        /// Byte offset of the node from the start of the device path.
+        offset: usize,
+    },
+    /// A node at the given byte offset has a length smaller than the node header.
+    NodeTooShort {
+        /// Byte offset of the node from the start of the device path.
+        offset: usize,
+    },
+    /// An end-entire node at the given byte offset does not have the length of an end node.
+    InvalidEndNode {
+        /// Byte offset of the node from the start of the device path.
+        offset: usize,
+    },
+    /// A node at the given byte offset extends past the maximum length.
+    ExceedsMaxLength {
+        /// Byte offset of the node from the start of the device path.
+        offset: usize,
+    },
+    /// The maximum length was reached without finding an end-entire node.
+    Unterminated,
+}
+
+impl From<DevicePathError> for efi::Status {
+    fn from(_: DevicePathError) -> Self {
+        efi::Status::INVALID_PARAMETER
+    }
+}
+
+/// Verifies that the device path is well-formed and fully contained in `max_len` bytes, returning its total length
+/// in bytes (including the terminating end node).
+///
+/// Every node must be at least as long as the node header, and the path must terminate with an end-entire node that
+/// is exactly the size of an end node. End-instance nodes separate the instances of a multi-instance device path and
+/// do not terminate it. No byte at or beyond `max_len` is read.
+///
+/// ## Safety
+///
+/// device_path must either be null or valid for reads of `max_len` bytes.
+///
+/// ## Examples
+///
+/// ```
+/// use patina_internal_device_path::{validate_device_path, DevicePathError};
+/// use r_efi::efi;
+/// let device_path_bytes = [
+///   efi::protocols::device_path::TYPE_HARDWARE,
+///   efi::protocols::device_path::Hardware::SUBTYPE_PCI,
+///   0x6,  //length[0]
+///   0x0,  //length[1]
+///   0x0,  //func
+///   0x1C, //device
+///   efi::protocols::device_path::TYPE_END,
+///   efi::protocols::device_path::End::SUBTYPE_ENTIRE,
+///   0x4,  //length[0]
+///   0x00, //length[1]
+/// ];
+/// let device_path_ptr = device_path_bytes.as_ptr() as *const efi::protocols::device_path::Protocol;
+/// assert_eq!(unsafe { validate_device_path(device_path_ptr, device_path_bytes.len()) }, Ok(10));
+/// assert_eq!(unsafe { validate_device_path(device_path_ptr, 8) }, Err(DevicePathError::ExceedsMaxLength { offset: 6 }));
+/// ```
+pub unsafe fn validate_device_path(
+    device_path: *const efi::protocols::device_path::Protocol,
+    max_len: usize,
+) -> Result<usize, DevicePathError> {
+    if device_path.is_null() {
+        return Err(DevicePathError::NullPointer);
+    }
+    let header_size = core::mem::size_of::<efi::protocols::device_path::Protocol>();
+    let end_size = core::mem::size_of::<efi::protocols::device_path::End>();
+    let mut offset = 0;
+    loop {
+        if max_len - offset < header_size {
+            return Err(if offset == max_len {
+                DevicePathError::Unterminated
+            } else {
+                DevicePathError::ExceedsMaxLength { offset }
+            });
+        }
+        // SAFETY: caller guarantees that device_path is valid for max_len bytes, and the header fits within them.
+        let node = unsafe {
+            (device_path as *const u8).add(offset).cast::<efi::protocols::device_path::Protocol>().read_unaligned()
+        };
+        let length: usize = u16::from_le_bytes(node.length).into();
+        let is_end = node.r#type == efi::protocols::device_path::TYPE_END
+            && node.sub_type == efi::protocols::device_path::End::SUBTYPE_ENTIRE;
+
+        if length == 0 && !is_end {
+            return Err(DevicePathError::ZeroLengthNode { offset });
+        }
+        if length < header_size {
+            return Err(DevicePathError::NodeTooShort { offset });
+        }
+        if length > max_len - offset {
+            return Err(DevicePathError::ExceedsMaxLength { offset });
+        }
+        offset += length;
+        if is_end {
+            if length != end_size {
+                return Err(DevicePathError::InvalidEndNode { offset: offset - length });
+            }
+            return Ok(offset);
+        }
+    }
+}
+
 /// Copies the device path from the given pointer into a Boxed [u8] slice.
 pub fn copy_device_path_to_boxed_slice(
     device_path: *const efi::protocols::device_path::Protocol,
@@ -336,6 +448,21 @@ impl DevicePathNode {
         &self.data
     }
 
+    /// Serializes the device path node, including its header.
+    ///
+    /// The length field is computed from the size of the header and data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header_len = size_of_val(&self.header);
+        let node_len = (header_len + self.data.len()) as u16;
+
+        let mut bytes = Vec::with_capacity(node_len.into());
+        bytes.push(self.header.r#type);
+        bytes.push(self.header.sub_type);
+        bytes.extend_from_slice(&node_len.to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
     fn len(&self) -> u16 {
         u16::from_le_bytes(self.header.length)
     }
@@ -404,6 +531,56 @@ impl Iterator for DevicePathWalker {
     }
 }
 
+/// Builds a device path from its nodes, terminating it with an end-entire node.
+///
+/// ```
+/// use patina_internal_device_path::{DevicePathBuilder, device_path_node_count};
+/// use r_efi::efi::protocols::device_path::{Hardware, TYPE_HARDWARE};
+///
+/// let mut builder = DevicePathBuilder::new();
+/// builder.push_node(TYPE_HARDWARE, Hardware::SUBTYPE_PCI, &[0x0, 0x1C]).unwrap();
+/// let device_path = builder.finish();
+///
+/// let (nodes, length) = device_path_node_count(device_path.as_ptr() as *const _).unwrap();
+/// assert_eq!((nodes, length), (2, device_path.len()));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DevicePathBuilder {
+    bytes: Vec<u8>,
+}
+
+impl DevicePathBuilder {
+    /// Creates a builder for a device path with no nodes.
+    pub const fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    /// Appends a node with the given type, sub-type, and data, computing its length field from the data.
+    ///
+    /// Returns `INVALID_PARAMETER`, leaving the device path unchanged, if the node with its header is longer than
+    /// `u16::MAX` bytes.
+    pub fn push_node(&mut self, node_type: u8, sub_type: u8, data: &[u8]) -> Result<(), efi::Status> {
+        let header_len = core::mem::size_of::<efi::protocols::device_path::Protocol>();
+        let node_len = u16::try_from(header_len + data.len()).map_err(|_| efi::Status::INVALID_PARAMETER)?;
+
+        self.bytes.reserve(node_len.into());
+        self.bytes.push(node_type);
+        self.bytes.push(sub_type);
+        self.bytes.extend_from_slice(&node_len.to_le_bytes());
+        self.bytes.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Returns the device path, with the end-entire node appended.
+    pub fn finish(mut self) -> Box<[u8]> {
+        let end_len = core::mem::size_of::<efi::protocols::device_path::End>() as u16;
+        self.bytes.push(efi::protocols::device_path::TYPE_END);
+        self.bytes.push(End::SUBTYPE_ENTIRE);
+        self.bytes.extend_from_slice(&end_len.to_le_bytes());
+        self.bytes.into_boxed_slice()
+    }
+}
+
 fn protocol_to_subtype_str(protocol: efi::protocols::device_path::Protocol) -> &'static str {
     match protocol.r#type {
         r_efi::protocols::device_path::TYPE_HARDWARE => match protocol.sub_type {
@@ -703,6 +880,47 @@ mod tests {
         assert_eq!(boxed_device_path.unwrap().to_vec(), device_path_bytes.to_vec());
     }
 
+    #[test]
+    fn device_path_nodes_can_be_serialized_back_to_bytes() {
+        //build a device path as a byte array for the test.
+        let device_path_bytes = [
+            TYPE_ACPI,
+            0x1,  //ACPI device path
+            0xC,  //length[0]
+            0x0,  //length[1]
+            0xD0, //hid
+            0x41,
+            0x03,
+            0x0A,
+            0x0, //uid
+            0x0,
+            0x0,
+            0x0,
+            TYPE_HARDWARE,
+            Hardware::SUBTYPE_PCI,
+            0x6,  //length[0]
+            0x0,  //length[1]
+            0x0,  //func
+            0x1C, //device
+            TYPE_END,
+            End::SUBTYPE_ENTIRE,
+            0x4,  //length[0]
+            0x00, //length[1]
+        ];
+        let device_path_ptr = device_path_bytes.as_ptr() as *const efi::protocols::device_path::Protocol;
+        let device_path_walker = unsafe { DevicePathWalker::new(device_path_ptr) };
+
+        let mut reassembled = Vec::new();
+        for node in device_path_walker {
+            let bytes = node.to_bytes();
+            assert_eq!(bytes.len(), node.len() as usize);
+            assert_eq!(&bytes[2..4], &node.header().length);
+            reassembled.extend_from_slice(&bytes);
+        }
+
+        assert_eq!(reassembled, device_path_bytes.to_vec());
+    }
+
     #[test]
     fn device_path_walker_can_be_converted_to_string() {
         let device_path_bytes = [
@@ -774,4 +992,111 @@ mod tests {
         protocol.r#type = 99; // Unknown type
         assert_eq!(protocol_to_subtype_str(protocol), "UnknownType");
     }
+
+    const PCI_NODE: [u8; 6] = [TYPE_HARDWARE, Hardware::SUBTYPE_PCI, 0x6, 0x0, 0x0, 0x1C];
+    const END_INSTANCE_NODE: [u8; 4] = [TYPE_END, End::SUBTYPE_INSTANCE, 0x4, 0x0];
+    const END_NODE: [u8; 4] = [TYPE_END, End::SUBTYPE_ENTIRE, 0x4, 0x0];
+
+    fn validate(bytes: &[u8], max_len: usize) -> Result<usize, DevicePathError> {
+        assert!(max_len <= bytes.len());
+        unsafe { validate_device_path(bytes.as_ptr() as *const efi::protocols::device_path::Protocol, max_len) }
+    }
+
+    #[test]
+    fn validate_device_path_should_return_length_of_well_formed_paths() {
+        let bytes = [PCI_NODE.as_slice(), &PCI_NODE, &END_INSTANCE_NODE, &PCI_NODE, &END_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Ok(bytes.len()));
+
+        // Trailing bytes after the end node are not part of the device path.
+        let bytes = [PCI_NODE.as_slice(), &END_NODE, &[0xff; 8]].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Ok(10));
+
+        assert_eq!(validate(&END_NODE, END_NODE.len()), Ok(4));
+        assert_eq!(unsafe { validate_device_path(core::ptr::null(), 0x100) }, Err(DevicePathError::NullPointer));
+    }
+
+    #[test]
+    fn validate_device_path_should_reject_zero_length_and_short_nodes() {
+        let bytes = [PCI_NODE.as_slice(), &[TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, 0x0, 0x0], &END_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::ZeroLengthNode { offset: 6 }));
+
+        let bytes = [PCI_NODE.as_slice(), &[TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, 0x2, 0x0], &END_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::NodeTooShort { offset: 6 }));
+
+        let bytes = [PCI_NODE.as_slice(), &[TYPE_END, End::SUBTYPE_ENTIRE, 0x6, 0x0, 0x0, 0x0]].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::InvalidEndNode { offset: 6 }));
+    }
+
+    #[test]
+    fn validate_device_path_should_reject_unterminated_paths() {
+        let bytes = [PCI_NODE.as_slice(), &PCI_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::Unterminated));
+
+        // An end-instance node does not terminate the device path.
+        let bytes = [PCI_NODE.as_slice(), &END_INSTANCE_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::Unterminated));
+    }
+
+    #[test]
+    fn validate_device_path_should_reject_paths_exceeding_max_len() {
+        let bytes = [PCI_NODE.as_slice(), &END_NODE].concat();
+        // The end node does not fit.
+        assert_eq!(validate(&bytes, 8), Err(DevicePathError::ExceedsMaxLength { offset: 6 }));
+        // Not even the end node header fits.
+        assert_eq!(validate(&bytes, 7), Err(DevicePathError::ExceedsMaxLength { offset: 6 }));
+
+        // A node claiming to be longer than the remaining buffer.
+        let bytes = [PCI_NODE.as_slice(), &[TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, 0xff, 0xff], &END_NODE].concat();
+        assert_eq!(validate(&bytes, bytes.len()), Err(DevicePathError::ExceedsMaxLength { offset: 6 }));
+    }
+
+    #[test]
+    fn device_path_builder_should_round_trip_through_the_walker() {
+        let file_name: Vec<u8> = "\\EFI\\app.efi\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut builder = DevicePathBuilder::new();
+        builder.push_node(TYPE_HARDWARE, Hardware::SUBTYPE_PCI, &[0x0, 0x1C]).unwrap();
+        builder.push_node(TYPE_HARDWARE, Hardware::SUBTYPE_PCI, &[0x2, 0x0]).unwrap();
+        builder.push_node(TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, &file_name).unwrap();
+        let device_path = builder.finish();
+
+        assert_eq!(device_path[..PCI_NODE.len()], PCI_NODE);
+        assert_eq!(device_path[device_path.len() - END_NODE.len()..], END_NODE);
+
+        let device_path_ptr = device_path.as_ptr() as *const efi::protocols::device_path::Protocol;
+        assert_eq!(device_path_node_count(device_path_ptr), Ok((4, device_path.len())));
+        assert_eq!(validate(&device_path, device_path.len()), Ok(device_path.len()));
+
+        let nodes: Vec<(u8, u8, Vec<u8>)> = unsafe { DevicePathWalker::new(device_path_ptr) }
+            .map(|node| (node.header().r#type, node.header().sub_type, node.data().to_vec()))
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                (TYPE_HARDWARE, Hardware::SUBTYPE_PCI, vec![0x0, 0x1C]),
+                (TYPE_HARDWARE, Hardware::SUBTYPE_PCI, vec![0x2, 0x0]),
+                (TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, file_name),
+                (TYPE_END, End::SUBTYPE_ENTIRE, vec![]),
+            ]
+        );
+
+        assert_eq!(*DevicePathBuilder::new().finish(), END_NODE);
+    }
+
+    #[test]
+    fn device_path_builder_should_reject_nodes_longer_than_u16_max() {
+        let data = vec![0xA5; u16::MAX as usize];
+        let mut builder = DevicePathBuilder::new();
+        builder.push_node(TYPE_HARDWARE, Hardware::SUBTYPE_PCI, &[0x0, 0x1C]).unwrap();
+        assert_eq!(builder.push_node(TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, &data[4..]), Ok(()));
+        assert_eq!(
+            builder.push_node(TYPE_MEDIA, Media::SUBTYPE_FILE_PATH, &data[3..]),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+
+        let device_path = builder.finish();
+        assert_eq!(device_path.len(), PCI_NODE.len() + u16::MAX as usize + END_NODE.len());
+        assert_eq!(device_path[PCI_NODE.len() + 2..PCI_NODE.len() + 4], u16::MAX.to_le_bytes());
+        let device_path_ptr = device_path.as_ptr() as *const efi::protocols::device_path::Protocol;
+        assert_eq!(device_path_node_count(device_path_ptr), Ok((3, device_path.len())));
+    }
 }
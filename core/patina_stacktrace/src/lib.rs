@@ -106,8 +106,22 @@
 //!
 //!     // Inside rust panic handler and drivers
 //!     StackTrace::dump();
+//!
+//!     // Capture the registers at the call site, to store or dump later
+//!     let (ip, sp, fp) = (current_ip(), current_sp(), current_fp());
+//!     StackTrace::dump_with(ip, sp);
 //! ```
 //!
+//! ## Unloaded Images
+//!
+//! Addresses that fall within an image that has since been unloaded cannot be
+//! resolved by scanning memory. The image loader may record such images with
+//! `record_unloaded_image()`; a frame within a recorded range is then reported
+//! as `module+<relative rip> (unloaded)` and the walk stops, as the unwind
+//! information went away with the image. An image loaded into a range that an
+//! unloaded image occupied should be recorded with `record_loaded_image()`, so
+//! that its frames are resolved as a live image rather than as the unloaded one.
+//!
 //! ## Reference
 //!
 //! More reference test cases are in `src\x64\tests\*.rs`
@@ -120,7 +134,9 @@ extern crate alloc;
 mod byte_reader;
 pub mod error;
 mod pe;
+mod registers;
 mod stacktrace;
+mod unloaded_images;
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
@@ -130,4 +146,8 @@ cfg_if::cfg_if! {
     }
 }
 
+pub use registers::{current_fp, current_ip, current_sp};
 pub use stacktrace::StackTrace;
+pub use unloaded_images::{
+    UNLOADED_IMAGE_HISTORY_CAPACITY, UnloadedImage, find_unloaded_image, record_loaded_image, record_unloaded_image,
+};
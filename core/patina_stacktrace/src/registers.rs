@@ -0,0 +1,119 @@
+//! Reads of the current register values at the call site.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+use core::arch::asm;
+
+/// Returns the current stack pointer (SP on AArch64, RSP on x64).
+///
+/// This is inlined, so the value is that of the calling function.
+#[inline(always)]
+pub fn current_sp() -> u64 {
+    let sp: u64;
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
+            // SAFETY: Reading the stack pointer has no side effects.
+            unsafe { asm!("mov {sp}, sp", sp = out(reg) sp, options(nomem, nostack, preserves_flags)) };
+        } else {
+            // SAFETY: Reading the stack pointer has no side effects.
+            unsafe { asm!("mov {sp}, rsp", sp = out(reg) sp, options(nomem, nostack, preserves_flags)) };
+        }
+    }
+
+    sp
+}
+
+/// Returns the current frame pointer (X29 on AArch64, RBP on x64).
+///
+/// This is inlined, so the value is that of the calling function. The value is only a frame pointer if the calling
+/// function was compiled with frame pointers, otherwise it is whatever the register holds.
+#[inline(always)]
+pub fn current_fp() -> u64 {
+    let fp: u64;
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
+            // SAFETY: Reading the frame pointer has no side effects.
+            unsafe { asm!("mov {fp}, x29", fp = out(reg) fp, options(nomem, nostack, preserves_flags)) };
+        } else {
+            // SAFETY: Reading the frame pointer has no side effects.
+            unsafe { asm!("mov {fp}, rbp", fp = out(reg) fp, options(nomem, nostack, preserves_flags)) };
+        }
+    }
+
+    fp
+}
+
+/// Returns the current instruction pointer (PC on AArch64, RIP on x64).
+///
+/// This is inlined, so the value is an address within the calling function.
+#[inline(always)]
+pub fn current_ip() -> u64 {
+    let ip: u64;
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
+            // SAFETY: Reading the program counter has no side effects.
+            unsafe { asm!("adr {ip}, .", ip = out(reg) ip, options(nomem, nostack, preserves_flags)) };
+        } else {
+            // SAFETY: Reading the instruction pointer has no side effects.
+            unsafe { asm!("lea {ip}, [rip]", ip = out(reg) ip, options(nomem, nostack, preserves_flags)) };
+        }
+    }
+
+    ip
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+
+    /// The default size of a spawned thread's stack in the standard library.
+    const MIN_THREAD_STACK_SIZE: u64 = 2 * 1024 * 1024;
+
+    #[test]
+    fn current_sp_should_be_within_the_thread_stack() {
+        let local = 0_u8;
+        let local_address = core::ptr::addr_of!(local) as u64;
+        let sp = current_sp();
+
+        // The stack grows down, so the local lies above the stack pointer, within the stack.
+        assert_ne!(sp, 0);
+        assert!(sp <= local_address, "sp {sp:#x} is above local {local_address:#x}");
+        assert!(local_address - sp < MIN_THREAD_STACK_SIZE, "sp {sp:#x} is far from local {local_address:#x}");
+    }
+
+    #[test]
+    fn current_ip_should_be_within_the_calling_function() {
+        #[inline(never)]
+        fn ip_of_caller() -> (u64, u64) {
+            (ip_of_caller as usize as u64, current_ip())
+        }
+
+        let (function, ip) = ip_of_caller();
+        assert_ne!(ip, 0);
+        // The function is small, so the instruction pointer is shortly after its start.
+        assert!(ip > function && ip - function < 0x1000, "ip {ip:#x} is not within function {function:#x}");
+    }
+
+    #[test]
+    fn current_fp_should_be_within_the_thread_stack_when_unoptimized() {
+        #[inline(never)]
+        fn fp_with_frame() -> (u64, u64) {
+            let local = [0_u8; 64];
+            (core::hint::black_box(&local).as_ptr() as u64, current_fp())
+        }
+
+        let (local_address, fp) = fp_with_frame();
+        // Unoptimized builds keep frame pointers, optimized builds may use the register for anything.
+        if cfg!(debug_assertions) {
+            assert_ne!(fp, 0);
+            assert!(fp.abs_diff(local_address) < MIN_THREAD_STACK_SIZE, "fp {fp:#x} is far from {local_address:#x}");
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::error::Error;
 use crate::error::StResult;
 use crate::pe::PE;
-use core::arch::asm;
+use crate::registers::{current_ip, current_sp};
+use crate::unloaded_images::find_unloaded_image;
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
@@ -46,6 +47,16 @@ impl StackTrace {
         loop {
             let no_name = "<no module>";
 
+            // The unwind information of an unloaded image is gone, so the walk
+            // cannot continue past a frame within one.
+            let unloaded = find_unloaded_image(pc, |image| {
+                let pc_rva = pc - image.base_address;
+                log::info!("      {i} {sp:016X}      {:<16}       {}+{pc_rva:X} (unloaded)", "<unknown>", image.name);
+            });
+            if unloaded.is_some() {
+                break;
+            }
+
             let image = unsafe { PE::locate_image(pc) }?;
 
             let image_name = image.image_name.unwrap_or(no_name);
@@ -99,30 +110,8 @@ impl StackTrace {
     /// 7 0000005E2AEFFD50      0000000000000000       ntdll+75AEC
     /// ```
     pub unsafe fn dump() -> StResult<()> {
-        let mut pc: u64;
-        let mut sp: u64;
-
-        cfg_if::cfg_if! {
-            if #[cfg(all(target_os = "uefi", target_arch = "aarch64"))] {
-                unsafe {
-                    asm!(
-                        "adr {pc}, .",  // Get current PC
-                        "mov {sp}, sp", // Get current SP
-                        pc = out(reg) pc,
-                        sp = out(reg) sp,
-                    );
-                }
-            } else {
-                unsafe {
-                    asm!(
-                        "lea {pc}, [rip]",
-                        "mov {sp}, rsp",
-                        pc = out(reg) pc,
-                        sp = out(reg) sp,
-                    );
-                }
-            }
-        }
+        let pc = current_ip();
+        let sp = current_sp();
 
         unsafe { StackTrace::dump_with(pc, sp) }
     }
@@ -0,0 +1,126 @@
+use alloc::{collections::VecDeque, string::String};
+use spin::Mutex;
+
+/// Maximum number of unloaded images retained in the history. Once full, the
+/// oldest entry is evicted to make room for the newest.
+pub const UNLOADED_IMAGE_HISTORY_CAPACITY: usize = 16;
+
+/// Load information for an image that has since been unloaded from memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnloadedImage {
+    /// Name of the image, typically derived from its PDB path.
+    pub name: String,
+
+    /// Base address the image was loaded at.
+    pub base_address: u64,
+
+    /// Size of the image in memory.
+    pub size: u64,
+}
+
+impl UnloadedImage {
+    /// Returns true if `address` fell within the image's former range.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.base_address && address - self.base_address < self.size
+    }
+
+    /// Returns true if the image's former range overlaps the `size` bytes at
+    /// `base_address`.
+    fn overlaps(&self, base_address: u64, size: u64) -> bool {
+        base_address < self.base_address.saturating_add(self.size)
+            && self.base_address < base_address.saturating_add(size)
+    }
+}
+
+static UNLOADED_IMAGES: Mutex<VecDeque<UnloadedImage>> = Mutex::new(VecDeque::new());
+
+/// Records an image that has been unloaded so that addresses within its former
+/// range can still be attributed to it in later stack traces.
+pub fn record_unloaded_image(image: UnloadedImage) {
+    let mut images = UNLOADED_IMAGES.lock();
+    // An image unloaded from an overlapping range supersedes any older record of it.
+    images.retain(|existing| !existing.overlaps(image.base_address, image.size));
+    if images.len() == UNLOADED_IMAGE_HISTORY_CAPACITY {
+        images.pop_front();
+    }
+    images.push_back(image);
+}
+
+/// Records that an image has been loaded at `base_address`, forgetting any
+/// unloaded image whose former range it overlaps, so that addresses within the
+/// new image are no longer attributed to an image that has since been unloaded.
+pub fn record_loaded_image(base_address: u64, size: u64) {
+    UNLOADED_IMAGES.lock().retain(|existing| !existing.overlaps(base_address, size));
+}
+
+/// Calls `f` with the most recently unloaded image whose former range contains
+/// `address`, returning its result, or `None` if there is no such image.
+///
+/// The image is borrowed from the history while it is locked, so that stack
+/// traces taken where allocating is not possible can still use it. This does
+/// not block; if the history is locked (for example, an exception was taken
+/// while recording), no image is found.
+pub fn find_unloaded_image<R>(address: u64, f: impl FnOnce(&UnloadedImage) -> R) -> Option<R> {
+    UNLOADED_IMAGES.try_lock()?.iter().rev().find(|image| image.contains(address)).map(f)
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn test_unloaded_image_history() {
+        let base = 0xDEAD_0000_0000;
+        record_unloaded_image(UnloadedImage { name: String::from("first"), base_address: base, size: 0x2000 });
+
+        let name = |address| find_unloaded_image(address, |image| image.name.clone());
+        assert_eq!(name(base + 0x1FFF).unwrap(), "first");
+        assert!(name(base + 0x2000).is_none());
+        assert!(name(base - 1).is_none());
+
+        // Reusing the same range replaces the old record.
+        record_unloaded_image(UnloadedImage { name: String::from("second"), base_address: base, size: 0x1000 });
+        assert_eq!(name(base).unwrap(), "second");
+
+        // Once full, the oldest records are evicted.
+        for i in 1..=UNLOADED_IMAGE_HISTORY_CAPACITY as u64 {
+            record_unloaded_image(UnloadedImage {
+                name: format!("image{i}"),
+                base_address: base + i * 0x10000,
+                size: 0x1000,
+            });
+        }
+        assert!(name(base).is_none());
+        assert_eq!(name(base + 0x10000).unwrap(), "image1");
+        assert_eq!(UNLOADED_IMAGES.lock().len(), UNLOADED_IMAGE_HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn test_loaded_image_supersedes_overlapping_unloaded_images() {
+        let base = 0xBEEF_0000_0000;
+        record_unloaded_image(UnloadedImage { name: String::from("first"), base_address: base, size: 0x2000 });
+        record_unloaded_image(UnloadedImage {
+            name: String::from("second"),
+            base_address: base + 0x2000,
+            size: 0x2000,
+        });
+        record_unloaded_image(UnloadedImage { name: String::from("third"), base_address: base + 0x4000, size: 0x1000 });
+
+        let name = |address| find_unloaded_image(address, |image| image.name.clone());
+
+        // An image unloaded from a range overlapping, but not starting at, an older record replaces it.
+        record_unloaded_image(UnloadedImage { name: String::from("fourth"), base_address: base + 0x3000, size: 0x800 });
+        assert!(name(base + 0x2000).is_none());
+        assert_eq!(name(base + 0x3000).unwrap(), "fourth");
+
+        // A new image loaded into part of a freed range is not reported as unloaded, and neither are the rest of
+        // the images it overlaps.
+        record_loaded_image(base + 0x1000, 0x2800);
+        assert!(name(base).is_none());
+        assert!(name(base + 0x1000).is_none());
+        assert!(name(base + 0x3000).is_none());
+        assert_eq!(name(base + 0x4000).unwrap(), "third");
+    }
+}
@@ -43,6 +43,7 @@ fn main() -> patina::error::Result<()> {
         // .with_some_config(true)
         .with_service(patina_ffs_extractors::CompositeSectionExtractor::default())
         .start()
+        .map(|_| ())
 }
 
 const MEM_SIZE: u64 = 0x2000000;
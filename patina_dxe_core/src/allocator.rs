@@ -6,6 +6,7 @@
 //!
 //! SPDX-License-Identifier: Apache-2.0
 //!
+mod allocation_tracking;
 mod fixed_size_block_allocator;
 mod uefi_allocator;
 
@@ -35,6 +36,7 @@ use crate::{
     systemtables::EfiSystemTable,
     tpl_lock,
 };
+pub(crate) use allocation_tracking::{report_outstanding_allocations, set_current_image};
 use patina::pi::{
     dxe_services::{self, GcdMemoryType, MemorySpaceDescriptor},
     hob::{self, EFiMemoryTypeInformation, Hob, HobList, MEMORY_TYPE_INFO_HOB_GUID},
@@ -244,6 +246,49 @@ impl Debug for MemoryDescriptorSlice<'_> {
     }
 }
 
+// Memory type value that terminates a Memory Type Information table (EfiMaxMemoryType).
+const MEMORY_TYPE_INFO_TERMINATOR: efi::MemoryType = 16;
+
+/// A decoded view of a Memory Type Information configuration table.
+///
+/// The wrapped slice holds the table entries up to, but not including, the terminating `EfiMaxMemoryType` entry.
+/// The [Display](core::fmt::Display) implementation renders the table for logging.
+pub struct MemoryTypeInfoTable<'a>(pub &'a [EFiMemoryTypeInformation]);
+
+impl MemoryTypeInfoTable<'_> {
+    /// Decodes a Memory Type Information table by reading entries from `table` until the terminating entry.
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a valid array of [EFiMemoryTypeInformation] entries that is terminated by an entry with a
+    /// memory type of `EfiMaxMemoryType` or greater, and must remain valid for the returned lifetime.
+    pub unsafe fn from_ptr<'a>(table: *const EFiMemoryTypeInformation) -> MemoryTypeInfoTable<'a> {
+        let mut count = 0;
+        // SAFETY: caller guarantees the table is valid and terminated.
+        while unsafe { (*table.add(count)).memory_type } < MEMORY_TYPE_INFO_TERMINATOR {
+            count += 1;
+        }
+        // SAFETY: the first `count` entries were just read above.
+        MemoryTypeInfoTable(unsafe { slice::from_raw_parts(table, count) })
+    }
+
+    /// Returns the decoded (memory type, page count) pairs in table order.
+    pub fn entries(&self) -> impl Iterator<Item = (efi::MemoryType, u32)> + '_ {
+        self.0.iter().map(|entry| (entry.memory_type, entry.number_of_pages))
+    }
+}
+
+impl core::fmt::Display for MemoryTypeInfoTable<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{:<24} {:<15}", "Type", "Number of Pages")?;
+        for (memory_type, number_of_pages) in self.entries() {
+            memory_type_to_str(f, memory_type)?;
+            writeln!(f, "{number_of_pages:<#15X}")?;
+        }
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
 /// Return a vector of the memory ranges owned by a particular allocator
 /// Returns an empty vector if the memory type is not found
@@ -392,9 +437,87 @@ impl AllocatorMap {
     }
 }
 
+/// A hook invoked when a heap allocation fails, after the diagnostic report has been logged and before the core
+/// halts. It receives the layout of the failed request and must not allocate.
+pub type AllocErrorHook = fn(alloc::alloc::Layout);
+
+static ALLOC_ERROR_HOOK: spin::RwLock<Option<AllocErrorHook>> = spin::RwLock::new(None);
+
+// Set once an allocation error is being reported, so that an allocation failing during the report does not recurse.
+#[cfg(any(target_os = "uefi", test))]
+static REPORTING_ALLOC_ERROR: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Registers a hook to be invoked when a heap allocation fails, replacing any previously registered hook.
+pub fn set_alloc_error_hook(hook: AllocErrorHook) {
+    *ALLOC_ERROR_HOOK.write() = Some(hook);
+}
+
+/// The diagnostic report for a failed allocation: the requested layout, followed by the statistics of each allocator.
+///
+/// Formatting the report does not allocate, and does not wait for any allocator lock.
+#[cfg(any(target_os = "uefi", test))]
+struct AllocErrorReport(alloc::alloc::Layout);
+
+#[cfg(any(target_os = "uefi", test))]
+impl core::fmt::Display for AllocErrorReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Allocation of {:#x} bytes with alignment {:#x} failed.", self.0.size(), self.0.align())?;
+        writeln!(
+            f,
+            "{:<24} {:<12} {:<12} {:<15} {:<15} {:<15}",
+            "Allocator", "Allocations", "Frees", "Reserved Size", "Reserved Used", "Claimed Pages"
+        )?;
+        // The allocator map lock may be held by the context that failed to allocate, so do not wait for it.
+        let allocators = ALLOCATORS.try_lock();
+        let dynamic_allocators = allocators.iter().flat_map(|allocators| allocators.map.values().copied());
+        for allocator in STATIC_ALLOCATORS.iter().copied().chain(dynamic_allocators) {
+            memory_type_to_str(f, allocator.memory_type())?;
+            // Likewise for the lock of each allocator, which the failed allocation may have been made under.
+            let Some(stats) = allocator.try_stats() else {
+                writeln!(f, "Statistics are unavailable, the allocator is locked.")?;
+                continue;
+            };
+            writeln!(
+                f,
+                "{:<#12X} {:<#12X} {:<#15X} {:<#15X} {:<#15X}",
+                stats.pool_allocation_calls,
+                stats.pool_free_calls,
+                stats.reserved_size,
+                stats.reserved_used,
+                stats.claimed_pages
+            )?;
+        }
+        if allocators.is_none() {
+            writeln!(f, "Statistics for additional memory types are unavailable, the allocator map is locked.")?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs the diagnostic report for a failed allocation and invokes the registered [AllocErrorHook].
+///
+/// Returns `false` without reporting if an allocation error is already being reported.
+#[cfg(any(target_os = "uefi", test))]
+fn report_alloc_error(layout: alloc::alloc::Layout) -> bool {
+    if REPORTING_ALLOC_ERROR.swap(true, core::sync::atomic::Ordering::SeqCst) {
+        return false;
+    }
+    log::error!("{}", AllocErrorReport(layout));
+    if let Some(hook) = ALLOC_ERROR_HOOK.try_read().and_then(|hook| *hook) {
+        hook(layout);
+    }
+    true
+}
+
 #[cfg(target_os = "uefi")]
 #[alloc_error_handler]
 fn alloc_error_handler(layout: alloc::alloc::Layout) -> ! {
+    if report_alloc_error(layout) {
+        // SAFETY: the stack trace is taken from the current, valid, execution context. Dumping it does not allocate.
+        if let Err(err) = unsafe { patina_stacktrace::StackTrace::dump() } {
+            log::error!("Failed to dump the stack trace for the allocation error: {err:?}");
+        }
+    }
     panic!("allocation error: {:?}", layout)
 }
 
@@ -420,14 +543,17 @@ pub fn core_allocate_pool(pool_type: efi::MemoryType, size: usize) -> Result<*mu
     }
 
     let handle = AllocatorMap::handle_for_memory_type(pool_type)?;
-    match ALLOCATORS.lock().get_or_create_allocator(pool_type, handle) {
+    let buffer = match ALLOCATORS.lock().get_or_create_allocator(pool_type, handle) {
         Ok(allocator) => {
             let mut buffer: *mut c_void = core::ptr::null_mut();
 
             unsafe { allocator.allocate_pool(size, core::ptr::addr_of_mut!(buffer)).map(|_| buffer) }
         }
         Err(err) => Err(err),
-    }
+    }?;
+
+    allocation_tracking::track_allocation(buffer as usize, size, pool_type);
+    Ok(buffer)
 }
 
 extern "efiapi" fn free_pool(buffer: *mut c_void) -> efi::Status {
@@ -444,6 +570,8 @@ pub fn core_free_pool(buffer: *mut c_void) -> Result<(), EfiError> {
     let allocators = ALLOCATORS.lock();
     unsafe {
         if allocators.iter().any(|allocator| allocator.free_pool(buffer).is_ok()) {
+            drop(allocators);
+            allocation_tracking::track_free(buffer as usize);
             Ok(())
         } else {
             Err(EfiError::InvalidParameter)
@@ -500,8 +628,10 @@ pub fn core_allocate_pages(
             };
 
             if let Ok(ptr) = result {
+                let address = ptr.cast::<u8>().as_ptr().expose_provenance();
+                allocation_tracking::track_allocation(address, pages * UEFI_PAGE_SIZE, memory_type);
                 // Safety: caller must ensure that "memory" is a valid pointer. It is null-checked above.
-                unsafe { memory.write_unaligned(ptr.cast::<u8>().as_ptr().expose_provenance() as u64) }
+                unsafe { memory.write_unaligned(address as u64) }
                 Ok(())
             } else {
                 result.map(|_| ())
@@ -570,6 +700,10 @@ pub fn core_free_pages(memory: efi::PhysicalAddress, pages: usize) -> Result<(),
     // tables are locked at TPL_NOTIFY
     drop(allocators);
 
+    if res.is_ok() {
+        allocation_tracking::track_free(memory as usize);
+    }
+
     // If the memory type is runtime services code or data, we need to install the memory attributes table to reflect
     // the update. The MAT logic will decide if it is a proper time to install the MAT or not.
     match memory_type {
@@ -795,8 +929,13 @@ pub fn terminate_memory_map(map_key: usize) -> Result<(), EfiError> {
 }
 
 pub fn install_memory_type_info_table(system_table: &mut EfiSystemTable) -> Result<(), EfiError> {
-    let table_ptr = NonNull::from(GCD.memory_type_info_table()).cast::<c_void>().as_ptr();
-    config_tables::core_install_configuration_table(guids::MEMORY_TYPE_INFORMATION, table_ptr, system_table)
+    let table = GCD.memory_type_info_table();
+    let table_ptr = NonNull::from(table).cast::<c_void>().as_ptr();
+    config_tables::core_install_configuration_table(guids::MEMORY_TYPE_INFORMATION, table_ptr, system_table)?;
+
+    // SAFETY: the GCD table is always terminated by an EfiMaxMemoryType entry.
+    log::debug!("Memory Type Information Table:\n{}", unsafe { MemoryTypeInfoTable::from_ptr(table.as_ptr()) });
+    Ok(())
 }
 
 fn process_hob_allocations(hob_list: &HobList) {
@@ -1175,6 +1314,7 @@ mod tests {
     };
 
     use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
     use patina::pi::hob::{GUID_EXTENSION, GuidHob, Hob, header};
     use r_efi::efi;
 
@@ -1455,6 +1595,38 @@ mod tests {
         });
     }
 
+    #[test]
+    #[cfg(feature = "allocation_tracking")]
+    fn pool_allocations_should_be_attributed_to_the_running_image() {
+        with_locked_state(0x1000000, || {
+            let image = 0x1234_0000 as efi::Handle;
+            let by_image = || {
+                allocation_tracking::outstanding_by_image()
+                    .into_iter()
+                    .find(|((handle, _), _)| *handle == image as usize)
+                    .map(|((_, name), allocations)| (name, allocations))
+            };
+
+            let unattributed = core_allocate_pool(efi::BOOT_SERVICES_DATA, 0x10).unwrap();
+            set_current_image(Some(image), |_| "LeakyDriver.efi".into());
+            let leaked = core_allocate_pool(efi::BOOT_SERVICES_DATA, 0x20).unwrap();
+            let freed = core_allocate_pool(efi::BOOT_SERVICES_DATA, 0x30).unwrap();
+            set_current_image(None, |_| unreachable!());
+            core_free_pool(freed).unwrap();
+
+            let (name, allocations) = by_image().unwrap();
+            assert_eq!(&*name, "LeakyDriver.efi");
+            assert_eq!(allocations.len(), 1);
+            assert_eq!(allocations[0].address, leaked as usize);
+            assert_eq!(allocations[0].size, 0x20);
+            assert_eq!(allocations[0].memory_type, efi::BOOT_SERVICES_DATA);
+
+            core_free_pool(leaked).unwrap();
+            core_free_pool(unattributed).unwrap();
+            assert!(by_image().is_none());
+        });
+    }
+
     #[test]
     fn new_allocators_should_be_created_on_demand() {
         with_locked_state(0x4000000, || {
@@ -1945,4 +2117,70 @@ mod tests {
             assert_eq!(terminate_memory_map(map_key + 1), Err(EfiError::InvalidParameter));
         });
     }
+
+    #[test]
+    fn memory_type_info_table_should_decode_and_display() {
+        let table = [
+            EFiMemoryTypeInformation { memory_type: efi::BOOT_SERVICES_DATA, number_of_pages: 0x200 },
+            EFiMemoryTypeInformation { memory_type: efi::RUNTIME_SERVICES_CODE, number_of_pages: 0x40 },
+            EFiMemoryTypeInformation { memory_type: efi::ACPI_MEMORY_NVS, number_of_pages: 0 },
+            EFiMemoryTypeInformation { memory_type: MEMORY_TYPE_INFO_TERMINATOR, number_of_pages: 0 },
+            EFiMemoryTypeInformation { memory_type: efi::LOADER_CODE, number_of_pages: 0x1000 },
+        ];
+
+        let decoded = unsafe { MemoryTypeInfoTable::from_ptr(table.as_ptr()) };
+        assert_eq!(
+            decoded.entries().collect::<Vec<_>>(),
+            vec![(efi::BOOT_SERVICES_DATA, 0x200), (efi::RUNTIME_SERVICES_CODE, 0x40), (efi::ACPI_MEMORY_NVS, 0)]
+        );
+
+        let output = std::format!("{decoded}");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Type"));
+        assert!(lines[1].starts_with("BootServicesData") && lines[1].contains("0x200"));
+        assert!(lines[2].starts_with("RuntimeServicesCode") && lines[2].contains("0x40"));
+        assert!(lines[3].starts_with("ACPI Memory NVS"));
+        assert!(!output.contains("Loader Code"));
+
+        let empty = [EFiMemoryTypeInformation { memory_type: MEMORY_TYPE_INFO_TERMINATOR, number_of_pages: 0 }];
+        assert!(unsafe { MemoryTypeInfoTable::from_ptr(empty.as_ptr()) }.entries().next().is_none());
+    }
+
+    static HOOKED_ALLOCATION_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    fn record_alloc_error(layout: alloc::alloc::Layout) {
+        HOOKED_ALLOCATION_SIZE.store(layout.size(), Ordering::SeqCst);
+    }
+
+    #[test]
+    fn report_alloc_error_should_describe_failure_and_invoke_hook() {
+        with_locked_state(0x1000000, || {
+            let layout = alloc::alloc::Layout::from_size_align(0x123000, 0x1000).unwrap();
+
+            let report = std::format!("{}", AllocErrorReport(layout));
+            let lines: Vec<&str> = report.lines().collect();
+            assert_eq!(lines[0], "Allocation of 0x123000 bytes with alignment 0x1000 failed.");
+            assert!(lines[1].starts_with("Allocator"));
+            assert_eq!(lines.len(), 2 + STATIC_ALLOCATORS.len());
+            assert!(lines.iter().any(|line| line.starts_with("BootServicesData")));
+
+            // The map lock is respected rather than waited on.
+            let guard = ALLOCATORS.lock();
+            let report = std::format!("{}", AllocErrorReport(layout));
+            assert!(report.contains("allocator map is locked"));
+            drop(guard);
+
+            set_alloc_error_hook(record_alloc_error);
+            assert!(report_alloc_error(layout));
+            assert_eq!(HOOKED_ALLOCATION_SIZE.load(Ordering::SeqCst), 0x123000);
+
+            // A failure while reporting is not reported again.
+            assert!(!report_alloc_error(alloc::alloc::Layout::new::<u64>()));
+            assert_eq!(HOOKED_ALLOCATION_SIZE.load(Ordering::SeqCst), 0x123000);
+
+            REPORTING_ALLOC_ERROR.store(false, Ordering::SeqCst);
+            *ALLOC_ERROR_HOOK.write() = None;
+        });
+    }
 }
@@ -0,0 +1,184 @@
+//! Attribution of outstanding allocations to the image that made them.
+//!
+//! With the `allocation_tracking` feature, each page and pool allocation made through boot services while an image is
+//! running is recorded with the handle and name of that image until it is freed. The allocations still outstanding at
+//! the end of dispatch are reported grouped by image, pointing at the driver that leaked them, even if it has since
+//! been unloaded.
+//!
+//! An allocation is attributed to the image whose StartImage call is in progress when it is made. Allocations made by
+//! an event notification function or protocol service of an image after its StartImage call returns are attributed to
+//! the image running at that time, if any, rather than to the image that owns the code.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+
+use r_efi::{efi, system::TPL_HIGH_LEVEL};
+
+use crate::tpl_lock;
+
+static ALLOCATION_TRACKER: AllocationTracker = AllocationTracker::new();
+
+/// An allocation that has not been freed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TrackedAllocation {
+    /// The address of the allocation.
+    pub(crate) address: usize,
+    /// The size of the allocation in bytes.
+    pub(crate) size: usize,
+    /// The memory type of the allocation.
+    pub(crate) memory_type: efi::MemoryType,
+}
+
+/// The image an allocation is attributed to, by handle and by the name it was resolved to when it was running.
+///
+/// The name is shared by every allocation of the image, so recording an allocation does not copy it.
+type TrackedImage = (usize, Arc<str>);
+
+struct TrackerState {
+    current_image: Option<TrackedImage>,
+    allocations: BTreeMap<usize, (TrackedImage, TrackedAllocation)>,
+}
+
+/// Records allocations made while an image is running, keyed by address, with the image that made them.
+struct AllocationTracker {
+    state: tpl_lock::TplMutex<TrackerState>,
+}
+
+impl AllocationTracker {
+    const fn new() -> Self {
+        Self {
+            state: tpl_lock::TplMutex::new(
+                TPL_HIGH_LEVEL,
+                TrackerState { current_image: None, allocations: BTreeMap::new() },
+                "AllocationTrackerLock",
+            ),
+        }
+    }
+
+    fn set_current_image(&self, image: Option<(efi::Handle, String)>) {
+        let image = image.map(|(handle, name)| (handle as usize, Arc::from(name)));
+        self.state.lock().current_image = image;
+    }
+
+    /// Records an allocation against the running image. Allocations made while no image is running are not recorded.
+    fn record_allocation(&self, allocation: TrackedAllocation) {
+        let mut state = self.state.lock();
+        if let Some(image) = state.current_image.clone() {
+            state.allocations.insert(allocation.address, (image, allocation));
+        }
+    }
+
+    /// Forgets the allocation at `address`, if it was recorded.
+    fn record_free(&self, address: usize) {
+        self.state.lock().allocations.remove(&address);
+    }
+
+    /// Returns the outstanding allocations, grouped by the image that made them.
+    fn outstanding_by_image(&self) -> BTreeMap<TrackedImage, Vec<TrackedAllocation>> {
+        let mut by_image: BTreeMap<TrackedImage, Vec<TrackedAllocation>> = BTreeMap::new();
+        for (image, allocation) in self.state.lock().allocations.values() {
+            by_image.entry(image.clone()).or_default().push(*allocation);
+        }
+        by_image
+    }
+}
+
+/// Sets the image that subsequent allocations are attributed to, or `None` once no image is running.
+///
+/// `name` resolves the handle of the image to the name it is reported by, and is only called with the
+/// `allocation_tracking` feature.
+pub(crate) fn set_current_image(image: Option<efi::Handle>, name: impl FnOnce(efi::Handle) -> String) {
+    if cfg!(feature = "allocation_tracking") {
+        ALLOCATION_TRACKER.set_current_image(image.map(|handle| (handle, name(handle))));
+    }
+}
+
+/// Records an allocation against the running image.
+pub(crate) fn track_allocation(address: usize, size: usize, memory_type: efi::MemoryType) {
+    if cfg!(feature = "allocation_tracking") {
+        ALLOCATION_TRACKER.record_allocation(TrackedAllocation { address, size, memory_type });
+    }
+}
+
+/// Forgets the allocation at `address`.
+///
+/// Freeing only part of a page allocation forgets the whole allocation if it starts at `address`, and nothing
+/// otherwise.
+pub(crate) fn track_free(address: usize) {
+    if cfg!(feature = "allocation_tracking") {
+        ALLOCATION_TRACKER.record_free(address);
+    }
+}
+
+/// Returns the outstanding allocations, grouped by the image that made them.
+#[cfg(all(test, feature = "allocation_tracking"))]
+pub(crate) fn outstanding_by_image() -> BTreeMap<(usize, Arc<str>), Vec<TrackedAllocation>> {
+    ALLOCATION_TRACKER.outstanding_by_image()
+}
+
+/// Logs the outstanding allocations of each image.
+pub fn report_outstanding_allocations() {
+    if !cfg!(feature = "allocation_tracking") {
+        return;
+    }
+
+    for ((image, name), allocations) in ALLOCATION_TRACKER.outstanding_by_image() {
+        let size: usize = allocations.iter().map(|allocation| allocation.size).sum();
+        log::warn!("Image {name} ({image:#x?}) has {} outstanding allocations, {size:#x?} bytes:", allocations.len());
+        for allocation in allocations {
+            log::warn!(
+                "  {:#x?}: {:#x?} bytes of memory type {:#x?}",
+                allocation.address,
+                allocation.size,
+                allocation.memory_type
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    fn allocation(address: usize, size: usize) -> TrackedAllocation {
+        TrackedAllocation { address, size, memory_type: efi::BOOT_SERVICES_DATA }
+    }
+
+    #[test]
+    fn test_allocations_are_attributed_to_the_running_image() {
+        let tracker = AllocationTracker::new();
+        let (first_image, second_image) = (0x1000 as efi::Handle, 0x2000 as efi::Handle);
+
+        tracker.record_allocation(allocation(0x10000, 0x10));
+        tracker.set_current_image(Some((first_image, "First.efi".to_string())));
+        tracker.record_allocation(allocation(0x20000, 0x20));
+        tracker.record_allocation(allocation(0x30000, 0x3000));
+        tracker.set_current_image(Some((second_image, "Second.efi".to_string())));
+        tracker.record_allocation(allocation(0x40000, 0x40));
+        tracker.record_allocation(allocation(0x50000, 0x50));
+        tracker.set_current_image(Some((first_image, "First.efi".to_string())));
+        tracker.record_allocation(allocation(0x60000, 0x60));
+        tracker.set_current_image(None);
+        tracker.record_allocation(allocation(0x70000, 0x70));
+
+        tracker.record_free(0x30000);
+        tracker.record_free(0x40000);
+        tracker.record_free(0x10000);
+
+        let outstanding = tracker.outstanding_by_image();
+        assert_eq!(outstanding.len(), 2);
+        assert_eq!(
+            outstanding[&(first_image as usize, Arc::from("First.efi"))],
+            [allocation(0x20000, 0x20), allocation(0x60000, 0x60)]
+        );
+        assert_eq!(outstanding[&(second_image as usize, Arc::from("Second.efi"))], [allocation(0x50000, 0x50)]);
+    }
+}
@@ -740,6 +740,12 @@ impl SpinLockedFixedSizeBlockAllocator {
     pub fn stats(&self) -> AllocationStatistics {
         *self.inner.lock().stats()
     }
+
+    /// Returns allocation statistics for this allocator, or `None` without waiting if it is locked.
+    #[cfg(any(target_os = "uefi", test))]
+    pub fn try_stats(&self) -> Option<AllocationStatistics> {
+        self.inner.try_lock().map(|allocator| *allocator.stats())
+    }
 }
 
 unsafe impl GlobalAlloc for SpinLockedFixedSizeBlockAllocator {
@@ -1571,6 +1577,12 @@ mod tests {
             assert_eq!(stats.reserved_used, 0);
             assert_eq!(stats.claimed_pages, 0);
 
+            // The stats are not waited for while the allocator is locked.
+            assert!(fsb.try_stats().is_some());
+            let guard = fsb.inner.lock();
+            assert!(fsb.try_stats().is_none());
+            drop(guard);
+
             //reserve some space and check the stats.
             fsb.reserve_memory_pages(uefi_size_to_pages!(MIN_EXPANSION * 2)).unwrap();
 
@@ -234,6 +234,12 @@ impl UefiAllocator {
     pub fn stats(&self) -> AllocationStatistics {
         self.allocator.stats()
     }
+
+    /// Returns the allocator stats, or `None` without waiting if the allocator is locked.
+    #[cfg(any(target_os = "uefi", test))]
+    pub fn try_stats(&self) -> Option<AllocationStatistics> {
+        self.allocator.try_stats()
+    }
 }
 
 unsafe impl GlobalAlloc for UefiAllocator {
@@ -23,6 +23,7 @@ use crate::{
     events::EVENT_DB,
     systemtables,
 };
+use patina::boot_services::allocation::MemoryAttributes;
 use r_efi::efi;
 
 // We cache the MAT here because we need to free it in whenever we get a new runtime code/data allocation
@@ -151,7 +152,7 @@ pub fn core_install_memory_attributes_table() {
             return;
         }
     };
-    let mat_allowed_attrs = efi::MEMORY_RO | efi::MEMORY_XP | efi::MEMORY_RUNTIME;
+    let mat_allowed_attrs = MemoryAttributes::RO | MemoryAttributes::XP | MemoryAttributes::RUNTIME;
 
     if desc_list.is_empty() {
         log::error!("Failed to install memory attributes table! Could not get memory map descriptors.");
@@ -166,17 +167,22 @@ pub fn core_install_memory_attributes_table() {
             match descriptor.r#type {
                 efi::RUNTIME_SERVICES_CODE | efi::RUNTIME_SERVICES_DATA => {
                     Some(efi::MemoryDescriptor {
-                        attribute: match descriptor.attribute & (efi::MEMORY_RO | efi::MEMORY_XP) {
+                        attribute: match MemoryAttributes::from(descriptor.attribute)
+                            & (MemoryAttributes::RO | MemoryAttributes::XP)
+                        {
                             // if we don't have any attributes set here, we should mark code as RO and XP. These are
                             // likely extra sections in the memory bins and so should not be used
                             // Data we will mark as XP only, as likely the caching attributes were changed, which
                             // dropped the XP attribute, so we need to set it here.
-                            0 if descriptor.r#type == efi::RUNTIME_SERVICES_CODE => mat_allowed_attrs,
-                            0 if descriptor.r#type == efi::RUNTIME_SERVICES_DATA => {
-                                efi::MEMORY_RUNTIME | efi::MEMORY_XP
+                            access if access.is_empty() && descriptor.r#type == efi::RUNTIME_SERVICES_CODE => {
+                                mat_allowed_attrs
+                            }
+                            access if access.is_empty() && descriptor.r#type == efi::RUNTIME_SERVICES_DATA => {
+                                MemoryAttributes::RUNTIME | MemoryAttributes::XP
                             }
-                            _ => descriptor.attribute & mat_allowed_attrs,
-                        },
+                            _ => MemoryAttributes::from(descriptor.attribute) & mat_allowed_attrs,
+                        }
+                        .bits(),
                         // use all other fields from the GCD descriptor
                         ..*descriptor
                     })
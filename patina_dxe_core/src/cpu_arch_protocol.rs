@@ -207,6 +207,10 @@ impl CpuArchProtocolInstaller {
             .inspect_err(|_| log::error!("Failed to install EFI_CPU_ARCH_PROTOCOL"))?;
         log::info!("installed EFI_CPU_ARCH_PROTOCOL_GUID");
 
+        // Platform timers registered through `InterruptManager::register_timer_interrupt` report their ticks to the
+        // core directly.
+        interrupts::set_timer_notify(Some(crate::events::timer_tick));
+
         Ok(())
     }
 }
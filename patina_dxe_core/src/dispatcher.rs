@@ -9,9 +9,10 @@
 use alloc::{
     boxed::Box,
     collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
     vec::Vec,
 };
-use core::{cmp::Ordering, ffi::c_void};
+use core::{cmp::Ordering, ffi::c_void, fmt::Write};
 use mu_rust_helpers::{function, guid::guid_fmt};
 use patina::pi::{fw_fs::ffs, protocols::firmware_volume_block};
 use patina::{
@@ -80,6 +81,24 @@ struct PendingDriver {
     security_status: efi::Status,
 }
 
+/// A driver that the dispatcher schedules once its dependency expression is satisfied.
+trait DispatchCandidate {
+    /// The file name the driver was discovered with, used to resolve BEFORE and AFTER associations.
+    fn file_name(&self) -> efi::Guid;
+    /// The dependency expression of the driver, if it has one.
+    fn depex_mut(&mut self) -> Option<&mut Depex>;
+}
+
+impl DispatchCandidate for PendingDriver {
+    fn file_name(&self) -> efi::Guid {
+        self.file_name
+    }
+
+    fn depex_mut(&mut self) -> Option<&mut Depex> {
+        self.depex.as_mut()
+    }
+}
+
 struct PendingFirmwareVolumeImage {
     parent_fv_handle: efi::Handle,
     file_name: efi::Guid,
@@ -140,6 +159,7 @@ struct DispatcherContext {
     associated_after: BTreeMap<OrdGuid, Vec<PendingDriver>>,
     processed_fvs: BTreeSet<efi::Handle>,
     section_extractor: CoreExtractor,
+    display_depex: bool,
 }
 
 impl DispatcherContext {
@@ -154,6 +174,7 @@ impl DispatcherContext {
             associated_after: BTreeMap::new(),
             processed_fvs: BTreeSet::new(),
             section_extractor: CoreExtractor::new(),
+            display_depex: false,
         }
     }
 }
@@ -175,41 +196,16 @@ pub fn dispatch() -> Result<bool, EfiError> {
             dispatcher.arch_protocols_available = Depex::from(ALL_ARCH_DEPEX).eval(&PROTOCOL_DB.registered_protocols());
         }
         let driver_candidates: Vec<_> = dispatcher.pending_drivers.drain(..).collect();
-        let mut scheduled_driver_candidates = Vec::new();
-        for mut candidate in driver_candidates {
-            log::trace!("Evaluating depex for candidate: {:?}", guid_fmt!(candidate.file_name));
-            let depex_satisfied = match candidate.depex {
-                Some(ref mut depex) => depex.eval(&PROTOCOL_DB.registered_protocols()),
-                None => dispatcher.arch_protocols_available,
-            };
-
-            if depex_satisfied {
-                scheduled_driver_candidates.push(candidate)
-            } else {
-                match candidate.depex.as_ref().map(|x| x.is_associated()) {
-                    Some(Some(AssociatedDependency::Before(guid))) => {
-                        dispatcher.associated_before.entry(OrdGuid(guid)).or_default().push(candidate)
-                    }
-                    Some(Some(AssociatedDependency::After(guid))) => {
-                        dispatcher.associated_after.entry(OrdGuid(guid)).or_default().push(candidate)
-                    }
-                    _ => dispatcher.pending_drivers.push(candidate),
-                }
-            }
-        }
-
-        // insert contents of associated_before/after at the appropriate point in the schedule if the associated driver is present.
-        scheduled = scheduled_driver_candidates
-            .into_iter()
-            .flat_map(|scheduled_driver| {
-                let filename = OrdGuid(scheduled_driver.file_name);
-                let mut list = dispatcher.associated_before.remove(&filename).unwrap_or_default();
-                let mut after_list = dispatcher.associated_after.remove(&filename).unwrap_or_default();
-                list.push(scheduled_driver);
-                list.append(&mut after_list);
-                list
-            })
-            .collect();
+        let arch_protocols_available = dispatcher.arch_protocols_available;
+        let dispatcher = &mut *dispatcher;
+        scheduled = schedule_candidates(
+            driver_candidates,
+            &PROTOCOL_DB.registered_protocols(),
+            arch_protocols_available,
+            &mut dispatcher.pending_drivers,
+            &mut dispatcher.associated_before,
+            &mut dispatcher.associated_after,
+        );
     }
     log::info!("Depex evaluation complete, scheduled {:} drivers", scheduled.len());
 
@@ -236,7 +232,14 @@ pub fn dispatch() -> Result<bool, EfiError> {
                     dispatch_attempted = true;
                     // Note: ignore error result of core_start_image here - an image returning an error code is expected in some
                     // cases, and a debug output for that is already implemented in core_start_image.
+                    let snapshot = log::log_enabled!(log::Level::Trace).then(|| PROTOCOL_DB.snapshot());
                     let _status = core_start_image(image_handle);
+                    if let Some(snapshot) = snapshot {
+                        let diff = PROTOCOL_DB.diff_since(&snapshot);
+                        if !diff.is_empty() {
+                            log::trace!("Protocol database changes from {:?}: {diff:#x?}", guid_fmt!(driver.file_name));
+                        }
+                    }
                 }
                 efi::Status::SECURITY_VIOLATION => {
                     log::info!(
@@ -262,9 +265,10 @@ pub fn dispatch() -> Result<bool, EfiError> {
         let fv_image_candidates: Vec<_> = dispatcher.pending_firmware_volume_images.drain(..).collect();
 
         for mut candidate in fv_image_candidates {
+            // As with drivers, an empty DEPEX is treated as if there were no DEPEX section.
             let depex_satisfied = match candidate.depex {
-                Some(ref mut depex) => depex.eval(&PROTOCOL_DB.registered_protocols()),
-                None => true,
+                Some(ref mut depex) if !depex.is_empty() => depex.eval(&PROTOCOL_DB.registered_protocols()),
+                _ => true,
             };
 
             if depex_satisfied && candidate.evaluate_auth().is_ok() {
@@ -297,6 +301,58 @@ pub fn dispatch() -> Result<bool, EfiError> {
     Ok(dispatch_attempted)
 }
 
+/// Evaluates the dependency expressions of `candidates` against `protocols`, returning the drivers to dispatch in
+/// order.
+///
+/// Drivers without a dependency expression, or with an empty one from a zero-length DEPEX section, are satisfied once
+/// `arch_protocols_available` is set, per the PI specification for DXE drivers without a DEPEX. Unsatisfied drivers
+/// are returned to `pending`, or held in `associated_before`/`associated_after` when they are BEFORE or AFTER drivers,
+/// to be scheduled around their associated driver once it is scheduled.
+fn schedule_candidates<T: DispatchCandidate>(
+    candidates: Vec<T>,
+    protocols: &[efi::Guid],
+    arch_protocols_available: bool,
+    pending: &mut Vec<T>,
+    associated_before: &mut BTreeMap<OrdGuid, Vec<T>>,
+    associated_after: &mut BTreeMap<OrdGuid, Vec<T>>,
+) -> Vec<T> {
+    let mut scheduled_candidates = Vec::new();
+    for mut candidate in candidates {
+        log::trace!("Evaluating depex for candidate: {:?}", guid_fmt!(candidate.file_name()));
+        let depex_satisfied = match candidate.depex_mut() {
+            Some(depex) if !depex.is_empty() => depex.eval(protocols),
+            _ => arch_protocols_available,
+        };
+
+        if depex_satisfied {
+            scheduled_candidates.push(candidate)
+        } else {
+            match candidate.depex_mut().and_then(|depex| depex.is_associated()) {
+                Some(AssociatedDependency::Before(guid)) => {
+                    associated_before.entry(OrdGuid(guid)).or_default().push(candidate)
+                }
+                Some(AssociatedDependency::After(guid)) => {
+                    associated_after.entry(OrdGuid(guid)).or_default().push(candidate)
+                }
+                _ => pending.push(candidate),
+            }
+        }
+    }
+
+    // insert contents of associated_before/after at the appropriate point in the schedule if the associated driver is present.
+    scheduled_candidates
+        .into_iter()
+        .flat_map(|scheduled_candidate| {
+            let filename = OrdGuid(scheduled_candidate.file_name());
+            let mut list = associated_before.remove(&filename).unwrap_or_default();
+            let mut after_list = associated_after.remove(&filename).unwrap_or_default();
+            list.push(scheduled_candidate);
+            list.append(&mut after_list);
+            list
+        })
+        .collect()
+}
+
 fn add_fv_handles(new_handles: Vec<efi::Handle>) -> Result<(), EfiError> {
     let mut dispatcher = DISPATCHER_CONTEXT.lock();
     for handle in new_handles {
@@ -475,6 +531,38 @@ pub fn core_schedule(handle: efi::Handle, file: &efi::Guid) -> Result<(), EfiErr
     Err(EfiError::NotFound)
 }
 
+/// Releases the schedule-on-request (SOR) driver with the given file name and runs the dispatcher.
+///
+/// This allows an external agent (for example, a setup UI enabling an optional driver) to release SOR drivers on
+/// demand, without knowing the firmware volume they were discovered in. Returns [`EfiError::NotFound`] if no pending
+/// SOR driver has the given file name.
+pub fn request_dispatch(file: &efi::Guid) -> Result<(), EfiError> {
+    if !schedule_on_request(&mut DISPATCHER_CONTEXT.lock().pending_drivers, file) {
+        return Err(EfiError::NotFound);
+    }
+
+    match core_dispatcher() {
+        // A dispatch pass is already running and will evaluate the released driver.
+        Err(EfiError::AlreadyStarted) => Ok(()),
+        result => result,
+    }
+}
+
+/// Removes the SOR opcode from the dependency expression of the pending SOR driver named `file_name`, so it is
+/// evaluated on the next dispatch pass. Returns whether such a driver was found.
+fn schedule_on_request<T: DispatchCandidate>(candidates: &mut [T], file_name: &efi::Guid) -> bool {
+    for candidate in candidates {
+        if OrdGuid(candidate.file_name()) == OrdGuid(*file_name)
+            && let Some(depex) = candidate.depex_mut()
+            && depex.is_sor()
+        {
+            depex.schedule();
+            return true;
+        }
+    }
+    false
+}
+
 pub fn core_trust(handle: efi::Handle, file: &efi::Guid) -> Result<(), EfiError> {
     let mut dispatcher = DISPATCHER_CONTEXT.lock();
     for driver in dispatcher.pending_drivers.iter_mut() {
@@ -518,10 +606,58 @@ pub fn register_section_extractor(extractor: Service<dyn SectionExtractor>) {
     DISPATCHER_CONTEXT.lock().section_extractor.set_extractor(extractor);
 }
 
+/// Sets whether [`display_discovered_not_dispatched`] also displays the DEPEX of each driver and the installed
+/// protocols.
+pub fn display_undispatched_depex(display: bool) {
+    DISPATCHER_CONTEXT.lock().display_depex = display;
+}
+
 pub fn display_discovered_not_dispatched() {
-    for driver in &DISPATCHER_CONTEXT.lock().pending_drivers {
+    let dispatcher = DISPATCHER_CONTEXT.lock();
+    for driver in &dispatcher.pending_drivers {
         log::warn!("Driver {:?} found but not dispatched.", guid_fmt!(driver.file_name));
     }
+
+    if dispatcher.display_depex && !dispatcher.pending_drivers.is_empty() {
+        let drivers = dispatcher.pending_drivers.iter().map(|driver| (driver.file_name, driver.depex.as_ref()));
+        log::warn!("{}", undispatched_depex_report(drivers, &PROTOCOL_DB.registered_protocols()));
+    }
+}
+
+/// Returns the file names of the drivers found but not dispatched.
+pub fn undispatched_drivers() -> Vec<efi::Guid> {
+    DISPATCHER_CONTEXT.lock().pending_drivers.iter().map(|driver| driver.file_name).collect()
+}
+
+/// Describes the disassembled DEPEX of each undispatched driver, followed by the currently installed protocols.
+fn undispatched_depex_report<'a>(
+    drivers: impl IntoIterator<Item = (efi::Guid, Option<&'a Depex>)>,
+    protocols: &[efi::Guid],
+) -> String {
+    let mut report = String::new();
+    for (file_name, depex) in drivers {
+        match depex {
+            Some(depex) if !depex.is_empty() => {
+                let _ = writeln!(report, "Driver {:?} DEPEX:", guid_fmt!(file_name));
+                for line in depex.disassemble().to_string().lines() {
+                    let _ = writeln!(report, "  {line}");
+                }
+            }
+            _ => {
+                let _ = writeln!(
+                    report,
+                    "Driver {:?} has no DEPEX, so requires all architectural protocols.",
+                    guid_fmt!(file_name)
+                );
+            }
+        }
+    }
+
+    let _ = write!(report, "Installed protocols:");
+    for protocol in protocols {
+        let _ = write!(report, "\n  {:?}", guid_fmt!(protocol));
+    }
+    report
 }
 
 /// Reset the dispatcher context to a clean default state for testing.
@@ -546,12 +682,133 @@ mod tests {
     use std::{fs::File, io::Read, vec};
 
     use log::{Level, LevelFilter, Metadata, Record};
+    use patina::base::guid::from_uuid;
     use patina_internal_device_path::DevicePathWalker;
-    use uuid::uuid;
+    use uuid::{Uuid, uuid};
 
     use super::*;
     use crate::test_collateral;
 
+    /// A synthetic driver for [`DispatchSimulation`] that installs `installs` when dispatched.
+    struct SimulatedDriver {
+        file_name: efi::Guid,
+        depex: Option<Depex>,
+        installs: Vec<efi::Guid>,
+    }
+
+    impl DispatchCandidate for SimulatedDriver {
+        fn file_name(&self) -> efi::Guid {
+            self.file_name
+        }
+
+        fn depex_mut(&mut self) -> Option<&mut Depex> {
+            self.depex.as_mut()
+        }
+    }
+
+    /// The outcome of a [`DispatchSimulation`].
+    #[derive(Debug, PartialEq)]
+    struct DispatchOrder {
+        /// The drivers dispatched by each pass of the dispatcher, in order.
+        waves: Vec<Vec<efi::Guid>>,
+        /// The drivers whose dependencies were never satisfied.
+        not_dispatched: Vec<efi::Guid>,
+    }
+
+    /// Drives the dispatcher scheduling over synthetic drivers and a simulated protocol database.
+    ///
+    /// Each wave installs the protocols scheduled for it, evaluates every pending driver against the installed
+    /// protocols, then dispatches the scheduled drivers, installing the protocols they produce. No images are loaded
+    /// and the global dispatcher state is not touched, so the resulting order is deterministic.
+    #[derive(Default)]
+    struct DispatchSimulation {
+        drivers: Vec<SimulatedDriver>,
+        installation_schedule: BTreeMap<usize, Vec<efi::Guid>>,
+        dispatch_requests: BTreeMap<usize, Vec<efi::Guid>>,
+    }
+
+    impl DispatchSimulation {
+        fn driver(mut self, file_name: Uuid, depex: Option<&[Opcode]>, installs: &[Uuid]) -> Self {
+            self.drivers.push(SimulatedDriver {
+                file_name: guid(file_name),
+                depex: depex.map(Depex::from),
+                installs: installs.iter().copied().map(guid).collect(),
+            });
+            self
+        }
+
+        fn install_at(mut self, wave: usize, protocols: &[Uuid]) -> Self {
+            self.installation_schedule.entry(wave).or_default().extend(protocols.iter().copied().map(guid));
+            self
+        }
+
+        fn request_dispatch_at(mut self, wave: usize, file_names: &[Uuid]) -> Self {
+            self.dispatch_requests.entry(wave).or_default().extend(file_names.iter().copied().map(guid));
+            self
+        }
+
+        fn run(mut self) -> DispatchOrder {
+            let mut pending = core::mem::take(&mut self.drivers);
+            let mut associated_before = BTreeMap::new();
+            let mut associated_after = BTreeMap::new();
+            let mut protocols = Vec::new();
+            let mut arch_protocols_available = false;
+            let mut waves = Vec::new();
+
+            fn install(protocols: &mut Vec<efi::Guid>, installs: impl IntoIterator<Item = efi::Guid>) {
+                for protocol in installs {
+                    if !protocols.contains(&protocol) {
+                        protocols.push(protocol);
+                    }
+                }
+            }
+
+            for wave in 0.. {
+                if let Some(installs) = self.installation_schedule.remove(&wave) {
+                    install(&mut protocols, installs);
+                }
+                for file_name in self.dispatch_requests.remove(&wave).unwrap_or_default() {
+                    assert!(schedule_on_request(&mut pending, &file_name), "no SOR driver to release in wave {wave}");
+                }
+                if !arch_protocols_available {
+                    arch_protocols_available = Depex::from(ALL_ARCH_DEPEX).eval(&protocols);
+                }
+
+                let candidates = core::mem::take(&mut pending);
+                let scheduled = schedule_candidates(
+                    candidates,
+                    &protocols,
+                    arch_protocols_available,
+                    &mut pending,
+                    &mut associated_before,
+                    &mut associated_after,
+                );
+                if scheduled.is_empty() && self.installation_schedule.is_empty() && self.dispatch_requests.is_empty() {
+                    break;
+                }
+
+                let mut order = Vec::new();
+                for driver in scheduled {
+                    install(&mut protocols, driver.installs);
+                    order.push(driver.file_name);
+                }
+                waves.push(order);
+            }
+
+            let not_dispatched = pending
+                .into_iter()
+                .chain(associated_before.into_values().flatten())
+                .chain(associated_after.into_values().flatten())
+                .map(|driver| driver.file_name)
+                .collect();
+            DispatchOrder { waves, not_dispatched }
+        }
+    }
+
+    fn guid(uuid: Uuid) -> efi::Guid {
+        from_uuid(&uuid)
+    }
+
     // Simple logger for log crate to dump stuff in tests
     struct SimpleLogger;
     impl log::Log for SimpleLogger {
@@ -824,11 +1081,41 @@ mod tests {
             add_fv_handles(vec![handle]).expect("Failed to add FV handle");
 
             display_discovered_not_dispatched();
+            display_undispatched_depex(true);
+            display_discovered_not_dispatched();
         });
 
         let _dropped_fv = unsafe { Box::from_raw(fv_raw) };
     }
 
+    #[test]
+    fn undispatched_depex_report_should_mention_the_missing_protocol() {
+        let installed = uuid!("26baccb1-6f42-11d4-bce7-0080c73c8881");
+        let missing = uuid!("665e3ff6-46cc-11d4-9a38-0090273fc14d");
+        let depex = Depex::from(
+            [Opcode::Push(installed, false), Opcode::Push(missing, false), Opcode::And, Opcode::End].as_slice(),
+        );
+
+        let report = undispatched_depex_report(
+            [
+                (guid(uuid!("a0b5f3d2-2fd1-4e2c-8f9b-7d1c5e6a4b30")), Some(&depex)),
+                (guid(uuid!("a0b5f3d2-2fd1-4e2c-8f9b-7d1c5e6a4b31")), None),
+            ],
+            &[guid(installed)],
+        );
+        assert_eq!(
+            report,
+            "Driver A0B5F3D2-2FD1-4E2C-8F9B-7D1C5E6A4B30 DEPEX:\n  \
+             PUSH 26BACCB1-6F42-11D4-BCE7-0080C73C8881\n  \
+             PUSH 665E3FF6-46CC-11D4-9A38-0090273FC14D\n  \
+             AND\n  \
+             END\n\
+             Driver A0B5F3D2-2FD1-4E2C-8F9B-7D1C5E6A4B31 has no DEPEX, so requires all architectural protocols.\n\
+             Installed protocols:\n  \
+             26BACCB1-6F42-11D4-BCE7-0080C73C8881"
+        );
+    }
+
     #[test]
     fn test_core_fw_col_event_protocol_notify() {
         set_logger();
@@ -911,10 +1198,8 @@ mod tests {
             add_fv_handles(vec![handle]).expect("Failed to add FV handle");
 
             // No SOR drivers to schedule in DXEFV, but we can test all the way to detecting that it does not have a SOR depex.
-            let result = core_schedule(
-                handle,
-                &efi::Guid::from_bytes(uuid::Uuid::from_u128(0x1fa1f39e_feff_4aae_bd7b_38a070a3b609).as_bytes()),
-            );
+            let result =
+                core_schedule(handle, &from_uuid(&uuid::Uuid::from_u128(0x1fa1f39e_feff_4aae_bd7b_38a070a3b609)));
             assert_eq!(result, Err(EfiError::NotFound));
         });
 
@@ -953,7 +1238,7 @@ mod tests {
                         file_node.header().sub_type,
                         efi::protocols::device_path::Media::SUBTYPE_PIWG_FIRMWARE_FILE
                     );
-                    assert_eq!(file_node.data(), uuid!("2DFBCBC7-14D6-4C70-A9C5-AD0AD03F4D75").to_bytes_le());
+                    assert_eq!(file_node.data(), from_uuid(&uuid!("2DFBCBC7-14D6-4C70-A9C5-AD0AD03F4D75")).as_bytes());
 
                     //device path end node
                     let end_node = node_walker.next().unwrap();
@@ -985,4 +1270,205 @@ mod tests {
             assert!(SECURITY_CALL_EXECUTED.load(core::sync::atomic::Ordering::SeqCst));
         })
     }
+
+    #[test]
+    fn test_simulated_dispatch_resolves_multi_wave_dependency_chain() {
+        const PROTOCOL_A: Uuid = uuid!("a0000000-0000-0000-0000-000000000000");
+        const PROTOCOL_B: Uuid = uuid!("b0000000-0000-0000-0000-000000000000");
+        const PROTOCOL_C: Uuid = uuid!("c0000000-0000-0000-0000-000000000000");
+        const PROTOCOL_D: Uuid = uuid!("d0000000-0000-0000-0000-000000000000");
+        const DRIVER_1: Uuid = uuid!("00000001-0000-0000-0000-000000000000");
+        const DRIVER_2: Uuid = uuid!("00000002-0000-0000-0000-000000000000");
+        const DRIVER_3: Uuid = uuid!("00000003-0000-0000-0000-000000000000");
+        const DRIVER_4: Uuid = uuid!("00000004-0000-0000-0000-000000000000");
+        const DRIVER_5: Uuid = uuid!("00000005-0000-0000-0000-000000000000");
+        const DRIVER_6: Uuid = uuid!("00000006-0000-0000-0000-000000000000");
+
+        // Declared in reverse so the order is driven by the dependencies, not discovery.
+        let order = DispatchSimulation::default()
+            .driver(DRIVER_6, None, &[])
+            .driver(DRIVER_5, Some(&[Opcode::After(DRIVER_2), Opcode::End]), &[])
+            .driver(DRIVER_4, Some(&[Opcode::Before(DRIVER_1), Opcode::End]), &[])
+            .driver(
+                DRIVER_3,
+                Some(&[Opcode::Push(PROTOCOL_C, false), Opcode::Push(PROTOCOL_D, false), Opcode::And, Opcode::End]),
+                &[],
+            )
+            .driver(DRIVER_2, Some(&[Opcode::Push(PROTOCOL_B, false), Opcode::End]), &[PROTOCOL_C])
+            .driver(DRIVER_1, Some(&[Opcode::Push(PROTOCOL_A, false), Opcode::End]), &[PROTOCOL_B])
+            .install_at(0, &[PROTOCOL_A])
+            .install_at(3, &[PROTOCOL_D])
+            .run();
+
+        assert_eq!(
+            order,
+            DispatchOrder {
+                waves: vec![
+                    vec![guid(DRIVER_4), guid(DRIVER_1)],
+                    vec![guid(DRIVER_2), guid(DRIVER_5)],
+                    vec![],
+                    vec![guid(DRIVER_3)],
+                ],
+                // Without a depex, the driver waits on the architectural protocols, which are never installed.
+                not_dispatched: vec![guid(DRIVER_6)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_simulated_dispatch_defers_drivers_without_depex_until_arch_protocols() {
+        const DRIVER_1: Uuid = uuid!("00000001-0000-0000-0000-000000000000");
+        const DRIVER_2: Uuid = uuid!("00000002-0000-0000-0000-000000000000");
+
+        let arch_protocols: Vec<Uuid> = ALL_ARCH_DEPEX
+            .iter()
+            .filter_map(|opcode| match opcode {
+                Opcode::Push(protocol, _) => Some(*protocol),
+                _ => None,
+            })
+            .collect();
+
+        let order = DispatchSimulation::default()
+            .driver(DRIVER_1, None, &[])
+            .driver(DRIVER_2, Some(&[Opcode::True, Opcode::End]), &[])
+            .install_at(1, &arch_protocols)
+            .run();
+
+        assert_eq!(
+            order,
+            DispatchOrder { waves: vec![vec![guid(DRIVER_2)], vec![guid(DRIVER_1)]], not_dispatched: vec![] }
+        );
+    }
+
+    #[test]
+    fn test_simulated_dispatch_treats_empty_depex_as_no_depex() {
+        const DRIVER_1: Uuid = uuid!("00000001-0000-0000-0000-000000000000");
+        const DRIVER_2: Uuid = uuid!("00000002-0000-0000-0000-000000000000");
+
+        let arch_protocols: Vec<Uuid> = ALL_ARCH_DEPEX
+            .iter()
+            .filter_map(|opcode| match opcode {
+                Opcode::Push(protocol, _) => Some(*protocol),
+                _ => None,
+            })
+            .collect();
+
+        // Without the architectural protocols, an empty depex is never satisfied.
+        let order = DispatchSimulation::default().driver(DRIVER_1, Some(&[]), &[]).run();
+        assert_eq!(order, DispatchOrder { waves: vec![], not_dispatched: vec![guid(DRIVER_1)] });
+
+        let order = DispatchSimulation::default()
+            .driver(DRIVER_1, Some(&[]), &[])
+            .driver(DRIVER_2, None, &[])
+            .install_at(2, &arch_protocols)
+            .run();
+        assert_eq!(
+            order,
+            DispatchOrder { waves: vec![vec![], vec![], vec![guid(DRIVER_1), guid(DRIVER_2)]], not_dispatched: vec![] }
+        );
+    }
+
+    #[test]
+    fn test_simulated_dispatch_holds_sor_drivers_until_requested() {
+        const PROTOCOL_A: Uuid = uuid!("a0000000-0000-0000-0000-000000000000");
+        const DRIVER_1: Uuid = uuid!("00000001-0000-0000-0000-000000000000");
+        const DRIVER_2: Uuid = uuid!("00000002-0000-0000-0000-000000000000");
+        const DRIVER_3: Uuid = uuid!("00000003-0000-0000-0000-000000000000");
+
+        let order = DispatchSimulation::default()
+            .driver(DRIVER_1, Some(&[Opcode::Sor, Opcode::Push(PROTOCOL_A, false), Opcode::End]), &[])
+            .driver(DRIVER_2, Some(&[Opcode::Push(PROTOCOL_A, false), Opcode::End]), &[])
+            .driver(DRIVER_3, Some(&[Opcode::Sor, Opcode::True, Opcode::End]), &[])
+            .install_at(0, &[PROTOCOL_A])
+            .request_dispatch_at(2, &[DRIVER_1])
+            .run();
+
+        assert_eq!(
+            order,
+            DispatchOrder {
+                waves: vec![vec![guid(DRIVER_2)], vec![], vec![guid(DRIVER_1)]],
+                // Never requested, so never released.
+                not_dispatched: vec![guid(DRIVER_3)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_request_dispatch_without_sor_driver() {
+        with_locked_state(|| {
+            let file =
+                efi::Guid::from_fields(0x1fa1f39e, 0xfeff, 0x4aae, 0xbd, 0x7b, &[0x38, 0xa0, 0x70, 0xa3, 0xb6, 0x09]);
+            assert_eq!(request_dispatch(&file), Err(EfiError::NotFound));
+        });
+    }
+
+    // Builds a pending driver that is already loaded, so dispatching it only attempts to start `image_handle`.
+    fn loaded_pending_driver(file_name: efi::Guid, depex: &[Opcode], image_handle: usize) -> PendingDriver {
+        PendingDriver {
+            firmware_volume_handle: core::ptr::null_mut(),
+            device_path: core::ptr::null_mut(),
+            file_name,
+            depex: Some(Depex::from(depex)),
+            pe32: Section::new_from_header_with_data(patina_ffs::section::SectionHeader::Pad(0), vec![]).unwrap(),
+            image_handle: Some(image_handle as efi::Handle),
+            security_status: efi::Status::SUCCESS,
+        }
+    }
+
+    fn pending_driver_is_sor(file_name: &efi::Guid) -> Option<bool> {
+        DISPATCHER_CONTEXT
+            .lock()
+            .pending_drivers
+            .iter_mut()
+            .find(|driver| OrdGuid(driver.file_name) == OrdGuid(*file_name))
+            .map(|driver| driver.depex.as_mut().is_some_and(|depex| depex.is_sor()))
+    }
+
+    #[test]
+    fn test_request_dispatch_dispatches_pending_sor_driver() {
+        with_locked_state(|| {
+            let file =
+                efi::Guid::from_fields(0x5a6b2d1e, 0x7c3f, 0x4e8a, 0x9b, 0x41, &[0x2d, 0x6e, 0x8f, 0x10, 0xa3, 0xc7]);
+            DISPATCHER_CONTEXT.lock().pending_drivers.push(loaded_pending_driver(
+                file,
+                &[Opcode::Sor, Opcode::True, Opcode::End],
+                0x1000,
+            ));
+
+            // Held by SOR until requested.
+            assert_eq!(core_dispatcher(), Err(EfiError::NotFound));
+            assert_eq!(pending_driver_is_sor(&file), Some(true));
+
+            // Released and dispatched on the next pass. The handle is not a real image, so starting it fails, but
+            // the dispatcher still consumes the driver.
+            assert_eq!(request_dispatch(&file), Ok(()));
+            assert_eq!(pending_driver_is_sor(&file), None);
+            assert!(DISPATCHER_CONTEXT.lock().pending_drivers.is_empty());
+
+            // No longer pending, so there is nothing left to release.
+            assert_eq!(request_dispatch(&file), Err(EfiError::NotFound));
+        });
+    }
+
+    #[test]
+    fn test_request_dispatch_on_already_released_driver() {
+        with_locked_state(|| {
+            let file =
+                efi::Guid::from_fields(0x0e4c7b92, 0x3d15, 0x4f7a, 0x8c, 0x26, &[0x51, 0x9a, 0xe3, 0x07, 0xb4, 0x6d]);
+            const UNINSTALLED_PROTOCOL: Uuid = uuid!("9f3e5a17-2b84-4c6d-a158-7e30c9421fb6");
+            DISPATCHER_CONTEXT.lock().pending_drivers.push(loaded_pending_driver(
+                file,
+                &[Opcode::Sor, Opcode::Push(UNINSTALLED_PROTOCOL, false), Opcode::End],
+                0x2000,
+            ));
+
+            // Released, but its dependency is not installed, so it stays pending without the SOR.
+            assert_eq!(request_dispatch(&file), Err(EfiError::NotFound));
+            assert_eq!(pending_driver_is_sor(&file), Some(false));
+
+            // A second request finds no SOR driver to release and leaves the driver pending.
+            assert_eq!(request_dispatch(&file), Err(EfiError::NotFound));
+            assert_eq!(pending_driver_is_sor(&file), Some(false));
+        });
+    }
 }
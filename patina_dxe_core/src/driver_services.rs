@@ -539,6 +539,7 @@ mod tests {
     use crate::test_support;
     use core::ffi::c_void;
     use core::ptr;
+    use patina::base::guid;
     use std::str::FromStr;
     use std::sync::atomic::AtomicUsize;
     use std::sync::atomic::Ordering;
@@ -655,7 +656,7 @@ mod tests {
         // Create a unique image handle by installing a protocol with arbitrary GUID
         // This is safer than arithmetic that could overflow
         let test_uuid = Uuid::from_str("12345678-1234-5678-9abc-def012345678").unwrap();
-        let test_guid = efi::Guid::from_bytes(test_uuid.as_bytes());
+        let test_guid = guid::from_uuid(&test_uuid);
         let image_handle = match PROTOCOL_DB.install_protocol_interface(
             None,
             test_guid,
@@ -1371,7 +1372,7 @@ mod tests {
     fn test_core_disconnect_controller() {
         with_locked_state(|| {
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
             let (handle1, _) = PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
 
@@ -1447,7 +1448,7 @@ mod tests {
             static LAST_CHILD_COUNT: AtomicUsize = AtomicUsize::new(0);
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
             let (handle1, _) = PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
 
@@ -1560,7 +1561,7 @@ mod tests {
             static DRIVER_STOP_CALLED: AtomicUsize = AtomicUsize::new(0); // Track full driver stops
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
             let (handle1, _) = PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
 
@@ -486,6 +486,7 @@ mod tests {
     use super::*;
     use crate::test_support;
     use dxe_services::{GcdIoType, GcdMemoryType};
+    use patina::base::guid;
 
     fn with_locked_state<F: Fn() + std::panic::RefUnwindSafe>(f: F) {
         test_support::with_global_lock(|| {
@@ -2136,7 +2137,7 @@ mod tests {
             let handle = unsafe { crate::fv::core_install_firmware_volume(fv.as_ptr() as u64, None).unwrap() };
 
             // Use the same GUID as the dispatcher tests; wrapper should map NotFound correctly
-            let file_guid = efi::Guid::from_bytes(Uuid::from_u128(0x1fa1f39e_feff_4aae_bd7b_38a070a3b609).as_bytes());
+            let file_guid = guid::from_uuid(&Uuid::from_u128(0x1fa1f39e_feff_4aae_bd7b_38a070a3b609));
             let s = schedule(handle, &file_guid);
             assert_eq!(s, efi::Status::NOT_FOUND);
         });
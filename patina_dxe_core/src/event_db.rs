@@ -153,7 +153,8 @@ impl fmt::Debug for EventNotification {
 
 //This type is necessary because the HeapSort used to order BTreeSet is not stable with respect
 //to insertion order. So we have to tag each event notification as it is added so that we can
-//use insertion order as part of the element comparison.
+//use insertion order as part of the element comparison. Tags are unique, so the ordering is a
+//strict total order: higher TPL first, then FIFO (lowest tag first) within the same TPL.
 #[derive(Debug, Clone)]
 struct TaggedEventNotification(EventNotification, u64);
 
@@ -165,24 +166,14 @@ impl PartialOrd for TaggedEventNotification {
 
 impl Ord for TaggedEventNotification {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.0.event.cmp(&other.0.event) {
-            // events are unique - so if event ids match, they are the same event.
-            Ordering::Equal => Ordering::Equal,
-            _ =>
-            // Higher TPL has precedence; if TPLs are equal, then earlier insertion has precedence.
-            {
-                match other.0.notify_tpl.cmp(&self.0.notify_tpl) {
-                    Ordering::Equal => self.1.cmp(&other.1),
-                    non_eq => non_eq,
-                }
-            }
-        }
+        // Higher TPL has precedence; if TPLs are equal, then earlier insertion has precedence.
+        other.0.notify_tpl.cmp(&self.0.notify_tpl).then_with(|| self.1.cmp(&other.1))
     }
 }
 
 impl PartialEq for TaggedEventNotification {
     fn eq(&self, other: &Self) -> bool {
-        self.0.event == other.0.event
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -351,7 +342,12 @@ impl EventDb {
     }
 
     //private helper function for signal_event.
+    //an event that already has a pending notify keeps its existing place in the queue, so that same-TPL notifies
+    //are always dispatched in the order they were first queued.
     fn queue_notify_event(pending_notifies: &mut BTreeSet<TaggedEventNotification>, event: &mut Event, tag: u64) {
+        if pending_notifies.iter().any(|pending| pending.0.event == event.efi_event()) {
+            return;
+        }
         if event.event_type.is_notify_signal() || event.event_type.is_notify_wait() {
             pending_notifies.insert(TaggedEventNotification(
                 EventNotification {
@@ -481,6 +477,25 @@ impl EventDb {
         }
     }
 
+    fn pending_timer_events(&self) -> Vec<efi::Event> {
+        self.events
+            .values()
+            .filter(|event| event.event_type.is_timer() && event.trigger_time.is_some())
+            .map(Event::efi_event)
+            .collect()
+    }
+
+    fn cancel_all_timers(&mut self) -> usize {
+        let mut cancelled = 0;
+        for event in self.events.values_mut().filter(|event| event.event_type.is_timer()) {
+            if event.trigger_time.take().is_some() {
+                cancelled += 1;
+            }
+            event.period = None;
+        }
+        cancelled
+    }
+
     fn timer_tick(&mut self, current_time: u64) {
         // Poll the debugger before processing any events. This has no effect if
         // the debugger is not enabled.
@@ -713,8 +728,29 @@ impl SpinLockedEventDb {
         self.lock().timer_tick(current_time);
     }
 
+    /// Returns the timer events that are armed and have not yet expired.
+    ///
+    /// Periodic timers are always pending until cancelled; one-shot timers are pending until they expire. The events
+    /// remain owned by their creators, so an event returned here may be closed before it is used.
+    pub fn pending_timer_events(&self) -> Vec<efi::Event> {
+        self.lock().pending_timer_events()
+    }
+
+    /// Cancels every pending timer, as if [`set_timer`](SpinLockedEventDb::set_timer) was called with
+    /// [`TimerDelay::Cancel`] on each of them. Returns the number of timers cancelled.
+    ///
+    /// This is used when handing off the environment, so that a stale timer callback does not fire into torn-down
+    /// state. Notifications already queued by expired timers are not affected.
+    pub fn cancel_all_timers(&self) -> usize {
+        self.lock().cancel_all_timers()
+    }
+
     /// Returns the next pending event notification (if any) that should be dispatched at or above the given TPL level.
     ///
+    /// Notifications are returned highest TPL first. Notifications at the same TPL are returned in the order they were
+    /// queued (FIFO); queueing an event that already has a pending notification does not change its position. Members
+    /// of an event group are queued in reverse creation order when the group is signaled, matching EDK II.
+    ///
     /// Events can be added to the pending queue directly via
     /// [`queue_event_notify`](SpinLockedEventDb::queue_event_notify) or via timer expiration configured via
     /// [`set_timer`](SpinLockedEventDb::set_timer) followed by a [`timer_tick`](SpinLockedEventDb::timer_tick) that
@@ -741,7 +777,7 @@ mod tests {
     use core::str::FromStr;
 
     use alloc::{vec, vec::Vec};
-    use patina::Guid;
+    use patina::{Guid, base::guid};
     use r_efi::efi;
     use uuid::Uuid;
 
@@ -934,11 +970,11 @@ mod tests {
     fn signal_event_on_an_event_group_should_put_all_members_in_signaled_state() {
         with_locked_state(|| {
             let uuid = Uuid::from_str("aefcf33c-ce02-47b4-89f6-4bacdeda3377").unwrap();
-            let group1 = efi::Guid::from_bytes(uuid.as_bytes());
+            let group1 = guid::from_uuid(&uuid);
             let uuid = Uuid::from_str("3a08a8c7-054b-4268-8aed-bc6a3aef999f").unwrap();
-            let group2 = efi::Guid::from_bytes(uuid.as_bytes());
+            let group2 = guid::from_uuid(&uuid);
             let uuid = Uuid::from_str("745e8316-4889-4f58-be3c-6b718b7170ec").unwrap();
-            let group3 = efi::Guid::from_bytes(uuid.as_bytes());
+            let group3 = guid::from_uuid(&uuid);
 
             static SPIN_LOCKED_EVENT_DB: SpinLockedEventDb = SpinLockedEventDb::new();
             let mut group1_events: Vec<efi::Event> = Vec::new();
@@ -1386,6 +1422,41 @@ mod tests {
         });
     }
 
+    #[test]
+    fn same_tpl_notifies_should_be_consumed_in_fifo_order() {
+        with_locked_state(|| {
+            static SPIN_LOCKED_EVENT_DB: SpinLockedEventDb = SpinLockedEventDb::new();
+
+            let events: Vec<efi::Event> = (0..8)
+                .map(|_| {
+                    SPIN_LOCKED_EVENT_DB
+                        .create_event(
+                            efi::EVT_TIMER | efi::EVT_NOTIFY_SIGNAL,
+                            efi::TPL_CALLBACK,
+                            Some(test_notify_function),
+                            None,
+                            None,
+                        )
+                        .unwrap()
+                })
+                .collect();
+
+            for event in &events {
+                SPIN_LOCKED_EVENT_DB.signal_event(*event).unwrap();
+            }
+            // Re-queueing already pending notifies must not move them in the queue.
+            for event in events.iter().rev() {
+                SPIN_LOCKED_EVENT_DB.queue_event_notify(*event).unwrap();
+            }
+
+            let consumed: Vec<efi::Event> =
+                iter::from_fn(|| SPIN_LOCKED_EVENT_DB.consume_next_event_notify(efi::TPL_APPLICATION))
+                    .map(|notify| notify.event)
+                    .collect();
+            assert_eq!(consumed, events);
+        });
+    }
+
     #[test]
     fn read_and_clear_signaled_should_clear_signal() {
         with_locked_state(|| {
@@ -1760,4 +1831,50 @@ mod tests {
             assert_eq!(event_iter.count(), 0);
         });
     }
+
+    #[test]
+    fn cancel_all_timers_should_clear_pending_timers() {
+        with_locked_state(|| {
+            static SPIN_LOCKED_EVENT_DB: SpinLockedEventDb = SpinLockedEventDb::new();
+            let create_timer = || {
+                SPIN_LOCKED_EVENT_DB
+                    .create_event(
+                        efi::EVT_TIMER | efi::EVT_NOTIFY_SIGNAL,
+                        efi::TPL_NOTIFY,
+                        Some(test_notify_function),
+                        None,
+                        None,
+                    )
+                    .unwrap()
+            };
+            let relative = create_timer();
+            let periodic = create_timer();
+            let unarmed = create_timer();
+            let not_a_timer = SPIN_LOCKED_EVENT_DB
+                .create_event(efi::EVT_NOTIFY_SIGNAL, efi::TPL_NOTIFY, Some(test_notify_function), None, None)
+                .unwrap();
+
+            SPIN_LOCKED_EVENT_DB.set_timer(relative, TimerDelay::Relative, Some(0x100), None).unwrap();
+            SPIN_LOCKED_EVENT_DB.set_timer(periodic, TimerDelay::Periodic, Some(0x200), Some(0x200)).unwrap();
+
+            let pending = SPIN_LOCKED_EVENT_DB.pending_timer_events();
+            assert_eq!(pending.len(), 2);
+            assert!(pending.contains(&relative) && pending.contains(&periodic));
+            assert!(!pending.contains(&unarmed) && !pending.contains(&not_a_timer));
+
+            assert_eq!(SPIN_LOCKED_EVENT_DB.cancel_all_timers(), 2);
+            assert!(SPIN_LOCKED_EVENT_DB.pending_timer_events().is_empty());
+            assert_eq!(SPIN_LOCKED_EVENT_DB.cancel_all_timers(), 0);
+
+            //cancelled timers should no longer fire.
+            SPIN_LOCKED_EVENT_DB.timer_tick(0x1000);
+            let event_iter = iter::from_fn(|| SPIN_LOCKED_EVENT_DB.consume_next_event_notify(efi::TPL_APPLICATION));
+            assert_eq!(event_iter.count(), 0);
+
+            //the events themselves remain valid and can be re-armed.
+            assert!(SPIN_LOCKED_EVENT_DB.is_valid(relative));
+            SPIN_LOCKED_EVENT_DB.set_timer(relative, TimerDelay::Relative, Some(0x1100), None).unwrap();
+            assert_eq!(SPIN_LOCKED_EVENT_DB.pending_timer_events(), [relative]);
+        });
+    }
 }
@@ -6,6 +6,7 @@
 //!
 //! SPDX-License-Identifier: Apache-2.0
 //!
+use alloc::vec::Vec;
 use core::{
     ffi::c_void,
     sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
@@ -18,8 +19,8 @@ use patina::pi::protocols::timer;
 use patina_internal_cpu::interrupts;
 
 use crate::{
-    event_db::{SpinLockedEventDb, TimerDelay},
-    gcd,
+    event_db::{EventNotification, SpinLockedEventDb, TimerDelay},
+    gcd, perf_timer_us,
     protocols::PROTOCOL_DB,
 };
 
@@ -27,6 +28,7 @@ pub static EVENT_DB: SpinLockedEventDb = SpinLockedEventDb::new();
 
 static CURRENT_TPL: AtomicUsize = AtomicUsize::new(efi::TPL_APPLICATION);
 static SYSTEM_TIME: AtomicU64 = AtomicU64::new(0);
+static SLOW_NOTIFY_THRESHOLD_US: AtomicU64 = AtomicU64::new(0);
 
 extern "efiapi" fn create_event(
     event_type: u32,
@@ -276,7 +278,7 @@ pub extern "efiapi" fn restore_tpl(new_tpl: efi::Tpl) {
             //callbacks as "unsafe", and the r_efi definition for EventNotify would need to
             //change.
             if let Some(notify_function) = event.notify_function {
-                (notify_function)(event.event, notify_context);
+                call_notify_function(&event, notify_function, notify_context, perf_timer_us);
             }
         }
     }
@@ -287,7 +289,73 @@ pub extern "efiapi" fn restore_tpl(new_tpl: efi::Tpl) {
     CURRENT_TPL.store(new_tpl, Ordering::SeqCst);
 }
 
-extern "efiapi" fn timer_tick(time: u64) {
+/// Sets the time, in microseconds, beyond which an event notify function is logged as slow. Zero disables timing
+/// notify functions, which is the default.
+pub fn set_slow_notify_threshold(threshold_us: u64) {
+    SLOW_NOTIFY_THRESHOLD_US.store(threshold_us, Ordering::Relaxed);
+}
+
+/// Returns the timer events that are armed and have not yet expired.
+///
+/// Outstanding timers are cancelled at ExitBootServices, so this allows a platform to find the timers that would be
+/// cancelled, for example to cancel its own before handing off.
+pub fn pending_timer_events() -> Vec<efi::Event> {
+    EVENT_DB.pending_timer_events()
+}
+
+/// A notify function that ran longer than the slow notify threshold. Displays as the warning logged for it.
+struct SlowNotify {
+    notify_function: usize,
+    event: efi::Event,
+    notify_tpl: efi::Tpl,
+    elapsed_us: u64,
+    threshold_us: u64,
+}
+
+impl core::fmt::Display for SlowNotify {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Notify function {:#x} of event {:?} ran for {}us at TPL {:#x}, exceeding {}us.",
+            self.notify_function, self.event, self.elapsed_us, self.notify_tpl, self.threshold_us
+        )
+    }
+}
+
+/// Calls the notify function of `event`, timing it with `now` (in microseconds) if a slow notify threshold is set.
+///
+/// If the notify function ran longer than the threshold, a warning identifying the event is logged and returned.
+fn call_notify_function(
+    event: &EventNotification,
+    notify_function: efi::EventNotify,
+    notify_context: *mut c_void,
+    now: fn() -> u64,
+) -> Option<SlowNotify> {
+    let threshold_us = SLOW_NOTIFY_THRESHOLD_US.load(Ordering::Relaxed);
+    if threshold_us == 0 {
+        (notify_function)(event.event, notify_context);
+        return None;
+    }
+
+    let start = now();
+    (notify_function)(event.event, notify_context);
+    let elapsed_us = now().saturating_sub(start);
+    if elapsed_us <= threshold_us {
+        return None;
+    }
+
+    let slow = SlowNotify {
+        notify_function: notify_function as usize,
+        event: event.event,
+        notify_tpl: event.notify_tpl,
+        elapsed_us,
+        threshold_us,
+    };
+    log::warn!("{slow}");
+    Some(slow)
+}
+
+pub(crate) extern "efiapi" fn timer_tick(time: u64) {
     let old_tpl = raise_tpl(efi::TPL_HIGH_LEVEL);
     SYSTEM_TIME.fetch_add(time, Ordering::SeqCst);
     let current_time = SYSTEM_TIME.load(Ordering::SeqCst);
@@ -372,6 +440,48 @@ mod tests {
         NOTIFY_CALLED.store(true, Ordering::SeqCst);
     }
 
+    // A clock for timing notify functions, advanced only by the notify functions themselves.
+    static SCRIPTED_CLOCK_US: AtomicU64 = AtomicU64::new(0);
+    fn scripted_now() -> u64 {
+        SCRIPTED_CLOCK_US.load(Ordering::SeqCst)
+    }
+    extern "efiapi" fn slow_notify(_event: efi::Event, _context: *mut c_void) {
+        SCRIPTED_CLOCK_US.fetch_add(5_000, Ordering::SeqCst);
+    }
+    extern "efiapi" fn fast_notify(_event: efi::Event, _context: *mut c_void) {
+        SCRIPTED_CLOCK_US.fetch_add(10, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn slow_notify_functions_should_be_flagged() {
+        with_locked_state(|| {
+            let notification = |notify_function| EventNotification {
+                event: 0x1234 as efi::Event,
+                notify_tpl: efi::TPL_NOTIFY,
+                notify_function: Some(notify_function),
+                notify_context: None,
+            };
+            let call = |notify_function| {
+                call_notify_function(&notification(notify_function), notify_function, ptr::null_mut(), scripted_now)
+            };
+
+            // Notify functions are not timed by default.
+            assert!(call(slow_notify).is_none());
+
+            set_slow_notify_threshold(1_000);
+            let slow = call(slow_notify).expect("The slow notify function was not flagged.");
+            assert_eq!(
+                slow.to_string(),
+                format!(
+                    "Notify function {:#x} of event 0x1234 ran for 5000us at TPL 0x10, exceeding 1000us.",
+                    slow_notify as usize
+                )
+            );
+            assert!(call(fast_notify).is_none());
+            set_slow_notify_threshold(0);
+        });
+    }
+
     #[test]
     fn test_create_event_null_event_pointer() {
         with_locked_state(|| {
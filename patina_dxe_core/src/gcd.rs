@@ -10,11 +10,12 @@ mod io_block;
 mod memory_block;
 mod spin_locked_gcd;
 
+use alloc::vec::Vec;
 use core::{ffi::c_void, ops::Range, panic};
 use patina::base::{align_down, align_up};
 use patina::error::EfiError;
 use patina::pi::{
-    dxe_services::{GcdIoType, GcdMemoryType},
+    dxe_services::{GcdIoType, GcdMemoryType, MemorySpaceDescriptor},
     hob::{self, Hob, HobList, PhaseHandoffInformationTable, ResourceDescriptorV2},
 };
 use patina_paging::MemoryAttributes;
@@ -267,6 +268,51 @@ fn remove_range_overlap<T: PartialOrd + Copy>(a: &Range<T>, b: &Range<T>) -> [Op
     }
 }
 
+/// Returns each pair of consecutive descriptors in `descriptors` where the second starts before the end of the first.
+///
+/// The descriptors of a consistent memory space map are in address order and do not overlap, so each pair returned is
+/// either an overlap or out of order.
+pub(crate) fn find_memory_descriptor_overlaps(
+    descriptors: &[MemorySpaceDescriptor],
+) -> Vec<(MemorySpaceDescriptor, MemorySpaceDescriptor)> {
+    descriptors
+        .windows(2)
+        .filter(|pair| pair[1].base_address < pair[0].base_address.saturating_add(pair[0].length))
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+/// Returns a snapshot of the GCD memory space map as EFI memory descriptors, such as for a measured boot log.
+///
+/// See [`SpinLockedGcd::memory_descriptors`] for how GCD memory types map to EFI memory types.
+pub fn memory_descriptors() -> Vec<efi::MemoryDescriptor> {
+    GCD.memory_descriptors()
+}
+
+/// Logs each overlapping or out of order descriptor in the GCD memory space map, returning how many were found.
+pub fn report_memory_descriptor_overlaps() -> usize {
+    let mut descriptors = Vec::with_capacity(GCD.memory_descriptor_count() + 10);
+    if let Err(err) = GCD.get_memory_descriptors(&mut descriptors) {
+        log::error!("Failed to get the GCD memory descriptors to check for overlaps: {err:?}");
+        return 0;
+    }
+
+    let overlaps = find_memory_descriptor_overlaps(&descriptors);
+    for (first, second) in &overlaps {
+        let kind = if second.base_address < first.base_address { "out of order with" } else { "overlaps" };
+        log::error!(
+            "GCD memory descriptor [{:#x?}, {:#x?}) {:?} {kind} [{:#x?}, {:#x?}) {:?}",
+            second.base_address,
+            second.base_address.saturating_add(second.length),
+            second.memory_type,
+            first.base_address,
+            first.base_address.saturating_add(first.length),
+            first.memory_type,
+        );
+    }
+    overlaps.len()
+}
+
 #[cfg(feature = "compatibility_mode_allowed")]
 /// This activates compatibility mode for the GCD.
 /// This will:
@@ -345,7 +391,9 @@ mod tests {
         test_support::{self, build_test_hob_list},
     };
 
-    use super::add_hob_resource_descriptors_to_gcd;
+    use super::{
+        add_hob_resource_descriptors_to_gcd, find_memory_descriptor_overlaps, report_memory_descriptor_overlaps,
+    };
 
     const MEM_SIZE: u64 = 0x200000;
 
@@ -418,6 +466,39 @@ mod tests {
             hob_list.discover_hobs(physical_hob_list);
 
             add_resource_descriptors_should_add_resource_descriptors(&hob_list, physical_hob_list as u64);
+            assert_eq!(report_memory_descriptor_overlaps(), 0);
         });
     }
+
+    fn memory_descriptor(base_address: u64, length: u64, memory_type: GcdMemoryType) -> MemorySpaceDescriptor {
+        MemorySpaceDescriptor {
+            base_address,
+            length,
+            capabilities: 0,
+            attributes: 0,
+            memory_type,
+            image_handle: core::ptr::null_mut(),
+            device_handle: core::ptr::null_mut(),
+        }
+    }
+
+    #[test]
+    fn test_find_memory_descriptor_overlaps_reports_both_ranges() {
+        let system = memory_descriptor(0x1000, 0x3000, GcdMemoryType::SystemMemory);
+        let overlapping = memory_descriptor(0x3000, 0x2000, GcdMemoryType::Reserved);
+        let adjacent = memory_descriptor(0x5000, 0x1000, GcdMemoryType::MemoryMappedIo);
+
+        assert!(find_memory_descriptor_overlaps(&[system, adjacent]).is_empty());
+        assert_eq!(find_memory_descriptor_overlaps(&[system, overlapping, adjacent]), [(system, overlapping)]);
+    }
+
+    #[test]
+    fn test_find_memory_descriptor_overlaps_reports_out_of_order_descriptors() {
+        let low = memory_descriptor(0x1000, 0x1000, GcdMemoryType::SystemMemory);
+        let high = memory_descriptor(0x8000, 0x1000, GcdMemoryType::SystemMemory);
+        let top = memory_descriptor(u64::MAX - 0xFFF, 0x1000, GcdMemoryType::Reserved);
+
+        assert_eq!(find_memory_descriptor_overlaps(&[high, low]), [(high, low)]);
+        assert!(find_memory_descriptor_overlaps(&[low, high, top]).is_empty());
+    }
 }
@@ -28,6 +28,7 @@ use crate::{
     GCD, allocator::DEFAULT_ALLOCATION_STRATEGY, ensure, error, events::EVENT_DB, protocol_db,
     protocol_db::INVALID_HANDLE, tpl_lock,
 };
+use patina::boot_services::allocation::MemoryAttributes as EfiMemoryAttributes;
 use patina_internal_cpu::paging::create_cpu_paging;
 use patina_paging::{MemoryAttributes, PageTable, PtError, PtResult, page_allocator::PageAllocator};
 
@@ -77,47 +78,47 @@ struct GcdAttributeConversionEntry {
 const ATTRIBUTE_CONVERSION_TABLE: [GcdAttributeConversionEntry; 15] = [
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_UNCACHEABLE,
-        capability: efi::MEMORY_UC,
+        capability: EfiMemoryAttributes::UC.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_UNCACHED_EXPORTED,
-        capability: efi::MEMORY_UCE,
+        capability: EfiMemoryAttributes::UCE.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_WRITE_COMBINEABLE,
-        capability: efi::MEMORY_WC,
+        capability: EfiMemoryAttributes::WC.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_WRITE_THROUGH_CACHEABLE,
-        capability: efi::MEMORY_WT,
+        capability: EfiMemoryAttributes::WT.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_WRITE_BACK_CACHEABLE,
-        capability: efi::MEMORY_WB,
+        capability: EfiMemoryAttributes::WB.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_READ_PROTECTABLE,
-        capability: efi::MEMORY_RP,
+        capability: EfiMemoryAttributes::RP.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_WRITE_PROTECTABLE,
-        capability: efi::MEMORY_WP,
+        capability: EfiMemoryAttributes::WP.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_EXECUTION_PROTECTABLE,
-        capability: efi::MEMORY_XP,
+        capability: EfiMemoryAttributes::XP.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
         attribute: hob::EFI_RESOURCE_ATTRIBUTE_READ_ONLY_PROTECTABLE,
-        capability: efi::MEMORY_RO,
+        capability: EfiMemoryAttributes::RO.bits(),
         memory: true,
     },
     GcdAttributeConversionEntry {
@@ -285,8 +286,8 @@ struct GCD {
     allocate_memory_space_fn: GcdAllocateFn,
     free_memory_space_fn: GcdFreeFn,
     /// Default attributes for memory allocations
-    /// This is efi::MEMORY_XP unless we have entered compatibility mode, in which case it is 0, e.g. no protection
-    default_attributes: u64,
+    /// This is XP unless we have entered compatibility mode, in which case it is empty, e.g. no protection
+    default_attributes: EfiMemoryAttributes,
     /// Whether to prioritize 32-bit memory allocations
     prioritize_32_bit_memory: bool,
 }
@@ -317,7 +318,7 @@ impl GCD {
             maximum_address: 1 << processor_address_bits,
             allocate_memory_space_fn: Self::allocate_memory_space_internal,
             free_memory_space_fn: Self::free_memory_space_worker,
-            default_attributes: efi::MEMORY_XP,
+            default_attributes: EfiMemoryAttributes::XP,
             prioritize_32_bit_memory: false,
         }
     }
@@ -372,7 +373,7 @@ impl GCD {
         match self.set_memory_space_attributes(
             base_address,
             len,
-            (MemoryAttributes::Writeback | MemoryAttributes::ExecuteProtect).bits(),
+            (EfiMemoryAttributes::WB | EfiMemoryAttributes::XP).bits(),
         ) {
             Ok(_) | Err(EfiError::NotReady) => Ok(()),
             Err(err) => Err(err),
@@ -393,7 +394,7 @@ impl GCD {
             match self.set_memory_space_attributes(
                 base_address + MEMORY_BLOCK_SLICE_SIZE,
                 len - MEMORY_BLOCK_SLICE_SIZE,
-                (MemoryAttributes::Writeback | MemoryAttributes::ReadProtect).bits(),
+                (EfiMemoryAttributes::WB | EfiMemoryAttributes::RP).bits(),
             ) {
                 Ok(_) | Err(EfiError::NotReady) => Ok(()),
                 Err(err) => Err(err),
@@ -428,11 +429,11 @@ impl GCD {
         log::trace!(target: "allocations", "[{}]   Capabilities: {:#x}\n", function!(), capabilities);
 
         // All software capabilities are supported for system memory
-        capabilities |= efi::MEMORY_ACCESS_MASK | efi::MEMORY_RUNTIME;
+        capabilities |= (EfiMemoryAttributes::ACCESS_MASK | EfiMemoryAttributes::RUNTIME).bits();
 
         // The MEMORY_MAPPED_IO_PORT_SPACE attribute should be supported for MMIO
         if memory_type == dxe_services::GcdMemoryType::MemoryMappedIo {
-            capabilities |= efi::MEMORY_ISA_VALID;
+            capabilities |= EfiMemoryAttributes::ISA_VALID.bits();
         }
 
         if self.memory_blocks.capacity() == 0 {
@@ -452,7 +453,7 @@ impl GCD {
             idx,
             base_address,
             len,
-            MemoryStateTransition::Add(memory_type, capabilities, efi::MEMORY_RP),
+            MemoryStateTransition::Add(memory_type, capabilities, EfiMemoryAttributes::RP.bits()),
         ) {
             Ok(idx) => Ok(idx),
             Err(InternalError::MemoryBlock(MemoryBlockError::BlockOutsideRange)) => error!(EfiError::AccessDenied),
@@ -602,7 +603,7 @@ impl GCD {
         match self.set_gcd_memory_attributes(
             base_address,
             len,
-            efi::MEMORY_RP | (desc.attributes & efi::CACHE_ATTRIBUTE_MASK),
+            (EfiMemoryAttributes::RP | EfiMemoryAttributes::from(desc.attributes).caching()).bits(),
         ) {
             Ok(_) => Ok(()),
             Err(e) => {
@@ -612,7 +613,7 @@ impl GCD {
                     "Failed to set memory attributes for {:#x?} of length {:#x?} with attributes {:#x?}. Status: {:#x?}",
                     base_address,
                     len,
-                    efi::MEMORY_RP,
+                    EfiMemoryAttributes::RP.bits(),
                     e
                 );
                 debug_assert!(false);
@@ -869,6 +870,78 @@ impl GCD {
         (self.free_memory_space_fn)(self, base_address, len, MemoryStateTransition::FreePreservingOwnership)
     }
 
+    /// Excludes a free system memory range from future allocations by converting it to reserved memory.
+    ///
+    /// Unlike an allocation, the excluded range has no owner and cannot be freed. Capabilities and attributes of the
+    /// range are retained. The range may span multiple blocks, but every block in it must be unallocated system (or
+    /// more reliable) memory.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`EfiError::AccessDenied`] if any part of the range is allocated, [`EfiError::NotFound`] if any part of
+    /// the range is not system memory, and [`EfiError::InvalidParameter`] if the range is empty or not page aligned.
+    pub fn exclude_range(&mut self, base_address: usize, len: usize) -> Result<(), EfiError> {
+        ensure!(self.maximum_address != 0, EfiError::NotReady);
+        ensure!(len > 0, EfiError::InvalidParameter);
+        ensure!((base_address & UEFI_PAGE_MASK) == 0 && (len & UEFI_PAGE_MASK) == 0, EfiError::InvalidParameter);
+        let end =
+            base_address.checked_add(len).filter(|end| *end <= self.maximum_address).ok_or(EfiError::Unsupported)?;
+
+        log::trace!(target: "allocations", "[{}] Excluding memory space at {:#x} of length {:#x}", function!(), base_address, len);
+
+        // Validate the whole range before changing anything, so a failure leaves the map untouched.
+        let mut segments = Vec::new();
+        let mut address = base_address;
+        while address < end {
+            let idx = self.memory_blocks.get_closest_idx(&(address as u64)).ok_or(EfiError::NotFound)?;
+            let block = self.memory_blocks.get_with_idx(idx).ok_or(EfiError::NotFound)?;
+            let descriptor = *block.as_ref();
+            ensure!(matches!(block, MemoryBlock::Unallocated(_)), EfiError::AccessDenied);
+            ensure!(
+                matches!(
+                    descriptor.memory_type,
+                    dxe_services::GcdMemoryType::SystemMemory | dxe_services::GcdMemoryType::MoreReliable
+                ),
+                EfiError::NotFound
+            );
+            let segment_end = end.min(block.end());
+            segments.push((address, segment_end - address, descriptor));
+            address = segment_end;
+        }
+
+        for (index, &(address, len, descriptor)) in segments.iter().enumerate() {
+            let excluded = self.remove_memory_space(address, len).and_then(|_| {
+                self.add_memory_space_like(address, len, dxe_services::GcdMemoryType::Reserved, &descriptor)
+            });
+            if let Err(err) = excluded {
+                // Restore every segment touched so far, so a failure leaves the map as it was.
+                for &(address, len, descriptor) in segments[..=index].iter().rev() {
+                    let _ = self.remove_memory_space(address, len);
+                    if let Err(err) = self.add_memory_space_like(address, len, descriptor.memory_type, &descriptor) {
+                        log::error!("Failed to restore memory space at {address:#x} of length {len:#x}: {err:?}");
+                    }
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds memory space of `memory_type` at `address`, with the capabilities and attributes of `descriptor`.
+    fn add_memory_space_like(
+        &mut self,
+        address: usize,
+        len: usize,
+        memory_type: dxe_services::GcdMemoryType,
+        descriptor: &dxe_services::MemorySpaceDescriptor,
+    ) -> Result<(), EfiError> {
+        // SAFETY: the range was free system memory known to the GCD, and is re-added with its own capabilities.
+        unsafe {
+            self.add_memory_space(memory_type, address, len, descriptor.capabilities)?;
+        }
+        self.set_gcd_memory_attributes(address, len, descriptor.attributes)
+    }
+
     /// This service sets attributes on the given memory space.
     ///
     /// # Documentation
@@ -1152,7 +1225,7 @@ impl GCD {
     /// to be pub(crate) because the only caller of it is SpinLockedGcd.activate_compatibility_mode(). And this should
     /// not be called except by that function.
     fn activate_compatibility_mode(&mut self) {
-        self.default_attributes = 0;
+        self.default_attributes = EfiMemoryAttributes::empty();
     }
 
     //Note: truncated strings here are expected and are for alignment with EDK2 reference prints.
@@ -1819,7 +1892,7 @@ impl SpinLockedGcd {
                     memory_blocks: Rbt::new(),
                     allocate_memory_space_fn: GCD::allocate_memory_space_internal,
                     free_memory_space_fn: GCD::free_memory_space_worker,
-                    default_attributes: efi::MEMORY_XP,
+                    default_attributes: EfiMemoryAttributes::XP,
                     prioritize_32_bit_memory: false,
                 },
                 "GcdMemLock",
@@ -2066,7 +2139,7 @@ impl SpinLockedGcd {
             if let Err(err) = self.set_memory_space_attributes(
                 desc.base_address as usize,
                 desc.length as usize,
-                (desc.attributes & efi::CACHE_ATTRIBUTE_MASK) | efi::MEMORY_XP,
+                (EfiMemoryAttributes::from(desc.attributes).caching() | EfiMemoryAttributes::XP).bits(),
             ) {
                 // if we fail to set these attributes (which should just be XP at this point), we should try to
                 // continue
@@ -2097,7 +2170,7 @@ impl SpinLockedGcd {
 
         let dxe_core_cache_attr =
             match self.get_memory_descriptor_for_address(dxe_core_hob.alloc_descriptor.memory_base_address) {
-                Ok(desc) => desc.attributes & efi::CACHE_ATTRIBUTE_MASK,
+                Ok(desc) => EfiMemoryAttributes::from(desc.attributes).caching(),
                 Err(e) => panic!("DXE Core not mapped in GCD {e:?}"),
             };
 
@@ -2105,7 +2178,7 @@ impl SpinLockedGcd {
         self.set_memory_space_attributes(
             dxe_core_hob.alloc_descriptor.memory_base_address as usize,
             dxe_core_hob.alloc_descriptor.memory_length as usize,
-            efi::MEMORY_XP | dxe_core_cache_attr,
+            (EfiMemoryAttributes::XP | dxe_core_cache_attr).bits(),
         )
         .unwrap_or_else(|_| {
             panic!(
@@ -2119,9 +2192,9 @@ impl SpinLockedGcd {
             // each section starts at image_base + virtual_address, per PE/COFF spec.
             let section_base_address =
                 dxe_core_hob.alloc_descriptor.memory_base_address + (section.virtual_address as u64);
-            let mut attributes = efi::MEMORY_XP;
+            let mut attributes = EfiMemoryAttributes::XP;
             if section.characteristics & pecoff::IMAGE_SCN_CNT_CODE == pecoff::IMAGE_SCN_CNT_CODE {
-                attributes = efi::MEMORY_RO;
+                attributes = EfiMemoryAttributes::RO;
             }
 
             // We need to use the virtual size for the section length, but
@@ -2138,22 +2211,27 @@ impl SpinLockedGcd {
 
             log::trace!(
                 target: "paging",
-                "Mapping DXE Core image memory region {section_base_address:#x?} of length {aligned_virtual_size:#x?} with attributes {attributes:#x?}",
+                "Mapping DXE Core image memory region {section_base_address:#x?} of length {aligned_virtual_size:#x?} with attributes {:#x?}",
+                attributes.bits(),
             );
 
             attributes |=
                 match self.get_memory_descriptor_for_address(dxe_core_hob.alloc_descriptor.memory_base_address) {
-                    Ok(desc) => desc.attributes & efi::CACHE_ATTRIBUTE_MASK,
+                    Ok(desc) => EfiMemoryAttributes::from(desc.attributes).caching(),
                     Err(e) => panic!("DXE Core section not mapped in GCD {e:?}"),
                 };
 
-            self.set_memory_space_attributes(section_base_address as usize, aligned_virtual_size as usize, attributes)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "Failed to map DXE Core image {:#x?} of length {:#x?} with attributes {:#x?}.",
-                        dxe_core_hob.alloc_descriptor.memory_base_address, 0x1000, 0
-                    )
-                });
+            self.set_memory_space_attributes(
+                section_base_address as usize,
+                aligned_virtual_size as usize,
+                attributes.bits(),
+            )
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Failed to map DXE Core image {:#x?} of length {:#x?} with attributes {:#x?}.",
+                    dxe_core_hob.alloc_descriptor.memory_base_address, 0x1000, 0
+                )
+            });
         }
 
         // now map MMIO. Drivers expect to be able to access MMIO regions as RW, so we need to map them as such
@@ -2162,7 +2240,8 @@ impl SpinLockedGcd {
             // table
             let base_address = desc.base_address as usize & !UEFI_PAGE_MASK;
             let len = (desc.length as usize + UEFI_PAGE_MASK) & !UEFI_PAGE_MASK;
-            let new_attributes = (desc.attributes & efi::CACHE_ATTRIBUTE_MASK) | efi::MEMORY_XP;
+            let new_attributes =
+                (EfiMemoryAttributes::from(desc.attributes).caching() | EfiMemoryAttributes::XP).bits();
 
             log::trace!(
                 target: "paging",
@@ -2192,7 +2271,7 @@ impl SpinLockedGcd {
         // only do this if page 0 actually exists
         if let Ok(descriptor) = self.get_memory_descriptor_for_address(0)
             && descriptor.memory_type != GcdMemoryType::NonExistent
-            && let Err(err) = self.set_memory_space_attributes(0, UEFI_PAGE_SIZE, efi::MEMORY_RP)
+            && let Err(err) = self.set_memory_space_attributes(0, UEFI_PAGE_SIZE, EfiMemoryAttributes::RP.bits())
         {
             // if we fail to set these attributes we can continue to boot, but we will not be able to detect null
             // pointer dereferences.
@@ -2287,14 +2366,14 @@ impl SpinLockedGcd {
                     Err(_) => DEFAULT_CACHE_ATTR,
                 };
                 // it is safe to call set_memory_space_attributes without calling set_memory_space_capabilities here
-                // because we set efi::MEMORY_XP as a capability on all memory ranges we add to the GCD. A driver could
+                // because we set XP as a capability on all memory ranges we add to the GCD. A driver could
                 // call set_memory_space_capabilities to remove the XP capability, but that is something that should
                 // be caught and fixed.
                 let default_attributes = self.memory.lock().default_attributes;
                 match self.set_memory_space_attributes(
                     *base_address,
                     len,
-                    (attributes & efi::CACHE_ATTRIBUTE_MASK) | default_attributes,
+                    (EfiMemoryAttributes::from(attributes).caching() | default_attributes).bits(),
                 ) {
                     Ok(_) => (),
                     Err(EfiError::NotReady) => {
@@ -2390,6 +2469,20 @@ impl SpinLockedGcd {
         result
     }
 
+    /// Excludes a free system memory range discovered after initialization (e.g. one reported as faulty) from future
+    /// allocations. The range becomes reserved memory that is not owned by any image.
+    ///
+    /// Returns [`EfiError::AccessDenied`] if any part of the range is already allocated.
+    pub fn exclude_range(&self, base_address: usize, len: usize) -> Result<(), EfiError> {
+        let result = self.memory.lock().exclude_range(base_address, len);
+        if result.is_ok()
+            && let Some(callback) = self.memory_change_callback
+        {
+            callback(MapChangeType::RemoveMemorySpace);
+        }
+        result
+    }
+
     /// This service sets attributes on the given memory space.
     ///
     /// # Documentation
@@ -2434,7 +2527,9 @@ impl SpinLockedGcd {
             // behavior where if the caller passes 0 for cache and paging attributes, then 0 (RWX) is not applied to
             // the page table and only the virtual attribute(s) are applied to the GCD, such as EFI_RUNTIME. In order
             // to maintain compatibility with existing drivers, we preserve this poor paradigm.
-            if attributes & (efi::CACHE_ATTRIBUTE_MASK | efi::MEMORY_ACCESS_MASK) != 0 {
+            if EfiMemoryAttributes::from(attributes)
+                .intersects(EfiMemoryAttributes::CACHE_MASK | EfiMemoryAttributes::ACCESS_MASK)
+            {
                 match self.set_paging_attributes(current_base as usize, current_len as usize, attributes) {
                     Ok(_) => {}
                     Err(EfiError::NotReady) => {
@@ -2523,6 +2618,78 @@ impl SpinLockedGcd {
         self.memory.lock().memory_descriptor_count()
     }
 
+    /// Returns the current memory map as a spec-formatted [`efi::MemoryDescriptor`] list, sourced directly from the
+    /// GCD.
+    ///
+    /// Unlike GetMemoryMap, this does not consult the UEFI allocators and is intended for diagnostic snapshots. As
+    /// the GCD does not track the EFI memory type of an allocation, GCD memory types map to EFI memory types as:
+    ///
+    /// | GCD memory type                 | EFI memory type                                                         |
+    /// |---------------------------------|-------------------------------------------------------------------------|
+    /// | SystemMemory, MoreReliable      | ConventionalMemory if free, otherwise RuntimeServicesData if the range  |
+    /// |                                 | has the `EFI_MEMORY_RUNTIME` attribute and BootServicesData if not      |
+    /// | MemoryMappedIo                  | MemoryMappedIO                                                          |
+    /// | Persistent                      | PersistentMemory                                                        |
+    /// | Unaccepted                      | UnacceptedMemoryType                                                    |
+    /// | Reserved                        | ReservedMemoryType                                                      |
+    /// | NonExistent                     | omitted                                                                 |
+    ///
+    /// Attributes follow the EFI memory map convention: the range capabilities without the access attributes, plus
+    /// `EFI_MEMORY_RUNTIME` if it is set, plus `EFI_MEMORY_NV` for persistent and `EFI_MEMORY_MORE_RELIABLE` for more
+    /// reliable memory. Ranges that are not page aligned are omitted. Returns an empty list if the GCD is not ready.
+    pub fn memory_descriptors(&self) -> Vec<efi::MemoryDescriptor> {
+        let mut descriptors = Vec::with_capacity(self.memory_descriptor_count());
+        if self.get_memory_descriptors(&mut descriptors).is_err() {
+            return Vec::new();
+        }
+
+        descriptors
+            .iter()
+            .filter_map(|descriptor| {
+                let allocated = !descriptor.image_handle.is_null();
+                let runtime = EfiMemoryAttributes::from(descriptor.attributes) & EfiMemoryAttributes::RUNTIME;
+                let (memory_type, type_attributes) = match descriptor.memory_type {
+                    GcdMemoryType::SystemMemory | GcdMemoryType::MoreReliable => {
+                        let memory_type = match (allocated, !runtime.is_empty()) {
+                            (false, _) => efi::CONVENTIONAL_MEMORY,
+                            (true, true) => efi::RUNTIME_SERVICES_DATA,
+                            (true, false) => efi::BOOT_SERVICES_DATA,
+                        };
+                        let reliable = match descriptor.memory_type {
+                            GcdMemoryType::MoreReliable => EfiMemoryAttributes::MORE_RELIABLE,
+                            _ => EfiMemoryAttributes::empty(),
+                        };
+                        (memory_type, reliable)
+                    }
+                    GcdMemoryType::MemoryMappedIo => (efi::MEMORY_MAPPED_IO, EfiMemoryAttributes::empty()),
+                    GcdMemoryType::Persistent => (efi::PERSISTENT_MEMORY, EfiMemoryAttributes::NV),
+                    GcdMemoryType::Unaccepted => (efi::UNACCEPTED_MEMORY_TYPE, EfiMemoryAttributes::empty()),
+                    GcdMemoryType::Reserved => (efi::RESERVED_MEMORY_TYPE, EfiMemoryAttributes::empty()),
+                    GcdMemoryType::NonExistent => return None,
+                };
+
+                if descriptor.base_address & UEFI_PAGE_MASK as u64 != 0
+                    || descriptor.length & UEFI_PAGE_MASK as u64 != 0
+                    || descriptor.length == 0
+                {
+                    return None;
+                }
+
+                Some(efi::MemoryDescriptor {
+                    r#type: memory_type,
+                    physical_start: descriptor.base_address,
+                    virtual_start: 0,
+                    number_of_pages: descriptor.length >> UEFI_PAGE_SHIFT,
+                    attribute: ((EfiMemoryAttributes::from(descriptor.capabilities)
+                        & !(EfiMemoryAttributes::ACCESS_MASK | EfiMemoryAttributes::RUNTIME))
+                        | runtime
+                        | type_attributes)
+                        .bits(),
+                })
+            })
+            .collect()
+    }
+
     /// Acquires lock and delegates to [`IoGCD::add_io_space`]
     pub fn add_io_space(
         &self,
@@ -2570,7 +2737,7 @@ impl SpinLockedGcd {
     /// This activates compatibility mode for the GCD.
     /// This will:
     /// - Map the range 0 - 0xA0000 as RWX if the memory type is SystemMemory.
-    /// - Update the locked GCD to not set efi::MEMORY_XP on newly allocated pages
+    /// - Update the locked GCD to not set XP on newly allocated pages
     pub(crate) fn activate_compatibility_mode(&self) {
         const LEGACY_BIOS_WB_ADDRESS: usize = 0xA0000;
 
@@ -2580,7 +2747,7 @@ impl SpinLockedGcd {
         if let Ok(descriptor) = self.get_memory_descriptor_for_address(0)
             // set_memory_space_attributes will set both the GCD and paging attributes
             && descriptor.memory_type != dxe_services::GcdMemoryType::NonExistent
-            && let Err(e) = self.set_memory_space_attributes(0, UEFI_PAGE_SIZE, efi::MEMORY_WB)
+            && let Err(e) = self.set_memory_space_attributes(0, UEFI_PAGE_SIZE, EfiMemoryAttributes::WB.bits())
         {
             log::error!("Failed to map page 0 for compat mode. Status: {e:#x?}");
             debug_assert!(false);
@@ -2602,7 +2769,7 @@ impl SpinLockedGcd {
                     match self.set_memory_space_attributes(
                         address,
                         size,
-                        descriptor.attributes & efi::CACHE_ATTRIBUTE_MASK,
+                        EfiMemoryAttributes::from(descriptor.attributes).caching().bits(),
                     ) {
                         Ok(_) => {}
                         Err(e) => {
@@ -2610,7 +2777,7 @@ impl SpinLockedGcd {
                                 "Failed to map legacy bios region at {:#x?} of length {:#x?} with attributes {:#x?}. Status: {:#x?}",
                                 address,
                                 size,
-                                descriptor.attributes & efi::CACHE_ATTRIBUTE_MASK,
+                                EfiMemoryAttributes::from(descriptor.attributes).caching().bits(),
                                 e
                             );
                             debug_assert!(false);
@@ -3622,7 +3789,7 @@ mod tests {
             maximum_address: 0,
             allocate_memory_space_fn: GCD::allocate_memory_space_internal,
             free_memory_space_fn: GCD::free_memory_space_worker,
-            default_attributes: efi::MEMORY_XP,
+            default_attributes: EfiMemoryAttributes::XP,
             prioritize_32_bit_memory: false,
         };
         assert_eq!(Err(EfiError::NotReady), gcd.set_memory_space_attributes(0, 0x50000, 0b1111));
@@ -4138,6 +4305,119 @@ mod tests {
         });
     }
 
+    #[test]
+    fn memory_descriptors_should_translate_gcd_map() {
+        with_locked_state(|| {
+            static GCD: SpinLockedGcd = SpinLockedGcd::new(None);
+            assert!(GCD.memory_descriptors().is_empty());
+
+            let mem = unsafe { get_memory(MEMORY_BLOCK_SLICE_SIZE * 3) };
+            let address = align_up(mem.as_ptr() as usize, 0x1000).unwrap();
+            GCD.init(48, 16);
+            unsafe {
+                GCD.add_memory_space(GcdMemoryType::SystemMemory, address, MEMORY_BLOCK_SLICE_SIZE * 2, efi::MEMORY_WB)
+                    .unwrap();
+                GCD.add_memory_space(
+                    GcdMemoryType::MemoryMappedIo,
+                    0x1000,
+                    0x2000,
+                    efi::MEMORY_UC | efi::MEMORY_XP | efi::MEMORY_RUNTIME,
+                )
+                .unwrap();
+                GCD.add_memory_space(GcdMemoryType::Reserved, 0x10000, 0x1000, efi::MEMORY_UC).unwrap();
+                GCD.add_memory_space(GcdMemoryType::Persistent, 0x20000, 0x1000, efi::MEMORY_WB).unwrap();
+            }
+            let allocation = GCD
+                .allocate_memory_space(
+                    AllocateType::TopDown(None),
+                    GcdMemoryType::SystemMemory,
+                    12,
+                    0x2000,
+                    1 as _,
+                    None,
+                )
+                .unwrap();
+
+            let descriptors = GCD.memory_descriptors();
+            let find = |start: usize| descriptors.iter().find(|d| d.physical_start == start as u64).unwrap();
+
+            // The runtime capability is dropped, as the range does not have the runtime attribute set.
+            let mmio = find(0x1000);
+            assert_eq!((mmio.r#type, mmio.number_of_pages), (efi::MEMORY_MAPPED_IO, 2));
+            assert_eq!(mmio.attribute, efi::MEMORY_UC | efi::MEMORY_ISA_VALID);
+
+            let reserved = find(0x10000);
+            assert_eq!((reserved.r#type, reserved.number_of_pages), (efi::RESERVED_MEMORY_TYPE, 1));
+
+            let persistent = find(0x20000);
+            assert_eq!(persistent.r#type, efi::PERSISTENT_MEMORY);
+            assert_eq!(persistent.attribute, efi::MEMORY_WB | efi::MEMORY_NV);
+
+            let allocated = find(allocation);
+            assert_eq!((allocated.r#type, allocated.number_of_pages), (efi::BOOT_SERVICES_DATA, 2));
+            assert_eq!(allocated.attribute, efi::MEMORY_WB);
+            assert!(descriptors.iter().any(|d| d.r#type == efi::CONVENTIONAL_MEMORY));
+
+            // Non-existent ranges are omitted, and every range the rest of the map covers is described.
+            let described_pages: u64 = descriptors.iter().map(|d| d.number_of_pages).sum();
+            assert_eq!(described_pages, ((MEMORY_BLOCK_SLICE_SIZE * 2) as u64 >> UEFI_PAGE_SHIFT) + 4);
+            assert!(descriptors.windows(2).all(|pair| pair[0].physical_start < pair[1].physical_start));
+        });
+    }
+
+    #[test]
+    fn exclude_range_should_prevent_future_allocations() {
+        with_locked_state(|| {
+            static GCD: SpinLockedGcd = SpinLockedGcd::new(None);
+
+            let mem = unsafe { get_memory(MEMORY_BLOCK_SLICE_SIZE * 3) };
+            let address = align_up(mem.as_ptr() as usize, 0x1000).unwrap();
+            GCD.init(48, 16);
+            unsafe {
+                GCD.add_memory_space(GcdMemoryType::SystemMemory, address, MEMORY_BLOCK_SLICE_SIZE * 2, efi::MEMORY_WB)
+                    .unwrap();
+                GCD.add_memory_space(GcdMemoryType::Reserved, 0x10000, 0x1000, efi::MEMORY_UC).unwrap();
+            }
+
+            let allocate_page = |allocate_type| {
+                GCD.allocate_memory_space(allocate_type, GcdMemoryType::SystemMemory, 12, 0x1000, 1 as _, None)
+            };
+
+            // Find the top three free pages and exclude the middle one.
+            let top = GCD
+                .allocate_memory_space(
+                    AllocateType::TopDown(None),
+                    GcdMemoryType::SystemMemory,
+                    12,
+                    0x3000,
+                    1 as _,
+                    None,
+                )
+                .unwrap();
+            GCD.free_memory_space(top, 0x3000).unwrap();
+            let excluded = top + 0x1000;
+            GCD.exclude_range(excluded, 0x1000).unwrap();
+
+            let descriptor = GCD.get_memory_descriptor_for_address(excluded as u64).unwrap();
+            assert_eq!(descriptor.memory_type, GcdMemoryType::Reserved);
+            assert!(descriptor.image_handle.is_null());
+
+            assert_eq!(allocate_page(AllocateType::TopDown(None)), Ok(top + 0x2000));
+            assert_eq!(allocate_page(AllocateType::TopDown(None)), Ok(top));
+            assert!(allocate_page(AllocateType::Address(excluded)).is_err());
+
+            // Allocated and non-system memory ranges cannot be excluded.
+            assert_eq!(GCD.exclude_range(top, 0x1000), Err(EfiError::AccessDenied));
+            assert_eq!(GCD.exclude_range(0x10000, 0x1000), Err(EfiError::NotFound));
+            assert_eq!(GCD.exclude_range(top, 0x800), Err(EfiError::InvalidParameter));
+
+            // A range that is only partly free is rejected before the free part is changed.
+            assert_eq!(GCD.exclude_range(top - 0x1000, 0x2000), Err(EfiError::AccessDenied));
+            let descriptor = GCD.get_memory_descriptor_for_address((top - 0x1000) as u64).unwrap();
+            assert_eq!(descriptor.memory_type, GcdMemoryType::SystemMemory);
+        });
+    }
+
     #[test]
     fn allocate_bottom_up_should_allocate_increasing_addresses() {
         with_locked_state(|| {
@@ -6,10 +6,11 @@
 //!
 //! SPDX-License-Identifier: Apache-2.0
 //!
-use alloc::{boxed::Box, collections::BTreeMap, string::String, vec, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec, vec::Vec};
 use core::{convert::TryInto, ffi::c_void, mem::transmute, slice, slice::from_raw_parts};
 use goblin::pe::section_table;
 use patina::base::{DEFAULT_CACHE_ATTR, UEFI_PAGE_SIZE, align_up};
+use patina::boot_services::allocation::MemoryAttributes;
 use patina::error::EfiError;
 use patina::performance::{
     logging::{perf_image_start_begin, perf_image_start_end, perf_load_image_begin, perf_load_image_end},
@@ -25,7 +26,7 @@ use patina_internal_device_path::{DevicePathWalker, copy_device_path_to_boxed_sl
 use r_efi::efi;
 
 use crate::{
-    allocator::{core_allocate_pages, core_free_pages},
+    allocator::{self, core_allocate_pages, core_free_pages},
     config_tables::debug_image_info_table::{
         EfiDebugImageInfoNormal, core_new_debug_image_info_entry, core_remove_debug_image_info_entry,
         initialize_debug_image_info_table,
@@ -317,6 +318,7 @@ struct DxeCoreGlobalImageData {
     private_image_data: BTreeMap<efi::Handle, PrivateImageData>,
     current_running_image: Option<efi::Handle>,
     image_start_contexts: Vec<*const Yielder<efi::Handle, efi::Status>>,
+    retain_unloaded_images: bool,
 }
 
 impl DxeCoreGlobalImageData {
@@ -327,6 +329,7 @@ impl DxeCoreGlobalImageData {
             private_image_data: BTreeMap::new(),
             current_running_image: None,
             image_start_contexts: Vec::new(),
+            retain_unloaded_images: false,
         }
     }
 
@@ -337,6 +340,7 @@ impl DxeCoreGlobalImageData {
         self.private_image_data = BTreeMap::new();
         self.current_running_image = None;
         self.image_start_contexts = Vec::new();
+        self.retain_unloaded_images = false;
     }
 }
 
@@ -369,15 +373,15 @@ fn empty_image_info() -> efi::protocols::loaded_image::Protocol {
 
 fn apply_image_memory_protections(pe_info: &UefiPeInfo, private_info: &PrivateImageData) {
     for section in &pe_info.sections {
-        let mut attributes = efi::MEMORY_XP;
+        let mut attributes = MemoryAttributes::XP;
         if section.characteristics & pecoff::IMAGE_SCN_CNT_CODE == pecoff::IMAGE_SCN_CNT_CODE {
-            attributes = efi::MEMORY_RO;
+            attributes = MemoryAttributes::RO;
         }
 
         if section.characteristics & section_table::IMAGE_SCN_MEM_WRITE == 0
             && ((section.characteristics & section_table::IMAGE_SCN_MEM_READ) == section_table::IMAGE_SCN_MEM_READ)
         {
-            attributes |= efi::MEMORY_RO;
+            attributes |= MemoryAttributes::RO;
         }
 
         // each section starts at image_base + virtual_address, per PE/COFF spec.
@@ -392,8 +396,8 @@ fn apply_image_memory_protections(pe_info: &UefiPeInfo, private_info: &PrivateIm
             // all new memory has efi::MEMORY_XP set, so we need to remove this if this is becoming a code
             // section
             Ok(desc) => {
-                attributes |= desc.attributes & !efi::MEMORY_ACCESS_MASK;
-                capabilities |= desc.capabilities;
+                attributes |= MemoryAttributes::from(desc.attributes) & !MemoryAttributes::ACCESS_MASK;
+                capabilities |= MemoryAttributes::from(desc.capabilities);
             }
             Err(status) => {
                 log::error!(
@@ -421,9 +425,11 @@ fn apply_image_memory_protections(pe_info: &UefiPeInfo, private_info: &PrivateIm
             continue;
         };
 
-        if let Err(status) =
-            dxe_services::core_set_memory_space_capabilities(section_base_addr, aligned_virtual_size, capabilities)
-        {
+        if let Err(status) = dxe_services::core_set_memory_space_capabilities(
+            section_base_addr,
+            aligned_virtual_size,
+            capabilities.bits(),
+        ) {
             // even if we fail to set the capabilities, we should still try to set the attributes, who knows, maybe we
             // will succeed
             log::error!(
@@ -434,10 +440,12 @@ fn apply_image_memory_protections(pe_info: &UefiPeInfo, private_info: &PrivateIm
         // this may be verbose to log, but we also have a lot of errors historically here, so let's log at info level
         // for now
         log::info!(
-            "Applying image memory protections on {section_base_addr:#X} for len {aligned_virtual_size:#X} with attributes {attributes:#X}",
+            "Applying image memory protections on {section_base_addr:#X} for len {aligned_virtual_size:#X} with attributes {:#X}",
+            attributes.bits(),
         );
 
-        match dxe_services::core_set_memory_space_attributes(section_base_addr, aligned_virtual_size, attributes) {
+        match dxe_services::core_set_memory_space_attributes(section_base_addr, aligned_virtual_size, attributes.bits())
+        {
             Ok(_) => continue,
             Err(status) => log::error!(
                 "Failed to set GCD attributes for image section {section_base_addr:#X} with Status {status:#X?}",
@@ -805,6 +813,22 @@ fn get_file_guid_from_device_path(path: *mut efi::protocols::device_path::Protoc
     Ok(Guid::from_bytes(file_path_node.data().try_into().map_err(|_| EfiError::BadBufferSize)?))
 }
 
+/// Returns the name an image is reported by: its PDB filename, else the GUID of the file it was loaded from.
+fn image_name(image_handle: efi::Handle) -> String {
+    let private_data = PRIVATE_IMAGE_DATA.lock();
+    let Some(private_info) = private_data.private_image_data.get(&image_handle) else {
+        return String::from("Unknown");
+    };
+    if let Some(filename) = &private_info.pe_info.filename {
+        return filename.clone();
+    }
+    let file_path = private_info.image_info.file_path;
+    match (!file_path.is_null()).then(|| get_file_guid_from_device_path(file_path)) {
+        Some(Ok(file_guid)) => format!("{}", patina::Guid::from_ref(&file_guid)),
+        _ => String::from("Unknown"),
+    }
+}
+
 fn get_file_buffer_from_fw(
     file_path: *mut efi::protocols::device_path::Protocol,
 ) -> Result<(Vec<u8>, efi::Handle), EfiError> {
@@ -1080,6 +1104,21 @@ pub fn core_load_image(
         private_info.entry_point as usize,
         private_info.pe_info.filename.as_ref().unwrap_or(&String::from("<no PDB>"))
     );
+    // SAFETY: the image was just loaded into the buffer described by the loaded image protocol.
+    let debug_info = unsafe {
+        pecoff::read_debug_info(
+            private_info.image_info.image_base as usize,
+            private_info.image_info.image_size as usize,
+        )
+    };
+    if let Some(debug_info) = debug_info {
+        log::debug!(
+            "  PDB {} {} age {}",
+            debug_info.pdb_path,
+            patina::Guid::from_ref(&debug_info.guid),
+            debug_info.age
+        );
+    }
 
     // install the loaded_image protocol for this freshly loaded image on a new
     // handle.
@@ -1145,8 +1184,18 @@ pub fn core_load_image(
     private_info.image_info_ptr = image_info_ptr;
     private_info.image_device_path_ptr = file_path as *mut c_void;
 
+    // frames within this image should no longer be attributed to an unloaded image that occupied its range.
+    let mut private_data = PRIVATE_IMAGE_DATA.lock();
+    if private_data.retain_unloaded_images {
+        patina_stacktrace::record_loaded_image(
+            private_info.image_info.image_base as u64,
+            private_info.image_info.image_size,
+        );
+    }
+
     // save the private image data for this image in the private image data map.
-    PRIVATE_IMAGE_DATA.lock().private_image_data.insert(handle, private_info);
+    private_data.private_image_data.insert(handle, private_info);
+    drop(private_data);
 
     perf_load_image_end(handle, create_performance_measurement);
 
@@ -1304,6 +1353,7 @@ pub fn core_start_image(image_handle: efi::Handle) -> Result<(), efi::Status> {
     let previous_image = private_data.current_running_image;
     private_data.current_running_image = Some(image_handle);
     drop(private_data);
+    allocator::set_current_image(Some(image_handle), image_name);
 
     // switch stacks and execute the above defined coroutine to start the image.
     let status = match coroutine.resume(image_handle) {
@@ -1324,6 +1374,7 @@ pub fn core_start_image(image_handle: efi::Handle) -> Result<(), efi::Status> {
     unsafe { coroutine.force_reset() };
 
     PRIVATE_IMAGE_DATA.lock().current_running_image = previous_image;
+    allocator::set_current_image(previous_image, image_name);
 
     perf_image_start_end(image_handle, create_performance_measurement);
 
@@ -1394,7 +1445,20 @@ pub fn core_unload_image(image_handle: efi::Handle, force_unload: bool) -> Resul
     // remove the private data for this image from the private_image_data map.
     // it will get dropped when it goes out of scope at the end of the function and the pages allocated for it
     // and the image_info box along with it.
-    let private_image_data = PRIVATE_IMAGE_DATA.lock().private_image_data.remove(&image_handle).unwrap();
+    let mut private_data = PRIVATE_IMAGE_DATA.lock();
+    let private_image_data = private_data.private_image_data.remove(&image_handle).unwrap();
+    let retain_unloaded_images = private_data.retain_unloaded_images;
+    drop(private_data);
+
+    // keep a record of the image's former range so later stack traces can still attribute addresses within it.
+    if retain_unloaded_images {
+        patina_stacktrace::record_unloaded_image(patina_stacktrace::UnloadedImage {
+            name: private_image_data.pe_info.filename.clone().unwrap_or(String::from("Unknown")),
+            base_address: private_image_data.image_info.image_base as u64,
+            size: private_image_data.image_info.image_size,
+        });
+    }
+
     // remove the image and device path protocols from the image handle.
     let _ = core_uninstall_protocol_interface(
         image_handle,
@@ -1423,6 +1487,12 @@ pub fn core_unload_image(image_handle: efi::Handle, force_unload: bool) -> Resul
     Ok(())
 }
 
+/// Sets whether the load info (name, base, and size) of unloaded images is retained in the bounded history used by
+/// stack trace symbolication.
+pub fn retain_unloaded_image_history(retain: bool) {
+    PRIVATE_IMAGE_DATA.lock().retain_unloaded_images = retain;
+}
+
 extern "efiapi" fn unload_image(image_handle: efi::Handle) -> efi::Status {
     match core_unload_image(image_handle, false) {
         Ok(()) => efi::Status::SUCCESS,
@@ -1528,7 +1598,7 @@ mod tests {
     extern crate std;
     use super::{empty_image_info, get_buffer_by_file_path, load_image};
     use crate::{
-        image::{PRIVATE_IMAGE_DATA, exit, start_image, unload_image},
+        image::{PRIVATE_IMAGE_DATA, exit, retain_unloaded_image_history, start_image, unload_image},
         protocol_db,
         protocols::{PROTOCOL_DB, core_install_protocol_interface},
         systemtables::{SYSTEM_TABLE, init_system_table},
@@ -1538,7 +1608,7 @@ mod tests {
     use patina::error::EfiError;
     use patina::pi;
     use r_efi::efi;
-    use std::{fs::File, io::Read};
+    use std::{fs::File, io::Read, string::String};
 
     fn with_locked_state<F: Fn() + std::panic::RefUnwindSafe>(f: F) {
         test_support::with_global_lock(|| unsafe {
@@ -1893,6 +1963,64 @@ mod tests {
         });
     }
 
+    #[test]
+    fn unload_image_should_record_unloaded_image_history_when_retained() {
+        with_locked_state(|| {
+            let mut test_file =
+                File::open(test_collateral!("RustImageTestDxe.efi")).expect("failed to open test file.");
+            let mut image: Vec<u8> = Vec::new();
+            test_file.read_to_end(&mut image).expect("failed to read test file");
+
+            retain_unloaded_image_history(true);
+
+            let mut image_handle: efi::Handle = core::ptr::null_mut();
+            let status = load_image(
+                false.into(),
+                protocol_db::DXE_CORE_HANDLE,
+                core::ptr::null_mut(),
+                image.as_mut_ptr() as *mut c_void,
+                image.len(),
+                core::ptr::addr_of_mut!(image_handle),
+            );
+            assert_eq!(status, efi::Status::SUCCESS);
+
+            let private_data = PRIVATE_IMAGE_DATA.lock();
+            let private_info = private_data.private_image_data.get(&image_handle).unwrap();
+            let image_base = private_info.image_info.image_base as u64;
+            let image_size = private_info.image_info.image_size;
+            let image_name = private_info.pe_info.filename.clone();
+            drop(private_data);
+
+            assert_eq!(unload_image(image_handle), efi::Status::SUCCESS);
+
+            let unloaded = patina_stacktrace::find_unloaded_image(image_base + image_size - 1, Clone::clone).unwrap();
+            assert_eq!(unloaded.base_address, image_base);
+            assert_eq!(unloaded.size, image_size);
+            assert_eq!(unloaded.name, image_name.unwrap_or(String::from("Unknown")));
+
+            // Loading an image into the freed range supersedes the record of the unloaded image.
+            let status = load_image(
+                false.into(),
+                protocol_db::DXE_CORE_HANDLE,
+                core::ptr::null_mut(),
+                image.as_mut_ptr() as *mut c_void,
+                image.len(),
+                core::ptr::addr_of_mut!(image_handle),
+            );
+            assert_eq!(status, efi::Status::SUCCESS);
+
+            let private_data = PRIVATE_IMAGE_DATA.lock();
+            let private_info = private_data.private_image_data.get(&image_handle).unwrap();
+            let reloaded_base = private_info.image_info.image_base as u64;
+            let reloaded_size = private_info.image_info.image_size;
+            drop(private_data);
+
+            assert!(patina_stacktrace::find_unloaded_image(reloaded_base, Clone::clone).is_none());
+            assert!(patina_stacktrace::find_unloaded_image(reloaded_base + reloaded_size - 1, Clone::clone).is_none());
+            assert_eq!(unload_image(image_handle), efi::Status::SUCCESS);
+        });
+    }
+
     #[test]
     fn get_buffer_by_file_path_should_fail_if_no_file_support() {
         with_locked_state(|| {
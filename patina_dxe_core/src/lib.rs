@@ -52,6 +52,7 @@ mod hw_interrupt_protocol;
 mod image;
 mod memory_attributes_protocol;
 mod memory_manager;
+mod milestone;
 mod misc_boot_services;
 mod pecoff;
 mod protocol_db;
@@ -65,26 +66,41 @@ mod tpl_lock;
 #[coverage(off)]
 pub mod test_support;
 
+pub use dispatcher::request_dispatch;
+pub use events::pending_timer_events;
+pub use gcd::memory_descriptors;
+pub use milestone::MilestoneEvent;
+pub use protocol_db::DuplicateProtocolPolicy;
+
 use core::{ffi::c_void, ptr, str::FromStr};
 
 use alloc::{boxed::Box, vec::Vec};
 use gcd::SpinLockedGcd;
 use memory_manager::CoreMemoryManager;
-use mu_rust_helpers::{function, guid::CALLER_ID};
+use mu_rust_helpers::{
+    function,
+    guid::{CALLER_ID, guid_fmt},
+    perf_timer::{Arch, ArchFunctionality},
+};
 use patina::pi::{
     hob::{HobList, get_c_hob_list_size},
     protocols::{bds, status_code},
-    status_code::{EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE, EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT},
+    status_code::{
+        EFI_ERROR_CODE, EFI_ERROR_UNRECOVERED, EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE, EFI_SW_DXE_CORE_EC_NO_ARCH,
+        EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT,
+    },
 };
 use patina::{
     boot_services::StandardBootServices,
     component::{Component, IntoComponent, Storage, service::IntoService},
     error::{self, Result},
     performance::{
-        logging::{perf_function_begin, perf_function_end},
+        logging::{perf_cross_module_begin, perf_cross_module_end, perf_function_begin, perf_function_end},
         measurement::create_performance_measurement,
+        record::known::KnownPerfToken,
     },
     runtime_services::StandardRuntimeServices,
+    uefi_protocol::performance_measurement::CreateMeasurement,
 };
 use patina_ffs::section::SectionExtractor;
 use patina_internal_cpu::{cpu::EfiCpu, interrupts::Interrupts};
@@ -199,22 +215,72 @@ pub struct Core<MemoryState> {
     physical_hob_list: *const c_void,
     hob_list: HobList<'static>,
     components: Vec<Box<dyn Component>>,
+    milestone_components: Vec<(MilestoneEvent, Box<dyn Component>)>,
     storage: Storage,
+    unknown_hob_policy: UnknownHobPolicy,
+    memory_overlap_policy: MemoryOverlapPolicy,
+    required_arch_protocols: Vec<efi::Guid>,
+    dispatch_statistics: DispatchStatistics,
     _memory_state: core::marker::PhantomData<MemoryState>,
 }
 
+/// A summary of the dispatch performed by [Core::start].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchStatistics {
+    /// The number of components that were dispatched and returned success.
+    pub components_dispatched: usize,
+    /// The number of components that were dispatched and returned an error.
+    pub components_failed: usize,
+    /// The number of components that were never dispatched because their parameters were never available.
+    pub components_not_dispatched: usize,
+    /// The number of passes of the combined component and UEFI driver dispatch loop, including the final pass of each
+    /// dispatch phase that dispatched nothing.
+    pub dispatch_waves: usize,
+    /// The total time spent dispatching, in microseconds.
+    pub dispatch_time_us: u64,
+}
+
+/// How the core handles a GUID HOB without a registered parser.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownHobPolicy {
+    /// Unparsed HOBs are silently ignored.
+    Ignore,
+    /// A warning is logged for each unparsed HOB.
+    #[default]
+    Warn,
+    /// An error is logged for each unparsed HOB, and [Core::start] fails if any were found.
+    Error,
+}
+
+/// How the core handles overlapping or out of order GCD memory space descriptors after memory is initialized.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryOverlapPolicy {
+    /// An error is logged for each overlap, with both ranges.
+    #[default]
+    Log,
+    /// An error is logged for each overlap, and [Core::init_memory] panics if any were found.
+    Panic,
+}
+
 impl Default for Core<NoAlloc> {
     fn default() -> Self {
         Core {
             physical_hob_list: core::ptr::null(),
             hob_list: HobList::default(),
             components: Vec::new(),
+            milestone_components: Vec::new(),
             storage: Storage::new(),
+            unknown_hob_policy: UnknownHobPolicy::default(),
+            memory_overlap_policy: MemoryOverlapPolicy::default(),
+            required_arch_protocols: Vec::new(),
+            dispatch_statistics: DispatchStatistics::default(),
             _memory_state: core::marker::PhantomData,
         }
     }
 }
 
+/// Some of these settings configure global state of the core rather than fields of `Core`, but still take and return
+/// `Core` so that they chain like the others.
 impl Core<NoAlloc> {
     /// Initializes the core with the given configuration, including GCD initialization, enabling allocations.
     pub fn init_memory(mut self, physical_hob_list: *const c_void) -> Core<Alloc> {
@@ -247,6 +313,10 @@ impl Core<NoAlloc> {
         PROTOCOL_DB.init_protocol_db();
         // Initialize full allocation support.
         allocator::init_memory_support(&self.hob_list);
+        let overlaps = gcd::report_memory_descriptor_overlaps();
+        if overlaps > 0 && self.memory_overlap_policy == MemoryOverlapPolicy::Panic {
+            panic!("Found {overlaps} overlapping GCD memory space descriptors after memory initialization.");
+        }
         // we have to relocate HOBs after memory services are initialized as we are going to allocate memory and
         // the initial free memory may not be enough to contain the HOB list. We need to relocate the HOBs because
         // the initial HOB list is not in mapped memory as passed from pre-DXE.
@@ -271,7 +341,12 @@ impl Core<NoAlloc> {
             physical_hob_list,
             hob_list: self.hob_list,
             components: self.components,
+            milestone_components: self.milestone_components,
             storage: self.storage,
+            unknown_hob_policy: self.unknown_hob_policy,
+            memory_overlap_policy: self.memory_overlap_policy,
+            required_arch_protocols: self.required_arch_protocols,
+            dispatch_statistics: self.dispatch_statistics,
             _memory_state: core::marker::PhantomData,
         }
     }
@@ -303,6 +378,60 @@ impl Core<NoAlloc> {
         GCD.prioritize_32_bit_memory(true);
         self
     }
+
+    /// Sets how overlapping GCD memory space descriptors found after memory initialization are handled. Defaults to
+    /// [MemoryOverlapPolicy::Log].
+    ///
+    /// Must be called prior to [`Core::init_memory`].
+    pub fn with_memory_overlap_policy(mut self, policy: MemoryOverlapPolicy) -> Self {
+        self.memory_overlap_policy = policy;
+        self
+    }
+
+    /// Retains the load info of recently unloaded images in a bounded history.
+    ///
+    /// Stack traces taken after an image has been unloaded (or failed and was unloaded) can then still attribute an
+    /// address within the image's former range to it, marked as unloaded.
+    pub fn retain_unloaded_image_history(self) -> Self {
+        image::retain_unloaded_image_history(true);
+        self
+    }
+
+    /// Displays the disassembled DEPEX of each driver that was discovered but not dispatched, along with the installed
+    /// protocols, when dispatch completes.
+    ///
+    /// This makes it clear which dependency of a driver was not met, but the output is large.
+    pub fn display_undispatched_depex(self) -> Self {
+        dispatcher::display_undispatched_depex(true);
+        self
+    }
+
+    /// Sets how installing a protocol that is already installed on the handle is treated.
+    ///
+    /// By default such an install is rejected with `INVALID_PARAMETER`, as required by the UEFI spec. See
+    /// [`DuplicateProtocolPolicy`] for the semantics of each policy.
+    pub fn with_duplicate_protocol_policy(self, policy: DuplicateProtocolPolicy) -> Self {
+        PROTOCOL_DB.set_duplicate_protocol_policy(policy);
+        self
+    }
+
+    /// Times each event notify function, logging a warning for any that run longer than `threshold`.
+    ///
+    /// Notify functions run at raised TPLs, so a slow one stalls boot without any other indication. Timing uses the
+    /// performance timer, and is disabled by default.
+    pub fn warn_on_slow_event_notifies(self, threshold: core::time::Duration) -> Self {
+        events::set_slow_notify_threshold(u64::try_from(threshold.as_micros()).unwrap_or(u64::MAX).max(1));
+        self
+    }
+
+    /// Registers a hook invoked when a heap allocation fails.
+    ///
+    /// The core logs the failed layout and the statistics of each allocator, invokes the hook, and dumps a stack
+    /// trace before halting. The hook must not allocate.
+    pub fn with_alloc_error_hook(self, hook: fn(core::alloc::Layout)) -> Self {
+        allocator::set_alloc_error_hook(hook);
+        self
+    }
 }
 
 impl Core<Alloc> {
@@ -320,12 +449,35 @@ impl Core<Alloc> {
         self
     }
 
+    /// Registers a component with the core, that will be run when `milestone` is signaled rather than during the
+    /// driver execution phase.
+    ///
+    /// The component is run once, the first time the milestone is signaled after the handoff to BDS. If its parameters
+    /// are not available then, it is not run.
+    pub fn with_component_at<I>(mut self, milestone: MilestoneEvent, component: impl IntoComponent<I>) -> Self {
+        let mut component = component.into_component();
+        component.initialize(&mut self.storage);
+        self.milestone_components.push((milestone, component));
+        self
+    }
+
     /// Inserts a component at the given index. If no index is provided, the component is added to the end of the list.
     fn insert_component(&mut self, idx: usize, mut component: Box<dyn Component>) {
         component.initialize(&mut self.storage);
         self.components.insert(idx, component);
     }
 
+    /// Excludes a free system memory range from allocation, such as a range the platform knows to be faulty.
+    ///
+    /// The range becomes reserved memory that is not owned by any image. Fails without changing the memory map if any
+    /// part of the range is allocated or is not system memory.
+    pub fn exclude_memory_range(self, base_address: usize, len: usize) -> Result<Self> {
+        GCD.exclude_range(base_address, len).inspect_err(|err| {
+            log::error!("Failed to exclude memory range {base_address:#x} of length {len:#x}: {err:?}")
+        })?;
+        Ok(self)
+    }
+
     /// Adds a configuration value to the Core's storage. All configuration is locked by default. If a component is
     /// present that requires a mutable configuration, it will automatically be unlocked.
     pub fn with_config<C: Default + 'static>(mut self, config: C) -> Self {
@@ -333,49 +485,122 @@ impl Core<Alloc> {
         self
     }
 
+    /// Sets how GUID HOBs without a registered parser are handled. Defaults to [UnknownHobPolicy::Warn].
+    pub fn with_unknown_hob_policy(mut self, policy: UnknownHobPolicy) -> Self {
+        self.unknown_hob_policy = policy;
+        self
+    }
+
+    /// Sets the architectural protocols that must be installed once all drivers have been dispatched.
+    ///
+    /// If any are missing, [Core::start] reports an error status code and fails rather than proceeding into BDS,
+    /// where the missing protocol would otherwise cause obscure failures. By default, missing architectural protocols
+    /// are only logged.
+    pub fn with_required_arch_protocols(mut self, protocols: &[efi::Guid]) -> Self {
+        self.required_arch_protocols = protocols.to_vec();
+        self
+    }
+
+    /// Returns an error, after reporting an error status code, if any required architectural protocol is missing.
+    fn check_required_arch_protocols(&self) -> Result<()> {
+        let mut missing = false;
+        for guid in self.required_arch_protocols.iter().filter(|guid| PROTOCOL_DB.locate_protocol(**guid).is_err()) {
+            let uuid = patina::base::guid::to_uuid(guid);
+            let name = ARCH_PROTOCOLS.iter().find(|(arch, _)| *arch == uuid).map_or("Unknown", |(_, name)| *name);
+            log::error!("Missing required architectural protocol: {uuid:?}, {name:?}");
+            missing = true;
+        }
+
+        if missing {
+            report_status_code(
+                EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED,
+                EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_EC_NO_ARCH,
+            );
+            return Err(error::EfiError::NotFound);
+        }
+        Ok(())
+    }
+
     /// Parses the HOB list producing a `Hob\<T\>` struct for each guided HOB found with a registered parser.
-    fn parse_hobs(&mut self) {
+    ///
+    /// GUID HOBs without a registered parser are handled according to the [UnknownHobPolicy]. Returns an error if the
+    /// policy is [UnknownHobPolicy::Error] and any were found. A GUID HOB with less data than the size of a type it is
+    /// parsed to is logged and skipped for that type, rather than read past its end.
+    fn parse_hobs(&mut self) -> Result<()> {
+        let mut unparsed = Vec::new();
         for hob in self.hob_list.iter() {
             if let patina::pi::hob::Hob::GuidHob(guid, data) = hob {
-                let parser_funcs = self.storage.get_hob_parsers(&patina::OwnedGuid::from(guid.name));
-                if parser_funcs.is_empty() {
-                    let (f0, f1, f2, f3, f4, &[f5, f6, f7, f8, f9, f10]) = guid.name.as_fields();
-                    let name = alloc::format!(
-                        "{f0:08x}-{f1:04x}-{f2:04x}-{f3:02x}{f4:02x}-{f5:02x}{f6:02x}{f7:02x}{f8:02x}{f9:02x}{f10:02x}"
-                    );
-                    log::warn!(
-                        "No parser registered for HOB: GuidHob {{ {:?}, name: Guid {{ {} }} }}",
-                        guid.header,
-                        name
-                    );
+                let parsers = self.storage.get_hob_parsers(&patina::OwnedGuid::from(guid.name));
+                let name = patina::base::guid::to_uuid(&guid.name);
+                if parsers.is_empty() {
+                    match self.unknown_hob_policy {
+                        UnknownHobPolicy::Ignore => {}
+                        UnknownHobPolicy::Warn => log::warn!(
+                            "No parser registered for HOB: GuidHob {{ {:?}, name: Guid {{ {} }} }}",
+                            guid.header,
+                            name
+                        ),
+                        UnknownHobPolicy::Error => log::error!(
+                            "No parser registered for HOB: GuidHob {{ {:?}, name: Guid {{ {} }} }}",
+                            guid.header,
+                            name
+                        ),
+                    }
+                    unparsed.push(name);
                 } else {
-                    for parser_func in parser_funcs {
-                        parser_func(data, &mut self.storage);
+                    for parser in parsers {
+                        if let Err(err) = parser.register(data, &mut self.storage) {
+                            log::error!("Skipping HOB {name} for {}: {err}", parser.type_name());
+                        }
                     }
                 }
             }
         }
+
+        if self.unknown_hob_policy == UnknownHobPolicy::Error && !unparsed.is_empty() {
+            log::error!("No parser registered for HOBs: {unparsed:?}");
+            return Err(error::EfiError::NotFound);
+        }
+        Ok(())
     }
 
     /// Attempts to dispatch all components.
     ///
     /// This method will exit once no components remain or no components were dispatched during a full iteration.
+    /// With the `component_trace` feature, the dispatch time of each component is logged as a trace event.
     fn dispatch_components(&mut self) -> bool {
+        if cfg!(feature = "component_trace") {
+            self.dispatch_components_with(Some(&mut |timing| log::info!("{}{timing}", ComponentTiming::LOG_PREFIX)))
+        } else {
+            self.dispatch_components_with(None)
+        }
+    }
+
+    /// Attempts to dispatch all components, passing the timing of each dispatched component to `trace`.
+    fn dispatch_components_with(&mut self, mut trace: Option<&mut dyn FnMut(ComponentTiming)>) -> bool {
         let len = self.components.len();
+        let statistics = &mut self.dispatch_statistics;
         self.components.retain_mut(|component| {
             // Ok(true): Dispatchable and dispatched returning success
             // Ok(false): Not dispatchable at this time.
             // Err(e): Dispatchable and dispatched returning failure
             let name = component.metadata().name();
             log::trace!("Dispatch Start: Id = [{name:?}]");
-            !match component.run(&mut self.storage) {
+            let start = trace.is_some().then(perf_timer_us);
+            let result = component.run(&mut self.storage);
+            if let (Some(trace), Some(start), Ok(true) | Err(_)) = (trace.as_mut(), start, &result) {
+                trace(ComponentTiming { name, start, end: perf_timer_us() });
+            }
+            !match result {
                 Ok(true) => {
                     log::info!("Dispatched: Id = [{name:?}] Status = [Success]");
+                    statistics.components_dispatched += 1;
                     true
                 }
                 Ok(false) => false,
                 Err(err) => {
                     log::error!("Dispatched: Id = [{name:?}] Status = [Failed] Error = [{err:?}]");
+                    statistics.components_failed += 1;
                     debug_assert!(false);
                     true // Component dispatched, even if it did fail, so remove from self.components to avoid re-dispatch.
                 }
@@ -394,6 +619,8 @@ impl Core<Alloc> {
     fn core_dispatcher(&mut self) -> Result<()> {
         perf_function_begin(function!(), &CALLER_ID, create_performance_measurement);
         loop {
+            self.dispatch_statistics.dispatch_waves += 1;
+
             // Patina component dispatch
             let dispatched = self.dispatch_components();
 
@@ -410,6 +637,20 @@ impl Core<Alloc> {
         Ok(())
     }
 
+    /// Dispatches components and drivers until configuration is locked, then again after, reporting the start and end
+    /// of dispatch with `report`.
+    fn dispatch_drivers(&mut self, report: impl Fn(BootMilestone)) -> Result<()> {
+        report(BootMilestone::DispatchStart);
+        let start = perf_timer_us();
+        self.core_dispatcher()?;
+        self.storage.lock_configs();
+        self.core_dispatcher()?;
+        self.dispatch_statistics.dispatch_time_us += perf_timer_us().saturating_sub(start);
+        self.dispatch_statistics.components_not_dispatched = self.components.len();
+        report(BootMilestone::DispatchEnd);
+        Ok(())
+    }
+
     fn display_components_not_dispatched(&self) {
         if !self.components.is_empty() {
             let name_len = "name".len();
@@ -435,6 +676,14 @@ impl Core<Alloc> {
                     metadata.failed_param().unwrap_or("")
                 );
             }
+
+            let config_stalled = self.components.iter().any(|c| self.storage.is_blocked_on_config(c.metadata()));
+            if config_stalled {
+                log::warn!("Registered configs:");
+                for config in self.storage.describe_configs() {
+                    log::warn!("{} (locked: {})", config.name, config.locked);
+                }
+            }
         }
     }
 
@@ -528,7 +777,9 @@ impl Core<Alloc> {
     }
 
     /// Starts the core, dispatching all drivers.
-    pub fn start(mut self) -> Result<()> {
+    ///
+    /// Returns a summary of the dispatch if BDS returns control to the core.
+    pub fn start(mut self) -> Result<DispatchStatistics> {
         log::info!("Registering default components");
         self.add_core_components();
         log::info!("Finished.");
@@ -538,7 +789,7 @@ impl Core<Alloc> {
         log::info!("Finished.");
 
         log::info!("Parsing HOB list for Guided HOBs.");
-        self.parse_hobs();
+        self.parse_hobs()?;
         log::info!("Finished.");
 
         if let Some(extractor) = self.storage.get_service::<dyn SectionExtractor>() {
@@ -552,21 +803,119 @@ impl Core<Alloc> {
         log::info!("Finished.");
 
         log::info!("Dispatching Drivers");
-        self.core_dispatcher()?;
-        self.storage.lock_configs();
-        self.core_dispatcher()?;
+        self.dispatch_drivers(BootMilestone::report)?;
         log::info!("Finished Dispatching Drivers");
+        log::info!("Dispatch statistics: {:?}", self.dispatch_statistics);
 
         self.display_components_not_dispatched();
 
         core_display_missing_arch_protocols();
+        self.check_required_arch_protocols()?;
 
         dispatcher::display_discovered_not_dispatched();
+        allocator::report_outstanding_allocations();
+
+        if cfg!(feature = "boot_digest") {
+            let digest = BootDigest {
+                boot_time_us: perf_timer_us(),
+                statistics: self.dispatch_statistics,
+                missing_arch_protocols: missing_arch_protocols().map(|(_, name)| *name).collect(),
+                undispatched_drivers: dispatcher::undispatched_drivers(),
+            };
+            log::info!("{digest}");
+        }
+
+        // SAFETY: The storage is not used again until the milestone events are closed below, and BDS only returns to
+        // the core if it fails.
+        let milestone_components = core::mem::take(&mut self.milestone_components);
+        let milestone_registrations = unsafe { milestone::register(milestone_components, &mut self.storage)? };
 
+        BootMilestone::BdsHandoff.report();
         call_bds();
 
+        milestone::unregister(milestone_registrations);
         log::info!("Finished");
-        Ok(())
+        Ok(self.dispatch_statistics)
+    }
+}
+
+/// Boot milestones the core reports as the standard cross-module performance records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BootMilestone {
+    /// The start of dispatch, beginning the DXE phase measurement.
+    DispatchStart,
+    /// The end of dispatch, ending the DXE phase measurement.
+    DispatchEnd,
+    /// The handoff to BDS, beginning the BDS phase measurement.
+    BdsHandoff,
+}
+
+impl BootMilestone {
+    /// Reports the milestone if the `instrument_performance` feature is enabled.
+    fn report(self) {
+        if cfg!(feature = "instrument_performance") {
+            self.report_with(create_performance_measurement);
+        }
+    }
+
+    /// Reports the milestone to `create_performance_measurement`.
+    fn report_with(self, create_performance_measurement: CreateMeasurement) {
+        match self {
+            Self::DispatchStart => {
+                perf_cross_module_begin(KnownPerfToken::DXE.as_str(), &CALLER_ID, create_performance_measurement)
+            }
+            Self::DispatchEnd => {
+                perf_cross_module_end(KnownPerfToken::DXE.as_str(), &CALLER_ID, create_performance_measurement)
+            }
+            Self::BdsHandoff => {
+                perf_cross_module_begin(KnownPerfToken::BDS.as_str(), &CALLER_ID, create_performance_measurement)
+            }
+        }
+    }
+}
+
+/// Returns the current performance timer value in microseconds.
+pub(crate) fn perf_timer_us() -> u64 {
+    let frequency = (Arch::perf_frequency() as u128).max(1);
+    (Arch::cpu_count() as u128 * 1_000_000 / frequency) as u64
+}
+
+/// The dispatch time of a single component, measured with the performance timer.
+///
+/// Displays as a Chrome trace "complete" event on a single line, which trace viewers and flamegraph tools can load
+/// once the lines following [`ComponentTiming::LOG_PREFIX`] are collected into a JSON array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ComponentTiming<'a> {
+    /// The name of the component.
+    name: &'a str,
+    /// Time the component started running, in microseconds.
+    start: u64,
+    /// Time the component finished running, in microseconds.
+    end: u64,
+}
+
+impl ComponentTiming<'_> {
+    /// Prefix of the log lines carrying a component trace event.
+    const LOG_PREFIX: &'static str = "ComponentTrace: ";
+}
+
+impl core::fmt::Display for ComponentTiming<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("{\"name\":\"")?;
+        for c in self.name.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        write!(
+            f,
+            "\",\"cat\":\"component\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":0}}",
+            self.start,
+            self.end.saturating_sub(self.start)
+        )
     }
 }
 
@@ -586,30 +935,72 @@ const ARCH_PROTOCOLS: &[(uuid::Uuid, &str)] = &[
     (uuid::uuid!("27cfac87-46cc-11d4-9a38-0090273fc14d"), "Real Time Clock"),
 ];
 
+/// Returns the architectural protocols that are not installed.
+fn missing_arch_protocols() -> impl Iterator<Item = &'static (uuid::Uuid, &'static str)> {
+    ARCH_PROTOCOLS
+        .iter()
+        .filter(|(uuid, _)| protocols::PROTOCOL_DB.locate_protocol(patina::base::guid::from_uuid(uuid)).is_err())
+}
+
 fn core_display_missing_arch_protocols() {
-    for (uuid, name) in ARCH_PROTOCOLS {
-        let guid = efi::Guid::from_bytes(&uuid.to_bytes_le());
-        if protocols::PROTOCOL_DB.locate_protocol(guid).is_err() {
-            log::warn!("Missing architectural protocol: {uuid:?}, {name:?}");
+    for (uuid, name) in missing_arch_protocols() {
+        log::warn!("Missing architectural protocol: {uuid:?}, {name:?}");
+    }
+}
+
+/// A summary of the health of the boot, logged at BDS handoff with the `boot_digest` feature.
+struct BootDigest {
+    /// Time since the performance timer started, in microseconds.
+    boot_time_us: u64,
+    statistics: DispatchStatistics,
+    missing_arch_protocols: Vec<&'static str>,
+    undispatched_drivers: Vec<efi::Guid>,
+}
+
+impl core::fmt::Display for BootDigest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let ms = |us: u64| (us / 1000, us % 1000);
+        let (boot_ms, boot_us) = ms(self.boot_time_us);
+        let (dispatch_ms, dispatch_us) = ms(self.statistics.dispatch_time_us);
+        writeln!(f, "Boot digest:")?;
+        writeln!(f, "  Boot time: {boot_ms}.{boot_us:03} ms, dispatch {dispatch_ms}.{dispatch_us:03} ms")?;
+        writeln!(
+            f,
+            "  Components: {} dispatched, {} failed, {} not dispatched",
+            self.statistics.components_dispatched,
+            self.statistics.components_failed,
+            self.statistics.components_not_dispatched
+        )?;
+
+        write!(f, "  Missing architectural protocols: ")?;
+        if self.missing_arch_protocols.is_empty() {
+            writeln!(f, "none")?;
+        } else {
+            writeln!(f, "{}", self.missing_arch_protocols.join(", "))?;
+        }
+
+        write!(f, "  Undispatched drivers: {}", self.undispatched_drivers.len())?;
+        for driver in &self.undispatched_drivers {
+            write!(f, "\n    {:?}", guid_fmt!(driver))?;
         }
+        Ok(())
     }
 }
 
-fn call_bds() {
-    // Enable status code capability in Firmware Performance DXE.
+/// Reports a status code from the DXE core through the status code runtime protocol.
+fn report_status_code(code_type: u32, value: u32) {
     match protocols::PROTOCOL_DB.locate_protocol(status_code::PROTOCOL_GUID) {
         Ok(status_code_ptr) => {
             let status_code_protocol = unsafe { (status_code_ptr as *mut status_code::Protocol).as_mut() }.unwrap();
-            (status_code_protocol.report_status_code)(
-                EFI_PROGRESS_CODE,
-                EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT,
-                0,
-                &patina::guids::DXE_CORE,
-                ptr::null(),
-            );
+            (status_code_protocol.report_status_code)(code_type, value, 0, &patina::guids::DXE_CORE, ptr::null());
         }
         Err(err) => log::error!("Unable to locate status code runtime protocol: {err:?}"),
     };
+}
+
+fn call_bds() {
+    // Enable status code capability in Firmware Performance DXE.
+    report_status_code(EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_PC_HANDOFF_TO_NEXT);
 
     if let Ok(protocol) = protocols::PROTOCOL_DB.locate_protocol(bds::PROTOCOL_GUID) {
         let bds = protocol as *mut bds::Protocol;
@@ -620,3 +1011,348 @@ fn call_bds() {
         }
     }
 }
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use core::ffi::{CStr, c_char};
+    use patina::{
+        component::IntoComponent, performance::record::known::KnownPerfId,
+        uefi_protocol::performance_measurement::PerfAttribute,
+    };
+    use std::{string::String, sync::Mutex, vec};
+
+    impl Core<Alloc> {
+        /// Returns a default core with `hob_list`, as if memory were initialized.
+        fn for_test(hob_list: HobList<'static>) -> Self {
+            let default = Core::default();
+            Core {
+                physical_hob_list: default.physical_hob_list,
+                hob_list,
+                components: default.components,
+                milestone_components: default.milestone_components,
+                storage: default.storage,
+                unknown_hob_policy: default.unknown_hob_policy,
+                memory_overlap_policy: default.memory_overlap_policy,
+                required_arch_protocols: default.required_arch_protocols,
+                dispatch_statistics: default.dispatch_statistics,
+                _memory_state: core::marker::PhantomData,
+            }
+        }
+    }
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(IntoComponent, Default)]
+    struct RecordDispatch;
+
+    impl RecordDispatch {
+        fn entry_point(self) -> patina::error::Result<()> {
+            EVENTS.lock().unwrap().push("component".into());
+            Ok(())
+        }
+    }
+
+    extern "efiapi" fn record_measurement(
+        caller_identifier: *const c_void,
+        _guid: Option<&efi::Guid>,
+        string: *const c_char,
+        _ticker: u64,
+        _address: usize,
+        identifier: u32,
+        attribute: PerfAttribute,
+    ) -> efi::Status {
+        assert_eq!(attribute, PerfAttribute::PerfEntry);
+        assert_eq!(unsafe { *(caller_identifier as *const efi::Guid) }, CALLER_ID);
+        let token = unsafe { CStr::from_ptr(string) }.to_str().unwrap();
+        let marker = match KnownPerfId::try_from(identifier as u16) {
+            Ok(KnownPerfId::PerfCrossModuleStart) => "begin",
+            Ok(KnownPerfId::PerfCrossModuleEnd) => "end",
+            _ => panic!("unexpected performance record {identifier:#x}"),
+        };
+        EVENTS.lock().unwrap().push(std::format!("{marker} {token}"));
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn boot_milestones_should_report_cross_module_records() {
+        test_support::with_global_lock(|| {
+            EVENTS.lock().unwrap().clear();
+
+            BootMilestone::DispatchStart.report_with(record_measurement);
+            BootMilestone::DispatchEnd.report_with(record_measurement);
+            BootMilestone::BdsHandoff.report_with(record_measurement);
+
+            assert_eq!(*EVENTS.lock().unwrap(), vec!["begin DXE", "end DXE", "begin BDS"]);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn dispatch_drivers_should_report_milestones_around_dispatch() {
+        test_support::with_global_lock(|| {
+            unsafe { test_support::init_test_protocol_db() };
+            dispatcher::reset_dispatcher_context_for_tests();
+            EVENTS.lock().unwrap().clear();
+
+            let mut core = Core::<Alloc>::for_test(HobList::default()).with_component(RecordDispatch);
+
+            core.dispatch_drivers(|milestone| EVENTS.lock().unwrap().push(std::format!("{milestone:?}"))).unwrap();
+
+            assert_eq!(*EVENTS.lock().unwrap(), vec!["DispatchStart", "component", "DispatchEnd"]);
+        })
+        .unwrap();
+    }
+
+    #[derive(IntoComponent, Default)]
+    struct NeverDispatched;
+
+    impl NeverDispatched {
+        fn entry_point(
+            self,
+            _: patina::component::service::Service<dyn SectionExtractor>,
+        ) -> patina::error::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dispatch_drivers_should_accumulate_dispatch_statistics() {
+        test_support::with_global_lock(|| {
+            unsafe { test_support::init_test_protocol_db() };
+            dispatcher::reset_dispatcher_context_for_tests();
+
+            let mut core = Core::<Alloc>::for_test(HobList::default())
+                .with_component(RecordDispatch)
+                .with_component(SecondDispatch)
+                .with_component(NeverDispatched);
+
+            core.dispatch_drivers(|_| {}).unwrap();
+
+            let statistics = core.dispatch_statistics;
+            assert_eq!(statistics.components_dispatched, 2);
+            assert_eq!(statistics.components_failed, 0);
+            assert_eq!(statistics.components_not_dispatched, 1);
+            // One pass dispatching components and one finding nothing left before configs are locked, then one more
+            // pass after.
+            assert_eq!(statistics.dispatch_waves, 3);
+        })
+        .unwrap();
+    }
+
+    #[derive(IntoComponent, Default)]
+    struct SecondDispatch;
+
+    impl SecondDispatch {
+        fn entry_point(self) -> patina::error::Result<()> {
+            EVENTS.lock().unwrap().push("second component".into());
+            Ok(())
+        }
+    }
+
+    /// Returns the value of an unsigned integer field in a trace event line.
+    fn trace_field(line: &str, field: &str) -> u64 {
+        let value = line.split_once(&std::format!("\"{field}\":")).unwrap().1;
+        value[..value.find([',', '}']).unwrap()].parse().unwrap()
+    }
+
+    #[test]
+    fn component_dispatch_should_emit_monotonic_trace_events() {
+        test_support::with_global_lock(|| {
+            let mut core = Core::<Alloc>::for_test(HobList::default())
+                .with_component(RecordDispatch)
+                .with_component(SecondDispatch);
+
+            let mut lines = Vec::new();
+            assert!(core.dispatch_components_with(Some(&mut |timing| lines.push(std::format!("{timing}")))));
+            assert!(core.components.is_empty());
+
+            assert_eq!(lines.len(), 2);
+            assert!(lines[0].starts_with("{\"name\":\"") && lines[0].contains("RecordDispatch"));
+            assert!(lines[1].contains("SecondDispatch"));
+
+            let mut previous_end = 0;
+            for line in &lines {
+                assert!(line.contains("\"ph\":\"X\"") && line.ends_with('}'));
+                let start = trace_field(line, "ts");
+                let end = start + trace_field(line, "dur");
+                assert!(start >= previous_end, "{line} starts before the previous component ended");
+                previous_end = end;
+            }
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn component_trace_event_should_escape_the_name() {
+        let timing = ComponentTiming { name: "a\"b\\c", start: 10, end: 15 };
+        assert_eq!(
+            std::format!("{timing}"),
+            r#"{"name":"a\"b\\c","cat":"component","ph":"X","ts":10,"dur":5,"pid":0,"tid":0}"#
+        );
+    }
+
+    #[test]
+    fn boot_digest_should_summarize_the_boot() {
+        let digest = BootDigest {
+            boot_time_us: 1_234_567,
+            statistics: DispatchStatistics {
+                components_dispatched: 12,
+                components_failed: 1,
+                components_not_dispatched: 2,
+                dispatch_waves: 4,
+                dispatch_time_us: 890_012,
+            },
+            missing_arch_protocols: vec!["Timer", "Bds"],
+            undispatched_drivers: vec![efi::Guid::from_fields(
+                0x12345678,
+                0x9abc,
+                0xdef0,
+                0x12,
+                0x34,
+                &[0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0],
+            )],
+        };
+        assert_eq!(
+            std::format!("{digest}"),
+            "Boot digest:\n\
+             \x20 Boot time: 1234.567 ms, dispatch 890.012 ms\n\
+             \x20 Components: 12 dispatched, 1 failed, 2 not dispatched\n\
+             \x20 Missing architectural protocols: Timer, Bds\n\
+             \x20 Undispatched drivers: 1\n\
+             \x20   12345678-9ABC-DEF0-1234-56789ABCDEF0"
+        );
+
+        let digest = BootDigest {
+            boot_time_us: 5,
+            statistics: DispatchStatistics::default(),
+            missing_arch_protocols: vec![],
+            undispatched_drivers: vec![],
+        };
+        assert!(std::format!("{digest}").ends_with("Missing architectural protocols: none\n  Undispatched drivers: 0"));
+    }
+
+    fn core_with_unknown_hob(policy: UnknownHobPolicy) -> Core<Alloc> {
+        core_with_guid_hob(
+            efi::Guid::from_fields(0x1b1d3c4a, 0x5b1e, 0x4e0f, 0x9a, 0x3b, &[0x2c, 0x4d, 0x5e, 0x6f, 0x70, 0x81]),
+            &[],
+        )
+        .with_unknown_hob_policy(policy)
+    }
+
+    fn core_with_guid_hob(name: efi::Guid, data: &'static [u8]) -> Core<Alloc> {
+        let guid_hob = Box::leak(Box::new(patina::pi::hob::GuidHob {
+            header: patina::pi::hob::header::Hob {
+                r#type: patina::pi::hob::GUID_EXTENSION,
+                length: (core::mem::size_of::<patina::pi::hob::GuidHob>() + data.len()) as u16,
+                reserved: 0,
+            },
+            name,
+        }));
+        let mut hob_list = HobList::default();
+        hob_list.push(patina::pi::hob::Hob::GuidHob(guid_hob, data));
+
+        Core::<Alloc>::for_test(hob_list)
+    }
+
+    #[test]
+    fn unknown_hob_policy_should_default_to_warn() {
+        assert_eq!(Core::default().unknown_hob_policy, UnknownHobPolicy::Warn);
+    }
+
+    #[test]
+    fn unknown_hob_policy_ignore_should_parse_hobs() {
+        assert_eq!(core_with_unknown_hob(UnknownHobPolicy::Ignore).parse_hobs(), Ok(()));
+    }
+
+    #[test]
+    fn unknown_hob_policy_warn_should_parse_hobs() {
+        assert_eq!(core_with_unknown_hob(UnknownHobPolicy::Warn).parse_hobs(), Ok(()));
+    }
+
+    #[test]
+    fn unknown_hob_policy_error_should_fail_parse_hobs() {
+        assert_eq!(core_with_unknown_hob(UnknownHobPolicy::Error).parse_hobs(), Err(error::EfiError::NotFound));
+
+        let mut core = core_with_unknown_hob(UnknownHobPolicy::Error);
+        core.hob_list = HobList::default();
+        assert_eq!(core.parse_hobs(), Ok(()));
+    }
+
+    #[test]
+    fn short_guid_hob_should_be_skipped() {
+        const SIZED_HOB_GUID: efi::Guid =
+            efi::Guid::from_fields(0x5d0b6a2e, 0x3c1f, 0x4b7a, 0x8e, 0x21, &[0x9f, 0x4a, 0x6c, 0x1d, 0x2b, 0x70]);
+
+        struct SizedHob(u64);
+
+        impl patina::component::hob::FromHob for SizedHob {
+            const HOB_GUID: patina::OwnedGuid = patina::Guid::Owned(SIZED_HOB_GUID);
+            const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
+
+            fn parse(bytes: &[u8]) -> Self {
+                SizedHob(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+            }
+        }
+
+        #[derive(IntoComponent)]
+        struct UsesSizedHob;
+
+        impl UsesSizedHob {
+            fn entry_point(self, _hob: patina::component::hob::Hob<SizedHob>) -> patina::error::Result<()> {
+                Ok(())
+            }
+        }
+
+        for (data, parsed) in [(&[1_u8, 0, 0, 0][..], None), (&[2, 0, 0, 0, 0, 0, 0, 0][..], Some(2))] {
+            let mut core = core_with_guid_hob(SIZED_HOB_GUID, data);
+            UsesSizedHob.into_component().initialize(&mut core.storage);
+
+            assert_eq!(core.parse_hobs(), Ok(()));
+            assert_eq!(core.storage.get_hob::<SizedHob>().map(|hob| hob.0), parsed);
+        }
+    }
+
+    static STATUS_CODES: Mutex<Vec<(u32, u32)>> = Mutex::new(Vec::new());
+
+    extern "efiapi" fn record_status_code(
+        code_type: u32,
+        value: u32,
+        _instance: u32,
+        _caller_id: *const efi::Guid,
+        _data: *const patina::pi::protocols::status_code::EfiStatusCodeData,
+    ) -> efi::Status {
+        STATUS_CODES.lock().unwrap().push((code_type, value));
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn missing_required_arch_protocol_should_fail_with_status_code() {
+        test_support::with_global_lock(|| {
+            unsafe { test_support::init_test_protocol_db() };
+            STATUS_CODES.lock().unwrap().clear();
+
+            let status_code = Box::leak(Box::new(status_code::Protocol { report_status_code: record_status_code }));
+            PROTOCOL_DB
+                .install_protocol_interface(None, status_code::PROTOCOL_GUID, status_code as *mut _ as *mut c_void)
+                .unwrap();
+
+            let timer = patina::pi::protocols::timer::PROTOCOL_GUID;
+            let core = Core::<Alloc>::for_test(HobList::default()).with_required_arch_protocols(&[timer]);
+
+            assert_eq!(core.check_required_arch_protocols(), Err(error::EfiError::NotFound));
+            assert_eq!(
+                *STATUS_CODES.lock().unwrap(),
+                vec![(EFI_ERROR_CODE | EFI_ERROR_UNRECOVERED, EFI_SOFTWARE_DXE_CORE | EFI_SW_DXE_CORE_EC_NO_ARCH)]
+            );
+
+            // Once the protocol is installed, the check passes without reporting anything.
+            STATUS_CODES.lock().unwrap().clear();
+            PROTOCOL_DB.install_protocol_interface(None, timer, ptr::null_mut()).unwrap();
+            assert_eq!(core.check_required_arch_protocols(), Ok(()));
+            assert!(STATUS_CODES.lock().unwrap().is_empty());
+        })
+        .unwrap();
+    }
+}
@@ -15,7 +15,7 @@ use core::{
     sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
 use mu_rust_helpers::function;
-use patina::{base::UEFI_PAGE_MASK, error::EfiError};
+use patina::{base::UEFI_PAGE_MASK, boot_services::allocation::MemoryAttributes, error::EfiError};
 use r_efi::efi;
 
 #[repr(C)]
@@ -54,7 +54,7 @@ extern "efiapi" fn get_memory_attributes(
                 return efi::Status::NO_MAPPING;
             }
             // Safety: caller must provide a valid pointer to receive the attributes. It is null-checked above.
-            unsafe { attributes.write_unaligned(descriptor.attributes & efi::MEMORY_ACCESS_MASK) };
+            unsafe { attributes.write_unaligned(MemoryAttributes::from(descriptor.attributes).access().bits()) };
             efi::Status::SUCCESS
         }
         Err(status) => {
@@ -82,7 +82,7 @@ extern "efiapi" fn set_memory_attributes(
     }
 
     // UEFI spec only allows MEMORY_RO, MEMORY_RP, and MEMORY_XP to be set through this API
-    if attributes == 0 || (attributes & efi::MEMORY_ACCESS_MASK) != attributes {
+    if attributes == 0 || !MemoryAttributes::ACCESS_MASK.contains(attributes.into()) {
         log::error!("Invalid attributes {:x?} in {}", attributes, function!());
         return efi::Status::INVALID_PARAMETER;
     }
@@ -142,7 +142,7 @@ extern "efiapi" fn clear_memory_attributes(
     }
 
     // UEFI spec only allows MEMORY_RO, MEMORY_RP, and MEMORY_XP to be cleared through this API
-    if attributes == 0 || (attributes & efi::MEMORY_ACCESS_MASK) != attributes {
+    if attributes == 0 || !MemoryAttributes::ACCESS_MASK.contains(attributes.into()) {
         log::error!("Invalid attributes {:x?} in {}", attributes, function!());
         return efi::Status::INVALID_PARAMETER;
     }
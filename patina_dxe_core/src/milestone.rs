@@ -0,0 +1,180 @@
+//! DXE Core Milestone Components
+//!
+//! Components registered to run at a boot milestone rather than by dependency-driven dispatch. Each milestone is a
+//! UEFI event group; the core creates an event in the group that runs the component, with its parameters injected
+//! from the core's storage, when the group is signaled.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use alloc::{boxed::Box, vec::Vec};
+use core::ffi::c_void;
+
+use patina::{
+    component::{Component, Storage},
+    error::EfiError,
+    guids,
+};
+use r_efi::efi;
+
+use crate::events::EVENT_DB;
+
+/// A boot milestone at which a component can be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneEvent {
+    /// The end of the DXE phase, signaled by BDS before any third party code is run.
+    EndOfDxe,
+    /// Selection of a boot option, signaled by BDS before booting it.
+    ReadyToBoot,
+    /// Exit of boot services, signaled by ExitBootServices(). The component must not allocate memory, and any boot
+    /// services it uses must be usable during the exit.
+    ExitBootServices,
+}
+
+impl MilestoneEvent {
+    /// Returns the event group signaled at the milestone.
+    pub const fn event_group(self) -> efi::Guid {
+        match self {
+            Self::EndOfDxe => guids::EVENT_GROUP_END_OF_DXE,
+            Self::ReadyToBoot => efi::EVENT_GROUP_READY_TO_BOOT,
+            Self::ExitBootServices => efi::EVENT_GROUP_EXIT_BOOT_SERVICES,
+        }
+    }
+}
+
+/// The context of a milestone event, running the component once when the milestone is signaled.
+struct MilestoneContext {
+    milestone: MilestoneEvent,
+    component: Box<dyn Component>,
+    storage: *mut Storage,
+    ran: bool,
+}
+
+/// An event created by [`register`], to be closed by [`unregister`].
+pub(crate) struct Registration {
+    event: efi::Event,
+    context: *mut MilestoneContext,
+}
+
+/// Creates an event in the group of each milestone, running its component with `storage` when the group is signaled.
+///
+/// A component runs at most once, the first time its milestone is signaled.
+///
+/// ## Safety
+///
+/// `storage` must be valid, and not otherwise borrowed whenever a milestone may be signaled, until the registrations
+/// are passed to [`unregister`].
+pub(crate) unsafe fn register(
+    components: Vec<(MilestoneEvent, Box<dyn Component>)>,
+    storage: *mut Storage,
+) -> Result<Vec<Registration>, EfiError> {
+    let mut registrations = Vec::with_capacity(components.len());
+    for (milestone, component) in components {
+        let context = Box::into_raw(Box::new(MilestoneContext { milestone, component, storage, ran: false }));
+        match EVENT_DB.create_event(
+            efi::EVT_NOTIFY_SIGNAL,
+            efi::TPL_CALLBACK,
+            Some(run_milestone_component),
+            Some(context as *mut c_void),
+            Some(milestone.event_group()),
+        ) {
+            Ok(event) => registrations.push(Registration { event, context }),
+            Err(err) => {
+                // SAFETY: The context was just leaked, and no event refers to it.
+                drop(unsafe { Box::from_raw(context) });
+                unregister(registrations);
+                return Err(err);
+            }
+        }
+    }
+    Ok(registrations)
+}
+
+/// Closes the events created by [`register`], so their components no longer run.
+pub(crate) fn unregister(registrations: Vec<Registration>) {
+    for registration in registrations {
+        if let Err(err) = EVENT_DB.close_event(registration.event) {
+            log::warn!("Could not close milestone event: {err:?}");
+            continue;
+        }
+        // SAFETY: The context was leaked by `register`, and the event referring to it is closed.
+        drop(unsafe { Box::from_raw(registration.context) });
+    }
+}
+
+extern "efiapi" fn run_milestone_component(_event: efi::Event, context: *mut c_void) {
+    // SAFETY: The context was leaked by `register`, and is valid until the event is closed.
+    let context = unsafe { &mut *(context as *mut MilestoneContext) };
+    if context.ran {
+        return;
+    }
+    context.ran = true;
+
+    let name = context.component.metadata().name();
+    let milestone = context.milestone;
+    // SAFETY: The caller of `register` guarantees the storage is valid and not otherwise borrowed.
+    match context.component.run(unsafe { &mut *context.storage }) {
+        Ok(true) => log::info!("Dispatched at {milestone:?}: Id = [{name:?}] Status = [Success]"),
+        Ok(false) => log::warn!("Not dispatched at {milestone:?}: Id = [{name:?}] Parameters are unavailable."),
+        Err(err) => log::error!("Dispatched at {milestone:?}: Id = [{name:?}] Status = [Failed] Error = [{err:?}]"),
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use crate::{events, test_support};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use patina::component::{IntoComponent, params::Config};
+
+    static RUN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(IntoComponent, Default)]
+    struct AtEndOfDxe;
+
+    impl AtEndOfDxe {
+        fn entry_point(self, increment: Config<usize>) -> patina::error::Result<()> {
+            RUN_COUNT.fetch_add(*increment, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Signals the group, dispatching the notify functions of its events.
+    fn signal(group: efi::Guid) {
+        let old_tpl = events::raise_tpl(efi::TPL_HIGH_LEVEL);
+        EVENT_DB.signal_group(group);
+        events::restore_tpl(old_tpl);
+    }
+
+    #[test]
+    fn milestone_component_should_run_once_when_the_milestone_is_signaled() {
+        test_support::with_global_lock(|| {
+            RUN_COUNT.store(0, Ordering::SeqCst);
+
+            let mut storage = Storage::new();
+            storage.add_config(3_usize);
+            let mut component = AtEndOfDxe.into_component();
+            component.initialize(&mut storage);
+
+            // SAFETY: The storage is only used by the milestone event until it is unregistered.
+            let registrations =
+                unsafe { register(alloc::vec![(MilestoneEvent::EndOfDxe, component)], &mut storage) }.unwrap();
+
+            signal(MilestoneEvent::ReadyToBoot.event_group());
+            assert_eq!(RUN_COUNT.load(Ordering::SeqCst), 0);
+
+            signal(MilestoneEvent::EndOfDxe.event_group());
+            assert_eq!(RUN_COUNT.load(Ordering::SeqCst), 3);
+
+            signal(MilestoneEvent::EndOfDxe.event_group());
+            assert_eq!(RUN_COUNT.load(Ordering::SeqCst), 3);
+
+            unregister(registrations);
+        })
+        .unwrap();
+    }
+}
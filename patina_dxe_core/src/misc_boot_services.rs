@@ -11,8 +11,8 @@ use core::{
     slice::from_raw_parts,
     sync::atomic::{AtomicBool, AtomicPtr, Ordering},
 };
-use patina::guids;
 use patina::pi::{protocols, status_code};
+use patina::{boot_services::StandardBootServices, guids};
 use patina_internal_cpu::interrupts;
 use r_efi::efi;
 
@@ -174,6 +174,13 @@ pub extern "efiapi" fn exit_boot_services(_handle: efi::Handle, map_key: usize)
     // Signal Exit Boot Services
     EVENT_DB.signal_group(efi::EVENT_GROUP_EXIT_BOOT_SERVICES);
 
+    // Cancel any outstanding timers so that no stale timer callback fires after the handoff.
+    // The memory map is terminated, so this must not allocate.
+    let cancelled_timers = EVENT_DB.cancel_all_timers();
+    if cancelled_timers != 0 {
+        log::info!("Cancelled {cancelled_timers} pending timer events.");
+    }
+
     // Initialize StatusCode and send EFI_SW_BS_PC_EXIT_BOOT_SERVICES
     match PROTOCOL_DB.locate_protocol(protocols::status_code::PROTOCOL_GUID) {
         Ok(status_code_ptr) => {
@@ -200,6 +207,9 @@ pub extern "efiapi" fn exit_boot_services(_handle: efi::Handle, map_key: usize)
         .expect("The System Table pointer is null. This is invalid.")
         .clear_boot_time_services();
 
+    // Boot services handed to components through StandardBootServices are no longer usable.
+    StandardBootServices::mark_exited();
+
     match PROTOCOL_DB.locate_protocol(protocols::runtime::PROTOCOL_GUID) {
         Ok(rt_arch_ptr) => {
             let rt_arch_ptr = rt_arch_ptr as *mut protocols::runtime::Protocol;
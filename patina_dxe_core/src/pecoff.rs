@@ -251,6 +251,9 @@ pub fn load_image(pe_info: &UefiPeInfo, image: &[u8], loaded_image: &mut [u8]) -
 ///
 /// Returns [`BufferTooShort`](error::Error::BufferTooShort) error if either of the buffers provided are
 /// not large enough to contain the image as specified by the image header.
+///
+/// Returns [`UnsupportedRelocationType`](error::Error::UnsupportedRelocationType) error if the image contains
+/// a relocation type other than ABSOLUTE, HIGHLOW and DIR64.
 pub fn relocate_image(
     pe_info: &UefiPeInfo,
     destination: usize,
@@ -266,20 +269,74 @@ pub fn relocate_image(
     let base = image.pread_with::<u64>(pe_info.image_base_header_field_offset, LE)?;
     image.pwrite_with::<u64>(destination as u64 - rva_offset as u64, pe_info.image_base_header_field_offset, LE)?;
 
-    let adjustment = (destination as u64).wrapping_sub(base + rva_offset as u64);
+    let Some(dir) = pe_info.reloc_dir else {
+        return Ok(Vec::new());
+    };
+
+    apply_relocations(image, &dir, rva_offset, base + rva_offset as u64, destination as u64, prev_reloc_blocks)
+}
 
-    if adjustment == 0 || pe_info.reloc_dir.is_none() {
+/// Applies the base relocations of an already loaded image.
+///
+/// Adjusts every fixup described by the relocation directory by the difference between `actual_base` and
+/// `preferred_base`, returning the relocation blocks with the relocated value of each DIR64 fixup. `rva_offset` is the
+/// RVA the start of `image` is loaded at, which is zero for an image laid out at its RVAs. Unlike
+/// [`relocate_image`], the image base in the image header is not updated, so this may be used on any loaded image.
+///
+/// If `prev_reloc_blocks` holds the blocks returned by a previous relocation of the image, DIR64 fixups the image has
+/// modified since are not recorded. Otherwise it should be empty.
+///
+/// ## Errors
+///
+/// Returns [`BufferTooShort`](error::Error::BufferTooShort) error if the relocation directory is not contained
+/// in the image.
+///
+/// Returns [`Parse`](error::Error::Parse) error if a relocation block or fixup is not contained in the image.
+///
+/// Returns [`UnsupportedRelocationType`](error::Error::UnsupportedRelocationType) error if the image contains
+/// a relocation type other than ABSOLUTE, HIGHLOW and DIR64.
+pub fn apply_relocations(
+    image: &mut [u8],
+    reloc_dir: &goblin::pe::data_directories::DataDirectory,
+    rva_offset: usize,
+    preferred_base: u64,
+    actual_base: u64,
+    prev_reloc_blocks: &[RelocationBlock],
+) -> error::Result<Vec<RelocationBlock>> {
+    let adjustment = actual_base.wrapping_sub(preferred_base);
+    if adjustment == 0 {
         return Ok(Vec::new());
     }
 
-    let dir = pe_info.reloc_dir.expect("Reloc Dir was not None above.");
+    let mut relocation_blocks = parse_relocation_directory(image, reloc_dir)?;
+    assert!(prev_reloc_blocks.is_empty() || relocation_blocks.len() == prev_reloc_blocks.len());
+    apply_relocation_blocks(image, &mut relocation_blocks, adjustment, rva_offset, prev_reloc_blocks)?;
+    Ok(relocation_blocks)
+}
+
+/// Parses the relocation blocks described by the relocation directory of a loaded image.
+fn parse_relocation_directory(
+    image: &[u8],
+    dir: &goblin::pe::data_directories::DataDirectory,
+) -> error::Result<Vec<RelocationBlock>> {
     let relocation_data = image
         .get((dir.virtual_address as usize)..(dir.virtual_address as usize + dir.size as usize))
         .ok_or(error::Error::BufferTooShort(dir.size as usize, "image"))?;
+    parse_relocation_blocks(relocation_data)
+}
 
-    let mut relocation_block = parse_relocation_blocks(relocation_data)?;
-    assert!(prev_reloc_blocks.is_empty() || relocation_block.len() == prev_reloc_blocks.len());
-    for (block_idx, reloc_block) in relocation_block.iter_mut().enumerate() {
+/// Applies `adjustment` to each fixup in `relocation_blocks`, recording the relocated value of DIR64 fixups.
+///
+/// If `prev_reloc_blocks` is not empty, DIR64 fixups whose value no longer matches the previously recorded value
+/// are not recorded, as the image has since modified them.
+fn apply_relocation_blocks(
+    image: &mut [u8],
+    relocation_blocks: &mut [RelocationBlock],
+    adjustment: u64,
+    rva_offset: usize,
+    prev_reloc_blocks: &[RelocationBlock],
+) -> error::Result<()> {
+    for (block_idx, reloc_block) in relocation_blocks.iter_mut().enumerate() {
         for (reloc_idx, reloc) in reloc_block.relocations.iter_mut().enumerate() {
             let fixup_type = reloc.type_and_offset >> 12;
             let fixup =
@@ -307,11 +364,11 @@ pub fn relocate_image(
                     let subslice = image.get_mut(fixup..fixup + 8).ok_or(error::Error::BufferTooShort(8, "image"))?;
                     subslice.copy_from_slice(&value.to_le_bytes()[..]);
                 }
-                _ => todo!(), // Other fixups not implemented at this time
+                _ => return Err(error::Error::UnsupportedRelocationType(fixup_type)),
             }
         }
     }
-    Ok(relocation_block)
+    Ok(())
 }
 
 /// Converts a vector of relocation blocks into a flat buffer suitable for use in the runtime protocol.
@@ -485,6 +542,118 @@ pub fn load_resource_section(pe_info: &UefiPeInfo, image: &[u8]) -> error::Resul
     Ok(None)
 }
 
+// Offset of the PE header offset in the DOS header.
+const DOS_PE_POINTER_OFFSET: usize = 0x3C;
+// Signature of the PE header.
+const PE_SIGNATURE: u32 = u32::from_le_bytes(*b"PE\0\0");
+// Magic value for a PE32 optional header.
+const PE32_OPTIONAL_HEADER_MAGIC: u16 = 0x10B;
+// Magic value for a PE32+ optional header.
+const PE32_PLUS_OPTIONAL_HEADER_MAGIC: u16 = 0x20B;
+// Offset of the size of the optional header in the COFF header.
+const COFF_SIZE_OF_OPTIONAL_HEADER_OFFSET: usize = 16;
+// Index of the debug directory in the optional header data directories.
+const DEBUG_DIRECTORY_INDEX: usize = 6;
+// Size of a data directory entry.
+const SIZEOF_DATA_DIRECTORY: usize = 8;
+// Size of a debug directory entry.
+const SIZEOF_DEBUG_DIRECTORY_ENTRY: usize = 28;
+// Debug directory entry type for CodeView debug information.
+const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+// Signature of CodeView PDB 7.0 debug information.
+const CODEVIEW_PDB70_SIGNATURE: u32 = u32::from_le_bytes(*b"RSDS");
+// Offset of the PDB path in CodeView PDB 7.0 debug information.
+const CODEVIEW_PDB70_PATH_OFFSET: usize = 24;
+
+/// CodeView debug information of an image, used to match the image with its symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugInfo {
+    /// The GUID identifying the PDB matching the image.
+    pub guid: r_efi::efi::Guid,
+    /// The age of the PDB matching the image.
+    pub age: u32,
+    /// The path to the PDB, as recorded by the linker.
+    pub pdb_path: String,
+}
+
+/// Reads the CodeView debug information of the image loaded at `image_base`.
+///
+/// Returns `None` if the image has no CodeView PDB 7.0 debug directory entry, or if any of the headers or directories
+/// do not fit within `image_size` bytes.
+///
+/// ## Safety
+///
+/// `image_base` must point to a loaded image that is readable for `image_size` bytes.
+pub unsafe fn read_debug_info(image_base: usize, image_size: usize) -> Option<DebugInfo> {
+    if image_base == 0 {
+        return None;
+    }
+    // SAFETY: The caller guarantees the image is readable for `image_size` bytes.
+    let loaded_image = unsafe { core::slice::from_raw_parts(image_base as *const u8, image_size) };
+    parse_debug_info(loaded_image)
+}
+
+/// Parses the CodeView debug information of a loaded image, where offsets are relative virtual addresses.
+///
+/// See [`read_debug_info`].
+pub fn parse_debug_info(loaded_image: &[u8]) -> Option<DebugInfo> {
+    if loaded_image.pread_with::<u16>(0, LE).ok()? != PE32_MAGIC {
+        return None;
+    }
+    let pe_offset = loaded_image.pread_with::<u32>(DOS_PE_POINTER_OFFSET, LE).ok()? as usize;
+    if loaded_image.pread_with::<u32>(pe_offset, LE).ok()? != PE_SIGNATURE {
+        return None;
+    }
+
+    let coff_offset = pe_offset.checked_add(SIZEOF_PE32_SIGNATURE)?;
+    let size_of_optional_header = loaded_image
+        .pread_with::<u16>(coff_offset.checked_add(COFF_SIZE_OF_OPTIONAL_HEADER_OFFSET)?, LE)
+        .ok()? as usize;
+    let optional_header_offset = coff_offset.checked_add(SIZEOF_COFF_HEADER)?;
+    let optional_header =
+        loaded_image.get(optional_header_offset..optional_header_offset.checked_add(size_of_optional_header)?)?;
+
+    // The number of data directories is the last of the windows fields, directly followed by the data directories.
+    let number_of_rva_and_sizes_offset = match optional_header.pread_with::<u16>(0, LE).ok()? {
+        PE32_OPTIONAL_HEADER_MAGIC => 92,
+        PE32_PLUS_OPTIONAL_HEADER_MAGIC => 108,
+        _ => return None,
+    };
+    let number_of_rva_and_sizes = optional_header.pread_with::<u32>(number_of_rva_and_sizes_offset, LE).ok()? as usize;
+    if number_of_rva_and_sizes <= DEBUG_DIRECTORY_INDEX {
+        return None;
+    }
+    let debug_directory_offset = number_of_rva_and_sizes_offset + 4 + DEBUG_DIRECTORY_INDEX * SIZEOF_DATA_DIRECTORY;
+    let debug_directory_rva = optional_header.pread_with::<u32>(debug_directory_offset, LE).ok()? as usize;
+    let debug_directory_size = optional_header.pread_with::<u32>(debug_directory_offset + 4, LE).ok()? as usize;
+
+    let debug_directory =
+        loaded_image.get(debug_directory_rva..debug_directory_rva.checked_add(debug_directory_size)?)?;
+    debug_directory.chunks_exact(SIZEOF_DEBUG_DIRECTORY_ENTRY).find_map(|entry| {
+        if entry.pread_with::<u32>(12, LE).ok()? != IMAGE_DEBUG_TYPE_CODEVIEW {
+            return None;
+        }
+        let size_of_data = entry.pread_with::<u32>(16, LE).ok()? as usize;
+        let address_of_raw_data = entry.pread_with::<u32>(20, LE).ok()? as usize;
+        parse_codeview_pdb70(loaded_image.get(address_of_raw_data..address_of_raw_data.checked_add(size_of_data)?)?)
+    })
+}
+
+/// Parses CodeView PDB 7.0 (`RSDS`) debug information.
+fn parse_codeview_pdb70(codeview: &[u8]) -> Option<DebugInfo> {
+    if codeview.pread_with::<u32>(0, LE).ok()? != CODEVIEW_PDB70_SIGNATURE {
+        return None;
+    }
+    let guid = r_efi::efi::Guid::from_bytes(codeview.get(4..20)?.try_into().ok()?);
+    let age = codeview.pread_with::<u32>(20, LE).ok()?;
+
+    let path = codeview.get(CODEVIEW_PDB70_PATH_OFFSET..)?;
+    let path_len = path.iter().position(|&c| c == b'\0')?;
+    let pdb_path = String::from_utf8_lossy(&path[..path_len]).into_owned();
+
+    Some(DebugInfo { guid, age, pdb_path })
+}
+
 #[cfg(test)]
 #[coverage(off)]
 mod tests {
@@ -667,6 +836,70 @@ mod tests {
         assert_eq!(relocated_once, relocated_twice);
     }
 
+    /// Builds a synthetic loaded image with a single relocation block at `RELOC_RVA` containing `entries`.
+    fn synthetic_relocated_image(entries: &[u16]) -> (Vec<u8>, goblin::pe::data_directories::DataDirectory) {
+        const RELOC_RVA: usize = 0x1000;
+        let mut image = vec![0_u8; 0x2000];
+        image.pwrite_with(0x1000_0100_u64, 0x100, LE).unwrap();
+        image.pwrite_with(0x1000_0200_u32, 0x200, LE).unwrap();
+
+        let block_size = 8 + entries.len() * 2;
+        image.pwrite_with(0_u32, RELOC_RVA, LE).unwrap();
+        image.pwrite_with(block_size as u32, RELOC_RVA + 4, LE).unwrap();
+        for (idx, entry) in entries.iter().enumerate() {
+            image.pwrite_with(*entry, RELOC_RVA + 8 + idx * 2, LE).unwrap();
+        }
+
+        let dir =
+            goblin::pe::data_directories::DataDirectory { virtual_address: RELOC_RVA as u32, size: block_size as u32 };
+        (image, dir)
+    }
+
+    #[test]
+    fn apply_relocations_should_patch_fixups() {
+        let entries = [IMAGE_REL_BASED_DIR64 << 12 | 0x100, IMAGE_REL_BASED_HIGHLOW << 12 | 0x200, 0, 0];
+        let (mut image, dir) = synthetic_relocated_image(&entries);
+        let original = image.clone();
+
+        let blocks = apply_relocations(&mut image, &dir, 0, 0x1000_0000, 0x2000_0000, &[]).unwrap();
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].relocations[0].value, 0x2000_0100);
+        assert_eq!(image.pread_with::<u64>(0x100, LE).unwrap(), 0x2000_0100);
+        assert_eq!(image.pread_with::<u32>(0x200, LE).unwrap(), 0x2000_0200);
+        // Nothing outside the fixups should change.
+        assert_eq!(image[..0x100], original[..0x100]);
+        assert_eq!(image[0x108..0x200], original[0x108..0x200]);
+        assert_eq!(image[0x204..], original[0x204..]);
+    }
+
+    #[test]
+    fn apply_relocations_to_preferred_base_should_do_nothing() {
+        let (mut image, dir) = synthetic_relocated_image(&[IMAGE_REL_BASED_DIR64 << 12 | 0x100, 0]);
+        let original = image.clone();
+
+        assert!(apply_relocations(&mut image, &dir, 0, 0x1000_0000, 0x1000_0000, &[]).unwrap().is_empty());
+        assert_eq!(image, original);
+    }
+
+    #[test]
+    fn apply_relocations_should_reject_unknown_types() {
+        // IMAGE_REL_BASED_ARM_MOV32
+        let (mut image, dir) = synthetic_relocated_image(&[5 << 12 | 0x100, 0]);
+
+        let result = apply_relocations(&mut image, &dir, 0, 0x1000_0000, 0x2000_0000, &[]);
+        assert!(matches!(result, Err(error::Error::UnsupportedRelocationType(5))));
+    }
+
+    #[test]
+    fn apply_relocations_should_reject_directory_outside_image() {
+        let (mut image, _) = synthetic_relocated_image(&[0, 0]);
+        let dir = goblin::pe::data_directories::DataDirectory { virtual_address: 0x1FFC, size: 0x10 };
+
+        let result = apply_relocations(&mut image, &dir, 0, 0x1000_0000, 0x2000_0000, &[]);
+        assert!(matches!(result, Err(error::Error::BufferTooShort(0x10, "image"))));
+    }
+
     #[test]
     fn pe_relocate_image_should_relocate_the_image() {
         let image = include_bytes!("../resources/test/pe32/test_image.pe32");
@@ -814,4 +1047,82 @@ mod tests {
             Err(e) => panic!("Expected BufferTooShort error, got {e:?}"),
         }
     }
+
+    /// Builds a synthetic loaded PE32+ image with a CodeView debug entry at `CODEVIEW_RVA`.
+    fn synthetic_debug_image(pdb_path: &str) -> Vec<u8> {
+        const PE_OFFSET: usize = 0x80;
+        const DEBUG_DIRECTORY_RVA: usize = 0x200;
+        const CODEVIEW_RVA: usize = 0x240;
+        let mut image = vec![0_u8; 0x400];
+        image.pwrite_with(PE32_MAGIC, 0, LE).unwrap();
+        image.pwrite_with(PE_OFFSET as u32, DOS_PE_POINTER_OFFSET, LE).unwrap();
+        image.pwrite_with(PE_SIGNATURE, PE_OFFSET, LE).unwrap();
+
+        let coff = PE_OFFSET + SIZEOF_PE32_SIGNATURE;
+        image.pwrite_with(240_u16, coff + COFF_SIZE_OF_OPTIONAL_HEADER_OFFSET, LE).unwrap();
+        let optional_header = coff + SIZEOF_COFF_HEADER;
+        image.pwrite_with(PE32_PLUS_OPTIONAL_HEADER_MAGIC, optional_header, LE).unwrap();
+        image.pwrite_with(16_u32, optional_header + 108, LE).unwrap();
+        let debug_directory = optional_header + 112 + DEBUG_DIRECTORY_INDEX * SIZEOF_DATA_DIRECTORY;
+        image.pwrite_with(DEBUG_DIRECTORY_RVA as u32, debug_directory, LE).unwrap();
+        image.pwrite_with(SIZEOF_DEBUG_DIRECTORY_ENTRY as u32, debug_directory + 4, LE).unwrap();
+
+        let codeview_size = CODEVIEW_PDB70_PATH_OFFSET + pdb_path.len() + 1;
+        image.pwrite_with(IMAGE_DEBUG_TYPE_CODEVIEW, DEBUG_DIRECTORY_RVA + 12, LE).unwrap();
+        image.pwrite_with(codeview_size as u32, DEBUG_DIRECTORY_RVA + 16, LE).unwrap();
+        image.pwrite_with(CODEVIEW_RVA as u32, DEBUG_DIRECTORY_RVA + 20, LE).unwrap();
+
+        image.pwrite_with(CODEVIEW_PDB70_SIGNATURE, CODEVIEW_RVA, LE).unwrap();
+        image[CODEVIEW_RVA + 4..CODEVIEW_RVA + 20].copy_from_slice(&[0xA5; 16]);
+        image.pwrite_with(3_u32, CODEVIEW_RVA + 20, LE).unwrap();
+        image.pwrite_with(pdb_path.as_bytes(), CODEVIEW_RVA + CODEVIEW_PDB70_PATH_OFFSET, ()).unwrap();
+        image
+    }
+
+    #[test]
+    fn parse_debug_info_should_read_codeview_entry() {
+        let image = synthetic_debug_image("c:\\build\\X64\\MyDriver.pdb");
+
+        let debug_info = parse_debug_info(&image).unwrap();
+        assert_eq!(debug_info.guid, r_efi::efi::Guid::from_bytes(&[0xA5; 16]));
+        assert_eq!(debug_info.age, 3);
+        assert_eq!(debug_info.pdb_path, "c:\\build\\X64\\MyDriver.pdb");
+
+        let debug_info_from_address = unsafe { read_debug_info(image.as_ptr() as usize, image.len()) };
+        assert_eq!(debug_info_from_address, Some(debug_info));
+    }
+
+    #[test]
+    fn parse_debug_info_should_bounds_check_directories() {
+        let image = synthetic_debug_image("/build/MyDriver.pdb");
+
+        // Truncating the image anywhere before the end of the PDB path hides the debug info.
+        assert!(parse_debug_info(&image[..0x240 + CODEVIEW_PDB70_PATH_OFFSET + 8]).is_none());
+        assert!(parse_debug_info(&image[..0x210]).is_none());
+        assert!(parse_debug_info(&image[..0x100]).is_none());
+
+        // Directories pointing outside of the image are rejected.
+        let mut bad_pe_offset = image.clone();
+        bad_pe_offset.pwrite_with(u32::MAX, DOS_PE_POINTER_OFFSET, LE).unwrap();
+        assert!(parse_debug_info(&bad_pe_offset).is_none());
+
+        let mut bad_directory = image.clone();
+        let debug_directory = 0x80 + SIZEOF_PE32_SIGNATURE + SIZEOF_COFF_HEADER + 112 + DEBUG_DIRECTORY_INDEX * 8;
+        bad_directory.pwrite_with(u32::MAX, debug_directory + 4, LE).unwrap();
+        assert!(parse_debug_info(&bad_directory).is_none());
+
+        let mut bad_codeview = image.clone();
+        bad_codeview.pwrite_with(u32::MAX - 4, 0x200 + 20, LE).unwrap();
+        assert!(parse_debug_info(&bad_codeview).is_none());
+
+        assert!(unsafe { read_debug_info(0, 0x1000) }.is_none());
+    }
+
+    #[test]
+    fn parse_debug_info_should_read_loaded_image() {
+        let loaded_image = include_bytes!("../resources/test/pe32/test_image_loaded.bin");
+        let debug_info = parse_debug_info(loaded_image).unwrap();
+        assert!(debug_info.pdb_path.ends_with("RustFfiTestDxe.pdb"), "{}", debug_info.pdb_path);
+        assert_ne!(debug_info.guid, r_efi::efi::Guid::from_bytes(&[0; 16]));
+    }
 }
@@ -21,6 +21,8 @@ pub enum Error {
     BadSignature(u16),
     /// The parsed PeCoff image does not contain an Optional Header.
     NoOptionalHeader,
+    /// The image contains a base relocation of an unsupported type.
+    UnsupportedRelocationType(u16),
 }
 
 impl From<scroll::Error> for Error {
@@ -16,7 +16,7 @@ use alloc::{
     vec::Vec,
 };
 use core::{cmp::Ordering, ffi::c_void, hash::Hasher};
-use patina::error::EfiError;
+use patina::{base::guid, error::EfiError};
 use r_efi::efi;
 
 use crate::tpl_lock;
@@ -36,6 +36,18 @@ pub const EFI_RUNTIME_SERVICES_DATA_ALLOCATOR_HANDLE: efi::Handle = 8 as efi::Ha
 pub const EFI_ACPI_RECLAIM_MEMORY_ALLOCATOR_HANDLE: efi::Handle = 9 as efi::Handle;
 pub const EFI_ACPI_MEMORY_NVS_ALLOCATOR_HANDLE: efi::Handle = 10 as efi::Handle;
 
+/// How an install treats a protocol that is already installed on the handle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateProtocolPolicy {
+    /// The install fails with `INVALID_PARAMETER`, as required by InstallProtocolInterface() in the UEFI spec.
+    #[default]
+    Reject,
+    /// The installed interface is replaced with the new interface, and notifies registered on the protocol are
+    /// signaled as for a new install. The install fails with `ACCESS_DENIED` if any agents have the protocol open, as
+    /// they would be left holding the replaced interface.
+    Reinstall,
+}
+
 /// This structure is used to track open protocol information on a handle.
 ///
 /// It is returned from [`get_open_protocol_information`](SpinLockedProtocolDb::get_open_protocol_information)],
@@ -187,6 +199,43 @@ impl Handle {
     }
 }
 
+// A single (handle, protocol, interface) entry as captured by a snapshot.
+type InterfaceEntry = (usize, [u8; 16], usize);
+
+/// An opaque point-in-time capture of the protocol database contents.
+///
+/// Returned from [`snapshot`](SpinLockedProtocolDb::snapshot) and passed to
+/// [`diff_since`](SpinLockedProtocolDb::diff_since) to compute what changed in the meantime.
+pub struct ProtocolDbSnapshot {
+    generation: u64,
+    interfaces: BTreeSet<InterfaceEntry>,
+}
+
+/// The changes made to the protocol database between a [`ProtocolDbSnapshot`] and a later point.
+///
+/// Handles and interfaces are listed in ascending handle order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolDbDiff {
+    /// Handles that did not exist at the time of the snapshot.
+    pub added_handles: Vec<efi::Handle>,
+    /// Handles that existed at the time of the snapshot but have since been removed.
+    pub removed_handles: Vec<efi::Handle>,
+    /// Protocol interfaces installed since the snapshot, as (handle, protocol, interface).
+    pub added_interfaces: Vec<(efi::Handle, efi::Guid, *mut c_void)>,
+    /// Protocol interfaces uninstalled since the snapshot, as (handle, protocol, interface).
+    pub removed_interfaces: Vec<(efi::Handle, efi::Guid, *mut c_void)>,
+}
+
+impl ProtocolDbDiff {
+    /// Returns true if the protocol database did not change.
+    pub fn is_empty(&self) -> bool {
+        self.added_handles.is_empty()
+            && self.removed_handles.is_empty()
+            && self.added_interfaces.is_empty()
+            && self.removed_interfaces.is_empty()
+    }
+}
+
 // This is the main implementation of the protocol database, but public
 // interaction with the database should be via [`SpinLockedProtocolDb`] below.
 struct ProtocolDb {
@@ -195,6 +244,8 @@ struct ProtocolDb {
     hash_new_handles: bool,
     next_handle: usize,
     next_registration: usize,
+    generation: u64,
+    duplicate_policy: DuplicateProtocolPolicy,
 }
 
 impl ProtocolDb {
@@ -205,6 +256,8 @@ impl ProtocolDb {
             hash_new_handles: false,
             next_handle: 1,
             next_registration: 1,
+            generation: 0,
+            duplicate_policy: DuplicateProtocolPolicy::Reject,
         }
     }
 
@@ -223,7 +276,10 @@ impl ProtocolDb {
         handle: Option<efi::Handle>,
         protocol: efi::Guid,
         interface: *mut c_void,
+        policy: Option<DuplicateProtocolPolicy>,
     ) -> Result<(efi::Handle, Vec<ProtocolNotify>), EfiError> {
+        let policy = policy.unwrap_or(self.duplicate_policy);
+
         //generate an output handle.
         let (output_handle, key) = match handle {
             Some(handle) => {
@@ -260,17 +316,23 @@ impl ProtocolDb {
         debug_assert!(self.handles.contains_key(&key));
         let handle_instance = self.handles.get_mut(&key).ok_or(EfiError::Unsupported)?;
 
-        if handle_instance.contains_key(&OrdGuid(protocol)) {
-            return Err(EfiError::InvalidParameter);
+        if let Some(instance) = handle_instance.get(&OrdGuid(protocol)) {
+            match policy {
+                DuplicateProtocolPolicy::Reject => return Err(EfiError::InvalidParameter),
+                DuplicateProtocolPolicy::Reinstall if !instance.usage.is_empty() => {
+                    return Err(EfiError::AccessDenied);
+                }
+                DuplicateProtocolPolicy::Reinstall => (),
+            }
         }
 
         //create a new protocol instance to match the input.
         let protocol_instance =
             ProtocolInstance { interface, opened_by_driver: false, opened_by_exclusive: false, usage: Vec::new() };
 
-        //attempt to add the protocol to the set of protocols on this handle.
+        //add the protocol to the set of protocols on this handle, replacing an unused instance if reinstalling.
         let exists = handle_instance.insert(OrdGuid(protocol), protocol_instance);
-        assert!(exists.is_none()); //should be guaranteed by the `contains_key` check above.
+        debug_assert!(exists.is_none() || policy == DuplicateProtocolPolicy::Reinstall);
 
         //determine if there are any events to be notified.
         if let Some(events) = self.notifications.get_mut(&OrdGuid(protocol)) {
@@ -283,6 +345,7 @@ impl ProtocolDb {
             None => vec![],
         };
 
+        self.generation += 1;
         Ok((output_handle, events))
     }
 
@@ -317,9 +380,45 @@ impl ProtocolDb {
             self.handles.remove(&key);
         }
 
+        self.generation += 1;
         Ok(())
     }
 
+    fn interface_entries(&self) -> BTreeSet<InterfaceEntry> {
+        self.handles
+            .iter()
+            .flat_map(|(key, handle)| {
+                handle.iter().map(|(OrdGuid(guid), instance)| (*key, *guid.as_bytes(), instance.interface as usize))
+            })
+            .collect()
+    }
+
+    fn snapshot(&self) -> ProtocolDbSnapshot {
+        ProtocolDbSnapshot { generation: self.generation, interfaces: self.interface_entries() }
+    }
+
+    fn diff_since(&self, snapshot: &ProtocolDbSnapshot) -> ProtocolDbDiff {
+        if snapshot.generation == self.generation {
+            return ProtocolDbDiff::default();
+        }
+
+        let current = self.interface_entries();
+        let to_interface = |&(key, guid, interface): &InterfaceEntry| {
+            (key as efi::Handle, efi::Guid::from_bytes(&guid), interface as *mut c_void)
+        };
+        let handles_of = |entries: &BTreeSet<InterfaceEntry>| -> BTreeSet<usize> {
+            entries.iter().map(|(key, _, _)| *key).collect()
+        };
+        let (old_handles, new_handles) = (handles_of(&snapshot.interfaces), handles_of(&current));
+
+        ProtocolDbDiff {
+            added_handles: new_handles.difference(&old_handles).map(|key| *key as efi::Handle).collect(),
+            removed_handles: old_handles.difference(&new_handles).map(|key| *key as efi::Handle).collect(),
+            added_interfaces: current.difference(&snapshot.interfaces).map(to_interface).collect(),
+            removed_interfaces: snapshot.interfaces.difference(&current).map(to_interface).collect(),
+        }
+    }
+
     fn locate_handles(&mut self, protocol: Option<efi::Guid>) -> Result<Vec<efi::Handle>, EfiError> {
         let mut handles: Vec<_> = self
             .handles
@@ -624,6 +723,8 @@ impl SpinLockedProtocolDb {
         inner.hash_new_handles = false;
         inner.next_handle = 1;
         inner.next_registration = 1;
+        inner.generation = 0;
+        inner.duplicate_policy = DuplicateProtocolPolicy::Reject;
     }
 
     fn lock(&self) -> tpl_lock::TplGuard<'_, ProtocolDb> {
@@ -638,7 +739,7 @@ impl SpinLockedProtocolDb {
     /// Initialize the protocol database. Installs well-known handles, and then enables hashing to ensure handles are
     /// opaque.
     pub fn init_protocol_db(&self) {
-        let well_known_handle_guid = efi::Guid::from_bytes(WELL_KNOWN_HANDLE_PROTOCOL_GUID.as_bytes());
+        let well_known_handle_guid = guid::from_uuid(&WELL_KNOWN_HANDLE_PROTOCOL_GUID);
 
         let well_known_handles = &[
             DXE_CORE_HANDLE,
@@ -671,6 +772,9 @@ impl SpinLockedProtocolDb {
     /// no handle was provided on input), as well as a vector of [`ProtocolNotify`] structures that the caller can use to
     /// signal events for any registered notifies on this protocol installation.
     ///
+    /// A protocol already installed on the handle is treated according to the policy set with
+    /// [`set_duplicate_protocol_policy`](Self::set_duplicate_protocol_policy), rejecting it by default.
+    ///
     /// ## Errors
     ///
     /// Returns r_efi:efi::Status::INVALID_PARAMETER if incorrect parameters are given.
@@ -680,7 +784,35 @@ impl SpinLockedProtocolDb {
         guid: efi::Guid,
         interface: *mut c_void,
     ) -> Result<(efi::Handle, Vec<ProtocolNotify>), EfiError> {
-        self.lock().install_protocol_interface(handle, guid, interface)
+        self.install_protocol_interface_with_policy(handle, guid, interface, None)
+    }
+
+    /// Installs a protocol interface on the given handle, treating a protocol already installed on the handle
+    /// according to `policy`.
+    ///
+    /// This is otherwise the same as [`install_protocol_interface`](Self::install_protocol_interface). If `policy` is
+    /// `None`, the policy set with [`set_duplicate_protocol_policy`](Self::set_duplicate_protocol_policy) is used.
+    ///
+    /// ## Errors
+    ///
+    /// Returns r_efi:efi::Status::INVALID_PARAMETER if incorrect parameters are given, or if the protocol is already
+    /// installed on the handle and the policy is [`DuplicateProtocolPolicy::Reject`].
+    /// Returns r_efi:efi::Status::ACCESS_DENIED if the protocol is already installed on the handle and open by an
+    /// agent, and the policy is [`DuplicateProtocolPolicy::Reinstall`].
+    pub fn install_protocol_interface_with_policy(
+        &self,
+        handle: Option<efi::Handle>,
+        guid: efi::Guid,
+        interface: *mut c_void,
+        policy: Option<DuplicateProtocolPolicy>,
+    ) -> Result<(efi::Handle, Vec<ProtocolNotify>), EfiError> {
+        self.lock().install_protocol_interface(handle, guid, interface, policy)
+    }
+
+    /// Sets how installs treat a protocol that is already installed on the handle, when no policy is given for the
+    /// install. The default is [`DuplicateProtocolPolicy::Reject`].
+    pub fn set_duplicate_protocol_policy(&self, policy: DuplicateProtocolPolicy) {
+        self.lock().duplicate_policy = policy;
     }
 
     /// Removes a protocol interface from the given handle.
@@ -864,6 +996,19 @@ impl SpinLockedProtocolDb {
     pub fn get_child_handles(&self, parent_handle: efi::Handle) -> Vec<efi::Handle> {
         self.lock().get_child_handles(parent_handle)
     }
+
+    /// Captures the current contents of the protocol database.
+    ///
+    /// The returned token can later be passed to [`diff_since`](Self::diff_since) to determine which handles and
+    /// protocol interfaces were added or removed in between, e.g. to attribute changes to a specific dispatch step.
+    pub fn snapshot(&self) -> ProtocolDbSnapshot {
+        self.lock().snapshot()
+    }
+
+    /// Returns the changes made to the protocol database since the given snapshot was taken.
+    pub fn diff_since(&self, snapshot: &ProtocolDbSnapshot) -> ProtocolDbDiff {
+        self.lock().diff_since(snapshot)
+    }
 }
 
 unsafe impl Send for SpinLockedProtocolDb {}
@@ -946,7 +1091,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -966,13 +1111,82 @@ mod tests {
         });
     }
 
+    #[test]
+    fn install_protocol_interface_should_reject_duplicates_by_default() {
+        with_locked_state(|| {
+            static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
+
+            let guid1 = guid::from_uuid(&Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap());
+            let interface1: *mut c_void = 0x1234 as *mut c_void;
+            let interface2: *mut c_void = 0x5678 as *mut c_void;
+
+            let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
+            assert_eq!(
+                SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(Some(handle), guid1, interface2).err(),
+                Some(EfiError::InvalidParameter)
+            );
+            assert_eq!(
+                SPIN_LOCKED_PROTOCOL_DB
+                    .install_protocol_interface_with_policy(
+                        Some(handle),
+                        guid1,
+                        interface2,
+                        Some(DuplicateProtocolPolicy::Reject)
+                    )
+                    .err(),
+                Some(EfiError::InvalidParameter)
+            );
+            assert_eq!(SPIN_LOCKED_PROTOCOL_DB.get_interface_for_handle(handle, guid1), Ok(interface1));
+        });
+    }
+
+    #[test]
+    fn install_protocol_interface_should_replace_duplicates_when_reinstalling() {
+        with_locked_state(|| {
+            static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
+
+            let guid1 = guid::from_uuid(&Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap());
+            let interface1: *mut c_void = 0x1234 as *mut c_void;
+            let interface2: *mut c_void = 0x5678 as *mut c_void;
+            let interface3: *mut c_void = 0x9abc as *mut c_void;
+
+            // Reinstall for a single install.
+            let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
+            let (reinstalled, _) = SPIN_LOCKED_PROTOCOL_DB
+                .install_protocol_interface_with_policy(
+                    Some(handle),
+                    guid1,
+                    interface2,
+                    Some(DuplicateProtocolPolicy::Reinstall),
+                )
+                .unwrap();
+            assert_eq!(reinstalled, handle);
+            assert_eq!(SPIN_LOCKED_PROTOCOL_DB.get_interface_for_handle(handle, guid1), Ok(interface2));
+
+            // Reinstall for all installs.
+            SPIN_LOCKED_PROTOCOL_DB.set_duplicate_protocol_policy(DuplicateProtocolPolicy::Reinstall);
+            SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(Some(handle), guid1, interface3).unwrap();
+            assert_eq!(SPIN_LOCKED_PROTOCOL_DB.get_interface_for_handle(handle, guid1), Ok(interface3));
+
+            // An interface in use is not replaced.
+            SPIN_LOCKED_PROTOCOL_DB
+                .add_protocol_usage(handle, guid1, Some(handle), None, efi::OPEN_PROTOCOL_GET_PROTOCOL)
+                .unwrap();
+            assert_eq!(
+                SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(Some(handle), guid1, interface1).err(),
+                Some(EfiError::AccessDenied)
+            );
+            assert_eq!(SPIN_LOCKED_PROTOCOL_DB.get_interface_for_handle(handle, guid1), Ok(interface3));
+        });
+    }
+
     #[test]
     fn uninstall_protocol_interface_should_uninstall_protocol_interface() {
         with_locked_state(|| {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -991,7 +1205,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1026,11 +1240,11 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let uuid2 = Uuid::from_str("9c5dca1d-ac0f-46db-9eba-2bc961c711a2").unwrap();
-            let guid2 = efi::Guid::from_bytes(uuid2.as_bytes());
+            let guid2 = guid::from_uuid(&uuid2);
             let interface2: *mut c_void = 0x4321 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1049,15 +1263,15 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let uuid2 = Uuid::from_str("9c5dca1d-ac0f-46db-9eba-2bc961c711a2").unwrap();
-            let guid2 = efi::Guid::from_bytes(uuid2.as_bytes());
+            let guid2 = guid::from_uuid(&uuid2);
             let interface2: *mut c_void = 0x4321 as *mut c_void;
 
             let uuid3 = Uuid::from_str("2a32017e-7e6b-4563-890d-fff945530438").unwrap();
-            let guid3 = efi::Guid::from_bytes(uuid3.as_bytes());
+            let guid3 = guid::from_uuid(&uuid3);
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
             assert_eq!(
@@ -1097,7 +1311,7 @@ mod tests {
             SPIN_LOCKED_PROTOCOL_DB.lock().enable_handle_hashing();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let mut created_handles = Vec::new();
@@ -1117,7 +1331,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1134,7 +1348,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1149,7 +1363,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1185,7 +1399,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1280,7 +1494,7 @@ mod tests {
         static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
         let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-        let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+        let guid1 = guid::from_uuid(&uuid1);
         let interface1: *mut c_void = 0x1234 as *mut c_void;
 
         let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1389,7 +1603,7 @@ mod tests {
         static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
         let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-        let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+        let guid1 = guid::from_uuid(&uuid1);
         let interface1: *mut c_void = 0x1234 as *mut c_void;
 
         let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1482,7 +1696,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (agent, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1519,7 +1733,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid = efi::Guid::from_bytes(uuid.as_bytes());
+            let guid = guid::from_uuid(&uuid);
             let interface: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid, interface).unwrap();
@@ -1585,7 +1799,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1637,7 +1851,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1685,7 +1899,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let attributes_list = [
@@ -1727,11 +1941,11 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let uuid2 = Uuid::from_str("98d32ea1-e980-46b5-bb2c-564934c8cce6").unwrap();
-            let guid2 = efi::Guid::from_bytes(uuid2.as_bytes());
+            let guid2 = guid::from_uuid(&uuid2);
             let interface2: *mut c_void = 0x4321 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1757,7 +1971,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1784,7 +1998,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let attributes_list = [
@@ -1827,7 +2041,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1843,11 +2057,11 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let uuid2 = Uuid::from_str("98d32ea1-e980-46b5-bb2c-564934c8cce6").unwrap();
-            let guid2 = efi::Guid::from_bytes(uuid2.as_bytes());
+            let guid2 = guid::from_uuid(&uuid2);
             let interface2: *mut c_void = 0x4321 as *mut c_void;
 
             let (handle, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1866,11 +2080,11 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let uuid2 = Uuid::from_str("98d32ea1-e980-46b5-bb2c-564934c8cce6").unwrap();
-            let guid2 = efi::Guid::from_bytes(uuid2.as_bytes());
+            let guid2 = guid::from_uuid(&uuid2);
             let interface2: *mut c_void = 0x4321 as *mut c_void;
 
             SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -1887,7 +2101,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
 
             let event = 0x1234 as *mut c_void;
             let result = SPIN_LOCKED_PROTOCOL_DB.register_protocol_notify(guid1, event);
@@ -1929,7 +2143,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let event = 0x8765 as *mut c_void;
@@ -1960,7 +2174,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let event = 0x8765 as *mut c_void;
@@ -1985,7 +2199,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let event = 0x8765 as *mut c_void;
@@ -2029,7 +2243,7 @@ mod tests {
             static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
 
             let uuid1 = Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap();
-            let guid1 = efi::Guid::from_bytes(uuid1.as_bytes());
+            let guid1 = guid::from_uuid(&uuid1);
             let interface1: *mut c_void = 0x1234 as *mut c_void;
 
             let (controller, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
@@ -2060,6 +2274,42 @@ mod tests {
         });
     }
 
+    #[test]
+    fn diff_since_should_report_changes_between_snapshots() {
+        with_locked_state(|| {
+            static SPIN_LOCKED_PROTOCOL_DB: SpinLockedProtocolDb = SpinLockedProtocolDb::new();
+
+            let guid1 = guid::from_uuid(&Uuid::from_str("0e896c7a-57dc-4987-bc22-abc3a8263210").unwrap());
+            let guid2 = guid::from_uuid(&Uuid::from_str("98d32ea1-e980-46b5-bb2c-564934c8cce6").unwrap());
+            let interface1: *mut c_void = 0x1111 as *mut c_void;
+            let interface2: *mut c_void = 0x2222 as *mut c_void;
+
+            let (handle1, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid1, interface1).unwrap();
+
+            let snapshot = SPIN_LOCKED_PROTOCOL_DB.snapshot();
+            assert!(SPIN_LOCKED_PROTOCOL_DB.diff_since(&snapshot).is_empty());
+
+            let (handle2, _) = SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(None, guid2, interface2).unwrap();
+            SPIN_LOCKED_PROTOCOL_DB.install_protocol_interface(Some(handle1), guid2, interface2).unwrap();
+
+            let diff = SPIN_LOCKED_PROTOCOL_DB.diff_since(&snapshot);
+            assert_eq!(diff.added_handles, vec![handle2]);
+            assert!(diff.removed_handles.is_empty());
+            assert_eq!(diff.added_interfaces, vec![(handle1, guid2, interface2), (handle2, guid2, interface2)]);
+            assert!(diff.removed_interfaces.is_empty());
+
+            let snapshot = SPIN_LOCKED_PROTOCOL_DB.snapshot();
+            SPIN_LOCKED_PROTOCOL_DB.uninstall_protocol_interface(handle1, guid1, interface1).unwrap();
+            SPIN_LOCKED_PROTOCOL_DB.uninstall_protocol_interface(handle2, guid2, interface2).unwrap();
+
+            let diff = SPIN_LOCKED_PROTOCOL_DB.diff_since(&snapshot);
+            assert!(diff.added_handles.is_empty());
+            assert_eq!(diff.removed_handles, vec![handle2]);
+            assert!(diff.added_interfaces.is_empty());
+            assert_eq!(diff.removed_interfaces, vec![(handle1, guid1, interface1), (handle2, guid2, interface2)]);
+        });
+    }
+
     #[test]
     fn xorshift64starhasher_test_different_seeds() {
         let seed1 = 12345;
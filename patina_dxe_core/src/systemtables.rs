@@ -11,7 +11,7 @@
 use core::{ffi::c_void, mem::size_of, slice::from_raw_parts};
 
 use alloc::{alloc::Allocator, boxed::Box};
-use patina::{boot_services::BootServices, component::IntoComponent};
+use patina::{base::guid, boot_services::BootServices, component::IntoComponent};
 use r_efi::efi;
 
 use crate::{allocator::EFI_RUNTIME_SERVICES_DATA_ALLOCATOR, tpl_lock};
@@ -728,22 +728,22 @@ impl SystemTableChecksumInstaller {
         }
 
         const GUIDS: [efi::Guid; 16] = [
-            efi::Guid::from_bytes(&uuid::uuid!("1DA97072-BDDC-4B30-99F1-72A0B56FFF2A").to_bytes_le()), // gEfiMonotonicCounterArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("1E5668E2-8481-11D4-BCF1-0080C73C8881").to_bytes_le()), // gEfiVariableArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("26BACCB1-6F42-11D4-BC7E-0080C73C8881").to_bytes_le()), // gEfiCpuArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("26BACCB2-6F42-11D4-BCE7-0080C73C8881").to_bytes_le()), // gEfiMetronomeArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("26BACCB3-6F42-11D4-BCE7-0080C73C8881").to_bytes_le()), // gEfiTimerArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("27CFAC87-46CC-11D4-9A38-0090273FC14D").to_bytes_le()), // gEfiRealTimeClockArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("27CFAC88-46CC-11D4-9A38-0090273FC14D").to_bytes_le()), // gEfiResetArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("5053697E-2CBC-4819-90D9-0580DEEE5754").to_bytes_le()), // gEfiCapsuleArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("55198405-26c0-4765-8b7d-be1df5f99712").to_bytes_le()), // gEfiCpu2ProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("6441F818-6362-4E44-B570-7DBA31DD2453").to_bytes_le()), // gEfiVariableWriteArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("665E3FF5-46CC-11D4-9A38-0090273FC14D").to_bytes_le()), // gEfiWatchdogTimerArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("665E3FF6-46CC-11D4-9A38-0090273FC14D").to_bytes_le()), // gEfiBdsArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("94AB2F58-1438-4EF1-9152-18941894A3A0").to_bytes_le()), // gEfiSecurity2ArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("A46423E3-4617-49F1-B9FF-D1BFA9115839").to_bytes_le()), // gEfiSecurityArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("B7DFB4E1-052F-449F-87BE-9818FC91B733").to_bytes_le()), // gEfiRuntimeArchProtocolGuid
-            efi::Guid::from_bytes(&uuid::uuid!("F4CCBFB7-F6E0-47FD-9DD4-10A8F150C191").to_bytes_le()), // gEfiSmmBase2ProtocolGuid
+            guid::from_uuid(&uuid::uuid!("1DA97072-BDDC-4B30-99F1-72A0B56FFF2A")), // gEfiMonotonicCounterArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("1E5668E2-8481-11D4-BCF1-0080C73C8881")), // gEfiVariableArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("26BACCB1-6F42-11D4-BC7E-0080C73C8881")), // gEfiCpuArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("26BACCB2-6F42-11D4-BCE7-0080C73C8881")), // gEfiMetronomeArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("26BACCB3-6F42-11D4-BCE7-0080C73C8881")), // gEfiTimerArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("27CFAC87-46CC-11D4-9A38-0090273FC14D")), // gEfiRealTimeClockArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("27CFAC88-46CC-11D4-9A38-0090273FC14D")), // gEfiResetArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("5053697E-2CBC-4819-90D9-0580DEEE5754")), // gEfiCapsuleArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("55198405-26c0-4765-8b7d-be1df5f99712")), // gEfiCpu2ProtocolGuid
+            guid::from_uuid(&uuid::uuid!("6441F818-6362-4E44-B570-7DBA31DD2453")), // gEfiVariableWriteArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("665E3FF5-46CC-11D4-9A38-0090273FC14D")), // gEfiWatchdogTimerArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("665E3FF6-46CC-11D4-9A38-0090273FC14D")), // gEfiBdsArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("94AB2F58-1438-4EF1-9152-18941894A3A0")), // gEfiSecurity2ArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("A46423E3-4617-49F1-B9FF-D1BFA9115839")), // gEfiSecurityArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("B7DFB4E1-052F-449F-87BE-9818FC91B733")), // gEfiRuntimeArchProtocolGuid
+            guid::from_uuid(&uuid::uuid!("F4CCBFB7-F6E0-47FD-9DD4-10A8F150C191")), // gEfiSmmBase2ProtocolGuid
         ];
 
         for guid in &GUIDS {
@@ -164,7 +164,7 @@ mod util {
             match hob {
                 patina::pi::hob::Hob::GuidHob(hob, data) => {
                     for parser in storage.get_hob_parsers(&patina::Guid::from(hob.name)) {
-                        parser(data, storage);
+                        parser.register(data, storage).expect("HOB data should match the type.");
                     }
                 }
                 _ => continue, // Skip other types of HOBs
@@ -9,3 +9,34 @@
 
 #[cfg(all(not(test), target_arch = "aarch64"))]
 pub mod aarch64;
+
+/// Flushes the CPU write-combining buffers.
+///
+/// Writes to write-combining memory may be held in the CPU and reach memory out of order. Once this returns, every
+/// earlier write has reached memory, so a device can be told to read the buffer, or the buffer read back.
+#[inline(always)]
+pub fn flush_write_combining() {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: SFENCE only orders prior stores, and is available on every x86_64 CPU.
+    unsafe {
+        core::arch::asm!("sfence", options(nostack, preserves_flags))
+    };
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: DSB ST only waits for prior stores to complete.
+    unsafe {
+        core::arch::asm!("dsb st", options(nostack, preserves_flags))
+    };
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_write_combining_is_callable() {
+        flush_write_combining();
+    }
+}
@@ -76,6 +76,21 @@
 //! # Ok::<(), GuidError>(())
 //! ```
 //!
+//! ## Converting to and from `uuid::Uuid`
+//!
+//! The first three fields of an `efi::Guid` are stored little-endian, while a `uuid::Uuid` stores all of its bytes
+//! big-endian, so the two cannot be converted by copying their bytes. Use [`to_uuid`] and [`from_uuid`] instead:
+//!
+//! ```rust
+//! use patina::base::guid;
+//!
+//! let uuid = uuid::uuid!("23c9322f-2af2-476a-bc4c-26bc88266c71");
+//! let efi_guid = guid::from_uuid(&uuid);
+//! assert_eq!(efi_guid, patina::guids::DXE_CORE);
+//! assert_eq!(guid::to_uuid(&efi_guid), uuid);
+//! assert_eq!(guid::from_str("23C9322F-2AF2-476A-BC4C-26BC88266C71"), Ok(efi_guid));
+//! ```
+//!
 //! ## License
 //!
 //! Copyright (C) Microsoft Corporation.
@@ -85,6 +100,7 @@
 
 use crate::error::EfiError;
 use r_efi::efi;
+use uuid::Uuid;
 
 /// The expected number of hexadecimal characters in a valid GUID string representation
 const EXPECTED_HEX_CHARS: usize = 32;
@@ -608,6 +624,23 @@ impl<'a> TryFrom<&'a str> for OwnedGuid {
     }
 }
 
+/// Converts an `efi::Guid` to the `uuid::Uuid` with the same value.
+pub const fn to_uuid(guid: &efi::Guid) -> Uuid {
+    Uuid::from_bytes_le(*guid.as_bytes())
+}
+
+/// Converts a `uuid::Uuid` to the `efi::Guid` with the same value.
+pub const fn from_uuid(uuid: &Uuid) -> efi::Guid {
+    efi::Guid::from_bytes(&uuid.to_bytes_le())
+}
+
+/// Parses an `efi::Guid` from its string representation, with or without dashes.
+///
+/// See [`OwnedGuid::try_from_string`] for the accepted formats.
+pub fn from_str(s: &str) -> core::result::Result<efi::Guid, GuidError> {
+    OwnedGuid::try_from_string(s).map(|guid| guid.to_efi_guid())
+}
+
 #[cfg(test)]
 #[coverage(off)]
 mod tests {
@@ -1758,4 +1791,50 @@ mod tests {
         assert_eq!(fields.1, 0x1122);
         assert_eq!(fields.2, 0x3344);
     }
+
+    #[test]
+    fn uuid_conversion_should_swap_the_little_endian_fields() {
+        let uuid = uuid::uuid!("00112233-4455-6677-8899-aabbccddeeff");
+        let guid = from_uuid(&uuid);
+
+        assert_eq!(
+            guid.as_bytes(),
+            &[0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]
+        );
+        assert_eq!(guid.as_fields(), (0x00112233, 0x4455, 0x6677, 0x88, 0x99, &[0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]));
+        assert_eq!(to_uuid(&guid), uuid);
+    }
+
+    #[test]
+    fn uuid_conversion_should_round_trip_known_guids() {
+        let known_guids = [
+            ("00000000-0000-0000-0000-000000000000", efi::Guid::from_bytes(&[0; 16])),
+            ("ffffffff-ffff-ffff-ffff-ffffffffffff", efi::Guid::from_bytes(&[0xff; 16])),
+            ("23c9322f-2af2-476a-bc4c-26bc88266c71", crate::guids::DXE_CORE),
+            ("387477c2-69c7-11d2-8e39-00a0c969723b", efi::protocols::simple_text_output::PROTOCOL_GUID),
+            ("5b1b31a1-9562-11d2-8e3f-00a0c969723b", efi::protocols::loaded_image::PROTOCOL_GUID),
+            ("09576e91-6d3f-11d2-8e39-00a0c969723b", efi::protocols::device_path::PROTOCOL_GUID),
+            (TEST_GUID_STRING, create_test_r_efi_guid()),
+        ];
+
+        for (string, guid) in known_guids {
+            let uuid = Uuid::parse_str(string).unwrap();
+            assert_eq!(from_uuid(&uuid), guid, "{string}");
+            assert_eq!(to_uuid(&guid), uuid, "{string}");
+            assert_eq!(from_uuid(&to_uuid(&guid)), guid, "{string}");
+            assert_eq!(to_uuid(&from_uuid(&uuid)), uuid, "{string}");
+            assert_eq!(from_str(string), Ok(guid), "{string}");
+            assert_eq!(from_str(&string.to_uppercase()), Ok(guid), "{string}");
+            assert!(Guid::from_ref(&guid).to_string().eq_ignore_ascii_case(string), "{string}");
+        }
+    }
+
+    #[test]
+    fn from_str_should_reject_invalid_guids() {
+        assert_eq!(from_str("23c9322f-2af2-476a-bc4c"), Err(GuidError::InvalidLength { expected: 32, actual: 20 }));
+        assert!(matches!(
+            from_str("23c9322f-2af2-476a-bc4c-26bc88266c7g"),
+            Err(GuidError::InvalidHexCharacter { character: 'g', .. })
+        ));
+    }
 }
@@ -0,0 +1,96 @@
+//! A guard running code at most once per boot.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Runs a closure at most once, however many times, or from however many callers, [`BootOnce::run`] is called.
+///
+/// Unlike `std::sync::Once`, a caller never waits for another: a call made while the closure is running, such as from
+/// an interrupt or a nested event notification, returns immediately without running it.
+///
+/// ```rust
+/// use patina::boot_once::BootOnce;
+///
+/// static LOGGED: BootOnce = BootOnce::new();
+///
+/// fn report_table_full() {
+///     LOGGED.run(|| log::info!("The table is full."));
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct BootOnce {
+    started: AtomicBool,
+}
+
+impl BootOnce {
+    /// Creates a guard whose closure has not run.
+    pub const fn new() -> Self {
+        Self { started: AtomicBool::new(false) }
+    }
+
+    /// Runs `f` if no earlier call has, returning whether it ran.
+    pub fn run(&self, f: impl FnOnce()) -> bool {
+        if self.started.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        f();
+        true
+    }
+
+    /// Returns whether a closure has been run, or is running.
+    pub fn has_run(&self) -> bool {
+        self.started.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    extern crate std;
+    use core::sync::atomic::AtomicUsize;
+    use std::{thread, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn test_closure_runs_once() {
+        let once = BootOnce::new();
+        let mut count = 0;
+
+        assert!(!once.has_run());
+        assert!(once.run(|| count += 1));
+        assert!(!once.run(|| count += 1));
+        assert!(once.has_run());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_nested_call_does_not_run() {
+        let once = BootOnce::new();
+        let mut nested_ran = None;
+
+        assert!(once.run(|| nested_ran = Some(once.run(|| {}))));
+        assert_eq!(nested_ran, Some(false));
+    }
+
+    #[test]
+    fn test_closure_runs_once_across_concurrent_callers() {
+        let once = BootOnce::new();
+        let count = AtomicUsize::new(0);
+
+        let ran = thread::scope(|scope| {
+            let callers = (0..16)
+                .map(|_| scope.spawn(|| once.run(|| _ = count.fetch_add(1, Ordering::SeqCst))))
+                .collect::<Vec<_>>();
+            callers.into_iter().map(|caller| caller.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(ran.iter().filter(|&&ran| ran).count(), 1);
+    }
+}
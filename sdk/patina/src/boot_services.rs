@@ -15,6 +15,7 @@ extern crate alloc;
 pub mod allocation;
 pub mod boxed;
 pub mod c_ptr;
+pub mod configuration_table;
 pub mod event;
 pub mod protocol_handler;
 pub mod tpl;
@@ -22,7 +23,7 @@ pub mod tpl;
 #[cfg(any(test, feature = "mockall"))]
 use mockall::automock;
 
-use alloc::vec::Vec;
+use alloc::{boxed::Box, vec::Vec};
 use c_ptr::{CMutPtr, CMutRef, CPtr, PtrMetadata};
 use core::{
     any,
@@ -31,8 +32,7 @@ use core::{
     mem::{self, MaybeUninit},
     option::Option,
     ptr::{self, NonNull},
-    sync::atomic::AtomicPtr,
-    sync::atomic::Ordering,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
 };
 
 use r_efi::efi;
@@ -40,10 +40,35 @@ use r_efi::efi;
 use crate::uefi_protocol::ProtocolInterface;
 use allocation::{AllocType, MemoryMap, MemoryType};
 use boxed::BootServicesBox;
+use configuration_table::ConfigTableEntry;
 use event::{EventNotifyCallback, EventTimerType, EventType};
 use protocol_handler::{HandleSearchType, Registration};
 use tpl::{Tpl, TplGuard};
 
+/// Whether boot services have been exited, shared by every [`StandardBootServices`] instance and their clones.
+#[cfg(not(test))]
+static EXITED: AtomicBool = AtomicBool::new(false);
+
+// Each test runs on its own thread, so tests marking boot services as exited do not affect each other.
+#[cfg(test)]
+std::thread_local! {
+    static EXITED: AtomicBool = const { AtomicBool::new(false) };
+}
+
+fn set_exited(exited: bool) {
+    #[cfg(not(test))]
+    EXITED.store(exited, Ordering::Relaxed);
+    #[cfg(test)]
+    EXITED.with(|flag| flag.store(exited, Ordering::Relaxed));
+}
+
+fn exited() -> bool {
+    #[cfg(not(test))]
+    return EXITED.load(Ordering::Relaxed);
+    #[cfg(test)]
+    return EXITED.with(|flag| flag.load(Ordering::Relaxed));
+}
+
 /// This is the boot services used in the UEFI.
 /// It wraps an atomic ptr to [`efi::BootServices`]
 pub struct StandardBootServices {
@@ -74,7 +99,43 @@ impl StandardBootServices {
         !self.efi_boot_services.load(Ordering::Relaxed).is_null()
     }
 
+    /// Return true if boot services have been exited.
+    ///
+    /// The exited state is shared by every instance. Once exited, the boot services table is no longer valid and any
+    /// call through an instance panics instead of calling into it.
+    pub fn has_exited(&self) -> bool {
+        exited()
+    }
+
+    /// Mark boot services as exited for every instance.
+    ///
+    /// The DXE Core does this once its ExitBootServices succeeds. It is also done when
+    /// [`BootServices::exit_boot_services`] succeeds through an instance, or when the event created by
+    /// [`Self::register_exit_boot_services_hook`] is signaled.
+    pub fn mark_exited() {
+        set_exited(true);
+    }
+
+    /// Register an event that marks boot services as exited when ExitBootServices is signaled.
+    ///
+    /// The event is notified at [`Tpl::CALLBACK`], so that notifications at a higher TPL can still use boot services.
+    /// This is only needed when boot services are not provided by the Patina DXE Core, which marks them as exited
+    /// itself.
+    pub fn register_exit_boot_services_hook(&'static self) -> Result<efi::Event, efi::Status> {
+        self.create_event(
+            EventType::SIGNAL_EXIT_BOOT_SERVICES,
+            Tpl::CALLBACK,
+            Some(Self::exit_boot_services_notify),
+            self,
+        )
+    }
+
+    extern "efiapi" fn exit_boot_services_notify(_event: efi::Event, _boot_services: &'static StandardBootServices) {
+        Self::mark_exited();
+    }
+
     fn efi_boot_services(&self) -> &efi::BootServices {
+        assert!(!self.has_exited(), "Standard Boot Services used after ExitBootServices!");
         // SAFETY: Boot services lifetime is expected to live long enough.
         unsafe { self.efi_boot_services.load(Ordering::Relaxed).as_ref() }
             .expect("Standard Boot Services is not initialized!")
@@ -98,6 +159,9 @@ impl Debug for StandardBootServices {
         if !self.is_init() {
             return f.debug_struct("StandardBootServices").field("efi_boot_services", &"Not Initialized").finish();
         }
+        if self.has_exited() {
+            return f.debug_struct("StandardBootServices").field("efi_boot_services", &"Exited").finish();
+        }
 
         f.debug_struct("StandardBootServices")
             .field("create_event", &(self.efi_boot_services().create_event))
@@ -905,7 +969,31 @@ pub trait BootServices {
         unsafe { self.install_configuration_table_unchecked(guid, table.into_mut_ptr() as *mut c_void) }
     }
 
-    /// Use [`BootServices::install_configuration_table`] when possible.
+    /// Installs `table` as the configuration table for `entry`.
+    ///
+    /// The table is leaked so that it remains valid for the lifetime of the system table. If the installation fails,
+    /// the table is dropped.
+    ///
+    /// [UEFI Spec Documentation: 7.5.6. EFI_BOOT_SERVICES.InstallConfigurationTable()](https://uefi.org/specs/UEFI/2.10/07_Services_Boot_Services.html#efi-boot-services-installconfigurationtable)
+    fn install_configuration_table_entry<T: 'static>(
+        &self,
+        entry: &ConfigTableEntry<T>,
+        table: Box<T>,
+    ) -> Result<(), efi::Status> {
+        let table = Box::into_raw(table);
+        // SAFETY: The entry guarantees `T` is the type associated with its guid, and the table is leaked so it
+        //         outlives boot services.
+        let status = unsafe { self.install_configuration_table_unchecked(entry.guid(), table as *mut c_void) };
+        if status.is_err() {
+            // SAFETY: The table was not installed, so this is the only reference to it.
+            drop(unsafe { Box::from_raw(table) });
+        }
+        status
+    }
+
+    /// Use [`BootServices::install_configuration_table_entry`] or [`BootServices::install_configuration_table`]
+    /// when possible. This is intended for tables that are only known by address, such as tables in memory that was
+    /// not allocated by the caller.
     ///
     /// # Safety
     ///
@@ -1498,7 +1586,10 @@ impl BootServices for StandardBootServices {
     fn exit_boot_services(&self, image_handle: efi::Handle, map_key: usize) -> Result<(), efi::Status> {
         match efi_boot_services_fn!(self.efi_boot_services(), exit_boot_services)(image_handle, map_key) {
             s if s.is_error() => Err(s),
-            _ => Ok(()),
+            _ => {
+                Self::mark_exited();
+                Ok(())
+            }
         }
     }
 
@@ -1583,6 +1674,7 @@ mod tests {
                 )*
                 bs.assume_init()
             };
+            set_exited(false);
             StandardBootServices::new(&efi_boot_services)
         }};
     }
@@ -1642,6 +1734,48 @@ mod tests {
         assert!(output.contains("Not Initialized"));
     }
 
+    #[test]
+    #[should_panic(expected = "Standard Boot Services used after ExitBootServices!")]
+    fn test_that_accessing_exited_boot_services_should_panic() {
+        let boot_services = boot_services!(stall = efi_stall);
+
+        extern "efiapi" fn efi_stall(_microseconds: usize) -> efi::Status {
+            efi::Status::SUCCESS
+        }
+
+        let clone = boot_services.clone();
+        boot_services.stall(1).unwrap();
+        StandardBootServices::mark_exited();
+        assert!(format!("{boot_services:?}").contains("Exited"));
+        assert!(clone.has_exited());
+        assert!(StandardBootServices::new_uninit().has_exited());
+        _ = boot_services.stall(1);
+    }
+
+    #[test]
+    fn test_exit_boot_services_hook_marks_exited() {
+        let boot_services = Box::leak(Box::new(boot_services!(create_event = efi_create_event)));
+
+        extern "efiapi" fn efi_create_event(
+            event_type: u32,
+            notify_tpl: efi::Tpl,
+            notify_function: Option<efi::EventNotify>,
+            notify_context: *mut c_void,
+            event: *mut efi::Event,
+        ) -> efi::Status {
+            assert_eq!(efi::EVT_SIGNAL_EXIT_BOOT_SERVICES, event_type);
+            assert_eq!(efi::TPL_CALLBACK, notify_tpl);
+            // Signal the event right away to simulate ExitBootServices.
+            notify_function.unwrap()(ptr::null_mut(), notify_context);
+            unsafe { event.write(1_usize as efi::Event) };
+            efi::Status::SUCCESS
+        }
+
+        assert!(!boot_services.has_exited());
+        boot_services.register_exit_boot_services_hook().unwrap();
+        assert!(boot_services.has_exited());
+    }
+
     #[test]
     #[should_panic = "Boot services function create_event is not initialized."]
     fn test_create_event_not_init() {
@@ -3010,6 +3144,7 @@ mod tests {
         }
 
         boot_services.exit_boot_services(1_usize as _, 2).unwrap();
+        assert!(boot_services.has_exited());
     }
 
     #[test]
@@ -3193,6 +3328,47 @@ mod tests {
         unsafe { boot_services.install_configuration_table(&GUID, &mut TABLE) }.unwrap();
     }
 
+    #[test]
+    fn test_install_configuration_table_entry() {
+        let boot_services = boot_services!(install_configuration_table = efi_install_configuration_table);
+
+        static INSTALLED: AtomicUsize = AtomicUsize::new(0);
+        const ENTRY: ConfigTableEntry<u64> = unsafe {
+            configuration_table::ConfigTableEntry::new(efi::Guid::from_bytes(&[
+                1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+            ]))
+        };
+
+        extern "efiapi" fn efi_install_configuration_table(guid: *mut efi::Guid, table: *mut c_void) -> efi::Status {
+            assert_eq!(ENTRY.guid(), unsafe { &*guid });
+            INSTALLED.store(table as usize, Ordering::SeqCst);
+            efi::Status::SUCCESS
+        }
+
+        boot_services.install_configuration_table_entry(&ENTRY, Box::new(42)).unwrap();
+
+        let tables = [efi::ConfigurationTable {
+            vendor_guid: *ENTRY.guid(),
+            vendor_table: INSTALLED.load(Ordering::SeqCst) as *mut c_void,
+        }];
+        assert_eq!(unsafe { ENTRY.find(&tables) }, Some(&42));
+    }
+
+    #[test]
+    fn test_install_configuration_table_entry_error() {
+        let boot_services = boot_services!(install_configuration_table = efi_install_configuration_table);
+
+        extern "efiapi" fn efi_install_configuration_table(_: *mut efi::Guid, _: *mut c_void) -> efi::Status {
+            efi::Status::OUT_OF_RESOURCES
+        }
+
+        let entry = unsafe { ConfigTableEntry::<u64>::new(efi::Guid::from_bytes(&[0; 16])) };
+        assert_eq!(
+            boot_services.install_configuration_table_entry(&entry, Box::new(42)),
+            Err(efi::Status::OUT_OF_RESOURCES)
+        );
+    }
+
     #[test]
     #[should_panic = "Boot services function calculate_crc32 is not initialized."]
     fn test_calculate_crc32_not_init() {
@@ -6,7 +6,7 @@
 //!
 //! SPDX-License-Identifier: Apache-2.0
 //!
-use core::ops::{BitOr, BitOrAssign};
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
 
 use r_efi::efi;
 
@@ -85,76 +85,187 @@ pub struct MemoryMap<'a, B: BootServices + ?Sized> {
 
 /// Memory attributes as specified in the UEFI specification.
 ///
-/// Used to describe the attributes of a memory region.
+/// Used to describe the attributes of a memory region, in memory descriptors, the GCD, and the memory attributes table.
+/// Bits without a named attribute, such as those of the resource descriptor HOB, are kept as they are, so a `u64` mask
+/// converts to and from `MemoryAttributes` without loss. Page tables describe their attributes with the
+/// `patina_paging` crate's own type instead, which uses the same bit values.
 ///
 /// <https://uefi.org/specs/UEFI/2.11/07_Services_Boot_Services.html#efi-boot-services-getmemorymap>
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MemoryAttribute(u64);
+///
+/// ## Example
+///
+/// ```
+/// use patina::boot_services::allocation::MemoryAttributes;
+///
+/// let attributes = MemoryAttributes::from(r_efi::efi::MEMORY_WB | r_efi::efi::MEMORY_XP);
+/// assert_eq!(attributes.caching(), MemoryAttributes::WB);
+/// assert_eq!((attributes & !MemoryAttributes::ACCESS_MASK) | MemoryAttributes::RO, MemoryAttributes::WB | MemoryAttributes::RO);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[repr(transparent)]
+pub struct MemoryAttributes(u64);
+
+/// The previous name of [`MemoryAttributes`].
+pub type MemoryAttribute = MemoryAttributes;
 
-impl MemoryAttribute {
+impl MemoryAttributes {
     /// Memory cacheability attribute: The memory region is not cacheable.
-    pub const UC: MemoryAttribute = MemoryAttribute(efi::MEMORY_UC);
+    pub const UC: MemoryAttributes = MemoryAttributes(efi::MEMORY_UC);
     /// Memory cacheability attribute: The memory region is write combined.
-    pub const WC: MemoryAttribute = MemoryAttribute(efi::MEMORY_WC);
+    pub const WC: MemoryAttributes = MemoryAttributes(efi::MEMORY_WC);
     /// Memory cacheability attribute: The memory region is cacheable with a “write through” policy. Writes that hit in
     /// the cache will also be written to main memory.
-    pub const WT: MemoryAttribute = MemoryAttribute(efi::MEMORY_WT);
+    pub const WT: MemoryAttributes = MemoryAttributes(efi::MEMORY_WT);
     /// Memory cacheability attribute: The memory region is cacheable with a “write back” policy. Reads and writes that
     /// hit in the cache do not propagate to main memory. Dirty data is written back to main memory when a new cache
     /// line is allocated.
-    pub const WB: MemoryAttribute = MemoryAttribute(efi::MEMORY_WB);
+    pub const WB: MemoryAttributes = MemoryAttributes(efi::MEMORY_WB);
     /// Memory cacheability attribute: The memory region is cacheable, exported, and supports the “fetch and add”
     /// semaphore mechanism.
-    pub const UCE: MemoryAttribute = MemoryAttribute(efi::MEMORY_UCE);
+    pub const UCE: MemoryAttributes = MemoryAttributes(efi::MEMORY_UCE);
     /// Physical memory protection attribute: The memory region is write-protected by system hardware. This is
     /// typically used as a cacheability attribute today. The memory region is cacheable with a “write protected”
     /// policy. Reads come from cache lines when possible, and read misses cause cache fills. Writes are propagated to
     /// the system bus and cause corresponding cache lines on all processors on the bus to be invalidated.
-    pub const WP: MemoryAttribute = MemoryAttribute(efi::MEMORY_WP);
+    pub const WP: MemoryAttributes = MemoryAttributes(efi::MEMORY_WP);
     /// Physical memory protection attribute: The memory region is read-protected by system hardware.
-    pub const RP: MemoryAttribute = MemoryAttribute(efi::MEMORY_RP);
+    pub const RP: MemoryAttributes = MemoryAttributes(efi::MEMORY_RP);
     /// Physical memory protection attribute: The memory region supports is protected by system hardware from executing code.
-    pub const XP: MemoryAttribute = MemoryAttribute(efi::MEMORY_XP);
+    pub const XP: MemoryAttributes = MemoryAttributes(efi::MEMORY_XP);
     /// Runtime memory attribute: The memory region refers to persistent memory
-    pub const NV: MemoryAttribute = MemoryAttribute(efi::MEMORY_NV);
+    pub const NV: MemoryAttributes = MemoryAttributes(efi::MEMORY_NV);
     /// The memory region provides higher reliability relative to other memory in the system. If all memory has the
     /// same reliability, then this bit is not used.
-    pub const MORE_RELIABLE: MemoryAttribute = MemoryAttribute(efi::MEMORY_MORE_RELIABLE);
+    pub const MORE_RELIABLE: MemoryAttributes = MemoryAttributes(efi::MEMORY_MORE_RELIABLE);
     /// Physical memory protection attribute: The memory region supports making this memory range read-only by system
     /// hardware.
-    pub const RO: MemoryAttribute = MemoryAttribute(efi::MEMORY_RO);
+    pub const RO: MemoryAttributes = MemoryAttributes(efi::MEMORY_RO);
     /// Specific-purpose memory (SPM). The memory is earmarked for specific purposes such as for specific device
     /// drivers or applications. The SPM attribute serves as a hint to the OS to avoid allocating this memory for core
     /// OS data or code that can not be relocated. Prolonged use of this memory for purposes other than the intended
     /// purpose may result in suboptimal platform performance.
-    pub const SP: MemoryAttribute = MemoryAttribute(efi::MEMORY_SP);
+    pub const SP: MemoryAttributes = MemoryAttributes(efi::MEMORY_SP);
     /// The memory region is protected with the CPU’s memory cryptographic capabilities. If this flag is clear, the
     /// memory region is not capable of being protected with the CPU’s memory cryptographic capabilities or the CPU
     /// does not support CPU memory cryptographic capabilities.
-    pub const CPU_CRYPTO: MemoryAttribute = MemoryAttribute(efi::MEMORY_CPU_CRYPTO);
+    pub const CPU_CRYPTO: MemoryAttributes = MemoryAttributes(efi::MEMORY_CPU_CRYPTO);
     /// Runtime memory attribute: The memory region needs to be given a virtual mapping by the operating system when
     /// SetVirtualAddressMap() is called.
-    pub const RUNTIME: MemoryAttribute = MemoryAttribute(efi::MEMORY_RUNTIME);
+    pub const RUNTIME: MemoryAttributes = MemoryAttributes(efi::MEMORY_RUNTIME);
     /// The memory region is described with additional ISA-specific memory attributes as specified in
     /// EFI_MEMORY_ISA_MASK.
-    pub const ISA_VALID: MemoryAttribute = MemoryAttribute(efi::MEMORY_ISA_VALID);
+    pub const ISA_VALID: MemoryAttributes = MemoryAttributes(efi::MEMORY_ISA_VALID);
     /// Bits reserved for describing optional ISA-specific cacheability attributes that are not covered by
     /// the standard UEFI Memory Attributes cacheability bits (EFI_MEMORY_UC, EFI_MEMORY_WC, EFI_MEMORY_WT,
     /// EFI_MEMORY_WB and EFI_MEMORY_UCE). See Calling Conventions for further ISA-specific enumeration of these bits.
-    pub const ISA_MASK: MemoryAttribute = MemoryAttribute(efi::MEMORY_ISA_MASK);
+    pub const ISA_MASK: MemoryAttributes = MemoryAttributes(efi::MEMORY_ISA_MASK);
+    /// The cacheability attributes.
+    pub const CACHE_MASK: MemoryAttributes = MemoryAttributes(efi::CACHE_ATTRIBUTE_MASK);
+    /// The physical memory protection attributes.
+    pub const ACCESS_MASK: MemoryAttributes = MemoryAttributes(efi::MEMORY_ACCESS_MASK);
+    /// The attributes reported in the memory attributes table and by the memory attribute protocol, in addition to
+    /// the physical memory protection attributes.
+    pub const ATTRIBUTE_MASK: MemoryAttributes = MemoryAttributes(efi::MEMORY_ATTRIBUTE_MASK);
+
+    /// Returns a set of no attributes.
+    pub const fn empty() -> Self {
+        MemoryAttributes(0)
+    }
+
+    /// Returns the attributes of a `u64` attribute mask.
+    pub const fn from_bits(bits: u64) -> Self {
+        MemoryAttributes(bits)
+    }
+
+    /// Returns the `u64` attribute mask.
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns whether no attributes are set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether all of `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether any of `other` is set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns the attributes set in both `self` and `other`.
+    pub const fn intersection(self, other: Self) -> Self {
+        MemoryAttributes(self.0 & other.0)
+    }
+
+    /// Returns the attributes set in either `self` or `other`.
+    pub const fn union(self, other: Self) -> Self {
+        MemoryAttributes(self.0 | other.0)
+    }
+
+    /// Returns the attributes set in `self` but not in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        MemoryAttributes(self.0 & !other.0)
+    }
+
+    /// Sets the attributes of `other`.
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0
+    }
+
+    /// Clears the attributes of `other`.
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0
+    }
+
+    /// Returns only the cacheability attributes.
+    pub const fn caching(self) -> Self {
+        self.intersection(Self::CACHE_MASK)
+    }
+
+    /// Returns only the physical memory protection attributes.
+    pub const fn access(self) -> Self {
+        self.intersection(Self::ACCESS_MASK)
+    }
 }
 
-impl BitOr for MemoryAttribute {
-    type Output = MemoryAttribute;
+impl BitOr for MemoryAttributes {
+    type Output = MemoryAttributes;
 
     fn bitor(self, rhs: Self) -> Self::Output {
-        MemoryAttribute(self.0 | rhs.0)
+        self.union(rhs)
     }
 }
 
-impl BitOrAssign for MemoryAttribute {
+impl BitOrAssign for MemoryAttributes {
     fn bitor_assign(&mut self, rhs: Self) {
-        self.0 |= rhs.0
+        self.insert(rhs)
+    }
+}
+
+impl BitAnd for MemoryAttributes {
+    type Output = MemoryAttributes;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAndAssign for MemoryAttributes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0
+    }
+}
+
+impl Not for MemoryAttributes {
+    type Output = MemoryAttributes;
+
+    fn not(self) -> Self::Output {
+        MemoryAttributes(!self.0)
     }
 }
 
@@ -168,8 +279,87 @@ impl From<AllocType> for efi::AllocateType {
     }
 }
 
-impl From<MemoryAttribute> for u64 {
-    fn from(val: MemoryAttribute) -> Self {
+impl From<MemoryAttributes> for u64 {
+    fn from(val: MemoryAttributes) -> Self {
         val.0
     }
 }
+
+impl From<u64> for MemoryAttributes {
+    fn from(val: u64) -> Self {
+        MemoryAttributes(val)
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_attributes_round_trip_bits() {
+        for bit in 0..u64::BITS {
+            let bits = 1 << bit;
+            assert_eq!(u64::from(MemoryAttributes::from(bits)), bits);
+            assert_eq!(MemoryAttributes::from_bits(bits).bits(), bits);
+        }
+
+        // Bits without a named attribute, such as the resource descriptor HOB bits, are kept.
+        let bits = efi::MEMORY_WB | efi::MEMORY_RUNTIME | crate::pi::hob::EFI_MEMORY_TESTED;
+        assert_eq!(u64::from(MemoryAttributes::from(bits)), bits);
+
+        for (attributes, bits) in [
+            (MemoryAttributes::UC, efi::MEMORY_UC),
+            (MemoryAttributes::WC, efi::MEMORY_WC),
+            (MemoryAttributes::WT, efi::MEMORY_WT),
+            (MemoryAttributes::WB, efi::MEMORY_WB),
+            (MemoryAttributes::UCE, efi::MEMORY_UCE),
+            (MemoryAttributes::WP, efi::MEMORY_WP),
+            (MemoryAttributes::RP, efi::MEMORY_RP),
+            (MemoryAttributes::XP, efi::MEMORY_XP),
+            (MemoryAttributes::RO, efi::MEMORY_RO),
+            (MemoryAttributes::RUNTIME, efi::MEMORY_RUNTIME),
+        ] {
+            assert_eq!(attributes.bits(), bits);
+            assert_eq!(attributes.bits().count_ones(), 1);
+        }
+    }
+
+    #[test]
+    fn test_memory_attributes_combine_and_mask() {
+        let attributes = MemoryAttributes::WB | MemoryAttributes::XP | MemoryAttributes::RUNTIME;
+        assert_eq!(attributes.caching(), MemoryAttributes::WB);
+        assert_eq!(attributes.access(), MemoryAttributes::XP);
+        assert!(attributes.contains(MemoryAttributes::WB | MemoryAttributes::XP));
+        assert!(!attributes.contains(MemoryAttributes::RO | MemoryAttributes::XP));
+        assert!(attributes.intersects(MemoryAttributes::RO | MemoryAttributes::XP));
+        assert!(!attributes.intersects(MemoryAttributes::ACCESS_MASK.difference(MemoryAttributes::XP)));
+
+        // Changing the access attributes keeps the cacheability attributes.
+        let mut code = attributes & !MemoryAttributes::ACCESS_MASK;
+        code |= MemoryAttributes::RO;
+        assert_eq!(code, MemoryAttributes::WB | MemoryAttributes::RO | MemoryAttributes::RUNTIME);
+
+        let mut data = code;
+        data.remove(MemoryAttributes::RO);
+        data.insert(MemoryAttributes::XP);
+        assert_eq!(data, attributes);
+        data &= MemoryAttributes::CACHE_MASK;
+        assert_eq!(data, MemoryAttributes::WB);
+
+        assert!(MemoryAttributes::empty().is_empty());
+        assert_eq!(MemoryAttributes::default(), MemoryAttributes::empty());
+        assert!((MemoryAttributes::RP & MemoryAttributes::CACHE_MASK).is_empty());
+        assert_eq!(
+            MemoryAttributes::CACHE_MASK,
+            MemoryAttributes::UC
+                | MemoryAttributes::WC
+                | MemoryAttributes::WT
+                | MemoryAttributes::WB
+                | MemoryAttributes::UCE
+                | MemoryAttributes::WP
+        );
+        assert_eq!(MemoryAttributes::ACCESS_MASK, MemoryAttributes::RP | MemoryAttributes::XP | MemoryAttributes::RO);
+        assert!(MemoryAttributes::ATTRIBUTE_MASK.contains(MemoryAttributes::ACCESS_MASK));
+    }
+}
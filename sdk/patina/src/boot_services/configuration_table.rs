@@ -0,0 +1,98 @@
+//! Typed configuration table entries.
+//!
+//! A [`ConfigTableEntry`] binds a configuration table GUID to the type of the table it identifies, so that installing
+//! a table with [`BootServices::install_configuration_table_entry`](super::BootServices::install_configuration_table_entry)
+//! and reading it back with [`ConfigTableEntry::find`] always agree on the type.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use core::{fmt, marker::PhantomData};
+
+use r_efi::efi;
+
+/// A configuration table GUID associated with the type of its table.
+pub struct ConfigTableEntry<T> {
+    guid: efi::Guid,
+    _table: PhantomData<fn() -> T>,
+}
+
+impl<T> ConfigTableEntry<T> {
+    /// Creates a new configuration table entry for `guid`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the type of the table identified by `guid`, as defined by the specification or producer of the
+    /// table.
+    pub const unsafe fn new(guid: efi::Guid) -> Self {
+        Self { guid, _table: PhantomData }
+    }
+
+    /// Returns the GUID of the entry.
+    pub const fn guid(&self) -> &efi::Guid {
+        &self.guid
+    }
+
+    /// Finds the table for this entry in a list of configuration tables.
+    ///
+    /// Returns `None` if no table in `tables` has the entry's GUID, or if the table pointer is null.
+    ///
+    /// # Safety
+    ///
+    /// `tables` must be the configuration tables of a valid system table, such that every table pointer is valid for
+    /// the type associated with its GUID for the lifetime `'a`.
+    pub unsafe fn find<'a>(&self, tables: &'a [efi::ConfigurationTable]) -> Option<&'a T> {
+        let table = tables.iter().find(|table| table.vendor_guid == self.guid)?;
+        // SAFETY: The caller guarantees the table pointer is valid, and the entry guarantees it is a `T`.
+        unsafe { (table.vendor_table as *const T).as_ref() }
+    }
+}
+
+impl<T> fmt::Debug for ConfigTableEntry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigTableEntry")
+            .field("guid", &self.guid)
+            .field("table", &core::any::type_name::<T>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use core::{ffi::c_void, ptr};
+
+    use super::*;
+
+    const ENTRY: ConfigTableEntry<u64> = unsafe {
+        ConfigTableEntry::new(efi::Guid::from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]))
+    };
+
+    #[test]
+    fn find_should_return_the_matching_table() {
+        let other = 1_u32;
+        let value = 0x1234_5678_u64;
+        let tables = [
+            efi::ConfigurationTable {
+                vendor_guid: efi::Guid::from_bytes(&[0; 16]),
+                vendor_table: ptr::addr_of!(other) as *mut c_void,
+            },
+            efi::ConfigurationTable { vendor_guid: *ENTRY.guid(), vendor_table: ptr::addr_of!(value) as *mut c_void },
+        ];
+
+        assert_eq!(unsafe { ENTRY.find(&tables) }, Some(&0x1234_5678));
+    }
+
+    #[test]
+    fn find_should_return_none_for_missing_or_null_tables() {
+        let tables =
+            [efi::ConfigurationTable { vendor_guid: efi::Guid::from_bytes(&[0; 16]), vendor_table: ptr::null_mut() }];
+        assert_eq!(unsafe { ENTRY.find(&tables) }, None);
+
+        let tables = [efi::ConfigurationTable { vendor_guid: *ENTRY.guid(), vendor_table: ptr::null_mut() }];
+        assert_eq!(unsafe { ENTRY.find(&tables) }, None);
+    }
+}
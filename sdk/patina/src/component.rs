@@ -160,9 +160,12 @@ pub trait Component {
     /// Runs the component with exclusive access to the storage.
     fn run(&mut self, storage: &mut storage::Storage) -> Result<bool> {
         storage.apply_deferred();
-        let storage_cell = storage::UnsafeStorageCell::from(storage);
+        storage.set_running_component(Some(self.metadata().name()));
+        let storage_cell = storage::UnsafeStorageCell::from(&mut *storage);
         // SAFETY: This is safe because this component has exclusive access to the storage.
-        unsafe { self.run_unsafe(storage_cell) }
+        let result = unsafe { self.run_unsafe(storage_cell) };
+        storage.set_running_component(None);
+        result
     }
 
     /// One-time initialization of the component. This is where parameter access requirements should be registered in
@@ -326,4 +329,42 @@ mod tests {
         assert!(component5.run(&mut storage).is_ok_and(|res| res));
         assert!(component6.run(&mut storage).is_ok_and(|res| res));
     }
+
+    struct ComponentAccumulator {
+        metadata: metadata::MetaData,
+    }
+
+    impl Component for ComponentAccumulator {
+        unsafe fn run_unsafe(&mut self, storage: storage::UnsafeStorageCell) -> Result<bool> {
+            // SAFETY: This component is only run via `Component::run`, which provides exclusive access.
+            let storage = unsafe { storage.storage_mut() };
+            let attempts = storage.component_state::<u32>().expect("A component should be running");
+            *attempts += 1;
+            Ok(*attempts == 2)
+        }
+
+        fn initialize(&mut self, _storage: &mut storage::Storage) {}
+
+        fn metadata(&self) -> &metadata::MetaData {
+            &self.metadata
+        }
+    }
+
+    #[test]
+    fn test_component_state_persists_across_runs() {
+        let mut storage = storage::Storage::new();
+        let mut component = ComponentAccumulator { metadata: metadata::MetaData::new::<ComponentAccumulator>() };
+        component.initialize(&mut storage);
+
+        assert!(component.run(&mut storage).is_ok_and(|res| !res));
+        assert!(component.run(&mut storage).is_ok_and(|res| res));
+
+        // The scratchpad is only reachable while a component is running.
+        assert!(storage.component_state::<u32>().is_none());
+
+        storage.set_running_component(Some(component.metadata().name()));
+        assert_eq!(storage.component_state::<u32>().copied(), Some(2));
+        storage.set_running_component(Some("OtherComponent"));
+        assert_eq!(storage.component_state::<u32>().copied(), Some(0));
+    }
 }
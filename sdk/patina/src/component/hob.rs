@@ -62,7 +62,7 @@ extern crate alloc;
 use alloc::{boxed::Box, vec::Vec};
 
 use crate::OwnedGuid;
-use core::{any::Any, ops::Deref};
+use core::{any::Any, fmt, ops::Deref};
 
 use super::{
     metadata::MetaData,
@@ -112,6 +112,12 @@ pub trait FromHob: Sized + 'static {
     /// The guid value associated with the guided HOB to parse.
     const HOB_GUID: OwnedGuid;
 
+    /// The size of the HOB data the type is read from, if it has a fixed size.
+    ///
+    /// A guided HOB with less data than this is skipped rather than parsed, as it was likely produced with a different
+    /// layout of the type. The derive macro sets this to the size of the type.
+    const DATA_SIZE: Option<usize> = None;
+
     /// Registers the parsed hob with the provided [Storage] instance.
     fn register(bytes: &[u8], storage: &mut Storage) {
         storage.add_hob(Self::parse(bytes));
@@ -123,6 +129,59 @@ pub trait FromHob: Sized + 'static {
 
 pub use patina_macro::FromHob;
 
+/// A parser for a guided HOB, registered with [Storage] for a [FromHob] type.
+#[derive(Debug, Clone, Copy)]
+pub struct HobParser {
+    type_name: &'static str,
+    data_size: Option<usize>,
+    register: fn(&[u8], &mut Storage),
+}
+
+impl HobParser {
+    /// Creates the parser for the [FromHob] type `T`.
+    pub(crate) fn new<T: FromHob>() -> Self {
+        Self { type_name: core::any::type_name::<T>(), data_size: T::DATA_SIZE, register: T::register }
+    }
+
+    /// Returns the name of the type the HOB is parsed to.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Parses the HOB data and registers the parsed value with the provided [Storage] instance.
+    ///
+    /// Returns an error without parsing if the data is shorter than [FromHob::DATA_SIZE].
+    pub fn register(&self, bytes: &[u8], storage: &mut Storage) -> Result<(), HobSizeMismatch> {
+        if let Some(expected) = self.data_size.filter(|&size| bytes.len() < size) {
+            return Err(HobSizeMismatch { type_name: self.type_name, expected, actual: bytes.len() });
+        }
+        (self.register)(bytes, storage);
+        Ok(())
+    }
+}
+
+/// The data of a guided HOB is shorter than the [FromHob] type it is parsed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HobSizeMismatch {
+    /// The name of the type the HOB is parsed to.
+    pub type_name: &'static str,
+    /// The size of the HOB data expected by the type.
+    pub expected: usize,
+    /// The size of the HOB data.
+    pub actual: usize,
+}
+
+impl fmt::Display for HobSizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HOB data is {} bytes, but {} expects at least {} bytes. The HOB may be from a different version of the \
+             type.",
+            self.actual, self.type_name, self.expected
+        )
+    }
+}
+
 /// An immutable Hob value registered with [Storage] via the [FromHob] trait.
 ///
 /// The underlying datum of this type is a slice. The first element of the slice can be directly accessed by
@@ -343,6 +402,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hob_parser_should_skip_short_data() {
+        #[derive(Debug, PartialEq)]
+        struct SizedStruct(u64);
+
+        impl FromHob for SizedStruct {
+            const HOB_GUID: OwnedGuid = Guid::ZERO;
+            const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
+
+            fn parse(bytes: &[u8]) -> Self {
+                SizedStruct(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+            }
+        }
+
+        let mut storage = Storage::new();
+        storage.register_hob::<SizedStruct>();
+        let parser = HobParser::new::<SizedStruct>();
+
+        let err = parser.register(&[0; 4], &mut storage).unwrap_err();
+        assert_eq!(err, HobSizeMismatch { type_name: parser.type_name(), expected: 8, actual: 4 });
+        assert!(alloc::format!("{err}").starts_with("HOB data is 4 bytes, but "));
+        assert!(alloc::format!("{err}").contains("SizedStruct expects at least 8 bytes."));
+        assert!(storage.get_hob::<SizedStruct>().is_none());
+
+        assert_eq!(parser.register(&[1, 0, 0, 0, 0, 0, 0, 0, 0xff], &mut storage), Ok(()));
+        assert_eq!(*storage.get_hob::<SizedStruct>().unwrap(), SizedStruct(1));
+
+        // Types without a fixed size parse any data.
+        assert_eq!(HobParser::new::<MyStruct>().register(&[], &mut storage), Ok(()));
+    }
+
     #[test]
     fn test_iter_next_function() {
         let hobs = Hob::mock(vec![MyStruct { unused: 5 }, MyStruct { unused: 10 }]);
@@ -120,6 +120,15 @@ impl Access {
         self.reads_all_configs | (self.config_read_and_writes.count_ones(..) > 0)
     }
 
+    /// Returns whether a config resource the component accesses by id is not in the lock state the access needs, which
+    /// is locked for a read and unlocked for a write. `is_locked` returns the lock state of a config resource, or
+    /// `None` if it cannot be determined.
+    pub(crate) fn has_unavailable_config(&self, is_locked: impl Fn(usize) -> Option<bool>) -> bool {
+        self.config_read_and_writes
+            .ones()
+            .any(|id| is_locked(id).is_some_and(|locked| locked == self.config_writes.contains(id)))
+    }
+
     /// Returns whether the component has exclusive access to all config resources
     pub fn has_writes_all_configs(&self) -> bool {
         self.writes_all_configs
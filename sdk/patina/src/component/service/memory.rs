@@ -36,9 +36,9 @@ use core::{
     ptr::{NonNull, with_exposed_provenance_mut},
 };
 
-use r_efi::efi;
-
-use crate::{base::UEFI_PAGE_SIZE, efi_types::EfiMemoryType, error::EfiError};
+use crate::{
+    base::UEFI_PAGE_SIZE, boot_services::allocation::MemoryAttributes, efi_types::EfiMemoryType, error::EfiError,
+};
 
 #[cfg(any(test, feature = "alloc"))]
 use core::alloc::Allocator;
@@ -282,6 +282,32 @@ pub trait MemoryManager {
     /// - `Err(MemoryError)` if the request failed for other reasons.
     ///
     fn get_page_attributes(&self, address: usize, page_count: usize) -> Result<(AccessType, CachingType), MemoryError>;
+
+    /// Allocates an identity-mapped buffer suitable for DMA.
+    ///
+    /// Allocates zeroed pages large enough to hold `size` bytes at the requested
+    /// alignment and applies the requested [`DmaCaching`] attributes to them.
+    /// The buffer is freed, and its attributes restored, when the returned
+    /// [`DmaBuffer`] is dropped.
+    ///
+    /// See [`DmaBuffer::new`] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use patina::component::service::memory::*;
+    ///
+    /// fn component(memory_manager: &dyn MemoryManager) -> Result<(), MemoryError> {
+    ///     let ring = memory_manager.alloc_dma_buffer(0x800, 0x1000, DmaCaching::Uncached)?;
+    ///     let device_address = ring.device_address();
+    ///     // Program `device_address` into the controller...
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    fn alloc_dma_buffer(&self, size: usize, alignment: usize, caching: DmaCaching) -> Result<DmaBuffer, MemoryError> {
+        DmaBuffer::new(self, size, alignment, caching)
+    }
 }
 
 /// The `AllocationOptions` structure allows for the caller to  specify
@@ -683,6 +709,200 @@ unsafe impl Allocator for PageFree {
     }
 }
 
+/// The cacheability options available for a [`DmaBuffer`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DmaCaching {
+    /// Uncached. Suitable for descriptor rings and other structures shared with
+    /// a bus master that does not snoop the CPU caches.
+    Uncached,
+    /// Write-combining. Suitable for streaming buffers that the CPU fills and
+    /// the device consumes in bulk.
+    WriteCombining,
+}
+
+impl From<DmaCaching> for CachingType {
+    fn from(value: DmaCaching) -> Self {
+        match value {
+            DmaCaching::Uncached => CachingType::Uncached,
+            DmaCaching::WriteCombining => CachingType::WriteCombining,
+        }
+    }
+}
+
+/// The `DmaBuffer` struct represents a page allocation that has been prepared
+/// for sharing with a bus master.
+///
+/// Memory is identity mapped, so the address the CPU uses to access the buffer
+/// is also the address that should be programmed into the device. The pages are
+/// mapped [`AccessType::ReadWrite`] with the [`DmaCaching`] requested at creation.
+///
+/// When dropped, the pages are returned to [`CachingType::WriteBack`] and freed.
+/// The caller is responsible for ensuring the device has stopped accessing the
+/// buffer before it is dropped.
+///
+#[must_use]
+pub struct DmaBuffer {
+    blob: NonNull<u8>,
+    size: usize,
+    page_count: usize,
+    caching: DmaCaching,
+    memory_manager: &'static dyn MemoryManager,
+}
+
+impl DmaBuffer {
+    /// Allocates a new DMA buffer of at least `size` bytes.
+    ///
+    /// The allocation is made from [`EfiMemoryType::BootServicesData`], is zeroed,
+    /// and is aligned to the larger of `alignment` and [`UEFI_PAGE_SIZE`].
+    ///
+    /// # Errors
+    ///
+    /// - `Err(MemoryError::InvalidPageCount)` if `size` is zero.
+    /// - `Err(MemoryError::InvalidAlignment)` if `alignment` is not a power of two.
+    /// - `Err(MemoryError::UnsupportedAttributes)` if the requested attributes
+    ///   were not applied to the pages.
+    /// - `Err(MemoryError)` if the allocation or attribute update failed.
+    ///
+    pub fn new<M: MemoryManager + ?Sized>(
+        memory_manager: &M,
+        size: usize,
+        alignment: usize,
+        caching: DmaCaching,
+    ) -> Result<Self, MemoryError> {
+        if size == 0 {
+            return Err(MemoryError::InvalidPageCount);
+        }
+
+        if !alignment.is_power_of_two() {
+            return Err(MemoryError::InvalidAlignment);
+        }
+
+        let options = AllocationOptions::new()
+            .with_alignment(alignment.max(UEFI_PAGE_SIZE))
+            .with_memory_type(EfiMemoryType::BootServicesData);
+        let allocation = ManuallyDrop::new(memory_manager.allocate_zero_pages(size.div_ceil(UEFI_PAGE_SIZE), options)?);
+
+        let buffer = Self {
+            blob: allocation.blob,
+            size,
+            page_count: allocation.page_count,
+            caching,
+            memory_manager: allocation.memory_manager,
+        };
+
+        // SAFETY: The pages were just allocated and nothing else references them.
+        if let Err(err) = unsafe {
+            buffer.memory_manager.set_page_attributes(
+                buffer.address(),
+                buffer.page_count,
+                AccessType::ReadWrite,
+                Some(caching.into()),
+            )
+        } {
+            let mut buffer = ManuallyDrop::new(buffer);
+            buffer.free_pages();
+            return Err(err);
+        }
+
+        // Confirm the paging layer actually applied the requested attributes;
+        // dropping the buffer restores the default caching and frees the pages.
+        match buffer.memory_manager.get_page_attributes(buffer.address(), buffer.page_count)? {
+            (AccessType::ReadWrite, applied) if applied == caching.into() => Ok(buffer),
+            _ => Err(MemoryError::UnsupportedAttributes),
+        }
+    }
+
+    /// Gets the size of the buffer in bytes, as requested at creation.
+    #[inline(always)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gets the number of pages backing the buffer.
+    #[inline(always)]
+    pub fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    /// Gets the cacheability applied to the buffer.
+    #[inline(always)]
+    pub fn caching(&self) -> DmaCaching {
+        self.caching
+    }
+
+    /// Gets the address a bus master should use to access the buffer. As memory
+    /// is identity mapped, this is the same as the CPU address.
+    #[inline(always)]
+    pub fn device_address(&self) -> u64 {
+        self.address() as u64
+    }
+
+    /// Gets a raw pointer to the start of the buffer.
+    ///
+    /// The device may write to the buffer at any time it is programmed with the
+    /// buffer address, so accesses should be made with volatile operations or
+    /// after the device has signaled completion.
+    #[inline(always)]
+    pub fn as_mut_ptr(&self) -> *mut u8 {
+        self.blob.as_ptr()
+    }
+
+    /// Flushes CPU writes to a [`DmaCaching::WriteCombining`] buffer, so that they reach memory before the device is
+    /// told to read the buffer, or the buffer is read back.
+    ///
+    /// See [`flush_write_combining`](crate::arch::flush_write_combining).
+    #[inline(always)]
+    pub fn flush_write_combining(&self) {
+        crate::arch::flush_write_combining();
+    }
+
+    #[inline(always)]
+    fn address(&self) -> usize {
+        self.blob.addr().get()
+    }
+
+    /// Frees the pages without restoring their attributes.
+    fn free_pages(&mut self) {
+        let address = self.address();
+        // SAFETY: This structure contains the only reference to the memory.
+        unsafe {
+            if self.memory_manager.free_pages(address, self.page_count).is_err() {
+                log::error!("Failed to free DMA buffer at {address:x}!");
+                debug_assert!(false, "Failed to free DMA buffer!");
+            }
+        }
+    }
+}
+
+impl Drop for DmaBuffer {
+    fn drop(&mut self) {
+        // SAFETY: The buffer is being dropped so no references to it remain.
+        if unsafe {
+            self.memory_manager
+                .set_page_attributes(
+                    self.address(),
+                    self.page_count,
+                    AccessType::ReadWrite,
+                    Some(CachingType::WriteBack),
+                )
+                .is_err()
+        } {
+            log::error!("Failed to restore attributes of DMA buffer at {:x}!", self.address());
+        }
+        self.free_pages();
+    }
+}
+
+impl core::fmt::Display for DmaBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DmaBuffer")
+            .field("address", &self.blob)
+            .field("size", &self.size)
+            .field("caching", &self.caching)
+            .finish()
+    }
+}
+
 /// The `AccessType` enum represents the different types of access that can be
 /// requested for a page of memory. This reflects the access types that can be
 /// set in the CPU page table structures.
@@ -717,18 +937,15 @@ impl AccessType {
     /// convert the types.
     ///
     pub fn from_efi_attributes(attributes: u64) -> AccessType {
-        if attributes & efi::MEMORY_RP != 0 {
+        let attributes = MemoryAttributes::from(attributes);
+        if attributes.contains(MemoryAttributes::RP) {
             AccessType::NoAccess
         } else {
-            let readable_attr = attributes & (efi::MEMORY_RO | efi::MEMORY_XP);
-            if readable_attr == efi::MEMORY_RO {
-                AccessType::ReadExecute
-            } else if readable_attr == efi::MEMORY_XP {
-                AccessType::ReadWrite
-            } else if readable_attr == (efi::MEMORY_RO | efi::MEMORY_XP) {
-                AccessType::ReadOnly
-            } else {
-                AccessType::ReadWriteExecute
+            match attributes & (MemoryAttributes::RO | MemoryAttributes::XP) {
+                MemoryAttributes::RO => AccessType::ReadExecute,
+                MemoryAttributes::XP => AccessType::ReadWrite,
+                readable_attr if readable_attr.is_empty() => AccessType::ReadWriteExecute,
+                _ => AccessType::ReadOnly,
             }
         }
     }
@@ -763,12 +980,12 @@ impl CachingType {
     /// unused.
     ///
     pub fn from_efi_attributes(attributes: u64) -> Option<CachingType> {
-        match attributes & efi::CACHE_ATTRIBUTE_MASK {
-            efi::MEMORY_WB => Some(CachingType::WriteBack),
-            efi::MEMORY_WC => Some(CachingType::WriteCombining),
-            efi::MEMORY_WT => Some(CachingType::WriteThrough),
-            efi::MEMORY_UC => Some(CachingType::Uncached),
-            efi::MEMORY_WP => Some(CachingType::WriteProtect),
+        match MemoryAttributes::from(attributes).caching() {
+            MemoryAttributes::WB => Some(CachingType::WriteBack),
+            MemoryAttributes::WC => Some(CachingType::WriteCombining),
+            MemoryAttributes::WT => Some(CachingType::WriteThrough),
+            MemoryAttributes::UC => Some(CachingType::Uncached),
+            MemoryAttributes::WP => Some(CachingType::WriteProtect),
             _ => None,
         }
     }
@@ -915,6 +1132,7 @@ mod tests {
 
     use super::*;
     use crate::component::service::Service;
+    use r_efi::efi;
 
     #[test]
     fn test_custom_mock_with_failing() {
@@ -1078,6 +1296,119 @@ mod tests {
         // When pa goes out of scope, free_pages will be called, which will panic due to the mock returning an error.
     }
 
+    #[test]
+    fn test_dma_buffer_applies_requested_caching() {
+        let mm = StdMemoryManager::new();
+
+        let buffer = mm.alloc_dma_buffer(0x1800, UEFI_PAGE_SIZE, DmaCaching::Uncached).unwrap();
+        assert_eq!(buffer.size(), 0x1800);
+        assert_eq!(buffer.page_count(), 2);
+        assert_eq!(buffer.caching(), DmaCaching::Uncached);
+        assert_eq!(buffer.device_address(), buffer.as_mut_ptr() as u64);
+        assert_eq!(buffer.device_address() % UEFI_PAGE_SIZE as u64, 0);
+        assert_eq!(
+            buffer.memory_manager.get_page_attributes(buffer.address(), buffer.page_count()).unwrap(),
+            (AccessType::ReadWrite, CachingType::Uncached)
+        );
+
+        // Dropping the buffer should return the pages to write-back before freeing them.
+        let address = buffer.address();
+        let memory_manager = buffer.memory_manager;
+        drop(buffer);
+        assert_eq!(
+            memory_manager.get_page_attributes(address, 2).unwrap(),
+            (AccessType::ReadWrite, CachingType::WriteBack)
+        );
+    }
+
+    #[test]
+    fn test_dma_buffer_applies_write_combining() {
+        let mm = StdMemoryManager::new();
+
+        let buffer = mm.alloc_dma_buffer(0x1000, UEFI_PAGE_SIZE, DmaCaching::WriteCombining).unwrap();
+        assert_eq!(buffer.caching(), DmaCaching::WriteCombining);
+        assert_eq!(
+            buffer.memory_manager.get_page_attributes(buffer.address(), buffer.page_count()).unwrap(),
+            (AccessType::ReadWrite, CachingType::WriteCombining)
+        );
+
+        // SAFETY: The buffer is one page, and no device is accessing it.
+        unsafe { buffer.as_mut_ptr().write_volatile(0xA5) };
+        buffer.flush_write_combining();
+        // SAFETY: As above.
+        assert_eq!(unsafe { buffer.as_mut_ptr().read_volatile() }, 0xA5);
+    }
+
+    #[test]
+    fn test_dma_buffer_rejects_invalid_parameters() {
+        let mm = StdMemoryManager::new();
+
+        assert!(matches!(
+            mm.alloc_dma_buffer(0, UEFI_PAGE_SIZE, DmaCaching::Uncached),
+            Err(MemoryError::InvalidPageCount)
+        ));
+        assert!(matches!(
+            mm.alloc_dma_buffer(0x1000, 0x1800, DmaCaching::WriteCombining),
+            Err(MemoryError::InvalidAlignment)
+        ));
+    }
+
+    #[test]
+    fn test_dma_buffer_requests_alignment_and_frees_on_attribute_failure() {
+        static FREED: AtomicBool = AtomicBool::new(false);
+
+        let mut mock = MockMemoryManager::new();
+        mock.expect_allocate_zero_pages().returning(|page_count, options| {
+            assert_eq!(page_count, 1);
+            assert_eq!(options.alignment(), 0x10000);
+            assert_eq!(options.memory_type(), EfiMemoryType::BootServicesData);
+
+            let mut inner = MockMemoryManager::new();
+            inner.expect_set_page_attributes().returning(|_, _, _, caching| {
+                assert_eq!(caching, Some(CachingType::WriteCombining));
+                Err(MemoryError::UnsupportedAttributes)
+            });
+            inner.expect_free_pages().returning(|_, _| {
+                FREED.store(true, core::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            });
+
+            let mut allocation = StdMemoryManager::new().allocate_pages(page_count, AllocationOptions::new())?;
+            allocation.memory_manager = Box::leak(Box::new(inner));
+            Ok(allocation)
+        });
+
+        let result = DmaBuffer::new(&mock, 0x10, 0x10000, DmaCaching::WriteCombining);
+        assert!(matches!(result, Err(MemoryError::UnsupportedAttributes)));
+        assert!(FREED.load(core::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_dma_buffer_fails_when_attributes_are_not_applied() {
+        static RESTORED: AtomicBool = AtomicBool::new(false);
+
+        let mut mock = MockMemoryManager::new();
+        mock.expect_allocate_zero_pages().returning(|page_count, _| {
+            let mut inner = MockMemoryManager::new();
+            inner.expect_set_page_attributes().returning(|_, _, _, caching| {
+                if caching == Some(CachingType::WriteBack) {
+                    RESTORED.store(true, core::sync::atomic::Ordering::SeqCst);
+                }
+                Ok(())
+            });
+            inner.expect_get_page_attributes().returning(|_, _| Ok((AccessType::ReadWrite, CachingType::WriteBack)));
+            inner.expect_free_pages().returning(|_, _| Ok(()));
+
+            let mut allocation = StdMemoryManager::new().allocate_pages(page_count, AllocationOptions::new())?;
+            allocation.memory_manager = Box::leak(Box::new(inner));
+            Ok(allocation)
+        });
+
+        let result = DmaBuffer::new(&mock, UEFI_PAGE_SIZE, UEFI_PAGE_SIZE, DmaCaching::Uncached);
+        assert!(matches!(result, Err(MemoryError::UnsupportedAttributes)));
+        assert!(RESTORED.load(core::sync::atomic::Ordering::SeqCst));
+    }
+
     #[test]
     fn test_page_allocation_display() {
         let mm = StdMemoryManager::new();
@@ -26,11 +26,11 @@ use core::{
 };
 
 use super::{
-    hob::{FromHob, Hob},
+    hob::{FromHob, Hob, HobParser},
     service::{IntoService, Service},
 };
 
-type HobParsers = BTreeMap<OwnedGuid, BTreeMap<TypeId, fn(&[u8], &mut Storage)>>;
+type HobParsers = BTreeMap<OwnedGuid, BTreeMap<TypeId, HobParser>>;
 
 /// A vector whose elements are sparsely populated.
 #[derive(Debug)]
@@ -134,6 +134,15 @@ impl DerefMut for ConfigRaw {
     }
 }
 
+/// A description of a config datum registered in [Storage], used for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigDescriptor {
+    /// The type name of the config, as reported by [core::any::type_name].
+    pub name: &'static str,
+    /// Whether the config is locked (immutable).
+    pub locked: bool,
+}
+
 /// A container for deferred commands that will be executed later.
 #[derive(Default)]
 #[allow(clippy::type_complexity)]
@@ -200,6 +209,8 @@ pub struct Storage {
     configs: SparseVec<RefCell<ConfigRaw>>,
     /// A map to convert from a TypeId to a config index.
     config_indices: BTreeMap<TypeId, usize>,
+    /// The type name of each config, indexed by config index.
+    config_names: Vec<&'static str>,
     /// A container for all service datums. This resource can only be accessed immutably, but one service datum can
     /// represent multiple services. Services must have internal mutability if they need to be modified.
     services: SparseVec<&'static dyn Any>,
@@ -215,6 +226,10 @@ pub struct Storage {
     boot_services: StandardBootServices,
     // Standard Runtime Services.
     runtime_services: StandardRuntimeServices,
+    /// Per-component scratchpad state, keyed by component name and state type.
+    component_states: BTreeMap<(&'static str, TypeId), Box<dyn Any>>,
+    /// The name of the component currently being run via [Component::run](super::Component::run), if any.
+    running_component: Option<&'static str>,
 }
 
 impl Default for Storage {
@@ -230,6 +245,7 @@ impl Storage {
             deferred: None,
             configs: SparseVec::new(),
             config_indices: BTreeMap::new(),
+            config_names: Vec::new(),
             services: SparseVec::new(),
             service_indices: BTreeMap::new(),
             hob_parsers: BTreeMap::new(),
@@ -237,6 +253,8 @@ impl Storage {
             hob_indices: BTreeMap::new(),
             boot_services: StandardBootServices::new_uninit(),
             runtime_services: StandardRuntimeServices::new_uninit(),
+            component_states: BTreeMap::new(),
+            running_component: None,
         }
     }
 
@@ -258,6 +276,24 @@ impl Storage {
         self.deferred.as_mut().unwrap()
     }
 
+    /// Sets the component whose scratchpad is accessed by [component_state](Self::component_state).
+    pub(crate) fn set_running_component(&mut self, name: Option<&'static str>) {
+        self.running_component = name;
+    }
+
+    /// Returns the running component's scratchpad state of type `T`, creating it with `T::default()` on first access.
+    ///
+    /// The state persists across that component's run attempts, so a component that returns `Ok(false)` to be
+    /// dispatched again later can remember partial progress without using a global. State is keyed by the component's
+    /// name, so it is shared between components of the same name. Returns `None` if no component is being run.
+    pub fn component_state<T: Default + 'static>(&mut self) -> Option<&mut T> {
+        let name = self.running_component?;
+        self.component_states
+            .entry((name, TypeId::of::<T>()))
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut::<T>()
+    }
+
     /// Stores a pointer to the UEFI Boot Services Table.
     pub fn set_boot_services(&mut self, bs: StandardBootServices) {
         self.boot_services = bs;
@@ -281,7 +317,30 @@ impl Storage {
     /// Registers a config type with the storage and returns its global id.
     pub(crate) fn register_config<C: Default + 'static>(&mut self) -> usize {
         let idx = self.config_indices.len();
-        *self.config_indices.entry(TypeId::of::<C>()).or_insert(idx)
+        *self.config_indices.entry(TypeId::of::<C>()).or_insert_with(|| {
+            self.config_names.push(core::any::type_name::<C>());
+            idx
+        })
+    }
+
+    /// Returns the type name and lock state of each config present in the storage.
+    pub fn describe_configs(&self) -> Vec<ConfigDescriptor> {
+        self.config_names
+            .iter()
+            .enumerate()
+            .filter_map(|(id, name)| Some(ConfigDescriptor { name, locked: self.config_is_locked(id)? }))
+            .collect()
+    }
+
+    /// Returns whether a component cannot run because a config it accesses is not in the lock state it needs, which is
+    /// locked for [Config](super::params::Config) and unlocked for [ConfigMut](super::params::ConfigMut).
+    pub fn is_blocked_on_config(&self, metadata: &MetaData) -> bool {
+        metadata.access().has_unavailable_config(|id| self.config_is_locked(id))
+    }
+
+    /// Returns whether the config with `id` is locked, or `None` if it is not present or is borrowed mutably.
+    fn config_is_locked(&self, id: usize) -> Option<bool> {
+        self.configs.get(id)?.try_borrow().ok().map(|config| config.is_locked())
     }
 
     /// Adds a default valued config datum to the storage if it does not exist.
@@ -375,7 +434,7 @@ impl Storage {
     }
 
     pub(crate) fn add_hob_parser<T: FromHob>(&mut self) {
-        self.hob_parsers.entry(T::HOB_GUID).or_default().insert(TypeId::of::<T>(), T::register);
+        self.hob_parsers.entry(T::HOB_GUID).or_default().insert(TypeId::of::<T>(), HobParser::new::<T>());
     }
 
     /// Registers a HOB with the storage and returns its global id.
@@ -418,7 +477,7 @@ impl Storage {
     }
 
     /// Attempts to retrieve a HOB parser from the storage.
-    pub fn get_hob_parsers(&self, guid: &OwnedGuid) -> Vec<fn(&[u8], &mut Storage)> {
+    pub fn get_hob_parsers(&self, guid: &OwnedGuid) -> Vec<HobParser> {
         self.hob_parsers.get(guid).map(|type_map| type_map.values().copied().collect()).unwrap_or_default()
     }
 }
@@ -696,4 +755,52 @@ mod tests {
         let service = storage.get_service::<dyn TestService>().unwrap();
         assert_eq!(service.test(), 42);
     }
+
+    #[test]
+    fn describe_configs_should_report_name_and_lock_state() {
+        let mut storage = Storage::new();
+        assert!(storage.describe_configs().is_empty());
+
+        storage.add_config(5u32);
+        let id = storage.add_config_default_if_not_present::<u64>();
+        storage.unlock_config(id);
+        // Registered but not present configs are not described.
+        storage.register_config::<i8>();
+
+        assert_eq!(
+            storage.describe_configs(),
+            vec![ConfigDescriptor { name: "u32", locked: true }, ConfigDescriptor { name: "u64", locked: false },]
+        );
+
+        storage.lock_configs();
+        assert!(storage.describe_configs().iter().all(|config| config.locked));
+
+        // A config that is borrowed mutably is not described.
+        let _config = storage.get_raw_config_mut(id);
+        assert_eq!(storage.describe_configs(), vec![ConfigDescriptor { name: "u32", locked: true }]);
+    }
+
+    #[test]
+    fn is_blocked_on_config_should_check_the_lock_state_of_accessed_configs() {
+        let mut storage = Storage::new();
+        let read_id = storage.add_config_default_if_not_present::<u32>();
+        let write_id = storage.add_config_default_if_not_present::<u64>();
+        storage.unlock_config(write_id);
+
+        let mut reader = MetaData::new::<u32>();
+        reader.access_mut().add_config_read(read_id);
+        let mut writer = MetaData::new::<u64>();
+        writer.access_mut().add_config_write(write_id);
+
+        assert!(!storage.is_blocked_on_config(&reader));
+        assert!(!storage.is_blocked_on_config(&writer));
+        assert!(!storage.is_blocked_on_config(&MetaData::new::<u8>()));
+
+        storage.unlock_config(read_id);
+        assert!(storage.is_blocked_on_config(&reader));
+
+        storage.lock_configs();
+        assert!(!storage.is_blocked_on_config(&reader));
+        assert!(storage.is_blocked_on_config(&writer));
+    }
 }
@@ -31,7 +31,7 @@ use crate::{
         params::{ComponentInput, Param, ParamFunction},
         storage::{Storage, UnsafeStorageCell},
     },
-    error::Result,
+    error::{EfiError, Result},
 };
 use core::marker::PhantomData;
 
@@ -44,6 +44,8 @@ where
     input: Option<Func::In>,
     param_state: Option<<Func::Param as Param>::State>,
     metadata: MetaData,
+    run_once: bool,
+    executed: bool,
     _marker: PhantomData<fn() -> Marker>,
 }
 
@@ -59,9 +61,22 @@ where
             input: Some(input),
             param_state: None,
             metadata: MetaData::new::<Func::In>(),
+            run_once: false,
+            executed: false,
             _marker: PhantomData,
         }
     }
+
+    /// Guarantees the component's function is executed at most once.
+    ///
+    /// Once the function has executed, any further attempt to run the component returns
+    /// [EfiError::AlreadyStarted](crate::error::EfiError::AlreadyStarted) without executing the function or
+    /// re-validating its parameters. This is set with `#[entry_point(run_once)]` when deriving
+    /// [IntoComponent](crate::component::IntoComponent).
+    pub fn run_once(mut self) -> Self {
+        self.run_once = true;
+        self
+    }
 }
 
 impl<Marker, In, Func> Component for StructComponent<Marker, Func>
@@ -77,6 +92,11 @@ where
     /// - Each parameter must properly register its access type.
     /// - Each parameter must properly validate its access ability.
     unsafe fn run_unsafe(&mut self, storage: UnsafeStorageCell) -> Result<bool> {
+        if self.run_once && self.executed {
+            log::error!("{} has already been executed and is marked run_once.", self.metadata.name());
+            return Err(EfiError::AlreadyStarted);
+        }
+
         let param_state = self.param_state.as_mut().expect("Param state created on initialize.");
 
         if let Err(bad_param) = Func::Param::try_validate(param_state, storage) {
@@ -91,6 +111,7 @@ where
             "{} `input` is `None` during run. Did this component already run?",
             core::any::type_name::<Self>()
         );
+        self.executed = true;
         self.func.run(&mut self.input, param_value).map(|_| true)
     }
 
@@ -196,6 +217,33 @@ mod tests {
         assert!(test_struct.run(&mut storage).is_err_and(|res| res == crate::error::EfiError::NotReady));
     }
 
+    #[test]
+    fn test_run_once_component_executes_exactly_once() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(IntoComponent)]
+        #[entry_point(path = RunOnceByRef::entry_point, run_once)]
+        struct RunOnceByRef;
+
+        impl RunOnceByRef {
+            fn entry_point(&self, _cfg: Config<i32>) -> crate::error::Result<()> {
+                RUNS.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let mut storage = crate::component::storage::Storage::new();
+        let mut component = RunOnceByRef.into_component();
+        component.initialize(&mut storage);
+
+        assert!(component.run(&mut storage).is_ok_and(|res| res));
+        assert_eq!(component.run(&mut storage), Err(crate::error::EfiError::AlreadyStarted));
+        assert_eq!(component.run(&mut storage), Err(crate::error::EfiError::AlreadyStarted));
+        assert_eq!(RUNS.load(Ordering::SeqCst), 1);
+    }
+
     //Test structs that use generics and where clause
     #[derive(crate::component::IntoComponent)]
     struct GenericStruct<T>
@@ -7,6 +7,8 @@
 //!
 //! - `core`: Exposes additional items in the [component] module necessary to
 //!   manage and execute components and their dependencies.
+//! - `perf_checksum`: Maintains a running CRC over the performance record buffer
+//!   and verifies it before the FBPT is reported, to detect corruption in memory.
 //!
 //! ## License
 //!
@@ -35,6 +37,7 @@ pub mod macros;
 
 pub mod arch;
 pub mod base;
+pub mod boot_once;
 pub mod boot_services;
 pub mod component;
 pub mod driver_binding;
@@ -39,19 +39,34 @@
 mod serial_logger;
 pub use serial_logger::Logger as SerialLogger;
 
+use core::fmt::Write;
+
 /// Enum to describe the format of the log message.
 pub enum Format {
     /// Standard text format containing the log level and message.
     Standard,
-    /// JSON blob containing the log level and message.
+    /// JSON object per line containing the timestamp, if known, log level and message.
     Json,
-    /// Verbose JSON blob containing the log level, message, target, and file path and line number.
+    /// JSON object per line containing the timestamp, if known, log level, target, message, and file path and line
+    /// number.
     VerboseJson,
 }
 
 impl Format {
     /// Formats the log message and writes it to the target.
     pub fn write<T: core::fmt::Write>(&self, target: &mut T, record: &log::Record) {
+        self.write_with_timestamp(target, record, None);
+    }
+
+    /// Formats the log message and writes it to the target, including the timestamp in the JSON formats.
+    ///
+    /// The timestamp is in the ticks of whatever time source the caller uses, and is not part of the standard format.
+    pub fn write_with_timestamp<T: core::fmt::Write>(
+        &self,
+        target: &mut T,
+        record: &log::Record,
+        timestamp: Option<u64>,
+    ) {
         // Note: This function may be called before memory allocation is fully initialized. Therefore, it should not
         //       depend on any heap allocation. In particular, the `format!()` macro creates a `String` which is
         //       allocated on the heap. It is avoided below in favor of directly writing to the target or preparing
@@ -71,29 +86,105 @@ impl Format {
             Format::Standard => {
                 writeln!(target, "{} - {}", record.level(), record.args()).expect("Printing to serial failed");
             }
-            Format::Json => {
-                write!(
-                    target,
-                    "{}",
-                    format_args!("{{\"level\": \"{}\" \"message\": \"{}\"}}\n", record.level(), record.args())
-                )
-                .expect("Printing to serial failed");
+            Format::Json | Format::VerboseJson => {
+                Self::write_json(target, record, timestamp, matches!(self, Format::VerboseJson))
+                    .expect("Printing to serial failed");
             }
-            Format::VerboseJson => {
-                write!(
-                    target,
-                    "{}",
-                    format_args!(
-            "{{\"level\": \"{}\", \"target\": \"{}\", \"message\": \"{}\", \"file\": \"{}\", \"line\": \"{}\"}}\n",
-            record.level(),
-            record.target(),
-            record.args(),
-            record.file().unwrap_or("unknown"),
-            record.line().unwrap_or(0)
-          )
-                )
-                .expect("Printing to serial failed");
+        }
+    }
+
+    fn write_json<T: core::fmt::Write>(
+        target: &mut T,
+        record: &log::Record,
+        timestamp: Option<u64>,
+        verbose: bool,
+    ) -> core::fmt::Result {
+        target.write_str("{")?;
+        if let Some(timestamp) = timestamp {
+            write!(target, "\"timestamp\": {timestamp}, ")?;
+        }
+        write!(target, "\"level\": \"{}\", ", record.level())?;
+        if verbose {
+            target.write_str("\"target\": \"")?;
+            JsonEscaped(target).write_str(record.target())?;
+            target.write_str("\", ")?;
+        }
+        target.write_str("\"message\": \"")?;
+        write!(JsonEscaped(target), "{}", record.args())?;
+        if verbose {
+            target.write_str("\", \"file\": \"")?;
+            JsonEscaped(target).write_str(record.file().unwrap_or("unknown"))?;
+            write!(target, "\", \"line\": \"{}", record.line().unwrap_or(0))?;
+        }
+        target.write_str("\"}\n")
+    }
+}
+
+/// Escapes everything written through it for use in a JSON string.
+struct JsonEscaped<'a, T: core::fmt::Write>(&'a mut T);
+
+impl<T: core::fmt::Write> Write for JsonEscaped<'_, T> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        // Unescaped runs are written as is, so a long message is passed through in as few writes as possible.
+        let mut run_start = 0;
+        for (i, c) in s.char_indices() {
+            let escape = match c {
+                '"' => Some("\\\""),
+                '\\' => Some("\\\\"),
+                '\n' => Some("\\n"),
+                '\r' => Some("\\r"),
+                '\t' => Some("\\t"),
+                c if c.is_control() => None,
+                _ => continue,
+            };
+            self.0.write_str(&s[run_start..i])?;
+            match escape {
+                Some(escape) => self.0.write_str(escape)?,
+                None => write!(self.0, "\\u{:04x}", c as u32)?,
             }
+            run_start = i + c.len_utf8();
         }
+        self.0.write_str(&s[run_start..])
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    extern crate std;
+    use std::string::String;
+
+    use super::*;
+
+    fn format_record(format: Format, message: core::fmt::Arguments, timestamp: Option<u64>) -> String {
+        let mut output = String::new();
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .target("crate::module")
+            .file(Some("src/module.rs"))
+            .line(Some(42))
+            .args(message)
+            .build();
+        format.write_with_timestamp(&mut output, &record, timestamp);
+        output
+    }
+
+    #[test]
+    fn json_should_be_one_object_per_line() {
+        assert_eq!(
+            format_record(Format::Json, format_args!("plain"), Some(1234)),
+            "{\"timestamp\": 1234, \"level\": \"WARN\", \"message\": \"plain\"}\n"
+        );
+        assert_eq!(
+            format_record(Format::VerboseJson, format_args!("plain"), None),
+            "{\"level\": \"WARN\", \"target\": \"crate::module\", \"message\": \"plain\", \"file\": \"src/module.rs\", \
+             \"line\": \"42\"}\n"
+        );
+    }
+
+    #[test]
+    fn json_should_escape_quotes_and_control_characters() {
+        let output = format_record(Format::Json, format_args!("say \"hi\"\\\n\tdone\u{1}{}", '\r'), None);
+        assert_eq!(output, "{\"level\": \"WARN\", \"message\": \"say \\\"hi\\\"\\\\\\n\\tdone\\u0001\\r\"}\n");
     }
 }
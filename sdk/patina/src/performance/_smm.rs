@@ -137,7 +137,7 @@ impl CommunicateProtocol {
         if status.is_error() {
             Err(status)
         } else {
-            Ok(comm_buffer.pread_with::<T>(data_offset, scroll::NATIVE).unwrap())
+            comm_buffer.pread_with::<T>(data_offset, scroll::NATIVE).map_err(|_| efi::Status::COMPROMISED_DATA)
         }
     }
 }
@@ -158,6 +158,11 @@ impl SmmGetRecordSize {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns the total size of the boot records reported by a response, or the error status it returned.
+    pub fn total_size(&self) -> Result<usize, efi::Status> {
+        if self.return_status.is_error() { Err(self.return_status) } else { Ok(self.boot_record_size) }
+    }
 }
 
 // SAFETY: `EFI_FIRMWARE_PERFORMANCE_GUID` matches layout of `SmmGetRecordSize` for serialization and deserialization.
@@ -217,7 +222,66 @@ impl<const BUFFER_SIZE: usize> SmmGetRecordDataByOffset<BUFFER_SIZE> {
     }
 
     pub fn boot_record_data(&self) -> &[u8] {
-        &self.boot_record_data[..self.boot_record_data_size]
+        &self.boot_record_data[..self.boot_record_data_size.min(BUFFER_SIZE)]
+    }
+}
+
+/// A request for the boot records at an offset, bounded by the total size reported by [`SmmGetRecordSize`].
+///
+/// The request is kept to check the response against, as the response is read back over the request in the
+/// communicate buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmmRecordDataRequest<const BUFFER_SIZE: usize> {
+    offset: usize,
+    size: usize,
+}
+
+impl<const BUFFER_SIZE: usize> SmmRecordDataRequest<BUFFER_SIZE> {
+    /// Creates a request for the boot records at `offset`, of at most `BUFFER_SIZE` bytes.
+    ///
+    /// Returns `INVALID_PARAMETER` if `offset` is not within `total_size`.
+    pub fn new(offset: usize, total_size: usize) -> Result<Self, efi::Status> {
+        if offset >= total_size {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        Ok(Self { offset, size: BUFFER_SIZE.min(total_size - offset) })
+    }
+
+    /// The offset of the requested boot records.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The number of bytes requested.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The communicate data for this request.
+    pub fn data(&self) -> SmmGetRecordDataByOffset<BUFFER_SIZE> {
+        let mut data = SmmGetRecordDataByOffset::new(self.offset);
+        data.boot_record_data_size = self.size;
+        data
+    }
+
+    /// Returns the boot record data of a response to this request.
+    ///
+    /// Returns the response status if it is an error, or `COMPROMISED_DATA` if the response is not for this
+    /// request's offset or returns no data or more data than requested.
+    pub fn checked_data<'a>(
+        &self,
+        response: &'a SmmGetRecordDataByOffset<BUFFER_SIZE>,
+    ) -> Result<&'a [u8], efi::Status> {
+        if response.return_status.is_error() {
+            return Err(response.return_status);
+        }
+        if response.boot_record_offset != self.offset
+            || response.boot_record_data_size == 0
+            || response.boot_record_data_size > self.size
+        {
+            return Err(efi::Status::COMPROMISED_DATA);
+        }
+        Ok(&response.boot_record_data[..response.boot_record_data_size])
     }
 }
 
@@ -257,3 +321,89 @@ impl<const BUFFER_SIZE: usize> TryFromCtx<'_, Endian> for SmmGetRecordDataByOffs
         Ok((Self { return_status, boot_record_data, boot_record_data_size, boot_record_offset }, offset))
     }
 }
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+
+    /// Writes a synthetic get record data by offset response.
+    fn record_data_response(status: efi::Status, data: &[u8], offset: usize, buffer_size: usize) -> std::vec::Vec<u8> {
+        let mut response = std::vec![0_u8; 40 + buffer_size];
+        let mut cursor = 0;
+        response.gwrite_with(3_u64, &mut cursor, scroll::NATIVE).unwrap();
+        response.gwrite_with(status.as_usize() as u64, &mut cursor, scroll::NATIVE).unwrap();
+        response.gwrite_with(data.len() as u64, &mut cursor, scroll::NATIVE).unwrap();
+        response.gwrite_with(0_u64, &mut cursor, scroll::NATIVE).unwrap();
+        response.gwrite_with(offset as u64, &mut cursor, scroll::NATIVE).unwrap();
+        response[cursor..cursor + data.len()].copy_from_slice(data);
+        response
+    }
+
+    #[test]
+    fn test_record_data_request_is_bounded_by_total_size() {
+        let request = SmmRecordDataRequest::<16>::new(0, 40).unwrap();
+        assert_eq!((request.offset(), request.size()), (0, 16));
+
+        let request = SmmRecordDataRequest::<16>::new(32, 40).unwrap();
+        assert_eq!((request.offset(), request.size()), (32, 8));
+
+        let data = request.data();
+        assert_eq!(data.boot_record_offset, 32);
+        assert_eq!(data.boot_record_data_size, 8);
+
+        let mut buffer = [0_u8; 40];
+        assert_eq!(buffer.pwrite_with(data, 0, scroll::NATIVE).unwrap(), 40);
+        assert_eq!(buffer.pread_with::<u64>(16, scroll::NATIVE).unwrap(), 8);
+        assert_eq!(buffer.pread_with::<u64>(32, scroll::NATIVE).unwrap(), 32);
+
+        assert_eq!(SmmRecordDataRequest::<16>::new(40, 40), Err(efi::Status::INVALID_PARAMETER));
+        assert_eq!(SmmRecordDataRequest::<16>::new(41, 40), Err(efi::Status::INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn test_record_data_response_is_checked_against_request() {
+        let request = SmmRecordDataRequest::<16>::new(32, 40).unwrap();
+
+        let response = record_data_response(efi::Status::SUCCESS, &[0xAA; 8], 32, 16);
+        let response = response.pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).unwrap();
+        assert_eq!(request.checked_data(&response), Ok([0xAA; 8].as_slice()));
+
+        let response = record_data_response(efi::Status::SUCCESS, &[0xAA; 8], 0, 16);
+        let response = response.pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).unwrap();
+        assert_eq!(request.checked_data(&response), Err(efi::Status::COMPROMISED_DATA));
+
+        let response = record_data_response(efi::Status::SUCCESS, &[0xAA; 12], 32, 16);
+        let response = response.pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).unwrap();
+        assert_eq!(request.checked_data(&response), Err(efi::Status::COMPROMISED_DATA));
+
+        let response = record_data_response(efi::Status::SUCCESS, &[], 32, 16);
+        let response = response.pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).unwrap();
+        assert_eq!(request.checked_data(&response), Err(efi::Status::COMPROMISED_DATA));
+
+        let response = record_data_response(efi::Status::ACCESS_DENIED, &[], 32, 16);
+        let response = response.pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).unwrap();
+        assert_eq!(request.checked_data(&response), Err(efi::Status::ACCESS_DENIED));
+    }
+
+    #[test]
+    fn test_malformed_short_response_is_rejected() {
+        let response = record_data_response(efi::Status::SUCCESS, &[0xAA; 8], 32, 16);
+        assert!(response[..response.len() - 1].pread_with::<SmmGetRecordDataByOffset<16>>(0, scroll::NATIVE).is_err());
+
+        let mut response = [0_u8; 40];
+        response
+            .pwrite_with(
+                SmmGetRecordSize { return_status: efi::Status::SUCCESS, boot_record_size: 0x80 },
+                0,
+                scroll::NATIVE,
+            )
+            .unwrap();
+        assert!(response[..24].pread_with::<SmmGetRecordSize>(0, scroll::NATIVE).is_err());
+
+        let response = response.pread_with::<SmmGetRecordSize>(0, scroll::NATIVE).unwrap();
+        assert_eq!(response.total_size(), Ok(0x80));
+        let response = SmmGetRecordSize { return_status: efi::Status::DEVICE_ERROR, boot_record_size: 0x80 };
+        assert_eq!(response.total_size(), Err(efi::Status::DEVICE_ERROR));
+    }
+}
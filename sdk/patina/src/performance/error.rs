@@ -29,6 +29,8 @@ pub enum Error {
     OutOfResources,
     /// Buffer too small to allocate fbpt.
     BufferTooSmall,
+    /// The performance records do not match their checksum, they were corrupted in memory.
+    ChecksumMismatch,
     /// UEFI specification defined error type.
     Efi(EfiError),
     /// Error returned when `debug_assert` is disabled.
@@ -53,6 +55,7 @@ impl Display for Error {
         match self {
             Error::OutOfResources => write!(f, "FBPT buffer full, can't add more performance records."),
             Error::BufferTooSmall => write!(f, "Buffer to small to allocate FBPT table"),
+            Error::ChecksumMismatch => write!(f, "Performance records do not match their checksum"),
             Error::Efi(efi_error) => write!(f, "{efi_error:?}"),
             Error::DebugAssert { msg, file, line } => write!(f, "Assertion at {file}:{line}: {msg}"),
         }
@@ -13,11 +13,18 @@ use crate::{
 };
 use core::{
     cell::OnceCell,
-    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
 };
+use mu_rust_helpers::perf_timer::{Arch, ArchFunctionality};
+
+/// Timer frequencies below this, in Hz, are too coarse to give meaningful timestamps.
+const MIN_PERF_FREQUENCY: u64 = 1_000;
+/// Marks the timer frequency as not yet set.
+const PERF_FREQUENCY_UNSET: u64 = u64::MAX;
 
 static LOAD_IMAGE_COUNT: AtomicU32 = AtomicU32::new(0);
 static PERF_MEASUREMENT_MASK: AtomicU32 = AtomicU32::new(0);
+static PERF_FREQUENCY: AtomicU64 = AtomicU64::new(PERF_FREQUENCY_UNSET);
 
 /// Static state for the performance component.
 struct StaticState<'a> {
@@ -92,6 +99,33 @@ pub fn get_perf_measurement_mask() -> u32 {
     PERF_MEASUREMENT_MASK.load(Ordering::Relaxed)
 }
 
+/// Set the frequency, in Hz, of the timer used to timestamp performance measurements.
+///
+/// A zero or implausibly low frequency disables the conversion of timestamps to nanoseconds, and a single warning is
+/// logged. Measurements are still recorded, timestamped with the raw timer ticks. Returns whether the conversion is
+/// enabled.
+pub fn set_perf_frequency(frequency: u64) -> bool {
+    let enabled = frequency >= MIN_PERF_FREQUENCY;
+    if !enabled {
+        log::warn!("Performance: Timer frequency of {frequency} Hz is unusable, timestamps will be in timer ticks.");
+    }
+    PERF_FREQUENCY.store(if enabled { frequency } else { 0 }, Ordering::Relaxed);
+    enabled
+}
+
+/// Get the frequency, in Hz, of the timer used to timestamp performance measurements.
+///
+/// Returns `None` if timestamps are not converted to nanoseconds. If the frequency was never set with
+/// [`set_perf_frequency`], it is read from the architecture timer.
+pub fn get_perf_frequency() -> Option<u64> {
+    let mut frequency = PERF_FREQUENCY.load(Ordering::Relaxed);
+    if frequency == PERF_FREQUENCY_UNSET {
+        set_perf_frequency(Arch::perf_frequency());
+        frequency = PERF_FREQUENCY.load(Ordering::Relaxed);
+    }
+    (frequency != 0).then_some(frequency)
+}
+
 /// Get the current load image count.
 pub fn get_load_image_count() -> u32 {
     LOAD_IMAGE_COUNT.load(Ordering::Relaxed)
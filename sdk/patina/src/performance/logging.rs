@@ -93,6 +93,26 @@ fn end_perf_measurement(
     };
 }
 
+/// Length of the buffer [`with_c_str`] copies a string into before falling back to an allocation.
+const C_STR_BUFFER_LEN: usize = 64;
+
+// Runs `f` with `string` as a C string, or a null pointer if `string` is empty. As in C, the string ends at its first 0
+// byte. Strings shorter than `C_STR_BUFFER_LEN` are copied to the stack, so only longer strings are allocated.
+pub(crate) fn with_c_str<R>(string: &str, f: impl FnOnce(*const c_char) -> R) -> R {
+    let bytes = string.as_bytes();
+    let bytes = &bytes[..bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len())];
+    if bytes.is_empty() {
+        return f(ptr::null());
+    }
+    if bytes.len() < C_STR_BUFFER_LEN {
+        let mut buffer = [0_u8; C_STR_BUFFER_LEN];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        return f(buffer.as_ptr() as *const c_char);
+    }
+    let string = CString::new(bytes).expect("String should not contain 0 bytes.");
+    f(string.as_ptr())
+}
+
 /// Begins performance measurement of start image in core.
 pub fn perf_image_start_begin(module_handle: efi::Handle, create_performance_measurement: CreateMeasurement) {
     if get_perf_measurement_mask() & Measurement::StartImage as u32 == 0 {
@@ -428,6 +448,48 @@ pub fn perf_cross_module_end(
     )
 }
 
+/// Measure the time from power-on to this function execution, tagged with caller provided context data.
+///
+/// The `context` value is stored alongside the event to correlate it with data outside of the performance records.
+pub fn perf_tagged_event(
+    event_string: &str,
+    context: u64,
+    caller_id: &efi::Guid,
+    create_performance_measurement: CreateMeasurement,
+) {
+    log_perf_measurement(
+        caller_id as *const efi::Guid as *mut c_void,
+        None,
+        Some(event_string),
+        context as usize,
+        KnownPerfId::PerfTaggedEvent.as_u16(),
+        create_performance_measurement,
+    )
+}
+
+/// Logs a [`perf_tagged_event`], tagging the measurement `name` with a 64-bit `context` value.
+///
+/// The record is created with [`create_performance_measurement`](crate::performance::measurement::create_performance_measurement)
+/// unless another [`CreateMeasurement`] function is given as the last argument.
+///
+/// ```ignore
+/// perf_tagged!("transaction", transaction_id, &CALLER_ID);
+/// ```
+#[macro_export]
+macro_rules! perf_tagged {
+    ($name:expr, $context:expr, $caller_id:expr) => {
+        $crate::perf_tagged!(
+            $name,
+            $context,
+            $caller_id,
+            $crate::performance::measurement::create_performance_measurement
+        )
+    };
+    ($name:expr, $context:expr, $caller_id:expr, $create_performance_measurement:expr) => {
+        $crate::performance::logging::perf_tagged_event($name, $context, $caller_id, $create_performance_measurement)
+    };
+}
+
 /// Adds a record that records the start time of a performance measurement.
 pub fn perf_start(
     handle: efi::Handle,
@@ -473,3 +535,37 @@ pub fn perf_end_ex(
 ) {
     end_perf_measurement(handle, token, module, timestamp, identifier, create_performance_measurement)
 }
+
+/// Adds a record that records the start time of a performance measurement, as [`perf_start`] does for C strings.
+///
+/// The measurement is named by `token`, or by `module` if `token` is empty. Names shorter than 64 bytes are passed
+/// on without allocating.
+pub fn perf_start_str(
+    handle: efi::Handle,
+    token: &str,
+    module: &str,
+    timestamp: u64,
+    create_performance_measurement: CreateMeasurement,
+) {
+    let name = if token.is_empty() { module } else { token };
+    with_c_str(name, |name| {
+        start_perf_measurement(handle, name, ptr::null(), timestamp, 0, create_performance_measurement)
+    })
+}
+
+/// Adds a record that records the end time of a performance measurement, as [`perf_end`] does for C strings.
+///
+/// The measurement is named by `token`, or by `module` if `token` is empty. Names shorter than 64 bytes are passed
+/// on without allocating.
+pub fn perf_end_str(
+    handle: efi::Handle,
+    token: &str,
+    module: &str,
+    timestamp: u64,
+    create_performance_measurement: CreateMeasurement,
+) {
+    let name = if token.is_empty() { module } else { token };
+    with_c_str(name, |name| {
+        end_perf_measurement(handle, name, ptr::null(), timestamp, 0, create_performance_measurement)
+    })
+}
@@ -8,30 +8,39 @@
 //!
 extern crate alloc;
 
-use alloc::{boxed::Box, string::ToString, vec::Vec};
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{
+    cell::UnsafeCell,
     clone::Clone,
     convert::AsRef,
     ffi::{CStr, c_char, c_void},
     mem,
     ops::BitOr,
-    ptr,
-    sync::atomic::{AtomicBool, Ordering},
+    ptr, slice,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 use crate::{
-    boot_services::BootServices,
+    boot_once::BootOnce,
+    boot_services::{BootServices, configuration_table::ConfigTableEntry},
     error::EfiError,
-    guids::EDKII_FPDT_EXTENDED_FIRMWARE_PERFORMANCE,
+    guids::{EDKII_FPDT_EXTENDED_FIRMWARE_PERFORMANCE, PERFORMANCE_PROTOCOL},
     performance::{
         self,
-        _smm::{CommunicateProtocol, MmCommRegion, SmmGetRecordDataByOffset, SmmGetRecordSize},
+        _smm::{CommunicateProtocol, MmCommRegion, SmmGetRecordSize, SmmRecordDataRequest},
         error::Error,
-        globals::{get_load_image_count, get_static_state, increment_load_image_count},
+        globals::{get_load_image_count, get_perf_frequency, get_static_state, increment_load_image_count},
+        logging::with_c_str,
         record::{
+            GenericPerformanceRecord, Iter,
             extended::{
                 DualGuidStringEventRecord, DynamicStringEventRecord, GuidEventRecord, GuidQwordEventRecord,
-                GuidQwordStringEventRecord,
+                GuidQwordStringEventRecord, TaggedEventRecord,
             },
             known::{KnownPerfId, KnownPerfToken},
         },
@@ -39,10 +48,17 @@ use crate::{
     },
     runtime_services::RuntimeServices,
     tpl_mutex::TplMutex,
-    uefi_protocol::{performance_measurement::PerfAttribute, status_code::StatusCodeRuntimeProtocol},
+    uefi_protocol::{
+        performance_measurement::{CreateMeasurement, PerfAttribute},
+        status_code::StatusCodeRuntimeProtocol,
+    },
 };
 
-use crate::pi::status_code::{EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_BS_DRIVER};
+use crate::pi::{
+    fw_fs::ffs::section,
+    protocols::firmware_volume,
+    status_code::{EFI_PROGRESS_CODE, EFI_SOFTWARE_DXE_BS_DRIVER},
+};
 use mu_rust_helpers::perf_timer::{Arch, ArchFunctionality};
 
 use r_efi::{
@@ -151,25 +167,25 @@ pub mod event_callback {
         let mut smm_boot_records_data = Vec::with_capacity(boot_record_size);
 
         while smm_boot_records_data.len() < boot_record_size {
+            // Ask smm to return us the next bytes in its buffer.
+            const BUFFER_SIZE: usize = 1024;
+            let Ok(request) = SmmRecordDataRequest::<BUFFER_SIZE>::new(smm_boot_records_data.len(), boot_record_size)
+            else {
+                break;
+            };
+
             // SAFETY: Is safe to use because the memory region commes from a thrusted source and can be considered valid.
-            match unsafe {
-                // Ask smm to return us the next bytes in its buffer.
-                const BUFFER_SIZE: usize = 1024;
-                communication.communicate(
-                    SmmGetRecordDataByOffset::<BUFFER_SIZE>::new(smm_boot_records_data.len()),
-                    mm_comm_region,
-                )
-            } {
-                Ok(record_data) if record_data.return_status == efi::Status::SUCCESS => {
+            match unsafe { communication.communicate(request.data(), mm_comm_region) } {
+                Ok(record_data) => match request.checked_data(&record_data) {
                     // Append the byte to the total smm performance record data.
-                    smm_boot_records_data.extend_from_slice(record_data.boot_record_data());
-                }
-                Ok(SmmGetRecordDataByOffset { return_status, .. }) => {
-                    log::error!(
-                        "Performance: Asking for smm perf records data result in an error with return status of: {return_status:?}",
-                    );
-                    return;
-                }
+                    Ok(data) => smm_boot_records_data.extend_from_slice(data),
+                    Err(return_status) => {
+                        log::error!(
+                            "Performance: Asking for smm perf records data result in an error with return status of: {return_status:?}",
+                        );
+                        return;
+                    }
+                },
                 Err(status) => {
                     log::error!(
                         "Performance: Error while trying to communicate with communicate protocol with error status code: {status:?}",
@@ -180,17 +196,39 @@ pub mod event_callback {
         }
 
         // Write found perf records in the fbpt table.
-        let mut fbpt = fbpt.lock();
-        let mut n = 0;
-        for r in performance::record::Iter::new(&smm_boot_records_data) {
-            _ = fbpt.add_record(r);
-            n += 1;
-        }
+        let n = add_mm_performance_records(&mut *fbpt.lock(), &smm_boot_records_data);
 
         log::info!("Performance: {n} smm performance records found.");
     }
 }
 
+/// Number of performance records dropped because the FBPT could not hold them.
+static DROPPED_RECORDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Return the number of performance records dropped because the FBPT could not hold them.
+pub fn dropped_record_count() -> usize {
+    DROPPED_RECORDS.load(Ordering::Relaxed)
+}
+
+/// Adds the MM performance records in `data` to the FBPT, returning the number of records added.
+///
+/// Records the table cannot hold are counted in [`dropped_record_count`] rather than failing the whole transfer.
+fn add_mm_performance_records<F: FirmwareBasicBootPerfTable>(fbpt: &mut F, data: &[u8]) -> usize {
+    let mut added = 0;
+    let mut dropped = 0;
+    for record in performance::record::Iter::new(data) {
+        match fbpt.add_record(record) {
+            Ok(()) => added += 1,
+            Err(_) => dropped += 1,
+        }
+    }
+    if dropped > 0 {
+        DROPPED_RECORDS.fetch_add(dropped, Ordering::Relaxed);
+        log::warn!("Performance: {dropped} smm performance records dropped, the FBPT is full.");
+    }
+    added
+}
+
 #[coverage(off)]
 // Tested via the generic version, see _create_performance_measurement. This one is using the static state which makes
 // it not mockable.
@@ -241,6 +279,9 @@ pub unsafe extern "efiapi" fn create_performance_measurement(
         }
     }
 
+    static MODULE_GUID_CACHE: ModuleGuidCache = ModuleGuidCache::new();
+    static MODULE_NAME_CACHE: ModuleNameCache = ModuleNameCache::new();
+
     match _create_performance_measurement(
         caller_identifier,
         guid,
@@ -251,13 +292,13 @@ pub unsafe extern "efiapi" fn create_performance_measurement(
         attribute,
         boot_services,
         fbpt,
+        &MODULE_GUID_CACHE,
+        &MODULE_NAME_CACHE,
     ) {
         Ok(_) => efi::Status::SUCCESS,
         Err(Error::OutOfResources) => {
-            static HAS_BEEN_LOGGED: AtomicBool = AtomicBool::new(false);
-            if HAS_BEEN_LOGGED.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok() {
-                log::info!("Performance: FBPT is full, can't add more performance records !");
-            };
+            static HAS_BEEN_LOGGED: BootOnce = BootOnce::new();
+            HAS_BEEN_LOGGED.run(|| log::info!("Performance: FBPT is full, can't add more performance records !"));
             efi::Status::OUT_OF_RESOURCES
         }
         Err(Error::Efi(status_code)) => {
@@ -273,6 +314,69 @@ pub unsafe extern "efiapi" fn create_performance_measurement(
     }
 }
 
+/// Creates a performance measurement for `caller`, as [`create_performance_measurement`] does for raw pointers.
+///
+/// The measurement is named by `token`, with `None` or an empty token leaving it unnamed. A `ticker` of 0 records the
+/// current time, and 1 records a time of 0.
+#[coverage(off)]
+// Tested via the generic version, see _measure.
+pub fn measure(
+    caller: &efi::Guid,
+    token: Option<&str>,
+    ticker: u64,
+    attribute: PerfAttribute,
+    identifier: u32,
+) -> Result<(), EfiError> {
+    _measure(caller, token, ticker, attribute, identifier, create_performance_measurement)
+}
+
+fn _measure(
+    caller: &efi::Guid,
+    token: Option<&str>,
+    ticker: u64,
+    attribute: PerfAttribute,
+    identifier: u32,
+    create_performance_measurement: CreateMeasurement,
+) -> Result<(), EfiError> {
+    let caller = ptr::from_ref(caller) as *const c_void;
+    let status = with_c_str(token.unwrap_or_default(), |token| {
+        // SAFETY: `token` is a valid C string or null, and `caller` points to a GUID.
+        unsafe { create_performance_measurement(caller, None, token, ticker, 0, identifier, attribute) }
+    });
+    EfiError::status_to_result(status)
+}
+
+/// Calls `f` with each performance record added to the FBPT, in the order they were added.
+///
+/// The records are copied out of the FBPT before `f` is called, so the FBPT is only locked briefly and `f` may itself
+/// create performance measurements, which are not visited.
+///
+/// ## Errors
+///
+/// Returns [`EfiError::NotStarted`] if performance measurements are not enabled, and [`EfiError::NotReady`] if the
+/// FBPT is locked, as when called while a measurement is being recorded.
+#[coverage(off)]
+// Tested via the generic version, see _with_fbpt_records.
+pub fn with_fbpt_records<F: FnMut(&GenericPerformanceRecord<&[u8]>)>(f: F) -> Result<(), EfiError> {
+    let Some((_, fbpt)) = get_static_state() else {
+        return Err(EfiError::NotStarted);
+    };
+    _with_fbpt_records(fbpt, f)
+}
+
+fn _with_fbpt_records<B, F>(
+    fbpt: &'static TplMutex<'static, F, B>,
+    mut f: impl FnMut(&GenericPerformanceRecord<&[u8]>),
+) -> Result<(), EfiError>
+where
+    B: BootServices,
+    F: FirmwareBasicBootPerfTable,
+{
+    let records = fbpt.try_lock().map_err(|_| EfiError::NotReady)?.perf_records().buffer().to_vec();
+    Iter::new(&records).for_each(|record| f(&record));
+    Ok(())
+}
+
 /// Create a performance measurement and add it to the FBPT.
 #[allow(clippy::too_many_arguments)]
 fn _create_performance_measurement<B, F>(
@@ -285,16 +389,17 @@ fn _create_performance_measurement<B, F>(
     attribute: PerfAttribute,
     boot_services: &B,
     fbpt: &TplMutex<'static, F, B>,
+    module_guid_cache: &ModuleGuidCache,
+    module_name_cache: &ModuleNameCache,
 ) -> Result<(), Error>
 where
     B: BootServices,
     F: FirmwareBasicBootPerfTable,
 {
-    let cpu_count = Arch::cpu_count();
     let timestamp = match ticker {
-        0 => (cpu_count as f64 / Arch::perf_frequency() as f64 * 1_000_000_000_f64) as u64,
+        0 => ticks_to_nanoseconds(Arch::cpu_count(), get_perf_frequency()),
         1 => 0,
-        ticker => (ticker as f64 / Arch::perf_frequency() as f64 * 1_000_000_000_f64) as u64,
+        ticker => ticks_to_nanoseconds(ticker, get_perf_frequency()),
     };
 
     let Ok(known_perf_id) = KnownPerfId::try_from(perf_id) else {
@@ -302,9 +407,23 @@ where
             return Err(EfiError::InvalidParameter.into());
         }
         // SAFETY: The caller of parent function `create_performance_measurement` ensures that `caller_identifier` is a valid image handle or GUID pointer.
-        let guid = get_module_guid_from_handle(boot_services, caller_identifier as efi::Handle)
+        let guid = module_guid_cache
+            .get_or_resolve(caller_identifier as efi::Handle, |handle| {
+                get_module_guid_from_handle(boot_services, handle)
+            })
             .unwrap_or_else(|_| unsafe { *(caller_identifier as *const Guid) });
-        let module_name = string.unwrap_or("unknown name");
+        let resolved_name;
+        let module_name = match string {
+            Some(string) => string,
+            None => {
+                resolved_name = module_name_cache
+                    .get_or_resolve(caller_identifier as efi::Handle, |handle| {
+                        get_module_name_from_handle(boot_services, handle).ok_or(())
+                    })
+                    .ok();
+                resolved_name.as_deref().unwrap_or("unknown name")
+            }
+        };
         fbpt.lock().add_record(DynamicStringEventRecord::new(perf_id, 0, timestamp, guid, module_name))?;
         return Ok(());
     };
@@ -312,7 +431,9 @@ where
     match known_perf_id {
         KnownPerfId::ModuleStart | KnownPerfId::ModuleEnd => {
             let module_handle = caller_identifier as efi::Handle;
-            let Ok(guid) = get_module_guid_from_handle(boot_services, module_handle) else {
+            let Ok(guid) = module_guid_cache
+                .get_or_resolve(module_handle, |handle| get_module_guid_from_handle(boot_services, handle))
+            else {
                 log::error!("Performance: Could not find the guid for module handle: {module_handle:?}");
                 return Err(EfiError::InvalidParameter.into());
             };
@@ -323,8 +444,13 @@ where
             if id == KnownPerfId::ModuleLoadImageStart {
                 increment_load_image_count();
             }
+            // A loaded image may be given the handle of one previously unloaded, so resolve it again.
+            module_guid_cache.invalidate();
+            module_name_cache.invalidate();
             let module_handle = caller_identifier as efi::Handle;
-            let Ok(guid) = get_module_guid_from_handle(boot_services, module_handle) else {
+            let Ok(guid) = module_guid_cache
+                .get_or_resolve(module_handle, |handle| get_module_guid_from_handle(boot_services, handle))
+            else {
                 log::error!("Performance: Could not find the guid for module handle: {module_handle:?}");
                 return Err(EfiError::InvalidParameter.into());
             };
@@ -332,26 +458,54 @@ where
             fbpt.lock().add_record(record)?;
         }
         KnownPerfId::ModuleDbStart
-        | KnownPerfId::ModuleDbEnd
         | KnownPerfId::ModuleDbSupportStart
         | KnownPerfId::ModuleDbSupportEnd
         | KnownPerfId::ModuleDbStopStart => {
             let module_handle = caller_identifier as efi::Handle;
-            let Ok(guid) = get_module_guid_from_handle(boot_services, module_handle) else {
+            let Ok(guid) = module_guid_cache
+                .get_or_resolve(module_handle, |handle| get_module_guid_from_handle(boot_services, handle))
+            else {
                 log::error!("Performance: Could not find the guid for module handle: {module_handle:?}");
                 return Err(EfiError::InvalidParameter.into());
             };
             let record = GuidQwordEventRecord::new(perf_id, 0, timestamp, guid, address as u64);
             fbpt.lock().add_record(record)?;
         }
+        KnownPerfId::ModuleDbEnd => {
+            let module_handle = caller_identifier as efi::Handle;
+            let Ok(guid) = module_guid_cache
+                .get_or_resolve(module_handle, |handle| get_module_guid_from_handle(boot_services, handle))
+            else {
+                log::error!("Performance: Could not find the guid for module handle: {module_handle:?}");
+                return Err(EfiError::InvalidParameter.into());
+            };
+            if address == 0 {
+                let record = GuidQwordEventRecord::new(perf_id, 0, timestamp, guid, address as u64);
+                fbpt.lock().add_record(record)?;
+            } else {
+                // As in EDK2, the record for a driver binding start also names the controller it was started on.
+                let controller_handle = address as efi::Handle;
+                let device_path = get_device_path_string_from_handle(boot_services, controller_handle)
+                    .inspect_err(|_| {
+                        log::warn!("Performance: Could not find the device path for controller: {controller_handle:?}")
+                    })
+                    .unwrap_or_default();
+                let record = GuidQwordStringEventRecord::new(perf_id, 0, timestamp, guid, address as u64, &device_path);
+                fbpt.lock().add_record(record)?;
+            }
+        }
         KnownPerfId::ModuleDbStopEnd => {
             let module_handle = caller_identifier as efi::Handle;
-            let Ok(guid) = get_module_guid_from_handle(boot_services, module_handle) else {
+            let Ok(guid) = module_guid_cache
+                .get_or_resolve(module_handle, |handle| get_module_guid_from_handle(boot_services, handle))
+            else {
                 log::error!("Performance Lib: Could not find the guid for module handle: {module_handle:?}");
                 return Err(EfiError::InvalidParameter.into());
             };
-            let module_name = "";
-            let record = GuidQwordStringEventRecord::new(perf_id, 0, timestamp, guid, address as u64, module_name);
+            let module_name = module_name_cache
+                .get_or_resolve(module_handle, |handle| get_module_name_from_handle(boot_services, handle).ok_or(()))
+                .unwrap_or_default();
+            let record = GuidQwordStringEventRecord::new(perf_id, 0, timestamp, guid, address as u64, &module_name);
             fbpt.lock().add_record(record)?;
         }
         KnownPerfId::PerfEventSignalStart
@@ -382,10 +536,27 @@ where
             let record = DynamicStringEventRecord::new(perf_id, 0, timestamp, module_guid, string);
             fbpt.lock().add_record(record)?;
         }
+        KnownPerfId::PerfTaggedEvent => {
+            // SAFETY: On this usecase, caller identifier is a guid, as for the other string events.
+            let module_guid = unsafe { *(caller_identifier as *const efi::Guid) };
+            let string = string.unwrap_or("unknown name");
+            let record = TaggedEventRecord::new(perf_id, 0, timestamp, module_guid, address as u64, string);
+            fbpt.lock().add_record(record)?;
+        }
     }
     Ok(())
 }
 
+/// Converts timer ticks to nanoseconds at `frequency` Hz, saturating at `u64::MAX`.
+///
+/// Without a usable frequency the ticks are returned unconverted, so the records still order the measurements.
+fn ticks_to_nanoseconds(ticks: u64, frequency: Option<u64>) -> u64 {
+    match frequency.and_then(|frequency| (ticks as u128 * 1_000_000_000).checked_div(frequency as u128)) {
+        Some(nanoseconds) => u64::try_from(nanoseconds).unwrap_or(u64::MAX),
+        None => ticks,
+    }
+}
+
 /// Measurement enum that represents the different performance measurements that can be enabled.
 #[derive(Debug, PartialEq)]
 #[repr(u32)]
@@ -442,6 +613,11 @@ impl BitOr<Measurement> for u32 {
     }
 }
 
+/// The configuration table entry for the [`PerformanceProperty`] table.
+// SAFETY: `PerformanceProperty` is the table type associated with `PERFORMANCE_PROTOCOL`.
+pub const PERFORMANCE_PROPERTY_TABLE: ConfigTableEntry<PerformanceProperty> =
+    unsafe { ConfigTableEntry::new(PERFORMANCE_PROTOCOL) };
+
 /// Performance property structure used to store performance related properties.
 #[repr(C)]
 pub struct PerformanceProperty {
@@ -473,66 +649,216 @@ impl PerformanceProperty {
     }
 }
 
+/// Caches what is resolved about the module of a handle, so repeated records for a module don't repeat its protocol
+/// lookups.
+///
+/// Handles may be reused once an image is unloaded, so the cache is invalidated whenever an image is loaded. Entries
+/// are only cached when the lookup resolves the module, and the cache is bypassed if it is already in use at a lower
+/// TPL.
+struct ModuleCache<T> {
+    busy: AtomicBool,
+    invalidated: AtomicBool,
+    is_resolved: fn(&T) -> bool,
+    entries: UnsafeCell<BTreeMap<usize, T>>,
+}
+
+/// Caches the module GUID of a handle. A zero GUID, returned for a module not loaded from a firmware volume, is not
+/// cached.
+type ModuleGuidCache = ModuleCache<efi::Guid>;
+
+/// Caches the module name of a handle, from the UI section of its firmware file.
+type ModuleNameCache = ModuleCache<String>;
+
+// SAFETY: `entries` is only accessed by the holder of `busy`.
+unsafe impl<T: Send> Sync for ModuleCache<T> {}
+
+impl ModuleGuidCache {
+    const fn new() -> Self {
+        Self::with_resolved(|guid| *guid != crate::guids::ZERO)
+    }
+}
+
+impl ModuleNameCache {
+    const fn new() -> Self {
+        Self::with_resolved(|_| true)
+    }
+}
+
+impl<T: Clone> ModuleCache<T> {
+    const fn with_resolved(is_resolved: fn(&T) -> bool) -> Self {
+        Self {
+            busy: AtomicBool::new(false),
+            invalidated: AtomicBool::new(false),
+            is_resolved,
+            entries: UnsafeCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the cached entry for `handle`, or resolves it with `resolve`.
+    fn get_or_resolve<E>(
+        &self,
+        handle: efi::Handle,
+        resolve: impl FnOnce(efi::Handle) -> Result<T, E>,
+    ) -> Result<T, E> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            return resolve(handle);
+        }
+
+        // SAFETY: `busy` is held, so this is the only reference to the entries.
+        let entries = unsafe { &mut *self.entries.get() };
+        if self.invalidated.swap(false, Ordering::Relaxed) {
+            entries.clear();
+        }
+
+        let result = match entries.get(&(handle as usize)) {
+            Some(entry) => Ok(entry.clone()),
+            None => resolve(handle).inspect(|entry| {
+                if (self.is_resolved)(entry) {
+                    entries.insert(handle as usize, entry.clone());
+                }
+            }),
+        };
+
+        self.busy.store(false, Ordering::Release);
+        result
+    }
+
+    /// Discards all cached entries.
+    fn invalidate(&self) {
+        self.invalidated.store(true, Ordering::Relaxed);
+    }
+}
+
 fn get_module_guid_from_handle(
     boot_services: &impl BootServices,
     handle: efi::Handle,
 ) -> Result<efi::Guid, efi::Status> {
-    let mut guid = efi::Guid::from_fields(0, 0, 0, 0, 0, &[0; 6]);
+    let file_name = match find_loaded_image(boot_services, handle) {
+        Some(loaded_image) => get_fv_file_name(loaded_image)?,
+        None => None,
+    };
+    Ok(file_name.unwrap_or(efi::Guid::from_fields(0, 0, 0, 0, 0, &[0; 6])))
+}
+
+/// Returns the name of the module `handle` belongs to, from the UI section of its firmware file.
+///
+/// Returns `None` if the module was not loaded from a firmware volume, or if its file has no UI section.
+fn get_module_name_from_handle(boot_services: &impl BootServices, handle: efi::Handle) -> Option<String> {
+    let loaded_image = find_loaded_image(boot_services, handle)?;
+    let file_name = get_fv_file_name(loaded_image).ok()??;
+    // SAFETY: `firmware_volume` is the only reference to the `firmware_volume::Protocol` in this scope.
+    let firmware_volume =
+        unsafe { boot_services.handle_protocol::<firmware_volume::Protocol>(loaded_image.device_handle) }.ok()?;
+
+    let mut buffer = ptr::null_mut();
+    let mut size = 0;
+    let mut authentication_status = 0;
+    let status = (firmware_volume.read_section)(
+        firmware_volume,
+        &file_name,
+        section::raw_type::USER_INTERFACE,
+        0,
+        &mut buffer,
+        &mut size,
+        &mut authentication_status,
+    );
+    if status.is_error() || buffer.is_null() {
+        return None;
+    }
 
-    let loaded_image_protocol = 'find_loaded_image_protocol: {
-        // SAFETY: `loaded_image_protocol` is the only reference to the `loaded_image::Protocol` in this scope.
-        if let Ok(loaded_image_protocol) =
-            unsafe { boot_services.handle_protocol::<efi::protocols::loaded_image::Protocol>(handle) }
+    // SAFETY: The firmware volume returns the section data, a null terminated UCS-2 string, in a pool buffer of
+    // `size` bytes, freed below.
+    let name = char::decode_utf16(
+        unsafe { slice::from_raw_parts(buffer as *const u16, size / mem::size_of::<u16>()) }.iter().copied(),
+    )
+    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    .take_while(|&c| c != '\0')
+    .collect::<String>();
+    let _ = boot_services.free_pool(buffer as *mut u8);
+    (!name.is_empty()).then_some(name)
+}
+
+fn find_loaded_image(
+    boot_services: &impl BootServices,
+    handle: efi::Handle,
+) -> Option<&'static mut efi::protocols::loaded_image::Protocol> {
+    // SAFETY: `loaded_image_protocol` is the only reference to the `loaded_image::Protocol` in this scope.
+    if let Ok(loaded_image_protocol) =
+        unsafe { boot_services.handle_protocol::<efi::protocols::loaded_image::Protocol>(handle) }
+    {
+        return Some(loaded_image_protocol);
+    }
+
+    // SAFETY: `driver_binding_protocol` is the only reference to the `driver_binding::Protocol` in this scope.
+    unsafe {
+        if let Ok(driver_binding_protocol) = boot_services.open_protocol::<efi::protocols::driver_binding::Protocol>(
+            handle,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            efi::OPEN_PROTOCOL_GET_PROTOCOL,
+        ) && let Ok(loaded_image_protocol) = boot_services
+            .handle_protocol::<efi::protocols::loaded_image::Protocol>(driver_binding_protocol.image_handle)
         {
-            break 'find_loaded_image_protocol Some(loaded_image_protocol);
+            return Some(loaded_image_protocol);
         }
+    }
+    None
+}
 
-        // SAFETY: `driver_binding_protocol` is the only reference to the `driver_binding::Protocol` in this scope.
-        unsafe {
-            if let Ok(driver_binding_protocol) = boot_services
-                .open_protocol::<efi::protocols::driver_binding::Protocol>(
-                    handle,
-                    ptr::null_mut(),
-                    ptr::null_mut(),
-                    efi::OPEN_PROTOCOL_GET_PROTOCOL,
-                )
-                && let Ok(loaded_image_protocol) = boot_services
-                    .handle_protocol::<efi::protocols::loaded_image::Protocol>(driver_binding_protocol.image_handle)
-            {
-                break 'find_loaded_image_protocol Some(loaded_image_protocol);
-            }
-        }
-        None
+/// Returns the name of the firmware file the image was loaded from, or `None` if it was not loaded from a firmware
+/// volume.
+fn get_fv_file_name(loaded_image: &efi::protocols::loaded_image::Protocol) -> Result<Option<efi::Guid>, efi::Status> {
+    // SAFETY: File path is a pointer from C that is valid and of type Device Path (efi).
+    let Some(file_path) = (unsafe { loaded_image.file_path.as_ref() }) else {
+        return Ok(None);
     };
+    if file_path.r#type != TYPE_MEDIA || file_path.sub_type != Media::SUBTYPE_PIWG_FIRMWARE_FILE {
+        return Ok(None);
+    }
 
-    if let Some(loaded_image) = loaded_image_protocol {
-        // SAFETY: File path is a pointer from C that is valid and of type Device Path (efi).
-        if let Some(file_path) = unsafe { loaded_image.file_path.as_ref() }
-            && file_path.r#type == TYPE_MEDIA
-            && file_path.sub_type == Media::SUBTYPE_PIWG_FIRMWARE_FILE
-        {
-            // The layout of MEDIA_FW_VOL_FILEPATH_DEVICE_PATH in memory is { Protocol (header) | Guid (file name) }.
-            let node_len = u16::from_le_bytes(file_path.length);
-            let expected_len =
-                (mem::size_of::<efi::protocols::device_path::Protocol>() + mem::size_of::<efi::Guid>()) as u16;
-
-            // Sanity check that the header matches the expected size.
-            if node_len != expected_len {
-                return Err(efi::Status::NOT_FOUND);
-            }
+    // The layout of MEDIA_FW_VOL_FILEPATH_DEVICE_PATH in memory is { Protocol (header) | Guid (file name) }.
+    let node_len = u16::from_le_bytes(file_path.length);
+    let expected_len = (mem::size_of::<efi::protocols::device_path::Protocol>() + mem::size_of::<efi::Guid>()) as u16;
 
-            // SAFETY: To be honest there is no way to guarantee the memory read here is valid and owned by us,
-            // but we have at least validated that the type gives a known layout and the layout of the device path matches its claimed length.
-            unsafe {
-                let guid_ptr = (loaded_image.file_path as *const u8)
-                    .add(mem::size_of::<efi::protocols::device_path::Protocol>())
-                    as *const efi::Guid;
-                guid = ptr::read(guid_ptr);
-            }
-        };
+    // Sanity check that the header matches the expected size.
+    if node_len != expected_len {
+        return Err(efi::Status::NOT_FOUND);
     }
 
-    Ok(guid)
+    // SAFETY: To be honest there is no way to guarantee the memory read here is valid and owned by us,
+    // but we have at least validated that the type gives a known layout and the layout of the device path matches its
+    // claimed length.
+    unsafe {
+        let guid_ptr = (loaded_image.file_path as *const u8)
+            .add(mem::size_of::<efi::protocols::device_path::Protocol>()) as *const efi::Guid;
+        Ok(Some(ptr::read(guid_ptr)))
+    }
+}
+
+fn get_device_path_string_from_handle(
+    boot_services: &impl BootServices,
+    handle: efi::Handle,
+) -> Result<String, efi::Status> {
+    // SAFETY: `device_path` is the only reference to the `device_path::Protocol` in this scope.
+    let device_path = unsafe { boot_services.handle_protocol::<efi::protocols::device_path::Protocol>(handle)? };
+    // SAFETY: `device_path_to_text` is the only reference to the `device_path_to_text::Protocol` in this scope.
+    let device_path_to_text =
+        unsafe { boot_services.locate_protocol::<efi::protocols::device_path_to_text::Protocol>(None)? };
+
+    let text = (device_path_to_text.convert_device_path_to_text)(device_path, efi::Boolean::TRUE, efi::Boolean::FALSE);
+    if text.is_null() {
+        return Err(efi::Status::OUT_OF_RESOURCES);
+    }
+
+    // SAFETY: The protocol returns a null terminated UCS-2 string allocated from pool, which is freed below.
+    let string = unsafe {
+        let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+        char::decode_utf16(core::slice::from_raw_parts(text, len).iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<String>()
+    };
+    let _ = boot_services.free_pool(text as *mut u8);
+    Ok(string)
 }
 
 /// This device path is used by systems implementing the UEFI PI Specification 1.0 to describe a firmware file.
@@ -549,20 +875,129 @@ mod tests {
     use super::*;
 
     use alloc::rc::Rc;
-    use core::{mem::MaybeUninit, ptr};
+    use core::{cell::Cell, mem::MaybeUninit, ptr};
 
     use mockall::predicate;
 
     use crate::{
         boot_services::{MockBootServices, c_ptr::CMutPtr, tpl::Tpl},
         performance::{
-            globals::set_perf_measurement_mask,
+            globals::{get_perf_frequency, set_perf_frequency, set_perf_measurement_mask},
             logging::*,
-            table::{FirmwarePerformanceVariable, MockFirmwareBasicBootPerfTable},
+            record::{PerformanceRecord, PerformanceRecordBuffer},
+            table::{FBPT, FirmwarePerformanceVariable, MockFirmwareBasicBootPerfTable},
         },
         runtime_services::MockRuntimeServices,
     };
 
+    /// The boot services and FBPT that [`fbpt_create_performance_measurement`] records into on the current thread.
+    struct FbptMeasurement {
+        boot_services: &'static MockBootServices,
+        fbpt: &'static TplMutex<'static, FBPT, MockBootServices>,
+        module_guid_cache: ModuleGuidCache,
+        module_name_cache: ModuleNameCache,
+    }
+
+    // Each test runs on its own thread, so tests recording performance measurements do not share an FBPT.
+    std::thread_local! {
+        static FBPT_MEASUREMENT: Cell<Option<&'static FbptMeasurement>> = const { Cell::new(None) };
+    }
+
+    /// Serializes the tests that depend on the global performance timer frequency.
+    static PERF_FREQUENCY_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// Holds [`PERF_FREQUENCY_LOCK`], restoring the timer frequency it was taken with when dropped.
+    struct PerfFrequencyGuard {
+        frequency: Option<u64>,
+        _lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl PerfFrequencyGuard {
+        fn lock() -> Self {
+            let lock = PERF_FREQUENCY_LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            Self { frequency: get_perf_frequency(), _lock: lock }
+        }
+    }
+
+    impl Drop for PerfFrequencyGuard {
+        fn drop(&mut self) {
+            set_perf_frequency(self.frequency.unwrap_or(0));
+        }
+    }
+
+    /// Returns a new FBPT that [`fbpt_create_performance_measurement`] records into on the current thread, resolving
+    /// modules with `boot_services`.
+    fn set_up_fbpt_measurement(
+        mut boot_services: MockBootServices,
+    ) -> &'static TplMutex<'static, FBPT, MockBootServices> {
+        boot_services.expect_raise_tpl().returning(|tpl| tpl);
+        boot_services.expect_restore_tpl().return_const(());
+        let boot_services: &'static MockBootServices = Box::leak(Box::new(boot_services));
+        let fbpt = Box::leak(Box::new(TplMutex::new(boot_services, Tpl::NOTIFY, FBPT::new())));
+        FBPT_MEASUREMENT.set(Some(Box::leak(Box::new(FbptMeasurement {
+            boot_services,
+            fbpt,
+            module_guid_cache: ModuleGuidCache::new(),
+            module_name_cache: ModuleNameCache::new(),
+        }))));
+        fbpt
+    }
+
+    /// A [`CreateMeasurement`] recording into the FBPT set up by [`set_up_fbpt_measurement`].
+    extern "efiapi" fn fbpt_create_performance_measurement(
+        caller_identifier: *const c_void,
+        guid: Option<&efi::Guid>,
+        string: *const c_char,
+        ticker: u64,
+        address: usize,
+        identifier: u32,
+        attribute: PerfAttribute,
+    ) -> efi::Status {
+        let measurement = FBPT_MEASUREMENT.get().expect("The FBPT measurement is not set up on this thread.");
+        let string = unsafe { string.as_ref().map(|s| CStr::from_ptr(s).to_str().unwrap().to_string()) };
+        match _create_performance_measurement(
+            caller_identifier,
+            guid,
+            string.as_deref(),
+            ticker,
+            address,
+            identifier as u16,
+            attribute,
+            measurement.boot_services,
+            measurement.fbpt,
+            &measurement.module_guid_cache,
+            &measurement.module_name_cache,
+        ) {
+            Ok(()) => efi::Status::SUCCESS,
+            Err(_) => efi::Status::INVALID_PARAMETER,
+        }
+    }
+
+    /// Returns a `handle_protocol` implementation resolving any handle to the loaded image of the firmware file
+    /// `file_name`, loaded from the firmware volume on `device_handle`.
+    fn fw_vol_loaded_image(
+        device_handle: efi::Handle,
+        file_name: efi::Guid,
+    ) -> impl FnMut(efi::Handle) -> Result<&'static mut efi::protocols::loaded_image::Protocol, efi::Status> + Send
+    {
+        let file_path = Box::leak(Box::new(MediaFwVolFilepathDevicePath {
+            header: efi::protocols::device_path::Protocol {
+                r#type: TYPE_MEDIA,
+                sub_type: Media::SUBTYPE_PIWG_FIRMWARE_FILE,
+                length: (mem::size_of::<MediaFwVolFilepathDevicePath>() as u16).to_le_bytes(),
+            },
+            fv_file_name: file_name,
+        }));
+        let loaded_image = Box::leak(Box::new(MaybeUninit::<efi::protocols::loaded_image::Protocol>::zeroed()));
+        unsafe {
+            loaded_image.assume_init_mut().device_handle = device_handle;
+            loaded_image.assume_init_mut().file_path =
+                file_path as *mut MediaFwVolFilepathDevicePath as *mut efi::protocols::device_path::Protocol;
+        }
+        let loaded_image_address = loaded_image.as_mut_ptr() as usize;
+        move |_| unsafe { Ok((loaded_image_address as *mut efi::protocols::loaded_image::Protocol).as_mut().unwrap()) }
+    }
+
     #[test]
     fn test_report_fbpt_record_buffer() {
         static REPORT_STATUS_CODE_CALLED: AtomicBool = AtomicBool::new(false);
@@ -622,104 +1057,501 @@ mod tests {
     fn test_create_performance_measurement() {
         set_perf_measurement_mask(u32::MAX);
         let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .returning(fw_vol_loaded_image(ptr::null_mut(), efi::Guid::from_bytes(&[3; 16])));
+        boot_services
+            .expect_handle_protocol::<efi::protocols::device_path::Protocol>()
+            .returning(|_| Err(efi::Status::UNSUPPORTED));
+        boot_services
+            .expect_handle_protocol::<firmware_volume::Protocol>()
+            .returning(|_| Err(efi::Status::UNSUPPORTED));
+        let fbpt = set_up_fbpt_measurement(boot_services);
 
-        let mut loaded_image_protocol = MaybeUninit::<efi::protocols::loaded_image::Protocol>::zeroed();
-        let mut media_fw_vol_file_path_device_path = MaybeUninit::<MediaFwVolFilepathDevicePath>::zeroed();
-        unsafe {
-            media_fw_vol_file_path_device_path.assume_init_mut().header.r#type = TYPE_MEDIA;
-            media_fw_vol_file_path_device_path.assume_init_mut().header.sub_type = Media::SUBTYPE_PIWG_FIRMWARE_FILE;
-            media_fw_vol_file_path_device_path.assume_init_mut().header.length =
-                (mem::size_of::<MediaFwVolFilepathDevicePath>() as u16).to_le_bytes();
-            media_fw_vol_file_path_device_path.assume_init_mut().fv_file_name = efi::Guid::from_bytes(&[3; 16]);
+        // These functions call create_performance_measurement with the right arguments.
+        let module_handle = 1_usize as efi::Handle;
+        let controller_handle = 2_usize as efi::Handle;
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        let trigger_guid = efi::Guid::from_bytes(&[2; 16]);
+        let event_guid = efi::Guid::from_bytes(&[3; 16]);
+
+        perf_image_start_begin(module_handle, fbpt_create_performance_measurement);
+        perf_image_start_end(module_handle, fbpt_create_performance_measurement);
+
+        perf_load_image_begin(module_handle, fbpt_create_performance_measurement);
+        perf_load_image_end(module_handle, fbpt_create_performance_measurement);
+
+        perf_driver_binding_support_begin(module_handle, controller_handle, fbpt_create_performance_measurement);
+        perf_driver_binding_support_end(module_handle, controller_handle, fbpt_create_performance_measurement);
+
+        perf_driver_binding_start_begin(module_handle, controller_handle, fbpt_create_performance_measurement);
+        perf_driver_binding_start_end(module_handle, controller_handle, fbpt_create_performance_measurement);
+
+        perf_driver_binding_stop_begin(module_handle, controller_handle, fbpt_create_performance_measurement);
+        perf_driver_binding_stop_end(module_handle, controller_handle, fbpt_create_performance_measurement);
+
+        perf_event("event_string", &caller_id, fbpt_create_performance_measurement);
+
+        perf_event_signal_begin(&event_guid, "fun_name", &caller_id, fbpt_create_performance_measurement);
+        perf_event_signal_end(&event_guid, "fun_name", &caller_id, fbpt_create_performance_measurement);
+
+        perf_callback_begin(&trigger_guid, "fun_name", &caller_id, fbpt_create_performance_measurement);
+        perf_callback_end(&trigger_guid, "fun_name", &caller_id, fbpt_create_performance_measurement);
+
+        perf_function_begin("fun_name", &caller_id, fbpt_create_performance_measurement);
+        perf_function_end("fun_name", &caller_id, fbpt_create_performance_measurement);
+
+        perf_in_module_begin("measurement_str", &caller_id, fbpt_create_performance_measurement);
+        perf_in_module_end("measurement_str", &caller_id, fbpt_create_performance_measurement);
+
+        perf_cross_module_begin("measurement_str", &caller_id, fbpt_create_performance_measurement);
+        perf_cross_module_end("measurement_str", &caller_id, fbpt_create_performance_measurement);
+
+        assert_eq!(fbpt.lock().perf_records().iter().count(), 21);
+    }
+
+    #[test]
+    fn test_tagged_event_is_decoded_from_fbpt() {
+        let fbpt = set_up_fbpt_measurement(MockBootServices::new());
 
-            loaded_image_protocol.assume_init_mut().file_path =
-                media_fw_vol_file_path_device_path.as_mut_ptr() as *mut efi::protocols::device_path::Protocol;
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        crate::perf_tagged!("transaction", 0x1234_5678_9ABC_DEF0, &caller_id, fbpt_create_performance_measurement);
+
+        let fbpt = fbpt.lock();
+        let records = fbpt.perf_records().iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            (records[0].record_type, records[0].revision),
+            (TaggedEventRecord::TYPE, TaggedEventRecord::REVISION)
+        );
+
+        let record = TaggedEventRecord::try_from(&records[0]).unwrap();
+        assert_eq!(record.progress_id, KnownPerfId::PerfTaggedEvent.as_u16());
+        assert_eq!(record.guid, caller_id);
+        assert_eq!(record.context, 0x1234_5678_9ABC_DEF0);
+        assert_eq!(record.string, "transaction");
+    }
+
+    #[test]
+    fn test_perf_start_end_str_record_the_same_as_c_strings() {
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .returning(|_| Err(efi::Status::UNSUPPORTED));
+        let fbpt = set_up_fbpt_measurement(boot_services);
+
+        // The handle does not resolve to a module, so the records name it by the GUID it points to.
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        let handle = &caller_id as *const efi::Guid as efi::Handle;
+        let long_token = "a_token_long_enough_that_it_does_not_fit_in_the_buffer_on_the_stack";
+
+        perf_start(handle, c"token".as_ptr(), c"module".as_ptr(), 1, fbpt_create_performance_measurement);
+        perf_end(handle, ptr::null(), c"module".as_ptr(), 1, fbpt_create_performance_measurement);
+        let long_token_c = alloc::ffi::CString::new(long_token).unwrap();
+        perf_start(handle, long_token_c.as_ptr(), ptr::null(), 1, fbpt_create_performance_measurement);
+
+        perf_start_str(handle, "token", "module", 1, fbpt_create_performance_measurement);
+        perf_end_str(handle, "", "module", 1, fbpt_create_performance_measurement);
+        perf_start_str(handle, long_token, "", 1, fbpt_create_performance_measurement);
+
+        let fbpt = fbpt.lock();
+        let records =
+            fbpt.perf_records().iter().map(|r| (r.record_type, r.revision, r.data.to_vec())).collect::<Vec<_>>();
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[..3], records[3..]);
+        assert_eq!(records[0].0, DynamicStringEventRecord::TYPE);
+    }
+
+    #[test]
+    fn test_measure_records_the_same_as_the_raw_call() {
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .returning(|_| Err(efi::Status::UNSUPPORTED));
+        boot_services
+            .expect_open_protocol::<efi::protocols::driver_binding::Protocol>()
+            .returning(|_, _, _, _| Err(efi::Status::UNSUPPORTED));
+        let fbpt = set_up_fbpt_measurement(boot_services);
+
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        let caller = &caller_id as *const efi::Guid as *const c_void;
+
+        let status = fbpt_create_performance_measurement(
+            caller,
+            None,
+            c"custom".as_ptr(),
+            1,
+            0,
+            0x1230,
+            PerfAttribute::PerfStartEntry,
+        );
+        assert_eq!(status, efi::Status::SUCCESS);
+        _measure(
+            &caller_id,
+            Some("custom"),
+            1,
+            PerfAttribute::PerfStartEntry,
+            0x1230,
+            fbpt_create_performance_measurement,
+        )
+        .unwrap();
+
+        // An event needs a known identifier, and the failure is reported.
+        assert_eq!(
+            _measure(&caller_id, None, 1, PerfAttribute::PerfEntry, 0x1230, fbpt_create_performance_measurement),
+            Err(EfiError::InvalidParameter)
+        );
+
+        let fbpt = fbpt.lock();
+        let records =
+            fbpt.perf_records().iter().map(|r| (r.record_type, r.revision, r.data.to_vec())).collect::<Vec<_>>();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], records[1]);
+        assert_eq!(records[0].0, DynamicStringEventRecord::TYPE);
+    }
+
+    #[test]
+    fn test_module_name_is_read_from_firmware_volume_file() {
+        const FV_HANDLE: usize = 5;
+        static FILE_NAME: efi::Guid = efi::Guid::from_fields(4, 0, 0, 0, 0, &[4; 6]);
+
+        extern "efiapi" fn read_section(
+            _this: *const firmware_volume::Protocol,
+            name: *const efi::Guid,
+            section_type: u8,
+            section_instance: usize,
+            buffer: *mut *mut c_void,
+            buffer_size: *mut usize,
+            _authentication_status: *mut u32,
+        ) -> efi::Status {
+            if unsafe { *name } != FILE_NAME
+                || section_type != section::raw_type::USER_INTERFACE
+                || section_instance != 0
+            {
+                return efi::Status::NOT_FOUND;
+            }
+            let name = "FileSystemDxe".encode_utf16().chain([0]).collect::<Vec<_>>().leak();
+            unsafe {
+                *buffer = name.as_mut_ptr() as *mut c_void;
+                *buffer_size = mem::size_of_val(name);
+            }
+            efi::Status::SUCCESS
         }
-        let loaded_image_protocol_address = loaded_image_protocol.as_mut_ptr() as usize;
+        let mut firmware_volume = MaybeUninit::<firmware_volume::Protocol>::zeroed();
+        unsafe { ptr::addr_of_mut!((*firmware_volume.as_mut_ptr()).read_section).write(read_section) };
+        let firmware_volume_address = firmware_volume.as_mut_ptr() as usize;
 
-        boot_services.expect_handle_protocol::<efi::protocols::loaded_image::Protocol>().returning(move |_| unsafe {
-            Ok((loaded_image_protocol_address as *mut efi::protocols::loaded_image::Protocol).as_mut().unwrap())
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .returning(fw_vol_loaded_image(FV_HANDLE as efi::Handle, FILE_NAME));
+        boot_services.expect_handle_protocol::<firmware_volume::Protocol>().returning(move |handle| {
+            assert_eq!(handle as usize, FV_HANDLE);
+            unsafe { Ok((firmware_volume_address as *mut firmware_volume::Protocol).as_mut().unwrap()) }
         });
+        boot_services.expect_free_pool().once().returning(|_| Ok(()));
+
+        // The name is only read from the firmware volume once.
+        let cache = ModuleNameCache::new();
+        for _ in 0..2 {
+            let name = cache.get_or_resolve(1_usize as efi::Handle, |handle| {
+                get_module_name_from_handle(&boot_services, handle).ok_or(())
+            });
+            assert_eq!(name.as_deref(), Ok("FileSystemDxe"));
+        }
+    }
+
+    #[test]
+    fn test_with_fbpt_records_visits_records_in_insertion_order() {
+        let mut boot_services = MockBootServices::new();
         boot_services.expect_raise_tpl().returning(|tpl| tpl);
         boot_services.expect_restore_tpl().return_const(());
+        let boot_services: &'static MockBootServices = Box::leak(Box::new(boot_services));
+        let fbpt: &'static TplMutex<'static, FBPT, MockBootServices> =
+            Box::leak(Box::new(TplMutex::new(boot_services, Tpl::NOTIFY, FBPT::new())));
 
-        let mut fbpt = MockFirmwareBasicBootPerfTable::new();
-        fbpt.expect_add_record().times(EXPECTED_NUMBER_OF_RECORD).returning(|_| Ok(()));
-        let fbpt = TplMutex::new(unsafe { &*ptr::addr_of!(boot_services) }, Tpl::NOTIFY, fbpt);
+        let guid = efi::Guid::from_bytes(&[1; 16]);
+        for name in ["first", "second", "third"] {
+            fbpt.lock().add_record(DynamicStringEventRecord::new(0x10, 0, 0, guid, name)).unwrap();
+        }
+
+        let mut names = Vec::new();
+        _with_fbpt_records(fbpt, |record| {
+            assert_eq!(record.record_type, DynamicStringEventRecord::TYPE);
+            names.push(CStr::from_bytes_until_nul(&record.data[30..]).unwrap().to_str().unwrap().to_string());
+            // Records added while visiting are kept, but not visited.
+            fbpt.lock().add_record(DynamicStringEventRecord::new(0x10, 0, 0, guid, "nested")).unwrap();
+        })
+        .unwrap();
+        assert_eq!(names, ["first", "second", "third"]);
+        assert_eq!(fbpt.lock().perf_records().iter().count(), 6);
+
+        let _guard = fbpt.lock();
+        assert_eq!(_with_fbpt_records(fbpt, |_| panic!("The FBPT is locked.")), Err(EfiError::NotReady));
+    }
+
+    #[test]
+    fn test_mm_records_the_fbpt_cannot_hold_are_dropped() {
+        let guid = efi::Guid::from_bytes(&[1; 16]);
+        let mut mm_records = PerformanceRecordBuffer::new();
+        for id in 0..3 {
+            mm_records.push_record(GuidEventRecord::new(id, 0, 0, guid)).unwrap();
+        }
+
+        // The published table only has room for two more records.
+        let record_size = GuidEventRecord::new(0, 0, 0, guid).record_size();
+        let table_buffer = Box::leak(vec![0_u8; 2 * record_size].into_boxed_slice());
+        let mut fbpt = FBPT::new();
+        fbpt.set_perf_records(PerformanceRecordBuffer::Published(table_buffer, 0));
+
+        let dropped_before = dropped_record_count();
+        assert_eq!(add_mm_performance_records(&mut fbpt, mm_records.buffer()), 2);
+        assert_eq!(dropped_record_count() - dropped_before, 1);
+        assert_eq!(fbpt.perf_records().iter().count(), 2);
+    }
+
+    #[test]
+    fn test_driver_binding_start_end_records_controller_device_path() {
+        static CONTROLLER_DEVICE_PATH: &str = "PciRoot(0x0)/Pci(0x2,0x1)";
+        let mut controller_device_path = MaybeUninit::<efi::protocols::device_path::Protocol>::zeroed();
+        let controller_device_path_address = controller_device_path.as_mut_ptr() as usize;
+
+        extern "efiapi" fn convert_device_path_to_text(
+            _device_path: *mut efi::protocols::device_path::Protocol,
+            display_only: efi::Boolean,
+            _allow_shortcuts: efi::Boolean,
+        ) -> *mut efi::Char16 {
+            assert_eq!(display_only, efi::Boolean::TRUE);
+            let text = CONTROLLER_DEVICE_PATH.encode_utf16().chain([0]).collect::<Vec<_>>();
+            Box::leak(text.into_boxed_slice()).as_mut_ptr()
+        }
+        extern "efiapi" fn convert_device_node_to_text(
+            _device_node: *mut efi::protocols::device_path::Protocol,
+            _display_only: efi::Boolean,
+            _allow_shortcuts: efi::Boolean,
+        ) -> *mut efi::Char16 {
+            ptr::null_mut()
+        }
+        let device_path_to_text = Box::leak(Box::new(efi::protocols::device_path_to_text::Protocol {
+            convert_device_node_to_text,
+            convert_device_path_to_text,
+        })) as *mut efi::protocols::device_path_to_text::Protocol as usize;
 
-        // These functions call create_performance_measurement with the right arguments.
         let module_handle = 1_usize as efi::Handle;
         let controller_handle = 2_usize as efi::Handle;
-        let caller_id = efi::Guid::from_bytes(&[1; 16]);
-        let trigger_guid = efi::Guid::from_bytes(&[2; 16]);
-        let event_guid = efi::Guid::from_bytes(&[3; 16]);
 
-        static mut BOOT_SERVICES: Option<&MockBootServices> = None;
-        static mut FBPT: Option<&TplMutex<'static, MockFirmwareBasicBootPerfTable, MockBootServices>> = None;
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .returning(fw_vol_loaded_image(ptr::null_mut(), efi::Guid::from_bytes(&[3; 16])));
+        boot_services
+            .expect_handle_protocol::<efi::protocols::device_path::Protocol>()
+            .once()
+            .withf(|handle| *handle as usize == 2)
+            .returning(move |_| unsafe {
+                Ok((controller_device_path_address as *mut efi::protocols::device_path::Protocol).as_mut().unwrap())
+            });
+        boot_services.expect_locate_protocol::<efi::protocols::device_path_to_text::Protocol>().once().returning(
+            move |_| unsafe {
+                Ok((device_path_to_text as *mut efi::protocols::device_path_to_text::Protocol).as_mut().unwrap())
+            },
+        );
+        boot_services.expect_free_pool().once().return_const(Ok(()));
+        let fbpt = set_up_fbpt_measurement(boot_services);
+
+        for controller in [controller_handle, ptr::null_mut()] {
+            let status = fbpt_create_performance_measurement(
+                module_handle,
+                None,
+                ptr::null(),
+                0,
+                controller as usize,
+                KnownPerfId::ModuleDbEnd.as_u16() as u32,
+                PerfAttribute::PerfEntry,
+            );
+            assert_eq!(status, efi::Status::SUCCESS);
+        }
 
-        unsafe {
-            BOOT_SERVICES = Some(&*ptr::addr_of!(boot_services));
-            FBPT = Some(&*ptr::addr_of!(fbpt));
-        }
-
-        extern "efiapi" fn test_create_performance_measurement(
-            caller_identifier: *const c_void,
-            guid: Option<&efi::Guid>,
-            string: *const c_char,
-            ticker: u64,
-            address: usize,
-            identifier: u32,
-            attribute: PerfAttribute,
-        ) -> efi::Status {
-            let string = unsafe { string.as_ref().map(|s| CStr::from_ptr(s).to_str().unwrap().to_string()) };
-            let perf_id = identifier as u16;
-            _create_performance_measurement::<MockBootServices, MockFirmwareBasicBootPerfTable>(
-                caller_identifier,
-                guid,
-                string.as_deref(),
+        let fbpt = fbpt.lock();
+        let records = fbpt.perf_records().iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 2);
+
+        // The record for a controller carries its device path after the controller handle.
+        assert_eq!(
+            (records[0].record_type, records[0].revision),
+            (GuidQwordStringEventRecord::TYPE, GuidQwordStringEventRecord::REVISION)
+        );
+        assert_eq!(u64::from_ne_bytes(records[0].data[30..38].try_into().unwrap()), controller_handle as u64);
+        let string = CStr::from_bytes_until_nul(&records[0].data[38..]).unwrap().to_str().unwrap();
+        assert_eq!(string, CONTROLLER_DEVICE_PATH);
+
+        // Without a controller handle, there is no device path to record.
+        assert_eq!(
+            (records[1].record_type, records[1].revision),
+            (GuidQwordEventRecord::TYPE, GuidQwordEventRecord::REVISION)
+        );
+    }
+
+    #[test]
+    fn test_zero_timer_frequency_records_without_timestamps() {
+        let fbpt = set_up_fbpt_measurement(MockBootServices::new());
+
+        let _frequency = PerfFrequencyGuard::lock();
+        assert!(!set_perf_frequency(999));
+        assert!(!set_perf_frequency(0));
+        assert_eq!(get_perf_frequency(), None);
+
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        for ticker in [0, 1, 1_000_000] {
+            let status = fbpt_create_performance_measurement(
+                &caller_id as *const efi::Guid as *const c_void,
+                None,
+                c"measurement".as_ptr(),
                 ticker,
-                address,
-                perf_id,
-                attribute,
-                unsafe { BOOT_SERVICES.unwrap() },
-                unsafe { FBPT.unwrap() },
-            )
-            .unwrap();
-            efi::Status::SUCCESS
+                0,
+                KnownPerfId::PerfEvent.as_u16() as u32,
+                PerfAttribute::PerfEntry,
+            );
+            assert_eq!(status, efi::Status::SUCCESS);
         }
 
-        const EXPECTED_NUMBER_OF_RECORD: usize = 21;
+        // The records are still added, timestamped with the raw ticks.
+        let fbpt = fbpt.lock();
+        let records = fbpt.perf_records().iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 3);
+        let timestamps = records
+            .iter()
+            .inspect(|record| assert_eq!(record.record_type, DynamicStringEventRecord::TYPE))
+            .map(|record| u64::from_ne_bytes(record.data[6..14].try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_ne!(timestamps[0], 0);
+        assert_eq!(timestamps[1..], [0, 1_000_000]);
+    }
 
-        perf_image_start_begin(module_handle, test_create_performance_measurement);
-        perf_image_start_end(module_handle, test_create_performance_measurement);
+    #[test]
+    fn test_ticks_to_nanoseconds() {
+        const GHZ: u64 = 1_000_000_000;
 
-        perf_load_image_begin(module_handle, test_create_performance_measurement);
-        perf_load_image_end(module_handle, test_create_performance_measurement);
+        assert_eq!(ticks_to_nanoseconds(3 * GHZ, Some(3 * GHZ)), GHZ);
+        assert_eq!(ticks_to_nanoseconds(1, Some(3 * GHZ)), 0);
+        assert_eq!(ticks_to_nanoseconds(1_234_567, Some(1_000)), 1_234_567_000_000);
 
-        perf_driver_binding_support_begin(module_handle, controller_handle, test_create_performance_measurement);
-        perf_driver_binding_support_end(module_handle, controller_handle, test_create_performance_measurement);
+        // Large tick counts are converted exactly, and saturate rather than overflow.
+        assert_eq!(ticks_to_nanoseconds(u64::MAX - 1, Some(2 * GHZ)), (u64::MAX - 1) / 2);
+        assert_eq!(ticks_to_nanoseconds(u64::MAX, Some(GHZ / 2)), u64::MAX);
 
-        perf_driver_binding_start_begin(module_handle, controller_handle, test_create_performance_measurement);
-        perf_driver_binding_start_end(module_handle, controller_handle, test_create_performance_measurement);
+        // Without a usable frequency, the ticks are kept.
+        assert_eq!(ticks_to_nanoseconds(1_000_000, None), 1_000_000);
+        assert_eq!(ticks_to_nanoseconds(1_000_000, Some(0)), 1_000_000);
+    }
 
-        perf_driver_binding_stop_begin(module_handle, controller_handle, test_create_performance_measurement);
-        perf_driver_binding_stop_end(module_handle, controller_handle, test_create_performance_measurement);
+    #[test]
+    fn test_ticker_sentinel_records_zero_timestamp() {
+        let _frequency = PerfFrequencyGuard::lock();
+        let fbpt = set_up_fbpt_measurement(MockBootServices::new());
 
-        perf_event("event_string", &caller_id, test_create_performance_measurement);
+        let caller_id = efi::Guid::from_bytes(&[1; 16]);
+        let status = fbpt_create_performance_measurement(
+            &caller_id as *const efi::Guid as *const c_void,
+            None,
+            c"measurement".as_ptr(),
+            1,
+            0,
+            KnownPerfId::PerfEvent.as_u16() as u32,
+            PerfAttribute::PerfEntry,
+        );
+        assert_eq!(status, efi::Status::SUCCESS);
 
-        perf_event_signal_begin(&event_guid, "fun_name", &caller_id, test_create_performance_measurement);
-        perf_event_signal_end(&event_guid, "fun_name", &caller_id, test_create_performance_measurement);
+        let fbpt = fbpt.lock();
+        let records = fbpt.perf_records().iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(u64::from_ne_bytes(records[0].data[6..14].try_into().unwrap()), 0);
+    }
 
-        perf_callback_begin(&trigger_guid, "fun_name", &caller_id, test_create_performance_measurement);
-        perf_callback_end(&trigger_guid, "fun_name", &caller_id, test_create_performance_measurement);
+    #[test]
+    fn test_tagged_event_decoding_rejects_other_records() {
+        let guid = efi::Guid::from_bytes(&[1; 16]);
+        let mut buffer = performance::record::PerformanceRecordBuffer::new();
+        buffer.push_record(GuidQwordStringEventRecord::new(0x60, 0, 10, guid, 64, "test")).unwrap();
+        buffer.push_record(TaggedEventRecord::new(0x60, 0, 10, guid, 64, "test")).unwrap();
+
+        let records = buffer.iter().collect::<Vec<_>>();
+        assert!(TaggedEventRecord::try_from(&records[0]).is_err());
+        assert_eq!(
+            TaggedEventRecord::try_from(&records[1]).unwrap(),
+            TaggedEventRecord::new(0x60, 0, 10, guid, 64, "test")
+        );
 
-        perf_function_begin("fun_name", &caller_id, test_create_performance_measurement);
-        perf_function_end("fun_name", &caller_id, test_create_performance_measurement);
+        // A record truncated before the end of its string is not decoded.
+        let truncated = performance::record::GenericPerformanceRecord {
+            record_type: TaggedEventRecord::TYPE,
+            length: 0,
+            revision: TaggedEventRecord::REVISION,
+            data: &records[1].data[..records[1].data.len() - 1],
+        };
+        assert!(TaggedEventRecord::try_from(&truncated).is_err());
+    }
 
-        perf_in_module_begin("measurement_str", &caller_id, test_create_performance_measurement);
-        perf_in_module_end("measurement_str", &caller_id, test_create_performance_measurement);
+    #[test]
+    fn test_module_guid_cache_resolves_each_handle_once() {
+        // The module start and end records only look up the module once.
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_handle_protocol::<efi::protocols::loaded_image::Protocol>()
+            .once()
+            .returning(fw_vol_loaded_image(ptr::null_mut(), efi::Guid::from_bytes(&[3; 16])));
+        let fbpt = set_up_fbpt_measurement(boot_services);
+
+        let module_handle = 1_usize as efi::Handle as *const c_void;
+        for perf_id in [KnownPerfId::ModuleStart, KnownPerfId::ModuleEnd] {
+            let status = fbpt_create_performance_measurement(
+                module_handle,
+                None,
+                ptr::null(),
+                0,
+                0,
+                perf_id.as_u16() as u32,
+                PerfAttribute::PerfEntry,
+            );
+            assert_eq!(status, efi::Status::SUCCESS);
+        }
+        assert_eq!(fbpt.lock().perf_records().iter().count(), 2);
+    }
+
+    #[test]
+    fn test_module_guid_cache_invalidate() {
+        let cache = ModuleGuidCache::new();
+        let handle = 1_usize as efi::Handle;
+        let module_guid = efi::Guid::from_bytes(&[3; 16]);
+        let resolved = Cell::new(0);
+
+        let resolve = |_| {
+            resolved.set(resolved.get() + 1);
+            Ok::<_, efi::Status>(module_guid)
+        };
+        assert_eq!(cache.get_or_resolve(handle, resolve), Ok(module_guid));
+        assert_eq!(cache.get_or_resolve(handle, resolve), Ok(module_guid));
+        assert_eq!(resolved.get(), 1);
+
+        // A reused handle is resolved again once the cache is invalidated.
+        cache.invalidate();
+        assert_eq!(cache.get_or_resolve(handle, resolve), Ok(module_guid));
+        assert_eq!(resolved.get(), 2);
+    }
 
-        perf_cross_module_begin("measurement_str", &caller_id, test_create_performance_measurement);
-        perf_cross_module_end("measurement_str", &caller_id, test_create_performance_measurement);
+    #[test]
+    fn test_module_guid_cache_does_not_cache_unresolved_handles() {
+        let cache = ModuleGuidCache::new();
+        let handle = 1_usize as efi::Handle;
+        let resolved = Cell::new(0);
+
+        let resolve = |_| {
+            resolved.set(resolved.get() + 1);
+            Ok::<_, efi::Status>(crate::guids::ZERO)
+        };
+        assert_eq!(cache.get_or_resolve(handle, resolve), Ok(crate::guids::ZERO));
+        assert_eq!(cache.get_or_resolve(handle, resolve), Ok(crate::guids::ZERO));
+        assert_eq!(cache.get_or_resolve(handle, |_| Err(efi::Status::NOT_FOUND)), Err(efi::Status::NOT_FOUND));
+        assert_eq!(resolved.get(), 2);
     }
 }
@@ -10,6 +10,7 @@
 pub mod extended;
 pub mod hob;
 pub mod known;
+pub mod stream;
 
 use crate::{performance::error::Error, performance_debug_assert};
 use alloc::vec::Vec;
@@ -57,6 +58,28 @@ pub trait PerformanceRecord {
 
         Ok(record_size)
     }
+
+    /// Returns the size in bytes of the record once written, including its header.
+    ///
+    /// A record too large to be written is reported as [`FPDT_MAX_PERF_RECORD_SIZE`].
+    fn record_size(&self) -> usize {
+        let mut buffer = [0; FPDT_MAX_PERF_RECORD_SIZE];
+        self.write_into(&mut buffer, &mut 0).unwrap_or(FPDT_MAX_PERF_RECORD_SIZE)
+    }
+}
+
+impl<T: PerformanceRecord + ?Sized> PerformanceRecord for &T {
+    fn record_type(&self) -> u16 {
+        (**self).record_type()
+    }
+
+    fn revision(&self) -> u8 {
+        (**self).revision()
+    }
+
+    fn write_data_into(&self, buff: &mut [u8], offset: &mut usize) -> Result<(), scroll::Error> {
+        (**self).write_data_into(buff, offset)
+    }
 }
 
 /// Performance record used to store any specific type of record.
@@ -105,16 +128,29 @@ impl PerformanceRecordBuffer {
         Self::Unpublished(Vec::new())
     }
 
+    /// Create a new performance record buffer in unpublished state, able to hold `capacity` bytes of records without
+    /// reallocating.
+    ///
+    /// Use [`required_size`](Self::required_size) to find the capacity needed for a known set of records.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::Unpublished(Vec::with_capacity(capacity))
+    }
+
+    /// Return the size in bytes needed to hold every record in `records`, including their headers.
+    pub fn required_size(records: &[&dyn PerformanceRecord]) -> usize {
+        records.iter().map(|record| record.record_size()).sum()
+    }
+
     /// Add a performance record to the buffer.
     pub fn push_record<T: PerformanceRecord>(&mut self, record: T) -> Result<usize, Error> {
         match self {
             Self::Unpublished(buffer) => {
-                let mut offset = buffer.len();
-                buffer.resize(offset + FPDT_MAX_PERF_RECORD_SIZE, 0);
-                let Ok(record_size) = record.write_into(buffer, &mut offset) else {
+                // The record is written aside first, so the buffer only grows by the size of the record.
+                let mut record_buffer = [0; FPDT_MAX_PERF_RECORD_SIZE];
+                let Ok(record_size) = record.write_into(&mut record_buffer, &mut 0) else {
                     return performance_debug_assert!("Record size should not exceed FPDT_MAX_PERF_RECORD_SIZE");
                 };
-                buffer.truncate(offset);
+                buffer.extend_from_slice(&record_buffer[..record_size]);
                 Ok(record_size)
             }
             Self::Published(buffer, offset) => record.write_into(buffer, offset).map_err(|_| Error::OutOfResources),
@@ -151,6 +187,11 @@ impl PerformanceRecordBuffer {
         Iter::new(self.buffer())
     }
 
+    /// Return the records as a self-describing binary stream, in the format described in [`stream`].
+    pub fn to_stream(&self) -> Vec<u8> {
+        stream::write_stream(self.buffer())
+    }
+
     /// Return the size in bytes of the buffer.
     pub fn size(&self) -> usize {
         match &self {
@@ -198,14 +239,35 @@ impl Debug for PerformanceRecordBuffer {
 }
 
 /// Performance record iterator.
+///
+/// Each record's length is validated against the remaining bytes of the buffer. The iteration stops at the first
+/// malformed record, which can be checked with [`Iter::is_malformed`].
 pub struct Iter<'a> {
     buffer: &'a [u8],
+    offset: usize,
+    malformed: bool,
 }
 
 impl<'a> Iter<'a> {
-    /// Iterate through performance records in a memory buffer. The buffer must contains valid records.
+    /// Iterate through performance records in a memory buffer.
     pub fn new(buffer: &'a [u8]) -> Self {
-        Self { buffer }
+        Self { buffer, offset: 0, malformed: false }
+    }
+
+    /// Return whether the iteration stopped at a malformed record rather than the end of the buffer.
+    pub fn is_malformed(&self) -> bool {
+        self.malformed
+    }
+
+    fn next_record(&self) -> Option<GenericPerformanceRecord<&'a [u8]>> {
+        let remaining = &self.buffer[self.offset..];
+        let mut offset = 0;
+        let record_type = remaining.gread::<u16>(&mut offset).ok()?;
+        let length = remaining.gread::<u8>(&mut offset).ok()?;
+        let revision = remaining.gread::<u8>(&mut offset).ok()?;
+
+        let data = remaining.get(offset..length as usize)?;
+        Some(GenericPerformanceRecord { record_type, length, revision, data })
     }
 }
 
@@ -213,17 +275,20 @@ impl<'a> Iterator for Iter<'a> {
     type Item = GenericPerformanceRecord<&'a [u8]>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.buffer.is_empty() {
+        if self.malformed || self.offset == self.buffer.len() {
             return None;
         }
-        let mut offset = 0;
-        let record_type = self.buffer.gread::<u16>(&mut offset).unwrap();
-        let length = self.buffer.gread::<u8>(&mut offset).unwrap();
-        let revision = self.buffer.gread::<u8>(&mut offset).unwrap();
-
-        let data = &self.buffer[offset..length as usize];
-        self.buffer = &self.buffer[length as usize..];
-        Some(GenericPerformanceRecord { record_type, length, revision, data })
+        let Some(record) = self.next_record() else {
+            log::warn!(
+                "Performance: Malformed record at offset {:#x} of a {:#x} byte record buffer.",
+                self.offset,
+                self.buffer.len()
+            );
+            self.malformed = true;
+            return None;
+        };
+        self.offset += record.length as usize;
+        Some(record)
     }
 }
 
@@ -272,6 +337,29 @@ mod tests {
         assert_eq!(size, performance_record_buffer.size());
     }
 
+    #[test]
+    fn test_performance_record_buffer_required_size() {
+        let guid = efi::Guid::from_bytes(&[0; 16]);
+        let records: [&dyn PerformanceRecord; 5] = [
+            &GuidEventRecord::new(1, 0, 10, guid),
+            &DynamicStringEventRecord::new(1, 0, 10, guid, "test"),
+            &DualGuidStringEventRecord::new(1, 0, 10, guid, guid, "a longer function name"),
+            &GuidQwordEventRecord::new(1, 0, 10, guid, 64),
+            &GuidQwordStringEventRecord::new(1, 0, 10, guid, 64, ""),
+        ];
+        let required_size = PerformanceRecordBuffer::required_size(&records);
+
+        let mut performance_record_buffer = PerformanceRecordBuffer::with_capacity(required_size);
+        for record in records {
+            performance_record_buffer.push_record(record).unwrap();
+        }
+
+        // The buffer is exactly the required size, and was never reallocated.
+        assert_eq!(required_size, performance_record_buffer.size());
+        assert_eq!(required_size, performance_record_buffer.capacity());
+        assert_eq!(0, PerformanceRecordBuffer::required_size(&[]));
+    }
+
     #[test]
     fn test_performance_record_buffer_iter() {
         let guid = efi::Guid::from_bytes(&[0; 16]);
@@ -310,6 +398,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_iter_stops_at_record_longer_than_buffer() {
+        let guid = efi::Guid::from_bytes(&[0; 16]);
+        let mut performance_record_buffer = PerformanceRecordBuffer::new();
+        performance_record_buffer.push_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+        let valid_size = performance_record_buffer.size();
+        performance_record_buffer.push_record(GuidEventRecord::new(1, 0, 20, guid)).unwrap();
+
+        // Claim the second record is longer than the remaining bytes.
+        let mut bytes = performance_record_buffer.buffer().to_vec();
+        bytes[valid_size + 2] = u8::MAX;
+
+        let mut iter = Iter::new(&bytes);
+        assert_eq!(GuidEventRecord::TYPE, iter.next().unwrap().record_type);
+        assert!(!iter.is_malformed());
+        assert!(iter.next().is_none());
+        assert!(iter.is_malformed());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_stops_at_truncated_or_undersized_record() {
+        // Too short for a record header.
+        let mut iter = Iter::new(&[0x10, 0x00, 0x04]);
+        assert!(iter.next().is_none());
+        assert!(iter.is_malformed());
+
+        // A length smaller than the header would never advance.
+        let mut iter = Iter::new(&[0x10, 0x00, 0x00, 0x01, 0xAA, 0xBB]);
+        assert!(iter.next().is_none());
+        assert!(iter.is_malformed());
+
+        let mut iter = Iter::new(&[]);
+        assert!(iter.next().is_none());
+        assert!(!iter.is_malformed());
+    }
+
     #[test]
     fn test_performance_record_buffer_reported_table() {
         let guid = efi::Guid::from_bytes(&[0; 16]);
@@ -10,9 +10,9 @@
 use core::fmt::Debug;
 
 use r_efi::efi;
-use scroll::Pwrite;
+use scroll::{Pread, Pwrite};
 
-use super::PerformanceRecord;
+use super::{GenericPerformanceRecord, PerformanceRecord};
 
 /// A performance string event record which includes a GUID.
 #[derive(Debug)]
@@ -293,3 +293,98 @@ impl PerformanceRecord for GuidQwordStringEventRecord<'_> {
         Ok(())
     }
 }
+
+/// A performance string event record which includes a GUID, an ASCII string, and caller provided context data.
+///
+/// This is a Patina extension used to correlate a measurement with data outside of the standard records, such as
+/// a transaction number.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TaggedEventRecord<'a> {
+    /// ProgressID < 0x10 are reserved for core performance entries.
+    /// Start measurement point shall have lowered one nibble set to zero and
+    /// corresponding end points shall have lowered one nibble set to non-zero value;
+    /// keeping other nibbles same as start point.
+    pub progress_id: u16,
+    /// APIC ID for the processor in the system used as a timestamp clock source.
+    /// If only one timestamp clock source is used, this field is Reserved and populated as 0.
+    pub acpi_id: u32,
+    /// 64-bit value (nanosecond) describing elapsed time since the most recent deassertion of processor reset.
+    pub timestamp: u64,
+    /// GUID of the module logging the event.
+    pub guid: efi::Guid,
+    /// Caller provided context data used to correlate the event.
+    pub context: u64,
+    /// ASCII string describing the event.
+    pub string: &'a str,
+}
+
+impl<'a> TaggedEventRecord<'a> {
+    /// The defined type ID for this record.
+    pub const TYPE: u16 = 0x1015;
+    /// The current revision version of this structure.
+    pub const REVISION: u8 = 1;
+
+    /// Creates a new `TaggedEventRecord`.
+    pub fn new(progress_id: u16, acpi_id: u32, timestamp: u64, guid: efi::Guid, context: u64, string: &'a str) -> Self {
+        Self { progress_id, acpi_id, timestamp, guid, context, string }
+    }
+}
+
+impl<'a> scroll::ctx::TryFromCtx<'a, scroll::Endian> for TaggedEventRecord<'a> {
+    type Error = scroll::Error;
+
+    fn try_from_ctx(src: &'a [u8], ctx: scroll::Endian) -> Result<(Self, usize), Self::Error> {
+        let mut offset = 0;
+        let progress_id = src.gread_with(&mut offset, ctx)?;
+        let acpi_id = src.gread_with(&mut offset, ctx)?;
+        let timestamp = src.gread_with(&mut offset, ctx)?;
+        let guid_bytes: &[u8] = src.gread_with(&mut offset, 16)?;
+        let guid = efi::Guid::from_bytes(
+            guid_bytes.try_into().map_err(|_| scroll::Error::BadInput { size: offset, msg: "Invalid GUID" })?,
+        );
+        let context = src.gread_with(&mut offset, ctx)?;
+
+        let string_bytes = &src[offset..];
+        let Some(string_len) = string_bytes.iter().position(|&b| b == 0) else {
+            return Err(scroll::Error::BadInput { size: offset, msg: "String is not null terminated" });
+        };
+        let string = core::str::from_utf8(&string_bytes[..string_len])
+            .map_err(|_| scroll::Error::BadInput { size: offset, msg: "String is not valid UTF-8" })?;
+        offset += string_len + 1;
+
+        Ok((Self { progress_id, acpi_id, timestamp, guid, context, string }, offset))
+    }
+}
+
+impl<'a> TryFrom<&GenericPerformanceRecord<&'a [u8]>> for TaggedEventRecord<'a> {
+    type Error = scroll::Error;
+
+    /// Decodes a record read from a performance table.
+    fn try_from(record: &GenericPerformanceRecord<&'a [u8]>) -> Result<Self, Self::Error> {
+        if record.record_type != Self::TYPE {
+            return Err(scroll::Error::BadInput { size: 0, msg: "Not a tagged event record" });
+        }
+        record.data.pread_with(0, scroll::NATIVE)
+    }
+}
+
+impl PerformanceRecord for TaggedEventRecord<'_> {
+    fn record_type(&self) -> u16 {
+        Self::TYPE
+    }
+
+    fn revision(&self) -> u8 {
+        Self::REVISION
+    }
+
+    fn write_data_into(&self, buff: &mut [u8], offset: &mut usize) -> Result<(), scroll::Error> {
+        buff.gwrite_with(self.progress_id, offset, scroll::NATIVE)?;
+        buff.gwrite_with(self.acpi_id, offset, scroll::NATIVE)?;
+        buff.gwrite_with(self.timestamp, offset, scroll::NATIVE)?;
+        buff.gwrite_with(self.guid.as_bytes().as_slice(), offset, ())?;
+        buff.gwrite_with(self.context, offset, scroll::NATIVE)?;
+        buff.gwrite_with(self.string.as_bytes(), offset, ())?;
+        buff.gwrite_with(0_u8, offset, scroll::NATIVE)?; // End of the string.
+        Ok(())
+    }
+}
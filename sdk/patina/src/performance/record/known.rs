@@ -125,6 +125,8 @@ pub enum KnownPerfId {
     PerfCrossModuleStart = 0x50,
     /// The performance ID for the end of behavior spanning multiple modules.
     PerfCrossModuleEnd = 0x51,
+    /// The performance ID for an event tagged with caller provided context data.
+    PerfTaggedEvent = 0x60,
 }
 
 impl KnownPerfId {
@@ -152,6 +154,7 @@ impl KnownPerfId {
             Self::PerfInModuleEnd => Self::PerfInModuleEnd as u16,
             Self::PerfCrossModuleStart => Self::PerfCrossModuleStart as u16,
             Self::PerfCrossModuleEnd => Self::PerfCrossModuleEnd as u16,
+            Self::PerfTaggedEvent => Self::PerfTaggedEvent as u16,
         }
     }
 
@@ -241,6 +244,7 @@ impl TryFrom<u16> for KnownPerfId {
             v if v == Self::PerfInModuleEnd as u16 => Self::PerfInModuleEnd,
             v if v == Self::PerfCrossModuleStart as u16 => Self::PerfCrossModuleStart,
             v if v == Self::PerfCrossModuleEnd as u16 => Self::PerfCrossModuleEnd,
+            v if v == Self::PerfTaggedEvent as u16 => Self::PerfTaggedEvent,
             _ => return Err(()),
         };
         Ok(this)
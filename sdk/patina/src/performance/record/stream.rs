@@ -0,0 +1,194 @@
+//! A self-describing binary stream of performance records, read by tooling without knowledge of the FBPT.
+//!
+//! All integers are little-endian. The stream starts with a header:
+//!
+//! | Offset | Size | Field                                     |
+//! |--------|------|-------------------------------------------|
+//! | 0      | 4    | Magic, the bytes of [`STREAM_MAGIC`]      |
+//! | 4      | 2    | Version of the format, [`STREAM_VERSION`] |
+//!
+//! The records follow in the order they were added, each as:
+//!
+//! | Offset | Size       | Field                                                           |
+//! |--------|------------|-----------------------------------------------------------------|
+//! | 0      | 4          | Length in bytes of the rest of the record, the data length + 3  |
+//! | 4      | 2          | Type of the record                                              |
+//! | 6      | 1          | Revision of the record                                          |
+//! | 7      | length - 3 | Data of the record, as it follows the record header in the FBPT |
+//!
+//! The stream ends after the last record. A later version may only add fields after the data of a record, so a
+//! reader can skip to the next record with its length.
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+
+use alloc::vec::Vec;
+use core::mem;
+
+use scroll::Pread;
+
+use super::{GenericPerformanceRecord, Iter, PERFORMANCE_RECORD_HEADER_SIZE};
+use crate::{error::EfiError, performance::error::Error};
+
+/// The bytes a performance record stream starts with.
+pub const STREAM_MAGIC: [u8; 4] = *b"PFRS";
+
+/// The version of the performance record stream format written by
+/// [`PerformanceRecordBuffer::to_stream`](super::PerformanceRecordBuffer::to_stream).
+pub const STREAM_VERSION: u16 = 1;
+
+/// Size in bytes of the stream header.
+pub const STREAM_HEADER_SIZE: usize = STREAM_MAGIC.len() + mem::size_of::<u16>();
+
+/// Size in bytes of the fields counted by the length of a record, before its data.
+const RECORD_TAG_SIZE: usize = mem::size_of::<u16>() + mem::size_of::<u8>();
+
+/// Writes the records of an FBPT record buffer as a performance record stream.
+///
+/// Only the well formed records before any malformed one are written.
+pub(super) fn write_stream(records: &[u8]) -> Vec<u8> {
+    // Each record is 3 bytes larger in the stream, with a 4 byte length rather than 1.
+    let capacity = STREAM_HEADER_SIZE + records.len() + Iter::new(records).count() * 3;
+    let mut stream = Vec::with_capacity(capacity);
+    stream.extend_from_slice(&STREAM_MAGIC);
+    stream.extend_from_slice(&STREAM_VERSION.to_le_bytes());
+    for record in Iter::new(records) {
+        let data = record.data;
+        stream.extend_from_slice(&((RECORD_TAG_SIZE + data.len()) as u32).to_le_bytes());
+        stream.extend_from_slice(&record.record_type.to_le_bytes());
+        stream.push(record.revision);
+        stream.extend_from_slice(data);
+    }
+    stream
+}
+
+/// Performance record stream iterator.
+///
+/// The iteration stops at the first malformed record, which can be checked with [`StreamIter::is_malformed`].
+pub struct StreamIter<'a> {
+    stream: &'a [u8],
+    offset: usize,
+    malformed: bool,
+}
+
+impl<'a> StreamIter<'a> {
+    /// Iterate through the records of a performance record stream.
+    ///
+    /// Returns [`EfiError::InvalidParameter`] if `stream` does not start with a stream header, and
+    /// [`EfiError::Unsupported`] if the stream is of a later version.
+    pub fn new(stream: &'a [u8]) -> Result<Self, Error> {
+        if stream.len() < STREAM_HEADER_SIZE || stream[..STREAM_MAGIC.len()] != STREAM_MAGIC {
+            return Err(EfiError::InvalidParameter.into());
+        }
+        let version =
+            stream.pread_with::<u16>(STREAM_MAGIC.len(), scroll::LE).map_err(|_| EfiError::InvalidParameter)?;
+        if version != STREAM_VERSION {
+            return Err(EfiError::Unsupported.into());
+        }
+        Ok(Self { stream, offset: STREAM_HEADER_SIZE, malformed: false })
+    }
+
+    /// Return whether the iteration stopped at a malformed record rather than the end of the stream.
+    pub fn is_malformed(&self) -> bool {
+        self.malformed
+    }
+
+    /// Returns the next record, and the size of the record in the stream.
+    fn next_record(&self) -> Option<(GenericPerformanceRecord<&'a [u8]>, usize)> {
+        let remaining = &self.stream[self.offset..];
+        let mut offset = 0;
+        let length = remaining.gread_with::<u32>(&mut offset, scroll::LE).ok()? as usize;
+        let record_type = remaining.gread_with::<u16>(&mut offset, scroll::LE).ok()?;
+        let revision = remaining.gread::<u8>(&mut offset).ok()?;
+
+        let end = length.checked_add(mem::size_of::<u32>())?;
+        let data = remaining.get(offset..end)?;
+        let length = u8::try_from(PERFORMANCE_RECORD_HEADER_SIZE + data.len()).ok()?;
+        Some((GenericPerformanceRecord { record_type, length, revision, data }, end))
+    }
+}
+
+impl<'a> Iterator for StreamIter<'a> {
+    type Item = GenericPerformanceRecord<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.malformed || self.offset == self.stream.len() {
+            return None;
+        }
+        let Some((record, size)) = self.next_record() else {
+            self.malformed = true;
+            return None;
+        };
+        self.offset += size;
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    use super::*;
+    use crate::performance::record::{
+        PerformanceRecord, PerformanceRecordBuffer,
+        extended::{DualGuidStringEventRecord, DynamicStringEventRecord, GuidEventRecord, GuidQwordEventRecord},
+    };
+
+    use r_efi::efi;
+
+    #[test]
+    fn test_stream_round_trips_mixed_records() {
+        let guid = efi::Guid::from_bytes(&[7; 16]);
+        let mut buffer = PerformanceRecordBuffer::new();
+        buffer.push_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+        buffer.push_record(DynamicStringEventRecord::new(2, 0, 20, guid, "a string")).unwrap();
+        buffer.push_record(DualGuidStringEventRecord::new(3, 0, 30, guid, guid, "")).unwrap();
+        buffer.push_record(GuidQwordEventRecord::new(4, 0, 40, guid, 64)).unwrap();
+
+        let stream = buffer.to_stream();
+        assert_eq!(stream[..4], *b"PFRS");
+        assert_eq!(stream[4..6], 1_u16.to_le_bytes());
+        assert_eq!(stream.len(), STREAM_HEADER_SIZE + buffer.size() + 4 * 3);
+
+        let mut records = StreamIter::new(&stream).unwrap();
+        for expected in buffer.iter() {
+            let record = records.next().unwrap();
+            assert_eq!(
+                (record.record_type, record.length, record.revision, record.data),
+                (expected.record_type, expected.length, expected.revision, expected.data)
+            );
+        }
+        assert!(records.next().is_none());
+        assert!(!records.is_malformed());
+
+        // The first record is laid out as documented.
+        let data_len = GuidEventRecord::new(1, 0, 10, guid).record_size() - PERFORMANCE_RECORD_HEADER_SIZE;
+        assert_eq!(stream[6..10], ((data_len + 3) as u32).to_le_bytes());
+        assert_eq!(stream[10..12], GuidEventRecord::TYPE.to_le_bytes());
+        assert_eq!(stream[12], GuidEventRecord::REVISION);
+        assert_eq!(stream[13..13 + data_len], *buffer.iter().next().unwrap().data);
+    }
+
+    #[test]
+    fn test_stream_iter_rejects_invalid_streams() {
+        assert!(matches!(StreamIter::new(b"PFR"), Err(Error::Efi(EfiError::InvalidParameter))));
+        assert!(matches!(StreamIter::new(b"NOPE\x01\x00"), Err(Error::Efi(EfiError::InvalidParameter))));
+        assert!(matches!(StreamIter::new(b"PFRS\x02\x00"), Err(Error::Efi(EfiError::Unsupported))));
+
+        let empty = PerformanceRecordBuffer::new().to_stream();
+        let mut records = StreamIter::new(&empty).unwrap();
+        assert!(records.next().is_none());
+        assert!(!records.is_malformed());
+
+        // A record longer than the rest of the stream, and one too short for its type and revision.
+        for record in [&[0x10, 0, 0, 0, 1, 0, 2, 0xAA][..], &[0x02, 0, 0, 0, 1, 0, 2]] {
+            let stream = [&empty[..], record].concat();
+            let mut records = StreamIter::new(&stream).unwrap();
+            assert!(records.next().is_none());
+            assert!(records.is_malformed());
+        }
+    }
+}
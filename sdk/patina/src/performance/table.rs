@@ -24,18 +24,20 @@ use crate::{
     boot_services::{
         BootServices,
         allocation::{AllocType, MemoryType},
+        configuration_table::ConfigTableEntry,
     },
     error::EfiError,
+    guids::EDKII_FPDT_EXTENDED_FIRMWARE_PERFORMANCE,
     performance::{
         self,
         error::Error,
-        record::{PerformanceRecord, PerformanceRecordBuffer},
+        record::{Iter, PERFORMANCE_RECORD_HEADER_SIZE, PerformanceRecord, PerformanceRecordBuffer},
     },
     runtime_services::RuntimeServices,
 };
 
 use r_efi::efi;
-use scroll::Pwrite;
+use scroll::{Pread, Pwrite};
 
 /// The number of extra space in byte that will be allocated when publishing the performance buffer.
 /// This is used for every performance records that will be added to the table after it is published.
@@ -77,6 +79,9 @@ pub struct FBPT {
     _length: (u32, AtomicPtr<u32>),
     /// Buffer containing all the performance record.
     other_records: PerformanceRecordBuffer,
+    /// Running CRC32 of `other_records`, verified before the table is reported.
+    #[cfg(feature = "perf_checksum")]
+    checksum: u32,
 }
 
 impl FBPT {
@@ -89,6 +94,8 @@ impl FBPT {
             fbpt_address: 0,
             _length: (Self::size_of_empty_table() as u32, AtomicPtr::new(ptr::null_mut())),
             other_records: PerformanceRecordBuffer::new(),
+            #[cfg(feature = "perf_checksum")]
+            checksum: 0,
         }
     }
 
@@ -103,6 +110,28 @@ impl FBPT {
         unsafe { self._length.1.load(Ordering::Relaxed).as_mut() }.unwrap_or(&mut self._length.0)
     }
 
+    /// Extends the running checksum with the bytes of the records added since the last update.
+    #[cfg(feature = "perf_checksum")]
+    fn update_checksum(&mut self, previous_size: usize) {
+        let mut hasher = crc32fast::Hasher::new_with_initial(self.checksum);
+        hasher.update(&self.other_records.buffer()[previous_size..]);
+        self.checksum = hasher.finalize();
+    }
+
+    /// Verifies the performance records still match the running checksum.
+    #[cfg(feature = "perf_checksum")]
+    fn verify_checksum(&self) -> Result<(), Error> {
+        let checksum = crc32fast::hash(self.other_records.buffer());
+        if checksum != self.checksum {
+            log::error!(
+                "Performance: FBPT records were corrupted, checksum is {checksum:#010x} (expected {:#010x}).",
+                self.checksum
+            );
+            return Err(Error::ChecksumMismatch);
+        }
+        Ok(())
+    }
+
     const fn size_of_empty_table() -> usize {
         mem::size_of::<u32>() // Header signature
         + mem::size_of::<u32>() // Header length
@@ -154,11 +183,20 @@ impl FirmwareBasicBootPerfTable for FBPT {
     fn set_perf_records(&mut self, perf_records: PerformanceRecordBuffer) {
         *self.length_mut() = (Self::size_of_empty_table() + perf_records.size()) as u32;
         self.other_records = perf_records;
+        #[cfg(feature = "perf_checksum")]
+        {
+            self.checksum = 0;
+            self.update_checksum(0);
+        }
     }
 
     fn add_record<T: PerformanceRecord>(&mut self, record: T) -> Result<(), Error> {
+        #[cfg(feature = "perf_checksum")]
+        let previous_size = self.other_records.size();
         let record_size = self.other_records.push_record(record)?;
         *self.length_mut() += record_size as u32;
+        #[cfg(feature = "perf_checksum")]
+        self.update_checksum(previous_size);
         Ok(())
     }
 
@@ -167,6 +205,9 @@ impl FirmwareBasicBootPerfTable for FBPT {
         address: Option<usize>,
         boot_services: &B,
     ) -> Result<usize, Error> {
+        #[cfg(feature = "perf_checksum")]
+        self.verify_checksum()?;
+
         let fbpt_buffer = self.allocate_table_buffer(address, boot_services)?;
 
         self.fbpt_address = fbpt_buffer.as_ptr() as usize;
@@ -194,6 +235,103 @@ impl Default for FBPT {
     }
 }
 
+/// The configuration table entry installed when the FBPT is reported, pointing to the header of the table.
+// SAFETY: The table installed with `EDKII_FPDT_EXTENDED_FIRMWARE_PERFORMANCE` is the FBPT, starting with its header.
+pub const FBPT_TABLE: ConfigTableEntry<FbptHeader> =
+    unsafe { ConfigTableEntry::new(EDKII_FPDT_EXTENDED_FIRMWARE_PERFORMANCE) };
+
+/// Header of a reported Firmware Basic Boot Performance Table.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct FbptHeader {
+    /// The table signature, [`FBPT::SIGNATURE`].
+    pub signature: u32,
+    /// The length of the table in bytes, including the header.
+    pub length: u32,
+}
+
+/// A validated view over a reported Firmware Basic Boot Performance Table.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportedFbpt<'a> {
+    table: &'a [u8],
+}
+
+impl<'a> ReportedFbpt<'a> {
+    /// Validates the FBPT at the start of `buffer`.
+    ///
+    /// Returns `None` if the signature does not match, if the table length does not fit in `buffer`, or if the table
+    /// does not start with a Firmware Basic Boot Performance Record.
+    pub fn from_bytes(buffer: &'a [u8]) -> Option<Self> {
+        let signature = buffer.pread_with::<u32>(0, scroll::NATIVE).ok()?;
+        let length = buffer.pread_with::<u32>(mem::size_of::<u32>(), scroll::NATIVE).ok()? as usize;
+        if signature != FBPT::SIGNATURE || length < FBPT::size_of_empty_table() {
+            log::warn!("Performance: Invalid FBPT header, signature {signature:#010x} and length {length:#x}.");
+            return None;
+        }
+        let Some(table) = buffer.get(..length) else {
+            log::warn!("Performance: FBPT length {length:#x} exceeds the {:#x} byte buffer.", buffer.len());
+            return None;
+        };
+
+        let basic_boot_record = Iter::new(&table[mem::size_of::<FbptHeader>()..]).next()?;
+        if basic_boot_record.record_type != FirmwareBasicBootPerfDataRecord::TYPE
+            || basic_boot_record.length as usize
+                != PERFORMANCE_RECORD_HEADER_SIZE + FirmwareBasicBootPerfDataRecord::data_size()
+        {
+            log::warn!("Performance: FBPT does not start with a Firmware Basic Boot Performance Record.");
+            return None;
+        }
+        Some(Self { table })
+    }
+
+    /// Finds and validates the FBPT installed in a list of configuration tables.
+    ///
+    /// # Safety
+    ///
+    /// `tables` must be the configuration tables of a valid system table, such that the table installed with
+    /// [`FBPT_TABLE`] is valid for the length in its header for the lifetime `'a`.
+    pub unsafe fn find(tables: &'a [efi::ConfigurationTable]) -> Option<Self> {
+        // SAFETY: The caller guarantees the table pointer is valid.
+        let header = unsafe { FBPT_TABLE.find(tables) }?;
+        if header.signature != FBPT::SIGNATURE {
+            log::warn!("Performance: Invalid FBPT signature {:#010x}.", header.signature);
+            return None;
+        }
+        let length = (header.length as usize).max(mem::size_of::<FbptHeader>());
+        // SAFETY: The caller guarantees the table is valid for the length in its header.
+        Self::from_bytes(unsafe { slice::from_raw_parts(ptr::from_ref(header) as *const u8, length) })
+    }
+
+    /// Return the address of the table.
+    pub fn address(&self) -> usize {
+        self.table.as_ptr() as usize
+    }
+
+    /// Return the length of the table in bytes.
+    pub fn length(&self) -> u32 {
+        self.table.len() as u32
+    }
+
+    /// Return the Firmware Basic Boot Performance Record at the start of the table.
+    pub fn basic_boot_record(&self) -> FirmwareBasicBootPerfDataRecord {
+        // Skip the table header, the record header and the reserved bytes.
+        let mut offset = mem::size_of::<FbptHeader>() + PERFORMANCE_RECORD_HEADER_SIZE + 4;
+        let mut read = || self.table.gread_with::<u64>(&mut offset, scroll::NATIVE).unwrap_or_default();
+        FirmwareBasicBootPerfDataRecord {
+            reset_end: read(),
+            os_loader_load_image_start: read(),
+            os_loader_start_image_start: read(),
+            exit_boot_services_entry: read(),
+            exit_boot_services_exit: read(),
+        }
+    }
+
+    /// Return an iterator over the performance records following the Firmware Basic Boot Performance Record.
+    pub fn records(&self) -> Iter<'a> {
+        Iter::new(&self.table[FBPT::size_of_empty_table()..])
+    }
+}
+
 /// Return the address where the FBPT has been allocated during the previous boot.
 pub fn find_previous_table_address(runtime_services: &impl RuntimeServices) -> Option<usize> {
     runtime_services
@@ -303,8 +441,8 @@ impl Default for FirmwareBasicBootPerfDataRecord {
 mod tests {
     use super::*;
 
-    use core::{assert_eq, slice, unreachable};
-    use scroll::Pread;
+    use alloc::vec;
+    use core::{assert_eq, ffi::c_void, slice, unreachable};
 
     use crate::{
         boot_services::MockBootServices,
@@ -495,4 +633,91 @@ mod tests {
         offset += FirmwareBasicBootPerfDataRecord::data_size();
         assert_eq!(fbpt.perf_records().buffer().as_ptr() as usize, address + offset);
     }
+
+    #[test]
+    fn test_reported_fbpt_is_read_back_from_the_configuration_table() {
+        let memory_buffer = vec![0_u64; 0x20_000 / mem::size_of::<u64>()];
+        let address = memory_buffer.as_ptr() as usize;
+
+        let mut boot_services = MockBootServices::new();
+        boot_services.expect_allocate_pages().once().returning(move |_, _, _| Ok(address));
+
+        let mut fbpt = FBPT::new();
+        let guid = efi::Guid::from_bytes(&[0; 16]);
+        fbpt.add_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+        fbpt.report_table(None, &boot_services).unwrap();
+        fbpt.add_record(DynamicStringEventRecord::new(2, 0, 20, guid, "test")).unwrap();
+
+        let tables = [
+            efi::ConfigurationTable { vendor_guid: efi::Guid::from_bytes(&[1; 16]), vendor_table: ptr::null_mut() },
+            efi::ConfigurationTable { vendor_guid: *FBPT_TABLE.guid(), vendor_table: address as *mut c_void },
+        ];
+        let reported = unsafe { ReportedFbpt::find(&tables) }.unwrap();
+
+        assert_eq!(address, reported.address());
+        assert_eq!(fbpt.length(), &reported.length());
+        assert_eq!(0, reported.basic_boot_record().reset_end);
+        let records = reported.records().map(|record| record.record_type).collect::<Vec<_>>();
+        assert_eq!(vec![GuidEventRecord::TYPE, DynamicStringEventRecord::TYPE], records);
+    }
+
+    #[test]
+    fn test_reported_fbpt_rejects_invalid_tables() {
+        let mut buffer = [0_u8; FBPT::size_of_empty_table()];
+        let mut offset = 0;
+        buffer.gwrite(FBPT::SIGNATURE, &mut offset).unwrap();
+        buffer.gwrite(FBPT::size_of_empty_table() as u32, &mut offset).unwrap();
+        FirmwareBasicBootPerfDataRecord::new().write_into(&mut buffer, &mut offset).unwrap();
+        assert!(ReportedFbpt::from_bytes(&buffer).unwrap().records().next().is_none());
+
+        // Length larger than the buffer.
+        assert!(ReportedFbpt::from_bytes(&buffer[..buffer.len() - 1]).is_none());
+
+        // Missing Firmware Basic Boot Performance Record.
+        let mut corrupted = buffer;
+        corrupted[mem::size_of::<FbptHeader>()] = 0xFF;
+        assert!(ReportedFbpt::from_bytes(&corrupted).is_none());
+
+        // Wrong signature.
+        let mut corrupted = buffer;
+        corrupted[0] = b'X';
+        assert!(ReportedFbpt::from_bytes(&corrupted).is_none());
+
+        let tables = [efi::ConfigurationTable { vendor_guid: *FBPT_TABLE.guid(), vendor_table: ptr::null_mut() }];
+        assert!(unsafe { ReportedFbpt::find(&tables) }.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "perf_checksum")]
+    fn test_report_table_detects_corrupted_records() {
+        let mut fbpt = FBPT::new();
+        let guid = efi::Guid::from_bytes(&[0; 16]);
+        fbpt.add_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+        fbpt.add_record(DynamicStringEventRecord::new(1, 0, 10, guid, "test")).unwrap();
+        assert_eq!(crc32fast::hash(fbpt.perf_records().buffer()), fbpt.checksum);
+
+        let PerformanceRecordBuffer::Unpublished(buffer) = &mut fbpt.other_records else { unreachable!() };
+        buffer[PERFORMANCE_RECORD_HEADER_SIZE] ^= 0xFF;
+
+        // No allocation is expected, the table is refused before it is moved.
+        let boot_services = MockBootServices::new();
+        assert!(matches!(fbpt.report_table(None, &boot_services), Err(Error::ChecksumMismatch)));
+    }
+
+    #[test]
+    #[cfg(feature = "perf_checksum")]
+    fn test_set_perf_records_resets_checksum() {
+        let mut performance_record_buffer = PerformanceRecordBuffer::new();
+        performance_record_buffer
+            .push_record(GenericPerformanceRecord { record_type: 1, length: 20, revision: 1, data: [0_u8; 16] })
+            .unwrap();
+        let guid = efi::Guid::from_bytes(&[0; 16]);
+
+        let mut fbpt = FBPT::new();
+        fbpt.add_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+        fbpt.set_perf_records(performance_record_buffer);
+        fbpt.add_record(GuidEventRecord::new(1, 0, 10, guid)).unwrap();
+
+        assert!(fbpt.verify_checksum().is_ok());
+    }
 }
@@ -1001,7 +1001,6 @@ mod unit_tests {
     use core::{mem, sync::atomic::AtomicBool};
     use r_efi::efi;
     use serde::Deserialize;
-    use uuid::Uuid;
 
     use crate::pi::fw_fs::{SectionMetaData, guid};
 
@@ -1042,7 +1041,7 @@ mod unit_tests {
         for ffs_file in fv.file_iter() {
             let ffs_file = ffs_file.map_err(stringify)?;
             count += 1;
-            let file_name = Uuid::from_bytes_le(*ffs_file.name().as_bytes()).to_string().to_uppercase();
+            let file_name = crate::base::guid::to_uuid(&ffs_file.name()).to_string().to_uppercase();
             if let Some(mut target) = expected_values.files_to_test.remove(&file_name) {
                 assert_eq!(target.file_type, ffs_file.file_type_raw(), "[{file_name}] Error with the file type.");
                 assert_eq!(
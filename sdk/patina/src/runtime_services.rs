@@ -15,6 +15,9 @@ extern crate alloc;
 /// Variable-services-specific structs and utilities
 pub mod variable_services;
 
+/// Conversion of pointers when the virtual address map is set
+pub mod virtual_address;
+
 #[cfg(any(test, feature = "mockall"))]
 use mockall::automock;
 
@@ -27,7 +30,36 @@ use core::{
 };
 
 use r_efi::efi;
-use variable_services::{GetVariableStatus, VariableInfo};
+use variable_services::{GetVariableStatus, VariableAttributes, VariableInfo, VariableNames};
+
+/// Number of spin-loop iterations to wait between [`RuntimeServices::get_variable_retrying`] attempts.
+const GET_VARIABLE_RETRY_DELAY_SPINS: usize = 10_000;
+
+/// Length in characters of the stack buffer [`RuntimeServices::get_variable_into`] copies the variable name into.
+const VARIABLE_NAME_STACK_LEN: usize = 128;
+
+/// Checks that a variable name passed into `function` is null-terminated.
+fn check_name_terminated(name: &[u16], function: &str) -> Result<(), efi::Status> {
+    if !name.contains(&0) {
+        debug_assert!(false, "Name passed into {function} is not null-terminated.");
+        return Err(efi::Status::INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+/// The kind of reset performed by [`RuntimeServices::reset_system`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ResetType {
+    /// Resets every circuit in the system, returning it to its initial state.
+    Cold = efi::RESET_COLD,
+    /// Reinitializes the system without resetting the processors or memory.
+    Warm = efi::RESET_WARM,
+    /// Places the system in a state where it is powered off.
+    Shutdown = efi::RESET_SHUTDOWN,
+    /// Performs a reset defined by the platform, as identified by the reset data.
+    PlatformSpecific = efi::RESET_PLATFORM_SPECIFIC,
+}
 
 /// The UEFI spec runtime services.
 /// Wrapper around [`efi::RuntimeServices`]
@@ -118,10 +150,7 @@ pub trait RuntimeServices {
     where
         T: AsRef<[u8]> + 'static,
     {
-        if !name.contains(&0) {
-            debug_assert!(false, "Name passed into set_variable is not null-terminated.");
-            return Err(efi::Status::INVALID_PARAMETER);
-        }
+        check_name_terminated(name, "set_variable")?;
 
         // Keep a local copy of name to unburden the caller of having to pass in a mutable slice
         let mut name_vec = name.to_vec();
@@ -129,6 +158,25 @@ pub trait RuntimeServices {
         unsafe { self.set_variable_unchecked(name_vec.as_mut_slice(), namespace, attributes, data.as_ref()) }
     }
 
+    /// Sets a UEFI variable, as [`Self::set_variable`] does, with typed attributes.
+    ///
+    /// Returns `INVALID_PARAMETER` without setting the variable if [`VariableAttributes::validate`] rejects the
+    /// attributes.
+    ///
+    fn set_variable_typed<T>(
+        &self,
+        name: &[u16],
+        namespace: &efi::Guid,
+        attributes: VariableAttributes,
+        data: &T,
+    ) -> Result<(), efi::Status>
+    where
+        T: AsRef<[u8]> + 'static,
+    {
+        attributes.validate()?;
+        self.set_variable(name, namespace, attributes.bits(), data)
+    }
+
     /// Gets a UEFI variable.
     ///
     /// Returns a tuple of (data, attributes)
@@ -144,10 +192,7 @@ pub trait RuntimeServices {
     where
         T: TryFrom<Vec<u8>> + 'static,
     {
-        if !name.contains(&0) {
-            debug_assert!(false, "Name passed into get_variable is not null-terminated.");
-            return Err(efi::Status::INVALID_PARAMETER);
-        }
+        check_name_terminated(name, "get_variable")?;
 
         // Keep a local copy of name to unburden the caller of having to pass in a mutable slice
         let mut name_vec = name.to_vec();
@@ -194,16 +239,77 @@ pub trait RuntimeServices {
         }
     }
 
+    /// Gets a UEFI variable into a caller-provided buffer, without allocating.
+    ///
+    /// Returns a tuple of (data size, attributes), where the data is written to the start of `buf`. Errors are
+    /// returned with the size `buf` must be to hold the variable, which is 0 unless the status is `BUFFER_TOO_SMALL`.
+    /// The name is copied to the stack, as the underlying service takes a mutable name, so names longer than 128
+    /// characters, including the null terminator, return `INVALID_PARAMETER`.
+    ///
+    /// UEFI Spec Documentation: [8.2.1. EFI_RUNTIME_SERVICES.GetVariable()](https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#getvariable)
+    ///
+    fn get_variable_into(
+        &self,
+        name: &[u16],
+        namespace: &efi::Guid,
+        buf: &mut [u8],
+    ) -> Result<(usize, u32), (efi::Status, usize)> {
+        check_name_terminated(name, "get_variable_into").map_err(|status| (status, 0))?;
+
+        let mut name_buffer = [0_u16; VARIABLE_NAME_STACK_LEN];
+        let Some(name_buffer) = name_buffer.get_mut(..name.len()) else {
+            return Err((efi::Status::INVALID_PARAMETER, 0));
+        };
+        name_buffer.copy_from_slice(name);
+
+        let data = if buf.is_empty() { None } else { Some(buf) };
+        // SAFETY: The name was checked to be null-terminated.
+        match unsafe { self.get_variable_unchecked(name_buffer, namespace, data) } {
+            GetVariableStatus::Success { data_size, attributes } => Ok((data_size, attributes)),
+            GetVariableStatus::BufferTooSmall { data_size, .. } => Err((efi::Status::BUFFER_TOO_SMALL, data_size)),
+            GetVariableStatus::Error(e) => Err((e, 0)),
+        }
+    }
+
+    /// Gets a UEFI variable, retrying up to `retries` additional times if the variable store reports
+    /// `DEVICE_ERROR`.
+    ///
+    /// Some flash-backed variable stores transiently return `DEVICE_ERROR` under contention. Each retry is preceded
+    /// by a short busy-wait. Any other error, such as `NOT_FOUND` or `BUFFER_TOO_SMALL`, is returned immediately, as
+    /// is the `DEVICE_ERROR` of the final attempt.
+    ///
+    /// Returns a tuple of (data, attributes)
+    ///
+    fn get_variable_retrying<T>(
+        &self,
+        name: &[u16],
+        namespace: &efi::Guid,
+        retries: usize,
+    ) -> Result<(T, u32), efi::Status>
+    where
+        T: TryFrom<Vec<u8>> + 'static,
+    {
+        let mut remaining = retries;
+        loop {
+            match self.get_variable::<T>(name, namespace, None) {
+                Err(efi::Status::DEVICE_ERROR) if remaining > 0 => {
+                    remaining -= 1;
+                    for _ in 0..GET_VARIABLE_RETRY_DELAY_SPINS {
+                        core::hint::spin_loop();
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Helper function to get a UEFI variable's size and attributes
     fn get_variable_size_and_attributes(
         &self,
         name: &[u16],
         namespace: &efi::Guid,
     ) -> Result<(usize, u32), efi::Status> {
-        if !name.contains(&0) {
-            debug_assert!(false, "Name passed into set_variable is not null-terminated.");
-            return Err(efi::Status::INVALID_PARAMETER);
-        }
+        check_name_terminated(name, "get_variable_size_and_attributes")?;
 
         // Keep a local copy of name to unburden the caller of having to pass in a mutable slice
         let mut name_vec = name.to_vec();
@@ -248,12 +354,72 @@ pub trait RuntimeServices {
         Ok((next_name, next_namespace))
     }
 
+    /// Returns an iterator over the name and namespace of every UEFI variable.
+    ///
+    /// The iterator ends once GetNextVariableName() returns `NOT_FOUND`, and yields any other error as its final
+    /// item.
+    ///
+    fn variables(&self) -> VariableNames<'_, Self> {
+        VariableNames::new(self)
+    }
+
     /// Queries variable information for given UEFI variable attributes.
     ///
     /// UEFI Spec Documentation: [8.2.4. EFI_RUNTIME_SERVICES.QueryVariableInfo()](https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#queryvariableinfo)
     ///
     fn query_variable_info(&self, attributes: u32) -> Result<VariableInfo, efi::Status>;
 
+    /// Returns whether a variable with the given attributes and data size can currently be stored.
+    ///
+    /// `data_len` should include the size of the null-terminated variable name in bytes, as the name is stored with
+    /// the data. The variable is assumed to consume [`variable_services::VARIABLE_STORAGE_OVERHEAD`] bytes of storage
+    /// beyond `data_len`, and fits if that total is within both the remaining variable storage and the maximum
+    /// variable size reported by [`Self::query_variable_info`]. The variable store may still reclaim or fragment
+    /// storage differently, so a `true` result does not guarantee a SetVariable will succeed.
+    ///
+    fn can_store(&self, attributes: u32, data_len: usize) -> Result<bool, efi::Status> {
+        let info = self.query_variable_info(attributes)?;
+
+        let Some(needed) = (data_len as u64).checked_add(variable_services::VARIABLE_STORAGE_OVERHEAD as u64) else {
+            return Ok(false);
+        };
+
+        Ok(needed <= info.remaining_variable_storage_size && needed <= info.maximum_variable_size)
+    }
+
+    /// Converts a pointer from its physical address to its address in the virtual mappings being applied.
+    ///
+    /// This is only usable while SetVirtualAddressMap() is signaling the virtual address change event, such as from
+    /// the event's notify function. A null pointer is left null. See
+    /// [`virtual_address::VirtualAddressChangeRegistry`] to convert pointers automatically.
+    ///
+    /// UEFI Spec Documentation: [8.4.2. EFI_RUNTIME_SERVICES.ConvertPointer()](https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#convertpointer)
+    ///
+    fn convert_pointer<T>(&self, pointer: &mut *mut T) -> Result<(), efi::Status>
+    where
+        T: 'static,
+    {
+        let debug_disposition = if pointer.is_null() { efi::OPTIONAL_POINTER as usize } else { 0 };
+
+        // SAFETY: The address is a valid pointer to a pointer, and a null pointer is marked as optional.
+        unsafe { self.convert_pointer_unchecked(debug_disposition, pointer as *mut *mut T as *mut *mut c_void) }
+    }
+
+    /// Resets the system.
+    ///
+    /// `data` is passed with the reset, such as a description of why `status` is an error, and may be empty. For
+    /// [`ResetType::PlatformSpecific`] it must start with a null-terminated string followed by the GUID of the reset.
+    /// This does not return if the reset is performed.
+    ///
+    /// The default implementation does not reset the system, for implementations that do not support it.
+    ///
+    /// UEFI Spec Documentation: [8.5.1. EFI_RUNTIME_SERVICES.ResetSystem()](https://uefi.org/specs/UEFI/2.10/08_Services_Runtime_Services.html#resetsystem)
+    ///
+    fn reset_system(&self, reset_type: ResetType, status: efi::Status, data: &[u8]) {
+        let _ = (reset_type, status, data);
+        debug_assert!(false, "ResetSystem is not supported by this Runtime Services implementation.");
+    }
+
     /// Set's a UEFI variable
     ///
     /// # Safety
@@ -295,6 +461,23 @@ pub trait RuntimeServices {
         next_name: &mut Vec<u16>,
         next_namespace: &mut efi::Guid,
     ) -> Result<(), efi::Status>;
+
+    /// Converts a pointer to its address in the virtual mappings being applied.
+    ///
+    /// # Safety
+    ///
+    /// Ensure address points to a valid pointer, which is only null if debug_disposition is
+    /// `efi::OPTIONAL_POINTER`.
+    ///
+    /// The default implementation returns `UNSUPPORTED`, for implementations that do not support it.
+    unsafe fn convert_pointer_unchecked(
+        &self,
+        debug_disposition: usize,
+        address: *mut *mut c_void,
+    ) -> Result<(), efi::Status> {
+        let _ = (debug_disposition, address);
+        Err(efi::Status::UNSUPPORTED)
+    }
 }
 
 impl RuntimeServices for StandardRuntimeServices {
@@ -434,13 +617,44 @@ impl RuntimeServices for StandardRuntimeServices {
 
         if status.is_error() { Err(status) } else { Ok(var_info) }
     }
+
+    fn reset_system(&self, reset_type: ResetType, status: efi::Status, data: &[u8]) {
+        let reset_system = self.efi_runtime_services().reset_system;
+        if reset_system as usize == 0 {
+            debug_assert!(false, "ResetSystem has not initialized in the Runtime Services Table.");
+            return;
+        }
+
+        let (data_size, data) =
+            if data.is_empty() { (0, ptr::null_mut()) } else { (data.len(), data.as_ptr() as *mut c_void) };
+
+        reset_system(reset_type as efi::ResetType, status, data_size, data);
+    }
+
+    unsafe fn convert_pointer_unchecked(
+        &self,
+        debug_disposition: usize,
+        address: *mut *mut c_void,
+    ) -> Result<(), efi::Status> {
+        let convert_pointer = self.efi_runtime_services().convert_pointer;
+        if convert_pointer as usize == 0 {
+            debug_assert!(false, "ConvertPointer has not initialized in the Runtime Services Table.");
+            return Err(efi::Status::NOT_FOUND);
+        }
+
+        let status = convert_pointer(debug_disposition, address);
+
+        if status.is_error() { Err(status) } else { Ok(()) }
+    }
 }
 
 #[cfg(test)]
 #[coverage(off)]
 pub(crate) mod test {
+    extern crate std;
     use super::*;
-    use core::{mem, slice};
+    use core::{mem, slice, sync::atomic::AtomicUsize};
+    use std::sync::Mutex;
 
     macro_rules! runtime_services {
         ($($efi_services:ident = $efi_service_fn:ident),*) => {{
@@ -733,6 +947,109 @@ pub(crate) mod test {
         assert_eq!(status.unwrap_err(), efi::Status::NOT_FOUND);
     }
 
+    #[test]
+    fn test_get_variable_into_exact_fit() {
+        let rs = runtime_services!(get_variable = mock_efi_get_variable);
+
+        let mut data = [0_u32; 1];
+        // SAFETY: The buffer is the 4 bytes of `data`, which is aligned for the u32 written by the mock.
+        let buf = unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, DUMMY_DATA_REPR_SIZE) };
+
+        let (size, attributes) = rs.get_variable_into(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, buf).unwrap();
+        assert_eq!(size, DUMMY_DATA_REPR_SIZE);
+        assert_eq!(attributes, DUMMY_ATTRIBUTES);
+        assert_eq!(data[0], DUMMY_DATA);
+    }
+
+    #[test]
+    fn test_get_variable_into_buffer_too_small() {
+        let rs = runtime_services!(get_variable = mock_efi_get_variable);
+
+        let mut buf = [0_u8; DUMMY_DATA_REPR_SIZE - 1];
+        let status = rs.get_variable_into(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, &mut buf);
+        assert_eq!(status, Err((efi::Status::BUFFER_TOO_SMALL, DUMMY_DATA_REPR_SIZE)));
+        assert_eq!(buf, [0; DUMMY_DATA_REPR_SIZE - 1]);
+
+        let status = rs.get_variable_into(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, &mut []);
+        assert_eq!(status, Err((efi::Status::BUFFER_TOO_SMALL, DUMMY_DATA_REPR_SIZE)));
+    }
+
+    #[test]
+    fn test_get_variable_into_name_too_long() {
+        let rs = runtime_services!(get_variable = mock_efi_get_variable);
+
+        let name = [0x41_u16; VARIABLE_NAME_STACK_LEN].into_iter().chain([0]).collect::<Vec<_>>();
+        let mut buf = [0_u8; DUMMY_DATA_REPR_SIZE];
+        let status = rs.get_variable_into(&name, &DUMMY_FIRST_NAMESPACE, &mut buf);
+        assert_eq!(status, Err((efi::Status::INVALID_PARAMETER, 0)));
+    }
+
+    #[test]
+    fn test_get_variable_into_not_found() {
+        let rs = runtime_services!(get_variable = mock_efi_get_variable);
+
+        let mut buf = [0_u8; DUMMY_DATA_REPR_SIZE];
+        let status = rs.get_variable_into(&DUMMY_UNKNOWN_NAME, &DUMMY_FIRST_NAMESPACE, &mut buf);
+        assert_eq!(status, Err((efi::Status::NOT_FOUND, 0)));
+    }
+
+    #[test]
+    fn test_get_variable_retrying_recovers_from_device_error() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        extern "efiapi" fn mock_efi_get_variable_device_error_once(
+            name: *mut u16,
+            namespace: *mut efi::Guid,
+            attributes: *mut u32,
+            data_size: *mut usize,
+            data: *mut c_void,
+        ) -> efi::Status {
+            if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                return efi::Status::DEVICE_ERROR;
+            }
+            mock_efi_get_variable(name, namespace, attributes, data_size, data)
+        }
+
+        let rs = runtime_services!(get_variable = mock_efi_get_variable_device_error_once);
+
+        let (data, attributes) =
+            rs.get_variable_retrying::<DummyVariableType>(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, 3).unwrap();
+        assert_eq!(attributes, DUMMY_ATTRIBUTES);
+        assert_eq!(data.value, DUMMY_DATA);
+        // One failed attempt, then the size query and the data read.
+        assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_get_variable_retrying_gives_up_after_retries() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        extern "efiapi" fn mock_efi_get_variable_device_error(
+            _name: *mut u16,
+            _namespace: *mut efi::Guid,
+            _attributes: *mut u32,
+            _data_size: *mut usize,
+            _data: *mut c_void,
+        ) -> efi::Status {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            efi::Status::DEVICE_ERROR
+        }
+
+        let rs = runtime_services!(get_variable = mock_efi_get_variable_device_error);
+
+        let status = rs.get_variable_retrying::<DummyVariableType>(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, 2);
+        assert_eq!(status.unwrap_err(), efi::Status::DEVICE_ERROR);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_get_variable_retrying_does_not_retry_terminal_errors() {
+        let rs = runtime_services!(get_variable = mock_efi_get_variable);
+
+        let status = rs.get_variable_retrying::<DummyVariableType>(&DUMMY_UNKNOWN_NAME, &DUMMY_FIRST_NAMESPACE, 5);
+        assert_eq!(status.unwrap_err(), efi::Status::NOT_FOUND);
+    }
+
     #[test]
     fn test_get_variable_size_and_attributes() {
         let rs = runtime_services!(get_variable = mock_efi_get_variable);
@@ -757,6 +1074,38 @@ pub(crate) mod test {
         assert!(status.is_ok());
     }
 
+    #[test]
+    fn test_set_variable_typed() {
+        extern "efiapi" fn mock_efi_set_variable_typed(
+            _name: *mut u16,
+            _namespace: *mut efi::Guid,
+            attributes: u32,
+            _data_size: usize,
+            _data: *mut c_void,
+        ) -> efi::Status {
+            assert_eq!(
+                attributes,
+                efi::VARIABLE_NON_VOLATILE | efi::VARIABLE_BOOTSERVICE_ACCESS | efi::VARIABLE_RUNTIME_ACCESS
+            );
+            efi::Status::SUCCESS
+        }
+
+        let rs = runtime_services!(set_variable = mock_efi_set_variable_typed);
+        let data = DummyVariableType { value: DUMMY_DATA };
+
+        let attributes = VariableAttributes::NON_VOLATILE
+            | VariableAttributes::BOOTSERVICE_ACCESS
+            | VariableAttributes::RUNTIME_ACCESS;
+        assert_eq!(rs.set_variable_typed(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, attributes, &data), Ok(()));
+
+        // The mock would fail the test if an invalid combination reached it.
+        let attributes = VariableAttributes::NON_VOLATILE | VariableAttributes::RUNTIME_ACCESS;
+        assert_eq!(
+            rs.set_variable_typed(&DUMMY_FIRST_NAME, &DUMMY_FIRST_NAMESPACE, attributes, &data),
+            Err(efi::Status::INVALID_PARAMETER)
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Name passed into set_variable is not null-terminated.")]
     fn test_set_variable_non_terminated() {
@@ -865,4 +1214,78 @@ pub(crate) mod test {
         assert!(status.is_err());
         assert_eq!(status.unwrap_err(), efi::Status::INVALID_PARAMETER);
     }
+
+    extern "efiapi" fn mock_efi_convert_pointer(debug_disposition: usize, address: *mut *mut c_void) -> efi::Status {
+        let pointer = unsafe { &mut *address };
+        if pointer.is_null() {
+            assert_eq!(debug_disposition, efi::OPTIONAL_POINTER as usize);
+            return efi::Status::SUCCESS;
+        }
+        assert_eq!(debug_disposition, 0);
+        *pointer = (*pointer as usize + 0x8000_0000) as *mut c_void;
+        efi::Status::SUCCESS
+    }
+
+    #[test]
+    fn test_convert_pointer() {
+        let rs = runtime_services!(convert_pointer = mock_efi_convert_pointer);
+
+        let mut pointer = 0x1000 as *mut u32;
+        assert_eq!(rs.convert_pointer(&mut pointer), Ok(()));
+        assert_eq!(pointer as usize, 0x8000_1000);
+
+        let mut pointer = ptr::null_mut::<u32>();
+        assert_eq!(rs.convert_pointer(&mut pointer), Ok(()));
+        assert!(pointer.is_null());
+    }
+
+    static RESET_ARGUMENTS: Mutex<Option<(efi::ResetType, efi::Status, usize, usize)>> = Mutex::new(None);
+
+    extern "efiapi" fn mock_efi_reset_system(
+        reset_type: efi::ResetType,
+        status: efi::Status,
+        data_size: usize,
+        data: *mut c_void,
+    ) {
+        *RESET_ARGUMENTS.lock().unwrap() = Some((reset_type, status, data_size, data as usize));
+    }
+
+    #[test]
+    fn test_reset_system() {
+        let rs = runtime_services!(reset_system = mock_efi_reset_system);
+        let data = [1_u8, 2, 3];
+
+        rs.reset_system(ResetType::Warm, efi::Status::SUCCESS, &[]);
+        assert_eq!(RESET_ARGUMENTS.lock().unwrap().take(), Some((efi::RESET_WARM, efi::Status::SUCCESS, 0, 0)));
+
+        rs.reset_system(ResetType::PlatformSpecific, efi::Status::ABORTED, &data);
+        assert_eq!(
+            RESET_ARGUMENTS.lock().unwrap().take(),
+            Some((efi::RESET_PLATFORM_SPECIFIC, efi::Status::ABORTED, data.len(), data.as_ptr() as usize))
+        );
+    }
+
+    #[test]
+    fn test_can_store_when_variable_fits() {
+        let rs = runtime_services!(query_variable_info = mock_efi_query_variable_info);
+
+        assert_eq!(rs.can_store(DUMMY_ATTRIBUTES, DUMMY_DATA_REPR_SIZE), Ok(true));
+    }
+
+    #[test]
+    fn test_can_store_when_variable_does_not_fit() {
+        let rs = runtime_services!(query_variable_info = mock_efi_query_variable_info);
+
+        // The data alone would fill the remaining storage, leaving no room for the variable header.
+        let data_len = DUMMY_REMAINING_VARIABLE_STORAGE_SIZE as usize;
+        assert_eq!(rs.can_store(DUMMY_ATTRIBUTES, data_len), Ok(false));
+        assert_eq!(rs.can_store(DUMMY_ATTRIBUTES, usize::MAX), Ok(false));
+    }
+
+    #[test]
+    fn test_can_store_invalid_attributes() {
+        let rs = runtime_services!(query_variable_info = mock_efi_query_variable_info);
+
+        assert_eq!(rs.can_store(DUMMY_INVALID_ATTRIBUTES, DUMMY_DATA_REPR_SIZE), Err(efi::Status::INVALID_PARAMETER));
+    }
 }
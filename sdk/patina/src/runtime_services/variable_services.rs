@@ -1,4 +1,7 @@
-use core::mem;
+use core::{
+    mem,
+    ops::{BitOr, BitOrAssign},
+};
 
 use alloc::{vec, vec::Vec};
 use fallible_streaming_iterator::FallibleStreamingIterator;
@@ -27,6 +30,133 @@ pub enum GetVariableStatus {
     },
 }
 
+/// The storage assumed to be consumed by a variable beyond its data, used by [`RuntimeServices::can_store`]
+///
+/// This is the size of the EDK II authenticated variable header (60 bytes), the largest header used by the EDK II
+/// variable store, rounded up to account for alignment padding between variables. The variable name is stored with
+/// the data and is not included.
+pub const VARIABLE_STORAGE_OVERHEAD: usize = 64;
+
+/// The attributes a variable is set with by [`RuntimeServices::set_variable_typed`]
+///
+/// [`VariableAttributes::validate`] rejects the combinations the UEFI spec does not allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct VariableAttributes(u32);
+
+impl VariableAttributes {
+    /// The variable is stored in non-volatile storage, and persists across resets.
+    pub const NON_VOLATILE: VariableAttributes = VariableAttributes(efi::VARIABLE_NON_VOLATILE);
+    /// The variable is accessible before ExitBootServices().
+    pub const BOOTSERVICE_ACCESS: VariableAttributes = VariableAttributes(efi::VARIABLE_BOOTSERVICE_ACCESS);
+    /// The variable is accessible after ExitBootServices(). Requires [`Self::BOOTSERVICE_ACCESS`].
+    pub const RUNTIME_ACCESS: VariableAttributes = VariableAttributes(efi::VARIABLE_RUNTIME_ACCESS);
+    /// The variable is a hardware error record. Requires [`Self::NON_VOLATILE`], [`Self::BOOTSERVICE_ACCESS`] and
+    /// [`Self::RUNTIME_ACCESS`].
+    pub const HARDWARE_ERROR_RECORD: VariableAttributes = VariableAttributes(efi::VARIABLE_HARDWARE_ERROR_RECORD);
+    /// The variable is written with count-based authentication. This is deprecated, and firmware should return
+    /// `UNSUPPORTED` when it is set.
+    pub const AUTHENTICATED_WRITE_ACCESS: VariableAttributes =
+        VariableAttributes(efi::VARIABLE_AUTHENTICATED_WRITE_ACCESS);
+    /// The variable is written with time-based authentication.
+    pub const TIME_BASED_AUTHENTICATED_WRITE_ACCESS: VariableAttributes =
+        VariableAttributes(efi::VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS);
+    /// The data is appended to the variable rather than replacing it.
+    pub const APPEND_WRITE: VariableAttributes = VariableAttributes(efi::VARIABLE_APPEND_WRITE);
+    /// The variable is written with enhanced authentication.
+    pub const ENHANCED_AUTHENTICATED_ACCESS: VariableAttributes =
+        VariableAttributes(efi::VARIABLE_ENHANCED_AUTHENTICATED_ACCESS);
+
+    /// Every attribute defined by the UEFI spec.
+    const ALL: VariableAttributes = VariableAttributes(
+        efi::VARIABLE_NON_VOLATILE
+            | efi::VARIABLE_BOOTSERVICE_ACCESS
+            | efi::VARIABLE_RUNTIME_ACCESS
+            | efi::VARIABLE_HARDWARE_ERROR_RECORD
+            | efi::VARIABLE_APPEND_WRITE
+            | Self::AUTHENTICATION.0,
+    );
+
+    /// The attributes selecting how writes to the variable are authenticated, of which at most one may be set.
+    const AUTHENTICATION: VariableAttributes = VariableAttributes(
+        efi::VARIABLE_AUTHENTICATED_WRITE_ACCESS
+            | efi::VARIABLE_TIME_BASED_AUTHENTICATED_WRITE_ACCESS
+            | efi::VARIABLE_ENHANCED_AUTHENTICATED_ACCESS,
+    );
+
+    /// Returns a set of no attributes.
+    pub const fn empty() -> Self {
+        VariableAttributes(0)
+    }
+
+    /// Returns the attributes of a `u32` attribute mask.
+    pub const fn from_bits(bits: u32) -> Self {
+        VariableAttributes(bits)
+    }
+
+    /// Returns the `u32` attribute mask.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether all of `other` is set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether any of `other` is set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Checks that the attributes are a combination the UEFI spec allows.
+    ///
+    /// Returns `INVALID_PARAMETER` if:
+    /// - a bit not defined by the UEFI spec is set.
+    /// - [`Self::RUNTIME_ACCESS`] is set without [`Self::BOOTSERVICE_ACCESS`].
+    /// - [`Self::HARDWARE_ERROR_RECORD`] is set without all of [`Self::NON_VOLATILE`], [`Self::BOOTSERVICE_ACCESS`]
+    ///   and [`Self::RUNTIME_ACCESS`].
+    /// - more than one of [`Self::AUTHENTICATED_WRITE_ACCESS`], [`Self::TIME_BASED_AUTHENTICATED_WRITE_ACCESS`] and
+    ///   [`Self::ENHANCED_AUTHENTICATED_ACCESS`] is set.
+    ///
+    /// Firmware may reject further combinations, such as any it does not support.
+    pub const fn validate(self) -> Result<(), efi::Status> {
+        let undefined = self.0 & !Self::ALL.0 != 0;
+        let runtime_without_boot_services =
+            self.contains(Self::RUNTIME_ACCESS) && !self.contains(Self::BOOTSERVICE_ACCESS);
+        let incomplete_hardware_error_record = self.contains(Self::HARDWARE_ERROR_RECORD)
+            && !self.contains(VariableAttributes(
+                efi::VARIABLE_NON_VOLATILE | efi::VARIABLE_BOOTSERVICE_ACCESS | efi::VARIABLE_RUNTIME_ACCESS,
+            ));
+        let multiple_authentications = (self.0 & Self::AUTHENTICATION.0).count_ones() > 1;
+
+        if undefined || runtime_without_boot_services || incomplete_hardware_error_record || multiple_authentications {
+            return Err(efi::Status::INVALID_PARAMETER);
+        }
+        Ok(())
+    }
+}
+
+impl BitOr for VariableAttributes {
+    type Output = VariableAttributes;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        VariableAttributes(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for VariableAttributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<VariableAttributes> for u32 {
+    fn from(val: VariableAttributes) -> Self {
+        val.0
+    }
+}
+
 /// Variable information returned by [`RuntimeServices::query_variable_info`]
 #[derive(Debug)]
 pub struct VariableInfo {
@@ -76,7 +206,7 @@ pub struct VariableIdentifier {
 /// }
 /// ```
 #[derive(Debug)]
-pub struct VariableNameIterator<'a, R: RuntimeServices> {
+pub struct VariableNameIterator<'a, R: RuntimeServices + ?Sized> {
     rs: &'a R,
 
     current: VariableIdentifier,
@@ -84,7 +214,7 @@ pub struct VariableNameIterator<'a, R: RuntimeServices> {
     finished: bool,
 }
 
-impl<'a, R: RuntimeServices> VariableNameIterator<'a, R> {
+impl<'a, R: RuntimeServices + ?Sized> VariableNameIterator<'a, R> {
     /// Produce a new iterator from the beginning of the UEFI variable list
     pub fn new_from_first(runtime_services: &'a R) -> Self {
         Self {
@@ -116,7 +246,7 @@ impl<'a, R: RuntimeServices> VariableNameIterator<'a, R> {
     }
 }
 
-impl<R: RuntimeServices> FallibleStreamingIterator for VariableNameIterator<'_, R> {
+impl<R: RuntimeServices + ?Sized> FallibleStreamingIterator for VariableNameIterator<'_, R> {
     type Item = VariableIdentifier;
     type Error = efi::Status;
 
@@ -150,6 +280,40 @@ impl<R: RuntimeServices> FallibleStreamingIterator for VariableNameIterator<'_,
     }
 }
 
+/// An [`Iterator`] over the names and namespaces of all UEFI variables, returned by [`RuntimeServices::variables`]
+///
+/// This adapts a [`VariableNameIterator`] started from the first variable, copying out each name. Iteration ends once
+/// the variable list is exhausted. Any other error is yielded as a final `Err`.
+#[derive(Debug)]
+pub struct VariableNames<'a, R: RuntimeServices + ?Sized> {
+    names: VariableNameIterator<'a, R>,
+    failed: bool,
+}
+
+impl<'a, R: RuntimeServices + ?Sized> VariableNames<'a, R> {
+    pub(super) fn new(runtime_services: &'a R) -> Self {
+        Self { names: VariableNameIterator::new_from_first(runtime_services), failed: false }
+    }
+}
+
+impl<R: RuntimeServices + ?Sized> Iterator for VariableNames<'_, R> {
+    type Item = Result<(Vec<u16>, efi::Guid), efi::Status>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.names.next() {
+            Ok(variable) => variable.map(|variable| Ok((variable.name.clone(), variable.namespace))),
+            Err(status) => {
+                self.failed = true;
+                Some(Err(status))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 #[coverage(off)]
 mod test {
@@ -165,6 +329,38 @@ mod test {
     };
     use std::mem;
 
+    #[test]
+    fn test_variable_attributes_validate_allows_legal_combinations() {
+        type A = VariableAttributes;
+        let legal = [
+            A::empty(),
+            A::BOOTSERVICE_ACCESS,
+            A::NON_VOLATILE | A::BOOTSERVICE_ACCESS | A::RUNTIME_ACCESS,
+            A::NON_VOLATILE | A::BOOTSERVICE_ACCESS | A::RUNTIME_ACCESS | A::HARDWARE_ERROR_RECORD,
+            A::NON_VOLATILE | A::BOOTSERVICE_ACCESS | A::TIME_BASED_AUTHENTICATED_WRITE_ACCESS | A::APPEND_WRITE,
+        ];
+        for attributes in legal {
+            assert_eq!(attributes.validate(), Ok(()), "{attributes:?}");
+        }
+    }
+
+    #[test]
+    fn test_variable_attributes_validate_rejects_illegal_combinations() {
+        type A = VariableAttributes;
+        let illegal = [
+            A::RUNTIME_ACCESS,
+            A::NON_VOLATILE | A::RUNTIME_ACCESS,
+            A::HARDWARE_ERROR_RECORD | A::RUNTIME_ACCESS,
+            A::HARDWARE_ERROR_RECORD | A::BOOTSERVICE_ACCESS | A::RUNTIME_ACCESS,
+            A::BOOTSERVICE_ACCESS | A::AUTHENTICATED_WRITE_ACCESS | A::TIME_BASED_AUTHENTICATED_WRITE_ACCESS,
+            A::BOOTSERVICE_ACCESS | A::TIME_BASED_AUTHENTICATED_WRITE_ACCESS | A::ENHANCED_AUTHENTICATED_ACCESS,
+            A::from_bits(efi::VARIABLE_BOOTSERVICE_ACCESS | 0x100),
+        ];
+        for attributes in illegal {
+            assert_eq!(attributes.validate(), Err(efi::Status::INVALID_PARAMETER), "{attributes:?}");
+        }
+    }
+
     #[test]
     fn test_variable_name_iterator_from_first() {
         let rs = runtime_services!(get_next_variable_name = mock_efi_get_next_variable_name);
@@ -193,6 +389,34 @@ mod test {
         assert!(status.unwrap().is_none());
     }
 
+    #[test]
+    fn test_variables_enumerates_every_variable() {
+        let rs = runtime_services!(get_next_variable_name = mock_efi_get_next_variable_name);
+
+        let mut variables = rs.variables();
+        assert_eq!(variables.next(), Some(Ok((DUMMY_FIRST_NAME.to_vec(), DUMMY_FIRST_NAMESPACE))));
+        assert_eq!(variables.next(), Some(Ok((DUMMY_SECOND_NAME.to_vec(), DUMMY_SECOND_NAMESPACE))));
+        assert_eq!(variables.next(), None);
+        assert_eq!(variables.next(), None);
+    }
+
+    #[test]
+    fn test_variables_yields_a_final_error() {
+        extern "efiapi" fn mock_efi_get_next_variable_name_device_error(
+            _name_size: *mut usize,
+            _name: *mut u16,
+            _namespace: *mut efi::Guid,
+        ) -> efi::Status {
+            efi::Status::DEVICE_ERROR
+        }
+
+        let rs = runtime_services!(get_next_variable_name = mock_efi_get_next_variable_name_device_error);
+
+        let mut variables = rs.variables();
+        assert_eq!(variables.next(), Some(Err(efi::Status::DEVICE_ERROR)));
+        assert_eq!(variables.next(), None);
+    }
+
     #[test]
     fn test_variable_name_iterator_from_second() {
         let rs = runtime_services!(get_next_variable_name = mock_efi_get_next_variable_name);
@@ -0,0 +1,196 @@
+//! Conversion of pointers to their virtual addresses when SetVirtualAddressMap() is called.
+//!
+//! A runtime driver keeps using the pointers it holds after the OS switches the firmware to virtual addressing, so
+//! each such pointer must be converted while the virtual address change event is signaled. A
+//! [`VirtualAddressChangeRegistry`] converts every pointer registered with it from that event.
+//!
+//! ```ignore
+//! static BUFFER: AtomicPtr<u8> = AtomicPtr::new(ptr::null_mut());
+//! static REGISTRY: VirtualAddressChangeRegistry<StandardRuntimeServices, 4> =
+//!     VirtualAddressChangeRegistry::new(&RUNTIME_SERVICES);
+//!
+//! REGISTRY.register(&BUFFER)?;
+//! REGISTRY.create_event(&boot_services)?;
+//! ```
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use core::{
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+use r_efi::efi;
+
+use super::RuntimeServices;
+use crate::boot_services::{BootServices, event::EventType, tpl::Tpl};
+
+/// Pointers converted to their virtual addresses when the virtual address change event is signaled.
+///
+/// The registry holds at most `N` pointers, so it needs no allocation. As it is used during SetVirtualAddressMap(),
+/// after ExitBootServices(), the registry and the registered pointers must be in runtime memory, such as statics of a
+/// runtime driver.
+pub struct VirtualAddressChangeRegistry<R, const N: usize>
+where
+    R: RuntimeServices + 'static,
+{
+    runtime_services: &'static R,
+    pointers: [AtomicPtr<AtomicPtr<c_void>>; N],
+    len: AtomicUsize,
+}
+
+impl<R, const N: usize> VirtualAddressChangeRegistry<R, N>
+where
+    R: RuntimeServices + 'static,
+{
+    /// Creates an empty registry, converting pointers with `runtime_services`.
+    pub const fn new(runtime_services: &'static R) -> Self {
+        Self { runtime_services, pointers: [const { AtomicPtr::new(ptr::null_mut()) }; N], len: AtomicUsize::new(0) }
+    }
+
+    /// Registers a pointer to be converted when the virtual address change event is signaled.
+    ///
+    /// Returns `OUT_OF_RESOURCES` if `N` pointers are already registered.
+    pub fn register<T>(&self, pointer: &'static AtomicPtr<T>) -> Result<(), efi::Status> {
+        let index = self
+            .len
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |len| (len < N).then_some(len + 1))
+            .map_err(|_| efi::Status::OUT_OF_RESOURCES)?;
+        self.pointers[index].store(ptr::from_ref(pointer) as *mut AtomicPtr<c_void>, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the number of registered pointers.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns whether no pointers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Creates the virtual address change event that converts the registered pointers.
+    pub fn create_event(&'static self, boot_services: &impl BootServices) -> Result<efi::Event, efi::Status> {
+        boot_services.create_event(
+            EventType::SIGNAL_VIRTUAL_ADDRESS_CHANGE,
+            Tpl::NOTIFY,
+            Some(Self::virtual_address_change_notify),
+            self,
+        )
+    }
+
+    /// Converts every registered pointer to its virtual address.
+    ///
+    /// Every pointer is converted even if one fails, and the first failure is returned.
+    pub fn convert_pointers(&self) -> Result<(), efi::Status> {
+        let mut result = Ok(());
+        for pointer in &self.pointers[..self.len()] {
+            // SAFETY: Only `&'static AtomicPtr<T>` are registered, and `AtomicPtr<T>` has the same layout for any `T`.
+            let Some(pointer) = (unsafe { pointer.load(Ordering::Acquire).as_ref() }) else {
+                // The pointer is still being registered.
+                continue;
+            };
+            let mut address = pointer.load(Ordering::Acquire);
+            match self.runtime_services.convert_pointer(&mut address) {
+                Ok(()) => pointer.store(address, Ordering::Release),
+                Err(status) => result = result.and(Err(status)),
+            }
+        }
+        result
+    }
+
+    extern "efiapi" fn virtual_address_change_notify(_event: efi::Event, registry: &'static Self) {
+        // Nothing can be reported this late, so a pointer that fails to convert is left as is.
+        let _ = registry.convert_pointers();
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod test {
+    extern crate std;
+    use std::boxed::Box;
+
+    use super::*;
+    use crate::{boot_services::MockBootServices, runtime_services::MockRuntimeServices};
+
+    const VIRTUAL_OFFSET: usize = 0x8000_0000;
+
+    type Registry = VirtualAddressChangeRegistry<MockRuntimeServices, 2>;
+
+    fn registry(runtime_services: MockRuntimeServices) -> &'static Registry {
+        Box::leak(Box::new(Registry::new(Box::leak(Box::new(runtime_services)))))
+    }
+
+    fn leaked_pointer(address: usize) -> &'static AtomicPtr<u64> {
+        Box::leak(Box::new(AtomicPtr::new(address as *mut u64)))
+    }
+
+    #[test]
+    fn test_registered_pointers_are_converted() {
+        let mut runtime_services = MockRuntimeServices::new();
+        runtime_services.expect_convert_pointer::<c_void>().times(2).returning(|pointer| {
+            *pointer = (*pointer as usize + VIRTUAL_OFFSET) as *mut c_void;
+            Ok(())
+        });
+        let registry = registry(runtime_services);
+
+        let first = leaked_pointer(0x1000);
+        let second = leaked_pointer(0x2000);
+        assert!(registry.is_empty());
+        registry.register(first).unwrap();
+        registry.register(second).unwrap();
+        assert_eq!(registry.register(leaked_pointer(0x3000)), Err(efi::Status::OUT_OF_RESOURCES));
+        assert_eq!(registry.len(), 2);
+
+        Registry::virtual_address_change_notify(ptr::null_mut(), registry);
+        assert_eq!(first.load(Ordering::Relaxed) as usize, 0x1000 + VIRTUAL_OFFSET);
+        assert_eq!(second.load(Ordering::Relaxed) as usize, 0x2000 + VIRTUAL_OFFSET);
+    }
+
+    #[test]
+    fn test_failed_conversion_leaves_the_pointer_and_converts_the_rest() {
+        let mut runtime_services = MockRuntimeServices::new();
+        runtime_services.expect_convert_pointer::<c_void>().times(2).returning(|pointer| {
+            if *pointer as usize == 0x1000 {
+                return Err(efi::Status::NOT_FOUND);
+            }
+            *pointer = (*pointer as usize + VIRTUAL_OFFSET) as *mut c_void;
+            Ok(())
+        });
+        let registry = registry(runtime_services);
+
+        let first = leaked_pointer(0x1000);
+        let second = leaked_pointer(0x2000);
+        registry.register(first).unwrap();
+        registry.register(second).unwrap();
+
+        assert_eq!(registry.convert_pointers(), Err(efi::Status::NOT_FOUND));
+        assert_eq!(first.load(Ordering::Relaxed) as usize, 0x1000);
+        assert_eq!(second.load(Ordering::Relaxed) as usize, 0x2000 + VIRTUAL_OFFSET);
+    }
+
+    #[test]
+    fn test_create_event_registers_virtual_address_change_event() {
+        let registry = registry(MockRuntimeServices::new());
+
+        let mut boot_services = MockBootServices::new();
+        boot_services
+            .expect_create_event::<&'static Registry>()
+            .once()
+            .withf(|event_type, tpl, notify_function, _| {
+                *event_type == EventType::SIGNAL_VIRTUAL_ADDRESS_CHANGE
+                    && *tpl == Tpl::NOTIFY
+                    && notify_function.is_some()
+            })
+            .returning(|_, _, _, _| Ok(1_usize as efi::Event));
+
+        assert_eq!(registry.create_event(&boot_services), Ok(1_usize as efi::Event));
+    }
+}
@@ -13,14 +13,31 @@ pub trait SerialIO: Sync {
     fn init(&self);
     /// Write a buffer to the serial port.
     fn write(&self, buffer: &[u8]);
+    /// Write a buffer to the serial port, returning an error if the port did not accept it.
+    ///
+    /// Calls [`SerialIO::write`] and returns `Ok` by default, for devices that cannot detect a failed write.
+    fn try_write(&self, buffer: &[u8]) -> crate::error::Result<()> {
+        self.write(buffer);
+        Ok(())
+    }
     /// Read a byte from the serial port, blocking until a byte is available.
     fn read(&self) -> u8;
     /// Try to read a byte from the serial port, returning `None` if no byte is available.
     fn try_read(&self) -> Option<u8>;
+    /// Change the baud rate of an initialized serial port.
+    ///
+    /// Returns [`EfiError::Unsupported`](crate::error::EfiError::Unsupported) by default, for devices that do not
+    /// support changing the baud rate at runtime.
+    fn set_baud_rate(&self, _baud_rate: u32) -> crate::error::Result<()> {
+        Err(crate::error::EfiError::Unsupported)
+    }
 }
 
 pub mod uart;
 
+mod line_buffered;
+pub use line_buffered::LineBuffered;
+
 #[cfg(feature = "std")]
 mod std;
 #[cfg(feature = "std")]
@@ -0,0 +1,271 @@
+//! A [SerialIO](crate::serial::SerialIO) adapter that writes whole lines.
+//!
+//! ## License
+//!
+//! Copyright (C) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::SerialIO;
+
+/// Buffers writes to a serial port, passing them on a line at a time.
+///
+/// Bytes are held in an `N` byte buffer until a newline is written or the buffer is full, so the port sees fewer,
+/// line-aligned writes that are not interleaved with other writers mid-line. Remaining bytes are written by
+/// [`LineBuffered::flush`], before reading from the port or changing its baud rate, so a prompt is shown before its
+/// input is read, or when the adapter is dropped.
+///
+/// A write made while another is in progress, such as from an interrupt, goes directly to the port rather than
+/// waiting for the buffer.
+pub struct LineBuffered<T: SerialIO, const N: usize = 128> {
+    inner: T,
+    buffer: UnsafeCell<([u8; N], usize)>,
+    busy: AtomicBool,
+}
+
+// SAFETY: The buffer is only accessed while `busy` is held.
+unsafe impl<T: SerialIO, const N: usize> Sync for LineBuffered<T, N> {}
+
+impl<T: SerialIO, const N: usize> LineBuffered<T, N> {
+    /// Creates an adapter buffering writes to `inner`.
+    pub const fn new(inner: T) -> Self {
+        const { assert!(N > 0, "The line buffer must not be empty.") };
+        Self { inner, buffer: UnsafeCell::new(([0; N], 0)), busy: AtomicBool::new(false) }
+    }
+
+    /// Returns the wrapped serial port.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Writes any buffered bytes to the serial port.
+    pub fn flush(&self) {
+        self.with_buffer(|buffer, len| self.flush_buffer(buffer, len));
+    }
+
+    /// Runs `f` with the buffer, returning `None` if another write holds it.
+    fn with_buffer<R>(&self, f: impl FnOnce(&mut [u8; N], &mut usize) -> R) -> Option<R> {
+        if self.busy.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return None;
+        }
+        // SAFETY: `busy` is held, so nothing else accesses the buffer.
+        let (buffer, len) = unsafe { &mut *self.buffer.get() };
+        let result = f(buffer, len);
+        self.busy.store(false, Ordering::Release);
+        Some(result)
+    }
+
+    fn flush_buffer(&self, buffer: &[u8; N], len: &mut usize) {
+        if *len > 0 {
+            self.inner.write(&buffer[..*len]);
+            *len = 0;
+        }
+    }
+
+    /// Buffers `bytes`, passing each completed line or full buffer to `emit` to write it to the port.
+    ///
+    /// A line that `emit` fails to write is dropped, and the first failure is returned.
+    fn write_with(
+        &self,
+        bytes: &[u8],
+        emit: impl Fn(&T, &[u8]) -> crate::error::Result<()>,
+    ) -> crate::error::Result<()> {
+        let buffered = self.with_buffer(|buffer, len| {
+            let mut result = Ok(());
+            let mut rest = bytes;
+            while !rest.is_empty() {
+                let line_len = rest.iter().position(|&byte| byte == b'\n').map(|newline| newline + 1);
+                let count = line_len.unwrap_or(rest.len()).min(N - *len);
+                buffer[*len..*len + count].copy_from_slice(&rest[..count]);
+                *len += count;
+                rest = &rest[count..];
+
+                if *len == N || line_len == Some(count) {
+                    result = result.and(emit(&self.inner, &buffer[..*len]));
+                    *len = 0;
+                }
+            }
+            result
+        });
+
+        buffered.unwrap_or_else(|| emit(&self.inner, bytes))
+    }
+}
+
+impl<T: SerialIO, const N: usize> SerialIO for LineBuffered<T, N> {
+    fn init(&self) {
+        self.inner.init();
+    }
+
+    fn write(&self, bytes: &[u8]) {
+        let _ = self.write_with(bytes, |inner, bytes| {
+            inner.write(bytes);
+            Ok(())
+        });
+    }
+
+    fn try_write(&self, bytes: &[u8]) -> crate::error::Result<()> {
+        self.write_with(bytes, |inner, bytes| inner.try_write(bytes))
+    }
+
+    fn read(&self) -> u8 {
+        self.flush();
+        self.inner.read()
+    }
+
+    fn try_read(&self) -> Option<u8> {
+        self.flush();
+        self.inner.try_read()
+    }
+
+    fn set_baud_rate(&self, baud_rate: u32) -> crate::error::Result<()> {
+        // Bytes buffered at the old rate are written at it, rather than garbled at the new one.
+        self.flush();
+        self.inner.set_baud_rate(baud_rate)
+    }
+}
+
+impl<T: SerialIO, const N: usize> Drop for LineBuffered<T, N> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+#[coverage(off)]
+mod tests {
+    extern crate std;
+    use std::{sync::Mutex, vec, vec::Vec};
+
+    use super::*;
+
+    /// Records each write made to it.
+    #[derive(Default)]
+    struct RecordingPort {
+        writes: Mutex<Vec<Vec<u8>>>,
+        failing: AtomicBool,
+    }
+
+    impl SerialIO for &RecordingPort {
+        fn init(&self) {}
+
+        fn write(&self, buffer: &[u8]) {
+            self.writes.lock().unwrap().push(buffer.to_vec());
+        }
+
+        fn try_write(&self, buffer: &[u8]) -> crate::error::Result<()> {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(crate::error::EfiError::DeviceError);
+            }
+            self.write(buffer);
+            Ok(())
+        }
+
+        fn read(&self) -> u8 {
+            0
+        }
+
+        fn try_read(&self) -> Option<u8> {
+            None
+        }
+    }
+
+    impl RecordingPort {
+        fn take(&self) -> Vec<Vec<u8>> {
+            core::mem::take(&mut self.writes.lock().unwrap())
+        }
+    }
+
+    #[test]
+    fn test_writes_are_emitted_a_line_at_a_time() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 16>::new(&port);
+
+        for byte in b"one\ntwo" {
+            serial.write(core::slice::from_ref(byte));
+        }
+        assert_eq!(port.take(), vec![b"one\n".to_vec()]);
+
+        serial.write(b"\nthree\nfour\nfi");
+        assert_eq!(port.take(), vec![b"two\n".to_vec(), b"three\n".to_vec(), b"four\n".to_vec()]);
+
+        serial.flush();
+        assert_eq!(port.take(), vec![b"fi".to_vec()]);
+        serial.flush();
+        assert!(port.take().is_empty());
+    }
+
+    #[test]
+    fn test_long_lines_are_emitted_when_the_buffer_is_full() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 4>::new(&port);
+
+        serial.write(b"abcdefghij\n");
+        assert_eq!(port.take(), vec![b"abcd".to_vec(), b"efgh".to_vec(), b"ij\n".to_vec()]);
+    }
+
+    #[test]
+    fn test_remaining_bytes_are_emitted_on_drop() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 16>::new(&port);
+
+        serial.write(b"partial");
+        assert!(port.take().is_empty());
+        drop(serial);
+        assert_eq!(port.take(), vec![b"partial".to_vec()]);
+    }
+
+    #[test]
+    fn test_pending_line_is_emitted_before_reading_or_changing_the_baud_rate() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 16>::new(&port);
+
+        serial.write(b"prompt> ");
+        assert_eq!(serial.read(), 0);
+        assert_eq!(port.take(), vec![b"prompt> ".to_vec()]);
+
+        serial.write(b"again> ");
+        assert_eq!(serial.try_read(), None);
+        assert_eq!(port.take(), vec![b"again> ".to_vec()]);
+
+        serial.write(b"old rate");
+        let _ = serial.set_baud_rate(115200);
+        assert_eq!(port.take(), vec![b"old rate".to_vec()]);
+    }
+
+    #[test]
+    fn test_writes_during_a_write_bypass_the_buffer() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 16>::new(&port);
+
+        serial.write(b"held");
+        serial.with_buffer(|_, _| serial.write(b"nested\n")).unwrap();
+        assert_eq!(port.take(), vec![b"nested\n".to_vec()]);
+        serial.flush();
+        assert_eq!(port.take(), vec![b"held".to_vec()]);
+    }
+
+    #[test]
+    fn test_try_write_reports_the_port_failing_a_line() {
+        let port = RecordingPort::default();
+        let serial = LineBuffered::<_, 16>::new(&port);
+
+        assert_eq!(serial.try_write(b"one\ntw"), Ok(()));
+        assert_eq!(port.take(), vec![b"one\n".to_vec()]);
+
+        port.failing.store(true, Ordering::Relaxed);
+        assert_eq!(serial.try_write(b"o"), Ok(()));
+        assert_eq!(serial.try_write(b"\nthree\n"), Err(crate::error::EfiError::DeviceError));
+        assert!(port.take().is_empty());
+
+        // The failed lines are dropped rather than written again.
+        port.failing.store(false, Ordering::Relaxed);
+        assert_eq!(serial.try_write(b"four\n"), Ok(()));
+        assert_eq!(port.take(), vec![b"four\n".to_vec()]);
+    }
+}
@@ -32,7 +32,22 @@ cfg_if::cfg_if! {
 
         use uart_16550::MmioSerialPort;
         use uart_16550::SerialPort as IoSerialPort;
-        use x86_64::instructions::interrupts;
+        use x86_64::instructions::{interrupts, port::Port};
+
+        use crate::error::EfiError;
+
+        /// The UART input clock divided by 16, i.e. the baud rate produced by a divisor of 1.
+        const UART_16550_MAX_BAUD_RATE: u32 = 115200;
+        /// Offset of the line control register.
+        const UART_16550_LCR_OFFSET: usize = 3;
+        /// Divisor latch access bit in the line control register.
+        const UART_16550_LCR_DLAB: u8 = 1 << 7;
+        /// Offset of the line status register.
+        const UART_16550_LSR_OFFSET: usize = 5;
+        /// Transmitter holding register empty bit in the line status register.
+        const UART_16550_LSR_THRE: u8 = 1 << 5;
+        /// Number of times the line status register is polled for room to send a byte before a write fails.
+        const UART_16550_TX_POLL_COUNT: usize = 1_000_000;
 
         /// An interface for writing to a Uart16550 device.
         #[derive(Debug)]
@@ -51,6 +66,39 @@ cfg_if::cfg_if! {
             },
         }
 
+        impl Uart16550 {
+            /// Sends a byte, failing with [`EfiError::Timeout`] if the transmitter does not become ready.
+            fn try_send(&self, byte: u8) -> crate::error::Result<()> {
+                match self {
+                    Uart16550::Io { base } => {
+                        let mut lsr = Port::<u8>::new(*base + UART_16550_LSR_OFFSET as u16);
+                        let mut data = Port::<u8>::new(*base);
+                        // SAFETY: `base` is the I/O port base of a 16550 UART, so these are its line status and
+                        // transmit holding registers.
+                        unsafe {
+                            if !(0..UART_16550_TX_POLL_COUNT).any(|_| lsr.read() & UART_16550_LSR_THRE != 0) {
+                                return Err(EfiError::Timeout);
+                            }
+                            data.write(byte);
+                        }
+                    }
+                    Uart16550::Mmio { base, reg_stride } => {
+                        let lsr = (*base + UART_16550_LSR_OFFSET * *reg_stride) as *const u8;
+                        let data = *base as *mut u8;
+                        // SAFETY: `base` and `reg_stride` describe the mapped registers of a 16550 UART, so these are
+                        // its line status and transmit holding registers.
+                        unsafe {
+                            if !(0..UART_16550_TX_POLL_COUNT).any(|_| lsr.read_volatile() & UART_16550_LSR_THRE != 0) {
+                                return Err(EfiError::Timeout);
+                            }
+                            data.write_volatile(byte);
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+
         impl super::SerialIO for Uart16550 {
             fn init(&self) {
                 match self {
@@ -86,6 +134,10 @@ cfg_if::cfg_if! {
                 }
             }
 
+            fn try_write(&self, buffer: &[u8]) -> crate::error::Result<()> {
+                interrupts::without_interrupts(|| buffer.iter().try_for_each(|byte| self.try_send(*byte)))
+            }
+
             fn read(&self) -> u8 {
                 match self {
                     Uart16550::Io { base } => {
@@ -112,6 +164,46 @@ cfg_if::cfg_if! {
                 }
             }
 
+            fn set_baud_rate(&self, baud_rate: u32) -> crate::error::Result<()> {
+                let divisor = UART_16550_MAX_BAUD_RATE.checked_div(baud_rate).ok_or(EfiError::InvalidParameter)?;
+                let [divisor_low, divisor_high] = match u16::try_from(divisor) {
+                    Ok(divisor) if divisor != 0 => divisor.to_le_bytes(),
+                    _ => return Err(EfiError::InvalidParameter),
+                };
+
+                // Registers 0 and 1 become the divisor latch while DLAB is set in the line control register.
+                match self {
+                    Uart16550::Io { base } => {
+                        let mut lcr = Port::<u8>::new(*base + UART_16550_LCR_OFFSET as u16);
+                        let mut dll = Port::<u8>::new(*base);
+                        let mut dlm = Port::<u8>::new(*base + 1);
+                        // SAFETY: `base` is the I/O port base of a 16550 UART, so these are its line control and
+                        // divisor latch registers.
+                        interrupts::without_interrupts(|| unsafe {
+                            let line_control = lcr.read();
+                            lcr.write(line_control | UART_16550_LCR_DLAB);
+                            dll.write(divisor_low);
+                            dlm.write(divisor_high);
+                            lcr.write(line_control & !UART_16550_LCR_DLAB);
+                        });
+                    }
+                    Uart16550::Mmio { base, reg_stride } => {
+                        let lcr = (*base + UART_16550_LCR_OFFSET * *reg_stride) as *mut u8;
+                        let dll = *base as *mut u8;
+                        let dlm = (*base + *reg_stride) as *mut u8;
+                        // SAFETY: `base` and `reg_stride` describe the mapped registers of a 16550 UART, so these are
+                        // its line control and divisor latch registers.
+                        interrupts::without_interrupts(|| unsafe {
+                            let line_control = lcr.read_volatile();
+                            lcr.write_volatile(line_control | UART_16550_LCR_DLAB);
+                            dll.write_volatile(divisor_low);
+                            dlm.write_volatile(divisor_high);
+                            lcr.write_volatile(line_control & !UART_16550_LCR_DLAB);
+                        });
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -123,6 +215,8 @@ cfg_if::cfg_if! {
             pub const FR_BUSY: u8 = 1 << 3;
             pub const FR_RXFE: u8 = 1 << 4;
             pub const FR_TXFF: u8 = 1 << 5;
+            /// Number of times the flag register is polled for the transmitter before a write fails.
+            pub const TX_POLL_COUNT: usize = 1_000_000;
         }
 
         /// An interface for writing to a UartPl011 device.
@@ -161,6 +255,26 @@ cfg_if::cfg_if! {
                 while self.read_flag_register() & uart_pl011::FR_BUSY != 0 {}
             }
 
+            /// Writes a single byte to the UART, failing with
+            /// [`EfiError::Timeout`](crate::error::EfiError::Timeout) if the transmitter does not become ready.
+            pub fn try_write_byte(&self, byte: u8) -> crate::error::Result<()> {
+                let wait_until_clear = |flag: u8| {
+                    if (0..uart_pl011::TX_POLL_COUNT).any(|_| self.read_flag_register() & flag == 0) {
+                        Ok(())
+                    } else {
+                        Err(crate::error::EfiError::Timeout)
+                    }
+                };
+
+                wait_until_clear(uart_pl011::FR_TXFF)?;
+                // SAFETY: We know that the base address points to the control
+                // registers of a PL011 device which is appropriately mapped.
+                unsafe {
+                    self.get_base().write_volatile(byte);
+                }
+                wait_until_clear(uart_pl011::FR_BUSY)
+            }
+
             /// Reads a single byte from the UART.
             pub fn read_byte(&self) -> Option<u8> {
                 // Wait until the RX buffer is not empty.
@@ -196,6 +310,10 @@ cfg_if::cfg_if! {
                 }
             }
 
+            fn try_write(&self, buffer: &[u8]) -> crate::error::Result<()> {
+                buffer.iter().try_for_each(|byte| self.try_write_byte(*byte))
+            }
+
             fn read(&self) -> u8 {
                 loop {
                     if let Some(byte) = self.read_byte() {
@@ -12,6 +12,7 @@ pub mod device_path;
 
 pub mod decompress;
 pub mod performance_measurement;
+pub mod serial_io;
 pub mod status_code;
 
 extern crate alloc;
@@ -44,6 +45,7 @@ impl_r_efi_protocol!(debugport);
 // impl_r_efi_protocol!(decompress);
 impl_r_efi_protocol!(device_path);
 impl_r_efi_protocol!(device_path_from_text);
+impl_r_efi_protocol!(device_path_to_text);
 impl_r_efi_protocol!(device_path_utilities);
 impl_r_efi_protocol!(disk_io);
 impl_r_efi_protocol!(disk_io2);
@@ -88,3 +90,8 @@ impl_r_efi_protocol!(tcp6);
 impl_r_efi_protocol!(timestamp);
 impl_r_efi_protocol!(udp4);
 impl_r_efi_protocol!(udp6);
+
+// SAFETY: `Protocol` is the interface of the PI Firmware Volume2 protocol.
+unsafe impl ProtocolInterface for crate::pi::protocols::firmware_volume::Protocol {
+    const PROTOCOL_GUID: efi::Guid = crate::pi::protocols::firmware_volume::PROTOCOL_GUID;
+}
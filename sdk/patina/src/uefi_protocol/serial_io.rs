@@ -0,0 +1,87 @@
+//! Serial I/O Protocol
+//!
+//! Provides the protocol used to communicate with a serial device.
+//!
+//! See <https://uefi.org/specs/UEFI/2.10/12_Protocols_Console_Support.html#serial-i-o-protocol>
+//!
+//! ## License
+//!
+//! Copyright (c) Microsoft Corporation.
+//!
+//! SPDX-License-Identifier: Apache-2.0
+//!
+
+use core::ffi::c_void;
+
+use r_efi::efi;
+
+use super::ProtocolInterface;
+
+/// GUID for the Serial I/O Protocol.
+pub const PROTOCOL_GUID: efi::Guid =
+    efi::Guid::from_fields(0xbb25cf6f, 0xf1d4, 0x11d2, 0x9a, 0x0c, &[0x00, 0x90, 0x27, 0x3f, 0xc1, 0xfd]);
+
+/// Revision 1.0 of the Serial I/O Protocol.
+pub const REVISION: u32 = 0x00010000;
+/// Revision 1.1 of the Serial I/O Protocol, which adds the device type GUID.
+pub const REVISION1P1: u32 = 0x00010001;
+
+/// Resets the serial device.
+pub type ResetFn = extern "efiapi" fn(*mut Protocol) -> efi::Status;
+/// Sets the baud rate, receive FIFO depth, transmit/receive timeout, parity, data bits, and stop bits.
+pub type SetAttributesFn = extern "efiapi" fn(*mut Protocol, u64, u32, u32, u32, u8, u32) -> efi::Status;
+/// Sets the control bits on the serial device.
+pub type SetControlBitsFn = extern "efiapi" fn(*mut Protocol, u32) -> efi::Status;
+/// Retrieves the status of the control bits on the serial device.
+pub type GetControlBitsFn = extern "efiapi" fn(*mut Protocol, *mut u32) -> efi::Status;
+/// Writes data to the serial device.
+pub type WriteFn = extern "efiapi" fn(*mut Protocol, *mut usize, *mut c_void) -> efi::Status;
+/// Reads data from the serial device.
+pub type ReadFn = extern "efiapi" fn(*mut Protocol, *mut usize, *mut c_void) -> efi::Status;
+
+/// The current attributes of the serial device.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Mode {
+    /// A mask of the control bits the device supports.
+    pub control_mask: u32,
+    /// The number of microseconds to wait before timing out a read or write operation.
+    pub timeout: u32,
+    /// The current baud rate, or 0 to indicate the device's default.
+    pub baud_rate: u64,
+    /// The number of characters the device will buffer on input.
+    pub receive_fifo_depth: u32,
+    /// The number of data bits in each character.
+    pub data_bits: u32,
+    /// The parity type (`EFI_PARITY_TYPE`).
+    pub parity: u32,
+    /// The number of stop bits per character (`EFI_STOP_BITS_TYPE`).
+    pub stop_bits: u32,
+}
+
+/// C struct for the Serial I/O Protocol.
+#[repr(C)]
+pub struct Protocol {
+    /// The revision of the protocol.
+    pub revision: u32,
+    /// Resets the serial device.
+    pub reset: ResetFn,
+    /// Sets the communication attributes of the serial device.
+    pub set_attributes: SetAttributesFn,
+    /// Sets the control bits on the serial device.
+    pub set_control: SetControlBitsFn,
+    /// Retrieves the status of the control bits on the serial device.
+    pub get_control: GetControlBitsFn,
+    /// Writes data to the serial device.
+    pub write: WriteFn,
+    /// Reads data from the serial device.
+    pub read: ReadFn,
+    /// The current attributes of the serial device.
+    pub mode: *mut Mode,
+    /// The type of the serial device. Only valid for revision 1.1 and later.
+    pub device_type_guid: *const efi::Guid,
+}
+
+unsafe impl ProtocolInterface for Protocol {
+    const PROTOCOL_GUID: efi::Guid = PROTOCOL_GUID;
+}
@@ -20,7 +20,7 @@ use patina::pi::fw_fs::{
 
 use crate::{
     FirmwareFileSystemError,
-    section::{Section, SectionComposer, SectionExtractor, SectionIterator},
+    section::{Section, SectionComposer, SectionExtractor, SectionIterator, SectionKind},
 };
 
 use alloc::vec::Vec;
@@ -605,6 +605,13 @@ impl File {
         self.sections.iter().flat_map(|x| x.sections())
     }
 
+    /// Iterate over all (flattened) sections in this file, classified by type.
+    ///
+    /// Yields `NotComposed` for any section that is dirty.
+    pub fn section_kinds(&self) -> impl Iterator<Item = Result<SectionKind<'_>, FirmwareFileSystemError>> {
+        self.section_iter().map(Section::kind)
+    }
+
     /// Iterate over top-level sections in this file mutably.
     ///
     /// Note: This yields only the top-level sections. To traverse nested
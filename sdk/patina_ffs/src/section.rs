@@ -14,6 +14,7 @@ use patina::pi::fw_fs::ffs::{self, section};
 use patina::{base::align_up, boot_services::c_ptr::CPtr};
 
 use core::{fmt, iter, mem, ptr, slice::from_raw_parts};
+use r_efi::efi;
 
 use crate::FirmwareFileSystemError;
 
@@ -199,6 +200,78 @@ impl SectionHeader {
     }
 }
 
+/// A section classified by type, borrowing its content.
+///
+/// Returned by [`Section::kind`]. Each variant carries the section content (the bytes following all headers) along
+/// with any type-specific header fields. Encapsulation variants carry the encapsulated (e.g. still compressed)
+/// payload; their extracted sub-sections are available via [`Section::sub_sections`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind<'a> {
+    /// Padding used for alignment.
+    Pad(&'a [u8]),
+    /// A PE32+ image.
+    Pe32(&'a [u8]),
+    /// A position-independent PE32+ image.
+    Pic(&'a [u8]),
+    /// A terse executable (TE) image.
+    Te(&'a [u8]),
+    /// A DXE dependency expression.
+    DxeDepex(&'a [u8]),
+    /// A PEI dependency expression.
+    PeiDepex(&'a [u8]),
+    /// An MM dependency expression.
+    MmDepex(&'a [u8]),
+    /// A null-terminated UCS-2 user interface (file name) string.
+    UserInterface(&'a [u8]),
+    /// A version section; `data` is the null-terminated UCS-2 version string.
+    Version {
+        /// The build number from the version header.
+        build_number: u16,
+        /// The version string.
+        data: &'a [u8],
+    },
+    /// A 16-bit compatibility image.
+    Compatibility16(&'a [u8]),
+    /// An embedded firmware volume image.
+    FirmwareVolumeImage(&'a [u8]),
+    /// Raw data.
+    Raw(&'a [u8]),
+    /// A freeform section identified by a subtype GUID.
+    FreeformSubtypeGuid {
+        /// The subtype GUID.
+        guid: efi::Guid,
+        /// The section data.
+        data: &'a [u8],
+    },
+    /// A disposable section.
+    Disposable(&'a [u8]),
+    /// A compression encapsulation section.
+    Compression {
+        /// The compression algorithm (see `ffs::section::header::STANDARD_COMPRESSION`).
+        compression_type: u8,
+        /// The length of the data once decompressed.
+        uncompressed_length: u32,
+        /// The compressed payload.
+        data: &'a [u8],
+    },
+    /// A GUID-defined encapsulation section.
+    GuidDefined {
+        /// The GUID identifying the encapsulation format.
+        guid: efi::Guid,
+        /// The GUID-defined section attributes.
+        attributes: u16,
+        /// The encapsulated payload.
+        data: &'a [u8],
+    },
+    /// A section of a type not known to this crate.
+    Unknown {
+        /// The raw section type.
+        section_type: u8,
+        /// The section data.
+        data: &'a [u8],
+    },
+}
+
 #[derive(Clone)]
 struct LeafSectionData {
     data: Vec<u8>,
@@ -555,7 +628,45 @@ impl Section {
         }
     }
 
-    /// Into-iterator over this section followed by its sub-sections (owned).
+    /// Classify this section by type, borrowing its content.
+    ///
+    /// Returns `NotComposed` if this section or any extracted child is dirty.
+    pub fn kind(&self) -> Result<SectionKind<'_>, FirmwareFileSystemError> {
+        let data = self.try_content_as_slice()?;
+        Ok(match &self.header {
+            SectionHeader::Pad(_) => SectionKind::Pad(data),
+            SectionHeader::Standard(section_type, _) => match *section_type {
+                ffs::section::raw_type::PE32 => SectionKind::Pe32(data),
+                ffs::section::raw_type::PIC => SectionKind::Pic(data),
+                ffs::section::raw_type::TE => SectionKind::Te(data),
+                ffs::section::raw_type::DXE_DEPEX => SectionKind::DxeDepex(data),
+                ffs::section::raw_type::PEI_DEPEX => SectionKind::PeiDepex(data),
+                ffs::section::raw_type::MM_DEPEX => SectionKind::MmDepex(data),
+                ffs::section::raw_type::USER_INTERFACE => SectionKind::UserInterface(data),
+                ffs::section::raw_type::COMPATIBILITY16 => SectionKind::Compatibility16(data),
+                ffs::section::raw_type::FIRMWARE_VOLUME_IMAGE => SectionKind::FirmwareVolumeImage(data),
+                ffs::section::raw_type::RAW => SectionKind::Raw(data),
+                ffs::section::raw_type::encapsulated::DISPOSABLE => SectionKind::Disposable(data),
+                section_type => SectionKind::Unknown { section_type, data },
+            },
+            SectionHeader::Compression(compression, _) => SectionKind::Compression {
+                compression_type: compression.compression_type,
+                uncompressed_length: compression.uncompressed_length,
+                data,
+            },
+            SectionHeader::GuidDefined(guid_defined, _, _) => SectionKind::GuidDefined {
+                guid: guid_defined.section_definition_guid,
+                attributes: guid_defined.attributes,
+                data,
+            },
+            SectionHeader::Version(version, _) => SectionKind::Version { build_number: version.build_number, data },
+            SectionHeader::FreeFormSubtypeGuid(freeform, _) => {
+                SectionKind::FreeformSubtypeGuid { guid: freeform.sub_type_guid, data }
+            }
+        })
+    }
+
+    /// Consumes this section, returning an iterator over it followed by its sub-sections.
     pub fn into_sections(self) -> impl Iterator<Item = Section> {
         let sub_sections = match &self.data {
             SectionData::Encapsulation(encapsulation) => encapsulation.sub_sections.clone(),
@@ -773,11 +773,11 @@ mod test {
         io::Cursor,
         path::Path,
     };
-    use uuid::Uuid;
 
     use crate::{
         FirmwareFileSystemError,
-        section::{Section, SectionComposer, SectionExtractor, SectionHeader},
+        file::{File as FfsFile, FileRef},
+        section::{Section, SectionComposer, SectionExtractor, SectionHeader, SectionKind},
         volume::{Volume, VolumeRef},
     };
 
@@ -858,7 +858,7 @@ mod test {
         for ffs_file in fv.files() {
             let ffs_file = ffs_file.map_err(stringify)?;
             count += 1;
-            let file_name = Uuid::from_bytes_le(*ffs_file.name().as_bytes()).to_string().to_uppercase();
+            let file_name = patina::base::guid::to_uuid(&ffs_file.name()).to_string().to_uppercase();
             if let Some(mut target) = expected_values.files_to_test.remove(&file_name) {
                 assert_eq!(target.file_type, ffs_file.file_type_raw(), "[{file_name}] Error with the file type.");
                 assert_eq!(
@@ -912,7 +912,7 @@ mod test {
         let mut count = 0;
         for ffs_file in fv.files() {
             count += 1;
-            let file_name = Uuid::from_bytes_le(*ffs_file.name().as_bytes()).to_string().to_uppercase();
+            let file_name = patina::base::guid::to_uuid(&ffs_file.name()).to_string().to_uppercase();
             if let Some(mut target) = expected_values.files_to_test.remove(&file_name) {
                 assert_eq!(target.file_type, ffs_file.file_type_raw(), "[{file_name}] Error with the file type.");
                 assert_eq!(
@@ -1128,6 +1128,53 @@ mod test {
         }
     }
 
+    #[test]
+    fn section_kinds_should_classify_sections_of_a_file() -> Result<(), FirmwareFileSystemError> {
+        set_logger();
+        let guid =
+            efi::Guid::from_fields(0x1e2ed096, 0x30e2, 0x4254, 0xbd, 0x89, &[0x86, 0x3b, 0xbe, 0xf8, 0x23, 0x25]);
+        let mut file = FfsFile::new(guid, ffs::file::raw::r#type::DRIVER);
+
+        let ui = [b'D', 0, b'r', 0, b'v', 0, 0, 0];
+        let guid_header = ffs::section::header::GuidDefined {
+            section_definition_guid: guid,
+            data_offset: (mem::size_of::<ffs::section::Header>() + mem::size_of::<ffs::section::header::GuidDefined>())
+                as u16,
+            attributes: 0x1,
+        };
+        let compression_header = ffs::section::header::Compression { uncompressed_length: 0x40, compression_type: 0x1 };
+        let sections = [
+            (SectionHeader::Standard(ffs::section::raw_type::PE32, 4), vec![b'M', b'Z', 0, 0]),
+            (SectionHeader::Standard(ffs::section::raw_type::DXE_DEPEX, 2), vec![0x06, 0x08]),
+            (SectionHeader::Standard(ffs::section::raw_type::USER_INTERFACE, ui.len() as u32), ui.to_vec()),
+            (SectionHeader::Version(ffs::section::header::Version { build_number: 7 }, 2), vec![0, 0]),
+            (SectionHeader::GuidDefined(guid_header, Vec::new(), 3), vec![1, 2, 3]),
+            (SectionHeader::Compression(compression_header, 2), vec![4, 5]),
+            (SectionHeader::Standard(0x7f, 1), vec![9]),
+        ];
+        for (header, data) in sections {
+            file.sections_mut().push(Section::new_from_header_with_data(header, data)?);
+        }
+
+        let bytes = file.serialize()?;
+        let file = FfsFile::try_from(FileRef::new(&bytes)?)?;
+        let kinds = file.section_kinds().collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            kinds,
+            vec![
+                SectionKind::Pe32(&[b'M', b'Z', 0, 0]),
+                SectionKind::DxeDepex(&[0x06, 0x08]),
+                SectionKind::UserInterface(&ui),
+                SectionKind::Version { build_number: 7, data: &[0, 0] },
+                SectionKind::GuidDefined { guid, attributes: 0x1, data: &[1, 2, 3] },
+                SectionKind::Compression { compression_type: 0x1, uncompressed_length: 0x40, data: &[4, 5] },
+                SectionKind::Unknown { section_type: 0x7f, data: &[9] },
+            ]
+        );
+        Ok(())
+    }
+
     struct ExampleSectionExtractor {}
     impl SectionExtractor for ExampleSectionExtractor {
         fn extract(&self, section: &Section) -> Result<Vec<u8>, FirmwareFileSystemError> {
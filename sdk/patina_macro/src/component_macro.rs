@@ -20,6 +20,8 @@ use syn::{
 struct AttrConfig {
     /// `#[entry_point = path::to::function]`: Used to override the default `Self::entry_point` entry point.
     entry_point: TokenStream,
+    /// `#[entry_point(run_once)]`: Guarantees the entry point is executed at most once.
+    run_once: bool,
 }
 
 /// A wrapper for simplifying parsing the supported Struct and Enum types.
@@ -75,38 +77,42 @@ impl Component {
 
     /// Parses attributes associated with the struct or enum, generating a configuration struct.
     fn parse_attr(attrs: &mut Vec<Attribute>) -> syn::Result<AttrConfig> {
-        let mut config = AttrConfig { entry_point: quote!(Self::entry_point) };
+        let mut config = AttrConfig { entry_point: quote!(Self::entry_point), run_once: false };
         for attr in attrs {
             if attr.path().is_ident("entry_point") {
-                config.entry_point = Self::parse_entry_point_attr(attr)?;
+                Self::parse_entry_point_attr(attr, &mut config)?;
             }
         }
 
         Ok(config)
     }
 
-    /// Parses the `#[entry_point(path = path::to::function)]` attribute to get `path::to::function`.
-    fn parse_entry_point_attr(attr: &Attribute) -> syn::Result<TokenStream> {
+    /// Parses the `#[entry_point(path = path::to::function, run_once)]` attribute into `config`.
+    fn parse_entry_point_attr(attr: &Attribute, config: &mut AttrConfig) -> syn::Result<()> {
         // the entry_point attribute must always be a list e.g. entry_point(A, B, C)
         let Meta::List(meta_list) = &attr.meta else {
             return Err(syn::Error::new_spanned(attr, "Expected `#[entry_point(...)]`"));
         };
 
         let parser = Punctuated::<Meta, Token![,]>::parse_terminated;
+        let metas = parser.parse2(meta_list.tokens.clone())?;
+        if metas.is_empty() {
+            return Err(syn::Error::new_spanned(meta_list, "Expected `entry_point()` to not be empty"));
+        }
 
-        // For now, we only support a single key-value pair in the list so we can just return an error if anything
-        // else is found. This makes for less code to change if we add more config.
-        #[allow(clippy::never_loop)]
-        for meta in parser.parse2(meta_list.tokens.clone())? {
-            if let Meta::NameValue(ref nv) = meta
-                && nv.path.is_ident("path")
-                && let syn::Expr::Path(path) = &nv.value
-            {
-                return Ok(quote!(#path));
+        for meta in metas {
+            match meta {
+                Meta::NameValue(ref nv) if nv.path.is_ident("path") => {
+                    let syn::Expr::Path(path) = &nv.value else {
+                        return Err(syn::Error::new_spanned(meta, "Expected `path = ...`"));
+                    };
+                    config.entry_point = quote!(#path);
+                }
+                Meta::Path(ref path) if path.is_ident("run_once") => config.run_once = true,
+                _ => return Err(syn::Error::new_spanned(meta, "Expected `path = ...` or `run_once`")),
             }
-            return Err(syn::Error::new_spanned(meta, "Expected `path = ...`"));
         }
-        Err(syn::Error::new_spanned(meta_list, "Expected `entry_point()` to not be empty"))
+        Ok(())
     }
 }
 
@@ -146,7 +152,8 @@ pub(crate) fn component2(item: proc_macro2::TokenStream) -> proc_macro2::TokenSt
         Err(e) => return e.to_compile_error(),
     };
 
-    let AttrConfig { entry_point, .. } = component.config();
+    let AttrConfig { entry_point, run_once } = component.config();
+    let run_once = if run_once { quote!(.run_once()) } else { quote!() };
 
     let lhs = component.lhs_generics();
     let rhs = component.rhs_generics();
@@ -164,7 +171,7 @@ pub(crate) fn component2(item: proc_macro2::TokenStream) -> proc_macro2::TokenSt
                     patina::component::StructComponent::new(
                         #entry_point,
                         self
-                    )
+                    )#run_once
                 )
             }
         }
@@ -204,6 +211,45 @@ mod tests {
         assert_eq!(expected.to_string(), component2(input).to_string());
     }
 
+    #[test]
+    fn test_run_once() {
+        let input = quote! {
+            #[entry_point(path = MyStruct::entry, run_once)]
+            struct MyStruct;
+        };
+
+        let expected = quote! {
+            extern crate alloc as __alloc_component_MyStruct;
+            impl patina::component::params::ComponentInput for MyStruct {}
+            impl patina::component::IntoComponent<fn(MyStruct)-> patina::error::Result<()>> for MyStruct {
+                fn into_component(self) -> __alloc_component_MyStruct::boxed::Box<dyn patina::component::Component> {
+                    __alloc_component_MyStruct::boxed::Box::new(
+                        patina::component::StructComponent::new(
+                            MyStruct::entry,
+                            self
+                        ).run_once()
+                    )
+                }
+            }
+        };
+
+        assert_eq!(expected.to_string(), component2(input).to_string());
+    }
+
+    #[test]
+    fn test_unknown_entry_point_option() {
+        let input = quote! {
+            #[entry_point(run_twice)]
+            struct MyStruct;
+        };
+
+        let expected = quote! {
+            :: core :: compile_error ! { "Expected `path = ...` or `run_once`" }
+        };
+
+        assert_eq!(component2(input).to_string(), expected.to_string());
+    }
+
     #[test]
     fn test_basic_enum() {
         let input = quote! {
@@ -131,6 +131,7 @@ pub fn hob_config2(item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
     quote! {
         impl #lhs patina::component::hob::FromHob for #name #rhs #where_clause {
             const HOB_GUID: patina::OwnedGuid = #hob_guid;
+            const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
 
             fn parse(bytes: &[u8]) -> Self {
                 let hob = match <Self as zerocopy::FromBytes>::read_from_prefix(bytes) {
@@ -177,6 +178,7 @@ mod tests {
         let expected = quote! {
             impl patina::component::hob::FromHob for MyStruct {
                 const HOB_GUID: patina::OwnedGuid = patina::OwnedGuid::from_fields(2347032417u32, 37834u16, 4562u16, 170u8, 13u8, [0u8, 224u8, 152u8, 3u8, 43u8, 140u8]);
+                const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
                 fn parse(bytes: &[u8]) -> Self {
                     let hob = match <Self as zerocopy::FromBytes>::read_from_prefix(bytes) {
                         Ok((hob, _)) => hob,
@@ -222,6 +224,7 @@ mod tests {
             impl patina::component::hob::FromHob for MyStruct {
 
                 const HOB_GUID: patina::OwnedGuid = patina::OwnedGuid::from_fields(3928583570u32, 2921u16, 16956u16, 140u8, 40u8, [51u8, 180u8, 224u8, 169u8, 18u8, 104u8]);
+                const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
                 fn parse(bytes: &[u8]) -> Self {
                     let hob = match <Self as zerocopy::FromBytes>::read_from_prefix(bytes) {
                         Ok((hob, _)) => hob,
@@ -259,6 +262,7 @@ mod tests {
         let expected = quote! {
             impl<T> patina::component::hob::FromHob for MyStruct<T> {
                 const HOB_GUID: patina::OwnedGuid = patina::OwnedGuid::from_fields(2347032417u32, 37834u16, 4562u16, 170u8, 13u8, [0u8, 224u8, 152u8, 3u8, 43u8, 140u8]);
+                const DATA_SIZE: Option<usize> = Some(core::mem::size_of::<Self>());
                 fn parse(bytes: &[u8]) -> Self {
                     let hob = match <Self as zerocopy::FromBytes>::read_from_prefix(bytes) {
                         Ok((hob, _)) => hob,
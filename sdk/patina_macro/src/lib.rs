@@ -27,7 +27,8 @@ mod test_macro;
 ///
 /// ## Macro Attribute
 ///
-/// - `entry_point`: The function to be called when the component is executed.
+/// - `entry_point`: The function to be called when the component is executed, set with `path = ...`. Add
+///   `run_once` to guarantee the function is executed at most once, even if the component is dispatched again.
 ///
 /// ## Examples
 ///
@@ -128,6 +129,8 @@ pub fn service(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// implementation to safely create an instance of the type from a byte slice. If FromBytes is not implemented on the
 /// type, a compile time error will be produced.
 ///
+/// The size of the type is used as the `DATA_SIZE` of the HOB, so a HOB with less data is skipped rather than parsed.
+///
 /// ## Macro Attribute
 ///
 /// - `guid`: The guid to associate with the type.